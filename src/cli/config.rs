@@ -0,0 +1,415 @@
+//! Defaults of the global options from a configuration file and the
+//! environment.
+//!
+//! The configuration file, `$XDG_CONFIG_HOME/shyaml/config.yaml` (or
+//! `~/.config/shyaml/config.yaml`), maps long names of global options to
+//! their default value. Its `actions` entry gives default options to
+//! actions, as `SHYAML_<ACTION>_OPTS` does:
+//!
+//! ```yaml
+//! indent: 4
+//! quote-strings: needed
+//! color: true
+//! actions:
+//!   apply: -m config=replace
+//! ```
+//!
+//! `SHYAML_CONFIG` names another file, or none when empty.
+//! `SHYAML_<OPTION>` variables, named after the option upper-cased with `-`
+//! as `_`, override the file: `SHYAML_INDENT=2` or `SHYAML_YAML=1`. The
+//! defaults are inserted before the global options of each command group,
+//! and those given on the command line replace them. Options naming the
+//! input and output of a run, and `--post-process` which runs a shell
+//! command, can't be given defaults.
+
+use super::def;
+use super::env_opts::action_index;
+use super::error::CliError;
+use crate::yaml::{serialize_raw, Value};
+use clap::{Arg, ArgAction, Command, CommandFactory};
+use indexmap::IndexMap;
+use std::path::PathBuf;
+
+/// Environment variable naming the configuration file.
+const CONFIG_VAR: &str = "SHYAML_CONFIG";
+
+/// Global options without defaults.
+const EXCLUDED: &[&str] = &[
+    "version",
+    "file",
+    "output",
+    "backup",
+    "watch",
+    "post-process",
+];
+
+/// Options that can't be given together, a default of one giving way to
+/// the other.
+const OPPOSITES: &[(&str, &str)] = &[("color", "no-color")];
+
+/// Default options.
+#[derive(Debug, Default)]
+pub struct Config {
+    /// Global options by long name, `None` for flags turned off
+    options: Vec<(String, Option<String>)>,
+    /// Options of actions, by action name
+    actions: IndexMap<String, String>,
+}
+
+/// Environment variable of a global option's default.
+fn var_name(long: &str) -> String {
+    format!("SHYAML_{}", long.to_uppercase().replace('-', "_"))
+}
+
+/// Configuration file, and whether it was named by `SHYAML_CONFIG`.
+fn config_path() -> Option<(PathBuf, bool)> {
+    if let Some(path) = std::env::var_os(CONFIG_VAR) {
+        return (!path.is_empty()).then(|| (PathBuf::from(path), true));
+    }
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some((dir.join("shyaml").join("config.yaml"), false))
+}
+
+/// Whether the option `long` is overridden by one of the options `given`.
+fn overridden(long: &str, given: &[&str]) -> bool {
+    given.iter().any(|g| {
+        *g == long
+            || OPPOSITES
+                .iter()
+                .any(|(a, b)| (a == g && *b == long) || (b == g && *a == long))
+    })
+}
+
+/// Global option of long name `long` that can be given a default.
+fn option<'a>(command: &'a Command, long: &str) -> Option<&'a Arg> {
+    command
+        .get_arguments()
+        .filter(|a| !EXCLUDED.contains(&a.get_long().unwrap_or_default()))
+        .find(|a| a.get_long() == Some(long))
+}
+
+/// Text of a scalar value.
+fn scalar(value: &Value) -> Result<String, String> {
+    match value {
+        Value::Bool(_) | Value::Number(_) | Value::String(_) => Ok(serialize_raw(value)),
+        _ => Err("expected a scalar value".to_string()),
+    }
+}
+
+/// Command line options giving `arg` the value `value`, by long name.
+fn tokens(arg: &Arg, value: &Value) -> Result<Vec<(String, Option<String>)>, String> {
+    let long = arg.get_long().unwrap_or_default().to_string();
+    let option = |value: Option<String>| {
+        let token = match value {
+            Some(value) => format!("--{}={}", long, value),
+            None => format!("--{}", long),
+        };
+        (long.clone(), Some(token))
+    };
+    let off = || vec![(long.clone(), None)];
+    match (arg.get_action(), value) {
+        (ArgAction::SetTrue, Value::Bool(true)) => Ok(vec![option(None)]),
+        (ArgAction::SetTrue, Value::Bool(false)) => Ok(off()),
+        (ArgAction::SetTrue, _) => Err("expected a boolean".to_string()),
+        (ArgAction::Count, value) => {
+            let count = match value {
+                Value::Bool(set) => usize::from(*set),
+                value => scalar(value)?
+                    .parse::<usize>()
+                    .map_err(|_| "expected a count".to_string())?,
+            };
+            Ok(if count == 0 {
+                off()
+            } else {
+                vec![option(None); count]
+            })
+        }
+        (ArgAction::Append, Value::Sequence(items)) => items
+            .iter()
+            .map(|item| Ok(option(Some(scalar(item)?))))
+            .collect(),
+        (_, value) => Ok(vec![option(Some(scalar(value)?))]),
+    }
+}
+
+impl Config {
+    /// Defaults of the configuration file, then of the environment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file named by `SHYAML_CONFIG` can't be read,
+    /// and on invalid files and values.
+    pub fn load() -> Result<Self, CliError> {
+        let command = def::Args::command();
+        let mut config = match config_path() {
+            Some((path, explicit)) if explicit || path.exists() => {
+                let source = path.display().to_string();
+                let text = std::fs::read_to_string(&path).map_err(|e| {
+                    CliError::io(format!("Failed to read config file '{}': {}", source, e))
+                })?;
+                Self::parse(&command, &source, &text)?
+            }
+            _ => Config::default(),
+        };
+        config.add(Self::from_env(&command, |name| std::env::var(name).ok())?);
+        Ok(config)
+    }
+
+    /// Defaults of the configuration file `source` of content `text`.
+    fn parse(command: &Command, source: &str, text: &str) -> Result<Self, CliError> {
+        let invalid = |message: String| format!("config file '{}': {}", source, message);
+        let value: Value = text.parse().map_err(|e| {
+            crate::yaml::Error::parse_in(&format!("config file '{}'", source), text, e)
+        })?;
+        let entries = match value {
+            Value::Mapping(entries) => entries,
+            Value::Null => return Ok(Config::default()),
+            _ => return Err(invalid("expected a mapping of options".to_string()).into()),
+        };
+        let mut config = Config::default();
+        for (key, value) in &entries {
+            let key = serialize_raw(key);
+            if key == "actions" {
+                config.actions = Self::parse_actions(command, value).map_err(invalid)?;
+                continue;
+            }
+            let arg = option(command, &key)
+                .ok_or_else(|| invalid(format!("unknown option '{}'", key)))?;
+            let options = tokens(arg, value).map_err(|e| invalid(format!("{}: {}", key, e)))?;
+            config.add(options);
+        }
+        Ok(config)
+    }
+
+    /// Options of the actions of an `actions` entry, given as a string or
+    /// a sequence of options.
+    fn parse_actions(command: &Command, value: &Value) -> Result<IndexMap<String, String>, String> {
+        let Value::Mapping(entries) = value else {
+            return Err("actions: expected a mapping of actions".to_string());
+        };
+        let mut actions = IndexMap::new();
+        for (name, opts) in entries {
+            let name = serialize_raw(name);
+            if command.find_subcommand(&name).is_none() {
+                return Err(format!("actions: unknown action '{}'", name));
+            }
+            let opts = match opts {
+                Value::Sequence(items) => items
+                    .iter()
+                    .map(scalar)
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| format!("actions: {}: {}", name, e))?
+                    .join(" "),
+                other => scalar(other).map_err(|e| format!("actions: {}: {}", name, e))?,
+            };
+            actions.insert(name, opts);
+        }
+        Ok(actions)
+    }
+
+    /// Defaults of the `SHYAML_<OPTION>` variables found by `lookup`.
+    fn from_env(
+        command: &Command,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<Vec<(String, Option<String>)>, CliError> {
+        let mut options = Vec::new();
+        for arg in command.get_arguments() {
+            let Some(long) = arg.get_long().filter(|long| !EXCLUDED.contains(long)) else {
+                continue;
+            };
+            let name = var_name(long);
+            let Some(text) = lookup(&name) else {
+                continue;
+            };
+            let value = match arg.get_action() {
+                ArgAction::SetTrue => match text.to_lowercase().as_str() {
+                    "1" | "true" | "yes" | "on" => Value::Bool(true),
+                    "" | "0" | "false" | "no" | "off" => Value::Bool(false),
+                    _ => Value::String(text),
+                },
+                _ => Value::String(text),
+            };
+            let defaults = tokens(arg, &value).map_err(|e| format!("{}: {}", name, e))?;
+            options.extend(defaults);
+        }
+        Ok(options)
+    }
+
+    /// Add `options`, replacing the previous defaults of these options.
+    fn add(&mut self, options: Vec<(String, Option<String>)>) {
+        let longs: Vec<&str> = options.iter().map(|(long, _)| long.as_str()).collect();
+        self.options.retain(|(long, _)| !overridden(long, &longs));
+        self.options.extend(options);
+    }
+
+    /// Default options of `action`.
+    #[must_use]
+    pub fn action_opts(&self, action: &str) -> Option<&str> {
+        self.actions.get(action).map(String::as_str)
+    }
+
+    /// Insert the default global options in each command group, but those
+    /// the group gives.
+    #[must_use]
+    pub fn with_defaults(&self, groups: Vec<Vec<String>>) -> Vec<Vec<String>> {
+        if self.options.is_empty() {
+            return groups;
+        }
+        let command = def::Args::command();
+        groups
+            .into_iter()
+            .map(|mut group| {
+                let end = action_index(&command, &group).unwrap_or(group.len());
+                let given = given_options(&command, &group[1..end]);
+                let given: Vec<&str> = given.iter().map(String::as_str).collect();
+                let defaults: Vec<String> = self
+                    .options
+                    .iter()
+                    .filter(|(long, _)| !overridden(long, &given))
+                    .filter_map(|(_, token)| token.clone())
+                    .collect();
+                group.splice(1..1, defaults);
+                group
+            })
+            .collect()
+    }
+}
+
+/// Long names of the options of `command` given in `tokens`.
+fn given_options(command: &Command, tokens: &[String]) -> Vec<String> {
+    let mut given = Vec::new();
+    for token in tokens.iter().take_while(|t| *t != "--") {
+        match (token.strip_prefix("--"), token.strip_prefix('-')) {
+            (Some(long), _) => {
+                given.push(long.split('=').next().unwrap_or(long).to_string());
+            }
+            (None, Some(shorts)) => {
+                let longs = shorts.chars().filter_map(|short| {
+                    command
+                        .get_arguments()
+                        .find(|a| a.get_short() == Some(short))
+                        .and_then(|a| a.get_long())
+                });
+                given.extend(longs.map(String::from));
+            }
+            _ => {}
+        }
+    }
+    given
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn parse(text: &str) -> Result<Config, CliError> {
+        Config::parse(&def::Args::command(), "config.yaml", text)
+    }
+
+    fn tokens(config: &Config) -> Vec<&str> {
+        config
+            .options
+            .iter()
+            .filter_map(|(_, token)| token.as_deref())
+            .collect()
+    }
+
+    #[test]
+    fn test_var_name() {
+        assert_eq!(var_name("quote-strings"), "SHYAML_QUOTE_STRINGS");
+    }
+
+    #[test]
+    fn test_parse() {
+        let config = parse(
+            "indent: 4\nyaml: true\nquiet: false\nverbose: 2\nlog: [a, b]\n\
+             actions:\n  apply: -m config=replace\n  get-value: [-y, --resolve-aliases]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            tokens(&config),
+            vec![
+                "--indent=4",
+                "--yaml",
+                "--verbose",
+                "--verbose",
+                "--log=a",
+                "--log=b"
+            ]
+        );
+        assert_eq!(config.action_opts("apply"), Some("-m config=replace"));
+        assert_eq!(
+            config.action_opts("get-value"),
+            Some("-y --resolve-aliases")
+        );
+        assert_eq!(config.action_opts("keys"), None);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (text, message) in [
+            ("- indent", "expected a mapping of options"),
+            ("indnet: 4", "unknown option 'indnet'"),
+            ("output: out.yaml", "unknown option 'output'"),
+            ("post-process: cat", "unknown option 'post-process'"),
+            ("yaml: 1", "yaml: expected a boolean"),
+            ("indent: [4]", "indent: expected a scalar value"),
+            ("actions: {nope: -y}", "actions: unknown action 'nope'"),
+        ] {
+            let err = parse(text).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", text, err);
+        }
+    }
+
+    #[test]
+    fn test_env_overrides_file() {
+        let command = def::Args::command();
+        let mut config = parse("indent: 4\ncolor: true\nwidth: 60\nyaml: true\n").unwrap();
+        let lookup = |name: &str| match name {
+            "SHYAML_INDENT" => Some("2".to_string()),
+            "SHYAML_NO_COLOR" => Some("1".to_string()),
+            "SHYAML_YAML" => Some("0".to_string()),
+            _ => None,
+        };
+        config.add(Config::from_env(&command, lookup).unwrap());
+        assert_eq!(
+            tokens(&config),
+            vec!["--width=60", "--no-color", "--indent=2"]
+        );
+    }
+
+    #[test]
+    fn test_with_defaults() {
+        let config = parse("indent: 4\ncolor: true\nyaml: true\n").unwrap();
+        let groups = config.with_defaults(vec![
+            args(&["shyaml", "get-value", "-y", "a"]),
+            args(&["shyaml", "--no-color", "-qy", "--indent=2", "keys"]),
+        ]);
+        assert_eq!(
+            groups,
+            vec![
+                args(&[
+                    "shyaml",
+                    "--indent=4",
+                    "--color",
+                    "--yaml",
+                    "get-value",
+                    "-y",
+                    "a"
+                ]),
+                args(&["shyaml", "--no-color", "-qy", "--indent=2", "keys"]),
+            ]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Reporting of internal errors.
+//!
+//! A panic is a bug of shyaml: instead of a raw Rust backtrace, users get
+//! a short message asking them to report it, and the process exits with
+//! code 70 (`EX_SOFTWARE`), removing the pending `--output` file. With
+//! `SHYAML_BUG_REPORT` set, a bundle to attach to the report is written to
+//! a new temporary file:
+//!
+//! - the command line, keeping only known flags and action names, as
+//!   argument values may hold secrets
+//! - the size and a short hash of the `--file` input (stdin is consumed)
+//! - the number of the document being processed
+//! - a backtrace
+
+use super::def;
+use clap::CommandFactory;
+use colored::Colorize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+const ISSUES_URL: &str = "https://github.com/0k/shyaml-rs/issues";
+const BUG_REPORT_VAR: &str = "SHYAML_BUG_REPORT";
+/// `EX_SOFTWARE` of `sysexits.h`
+const EXIT_CODE: i32 = 70;
+
+static INPUT_FILE: OnceLock<String> = OnceLock::new();
+/// Temporary file of the `--output` redirection
+static PENDING_OUTPUT: OnceLock<PathBuf> = OnceLock::new();
+/// Number of the document being processed, 0 before the first one
+static DOCUMENT: AtomicUsize = AtomicUsize::new(0);
+
+/// Replace the default panic output by a bug report message.
+pub fn install() {
+    std::panic::set_hook(Box::new(|info| {
+        let payload = info.payload();
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        // As `unreachable!()` words it
+        let message = message.strip_prefix("internal error: ").unwrap_or(message);
+        let location = info
+            .location()
+            .map_or(String::new(), |l| format!(" ({}:{})", l.file(), l.line()));
+        eprintln!(
+            "{}: internal error: {}{}\nThis is a bug, please report it at {}",
+            "Error".bright_red(),
+            message,
+            location,
+            ISSUES_URL
+        );
+        if std::env::var_os(BUG_REPORT_VAR).is_some() {
+            let args: Vec<String> = std::env::args().collect();
+            let backtrace = std::backtrace::Backtrace::force_capture().to_string();
+            let path = std::env::temp_dir().join(format!("shyaml-bug-{}.txt", std::process::id()));
+            let text = bundle(&args, &format!("{}{}", message, location), &backtrace);
+            // Never through a file planted at this predictable name
+            let written = std::fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)
+                .and_then(|mut file| file.write_all(text.as_bytes()));
+            match written {
+                Ok(()) => eprintln!("Attach {} to the report.", path.display()),
+                Err(e) => eprintln!("Could not write '{}': {}", path.display(), e),
+            }
+        } else {
+            eprintln!(
+                "Run again with {}=1 to get a file to attach to the report.",
+                BUG_REPORT_VAR
+            );
+        }
+        // Exiting skips the drop that would remove it
+        if let Some(tmp) = PENDING_OUTPUT.get() {
+            std::fs::remove_file(tmp).ok();
+        }
+        std::process::exit(EXIT_CODE);
+    }));
+}
+
+/// Record the temporary file of the `--output` redirection, to remove on
+/// a panic.
+pub fn set_pending_output(tmp: &Path) {
+    PENDING_OUTPUT.set(tmp.to_path_buf()).ok();
+}
+
+/// Record the file the input documents are read from.
+pub fn set_input_file(file: &str) {
+    INPUT_FILE.set(file.to_string()).ok();
+}
+
+/// Record the number of the document about to be processed, from 1.
+pub fn set_document(number: usize) {
+    DOCUMENT.store(number, Ordering::Relaxed);
+}
+
+/// Long and short flags of a command and of its subcommands, with their
+/// aliases.
+fn known_flags(command: &clap::Command, flags: &mut Vec<String>) {
+    for arg in command.get_arguments() {
+        let longs = arg.get_long_and_visible_aliases().unwrap_or_default();
+        let shorts = arg.get_short_and_visible_aliases().unwrap_or_default();
+        flags.extend(longs.into_iter().map(|l| format!("--{}", l)));
+        flags.extend(shorts.into_iter().map(|s| format!("-{}", s)));
+    }
+    for subcommand in command.get_subcommands() {
+        known_flags(subcommand, flags);
+    }
+}
+
+/// Command line arguments keeping only known flags and action names, other
+/// arguments being replaced by their length: even starting with `-`, an
+/// argument may be the value of an option.
+fn redact_args(args: &[String]) -> Vec<String> {
+    let mut command = def::Args::command();
+    command.build();
+    let actions: Vec<&str> = command.get_subcommands().map(|c| c.get_name()).collect();
+    let mut flags = vec!["--".to_string()];
+    known_flags(&command, &mut flags);
+    let known = |flag: &str| flags.iter().any(|f| f == flag);
+    args.iter()
+        .enumerate()
+        .map(|(i, arg)| match arg.split_once('=') {
+            _ if i == 0 => "shyaml".to_string(),
+            Some((flag, _)) if flag.starts_with("--") && known(flag) => {
+                format!("{}=<redacted>", flag)
+            }
+            None if arg == ";" || known(arg) || actions.contains(&arg.as_str()) => arg.clone(),
+            _ => format!("<{} chars>", arg.chars().count()),
+        })
+        .collect()
+}
+
+/// Size and short hash of the input file, if it can still be read.
+fn input_digest() -> String {
+    let Some(file) = INPUT_FILE.get() else {
+        return "stdin".to_string();
+    };
+    match std::fs::read(file) {
+        Ok(content) => {
+            let mut hasher = DefaultHasher::new();
+            content.hash(&mut hasher);
+            format!(
+                "{} bytes, hash {:08x}",
+                content.len(),
+                hasher.finish() >> 32
+            )
+        }
+        Err(e) => format!("unreadable file ({})", e),
+    }
+}
+
+/// Text of the bug report bundle.
+fn bundle(args: &[String], message: &str, backtrace: &str) -> String {
+    format!(
+        "version: {}\nargs: {}\ninput: {}\ndocument: {}\npanic: {}\n\n{}",
+        env!("CARGO_PKG_VERSION"),
+        redact_args(args).join(" "),
+        input_digest(),
+        DOCUMENT.load(Ordering::Relaxed),
+        message,
+        backtrace
+    )
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_redact_args() {
+        assert_eq!(
+            redact_args(&args(&[
+                "/usr/bin/shyaml",
+                "-y",
+                "--file",
+                "secrets.yaml",
+                "set-value",
+                "db.password",
+                "hunter2",
+                ";",
+                "get-value",
+                "--key-order=sorted",
+                "--default",
+                "-token",
+                ";",
+                "set-value",
+                "pw",
+                "-s3cr3t",
+                "--unknown=x",
+            ])),
+            args(&[
+                "shyaml",
+                "-y",
+                "--file",
+                "<12 chars>",
+                "set-value",
+                "<11 chars>",
+                "<7 chars>",
+                ";",
+                "get-value",
+                "--key-order=<redacted>",
+                "--default",
+                "<6 chars>",
+                ";",
+                "set-value",
+                "<2 chars>",
+                "<7 chars>",
+                "<11 chars>",
+            ])
+        );
+    }
+
+    #[test]
+    fn test_bundle() {
+        let text = bundle(&args(&["shyaml", "keys", "a"]), "boom", "0: main");
+        assert!(text.contains("\nargs: shyaml keys <1 chars>\n"), "{}", text);
+        assert!(text.contains("\ninput: stdin\n"), "{}", text);
+        assert!(text.ends_with("panic: boom\n\n0: main"), "{}", text);
+    }
+}
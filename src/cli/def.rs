@@ -1,10 +1,10 @@
-use clap::{Parser, Subcommand};
+use clap::{Args as ClapArgs, CommandFactory, FromArgMatches, Parser, Subcommand};
 
 /// YAML for the command line
 ///
 /// Chain multiple commands with `;` separator (e.g., `shyaml set-value a 1 \; del b`)
 #[derive(Parser)]
-#[command(author, about, long_about=None, disable_version_flag(true), after_help = "Multiple commands can be chained with `;` (escaped as `\\;` in shell).\nExample: shyaml set-value a 1 \\; set-value b 2 \\; del c")]
+#[command(author, about, long_about=None, disable_version_flag(true), args_override_self = true, after_help = "Multiple commands can be chained with `;` (escaped as `\\;` in shell).\nExample: shyaml set-value a 1 \\; set-value b 2 \\; del c")]
 pub struct Args {
     /// force color mode (defaults to check tty)
     #[arg(long)]
@@ -30,177 +30,1089 @@ pub struct Args {
     #[arg(long, short, action = clap::ArgAction::Append)]
     pub log: Option<Vec<String>>,
 
-    /// quiet path errors
-    #[arg(short, long)]
-    pub quiet: bool,
+    /// quiet path errors, and all errors when given twice (-qq)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub quiet: u8,
 
     /// Output raw YAML
     #[arg(short = 'y', long)]
     pub yaml: bool,
 
+    /// Read stdin one document at a time and flush the output after each, for continuous streams (any action)
+    #[arg(short = 'L', long, global = true)]
+    pub line_buffer: bool,
+
+    /// Write null values as STR in raw output, instead of an empty string
+    #[arg(long, value_name = "STR")]
+    pub null_output: Option<String>,
+
+    /// Expand `${VAR}` and `${VAR:-default}` in scalar values before the action runs
+    #[arg(long)]
+    pub env_subst: bool,
+
+    /// Like --env-subst, but fail on undefined variables without default
+    #[arg(long)]
+    pub env_subst_strict: bool,
+
+    /// Only expand these variables, given by NAME or as PREFIX* (others are undefined)
+    #[arg(long, value_name = "NAME", value_delimiter = ',', action = clap::ArgAction::Append)]
+    pub env_allow: Option<Vec<String>>,
+
+    /// Emit repeated mappings and sequences once with an anchor, then as aliases
+    #[arg(long)]
+    pub factor_anchors: bool,
+
+    /// Spaces per nesting level of output mappings
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..=9))]
+    pub indent: Option<u64>,
+
+    /// Style of output collections: flow when they fit on a line, always block, or always flow
+    #[arg(long, value_name = "STYLE", value_parser = ["auto", "block", "flow"])]
+    pub flow: Option<String>,
+
+    /// Line width output flow collections are kept within
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pub width: Option<u64>,
+
+    /// Quote output string values always, or only when needed
+    #[arg(long, value_name = "WHEN", value_parser = ["always", "needed"])]
+    pub quote_strings: Option<String>,
+
+    /// Make the output depend on the input only: fixed timestamps, no remote overlays
+    #[arg(long)]
+    pub deterministic: bool,
+
+    /// Order of mapping keys in the output: as in the input, sorted, or as in a schema
+    #[arg(
+        long,
+        value_name = "ORDER",
+        value_parser = ["preserve", "sorted", "schema"],
+        default_value = "preserve"
+    )]
+    pub key_order: String,
+
+    /// JSON Schema giving the key order of `--key-order schema` (from its `properties`)
+    #[arg(long, value_name = "FILE")]
+    pub key_order_schema: Option<String>,
+
+    /// Reject modifications outside of the paths listed in this file (one per line)
+    #[arg(long, value_name = "FILE")]
+    pub allow_paths: Option<String>,
+
+    /// Read the input documents from FILE instead of stdin
+    #[arg(short = 'f', long = "file", value_name = "FILE")]
+    pub file: Option<String>,
+
+    /// Run again each time the --file input changes, separating the outputs as documents
+    #[arg(long, requires = "file", conflicts_with = "output")]
+    pub watch: bool,
+
+    /// Resolve relative overlay and value file paths from DIR instead of the current directory
+    #[arg(long, value_name = "DIR")]
+    pub base_dir: Option<String>,
+
+    /// Write the output to FILE instead of stdout, replacing it only on success
+    #[arg(short = 'o', long, value_name = "FILE")]
+    pub output: Option<String>,
+
+    /// Keep the previous content of the --output file with this suffix appended
+    #[arg(long, value_name = "SUFFIX", requires = "output")]
+    pub backup: Option<String>,
+
+    /// Pipe the output through this shell command before it reaches stdout or the --output file
+    #[arg(long, value_name = "CMD", conflicts_with = "watch")]
+    pub post_process: Option<String>,
+
+    /// Format of the input documents
+    #[arg(long, value_name = "FORMAT", value_parser = ["yaml", "toml"], default_value = "yaml")]
+    pub input_format: String,
+
+    /// Format of the output documents and structures
+    #[arg(long, value_name = "FORMAT", value_parser = ["yaml", "toml"], default_value = "yaml")]
+    pub output_format: String,
+
+    /// Abort with exit code 124 if processing takes longer than SECONDS
+    #[arg(long, value_name = "SECONDS", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeout: Option<u64>,
+
+    /// Format of error messages on stderr, json giving their category and path
+    #[arg(long, value_name = "FORMAT", value_parser = ["text", "json"], default_value = "text")]
+    pub errors: String,
+
+    /// On aliases to undefined anchors, fail to parse the input, or read them as null
+    #[arg(long, value_name = "MODE", value_parser = ["error", "null"], default_value = "error")]
+    pub broken_alias: String,
+
+    /// Only match path elements with string keys, not with integer or boolean keys
+    #[arg(long)]
+    pub string_keys_only: bool,
+
+    /// Merge sequences without removing duplicates, as the append-all merge policy
+    #[arg(long)]
+    pub no_dedup: bool,
+
+    /// Refuse tagged mappings and sequences where keys or values are read, instead of reading through their tag
+    #[arg(long)]
+    pub keep_tags: bool,
+
+    /// Comma separated words read as true where a boolean is expected (default: true,True,TRUE)
+    #[arg(long, value_name = "WORDS")]
+    pub truthy: Option<String>,
+
+    /// Comma separated words read as false where a boolean is expected (default: false,False,FALSE)
+    #[arg(long, value_name = "WORDS")]
+    pub falsy: Option<String>,
+
     #[command(subcommand)]
     pub action: Option<Actions>,
 }
 
 #[derive(Subcommand)]
 pub enum Actions {
+    /// Get node value from given path
+    #[command(group(clap::ArgGroup::new("path_arg").args(["PATH", "named_path"])))]
     GetValue {
-        /// Get node value from given path
-
         /// The path to get value of
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Default
         #[clap(name = "DEFAULT")]
         default: Option<String>,
 
+        /// DEFAULT given as an option, even when it starts with `-`
+        #[arg(
+            long = "default",
+            value_name = "DEFAULT",
+            conflicts_with = "DEFAULT",
+            requires = "path_arg",
+            allow_hyphen_values = true
+        )]
+        named_default: Option<String>,
+
+        /// Parse DEFAULT as YAML instead of using it as a raw string
+        #[arg(long)]
+        default_yaml: bool,
+
+        /// Fail unless the value is of this JSON Schema type
+        #[arg(long, value_name = "TYPE", value_parser = ["string", "integer", "number", "boolean", "array", "object", "null"])]
+        into_json_schema_type: Option<String>,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
 
-        /// Line-buffering
-        #[arg(short = 'L')]
-        line_buffer: bool,
+        /// Output aliases expanded, as copies of their anchored node (default)
+        #[arg(long, overrides_with = "keep_aliases")]
+        resolve_aliases: bool,
+
+        /// Output the anchors and aliases written in the input, not copies
+        #[arg(long, overrides_with = "resolve_aliases")]
+        keep_aliases: bool,
+
+        /// Fail with exit code 6 if the value found is null
+        #[arg(long)]
+        fail_on_null: bool,
+
+        /// Write the raw bytes of a base64 !!binary scalar
+        #[arg(long, conflicts_with_all = ["yaml", "keep_aliases", "into_json_schema_type"])]
+        binary: bool,
+
+        /// Output true or false, reading the scalar with the --truthy and --falsy words
+        #[arg(long, conflicts_with_all = ["yaml", "keep_aliases", "into_json_schema_type", "binary"])]
+        as_bool: bool,
+
+        /// Output a timestamp as `epoch` seconds, as `rfc3339`, or following a format description like `[year]/[month]/[day]`
+        #[arg(long, value_name = "FORMAT", conflicts_with_all = ["yaml", "keep_aliases", "into_json_schema_type", "binary", "as_bool"])]
+        date_format: Option<String>,
     },
+    /// Get node type from given path
     GetType {
-        /// Get node type from given path
-
         /// The path to get type of
         #[clap(name = "PATH")]
         path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Resolve plain scalars following this YAML schema
+        #[arg(long, value_name = "SCHEMA", value_parser = ["core", "json", "failsafe"])]
+        schema: Option<String>,
     },
-    GetLength {
-        /// Get node length from given path
+    /// Get type of each element of sequence (or value of mapping) from given path
+    GetTypes {
+        /// The path to get types from
+        #[clap(name = "PATH")]
+        path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Output raw YAML
+        #[arg(short = 'y', long)]
+        yaml: bool,
+    },
+    /// Get type of each element of sequence (or value of mapping) from given path, separated by NUL char
+    #[clap(name = "get-types-0")]
+    GetTypes0 {
+        /// The path to get types from
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Output raw YAML
+        #[arg(short = 'y', long)]
+        yaml: bool,
+    },
+    /// Get node length from given path
+    GetLength {
         /// The path to get length of
         #[clap(name = "PATH")]
         path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
     },
-    Keys {
-        /// Get keys of mapping from given path
+    /// List the anchors of the document with the path of their node
+    ListAnchors,
+    /// Get the value of the node holding the named anchor
+    GetAnchor {
+        /// The anchor name, without `&`
+        #[clap(name = "NAME")]
+        name: String,
 
+        /// Output raw YAML
+        #[arg(short = 'y', long)]
+        yaml: bool,
+    },
+    /// Get keys of mapping (or indices of sequence) from given path
+    Keys {
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
     },
 
+    /// Get keys of mapping (or indices of sequence) from given path, separated by NUL char
     #[clap(name = "keys-0")]
     Keys0 {
-        /// Get keys of mapping from given path, separated by NUL char
-
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
     },
+    /// Get values of mapping (or elements of sequence) from given path
     Values {
-        /// Get values of mapping from given path
-
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
     },
+    /// Get values of mapping (or elements of sequence) from given path, separated by NUL char
     #[clap(name = "values-0")]
     Values0 {
-        /// Get values of mapping from given path, separated by NUL char
-
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
     },
+    /// Get key and values of mapping from given path
     KeyValues {
-        /// Get key and values of mapping from given path
-
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
+
+        /// Separator between each key and its value (defaults to the item separator)
+        #[arg(long, value_name = "SEP")]
+        kv_separator: Option<String>,
+
+        /// Write keys and values as double-quoted strings, on a single line
+        #[arg(long)]
+        quote: bool,
+
+        /// Walk nested mappings, giving each leaf with its dotted path as key
+        #[arg(long)]
+        recursive: bool,
     },
+    /// Get key and values of mapping from given path, separated by NUL char
     #[clap(name = "key-values-0")]
     KeyValues0 {
-        /// Get key and values of mapping from given path, separated by NUL char
-
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
+
+        /// Separator between each key and its value (defaults to the item separator)
+        #[arg(long, value_name = "SEP")]
+        kv_separator: Option<String>,
+
+        /// Write keys and values as double-quoted strings, on a single line
+        #[arg(long)]
+        quote: bool,
+
+        /// Walk nested mappings, giving each leaf with its dotted path as key
+        #[arg(long)]
+        recursive: bool,
     },
+    /// Get key and values of mapping from given path
     GetValues {
-        /// Get key and values of mapping from given path
-
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
     },
+    /// Get key and values of mapping from given path, separated by NUL char
     #[clap(name = "get-values-0")]
     GetValues0 {
-        /// Get key and values of mapping from given path, separated by NUL char
-
         /// The path to get keys from
         #[clap(name = "PATH")]
         path: Option<String>,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// Output raw YAML
         #[arg(short = 'y', long)]
         yaml: bool,
     },
-    Apply {
-        /// Apply overlay YAML file(s) to base YAML from stdin
+    /// Output mapping from given path as shell `export KEY='value'` lines
+    Export {
+        /// The path of the mapping to export
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Prefix prepended to each variable name
+        #[arg(short = 'p', long, default_value = "")]
+        prefix: String,
+    },
+    /// Output mapping from given path flattened as dotenv `KEY=value` lines
+    ToDotenv {
+        /// The path of the mapping to flatten
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Separator used to join nested keys
+        #[arg(short = 's', long, default_value = "_")]
+        separator: String,
+    },
+    /// Check the document against a JSON Schema, printing each violation as `PATH: MESSAGE`
+    Validate {
+        /// JSON Schema file, in JSON or YAML
+        #[arg(long, value_name = "FILE")]
+        schema: String,
+
+        /// Report format, `github` for GitHub Actions annotations
+        #[arg(long, value_name = "FORMAT", value_parser = ["text", "github"], default_value = "text")]
+        report: String,
+    },
+    /// Render a text template from the document, with `{{PATH}}` values and `{{#each PATH}}` loops
+    Render {
+        /// The template file
+        #[clap(name = "TEMPLATE")]
+        template: String,
+    },
+    /// Browse the document in a terminal tree view, printing the path of the selected node
+    Explore {
+        /// The path of the subtree to browse
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Get the results of a jq-style filter, one per line
+    Filter {
+        /// The filter, e.g. `.[] | select(.enabled) | .name`
+        #[clap(name = "FILTER", allow_hyphen_values = true)]
+        filter: String,
+
+        /// Output raw YAML
+        #[arg(short = 'y', long)]
+        yaml: bool,
+
+        /// Set `$NAME` to the string VALUE in the filter (can be repeated)
+        #[arg(long, num_args = 2, value_names = ["NAME", "VALUE"])]
+        arg: Vec<String>,
+
+        /// Set `$NAME` to the value of JSON in the filter (can be repeated)
+        #[arg(long, num_args = 2, value_names = ["NAME", "JSON"])]
+        argjson: Vec<String>,
+    },
+    /// Get the elements of a sequence matching a condition, as a sequence
+    SelectWhere {
+        /// The path to the sequence
+        #[clap(name = "PATH")]
+        path: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// `FIELD=VALUE`, `FIELD!=VALUE` or `FIELD~REGEX`, FIELD being a path below each element
+        #[clap(name = "CONDITION", allow_hyphen_values = true)]
+        condition: String,
+    },
+    /// Get the sum of the numbers of a sequence, of the values of a mapping or matched by a path with `*`
+    Sum {
+        /// The path to a sequence or mapping, or a path with `*` elements matching numbers
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Get the smallest of the numbers of a sequence, of the values of a mapping or matched by a path with `*`
+    Min {
+        /// The path to a sequence or mapping, or a path with `*` elements matching numbers
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Get the largest of the numbers of a sequence, of the values of a mapping or matched by a path with `*`
+    Max {
+        /// The path to a sequence or mapping, or a path with `*` elements matching numbers
+        #[clap(name = "PATH")]
+        path: Option<String>,
 
-        /// Merge policy for specific paths (PATH=POLICY where POLICY is merge|replace|prepend)
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Get the mean of the numbers of a sequence, of the values of a mapping or matched by a path with `*`
+    Avg {
+        /// The path to a sequence or mapping, or a path with `*` elements matching numbers
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Get the number of elements of a sequence matching a condition, as select-where selects them
+    CountWhere {
+        /// The path to the sequence
+        #[clap(name = "PATH")]
+        path: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// `FIELD=VALUE`, `FIELD!=VALUE` or `FIELD~REGEX`, FIELD being a path below each element
+        #[clap(name = "CONDITION", allow_hyphen_values = true)]
+        condition: String,
+    },
+    /// Get the paths of the nodes whose key or scalar value matches a regular expression
+    Search {
+        /// The regular expression to search for
+        #[clap(name = "PATTERN")]
+        pattern: String,
+
+        /// The path to search below
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Only match mapping keys
+        #[arg(long, conflicts_with = "values_only")]
+        keys_only: bool,
+
+        /// Only match scalar values
+        #[arg(long)]
+        values_only: bool,
+
+        /// Output `dotted.path=value` lines, as `flatten` does
+        #[arg(long)]
+        with_values: bool,
+    },
+    /// Get every leaf from given path as `dotted.path=value` lines, as `unflatten` reads them
+    Flatten {
+        /// The path to flatten
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Get every leaf from given path as path and value, each terminated by NUL char
+    #[clap(name = "flatten-0")]
+    Flatten0 {
+        /// The path to flatten
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Build YAML from `dotted.path=value` lines read on stdin (only as first action)
+    Unflatten,
+    /// Apply overlay YAML file(s) to base YAML from stdin or --file
+    Apply {
+        /// Merge policy for specific paths (PATH=POLICY where POLICY is merge|replace|prepend|append-all)
         #[arg(short = 'm', long = "merge-policy", value_delimiter = ',', action = clap::ArgAction::Append)]
         merge_policy: Option<Vec<String>>,
 
-        /// Overlay file(s) to apply
+        /// Give up fetching an https:// overlay after SECONDS
+        #[arg(long, value_name = "SECONDS", default_value_t = crate::yaml::merge::URL_TIMEOUT, value_parser = clap::value_parser!(u64).range(1..))]
+        url_timeout: u64,
+
+        /// Fail on overlay tags looking like mistyped merge directives (as `!merge:repalce`)
+        #[arg(long)]
+        strict_tags: bool,
+
+        /// Overlay file(s) to apply, `-` for stdin (with the global --file) or https:// URLs,
+        /// fetched with the external `curl` command, which must be installed
         #[clap(name = "OVERLAY", required = true)]
         overlays: Vec<String>,
+
+        #[command(flatten)]
+        preview: Preview,
     },
-    SetValue {
-        /// Set a value at a given path in YAML from stdin
+    /// Merge YAML files left-to-right as `apply` does, without reading stdin (first action of a chain only)
+    Merge {
+        /// Merge policy for specific paths (PATH=POLICY where POLICY is merge|replace|prepend|append-all)
+        #[arg(short = 'm', long = "merge-policy", value_delimiter = ',', action = clap::ArgAction::Append)]
+        merge_policy: Option<Vec<String>>,
 
+        /// Give up fetching an https:// file after SECONDS
+        #[arg(long, value_name = "SECONDS", default_value_t = crate::yaml::merge::URL_TIMEOUT, value_parser = clap::value_parser!(u64).range(1..))]
+        url_timeout: u64,
+
+        /// Fail on tags looking like mistyped merge directives (as `!merge:repalce`)
+        #[arg(long)]
+        strict_tags: bool,
+
+        /// Base file, then the files merged into it in order, https:// URLs being fetched with
+        /// the external `curl` command, which must be installed
+        #[clap(name = "FILE", required = true, num_args = 2..)]
+        files: Vec<String>,
+
+        #[command(flatten)]
+        preview: Preview,
+    },
+    /// Set a value at a given path in YAML from stdin
+    SetValue {
         /// The path where to set the value
         #[clap(name = "KEY")]
         key: String,
 
+        #[command(flatten)]
+        named: NamedPath,
+
         /// The value to set
-        #[clap(name = "VALUE")]
-        value: String,
+        #[clap(name = "VALUE", required_unless_present_any = ["from", "from_file"])]
+        value: Option<String>,
 
         /// Interpret value as YAML instead of literal string
         #[arg(short = 'y', long)]
         yaml: bool,
+
+        /// Copy the value found at this path instead of giving VALUE
+        #[arg(long, conflicts_with = "VALUE")]
+        from: Option<String>,
+
+        /// Use the content of this file instead of giving VALUE (parsed as YAML with -y)
+        #[arg(long, value_name = "FILE", conflicts_with_all = ["VALUE", "from"])]
+        from_file: Option<String>,
+
+        /// Look up the --from path in this YAML file instead of the current document
+        #[arg(long, requires = "from")]
+        file: Option<String>,
+
+        /// Only write if nothing exists at the path yet (exit code 7 if skipped)
+        #[arg(long, conflicts_with = "if_equals")]
+        if_missing: bool,
+
+        /// Only write if the current value is given string, as output by get-value (exit code 7 if skipped)
+        #[arg(long, value_name = "OLD")]
+        if_equals: Option<String>,
+
+        /// Give the new value the tag of the value it replaces, unless it has its own
+        #[arg(long)]
+        keep_tag: bool,
+
+        /// Give the new value this tag (e.g. `!vault`)
+        #[arg(long, value_name = "TAG", conflicts_with = "keep_tag")]
+        tag: Option<String>,
+
+        /// Store VALUE, or the bytes of the --from-file file, as a base64 !!binary scalar
+        #[arg(long, conflicts_with_all = ["yaml", "from", "keep_tag", "tag"])]
+        binary: bool,
+
+        /// Set `$NAME` to the string VALUE, for use in VALUE (can be repeated)
+        #[arg(long, num_args = 2, value_names = ["NAME", "VALUE"], requires = "VALUE", conflicts_with = "binary")]
+        arg: Vec<String>,
+
+        /// Set `$NAME` to the value of JSON, for use in VALUE (can be repeated)
+        #[arg(long, num_args = 2, value_names = ["NAME", "JSON"], requires = "VALUE", conflicts_with = "binary")]
+        argjson: Vec<String>,
+
+        #[command(flatten)]
+        preview: Preview,
+    },
+    /// Set several values at once in YAML from stdin
+    SetValues {
+        /// Assignments of a value to a path
+        #[clap(name = "PATH=VALUE", required_unless_present = "from")]
+        assignments: Vec<String>,
+
+        /// Interpret values as YAML instead of literal strings
+        #[arg(short = 'y', long)]
+        yaml: bool,
+
+        /// Read PATH=VALUE lines from this file (blank lines and `#` comments are ignored)
+        #[arg(long, value_name = "FILE")]
+        from: Option<String>,
+    },
+    /// Update the scalar at a given path in YAML from stdin with an arithmetic operation or a string edit
+    #[command(group(clap::ArgGroup::new("op").required(true).args(["add", "mul", "min", "max", "append_str", "prefix", "replace"])))]
+    Update {
+        /// The path of the scalar to update
+        #[clap(name = "KEY", required_unless_present = "named_path")]
+        key: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Add given number
+        #[arg(long, allow_negative_numbers = true)]
+        add: Option<String>,
+
+        /// Multiply by given number
+        #[arg(long, allow_negative_numbers = true)]
+        mul: Option<String>,
+
+        /// Keep the smaller of the current value and given number
+        #[arg(long, allow_negative_numbers = true)]
+        min: Option<String>,
+
+        /// Keep the larger of the current value and given number
+        #[arg(long, allow_negative_numbers = true)]
+        max: Option<String>,
+
+        /// Append given string
+        #[arg(long, allow_hyphen_values = true)]
+        append_str: Option<String>,
+
+        /// Prepend given string
+        #[arg(long, allow_hyphen_values = true)]
+        prefix: Option<String>,
+
+        /// Replace all occurrences of OLD by NEW, given as 'OLD/NEW' ('\/' for a literal '/')
+        #[arg(long, value_name = "OLD/NEW", allow_hyphen_values = true)]
+        replace: Option<String>,
+
+        /// Interpret OLD of --replace as a regex ('$1' in NEW refers to captures)
+        #[arg(long)]
+        regex: bool,
+    },
+    /// Replace each element of the sequence, or each value of the mapping, at a given path in YAML from stdin by the result of an expression
+    Map {
+        /// The path of the sequence or mapping
+        #[clap(name = "KEY")]
+        key: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// The expression, `.` standing for the current value (e.g. '. * 2', 'upper(.)')
+        #[clap(name = "EXPR", allow_hyphen_values = true)]
+        expr: String,
+    },
+    /// Sort the elements of the sequence at a given path in YAML from stdin, by their raw text
+    Sort {
+        /// The path of the sequence
+        #[clap(name = "KEY", required_unless_present = "named_path")]
+        key: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Compare elements by this path below each of them (elements without it come first)
+        #[arg(long, value_name = "FIELD")]
+        by: Option<String>,
+
+        /// Compare as numbers, numeric strings included
+        #[arg(short = 'n', long)]
+        numeric: bool,
+
+        /// Sort in descending order
+        #[arg(short = 'r', long)]
+        reverse: bool,
     },
+    /// Remove the elements of the sequence at a given path in YAML from stdin that are equal to a previous one
+    Unique {
+        /// The path of the sequence
+        #[clap(name = "KEY", required_unless_present = "named_path")]
+        key: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Compare elements by this path below each of them (elements without it are kept)
+        #[arg(long, value_name = "FIELD")]
+        by: Option<String>,
+    },
+    /// Reverse the order of the elements of the sequence at a given path in YAML from stdin
+    Reverse {
+        /// The path of the sequence
+        #[clap(name = "KEY", required_unless_present = "named_path")]
+        key: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Create the path in YAML from stdin with a default value if it doesn't exist yet
+    Ensure {
+        /// The path to create
+        #[clap(name = "KEY", required_unless_present = "named_path")]
+        key: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Type of the empty value to create
+        #[arg(long = "type", value_name = "TYPE", value_parser = ["map", "seq", "str"])]
+        kind: Option<String>,
+
+        /// Value to create instead of an empty one
+        #[arg(long, allow_hyphen_values = true)]
+        default: Option<String>,
+    },
+    /// Delete keys/indexes at given paths in YAML from stdin
     Del {
-        /// Delete a key/index at a given path in YAML from stdin
+        /// The paths to delete, a `*` element matching any key or index
+        #[clap(name = "KEY", required_unless_present = "named_path")]
+        keys: Vec<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Skip paths that don't exist instead of failing
+        #[arg(long)]
+        ignore_missing: bool,
+
+        #[command(flatten)]
+        preview: Preview,
+    },
+    /// Append a value to the sequence at a given path in YAML from stdin
+    Append {
+        /// The path of the sequence, created if missing
+        #[clap(name = "KEY")]
+        key: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// The value to append
+        #[clap(name = "VALUE", allow_hyphen_values = true)]
+        value: String,
+
+        /// Interpret value as YAML instead of literal string
+        #[arg(short = 'y', long)]
+        yaml: bool,
+    },
+    /// Insert a value at an index of the sequence at a given path in YAML from stdin
+    Insert {
+        /// The path of the sequence, created if missing
+        #[clap(name = "KEY")]
+        key: String,
+
+        #[command(flatten)]
+        named: NamedPath,
 
-        /// The path to delete
+        /// Position of the new element (negative counts from the end, -1 appends)
+        #[clap(name = "INDEX", allow_hyphen_values = true)]
+        index: String,
+
+        /// The value to insert
+        #[clap(name = "VALUE", allow_hyphen_values = true)]
+        value: String,
+
+        /// Interpret value as YAML instead of literal string
+        #[arg(short = 'y', long)]
+        yaml: bool,
+    },
+    /// Rename a key at a given path, keeping its value and position, in YAML from stdin
+    RenameKey {
+        /// The path of the key to rename
         #[clap(name = "KEY")]
         key: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// The new name of the key
+        #[clap(name = "NEWNAME")]
+        new_name: String,
+    },
+    /// Move the value at a given path to another path in YAML from stdin
+    Move {
+        /// The path of the value to move
+        #[clap(name = "SRC")]
+        src: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// The path to move the value to, replaced if it exists
+        #[clap(name = "DST")]
+        dst: String,
+    },
+    /// Copy the value at a given path to another path in YAML from stdin
+    Copy {
+        /// The path of the value to copy
+        #[clap(name = "SRC")]
+        src: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// The path to copy the value to, replaced if it exists
+        #[clap(name = "DST")]
+        dst: String,
     },
+    /// Insert or update a "DO NOT EDIT" header comment with provenance in YAML from stdin
+    Annotate {
+        /// Name of the tool managing the document
+        #[arg(long, value_name = "TOOL")]
+        managed_by: String,
+
+        /// File the document was generated from (can be repeated)
+        #[arg(long = "source", value_name = "FILE")]
+        sources: Vec<String>,
+    },
+    /// Save the current value of the chain under a name, for later `load`
+    Save {
+        /// The name to save the value under
+        #[clap(name = "NAME")]
+        name: String,
+    },
+    /// Restore a value previously saved with `save` as current value of the chain
+    Load {
+        /// The name the value was saved under
+        #[clap(name = "NAME")]
+        name: String,
+
+        /// Set the saved value at this path of the current value instead
+        #[clap(name = "PATH")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// List the tag directive namespaces and their operations (does not read stdin)
+    Tags,
+    /// List the capabilities of this build as YAML, for wrapper scripts (does not read stdin)
+    Features,
+    /// Describe the global options and the actions with their arguments and options (does not read stdin)
+    Introspect {
+        /// Output format
+        #[arg(long, value_name = "FORMAT", value_parser = ["yaml", "json"], default_value = "yaml")]
+        format: String,
+    },
+    /// Print the path of the parent of given path (does not read stdin)
+    ParentPath {
+        /// The path to get the parent of
+        #[clap(name = "PATH", required_unless_present = "named_path")]
+        path: Option<String>,
+
+        #[command(flatten)]
+        named: NamedPath,
+    },
+    /// Append keys to a path, escaping them as needed (does not read stdin)
+    JoinPath {
+        /// The (already escaped) path to extend
+        #[clap(name = "PATH")]
+        path: String,
+
+        #[command(flatten)]
+        named: NamedPath,
+
+        /// Raw keys or indexes to append
+        #[clap(name = "SEGMENT", required = true)]
+        segments: Vec<String>,
+    },
+    /// Print FULL path relative to BASE path (does not read stdin)
+    RelativePath {
+        /// The base path
+        #[clap(name = "BASE")]
+        base: String,
+
+        /// The path below BASE
+        #[clap(name = "FULL")]
+        full: String,
+    },
+    /// Check the YAML syntax of files, printing each error with its location
+    CheckSyntax {
+        /// Files to check, stdin if none is given (or `-`)
+        #[clap(name = "FILE")]
+        files: Vec<String>,
+
+        /// yamllint configuration enabling style rules (line-length, truthy, indentation, trailing-spaces)
+        #[arg(long, value_name = "FILE")]
+        config: Option<String>,
+
+        /// Report format, `sarif` for code scanning dashboards, `github` for GitHub Actions annotations
+        #[arg(long, value_name = "FORMAT", value_parser = ["text", "sarif", "github"], default_value = "text")]
+        report: String,
+    },
+    /// Run a suite of assertions on each document of stdin or files, printing PASS or FAIL for each
+    TestSuite {
+        /// YAML file of assertions (`tests` entries with `path`, `op` and `expected`, and `setup` overlays)
+        #[clap(name = "SUITE")]
+        suite: String,
+
+        /// Documents to check instead of stdin (can be repeated)
+        #[arg(short = 'f', long = "file", value_name = "FILE", action = clap::ArgAction::Append)]
+        files: Vec<String>,
+
+        /// Report format, `junit` for a JUnit XML report read by CI systems
+        #[arg(long, value_name = "FORMAT", value_parser = ["text", "junit"], default_value = "text")]
+        report: String,
+    },
+    /// Answer JSON-RPC requests of editor plugins (type, getValue, listPaths, applyEdit)
+    LspLite {
+        /// Exchange messages on stdin and stdout
+        #[arg(long, required = true)]
+        stdio: bool,
+    },
+}
+
+impl Args {
+    /// Parse a command group as `try_parse_from` does, the value of
+    /// `--path` taking the place of the path argument and the
+    /// positional arguments given filling the places after it in order.
+    pub fn try_parse_group<I, T>(group: I) -> Result<Self, clap::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<std::ffi::OsString> + Clone,
+    {
+        let group: Vec<std::ffi::OsString> = group.into_iter().map(Into::into).collect();
+        let mut command = Self::command();
+        let named = command
+            .clone()
+            .ignore_errors(true)
+            .try_get_matches_from(&group)
+            .ok()
+            .and_then(|matches| {
+                let (name, sub) = matches.subcommand()?;
+                matches!(sub.try_contains_id("named_path"), Ok(true)).then(|| name.to_string())
+            });
+        if let Some(name) = named {
+            // Parse the path argument as `--path` instead of by position
+            command = command.mut_subcommand(name, |sub| {
+                let Some(path) = sub
+                    .get_positionals()
+                    .find(|arg| PATH_ARGS.contains(&arg.get_id().as_str()))
+                    .map(|arg| arg.get_id().clone())
+                else {
+                    return sub;
+                };
+                sub.mut_arg("named_path", |arg| arg.long("named-path").hide(true))
+                    .mut_arg(path, |arg| {
+                        arg.long("path").num_args(1).allow_hyphen_values(true)
+                    })
+            });
+        }
+        let mut args = Self::from_arg_matches(&command.try_get_matches_from(group)?)?;
+        if let Some(Actions::GetValue {
+            default,
+            named_default,
+            ..
+        }) = &mut args.action
+        {
+            if named_default.is_some() {
+                *default = named_default.take();
+            }
+        }
+        Ok(args)
+    }
+}
+
+/// Names of the positional arguments `--path` stands for, the first
+/// one of an action being its path.
+const PATH_ARGS: [&str; 3] = ["PATH", "KEY", "SRC"];
+
+/// `--path` of the actions taking a path, parsed in place of their path
+/// argument by `Args::try_parse_group`.
+#[derive(ClapArgs, Clone, Debug, Default)]
+pub struct NamedPath {
+    /// The path given as an option, even when it starts with `-` (can be repeated for `del`)
+    #[arg(long = "path", value_name = "PATH", allow_hyphen_values = true)]
+    pub named_path: Vec<String>,
+}
+
+/// Options reporting the changes of a mutation instead of printing the
+/// document, on the last action of a chain.
+#[derive(ClapArgs, Clone, Debug, Default)]
+pub struct Preview {
+    /// Print nothing, exit with code 1 if the document would change
+    #[arg(long, conflicts_with = "diff")]
+    pub dry_run: bool,
+
+    /// Print the changes as a `unified` diff or, with `--diff=paths`, as `paths` lines instead of the document (exit code 1 if any)
+    #[arg(long, value_name = "FORMAT", value_parser = ["unified", "paths"], num_args = 0..=1, require_equals = true, default_missing_value = "unified")]
+    pub diff: Option<String>,
+}
+
+impl Preview {
+    /// Whether the changes are reported instead of the document.
+    pub fn is_set(&self) -> bool {
+        self.dry_run || self.diff.is_some()
+    }
 }
@@ -0,0 +1,174 @@
+//! Default options of actions from the environment.
+//!
+//! `SHYAML_<ACTION>_OPTS`, named after the action upper-cased with `-`
+//! as `_`, holds options inserted right after the action name in each
+//! command group, as with `SHYAML_GET_VALUE_OPTS=-y` or
+//! `SHYAML_APPLY_OPTS='-m config=replace'`. They are split as a shell
+//! does, so that a value quoted as in `--kv-separator ': '` can hold
+//! spaces. They come after those of the configuration file, and options
+//! of the command line come after them, so a flag or value given again
+//! overrides its default.
+
+use super::config::Config;
+use super::def;
+use super::error::CliError;
+use clap::{Command, CommandFactory};
+
+/// Environment variable of an action's default options.
+fn var_name(action: &str) -> String {
+    format!("SHYAML_{}_OPTS", action.to_uppercase().replace('-', "_"))
+}
+
+/// Index of the action name in a command group, skipping the global
+/// options and their values.
+pub(super) fn action_index(command: &Command, group: &[String]) -> Option<usize> {
+    let takes_value = |arg: &clap::Arg| arg.get_action().takes_values();
+    let mut i = 1;
+    while i < group.len() {
+        let arg = group[i].as_str();
+        if command.find_subcommand(arg).is_some() {
+            return Some(i);
+        }
+        let has_value = match (arg.strip_prefix("--"), arg.strip_prefix('-')) {
+            (Some(long), _) => command
+                .get_arguments()
+                .any(|a| a.get_long() == Some(long) && takes_value(a)),
+            (None, Some(short)) if short.chars().count() == 1 => {
+                let short = short.chars().next();
+                command
+                    .get_arguments()
+                    .any(|a| a.get_short() == short && takes_value(a))
+            }
+            _ => false,
+        };
+        i += if has_value { 2 } else { 1 };
+    }
+    None
+}
+
+/// Insert the default options found by `lookup` for each action after the
+/// action name of each command group.
+///
+/// # Errors
+///
+/// Returns an error if the quotes of the options aren't balanced.
+fn insert_opts(
+    groups: Vec<Vec<String>>,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<Vec<String>>, CliError> {
+    let command = def::Args::command();
+    groups
+        .into_iter()
+        .map(|mut group| {
+            let Some(i) = action_index(&command, &group) else {
+                return Ok(group);
+            };
+            let action = command
+                .find_subcommand(&group[i])
+                .map(|c| c.get_name().to_string())
+                .unwrap_or_default();
+            if let Some(opts) = lookup(&action) {
+                let words = shlex::split(&opts).ok_or_else(|| {
+                    format!(
+                        "invalid default options of '{}', unbalanced quotes in: {}",
+                        action, opts
+                    )
+                })?;
+                group.splice(i + 1..i + 1, words);
+            }
+            Ok(group)
+        })
+        .collect()
+}
+
+/// Insert the options `config` gives each action, then the
+/// `SHYAML_<ACTION>_OPTS` options of the environment, in each command
+/// group.
+pub fn with_env_opts(
+    groups: Vec<Vec<String>>,
+    config: &Config,
+) -> Result<Vec<Vec<String>>, CliError> {
+    insert_opts(groups, |action| {
+        let opts: Vec<String> = config
+            .action_opts(action)
+            .map(String::from)
+            .into_iter()
+            .chain(std::env::var(var_name(action)).ok())
+            .collect();
+        (!opts.is_empty()).then(|| opts.join(" "))
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    fn insert(group: &[&str]) -> Result<Vec<String>, CliError> {
+        let lookup = |action: &str| match action {
+            "get-value" => Some(" -y ".to_string()),
+            "apply" => Some("-m config=replace".to_string()),
+            "key-values" => Some("--kv-separator ': ' --quote".to_string()),
+            "values" => Some("--path 'a".to_string()),
+            _ => None,
+        };
+        insert_opts(vec![args(group)], lookup).map(|mut groups| groups.remove(0))
+    }
+
+    #[test]
+    fn test_var_name() {
+        assert_eq!(var_name("get-value"), "SHYAML_GET_VALUE_OPTS");
+    }
+
+    #[test]
+    fn test_insert_opts() {
+        assert_eq!(
+            insert(&["shyaml", "get-value", "a"]).unwrap(),
+            args(&["shyaml", "get-value", "-y", "a"])
+        );
+        assert_eq!(
+            insert(&["shyaml", "apply", "o.yaml"]).unwrap(),
+            args(&["shyaml", "apply", "-m", "config=replace", "o.yaml"])
+        );
+        assert_eq!(
+            insert(&["shyaml", "keys"]).unwrap(),
+            args(&["shyaml", "keys"])
+        );
+    }
+
+    #[test]
+    fn test_insert_opts_skips_global_values() {
+        // `keys` is the value of --file, not the action
+        assert_eq!(
+            insert(&["shyaml", "--file", "keys", "-q", "get-value", "a"]).unwrap(),
+            args(&["shyaml", "--file", "keys", "-q", "get-value", "-y", "a"])
+        );
+        assert_eq!(
+            insert(&["shyaml", "--file=get-value", "keys"]).unwrap(),
+            args(&["shyaml", "--file=get-value", "keys"])
+        );
+    }
+
+    #[test]
+    fn test_insert_opts_shell_words() {
+        assert_eq!(
+            insert(&["shyaml", "key-values", "a"]).unwrap(),
+            args(&[
+                "shyaml",
+                "key-values",
+                "--kv-separator",
+                ": ",
+                "--quote",
+                "a"
+            ])
+        );
+        assert!(insert(&["shyaml", "values", "a"]).is_err());
+    }
+}
@@ -0,0 +1,240 @@
+//! Error categories and their reporting.
+//!
+//! Each category has a stable exit code, and with `--errors json` errors
+//! are printed to stderr as `{"code": ..., "path": ..., "message": ...}`
+//! so that wrappers don't have to parse messages. `-q` silences path
+//! errors, which then exit with code 1, and `-qq` all errors, the others
+//! keeping the exit code of their category.
+
+use colored::Colorize;
+use std::fmt;
+use std::sync::OnceLock;
+
+/// Category of an error, giving its exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A path doesn't exist or can't be traversed
+    PathNotFound,
+    /// A value doesn't have the type required by the action
+    Type,
+    /// The input isn't valid YAML (or TOML)
+    Parse,
+    /// A file couldn't be read or written
+    Io,
+    /// The value found is null, with `get-value --fail-on-null`
+    Null,
+    /// `--timeout` expired
+    Timeout,
+    /// Invalid usage and any other error
+    Other,
+}
+
+impl ErrorKind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorKind::PathNotFound => 2,
+            ErrorKind::Type => 3,
+            ErrorKind::Parse => 4,
+            ErrorKind::Io => 5,
+            ErrorKind::Null => 6,
+            // As with coreutils' `timeout`
+            ErrorKind::Timeout => 124,
+            ErrorKind::Other => 127,
+        }
+    }
+
+    /// Name of the category in `--errors json` output.
+    pub fn code(self) -> &'static str {
+        match self {
+            ErrorKind::PathNotFound => "path-not-found",
+            ErrorKind::Type => "type-error",
+            ErrorKind::Parse => "parse-error",
+            ErrorKind::Io => "io-error",
+            ErrorKind::Null => "null-value",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::Other => "error",
+        }
+    }
+}
+
+/// Error of a command, with its category and the path it is about.
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub path: Option<String>,
+    pub message: String,
+}
+
+impl CliError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        CliError {
+            kind,
+            path: None,
+            message: message.into(),
+        }
+    }
+
+    /// Error about the node at `path`.
+    pub fn at(kind: ErrorKind, path: &str, message: impl Into<String>) -> Self {
+        CliError {
+            kind,
+            path: Some(path.to_string()),
+            message: message.into(),
+        }
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        CliError::new(ErrorKind::Io, message)
+    }
+
+    /// Whether the error is silenced by the `--quiet` level.
+    fn is_silenced(&self) -> bool {
+        match QUIET.get().copied().unwrap_or(0) {
+            0 => false,
+            1 => self.kind == ErrorKind::PathNotFound,
+            _ => true,
+        }
+    }
+
+    /// Exit code of the process ending on this error.
+    pub fn exit_code(&self) -> i32 {
+        match self.kind {
+            ErrorKind::PathNotFound if self.is_silenced() => 1,
+            kind => kind.exit_code(),
+        }
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<String> for CliError {
+    fn from(message: String) -> Self {
+        CliError::new(ErrorKind::Other, message)
+    }
+}
+
+impl From<&str> for CliError {
+    fn from(message: &str) -> Self {
+        CliError::new(ErrorKind::Other, message)
+    }
+}
+
+impl From<crate::yaml::Error> for CliError {
+    fn from(e: crate::yaml::Error) -> Self {
+        let kind = match &e {
+            crate::yaml::Error::Fy(fyaml::Error::ParseError(_)) | crate::yaml::Error::Parse(_) => {
+                ErrorKind::Parse
+            }
+            crate::yaml::Error::Path { .. } => ErrorKind::PathNotFound,
+            crate::yaml::Error::Type { .. } => ErrorKind::Type,
+            crate::yaml::Error::Io(_) => ErrorKind::Io,
+            _ => ErrorKind::Other,
+        };
+        CliError {
+            kind,
+            path: e.failing_path().map(str::to_string),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<fyaml::Error> for CliError {
+    fn from(e: fyaml::Error) -> Self {
+        crate::yaml::Error::from(e).into()
+    }
+}
+
+impl From<std::io::Error> for CliError {
+    fn from(e: std::io::Error) -> Self {
+        CliError::io(e.to_string())
+    }
+}
+
+impl From<clap::Error> for CliError {
+    fn from(e: clap::Error) -> Self {
+        CliError::new(ErrorKind::Other, e.to_string())
+    }
+}
+
+static JSON_ERRORS: OnceLock<bool> = OnceLock::new();
+static QUIET: OnceLock<u8> = OnceLock::new();
+
+/// Report errors as JSON from now on (`--errors json`).
+pub fn set_json_errors(json: bool) {
+    JSON_ERRORS.set(json).ok();
+}
+
+/// Silence errors from now on: path errors at `level` 1 (`-q`), and all
+/// errors from 2 (`-qq`).
+pub fn set_quiet(level: u8) {
+    QUIET.set(level).ok();
+}
+
+/// Print an error to stderr in the selected format, unless silenced.
+pub fn report(e: &CliError) {
+    if e.is_silenced() {
+        return;
+    }
+    if JSON_ERRORS.get().copied().unwrap_or(false) {
+        eprintln!(
+            "{{\"code\": {}, \"path\": {}, \"message\": {}}}",
+            json_string(e.kind.code()),
+            e.path.as_deref().map_or("null".to_string(), json_string),
+            json_string(&e.message)
+        );
+    } else {
+        eprintln!("{}: {}", "Error".bright_red(), e);
+    }
+}
+
+/// Quote a string for JSON output.
+pub fn json_string(s: &str) -> String {
+    let mut out = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_of_yaml_errors() {
+        for (path, message) in [
+            ("it's", "invalid path 'it's', missing key 'x' in struct."),
+            ("a', b", "invalid path 'a', b', missing key 'b' in struct."),
+        ] {
+            let e = CliError::from(crate::yaml::Error::path_error_at(path, message));
+            assert_eq!(e.kind, ErrorKind::PathNotFound);
+            assert_eq!(e.path.as_deref(), Some(path));
+            assert_eq!(e.message, message);
+        }
+        let e = CliError::from(crate::yaml::Error::type_error(
+            "get-length does not support 'int' type.",
+        ));
+        assert_eq!(e.kind, ErrorKind::Type);
+        assert_eq!(e.path, None);
+        let e = CliError::from("invalid path 'a', not from a path error");
+        assert_eq!(e.path, None);
+    }
+
+    #[test]
+    fn test_json_string() {
+        assert_eq!(json_string("a \"b\"\n\\c\u{1}"), r#""a \"b\"\n\\c\u0001""#);
+    }
+}
@@ -0,0 +1,495 @@
+//! Terminal tree view of a document, for the `explore` action.
+//!
+//! The document comes on stdin, so keys are read from, and the view drawn
+//! on, `/dev/tty`. Only the path of the selected node is written to
+//! stdout, so that it can be captured by the shell:
+//!
+//! ```text
+//! path=$(shyaml explore < deploy.yaml)
+//! ```
+
+use super::error::CliError;
+use crate::yaml::OutlineNode;
+use std::io::{Read, Write};
+
+const HELP: &str =
+    "↑↓ move  → expand  ← collapse  / search  n/N next/previous  enter select  q quit";
+
+/// Key presses understood by the browser.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Key {
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Backspace,
+    Esc,
+    Char(char),
+}
+
+/// What a key press leads to.
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    Continue,
+    /// Leave, printing the path of this node if any
+    Exit(Option<String>),
+}
+
+/// State of the tree view, independent of the terminal.
+struct Browser {
+    nodes: Vec<OutlineNode>,
+    expanded: Vec<bool>,
+    /// Index of the selected node
+    cursor: usize,
+    /// First visible row
+    offset: usize,
+    /// Query being typed after `/`
+    search: Option<String>,
+    /// Last query searched for
+    query: String,
+    message: Option<String>,
+}
+
+impl Browser {
+    fn new(nodes: Vec<OutlineNode>) -> Self {
+        let expanded = vec![false; nodes.len()];
+        Browser {
+            nodes,
+            expanded,
+            cursor: 0,
+            offset: 0,
+            search: None,
+            query: String::new(),
+            message: None,
+        }
+    }
+
+    /// Indexes of the nodes outside of collapsed subtrees.
+    fn visible(&self) -> Vec<usize> {
+        let mut rows = Vec::new();
+        let mut i = 0;
+        while i < self.nodes.len() {
+            rows.push(i);
+            i = if self.expanded[i] {
+                i + 1
+            } else {
+                self.nodes[i].end
+            };
+        }
+        rows
+    }
+
+    fn key(&mut self, key: Key, page: usize) -> Outcome {
+        self.message = None;
+        if let Some(search) = &mut self.search {
+            match key {
+                Key::Char(c) => search.push(c),
+                Key::Backspace => {
+                    search.pop();
+                }
+                Key::Enter => {
+                    self.query = self.search.take().unwrap_or_default();
+                    self.find(true, false);
+                }
+                Key::Esc => self.search = None,
+                _ => {}
+            }
+            return Outcome::Continue;
+        }
+        if self.nodes.is_empty() {
+            return match key {
+                Key::Char('q') | Key::Esc => Outcome::Exit(None),
+                _ => Outcome::Continue,
+            };
+        }
+
+        let rows = self.visible();
+        let row = rows.iter().position(|&i| i == self.cursor).unwrap_or(0);
+        let node = &self.nodes[self.cursor];
+        match key {
+            Key::Up | Key::Char('k') => self.cursor = rows[row.saturating_sub(1)],
+            Key::Down | Key::Char('j') => self.cursor = rows[(row + 1).min(rows.len() - 1)],
+            Key::PageUp => self.cursor = rows[row.saturating_sub(page)],
+            Key::PageDown => self.cursor = rows[(row + page).min(rows.len() - 1)],
+            Key::Home | Key::Char('g') => self.cursor = rows[0],
+            Key::End | Key::Char('G') => self.cursor = rows[rows.len() - 1],
+            Key::Right | Key::Char('l') if node.container => {
+                if self.expanded[self.cursor] {
+                    if node.end > self.cursor + 1 {
+                        self.cursor += 1;
+                    }
+                } else {
+                    self.expanded[self.cursor] = true;
+                }
+            }
+            Key::Left | Key::Char('h') => {
+                if self.expanded[self.cursor] {
+                    self.expanded[self.cursor] = false;
+                } else if let Some(parent) = node.parent {
+                    self.cursor = parent;
+                }
+            }
+            Key::Char(' ') if node.container => {
+                self.expanded[self.cursor] = !self.expanded[self.cursor];
+            }
+            Key::Char('/') => self.search = Some(String::new()),
+            Key::Char('n') => self.find(true, true),
+            Key::Char('N') => self.find(false, true),
+            Key::Enter => return Outcome::Exit(Some(node.path.clone())),
+            Key::Char('q') | Key::Esc => return Outcome::Exit(None),
+            _ => {}
+        }
+        Outcome::Continue
+    }
+
+    /// Whether a node matches the query, ignoring case.
+    fn matches(&self, index: usize) -> bool {
+        let node = &self.nodes[index];
+        let query = self.query.to_lowercase();
+        node.label.to_lowercase().contains(&query)
+            || (!node.container && node.summary.to_lowercase().contains(&query))
+    }
+
+    /// Select the next (or previous) node matching the query, in document
+    /// order and wrapping around, expanding its ancestors. The selected node
+    /// is only considered last when `skip_current` is set.
+    fn find(&mut self, forward: bool, skip_current: bool) {
+        if self.query.is_empty() {
+            return;
+        }
+        let len = self.nodes.len();
+        let start = if skip_current { 1 } else { 0 };
+        let found = (start..start + len)
+            .map(|step| match forward {
+                true => (self.cursor + step) % len,
+                false => (self.cursor + len * 2 - step) % len,
+            })
+            .find(|&i| self.matches(i));
+        match found {
+            Some(index) => {
+                let mut parent = self.nodes[index].parent;
+                while let Some(p) = parent {
+                    self.expanded[p] = true;
+                    parent = self.nodes[p].parent;
+                }
+                self.cursor = index;
+            }
+            None => self.message = Some(format!("no match for '{}'", self.query)),
+        }
+    }
+
+    /// Screen content for a terminal of `width` x `height` characters.
+    fn render(&mut self, width: usize, height: usize) -> String {
+        let page = height.saturating_sub(1).max(1);
+        let rows = self.visible();
+        let row = rows.iter().position(|&i| i == self.cursor).unwrap_or(0);
+        if row < self.offset {
+            self.offset = row;
+        } else if row >= self.offset + page {
+            self.offset = row + 1 - page;
+        }
+
+        let mut screen = String::from("\x1b[H");
+        for line in 0..page {
+            screen.push_str("\x1b[K");
+            if let Some(&index) = rows.get(self.offset + line) {
+                let node = &self.nodes[index];
+                let marker = match (node.container, self.expanded[index]) {
+                    (false, _) => ' ',
+                    (true, false) => '▸',
+                    (true, true) => '▾',
+                };
+                let summary = match (node.container, self.expanded[index]) {
+                    (true, true) => String::new(),
+                    (true, false) => format!(" {}", node.summary),
+                    (false, _) => format!(": {}", node.summary),
+                };
+                let text = format!(
+                    "{}{} {}{}",
+                    "  ".repeat(node.depth),
+                    marker,
+                    node.label,
+                    summary
+                );
+                let text = truncate(&text, width);
+                if index == self.cursor {
+                    screen.push_str(&format!("\x1b[7m{}\x1b[0m", text));
+                } else {
+                    screen.push_str(&text);
+                }
+            } else if line == 0 && rows.is_empty() {
+                screen.push_str("(empty)");
+            }
+            screen.push_str("\r\n");
+        }
+
+        let status = match (&self.search, &self.message) {
+            (Some(search), _) => format!("/{}", search),
+            (None, Some(message)) => message.clone(),
+            (None, None) => match self.nodes.get(self.cursor) {
+                Some(node) => format!("{}    {}", node.path, HELP),
+                None => HELP.to_string(),
+            },
+        };
+        screen.push_str(&format!("\x1b[K\x1b[7m{}\x1b[0m", truncate(&status, width)));
+        screen
+    }
+}
+
+fn truncate(text: &str, width: usize) -> String {
+    match text.char_indices().nth(width) {
+        Some((end, _)) => text[..end].to_string(),
+        None => text.to_string(),
+    }
+}
+
+/// Browse the outline of a document, returning the path of the selected
+/// node, or `None` if the user quit without selecting one.
+pub fn explore(nodes: Vec<OutlineNode>) -> Result<Option<String>, CliError> {
+    let mut terminal = Terminal::open()?;
+    let mut browser = Browser::new(nodes);
+    loop {
+        let (width, height) = terminal.size();
+        let screen = browser.render(width, height);
+        terminal.write(&screen)?;
+        let key = terminal.read_key()?;
+        if let Outcome::Exit(path) = browser.key(key, height.saturating_sub(1).max(1)) {
+            return Ok(path);
+        }
+    }
+}
+
+/// The controlling terminal, in raw mode on the alternate screen until
+/// dropped.
+struct Terminal {
+    tty: std::fs::File,
+    saved: libc::termios,
+}
+
+impl Terminal {
+    fn open() -> Result<Self, CliError> {
+        use std::os::unix::io::AsRawFd;
+
+        let tty = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/tty")
+            .map_err(|e| CliError::io(format!("explore needs a terminal: {}", e)))?;
+        // SAFETY: `termios` is plain data, filled in by `tcgetattr`
+        let mut saved: libc::termios = unsafe { std::mem::zeroed() };
+        if unsafe { libc::tcgetattr(tty.as_raw_fd(), &mut saved) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut raw = saved;
+        unsafe { libc::cfmakeraw(&mut raw) };
+        if unsafe { libc::tcsetattr(tty.as_raw_fd(), libc::TCSAFLUSH, &raw) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let mut terminal = Terminal { tty, saved };
+        // Alternate screen, hidden cursor
+        terminal.write("\x1b[?1049h\x1b[?25l\x1b[2J")?;
+        Ok(terminal)
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), CliError> {
+        self.tty.write_all(text.as_bytes())?;
+        self.tty.flush()?;
+        Ok(())
+    }
+
+    /// Width and height in characters, 80x24 if unknown.
+    fn size(&self) -> (usize, usize) {
+        use std::os::unix::io::AsRawFd;
+
+        // SAFETY: `winsize` is plain data, filled in by the ioctl
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        match unsafe { libc::ioctl(self.tty.as_raw_fd(), libc::TIOCGWINSZ, &mut size) } {
+            0 if size.ws_col > 0 && size.ws_row > 0 => (size.ws_col as usize, size.ws_row as usize),
+            _ => (80, 24),
+        }
+    }
+
+    /// Whether more input comes within a few milliseconds, telling an
+    /// escape sequence from a lone Esc.
+    fn pending(&self) -> bool {
+        use std::os::unix::io::AsRawFd;
+
+        let mut poll = libc::pollfd {
+            fd: self.tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `poll` outlives the call
+        unsafe { libc::poll(&mut poll, 1, 30) > 0 }
+    }
+
+    fn read_byte(&mut self) -> Result<u8, CliError> {
+        let mut byte = [0u8];
+        self.tty.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+
+    fn read_key(&mut self) -> Result<Key, CliError> {
+        loop {
+            let key = match self.read_byte()? {
+                b'\r' | b'\n' => Key::Enter,
+                0x7f | 0x08 => Key::Backspace,
+                // Ctrl-C
+                0x03 => Key::Esc,
+                0x1b if !self.pending() => Key::Esc,
+                0x1b => match (self.read_byte()?, self.read_byte()?) {
+                    (b'[' | b'O', b'A') => Key::Up,
+                    (b'[' | b'O', b'B') => Key::Down,
+                    (b'[' | b'O', b'C') => Key::Right,
+                    (b'[' | b'O', b'D') => Key::Left,
+                    (b'[' | b'O', b'H') => Key::Home,
+                    (b'[' | b'O', b'F') => Key::End,
+                    (b'[', b'5') if self.read_byte()? == b'~' => Key::PageUp,
+                    (b'[', b'6') if self.read_byte()? == b'~' => Key::PageDown,
+                    _ => continue,
+                },
+                byte if byte.is_ascii() && !byte.is_ascii_control() => Key::Char(byte as char),
+                // Other characters can only be part of a search
+                byte if byte >= 0xc0 => {
+                    let len = match byte {
+                        0xf0..=0xff => 4,
+                        0xe0..=0xef => 3,
+                        _ => 2,
+                    };
+                    let mut bytes = vec![byte];
+                    for _ in 1..len {
+                        bytes.push(self.read_byte()?);
+                    }
+                    match std::str::from_utf8(&bytes)
+                        .ok()
+                        .and_then(|s| s.chars().next())
+                    {
+                        Some(c) => Key::Char(c),
+                        None => continue,
+                    }
+                }
+                _ => continue,
+            };
+            return Ok(key);
+        }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        use std::os::unix::io::AsRawFd;
+
+        self.write("\x1b[?25h\x1b[?1049l").ok();
+        // SAFETY: restores the settings read when opening
+        unsafe { libc::tcsetattr(self.tty.as_raw_fd(), libc::TCSAFLUSH, &self.saved) };
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn browser(yaml: &str) -> Browser {
+        let value: crate::yaml::Value = yaml.parse().unwrap();
+        Browser::new(crate::yaml::outline(None, &value, &crate::yaml::Options::default()).unwrap())
+    }
+
+    fn press(browser: &mut Browser, keys: &[Key]) -> Outcome {
+        let mut outcome = Outcome::Continue;
+        for key in keys {
+            outcome = browser.key(*key, 10);
+        }
+        outcome
+    }
+
+    fn selected(browser: &Browser) -> &str {
+        &browser.nodes[browser.cursor].path
+    }
+
+    const DOC: &str = "a: {b: 1, c: {d: 2}}\ne: [x, y]\nf: 3\n";
+
+    #[test]
+    fn test_moves_over_collapsed_nodes() {
+        let mut b = browser(DOC);
+        assert_eq!(b.visible(), vec![0, 4, 7]);
+        press(&mut b, &[Key::Down]);
+        assert_eq!(selected(&b), "e");
+        press(&mut b, &[Key::Down, Key::Down]);
+        assert_eq!(selected(&b), "f");
+        press(&mut b, &[Key::Home]);
+        assert_eq!(selected(&b), "a");
+    }
+
+    #[test]
+    fn test_expand_and_collapse() {
+        let mut b = browser(DOC);
+        press(&mut b, &[Key::Right]);
+        assert_eq!(b.visible(), vec![0, 1, 2, 4, 7]);
+        press(&mut b, &[Key::Right, Key::Down, Key::Char('l'), Key::Down]);
+        assert_eq!(selected(&b), "a.c.d");
+        // Back to the parent, then collapse it
+        press(&mut b, &[Key::Left]);
+        assert_eq!(selected(&b), "a.c");
+        press(&mut b, &[Key::Left]);
+        assert!(!b.expanded[2]);
+        // Scalars are not expanded
+        press(&mut b, &[Key::End, Key::Right]);
+        assert_eq!(selected(&b), "f");
+    }
+
+    #[test]
+    fn test_search_expands_ancestors() {
+        let mut b = browser(DOC);
+        let mut keys = vec![Key::Char('/')];
+        keys.extend("D".chars().map(Key::Char));
+        keys.push(Key::Enter);
+        press(&mut b, &keys);
+        assert_eq!(selected(&b), "a.c.d");
+        assert!(b.expanded[0] && b.expanded[2]);
+
+        // Values match too, and `n` wraps around
+        b.query = "y".to_string();
+        press(&mut b, &[Key::Char('n')]);
+        assert_eq!(selected(&b), "e.1");
+        press(&mut b, &[Key::Char('n')]);
+        assert_eq!(selected(&b), "e.1");
+        b.query = "nothing".to_string();
+        press(&mut b, &[Key::Char('n')]);
+        assert_eq!(b.message.as_deref(), Some("no match for 'nothing'"));
+    }
+
+    #[test]
+    fn test_exit() {
+        let mut b = browser(DOC);
+        assert_eq!(
+            press(&mut b, &[Key::Down, Key::Right, Key::Right, Key::Enter]),
+            Outcome::Exit(Some("e.0".to_string()))
+        );
+        assert_eq!(press(&mut b, &[Key::Char('q')]), Outcome::Exit(None));
+        // Keys typed in a search don't quit
+        assert_eq!(
+            press(&mut b, &[Key::Char('/'), Key::Char('q')]),
+            Outcome::Continue
+        );
+    }
+
+    #[test]
+    fn test_render_scrolls_to_cursor() {
+        let mut b = browser("a: 1\nb: 2\nc: 3\nd: 4\n");
+        press(&mut b, &[Key::End]);
+        let screen = b.render(20, 3);
+        assert!(!screen.contains("a: 1"));
+        assert!(screen.contains("c: 3"));
+        assert!(screen.contains("\x1b[7m  d: 4\x1b[0m"));
+        assert!(screen.contains("\x1b[7md    ↑↓ move"));
+    }
+}
@@ -0,0 +1,198 @@
+//! Machine-readable catalogue of the command line.
+//!
+//! `introspect` describes the global options and every action with its
+//! arguments and options, as found in the clap model of `def`, so that
+//! wrappers, documentation and completions follow the binary they run.
+//! Each action is annotated with its kind, telling how it takes part in a
+//! chain.
+
+use super::def;
+use super::error::CliError;
+use super::plan::ActionKind;
+use crate::yaml::Value;
+use clap::{Arg, ArgAction, Command, CommandFactory};
+use indexmap::IndexMap;
+
+fn string(s: impl Into<String>) -> Value {
+    Value::String(s.into())
+}
+
+fn mapping(entries: Vec<(&str, Value)>) -> Value {
+    Value::Mapping(
+        entries
+            .into_iter()
+            .map(|(k, v)| (string(k), v))
+            .collect::<IndexMap<_, _>>(),
+    )
+}
+
+/// How an option is given: alone, counted, or with a value once or
+/// repeatedly.
+fn arity(arg: &Arg) -> &'static str {
+    match arg.get_action() {
+        ArgAction::SetTrue | ArgAction::SetFalse => "flag",
+        ArgAction::Count => "count",
+        ArgAction::Append => "values",
+        _ => "value",
+    }
+}
+
+/// Description of an argument or option.
+fn describe_arg(arg: &Arg) -> Value {
+    let mut entries = Vec::new();
+    if arg.is_positional() {
+        entries.push(("name", string(arg.get_id().as_str())));
+    } else {
+        if let Some(long) = arg.get_long() {
+            entries.push(("long", string(format!("--{}", long))));
+        }
+        if let Some(short) = arg.get_short() {
+            entries.push(("short", string(format!("-{}", short))));
+        }
+        entries.push(("arity", string(arity(arg))));
+        if let Some(names) = arg
+            .get_value_names()
+            .filter(|_| arg.get_action().takes_values())
+        {
+            let names: Vec<&str> = names.iter().map(|n| n.as_str()).collect();
+            entries.push(("value", string(names.join(" "))));
+        }
+    }
+    if let Some(help) = arg.get_help() {
+        entries.push(("help", string(help.to_string())));
+    }
+    entries.push(("required", Value::Bool(arg.is_required_set())));
+    if arg.is_positional() {
+        entries.push((
+            "multiple",
+            Value::Bool(matches!(arg.get_action(), ArgAction::Append)),
+        ));
+    }
+    let choices: Vec<Value> = arg
+        .get_possible_values()
+        .iter()
+        .map(|v| string(v.get_name()))
+        .collect();
+    if !choices.is_empty() {
+        entries.push(("choices", Value::Sequence(choices)));
+    }
+    if let Some(default) = arg.get_default_values().first() {
+        entries.push(("default", string(default.to_string_lossy())));
+    }
+    mapping(entries)
+}
+
+/// Descriptions of the options of a command.
+fn describe_options(command: &Command) -> Value {
+    Value::Sequence(
+        command
+            .get_arguments()
+            .filter(|a| !a.is_positional() && !a.is_hide_set())
+            .map(describe_arg)
+            .collect(),
+    )
+}
+
+/// Description of an action.
+fn describe_action(action: &Command) -> Value {
+    let name = action.get_name();
+    let kind = ActionKind::of_name(name).map_or("unknown", ActionKind::name);
+    let mut entries = vec![("name", string(name)), ("kind", string(kind))];
+    if let Some(about) = action.get_about() {
+        entries.push(("about", string(about.to_string())));
+    }
+    entries.push((
+        "arguments",
+        Value::Sequence(action.get_positionals().map(describe_arg).collect()),
+    ));
+    entries.push(("options", describe_options(action)));
+    mapping(entries)
+}
+
+/// Catalogue of the command line.
+fn catalogue() -> Value {
+    let command = def::Args::command();
+    mapping(vec![
+        ("version", string(env!("CARGO_PKG_VERSION"))),
+        ("options", describe_options(&command)),
+        (
+            "kinds",
+            mapping(
+                ActionKind::ALL
+                    .iter()
+                    .map(|k| (k.name(), string(k.description())))
+                    .collect(),
+            ),
+        ),
+        (
+            "actions",
+            Value::Sequence(command.get_subcommands().map(describe_action).collect()),
+        ),
+    ])
+}
+
+/// Print the catalogue as YAML or, with `format` `json`, as JSON.
+pub fn print(format: &str) -> Result<(), CliError> {
+    let catalogue = catalogue();
+    match format {
+        "json" => println!("{}", super::lsp::json_value(&catalogue)),
+        _ => println!("{}", crate::yaml::serialize(&catalogue)?),
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_action_has_a_kind() {
+        let command = def::Args::command();
+        for action in command.get_subcommands() {
+            assert!(
+                ActionKind::of_name(action.get_name()).is_some(),
+                "no kind for {}",
+                action.get_name()
+            );
+        }
+    }
+
+    #[test]
+    fn test_describe_action() {
+        let command = def::Args::command();
+        for action in command.get_subcommands() {
+            assert!(
+                action.get_about().is_some(),
+                "no about for {}",
+                action.get_name()
+            );
+        }
+        let get_value = command.find_subcommand("get-value").unwrap();
+        let description = crate::yaml::serialize(&describe_action(get_value)).unwrap();
+        assert!(
+            description.starts_with(
+                "name: get-value\nkind: read-only\nabout: Get node value from given path\n\
+                 arguments:\n- name: PATH\n  help: The path to get value of\n"
+            ),
+            "{}",
+            description
+        );
+    }
+
+    #[test]
+    fn test_describe_arg() {
+        let command = def::Args::command();
+        let get_type = command.find_subcommand("get-type").unwrap();
+        let schema = get_type
+            .get_arguments()
+            .find(|a| a.get_long() == Some("schema"))
+            .unwrap();
+        let description = crate::yaml::serialize(&describe_arg(schema)).unwrap();
+        assert!(description.starts_with("long: --schema\narity: value\nvalue: SCHEMA\n"));
+        assert!(description.contains("choices:\n- "));
+    }
+}
@@ -0,0 +1,423 @@
+//! Minimal JSON-RPC server for editor plugins, for the `lsp-lite` action.
+//!
+//! Plugins send the text of a buffer along with each request, and get
+//! answers following the path semantics of the command line:
+//!
+//! ```text
+//! → {"jsonrpc": "2.0", "id": 1, "method": "type", "params": {"text": "a: [1]\n", "path": "a"}}
+//! ← {"jsonrpc": "2.0", "id": 1, "result": "sequence"}
+//! ```
+//!
+//! Messages are read one per line, or framed by `Content-Length` headers
+//! as in the Language Server Protocol, and answered the same way, in
+//! order. The server stops at the end of its input or on an `exit`
+//! notification.
+
+use super::error::{json_string, CliError};
+use crate::yaml::{Document, FyParser, InnerValue, Value};
+use std::io::{BufRead, Write};
+
+/// JSON-RPC error codes.
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// Errors of the action itself, with their category in `data`
+const ACTION_ERROR: i64 = -32000;
+
+/// Error answered to a request.
+#[derive(Debug)]
+struct RpcError {
+    code: i64,
+    message: String,
+    /// JSON value giving details
+    data: Option<String>,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        RpcError {
+            code,
+            message: message.into(),
+            data: None,
+        }
+    }
+}
+
+impl From<CliError> for RpcError {
+    fn from(e: CliError) -> Self {
+        let data = format!(
+            "{{\"category\": {}, \"path\": {}}}",
+            json_string(e.kind.code()),
+            e.path.as_deref().map_or("null".to_string(), json_string)
+        );
+        RpcError {
+            code: ACTION_ERROR,
+            message: e.message,
+            data: Some(data),
+        }
+    }
+}
+
+impl From<crate::yaml::Error> for RpcError {
+    fn from(e: crate::yaml::Error) -> Self {
+        CliError::from(e).into()
+    }
+}
+
+impl From<fyaml::Error> for RpcError {
+    fn from(e: fyaml::Error) -> Self {
+        CliError::from(e).into()
+    }
+}
+
+/// Answer the requests read on stdin until its end or an `exit`
+/// notification.
+pub fn serve(opts: &crate::yaml::Options) -> Result<bool, CliError> {
+    let mut input = std::io::stdin().lock();
+    let mut output = std::io::stdout().lock();
+    while let Some((message, framed)) = read_message(&mut input)? {
+        let (reply, exit) = handle(&message, opts);
+        if let Some(reply) = reply {
+            write_message(&mut output, &reply, framed)?;
+        }
+        if exit {
+            break;
+        }
+    }
+    Ok(true)
+}
+
+// =============================================================================
+// Framing
+// =============================================================================
+
+/// Read the next message, telling whether it was framed by headers.
+fn read_message(input: &mut impl BufRead) -> Result<Option<(String, bool)>, CliError> {
+    const HEADER: &str = "content-length:";
+
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if !trimmed.to_ascii_lowercase().starts_with(HEADER) {
+            return Ok(Some((trimmed.to_string(), false)));
+        }
+        let length: usize = trimmed[HEADER.len()..]
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid header '{}'", trimmed))?;
+        // Any other header, up to the empty line ending them
+        loop {
+            line.clear();
+            if input.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            if line.trim().is_empty() {
+                break;
+            }
+        }
+        let mut body = vec![0; length];
+        input.read_exact(&mut body)?;
+        return Ok(Some((String::from_utf8_lossy(&body).into_owned(), true)));
+    }
+}
+
+fn write_message(output: &mut impl Write, message: &str, framed: bool) -> Result<(), CliError> {
+    if framed {
+        write!(
+            output,
+            "Content-Length: {}\r\n\r\n{}",
+            message.len(),
+            message
+        )?;
+    } else {
+        writeln!(output, "{}", message)?;
+    }
+    output.flush()?;
+    Ok(())
+}
+
+// =============================================================================
+// Requests
+// =============================================================================
+
+/// Value as JSON, mapping keys as strings.
+pub(super) fn json_value(value: &Value) -> String {
+    match value.inner() {
+        Value::Null => "null".to_string(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(crate::yaml::Number::Float(f)) if !f.is_finite() => "null".to_string(),
+        Value::Number(crate::yaml::Number::Float(f)) => format!("{:?}", f),
+        Value::Number(_) => crate::yaml::serialize_raw(value.inner()),
+        Value::String(s) => json_string(s),
+        Value::Sequence(seq) => format!(
+            "[{}]",
+            seq.iter().map(json_value).collect::<Vec<_>>().join(", ")
+        ),
+        Value::Mapping(map) => format!(
+            "{{{}}}",
+            map.iter()
+                .map(|(k, v)| format!(
+                    "{}: {}",
+                    json_string(&crate::yaml::serialize_raw(k)),
+                    json_value(v)
+                ))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Tagged(_) => unreachable!("inner() removes tags"),
+    }
+}
+
+fn error_reply(id: &str, e: &RpcError) -> String {
+    let data = e
+        .data
+        .as_deref()
+        .map_or(String::new(), |data| format!(", \"data\": {}", data));
+    format!(
+        "{{\"jsonrpc\": \"2.0\", \"id\": {}, \"error\": {{\"code\": {}, \"message\": {}{}}}}}",
+        id,
+        e.code,
+        json_string(&e.message),
+        data
+    )
+}
+
+/// Reply to a message, if it is a request, and whether to stop.
+fn handle(message: &str, opts: &crate::yaml::Options) -> (Option<String>, bool) {
+    // JSON is read as YAML
+    let request = match crate::yaml::parse_value(message, true) {
+        Ok(request) => request,
+        Err(e) => {
+            let e = RpcError::new(PARSE_ERROR, format!("invalid JSON: {}", e));
+            return (Some(error_reply("null", &e)), false);
+        }
+    };
+    let request = Params(Some(&request));
+    // Notifications have no id and get no reply
+    let id = request.get("id").map(json_value);
+    let method = match request.string("method") {
+        Ok(Some(method)) => method,
+        _ => {
+            let e = RpcError::new(INVALID_REQUEST, "missing method");
+            return (
+                Some(error_reply(id.as_deref().unwrap_or("null"), &e)),
+                false,
+            );
+        }
+    };
+    if method == "exit" {
+        return (None, true);
+    }
+    let result = call(method, Params(request.get("params")), opts);
+    let reply = id.map(|id| match result {
+        Ok(result) => format!(
+            "{{\"jsonrpc\": \"2.0\", \"id\": {}, \"result\": {}}}",
+            id, result
+        ),
+        Err(e) => error_reply(&id, &e),
+    });
+    (reply, false)
+}
+
+/// Parameters of a request.
+struct Params<'a>(Option<&'a Value>);
+
+impl<'a> Params<'a> {
+    fn get(&self, name: &str) -> Option<&'a Value> {
+        match self.0.map(InnerValue::inner) {
+            Some(Value::Mapping(map)) => map.get(&Value::String(name.to_string())),
+            _ => None,
+        }
+    }
+
+    fn string(&self, name: &str) -> Result<Option<&'a str>, RpcError> {
+        match self.get(name).map(InnerValue::inner) {
+            None | Some(Value::Null) => Ok(None),
+            Some(Value::String(s)) => Ok(Some(s)),
+            Some(_) => Err(RpcError::new(
+                INVALID_PARAMS,
+                format!("'{}' must be a string", name),
+            )),
+        }
+    }
+
+    fn required(&self, name: &str) -> Result<&'a str, RpcError> {
+        self.string(name)?
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("missing '{}'", name)))
+    }
+
+    /// The first document of the `text` parameter.
+    fn document(&self) -> Result<Document, RpcError> {
+        match FyParser::from_string(self.required("text")?)?
+            .doc_iter()
+            .next()
+        {
+            Some(doc) => Ok(doc?),
+            None => Ok(Document::new()?),
+        }
+    }
+
+    fn value(&self) -> Result<Value, RpcError> {
+        Ok(crate::yaml::document_to_value(&self.document()?)?)
+    }
+}
+
+/// Result of a method, as JSON.
+fn call(method: &str, params: Params, opts: &crate::yaml::Options) -> Result<String, RpcError> {
+    let path = params.string("path")?;
+    match method {
+        "type" => Ok(json_value(&crate::yaml::get_type(
+            path,
+            &params.value()?,
+            None,
+            opts,
+        )?)),
+        "getValue" => Ok(json_value(&crate::yaml::get_value(
+            path,
+            &params.value()?,
+            opts,
+        )?)),
+        // Paths of all the nodes below `path`
+        "listPaths" => {
+            let value = params.value()?;
+            let target = crate::yaml::get_value(path, &value, opts)?;
+            if !matches!(target.inner(), Value::Mapping(_) | Value::Sequence(_)) {
+                return Ok("[]".to_string());
+            }
+            let paths: Vec<String> = crate::yaml::outline(path, &value, opts)?
+                .iter()
+                .map(|node| json_string(&node.path))
+                .collect();
+            Ok(format!("[{}]", paths.join(", ")))
+        }
+        // New text of the document, as `set-value` writes it
+        "applyEdit" => {
+            let text = params.required("text")?;
+            let path = params.required("path")?;
+            let new_value = params.required("value")?;
+            let yaml = matches!(
+                params.get("yaml").map(InnerValue::inner),
+                Some(Value::Bool(true))
+            );
+            let mut doc = params.document()?;
+            let protected = crate::yaml::protected_paths(text)?;
+            crate::yaml::check_protected_doc(&doc, &protected, path)?;
+            crate::yaml::set_value_doc(&mut doc, path, new_value, yaml, opts)?;
+            let output = doc.emit()?;
+            Ok(json_string(output.strip_prefix("---\n").unwrap_or(&output)))
+        }
+        _ => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("unknown method '{}'", method),
+        )),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reply(message: &str) -> String {
+        handle(message, &crate::yaml::Options::default()).0.unwrap()
+    }
+
+    #[test]
+    fn test_read_message() {
+        let mut input: &[u8] =
+            b"{\"id\": 1}\n\nContent-Length: 9\r\nContent-Type: x\r\n\r\n{\"id\": 2}";
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some(("{\"id\": 1}".to_string(), false))
+        );
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some(("{\"id\": 2}".to_string(), true))
+        );
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn test_json_value() {
+        let value: Value = "a: [1, 2.5, true, null]\nb: \"x\\ny\"\n3: {}\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            json_value(&value),
+            r#"{"a": [1, 2.5, true, null], "b": "x\ny", "3": {}}"#
+        );
+    }
+
+    #[test]
+    fn test_methods() {
+        let params = r#""text": "a:\n  b:\n  - 1\n  - 2\n""#;
+        assert_eq!(
+            reply(&format!(
+                r#"{{"jsonrpc": "2.0", "id": 1, "method": "type", "params": {{{}, "path": "a.b"}}}}"#,
+                params
+            )),
+            r#"{"jsonrpc": "2.0", "id": 1, "result": "sequence"}"#
+        );
+        assert_eq!(
+            reply(&format!(
+                r#"{{"id": "x", "method": "getValue", "params": {{{}, "path": "a"}}}}"#,
+                params
+            )),
+            r#"{"jsonrpc": "2.0", "id": "x", "result": {"b": [1, 2]}}"#
+        );
+        assert_eq!(
+            reply(&format!(
+                r#"{{"id": 2, "method": "listPaths", "params": {{{}}}}}"#,
+                params
+            )),
+            r#"{"jsonrpc": "2.0", "id": 2, "result": ["a", "a.b", "a.b.0", "a.b.1"]}"#
+        );
+        assert_eq!(
+            reply(&format!(
+                r#"{{"id": 3, "method": "applyEdit", "params": {{{}, "path": "a.c", "value": "x"}}}}"#,
+                params
+            )),
+            r#"{"jsonrpc": "2.0", "id": 3, "result": "a:\n  b:\n  - 1\n  - 2\n  c: x\n"}"#
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            reply(r#"{"id": 1, "method": "getValue", "params": {"text": "a: 1", "path": "b"}}"#),
+            r#"{"jsonrpc": "2.0", "id": 1, "error": {"code": -32000, "message": "invalid path 'b', missing key 'b' in struct.", "data": {"category": "path-not-found", "path": "b"}}}"#
+        );
+        assert!(reply(r#"{"id": 1, "method": "hover"}"#).contains("\"code\": -32601"));
+        assert!(reply(r#"{"id": 1, "method": "type"}"#).contains("missing 'text'"));
+        assert!(reply("{\"id\": [").contains("\"code\": -32700"));
+    }
+
+    #[test]
+    fn test_notifications() {
+        assert_eq!(
+            handle(
+                r#"{"method": "type", "params": {"text": "a: 1"}}"#,
+                &crate::yaml::Options::default()
+            ),
+            (None, false)
+        );
+        assert_eq!(
+            handle(
+                r#"{"jsonrpc": "2.0", "method": "exit"}"#,
+                &crate::yaml::Options::default()
+            ),
+            (None, true)
+        );
+    }
+}
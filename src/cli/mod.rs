@@ -1,36 +1,23 @@
+mod config;
+mod crash;
 mod def;
+mod env_opts;
+pub mod error;
+mod explore;
+mod introspect;
+mod lsp;
 mod output;
 mod plan;
+mod report;
+mod watch;
 include!(concat!(env!("OUT_DIR"), "/rustc_version.rs"));
-use clap::Parser;
+use error::{CliError, ErrorKind};
 use fyaml::Document;
 use plan::ExecutionMode;
+use std::collections::HashMap;
 
 pub mod log;
 
-impl From<crate::yaml::Error> for String {
-    fn from(e: crate::yaml::Error) -> Self {
-        e.to_string()
-    }
-}
-
-// =============================================================================
-// Error Conversion Trait
-// =============================================================================
-
-/// Extension trait for converting errors to String with `.str_err()`.
-///
-/// This reduces boilerplate from `.map_err(|e| e.to_string())?` to `.str_err()?`.
-trait StringError<T> {
-    fn str_err(self) -> Result<T, String>;
-}
-
-impl<T, E: ToString> StringError<T> for Result<T, E> {
-    fn str_err(self) -> Result<T, String> {
-        self.map_err(|e| e.to_string())
-    }
-}
-
 // =============================================================================
 // Normalized Action Types
 // =============================================================================
@@ -41,7 +28,10 @@ enum IterKind {
     Keys,
     Values,
     KeyValues,
+    KeyValuesRecursive,
     GetValues,
+    Types,
+    Flatten,
 }
 
 /// Normalized iteration action with common parameters extracted.
@@ -56,54 +46,103 @@ struct IterAction<'a> {
 fn normalize_iter_action<'a>(
     action: &'a def::Actions,
     base_yaml_mode: bool,
+    null_output: Option<&str>,
 ) -> Option<IterAction<'a>> {
-    match action {
-        def::Actions::Keys { path, yaml } => Some(IterAction {
+    let iter_action = match action {
+        def::Actions::Keys { path, yaml, .. } => Some(IterAction {
             kind: IterKind::Keys,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::newline(base_yaml_mode || *yaml),
         }),
-        def::Actions::Keys0 { path, yaml } => Some(IterAction {
+        def::Actions::Keys0 { path, yaml, .. } => Some(IterAction {
             kind: IterKind::Keys,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::nul(base_yaml_mode || *yaml),
         }),
-        def::Actions::Values { path, yaml } => Some(IterAction {
+        def::Actions::Values { path, yaml, .. } => Some(IterAction {
             kind: IterKind::Values,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::newline(base_yaml_mode || *yaml),
         }),
-        def::Actions::Values0 { path, yaml } => Some(IterAction {
+        def::Actions::Values0 { path, yaml, .. } => Some(IterAction {
             kind: IterKind::Values,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::nul(base_yaml_mode || *yaml),
         }),
-        def::Actions::KeyValues { path, yaml } => Some(IterAction {
-            kind: IterKind::KeyValues,
+        def::Actions::KeyValues {
+            path,
+            yaml,
+            kv_separator,
+            quote,
+            recursive,
+            ..
+        } => Some(IterAction {
+            kind: if *recursive {
+                IterKind::KeyValuesRecursive
+            } else {
+                IterKind::KeyValues
+            },
+            path: path.as_ref().map(|s| s.as_str()),
+            policy: output::OutputPolicy::newline(base_yaml_mode || *yaml)
+                .with_pairs(kv_separator.as_deref(), *quote),
+        }),
+        def::Actions::KeyValues0 {
+            path,
+            yaml,
+            kv_separator,
+            quote,
+            recursive,
+            ..
+        } => Some(IterAction {
+            kind: if *recursive {
+                IterKind::KeyValuesRecursive
+            } else {
+                IterKind::KeyValues
+            },
+            path: path.as_ref().map(|s| s.as_str()),
+            policy: output::OutputPolicy::nul(base_yaml_mode || *yaml)
+                .with_pairs(kv_separator.as_deref(), *quote),
+        }),
+        def::Actions::GetValues { path, yaml, .. } => Some(IterAction {
+            kind: IterKind::GetValues,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::newline(base_yaml_mode || *yaml),
         }),
-        def::Actions::KeyValues0 { path, yaml } => Some(IterAction {
-            kind: IterKind::KeyValues,
+        def::Actions::GetValues0 { path, yaml, .. } => Some(IterAction {
+            kind: IterKind::GetValues,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::nul(base_yaml_mode || *yaml),
         }),
-        def::Actions::GetValues { path, yaml } => Some(IterAction {
-            kind: IterKind::GetValues,
+        def::Actions::GetTypes { path, yaml, .. } => Some(IterAction {
+            kind: IterKind::Types,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::newline(base_yaml_mode || *yaml),
         }),
-        def::Actions::GetValues0 { path, yaml } => Some(IterAction {
-            kind: IterKind::GetValues,
+        def::Actions::GetTypes0 { path, yaml, .. } => Some(IterAction {
+            kind: IterKind::Types,
             path: path.as_ref().map(|s| s.as_str()),
             policy: output::OutputPolicy::nul(base_yaml_mode || *yaml),
         }),
+        def::Actions::Flatten { path, .. } => Some(IterAction {
+            kind: IterKind::Flatten,
+            path: path.as_ref().map(|s| s.as_str()),
+            policy: output::OutputPolicy::newline(base_yaml_mode),
+        }),
+        def::Actions::Flatten0 { path, .. } => Some(IterAction {
+            kind: IterKind::Flatten,
+            path: path.as_ref().map(|s| s.as_str()),
+            policy: output::OutputPolicy::nul(base_yaml_mode),
+        }),
         _ => None,
-    }
+    };
+    iter_action.map(|iter_action| IterAction {
+        policy: iter_action.policy.with_null_output(null_output),
+        ..iter_action
+    })
 }
 
 /// Setup logging and color output based on CLI arguments.
-fn setup_logging_and_colors(cli: &def::Args) -> Result<(), String> {
+fn setup_logging_and_colors(cli: &def::Args) -> Result<(), CliError> {
     let logs = cli.log.clone().unwrap_or_default();
     let logs = logs
         .iter()
@@ -115,7 +154,7 @@ fn setup_logging_and_colors(cli: &def::Args) -> Result<(), String> {
     log::setup(cli.verbose, logs, cli.log_time)?;
 
     if cli.color && cli.no_color {
-        return Err("Cannot use both --color and --no-color".to_string());
+        return Err("Cannot use both --color and --no-color".into());
     }
     if cli.color {
         colored::control::set_override(true);
@@ -157,8 +196,8 @@ fn split_compound_args(args: Vec<String>) -> Vec<Vec<String>> {
     groups
 }
 
-fn setup_cli_context(args: &[String]) -> Result<def::Args, String> {
-    let cli = def::Args::try_parse_from(args).str_err()?;
+fn setup_cli_context(args: &[String]) -> Result<def::Args, CliError> {
+    let cli = def::Args::try_parse_group(args)?;
     setup_logging_and_colors(&cli)?;
     Ok(cli)
 }
@@ -168,17 +207,17 @@ fn setup_cli_context(args: &[String]) -> Result<def::Args, String> {
 // =============================================================================
 
 /// Parse all command groups to extract their actions for analysis.
-fn parse_actions(command_groups: &[Vec<String>]) -> Result<Vec<Option<def::Actions>>, String> {
+fn parse_actions(command_groups: &[Vec<String>]) -> Result<Vec<Option<def::Actions>>, CliError> {
     let mut actions = Vec::with_capacity(command_groups.len());
     for group in command_groups {
-        let cli = def::Args::try_parse_from(group).str_err()?;
+        let cli = def::Args::try_parse_group(group)?;
         actions.push(cli.action);
     }
     Ok(actions)
 }
 
 /// Determine the execution mode for a command chain.
-fn determine_execution_mode(command_groups: &[Vec<String>]) -> Result<ExecutionMode, String> {
+fn determine_execution_mode(command_groups: &[Vec<String>]) -> Result<ExecutionMode, CliError> {
     let actions = parse_actions(command_groups)?;
     Ok(plan::analyze_chain(&actions))
 }
@@ -191,73 +230,553 @@ fn determine_execution_mode(command_groups: &[Vec<String>]) -> Result<ExecutionM
 ///
 /// This avoids full document cloning - only modified nodes are allocated.
 /// Supports both mapping and sequence mutations via fyaml's Editor.
+/// Returns `Outcome::Skipped` if a guarded `set-value` was skipped, or
+/// with a `report`, `Outcome::Failure` if the document changed.
 fn run_doc_mode_chain(
     command_groups: &[Vec<String>],
     doc: &mut Document,
     multi_doc_yaml: bool,
-) -> Result<(), String> {
-    let _yaml_mode = {
-        let cli = def::Args::try_parse_from(&command_groups[0]).str_err()?;
-        cli.yaml
-    };
+    allowed: Option<&crate::yaml::AllowedPaths>,
+    report: Option<&ChangeReport>,
+) -> Result<Outcome, CliError> {
+    let first_cli = def::Args::try_parse_group(&command_groups[0])?;
+    let _yaml_mode = first_cli.yaml;
+    let base_dir = first_cli.base_dir.as_deref();
+    let deterministic = first_cli.deterministic;
+    let stdin_values = reads_file_input(&first_cli);
+    let opts = &yaml_options(&first_cli)?;
+    let null_output = first_cli.null_output.as_deref();
+    let protected = protected_regions(command_groups, doc)?;
+    let mut outcome = Outcome::Success;
+    let before = report.map(|_| Snapshot::of_document(doc)).transpose()?;
 
     // Apply all mutations
     for (i, cmd_args) in command_groups.iter().enumerate() {
-        let cli = def::Args::try_parse_from(cmd_args).str_err()?;
-        let is_last = i == command_groups.len() - 1;
+        let cli = def::Args::try_parse_group(cmd_args)?;
+        // The changes are reported instead of the document
+        let is_last = i == command_groups.len() - 1 && report.is_none();
 
         match &cli.action {
-            Some(def::Actions::SetValue { key, value, yaml }) => {
-                crate::yaml::set_value_doc(doc, key, value, *yaml).str_err()?;
+            Some(def::Actions::SetValue {
+                key,
+                value,
+                yaml,
+                from,
+                from_file,
+                file,
+                if_missing,
+                if_equals,
+                keep_tag,
+                tag,
+                binary,
+                arg,
+                argjson,
+                preview: _,
+                ..
+            }) => {
+                let vars = crate::yaml::Vars::new(arg, argjson)?;
+                let current = crate::yaml::get_value_ref(Some(key), doc, opts)
+                    .map(crate::yaml::serialize_raw_ref);
+                if !set_value_allowed(*if_missing, if_equals.as_deref(), current)? {
+                    outcome = Outcome::Skipped;
+                    if is_last {
+                        emit_document(doc, multi_doc_yaml)?;
+                    }
+                    continue;
+                }
+                crate::yaml::check_protected_doc(doc, &protected, key)?;
+                crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                let (text, parse_as_yaml) = match (value, from, from_file) {
+                    (Some(value), _, _) if *binary => {
+                        let value = crate::yaml::binary_value(value_bytes(value, stdin_values)?);
+                        (crate::yaml::serialize(&value)?, true)
+                    }
+                    (Some(value), _, _) if !vars.is_empty() => {
+                        let value = vars.resolve(value_text(value, stdin_values)?, *yaml)?;
+                        (crate::yaml::serialize(&value)?, true)
+                    }
+                    // Like `--from-file -`, the text read is kept as is
+                    (Some(value), _, _) if value == "-" && stdin_values => {
+                        let value = from_file_value(value, false, *yaml, base_dir, true)?;
+                        (crate::yaml::serialize(&value)?, true)
+                    }
+                    (Some(value), _, _) => (value_text(value, stdin_values)?.to_string(), *yaml),
+                    (None, Some(from), _) => (
+                        source_value_yaml(from, file.as_deref(), base_dir, doc, opts)?,
+                        true,
+                    ),
+                    (None, None, Some(from_file)) => {
+                        let value =
+                            from_file_value(from_file, *binary, *yaml, base_dir, stdin_values)?;
+                        (crate::yaml::serialize(&value)?, true)
+                    }
+                    (None, None, None) => {
+                        unreachable!("clap requires VALUE, --from or --from-file")
+                    }
+                };
+                if tag.is_some() || *keep_tag {
+                    let replaced_tag = crate::yaml::get_value_ref(Some(key), doc, opts)
+                        .ok()
+                        .and_then(|current| current.tag().map(|t| t.to_string()))
+                        .filter(|_| *keep_tag);
+                    let new_value = crate::yaml::tag_new_value(
+                        crate::yaml::parse_value(&text, parse_as_yaml)?,
+                        tag.as_deref(),
+                        replaced_tag,
+                    );
+                    let text = crate::yaml::serialize(&new_value)?;
+                    crate::yaml::set_value_doc(doc, key, &text, true, opts)?;
+                } else {
+                    crate::yaml::set_value_doc(doc, key, &text, parse_as_yaml, opts)?;
+                }
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::SetValues {
+                assignments,
+                yaml,
+                from,
+            }) => {
+                let assignments = set_values_assignments(assignments, from.as_deref(), base_dir)?;
+                for (key, value) in assignments {
+                    crate::yaml::check_protected_doc(doc, &protected, &key)?;
+                    crate::yaml::check_allowed_doc(doc, allowed, &key)?;
+                    crate::yaml::set_value_doc(doc, &key, &value, *yaml, opts)?;
+                }
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(update @ def::Actions::Update { key: Some(key), .. }) => {
+                let op = parse_update_op(update)?;
+                crate::yaml::check_protected_doc(doc, &protected, key)?;
+                crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                crate::yaml::update_doc(doc, key, &op, opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::Map { key, expr, .. }) => {
+                let expr = crate::yaml::Expr::parse(expr)?;
+                crate::yaml::check_protected_doc(doc, &protected, key)?;
+                crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                crate::yaml::map_doc(doc, key, &expr, opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(
+                action @ (def::Actions::Sort { key: Some(key), .. }
+                | def::Actions::Unique { key: Some(key), .. }
+                | def::Actions::Reverse { key: Some(key), .. }),
+            ) => {
+                crate::yaml::check_protected_doc(doc, &protected, key)?;
+                crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                crate::yaml::reorder_doc(doc, key, &seq_op(action), opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::Ensure {
+                key: Some(key),
+                kind,
+                default,
+                ..
+            }) => {
+                let default = crate::yaml::ensure_default(kind.as_deref(), default.as_deref())?;
+                crate::yaml::check_protected_doc(doc, &protected, key)?;
+                crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                crate::yaml::ensure_doc(doc, key, &default, opts)?;
                 if is_last {
                     emit_document(doc, multi_doc_yaml)?;
                 }
             }
-            Some(def::Actions::Del { key }) => {
-                crate::yaml::del_doc(doc, key)?;
+            Some(def::Actions::Del {
+                keys,
+                ignore_missing,
+                preview: _,
+                ..
+            }) => {
+                for key in &crate::yaml::deletion_paths_doc(doc, keys) {
+                    if *ignore_missing && crate::yaml::get_value_ref(Some(key), doc, opts).is_err()
+                    {
+                        continue;
+                    }
+                    crate::yaml::check_protected_doc(doc, &protected, key)?;
+                    crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                    crate::yaml::del_doc(doc, key, opts)?;
+                }
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::Append {
+                key, value, yaml, ..
+            }) => {
+                let value = crate::yaml::parse_value(value, *yaml)?;
+                crate::yaml::check_protected_doc(doc, &protected, key)?;
+                crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                crate::yaml::insert_doc(doc, key, None, &value, opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::Insert {
+                key,
+                index,
+                value,
+                yaml,
+                ..
+            }) => {
+                let value = crate::yaml::parse_value(value, *yaml)?;
+                crate::yaml::check_protected_doc(doc, &protected, key)?;
+                crate::yaml::check_allowed_doc(doc, allowed, key)?;
+                crate::yaml::insert_doc(doc, key, Some(index), &value, opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::RenameKey { key, new_name, .. }) => {
+                let renamed = crate::yaml::sibling_path(key, new_name);
+                for path in [key, &renamed] {
+                    crate::yaml::check_protected_doc(doc, &protected, path)?;
+                    crate::yaml::check_allowed_doc(doc, allowed, path)?;
+                }
+                crate::yaml::rename_key_doc(doc, key, new_name, opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::Move { src, dst, .. }) => {
+                for path in [src, dst] {
+                    crate::yaml::check_protected_doc(doc, &protected, path)?;
+                    crate::yaml::check_allowed_doc(doc, allowed, path)?;
+                }
+                crate::yaml::move_doc(doc, src, dst, opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::Copy { src, dst, .. }) => {
+                crate::yaml::check_protected_doc(doc, &protected, dst)?;
+                crate::yaml::check_allowed_doc(doc, allowed, dst)?;
+                crate::yaml::copy_doc(doc, src, dst, opts)?;
+                if is_last {
+                    emit_document(doc, multi_doc_yaml)?;
+                }
+            }
+            Some(def::Actions::Annotate {
+                managed_by,
+                sources,
+            }) => {
+                let timestamp = crate::yaml::annotation_timestamp(deterministic)?;
+                crate::yaml::annotate_doc(doc, managed_by, sources, &timestamp)?;
                 if is_last {
                     emit_document(doc, multi_doc_yaml)?;
                 }
             }
             Some(def::Actions::GetValue { .. })
             | Some(def::Actions::GetType { .. })
-            | Some(def::Actions::GetLength { .. }) => {
+            | Some(def::Actions::GetLength { .. })
+            | Some(def::Actions::Export { .. })
+            | Some(def::Actions::ToDotenv { .. })
+            | Some(def::Actions::Validate { .. })
+            | Some(def::Actions::Render { .. })
+            | Some(def::Actions::Explore { .. })
+            | Some(def::Actions::Filter { .. })
+            | Some(def::Actions::SelectWhere { .. })
+            | Some(def::Actions::Sum { .. })
+            | Some(def::Actions::Min { .. })
+            | Some(def::Actions::Max { .. })
+            | Some(def::Actions::Avg { .. })
+            | Some(def::Actions::CountWhere { .. })
+            | Some(def::Actions::Search { .. }) => {
                 // Final read-only action: use zero-copy path
                 if is_last {
-                    run_single_readonly(&cli, doc, multi_doc_yaml)?;
+                    run_single_readonly(&cli, doc, multi_doc_yaml, opts, null_output)?;
                 }
             }
             // Single iteration action: use zero-copy path (preserves formatting)
-            Some(action) if is_last && normalize_iter_action(action, _yaml_mode).is_some() => {
-                run_single_readonly(&cli, doc, multi_doc_yaml)?;
+            Some(action)
+                if is_last && normalize_iter_action(action, _yaml_mode, null_output).is_some() =>
+            {
+                run_single_readonly(&cli, doc, multi_doc_yaml, opts, null_output)?;
             }
             _ => {
                 // This shouldn't happen in DocMode - analyze_chain should have caught it
-                return Err("Unexpected action in DocMode".to_string());
+                return Err("Unexpected action in DocMode".into());
             }
         }
     }
 
-    Ok(())
+    if let (Some(report), Some(before)) = (report, before) {
+        return Ok(report.print(&before, &Snapshot::of_document(doc)?).into());
+    }
+    Ok(outcome)
+}
+
+/// Find the regions protected by `# shyaml:ignore-start/end` comments, when
+/// the chain may write to the document.
+fn protected_regions(
+    command_groups: &[Vec<String>],
+    doc: &Document,
+) -> Result<Vec<String>, CliError> {
+    let writes = parse_actions(command_groups)?
+        .iter()
+        .flatten()
+        .any(plan::is_modifying);
+    if !writes || doc.root().is_none() {
+        return Ok(Vec::new());
+    }
+    let text = doc.emit()?;
+    // A stray end marker doesn't get in the way of documents without regions
+    if !text.contains(crate::yaml::IGNORE_START) {
+        return Ok(Vec::new());
+    }
+    Ok(crate::yaml::protected_paths(&text)?)
+}
+
+/// Whether the chain looks up anchors, which are then found in the text of
+/// the input: parsed documents don't keep them.
+fn looks_up_anchors(command_groups: &[Vec<String>]) -> Result<bool, CliError> {
+    Ok(parse_actions(command_groups)?
+        .iter()
+        .flatten()
+        .any(|action| {
+            matches!(
+                action,
+                def::Actions::ListAnchors | def::Actions::GetAnchor { .. }
+            )
+        }))
+}
+
+/// Whether a `get-value` of the chain keeps the aliases of the input,
+/// which are then found in its text.
+fn keeps_aliases(command_groups: &[Vec<String>]) -> Result<bool, CliError> {
+    Ok(parse_actions(command_groups)?
+        .iter()
+        .flatten()
+        .any(|action| {
+            matches!(
+                action,
+                def::Actions::GetValue {
+                    keep_aliases: true,
+                    ..
+                }
+            )
+        }))
+}
+
+/// Collect the assignments of `set-values`, those of the `--from` file first.
+///
+/// All of them are parsed before any is applied.
+fn set_values_assignments(
+    assignments: &[String],
+    from: Option<&str>,
+    base_dir: Option<&str>,
+) -> Result<Vec<(String, String)>, CliError> {
+    let mut all = match from {
+        Some(file) => crate::yaml::read_assignments(&in_base_dir(base_dir, file))?,
+        None => Vec::new(),
+    };
+    for assignment in assignments {
+        all.push(crate::yaml::parse_assignment(assignment)?);
+    }
+    Ok(all)
+}
+
+/// Resolve a relative overlay or value file path from `--base-dir`, if given.
+///
+/// `-` (stdin) and URLs are kept as they are.
+fn in_base_dir(base_dir: Option<&str>, path: &str) -> String {
+    match base_dir {
+        Some(dir) if path != "-" && !path.contains("://") => {
+            std::path::Path::new(dir).join(path).display().to_string()
+        }
+        _ => path.to_string(),
+    }
+}
+
+/// Whether the input document comes from a file, leaving stdin to
+/// overlays and to `set-value` values of `-`.
+fn reads_file_input(cli: &def::Args) -> bool {
+    cli.file.is_some() || matches!(cli.action, Some(def::Actions::Merge { .. }))
+}
+
+/// Options of the operations on documents, from the global flags.
+fn yaml_options(cli: &def::Args) -> Result<crate::yaml::Options, CliError> {
+    Ok(crate::yaml::Options {
+        string_keys_only: cli.string_keys_only,
+        no_dedup: cli.no_dedup,
+        keep_tags: cli.keep_tags,
+        truthiness: crate::yaml::Truthiness::parse(cli.truthy.as_deref(), cli.falsy.as_deref())?,
+    })
+}
+
+/// Read stdin for the `-` values of `set-value`, once.
+fn stdin_value() -> Result<&'static [u8], CliError> {
+    use std::io::Read;
+
+    static STDIN: std::sync::OnceLock<Vec<u8>> = std::sync::OnceLock::new();
+    if let Some(content) = STDIN.get() {
+        return Ok(content);
+    }
+    let mut content = Vec::new();
+    std::io::stdin().read_to_end(&mut content)?;
+    Ok(STDIN.get_or_init(|| content))
+}
+
+/// Bytes of a `set-value` VALUE. When the document comes from a file, `-`
+/// reads them from stdin, otherwise it is the value itself.
+fn value_bytes(value: &str, stdin_values: bool) -> Result<&[u8], CliError> {
+    if value == "-" && stdin_values {
+        stdin_value()
+    } else {
+        Ok(value.as_bytes())
+    }
+}
+
+/// Text of a `set-value` VALUE, as given by `value_bytes()`.
+fn value_text(value: &str, stdin_values: bool) -> Result<&str, CliError> {
+    std::str::from_utf8(value_bytes(value, stdin_values)?)
+        .map_err(|_| "the value read from stdin isn't UTF-8 text, use --binary".into())
+}
+
+/// Value of `set-value --from-file FILE`. A FILE of `-` reads stdin, which
+/// requires the document to come from a file.
+fn from_file_value(
+    file: &str,
+    binary: bool,
+    parse_as_yaml: bool,
+    base_dir: Option<&str>,
+    stdin_values: bool,
+) -> Result<crate::yaml::Value, CliError> {
+    if file != "-" {
+        let file = in_base_dir(base_dir, file);
+        return Ok(if binary {
+            crate::yaml::binary_from_file(&file)?
+        } else {
+            crate::yaml::value_from_file(&file, parse_as_yaml)?
+        });
+    }
+    if !stdin_values {
+        return Err(
+            "cannot read a value from stdin ('-'), it already holds the YAML document. \
+             Give the document with --file FILE instead."
+                .into(),
+        );
+    }
+    if binary {
+        return Ok(crate::yaml::binary_value(stdin_value()?));
+    }
+    let content = value_text("-", true)?;
+    Ok(if parse_as_yaml {
+        crate::yaml::parse_value(content, true)?
+    } else {
+        crate::yaml::Value::String(content.to_string())
+    })
+}
+
+/// Check whether a guarded `set-value` may write, given the raw text of the
+/// value currently at its path.
+///
+/// A path error means the value is missing, like for `get-value` defaults.
+fn set_value_allowed(
+    if_missing: bool,
+    if_equals: Option<&str>,
+    current: Result<String, crate::yaml::Error>,
+) -> Result<bool, CliError> {
+    let current = match current {
+        Ok(current) => Some(current),
+        Err(crate::yaml::Error::Path { .. }) => None,
+        Err(e) => return Err(e.into()),
+    };
+    Ok(match (if_missing, if_equals) {
+        (true, _) => current.is_none(),
+        (_, Some(expected)) => current.as_deref() == Some(expected),
+        _ => true,
+    })
+}
+
+/// Get the operation of an `update` action.
+fn parse_update_op(action: &def::Actions) -> Result<crate::yaml::UpdateOp, CliError> {
+    use crate::yaml::{parse_number, parse_replace, UpdateOp};
+    let def::Actions::Update {
+        add,
+        mul,
+        min,
+        max,
+        append_str,
+        prefix,
+        replace,
+        regex,
+        ..
+    } = action
+    else {
+        unreachable!("parse_update_op() is only called on update actions");
+    };
+    if *regex && replace.is_none() {
+        return Err("--regex can only be used with --replace.".into());
+    }
+    let op = match (add, mul, min, max) {
+        (Some(n), _, _, _) => UpdateOp::Add(parse_number(n)?),
+        (_, Some(n), _, _) => UpdateOp::Mul(parse_number(n)?),
+        (_, _, Some(n), _) => UpdateOp::Min(parse_number(n)?),
+        (_, _, _, Some(n)) => UpdateOp::Max(parse_number(n)?),
+        _ => match (append_str, prefix, replace) {
+            (Some(s), _, _) => UpdateOp::AppendStr(s.clone()),
+            (_, Some(s), _) => UpdateOp::Prefix(s.clone()),
+            (_, _, Some(spec)) => parse_replace(spec, *regex)?,
+            _ => unreachable!("clap requires one update operation"),
+        },
+    };
+    Ok(op)
+}
+
+/// Get the YAML text of the value a `set-value --from` copies.
+fn source_value_yaml(
+    from: &str,
+    file: Option<&str>,
+    base_dir: Option<&str>,
+    doc: &Document,
+    opts: &crate::yaml::Options,
+) -> Result<String, CliError> {
+    match file {
+        Some(file) => {
+            let value =
+                crate::yaml::get_value_from_file(Some(from), &in_base_dir(base_dir, file), opts)?;
+            Ok(crate::yaml::serialize(&value)?)
+        }
+        None => {
+            let value_ref = crate::yaml::get_value_ref(Some(from), doc, opts)?;
+            Ok(crate::yaml::serialize_ref(value_ref)?)
+        }
+    }
 }
 
 /// Execute DocMode on empty input (no document).
-fn run_doc_mode_empty(command_groups: &[Vec<String>], multi_doc_yaml: bool) -> Result<(), String> {
+fn run_doc_mode_empty(
+    command_groups: &[Vec<String>],
+    multi_doc_yaml: bool,
+    allowed: Option<&crate::yaml::AllowedPaths>,
+    report: Option<&ChangeReport>,
+) -> Result<Outcome, CliError> {
     // Create empty document
-    let mut doc = Document::new().str_err()?;
+    let mut doc = Document::new()?;
 
     // Check if we need to handle empty readonly/iteration first
-    let first_cli = def::Args::try_parse_from(&command_groups[0]).str_err()?;
+    let first_cli = def::Args::try_parse_group(&command_groups[0])?;
     let first_action = first_cli.action.as_ref().unwrap();
     if command_groups.len() == 1
         && (plan::is_readonly(first_action) || plan::is_derived(first_action))
     {
         run_single_readonly_empty(&first_cli)?;
-        return Ok(());
+        return Ok(Outcome::Success);
     }
 
     // Otherwise, process as normal (mutations will create structure)
-    run_doc_mode_chain(command_groups, &mut doc, multi_doc_yaml)
+    run_doc_mode_chain(command_groups, &mut doc, multi_doc_yaml, allowed, report)
 }
 
 /// Emit document to stdout, preserving comments and original formatting.
@@ -267,8 +786,12 @@ fn run_doc_mode_empty(command_groups: &[Vec<String>], multi_doc_yaml: bool) -> R
 ///
 /// When not in yaml mode (multi_doc_yaml=false), strips leading `---\n` from
 /// output since we use `\0` as document separator instead.
-fn emit_document(doc: &Document, multi_doc_yaml: bool) -> Result<(), String> {
-    let output = doc.emit().str_err()?;
+fn emit_document(doc: &Document, multi_doc_yaml: bool) -> Result<(), CliError> {
+    // Nothing was created in an empty input (ie: `del --ignore-missing`)
+    if doc.root().is_none() {
+        return Ok(());
+    }
+    let output = doc.emit()?;
     // In non-yaml mode, strip document start marker since we use \0 as separator
     let output = if !multi_doc_yaml {
         output.strip_prefix("---\n").unwrap_or(&output)
@@ -286,36 +809,257 @@ fn emit_document(doc: &Document, multi_doc_yaml: bool) -> Result<(), String> {
 // ValueMode Execution (fallback, full Value cloning)
 // =============================================================================
 
+/// State carried along a chain, scoped to one document of the input stream.
+#[derive(Default)]
+struct ChainState {
+    /// Named values stashed with `save` and restored with `load`
+    registers: HashMap<String, crate::yaml::Value>,
+    /// Set when a guarded `set-value` didn't write
+    skipped: bool,
+    /// Emit repeated subtrees of output documents as anchor and aliases
+    factor_anchors: bool,
+    /// Order of the keys of output documents
+    key_order: crate::yaml::KeyOrder,
+    /// Layout of output documents, from `--indent`, `--flow` and others
+    style: Option<crate::yaml::Style>,
+    /// Timestamps don't depend on the current time
+    deterministic: bool,
+    /// Write output documents and structures as TOML
+    toml_output: bool,
+    /// Paths of the entries in regions protected by comment directives
+    protected: Vec<String>,
+    /// Anchors of the input document with the path of their node
+    anchors: Vec<(String, String)>,
+    /// Anchors and aliases written in the input document, for
+    /// `get-value --keep-aliases`
+    aliases: crate::yaml::SourceAliases,
+    /// The input document, for the text of scalars as written
+    document: Option<Document>,
+    /// Paths mutations are restricted to, from `--allow-paths`
+    allowed: Option<crate::yaml::AllowedPaths>,
+    /// Changes to print instead of the output document
+    report: Option<ChangeReport>,
+    /// Directory relative overlay and value files are found from
+    base_dir: Option<String>,
+    /// The input document comes from a file, so `set-value` reads values
+    /// of `-` from stdin
+    stdin_values: bool,
+    /// Options of the operations on documents
+    options: crate::yaml::Options,
+    /// Text of null values in raw output, from `--null-output`
+    null_output: Option<String>,
+}
+
+impl ChainState {
+    fn new(
+        cli: &def::Args,
+        protected: Vec<String>,
+        allowed: Option<&crate::yaml::AllowedPaths>,
+        report: Option<&ChangeReport>,
+        key_order: &crate::yaml::KeyOrder,
+        style: Option<&crate::yaml::Style>,
+        options: &crate::yaml::Options,
+    ) -> Self {
+        ChainState {
+            factor_anchors: cli.factor_anchors,
+            key_order: key_order.clone(),
+            style: style.cloned(),
+            deterministic: cli.deterministic,
+            toml_output: cli.output_format == "toml",
+            protected,
+            allowed: allowed.cloned(),
+            report: report.cloned(),
+            base_dir: cli.base_dir.clone(),
+            stdin_values: reads_file_input(cli),
+            options: options.clone(),
+            null_output: cli.null_output.clone(),
+            ..ChainState::default()
+        }
+    }
+
+    /// Forget what was found in the input document, once later actions work
+    /// on a value derived from it.
+    fn leave_document(&mut self) {
+        self.protected.clear();
+        self.anchors.clear();
+        self.aliases = crate::yaml::SourceAliases::default();
+        self.document = None;
+    }
+
+    /// Serialize a document produced by the chain.
+    fn serialize(&self, value: &crate::yaml::Value) -> Result<String, crate::yaml::Error> {
+        let ordered;
+        let value = if self.key_order.is_preserve() {
+            value
+        } else {
+            ordered = self.key_order.apply(value.clone());
+            &ordered
+        };
+        if self.toml_output {
+            crate::yaml::serialize_toml(value)
+        } else if self.factor_anchors {
+            crate::yaml::serialize_factored(value)
+        } else if let Some(style) = &self.style {
+            crate::yaml::serialize_styled(value, style)
+        } else {
+            crate::yaml::serialize(value)
+        }
+    }
+
+    /// Output of a value the chain ends with: a structure as a document,
+    /// a scalar raw unless YAML output is asked.
+    fn output(&self, value: &crate::yaml::Value, yaml_mode: bool) -> Result<String, CliError> {
+        match value {
+            crate::yaml::Value::Mapping(_) | crate::yaml::Value::Sequence(_) => {
+                Ok(self.serialize(value)?)
+            }
+            _ => output_value(value, yaml_mode, self.null_output.as_deref()),
+        }
+    }
+}
+
+/// Changes of a document printed instead of the document itself, asked with
+/// `--dry-run` or `--diff` on the last action of a chain.
+#[derive(Clone)]
+struct ChangeReport {
+    preview: def::Preview,
+    /// Name of the input in unified diff headers
+    input: String,
+}
+
+impl ChangeReport {
+    /// Print the changes between two versions of a document, returning
+    /// false if there are any.
+    fn print(&self, before: &Snapshot, after: &Snapshot) -> bool {
+        let changes = match self.preview.diff.as_deref() {
+            Some("paths") => crate::yaml::path_diff(&before.value, &after.value),
+            Some(_) => crate::yaml::unified_diff(&self.input, &before.text, &after.text),
+            None => return before.text == after.text,
+        };
+        print!("{}", changes);
+        changes.is_empty()
+    }
+}
+
+/// A version of a document, as it would be output and as a value.
+struct Snapshot {
+    text: String,
+    value: crate::yaml::Value,
+}
+
+impl Snapshot {
+    fn of_document(doc: &Document) -> Result<Self, CliError> {
+        let text = match doc.root() {
+            Some(_) => doc.emit()?,
+            None => String::new(),
+        };
+        Ok(Snapshot {
+            text,
+            value: crate::yaml::document_to_value(doc)?,
+        })
+    }
+
+    fn of_value(state: &ChainState, value: &crate::yaml::Value) -> Result<Self, CliError> {
+        Ok(Snapshot {
+            // Values are printed with a final newline
+            text: format!("{}\n", state.serialize(value)?),
+            value: value.clone(),
+        })
+    }
+}
+
+/// Get the change report asked on the last action of a chain, the only one
+/// that may have `--dry-run` or `--diff`.
+fn change_report(
+    cli: &def::Args,
+    command_groups: &[Vec<String>],
+    base_file: Option<&str>,
+) -> Result<Option<ChangeReport>, CliError> {
+    let actions = parse_actions(command_groups)?;
+    let mut previews = actions.iter().map(|action| {
+        action
+            .as_ref()
+            .and_then(preview_options)
+            .filter(|p| p.is_set())
+    });
+    let last = previews.next_back().flatten();
+    if previews.any(|p| p.is_some()) {
+        return Err("--dry-run and --diff can only be used on the last action of a chain".into());
+    }
+    let Some(preview) = last else {
+        return Ok(None);
+    };
+    if cli.output.is_some() {
+        return Err("--dry-run and --diff can't be used with --output".into());
+    }
+    Ok(Some(ChangeReport {
+        preview: preview.clone(),
+        input: base_file.unwrap_or("<stdin>").to_string(),
+    }))
+}
+
+/// Options of the actions that can report their changes.
+fn preview_options(action: &def::Actions) -> Option<&def::Preview> {
+    match action {
+        def::Actions::SetValue { preview, .. }
+        | def::Actions::Del { preview, .. }
+        | def::Actions::Apply { preview, .. }
+        | def::Actions::Merge { preview, .. } => Some(preview),
+        _ => None,
+    }
+}
+
+/// Run a chain on a Value, returning `Outcome::Skipped` if a guarded write
+/// was skipped, or with a change report, `Outcome::Failure` if the
+/// document changed.
 fn run_value_mode_chain(
     command_groups: &[Vec<String>],
     initial_value: crate::yaml::Value,
     multi_doc_yaml: bool,
-) -> Result<crate::yaml::Value, String> {
+    mut state: ChainState,
+) -> Result<Outcome, CliError> {
+    let before = state
+        .report
+        .as_ref()
+        .map(|_| Snapshot::of_value(&state, &initial_value))
+        .transpose()?;
     let mut current_value = initial_value;
 
     for (i, cmd_args) in command_groups.iter().enumerate() {
-        let is_last_cmd = i == command_groups.len() - 1;
+        // The changes are reported instead of the document
+        let is_last_cmd = i == command_groups.len() - 1 && state.report.is_none();
         // Only apply multi_doc_yaml newline handling on the last command
         let apply_multi_doc = is_last_cmd && multi_doc_yaml;
         current_value = run_single(
             cmd_args.clone(),
             current_value,
+            &mut state,
             is_last_cmd,
             false,
             apply_multi_doc,
         )?;
     }
-    Ok(current_value)
+    if let (Some(report), Some(before)) = (&state.report, before) {
+        return Ok(report
+            .print(&before, &Snapshot::of_value(&state, &current_value)?)
+            .into());
+    }
+    Ok(if state.skipped {
+        Outcome::Skipped
+    } else {
+        Outcome::Success
+    })
 }
 
-fn is_line_buffered(cli: &def::Args) -> bool {
-    matches!(
-        &cli.action,
-        Some(def::Actions::GetValue {
-            line_buffer: true,
-            ..
-        })
-    )
+/// Whether `-L` is given, before or after the action of any command
+/// group.
+fn is_line_buffered(command_groups: &[Vec<String>]) -> Result<bool, CliError> {
+    for group in command_groups {
+        if def::Args::try_parse_group(group)?.line_buffer {
+            return Ok(true);
+        }
+    }
+    Ok(false)
 }
 
 fn is_yaml_output(cli: &def::Args) -> bool {
@@ -329,24 +1073,178 @@ fn is_yaml_output(cli: &def::Args) -> bool {
 // Main Entry Point
 // =============================================================================
 
-pub fn run() -> Result<bool, String> {
+/// How a run ended when no error occurred.
+///
+/// Variants are ordered so that the outcome of several documents is the
+/// greatest of theirs, a failure outweighing a skipped write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Outcome {
+    Success,
+    /// A guarded `set-value` was skipped
+    Skipped,
+    /// A check found problems, or a report found changes
+    Failure,
+}
+
+impl Outcome {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Success => 0,
+            Outcome::Failure => 1,
+            // Apart from the 1 of path errors silenced by `-q` and from
+            // the error categories
+            Outcome::Skipped => 7,
+        }
+    }
+}
+
+impl From<bool> for Outcome {
+    fn from(success: bool) -> Self {
+        if success {
+            Outcome::Success
+        } else {
+            Outcome::Failure
+        }
+    }
+}
+
+pub fn run() -> Result<Outcome, CliError> {
+    crash::install();
     let args: Vec<String> = std::env::args().collect();
-    let command_groups = split_compound_args(args);
+    let config = config::Config::load()?;
+    let command_groups =
+        env_opts::with_env_opts(config.with_defaults(split_compound_args(args)), &config)?;
 
     if command_groups.is_empty() {
-        return Err("No command provided".to_string());
+        return Err("No command provided".into());
     }
 
     let cli = setup_cli_context(&command_groups[0])?;
-    let quiet = cli.quiet;
+    error::set_json_errors(cli.errors == "json");
+    error::set_quiet(cli.quiet);
+
+    let output = redirect_output(&cli)?;
+    if let Some(output) = &output {
+        crash::set_pending_output(output.tmp_path());
+    }
+    // Captured before the watchdog starts, so that its file is already
+    // unlinked when the process may be ended
+    let post_process = cli
+        .post_process
+        .as_deref()
+        .map(output::PostProcess::capture)
+        .transpose()?;
+    let deadline = cli
+        .timeout
+        .map(|seconds| start_timeout(seconds, output.as_ref().map(|o| o.tmp_path().to_path_buf())));
+    if cli.watch {
+        return watch(&cli, &command_groups);
+    }
+    let mut result = run_commands(&cli, &command_groups);
+    if result.is_ok() && deadline.is_some_and(|deadline| !deadline.finish()) {
+        // The watchdog is ending the process
+        loop {
+            std::thread::park();
+        }
+    }
+    // On error, dropping the capture and the redirection leaves stdout and
+    // the output file untouched
+    if let Some(post_process) = post_process {
+        result = result.and_then(|outcome| post_process.finish().map(|()| outcome));
+    }
+    if let Some(output) = output {
+        result = result.and_then(|outcome| output.commit().map(|()| outcome));
+    }
+    result
+}
+
+/// Run the commands again each time the `--file` input changes, until
+/// interrupted.
+///
+/// The file may be caught in the middle of an edit, so errors are reported
+/// without ending the watch.
+fn watch(cli: &def::Args, command_groups: &[Vec<String>]) -> Result<Outcome, CliError> {
+    use std::io::Write;
+
+    let file = cli.file.as_deref().ok_or("--watch requires --file")?;
+    // Changes made during the first run are not missed
+    let mut watcher = watch::FileWatcher::new(file)?;
+    let separator = if is_yaml_output(cli) { "---\n" } else { "\0" };
+    loop {
+        if let Err(e) = run_commands(cli, command_groups) {
+            error::report(&e);
+        }
+        std::io::stdout().flush()?;
+        watcher.wait()?;
+        print!("{}", separator);
+    }
+}
+
+/// State of a run shared with the `--timeout` watchdog.
+#[derive(Clone, Default)]
+struct Deadline(std::sync::Arc<std::sync::atomic::AtomicU8>);
+
+impl Deadline {
+    const RUNNING: u8 = 0;
+    const FINISHING: u8 = 1;
+    const EXPIRED: u8 = 2;
+
+    /// Let the run finish, committing the `--output` file and running the
+    /// `--post-process` command, without being ended midway. Returns
+    /// `false` if the watchdog is already ending the process.
+    fn finish(&self) -> bool {
+        self.switch(Self::FINISHING)
+    }
+
+    /// Mark the deadline as passed, unless the run is finishing.
+    fn expire(&self) -> bool {
+        self.switch(Self::EXPIRED)
+    }
 
-    match run_commands(&cli, &command_groups) {
-        Err(_) if quiet => Ok(false),
-        other => other,
+    fn switch(&self, state: u8) -> bool {
+        use std::sync::atomic::Ordering;
+        self.0
+            .compare_exchange(Self::RUNNING, state, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok()
     }
 }
 
-fn run_commands(cli: &def::Args, command_groups: &[Vec<String>]) -> Result<bool, String> {
+/// Exit the process once `seconds` have elapsed.
+///
+/// Parsing can't be interrupted, so the process is ended from a watchdog
+/// thread. The pending `--output` file is removed, so the target is left
+/// untouched; output already written to stdout is not taken back. Once
+/// the run is finishing, the watchdog lets it complete rather than leave
+/// a half-written backup or a running post-process command behind.
+fn start_timeout(seconds: u64, pending_output: Option<std::path::PathBuf>) -> Deadline {
+    let deadline = Deadline::default();
+    let watched = deadline.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(seconds));
+        if !watched.expire() {
+            return;
+        }
+        if let Some(tmp) = pending_output {
+            std::fs::remove_file(tmp).ok();
+        }
+        let e = CliError::new(
+            ErrorKind::Timeout,
+            format!("timed out after {} seconds", seconds),
+        );
+        error::report(&e);
+        std::process::exit(e.exit_code());
+    });
+    deadline
+}
+
+fn redirect_output(cli: &def::Args) -> Result<Option<output::OutputFile>, CliError> {
+    cli.output
+        .as_deref()
+        .map(|path| output::OutputFile::redirect(path, cli.backup.as_deref()))
+        .transpose()
+}
+
+fn run_commands(cli: &def::Args, command_groups: &[Vec<String>]) -> Result<Outcome, CliError> {
     if cli.version {
         println!("version: {}", env!("CARGO_PKG_VERSION"));
         println!(
@@ -354,23 +1252,209 @@ fn run_commands(cli: &def::Args, command_groups: &[Vec<String>]) -> Result<bool,
             crate::yaml::get_version()?
         );
         println!("Rust: {}", RUSTC_VERSION);
-        return Ok(true);
+        return Ok(Outcome::Success);
     }
 
-    let line_buffered = is_line_buffered(cli);
-    let yaml_output = is_yaml_output(cli);
+    if command_groups.len() > 1 {
+        let actions = parse_actions(command_groups)?;
+        // Path helpers, syntax checks, test suites and the editor server
+        // don't run on a single input document, so they can't take part in
+        // a chain
+        if actions.iter().flatten().any(plan::is_standalone) {
+            return Err(
+                "path helper, check-syntax, test-suite and lsp-lite actions cannot be chained"
+                    .into(),
+            );
+        }
+        // Unflatten reads its own input format, so it must come first
+        if actions[1..]
+            .iter()
+            .flatten()
+            .any(|a| matches!(a, def::Actions::Unflatten))
+        {
+            return Err("unflatten can only be the first action of a chain".into());
+        }
+        // So must `merge`, which replaces the input of the chain
+        if actions[1..]
+            .iter()
+            .flatten()
+            .any(|a| matches!(a, def::Actions::Merge { .. }))
+        {
+            return Err("merge can only be the first action of a chain".into());
+        }
+    }
+    // Input documents come from `--file` or the first file of `merge`,
+    // leaving stdin to overlays
+    let base_file = match (&cli.file, &cli.action) {
+        (Some(_), Some(def::Actions::Merge { .. })) => {
+            return Err("merge reads its own files, it can't be used with --file".into());
+        }
+        (Some(file), _) => Some(file.as_str()),
+        (None, Some(def::Actions::Merge { files, .. })) => Some(files[0].as_str()),
+        (None, _) => None,
+    };
+    if let Some(file) = base_file {
+        crash::set_input_file(file);
+    }
+    if base_file.is_none()
+        && parse_actions(command_groups)?
+            .iter()
+            .flatten()
+            .any(reads_stdin_overlay)
+    {
+        return Err("overlay '-' reads stdin, the base document must be given with --file".into());
+    }
+    if cli.deterministic
+        && parse_actions(command_groups)?
+            .iter()
+            .flatten()
+            .any(fetches_url_overlay)
+    {
+        return Err(
+            "--deterministic doesn't allow https:// overlays, their content can change \
+             between runs"
+                .into(),
+        );
+    }
+    let options = yaml_options(cli)?;
+    if let Some(action) = cli.action.as_ref().filter(|a| plan::is_standalone(a)) {
+        return run_standalone(action, &options).map(Outcome::from);
+    }
+    let report = change_report(cli, command_groups, base_file)?;
+
+    let line_buffered = is_line_buffered(command_groups)?;
+    let yaml_output = is_yaml_output(cli);
     let separator = if yaml_output { "---\n" } else { "\0" };
 
+    let env_subst = cli.env_subst || cli.env_subst_strict;
+    let env_allow = match &cli.env_allow {
+        Some(_) if !env_subst => {
+            return Err("--env-allow requires --env-subst or --env-subst-strict".into())
+        }
+        Some(entries) => Some(crate::yaml::EnvAllow::new(entries)?),
+        None => None,
+    };
+    let allowed = cli
+        .allow_paths
+        .as_deref()
+        .map(crate::yaml::AllowedPaths::from_file)
+        .transpose()?;
+    let key_order = crate::yaml::KeyOrder::new(&cli.key_order, cli.key_order_schema.as_deref())?;
+    let style = crate::yaml::Style::new(
+        cli.indent.map(|n| n as usize),
+        cli.flow.as_deref(),
+        cli.width.map(|n| n as usize),
+        cli.quote_strings.as_deref(),
+    )?;
+    if style.is_some() && (cli.factor_anchors || cli.output_format == "toml") {
+        return Err(
+            "--indent, --flow, --width and --quote-strings can't be used with \
+             --factor-anchors or --output-format toml"
+                .into(),
+        );
+    }
+
     // Determine execution mode for the command chain
     let exec_mode = determine_execution_mode(command_groups)?;
+    let toml_input = cli.input_format == "toml";
+    let keep_aliases = keeps_aliases(command_groups)?;
+    let lists_anchors = looks_up_anchors(command_groups)?;
+    // Interpolation rewrites scalars and reordering rewrites mappings,
+    // anchors, kept aliases, styled YAML and TOML are emitted from the
+    // Value and TOML is read into one, anchors of the input are looked up
+    // in its text, all require the Value pipeline
+    let exec_mode = if env_subst
+        || cli.factor_anchors
+        || keep_aliases
+        || lists_anchors
+        || !key_order.is_preserve()
+        || style.is_some()
+        || toml_input
+        || cli.output_format == "toml"
+    {
+        ExecutionMode::ValueMode
+    } else {
+        exec_mode
+    };
 
     use std::io::Write;
 
-    let doc_iter = crate::yaml::streaming_documents_from_stdin(line_buffered)?;
+    // Input is made of `path=value` lines or TOML instead of YAML documents
+    let unflatten = matches!(cli.action, Some(def::Actions::Unflatten));
+    if unflatten && toml_input {
+        return Err(
+            "unflatten reads its own input format, it can't be used with \
+             --input-format toml"
+                .into(),
+        );
+    }
+    if unflatten || toml_input {
+        let input = match base_file {
+            Some(file) => std::fs::read_to_string(file)
+                .map_err(|e| crate::yaml::Error::Io(format!("Failed to read '{}': {}", file, e)))?,
+            None => std::io::read_to_string(std::io::stdin())?,
+        };
+        let mut value = if unflatten {
+            crate::yaml::unflatten(&input, &options)?
+        } else {
+            crate::yaml::from_toml(&input)?
+        };
+        if env_subst {
+            value = crate::yaml::env_subst(value, cli.env_subst_strict, env_allow.as_ref())?;
+        }
+        return run_value_mode_chain(
+            command_groups,
+            key_order.apply(value),
+            yaml_output,
+            ChainState::new(
+                cli,
+                Vec::new(),
+                allowed.as_ref(),
+                report.as_ref(),
+                &key_order,
+                style.as_ref(),
+                &options,
+            ),
+        );
+    }
+
+    // Anchors and aliases written in each input document
+    let mut sources = Vec::new();
+    let mut anchors = Vec::new();
+    let doc_iter: Box<dyn Iterator<Item = Result<Document, crate::yaml::Error>>> =
+        match (base_file, cli.broken_alias.as_str()) {
+            // The whole input is needed to find the anchors aliases refer
+            // to, and where aliases were once they are resolved
+            (base_file, broken_alias)
+                if broken_alias == "null" || keep_aliases || lists_anchors =>
+            {
+                let mut input = match base_file {
+                    Some(file) => std::fs::read_to_string(file).map_err(|e| {
+                        crate::yaml::Error::Io(format!("Failed to read '{}': {}", file, e))
+                    })?,
+                    None => std::io::read_to_string(std::io::stdin())?,
+                };
+                if broken_alias == "null" {
+                    input = crate::yaml::null_undefined_aliases(&input);
+                }
+                if keep_aliases {
+                    sources = crate::yaml::source_aliases(&input);
+                }
+                if lists_anchors {
+                    anchors = crate::yaml::source_anchors(&input);
+                }
+                Box::new(crate::yaml::streaming_documents_from_str(&input)?)
+            }
+            (Some(file), _) => Box::new(crate::yaml::streaming_documents_from_file(file)?),
+            (None, _) => Box::new(crate::yaml::streaming_documents_from_stdin(line_buffered)?),
+        };
     let mut first = true;
+    let mut outcome = Outcome::Success;
 
-    for doc_result in doc_iter {
-        if !first {
+    for (index, doc_result) in doc_iter.enumerate() {
+        crash::set_document(index + 1);
+        // A dry run prints nothing at all
+        if !first && !report.as_ref().is_some_and(|r| r.preview.dry_run) {
             print!("{}", separator);
         }
         first = false;
@@ -378,14 +1462,41 @@ fn run_commands(cli: &def::Args, command_groups: &[Vec<String>]) -> Result<bool,
         match exec_mode {
             ExecutionMode::DocMode => {
                 // DocMode: work directly with Document via Editor (practical COW)
-                let mut doc = doc_result.str_err()?;
-                run_doc_mode_chain(command_groups, &mut doc, yaml_output)?;
+                let mut doc = doc_result?;
+                outcome = outcome.max(run_doc_mode_chain(
+                    command_groups,
+                    &mut doc,
+                    yaml_output,
+                    allowed.as_ref(),
+                    report.as_ref(),
+                )?);
             }
             ExecutionMode::ValueMode => {
                 // ValueMode: convert to owned Value (for complex operations like apply, keys, values)
-                let doc = doc_result.str_err()?;
-                let value = crate::yaml::document_to_value(&doc).str_err()?;
-                run_value_mode_chain(command_groups, value, yaml_output)?;
+                let doc = doc_result?;
+                let mut value = crate::yaml::document_to_value(&doc)?;
+                if env_subst {
+                    value =
+                        crate::yaml::env_subst(value, cli.env_subst_strict, env_allow.as_ref())?;
+                }
+                let mut state = ChainState::new(
+                    cli,
+                    protected_regions(command_groups, &doc)?,
+                    allowed.as_ref(),
+                    report.as_ref(),
+                    &key_order,
+                    style.as_ref(),
+                    &options,
+                );
+                state.anchors = anchors.get(index).cloned().unwrap_or_default();
+                state.aliases = sources.get(index).cloned().unwrap_or_default();
+                state.document = Some(doc);
+                outcome = outcome.max(run_value_mode_chain(
+                    command_groups,
+                    key_order.apply(value),
+                    yaml_output,
+                    state,
+                )?);
             }
         }
 
@@ -396,19 +1507,295 @@ fn run_commands(cli: &def::Args, command_groups: &[Vec<String>]) -> Result<bool,
 
     if first {
         // Empty input - no multi-doc separation needed
-        match exec_mode {
+        outcome = match exec_mode {
             ExecutionMode::DocMode => {
-                run_doc_mode_empty(command_groups, false)?;
-            }
-            ExecutionMode::ValueMode => {
-                run_value_mode_chain(command_groups, crate::yaml::Value::Null, false)?;
+                run_doc_mode_empty(command_groups, false, allowed.as_ref(), report.as_ref())?
             }
+            ExecutionMode::ValueMode => run_value_mode_chain(
+                command_groups,
+                crate::yaml::Value::Null,
+                false,
+                ChainState::new(
+                    cli,
+                    Vec::new(),
+                    allowed.as_ref(),
+                    report.as_ref(),
+                    &key_order,
+                    style.as_ref(),
+                    &options,
+                ),
+            )?,
+        };
+    }
+
+    Ok(outcome)
+}
+
+/// Merge overlays into a value, keeping protected regions and allowed paths.
+fn apply_overlays(
+    overlays: &[String],
+    merge_policy: Option<&Vec<String>>,
+    url_timeout: u64,
+    strict_tags: bool,
+    value: crate::yaml::Value,
+    state: &ChainState,
+) -> Result<crate::yaml::Value, CliError> {
+    let policies = crate::yaml::parse_merge_policies(merge_policy)?;
+    let original = (!state.protected.is_empty() || state.allowed.is_some()).then(|| value.clone());
+    let overlays: Vec<String> = overlays
+        .iter()
+        .map(|overlay| in_base_dir(state.base_dir.as_deref(), overlay))
+        .collect();
+    let result = crate::yaml::apply(
+        &overlays,
+        &policies,
+        value,
+        url_timeout,
+        strict_tags,
+        &state.options,
+    )?;
+    Ok(match original {
+        Some(original) => {
+            let result = crate::yaml::restore_protected(
+                &original,
+                result,
+                &state.protected,
+                &state.options,
+            )?;
+            crate::yaml::check_allowed_changes(&original, &result, state.allowed.as_ref())?;
+            result
         }
+        None => result,
+    })
+}
+
+/// Whether an action reads an overlay from stdin.
+fn reads_stdin_overlay(action: &def::Actions) -> bool {
+    match action {
+        def::Actions::Apply { overlays, .. } => overlays.iter().any(|o| o == "-"),
+        def::Actions::Merge { files, .. } => files[1..].iter().any(|f| f == "-"),
+        _ => false,
+    }
+}
+
+/// Whether an action fetches an overlay from a URL.
+fn fetches_url_overlay(action: &def::Actions) -> bool {
+    match action {
+        def::Actions::Apply { overlays, .. } => overlays.iter().any(|o| o.starts_with("https://")),
+        def::Actions::Merge { files, .. } => files[1..].iter().any(|f| f.starts_with("https://")),
+        _ => false,
     }
+}
 
+/// Handle actions that work on their arguments only, without reading stdin.
+fn run_standalone(action: &def::Actions, opts: &crate::yaml::Options) -> Result<bool, CliError> {
+    let path = match action {
+        def::Actions::ParentPath {
+            path: Some(path), ..
+        } => crate::yaml::parent_path(path)?,
+        def::Actions::JoinPath { path, segments, .. } => crate::yaml::join_path(path, segments),
+        def::Actions::RelativePath { base, full } => crate::yaml::relative_path(base, full)?,
+        def::Actions::CheckSyntax {
+            files,
+            config,
+            report,
+        } => return check_syntax(files, config.as_deref(), report),
+        def::Actions::TestSuite {
+            suite,
+            files,
+            report,
+        } => return test_suite(suite, files, report, opts),
+        def::Actions::LspLite { stdio: _ } => return lsp::serve(opts),
+        def::Actions::Tags => {
+            print_tags();
+            return Ok(true);
+        }
+        def::Actions::Features => {
+            print_features()?;
+            return Ok(true);
+        }
+        def::Actions::Introspect { format } => {
+            introspect::print(format)?;
+            return Ok(true);
+        }
+        _ => unreachable!("Non-standalone action in standalone path"),
+    };
+    println!("{}", path);
     Ok(true)
 }
 
+/// Print the capabilities of this build and of its environment as YAML:
+///
+/// - `net`: `https://` overlays, which need `curl` in the `PATH`
+/// - `crypto`: encrypted values, not supported
+/// - `tui`: the `explore` tree view
+/// - `watch`: `--watch`, depending on the platform
+/// - `compat`: the command line of the Python shyaml
+fn print_features() -> Result<(), CliError> {
+    let curl = std::process::Command::new("curl")
+        .arg("--version")
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    println!("version: {}", env!("CARGO_PKG_VERSION"));
+    println!("features:");
+    for (name, available) in [
+        ("net", curl),
+        ("crypto", false),
+        ("tui", cfg!(unix)),
+        ("watch", watch::SUPPORTED),
+        ("compat", true),
+    ] {
+        println!("  {}: {}", name, available);
+    }
+    println!("backends:");
+    println!("  yaml: libfyaml {}", crate::yaml::get_version()?);
+    println!("  toml: toml-rs");
+    Ok(())
+}
+
+/// Print each registered tag namespace with its directives, aligned:
+///
+/// ```text
+/// merge: How apply merges an overlay value into the base document
+///   !merge:replace  Replace the base value entirely
+/// ```
+fn print_tags() {
+    for namespace in crate::tag::registry().namespaces() {
+        println!("{}: {}", namespace.name(), namespace.description());
+        let directives: Vec<(String, &str)> = namespace
+            .operations()
+            .iter()
+            .map(|op| (format!("!{}:{}", namespace.name(), op.name), op.description))
+            .collect();
+        let width = directives.iter().map(|(d, _)| d.len()).max().unwrap_or(0);
+        for (directive, description) in directives {
+            println!("  {:width$}  {}", directive, description, width = width);
+        }
+    }
+}
+
+/// Check the syntax, anchors and style of files (stdin if none), printing
+/// each problem as `FILE:LINE:COLUMN: MESSAGE`, as GitHub Actions
+/// annotations with `report` `github`, or all of them as a SARIF log with
+/// `report` `sarif`. Returns false if any check failed.
+fn check_syntax(files: &[String], config: Option<&str>, report: &str) -> Result<bool, CliError> {
+    let rules = match config {
+        Some(file) => crate::yaml::StyleRules::from_file(file)?,
+        None => crate::yaml::StyleRules::default(),
+    };
+    let stdin = ["-".to_string()];
+    let files = if files.is_empty() { &stdin[..] } else { files };
+
+    let mut valid = true;
+    let mut found = Vec::new();
+    for file in files {
+        let (label, content) = if file == "-" {
+            ("<stdin>", std::io::read_to_string(std::io::stdin()))
+        } else {
+            (file.as_str(), std::fs::read_to_string(file))
+        };
+        let result = content
+            .map_err(|e| {
+                vec![crate::yaml::Problem {
+                    location: None,
+                    rule: "read-error",
+                    message: format!("Failed to read: {}", e),
+                }]
+            })
+            .and_then(|content| crate::yaml::check_syntax(&content, &rules));
+        let Err(problems) = result else {
+            continue;
+        };
+        valid = false;
+        if report == "sarif" {
+            found.extend(problems.into_iter().map(|p| (label.to_string(), p)));
+            continue;
+        }
+        for problem in problems {
+            if report == "github" {
+                println!("{}", report::github(label, &problem));
+                continue;
+            }
+            match problem.location {
+                Some((line, column)) => {
+                    println!("{}:{}:{}: {}", label, line, column, problem.message)
+                }
+                None => println!("{}: {}", label, problem.message),
+            }
+        }
+    }
+    if report == "sarif" {
+        println!("{}", report::sarif(&found));
+    }
+    Ok(valid)
+}
+
+/// Run the assertions of a suite file on each document of `files` (stdin if
+/// none), printing `PASS: NAME` or `FAIL: NAME: REASON` for each and a
+/// summary, or a JUnit XML report with `report` `junit`. Returns false if
+/// any assertion failed.
+fn test_suite(
+    suite_file: &str,
+    files: &[String],
+    report: &str,
+    opts: &crate::yaml::Options,
+) -> Result<bool, CliError> {
+    let suite = crate::yaml::TestSuite::from_file(suite_file)?;
+    let mut inputs = Vec::new();
+    if files.is_empty() {
+        let docs = crate::yaml::streaming_documents_from_stdin(false)?.collect::<Result<_, _>>()?;
+        inputs.push(("<stdin>", docs));
+    }
+    for file in files {
+        inputs.push((file.as_str(), crate::yaml::documents_from_file(file)?));
+    }
+
+    let mut results: Vec<(String, Vec<crate::yaml::Outcome>)> = Vec::new();
+    for (name, docs) in inputs {
+        if docs.is_empty() {
+            results.push((
+                name.to_string(),
+                suite.run(&crate::yaml::Value::Null, None, opts)?,
+            ));
+        }
+        for (i, doc) in docs.iter().enumerate() {
+            let label = match docs.len() {
+                1 => name.to_string(),
+                _ => format!("{} (document {})", name, i + 1),
+            };
+            let value = crate::yaml::document_to_value(doc)?;
+            results.push((label, suite.run(&value, Some(doc), opts)?));
+        }
+    }
+    let failed = results
+        .iter()
+        .flat_map(|(_, outcomes)| outcomes)
+        .filter(|o| o.failure.is_some())
+        .count();
+
+    if report == "junit" {
+        println!("{}", report::junit(&suite.name, &results));
+        return Ok(failed == 0);
+    }
+    for (label, outcomes) in &results {
+        let prefix = match results.len() {
+            1 => String::new(),
+            _ => format!("{}: ", label),
+        };
+        for outcome in outcomes {
+            match &outcome.failure {
+                Some(failure) => println!("{}FAIL: {}: {}", prefix, outcome.name, failure),
+                None => println!("{}PASS: {}", prefix, outcome.name),
+            }
+        }
+    }
+    let total: usize = results.iter().map(|(_, outcomes)| outcomes.len()).sum();
+    println!("{} passed, {} failed", total - failed, failed);
+    Ok(failed == 0)
+}
+
 // =============================================================================
 // Zero-Copy Command Handler
 // =============================================================================
@@ -419,25 +1806,57 @@ fn run_single_readonly(
     cli: &def::Args,
     doc: &Document,
     multi_doc_yaml: bool,
-) -> Result<(), String> {
+    opts: &crate::yaml::Options,
+    null_output: Option<&str>,
+) -> Result<(), CliError> {
     let yaml_mode = cli.yaml;
 
     match &cli.action {
         Some(def::Actions::GetValue {
             path,
             default,
+            default_yaml,
+            into_json_schema_type,
             yaml,
-            line_buffer: _,
+            resolve_aliases: _,
+            keep_aliases: _,
+            fail_on_null,
+            binary,
+            as_bool,
+            date_format,
+            ..
         }) => {
             let yaml_mode = yaml_mode || *yaml;
             let path = path.as_ref().map(|s| s.as_str());
 
-            match crate::yaml::get_value_ref(path, doc) {
+            match crate::yaml::get_value_ref(path, doc, opts) {
+                Ok(value_ref) if *fail_on_null && value_ref.is_null() => Err(null_error(path)),
+                Ok(value_ref) if *binary => {
+                    let text = value_ref.as_node().scalar_str().ok().map(String::from);
+                    write_binary(text, crate::yaml::value_ref_type_name(&value_ref), path)
+                }
+                Ok(value_ref) if *as_bool || date_format.is_some() => {
+                    let text = (!value_ref.is_mapping() && !value_ref.is_sequence())
+                        .then(|| crate::yaml::serialize_raw_ref(value_ref));
+                    let type_name = crate::yaml::value_ref_type_name(&value_ref);
+                    let converted =
+                        converted_scalar(text, type_name, path, date_format.as_deref(), opts)?;
+                    println!("{}", crate::yaml::serialize_raw(&converted));
+                    Ok(())
+                }
                 Ok(value_ref) => {
+                    if let Some(expected) = into_json_schema_type {
+                        let type_name = crate::yaml::value_ref_type_name(&value_ref);
+                        crate::yaml::check_json_schema_type(
+                            type_name,
+                            expected,
+                            path.unwrap_or_default(),
+                        )?;
+                    }
                     let output = if yaml_mode {
-                        crate::yaml::serialize_ref(value_ref).str_err()?
+                        crate::yaml::serialize_ref(value_ref)?
                     } else {
-                        crate::yaml::serialize_raw_ref(value_ref)
+                        output::raw_ref(value_ref, null_output)
                     };
                     print!("{}", output);
                     // Ensure output ends with newline for proper multi-doc YAML separation
@@ -446,54 +1865,164 @@ fn run_single_readonly(
                     }
                     Ok(())
                 }
-                Err(crate::yaml::Error::Path(e)) => {
+                Err(e @ crate::yaml::Error::Path { .. }) => {
                     if let Some(default_val) = default {
-                        print!("{}", default_val);
-                        if multi_doc_yaml && !default_val.ends_with('\n') {
+                        let (output, result) =
+                            get_value_default(default_val, *default_yaml, yaml_mode, null_output)?;
+                        check_value_type(&result, into_json_schema_type.as_deref(), path)?;
+                        print!("{}", output);
+                        if multi_doc_yaml && !output.ends_with('\n') {
                             println!();
                         }
                         return Ok(());
                     }
-                    Err(e)
+                    Err(e.into())
                 }
-                Err(e) => Err(e.to_string()),
+                Err(e) => Err(e.into()),
             }
         }
 
-        Some(def::Actions::GetType { path }) => {
+        Some(def::Actions::GetType { path, schema, .. }) => {
             let path = path.as_ref().map(|s| s.as_str());
-            let type_name = crate::yaml::get_type_ref(path, doc).str_err()?;
+            let schema = parse_schema(schema.as_deref())?;
+            let type_name = crate::yaml::get_type_ref(path, doc, schema, opts)?;
             println!("{}", type_name);
             Ok(())
         }
 
-        Some(def::Actions::GetLength { path }) => {
+        Some(def::Actions::GetLength { path, .. }) => {
             let path = path.as_ref().map(|s| s.as_str());
-            let len = crate::yaml::get_length_ref(path, doc).str_err()?;
+            let len = crate::yaml::get_length_ref(path, doc, opts)?;
             println!("{}", len);
             Ok(())
         }
 
+        Some(def::Actions::Export { path, prefix, .. }) => {
+            let path = path.as_ref().map(|s| s.as_str());
+            print!("{}", crate::yaml::export_ref(path, doc, prefix, opts)?);
+            Ok(())
+        }
+
+        Some(def::Actions::ToDotenv {
+            path, separator, ..
+        }) => {
+            let path = path.as_ref().map(|s| s.as_str());
+            print!(
+                "{}",
+                crate::yaml::to_dotenv_ref(path, doc, separator, opts)?
+            );
+            Ok(())
+        }
+
+        Some(def::Actions::Validate { schema, report }) => {
+            validate(&crate::yaml::document_to_value(doc)?, schema, report)
+        }
+
+        Some(def::Actions::Render { template }) => {
+            let template = crate::yaml::Template::from_file(template)?;
+            print!(
+                "{}",
+                template.render(&crate::yaml::document_to_value(doc)?, opts)?
+            );
+            Ok(())
+        }
+
+        Some(def::Actions::Explore { path, .. }) => {
+            let value = crate::yaml::document_to_value(doc)?;
+            explore(path.as_deref(), &value, opts)
+        }
+
+        Some(def::Actions::Filter {
+            filter,
+            yaml,
+            arg,
+            argjson,
+        }) => {
+            let vars = crate::yaml::Vars::new(arg, argjson)?;
+            let filter = crate::yaml::JqFilter::parse_with_vars(filter, &vars)?;
+            let results = filter.run(&crate::yaml::document_to_value(doc)?)?;
+            let policy =
+                output::OutputPolicy::newline(yaml_mode || *yaml).with_null_output(null_output);
+            output::print_items(results.iter(), &policy);
+            Ok(())
+        }
+
+        Some(def::Actions::SelectWhere {
+            path, condition, ..
+        }) => {
+            let value = crate::yaml::document_to_value(doc)?;
+            let selected = crate::yaml::select_where(Some(path), condition, &value, opts)?;
+            println!("{}", crate::yaml::serialize(&selected)?);
+            Ok(())
+        }
+
+        Some(
+            action @ (def::Actions::Sum { .. }
+            | def::Actions::Min { .. }
+            | def::Actions::Max { .. }
+            | def::Actions::Avg { .. }
+            | def::Actions::CountWhere { .. }),
+        ) => {
+            let result = aggregate(action, &crate::yaml::document_to_value(doc)?, opts)?;
+            println!("{}", crate::yaml::serialize_raw(&result));
+            Ok(())
+        }
+
+        Some(action @ def::Actions::Search { with_values, .. }) => {
+            let value = crate::yaml::document_to_value(doc)?;
+            print_search(
+                &search(action, &value, opts)?,
+                *with_values,
+                yaml_mode,
+                null_output,
+            );
+            Ok(())
+        }
+
         // Handle all iteration actions (Keys/Keys0, Values/Values0, etc.) uniformly
-        Some(action) if normalize_iter_action(action, yaml_mode).is_some() => {
-            let iter_action = normalize_iter_action(action, yaml_mode).unwrap();
+        Some(action) if normalize_iter_action(action, yaml_mode, null_output).is_some() => {
+            let iter_action = normalize_iter_action(action, yaml_mode, null_output).unwrap();
             match iter_action.kind {
-                IterKind::Keys => {
-                    let keys = crate::yaml::keys_ref(iter_action.path, doc).str_err()?;
-                    output::print_items(keys, &iter_action.policy);
-                }
+                IterKind::Keys => match crate::yaml::keys_ref(iter_action.path, doc, opts)? {
+                    crate::yaml::KeysIter::Map(keys) => {
+                        output::print_items(keys.map(output::MappingKey), &iter_action.policy)
+                    }
+                    crate::yaml::KeysIter::Indices(indices) => output::print_items(
+                        indices.map(|i| {
+                            crate::yaml::Value::Number(crate::yaml::Number::Int(i as i64))
+                        }),
+                        &iter_action.policy,
+                    ),
+                },
                 IterKind::Values => {
-                    let values = crate::yaml::values_ref(iter_action.path, doc).str_err()?;
+                    let values = crate::yaml::values_ref(iter_action.path, doc, opts)?;
                     output::print_items(values, &iter_action.policy);
                 }
                 IterKind::KeyValues => {
-                    let kv = crate::yaml::key_values_ref(iter_action.path, doc).str_err()?;
+                    let kv = crate::yaml::key_values_ref(iter_action.path, doc, opts)?;
+                    let kv = kv.map(|(k, v)| (output::MappingKey(k), v));
+                    output::print_kv_items(kv, &iter_action.policy);
+                }
+                IterKind::KeyValuesRecursive => {
+                    let leaves =
+                        crate::yaml::key_values_recursive_ref(iter_action.path, doc, opts)?;
+                    let kv = leaves
+                        .into_iter()
+                        .map(|(k, v)| (crate::yaml::Value::String(k), v));
                     output::print_kv_items(kv, &iter_action.policy);
                 }
                 IterKind::GetValues => {
-                    let iter = crate::yaml::get_values_ref(iter_action.path, doc).str_err()?;
+                    let iter = crate::yaml::get_values_ref(iter_action.path, doc, opts)?;
                     output::print_get_values(iter, &iter_action.policy);
                 }
+                IterKind::Types => {
+                    let types = crate::yaml::get_types_ref(iter_action.path, doc, opts)?;
+                    output::print_items(types, &iter_action.policy);
+                }
+                IterKind::Flatten => {
+                    let leaves = crate::yaml::flatten_ref(iter_action.path, doc, opts)?;
+                    output::print_flat_items(leaves.into_iter(), &iter_action.policy);
+                }
             }
             Ok(())
         }
@@ -503,48 +2032,140 @@ fn run_single_readonly(
 }
 
 /// Handle read-only commands on empty input.
-fn run_single_readonly_empty(cli: &def::Args) -> Result<(), String> {
+fn run_single_readonly_empty(cli: &def::Args) -> Result<(), CliError> {
+    let opts = &yaml_options(cli)?;
+    let null_output = cli.null_output.as_deref();
     let yaml_mode = cli.yaml;
 
     match &cli.action {
         Some(def::Actions::GetValue {
-            path: _,
+            path,
             default,
-            yaml: _,
-            line_buffer: _,
+            default_yaml,
+            into_json_schema_type,
+            yaml,
+            resolve_aliases: _,
+            keep_aliases: _,
+            fail_on_null: _,
+            binary: _,
+            as_bool: _,
+            date_format: _, ..
         }) => {
             // Empty document with path access should use default or error
             if let Some(default_val) = default {
-                print!("{}", default_val);
+                let (output, result) =
+                    get_value_default(
+                        default_val,
+                        *default_yaml,
+                        yaml_mode || *yaml,
+                        null_output,
+                    )?;
+                check_value_type(
+                    &result,
+                    into_json_schema_type.as_deref(),
+                    path.as_deref(),
+                )?;
+                print!("{}", output);
                 return Ok(());
             }
-            Err("empty document".to_string())
+            Err(crate::yaml::Error::path_error("empty document".to_string()).into())
         }
 
-        Some(def::Actions::GetType { path: _ }) => {
+        Some(def::Actions::GetType { .. }) => {
             // Empty document type
             println!("NoneType");
             Ok(())
         }
 
-        Some(def::Actions::GetLength { path: _ }) => {
-            Err("get-length does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string())
+        Some(def::Actions::GetLength { path: _, .. }) => {
+            Err(crate::yaml::Error::type_error("get-length does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string()).into())
+        }
+
+        Some(def::Actions::Export { path: _, prefix: _, .. }) => {
+            Err(crate::yaml::Error::type_error("export does not support 'NoneType' type. Please provide or select a struct.".to_string()).into())
+        }
+
+        Some(def::Actions::ToDotenv {
+            path: _,
+            separator: _, ..
+        }) => {
+            Err(crate::yaml::Error::type_error("to-dotenv does not support 'NoneType' type. Please provide or select a struct.".to_string()).into())
+        }
+
+        Some(def::Actions::Validate { schema, report }) => {
+            validate(&crate::yaml::Value::Null, schema, report)
+        }
+
+        Some(def::Actions::Render { template }) => {
+            let template = crate::yaml::Template::from_file(template)?;
+            print!("{}", template.render(&crate::yaml::Value::Null, opts)?);
+            Ok(())
+        }
+
+        Some(def::Actions::Explore { path, .. }) => explore(path.as_deref(), &crate::yaml::Value::Null, opts),
+
+        Some(def::Actions::Keys { path: _, yaml: _, .. })
+        | Some(def::Actions::Keys0 { path: _, yaml: _, .. })
+        | Some(def::Actions::Values { path: _, yaml: _, .. })
+        | Some(def::Actions::Values0 { path: _, yaml: _, .. })
+        | Some(def::Actions::KeyValues { .. })
+        | Some(def::Actions::KeyValues0 { .. }) => {
+            Err(crate::yaml::Error::type_error("keys/values does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string()).into())
+        }
+
+        Some(def::Actions::GetValues { path: _, yaml: _, .. })
+        | Some(def::Actions::GetValues0 { path: _, yaml: _, .. }) => {
+            Err(crate::yaml::Error::type_error("get-values does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string()).into())
+        }
+
+        Some(def::Actions::GetTypes { path: _, yaml: _, .. })
+        | Some(def::Actions::GetTypes0 { path: _, yaml: _, .. }) => {
+            Err(crate::yaml::Error::type_error("get-types does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string()).into())
+        }
+
+        Some(def::Actions::Flatten { path: _, .. }) | Some(def::Actions::Flatten0 { path: _, .. }) => {
+            Err(crate::yaml::Error::type_error("flatten does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string()).into())
+        }
+
+        Some(def::Actions::Filter {
+            filter,
+            yaml,
+            arg,
+            argjson,
+        }) => {
+            let vars = crate::yaml::Vars::new(arg, argjson)?;
+            let filter = crate::yaml::JqFilter::parse_with_vars(filter, &vars)?;
+            let results = filter.run(&crate::yaml::Value::Null)?;
+            let policy = output::OutputPolicy::newline(yaml_mode || *yaml).with_null_output(null_output);
+            output::print_items(results.iter(), &policy);
+            Ok(())
+        }
+
+        Some(action @ (def::Actions::Sum { .. }
+        | def::Actions::Min { .. }
+        | def::Actions::Max { .. }
+        | def::Actions::Avg { .. }
+        | def::Actions::CountWhere { .. })) => {
+            let result = aggregate(action, &crate::yaml::Value::Null, opts)?;
+            println!("{}", crate::yaml::serialize_raw(&result));
+            Ok(())
+        }
+
+        Some(def::Actions::SelectWhere { .. }) => {
+            Err(crate::yaml::Error::type_error("select-where does not support 'NoneType' type. Please provide or select a sequence.".to_string()).into())
         }
 
-        Some(def::Actions::Keys { path: _, yaml: _ })
-        | Some(def::Actions::Keys0 { path: _, yaml: _ })
-        | Some(def::Actions::Values { path: _, yaml: _ })
-        | Some(def::Actions::Values0 { path: _, yaml: _ })
-        | Some(def::Actions::KeyValues { path: _, yaml: _ })
-        | Some(def::Actions::KeyValues0 { path: _, yaml: _ }) => {
-            Err("keys/values does not support 'NoneType' type. Please provide or select a struct.".to_string())
+        Some(def::Actions::Search { .. }) => {
+            Err(crate::yaml::Error::type_error("search does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string()).into())
         }
 
-        Some(def::Actions::GetValues { path: _, yaml: _ })
-        | Some(def::Actions::GetValues0 { path: _, yaml: _ }) => {
-            Err("get-values does not support 'NoneType' type. Please provide or select a sequence or struct.".to_string())
+        Some(def::Actions::ListAnchors) => {
+            println!("{{}}");
+            Ok(())
         }
 
+        Some(def::Actions::GetAnchor { name, .. }) => anchor_path(&[], name).map(|_| ()),
+
         _ => {
             // For other cases, output nothing for null
             if yaml_mode {
@@ -558,22 +2179,287 @@ fn run_single_readonly_empty(cli: &def::Args) -> Result<(), String> {
 // Value-Based Command Handler (for mutations/chains)
 // =============================================================================
 
-fn output_value(value: &crate::yaml::Value, yaml_mode: bool) -> Result<String, String> {
+fn output_value(
+    value: &crate::yaml::Value,
+    yaml_mode: bool,
+    null_output: Option<&str>,
+) -> Result<String, CliError> {
     if yaml_mode {
-        crate::yaml::serialize(value).map_err(|e| e.to_string())
+        Ok(crate::yaml::serialize(value)?)
     } else {
-        Ok(crate::yaml::serialize_raw(value))
+        Ok(output::raw(value, null_output))
+    }
+}
+
+/// Error of `get-value --fail-on-null` on a null value.
+fn null_error(path: Option<&str>) -> CliError {
+    let path = path.unwrap_or_default();
+    CliError::at(
+        ErrorKind::Null,
+        path,
+        format!("invalid value at '{}', value is null.", path),
+    )
+}
+
+/// Browse the document in a terminal tree view, printing the path of the
+/// selected node, if any.
+fn explore(
+    path: Option<&str>,
+    value: &crate::yaml::Value,
+    opts: &crate::yaml::Options,
+) -> Result<(), CliError> {
+    let nodes = crate::yaml::outline(path, value, opts)?;
+    if let Some(selected) = explore::explore(nodes)? {
+        println!("{}", selected);
+    }
+    Ok(())
+}
+
+/// Mapping of anchor names to the path of their node, the last definition
+/// of a name winning.
+fn anchors_mapping(anchors: &[(String, String)]) -> crate::yaml::Value {
+    crate::yaml::Value::Mapping(
+        anchors
+            .iter()
+            .map(|(name, path)| {
+                (
+                    crate::yaml::Value::String(name.clone()),
+                    crate::yaml::Value::String(path.clone()),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Path of the node holding the last definition of anchor `name`, `None`
+/// for the root.
+fn anchor_path<'a>(
+    anchors: &'a [(String, String)],
+    name: &str,
+) -> Result<Option<&'a str>, CliError> {
+    let Some((_, path)) = anchors.iter().rev().find(|(n, _)| n == name) else {
+        return Err(crate::yaml::Error::path_error(format!("anchor '{}' not found.", name)).into());
+    };
+    Ok((!path.is_empty()).then_some(path.as_str()))
+}
+
+/// Paths and values of the nodes matched by a `search` action.
+fn search<'a>(
+    action: &def::Actions,
+    value: &'a crate::yaml::Value,
+    opts: &crate::yaml::Options,
+) -> Result<Vec<(String, &'a crate::yaml::Value)>, CliError> {
+    let def::Actions::Search {
+        pattern,
+        path,
+        keys_only,
+        values_only,
+        with_values: _,
+        ..
+    } = action
+    else {
+        unreachable!("search() is only called on search actions");
+    };
+    let scope = match (keys_only, values_only) {
+        (true, _) => crate::yaml::SearchScope::Keys,
+        (_, true) => crate::yaml::SearchScope::Values,
+        _ => crate::yaml::SearchScope::All,
+    };
+    Ok(crate::yaml::search(
+        path.as_deref(),
+        value,
+        pattern,
+        scope,
+        opts,
+    )?)
+}
+
+/// Result of an aggregation action: `sum`, `min`, `max`, `avg` or
+/// `count-where`.
+fn aggregate(
+    action: &def::Actions,
+    value: &crate::yaml::Value,
+    opts: &crate::yaml::Options,
+) -> Result<crate::yaml::Value, CliError> {
+    let (op, path) = match action {
+        def::Actions::Sum { path, .. } => (crate::yaml::Aggregate::Sum, path),
+        def::Actions::Min { path, .. } => (crate::yaml::Aggregate::Min, path),
+        def::Actions::Max { path, .. } => (crate::yaml::Aggregate::Max, path),
+        def::Actions::Avg { path, .. } => (crate::yaml::Aggregate::Avg, path),
+        def::Actions::CountWhere {
+            path, condition, ..
+        } => {
+            return Ok(crate::yaml::count_where(
+                Some(path),
+                condition,
+                value,
+                opts,
+            )?);
+        }
+        _ => unreachable!("aggregate() is only called on aggregation actions"),
+    };
+    Ok(crate::yaml::aggregate(op, path.as_deref(), value, opts)?)
+}
+
+/// Rearrangement of a `sort`, `unique` or `reverse` action.
+fn seq_op(action: &def::Actions) -> crate::yaml::SeqOp {
+    match action {
+        def::Actions::Sort {
+            key: _,
+            by,
+            numeric,
+            reverse,
+            ..
+        } => crate::yaml::SeqOp::Sort {
+            by: by.clone(),
+            numeric: *numeric,
+            reverse: *reverse,
+        },
+        def::Actions::Unique { key: _, by, .. } => crate::yaml::SeqOp::Unique { by: by.clone() },
+        def::Actions::Reverse { .. } => crate::yaml::SeqOp::Reverse,
+        _ => unreachable!("seq_op() is only called on sort, unique and reverse actions"),
+    }
+}
+
+/// Print the paths found by `search`, one per line, followed by their
+/// value `with_values`.
+fn print_search(
+    matches: &[(String, &crate::yaml::Value)],
+    with_values: bool,
+    yaml_mode: bool,
+    null_output: Option<&str>,
+) {
+    if with_values {
+        let policy = output::OutputPolicy::newline(yaml_mode).with_null_output(null_output);
+        output::print_flat_items(matches.iter().map(|(p, v)| (p.clone(), *v)), &policy);
+    } else {
+        for (path, _) in matches {
+            println!("{}", path);
+        }
+    }
+}
+
+/// Check a document against the JSON Schema in `schema_file`, printing
+/// each violation as `PATH: MESSAGE`, or as a GitHub Actions annotation
+/// with `report` `github`, before failing.
+fn validate(value: &crate::yaml::Value, schema_file: &str, report: &str) -> Result<(), CliError> {
+    let schema = crate::yaml::JsonSchema::from_file(schema_file)?;
+    let violations = schema.validate(value)?;
+    if violations.is_empty() {
+        return Ok(());
+    }
+    for violation in &violations {
+        match report {
+            "github" => println!(
+                "{}",
+                report::github_error(&violation.path, &violation.message)
+            ),
+            _ => println!("{}: {}", violation.path, violation.message),
+        }
+    }
+    Err(crate::yaml::Error::type_error(format!(
+        "document doesn't match the schema '{}', {} violation(s) found.",
+        schema_file,
+        violations.len()
+    ))
+    .into())
+}
+
+/// Check the value output by `get-value --into-json-schema-type`.
+fn check_value_type(
+    value: &crate::yaml::Value,
+    expected: Option<&str>,
+    path: Option<&str>,
+) -> Result<(), CliError> {
+    if let Some(expected) = expected {
+        let type_name = crate::yaml::value_to_type_name(value);
+        crate::yaml::check_json_schema_type(type_name, expected, path.unwrap_or_default())?;
+    }
+    Ok(())
+}
+
+/// Parse the `--schema` option of `get-type`.
+fn parse_schema(schema: Option<&str>) -> Result<Option<crate::yaml::Schema>, CliError> {
+    Ok(schema.map(str::parse).transpose()?)
+}
+
+/// Value of the raw text of a scalar (`None` for a mapping or sequence):
+/// the timestamp written with `--date-format` if given, else the boolean
+/// of `--as-bool` with the `--truthy` and `--falsy` words.
+fn converted_scalar(
+    text: Option<String>,
+    type_name: &str,
+    path: Option<&str>,
+    date_format: Option<&str>,
+    opts: &crate::yaml::Options,
+) -> Result<crate::yaml::Value, CliError> {
+    let Some(text) = text else {
+        let option = match date_format {
+            Some(_) => "--date-format",
+            None => "--as-bool",
+        };
+        return Err(crate::yaml::Error::type_error(format!(
+            "get-value {} does not support '{}' type. Please provide or select a scalar.",
+            option, type_name
+        ))
+        .into());
+    };
+    let path = path.unwrap_or_default();
+    Ok(match date_format {
+        Some(format) => crate::yaml::format_timestamp(&text, format, path)?,
+        None => crate::yaml::Value::Bool(opts.truthiness.to_bool(&text, path)?),
+    })
+}
+
+/// Write the decoded bytes of the base64 text of a `!!binary` scalar,
+/// `None` for a mapping or sequence.
+fn write_binary(text: Option<String>, type_name: &str, path: Option<&str>) -> Result<(), CliError> {
+    use std::io::Write;
+
+    let Some(text) = text else {
+        return Err(crate::yaml::Error::type_error(format!(
+            "get-value --binary does not support '{}' type. Please provide or select a !!binary scalar.",
+            type_name
+        ))
+        .into());
+    };
+    let bytes = crate::yaml::decode_binary(&text, path.unwrap_or_default())?;
+    std::io::stdout().write_all(&bytes)?;
+    Ok(())
+}
+
+/// Get the output and the value of a `get-value` DEFAULT argument.
+///
+/// DEFAULT is output as is, unless `--default-yaml` is given: it is then
+/// parsed and output like a value found in the document.
+fn get_value_default(
+    default: &str,
+    default_yaml: bool,
+    yaml_mode: bool,
+    null_output: Option<&str>,
+) -> Result<(String, crate::yaml::Value), CliError> {
+    if !default_yaml {
+        return Ok((
+            default.to_string(),
+            crate::yaml::Value::String(default.to_string()),
+        ));
     }
+    let value = crate::yaml::parse_value(default, true)?;
+    Ok((output_value(&value, yaml_mode, null_output)?, value))
 }
 
 fn run_single(
     args: Vec<String>,
     value: crate::yaml::Value,
+    state: &mut ChainState,
     is_last: bool,
     setup_logging: bool,
     multi_doc_yaml: bool,
-) -> Result<crate::yaml::Value, String> {
-    let cli = def::Args::try_parse_from(args).str_err()?;
+) -> Result<crate::yaml::Value, CliError> {
+    let cli = def::Args::try_parse_group(args)?;
+    let opts = &state.options;
+    let null_output = state.null_output.clone();
+    let null_output = null_output.as_deref();
 
     if setup_logging {
         setup_logging_and_colors(&cli)?;
@@ -593,18 +2479,57 @@ fn run_single(
 
     // Handle iteration actions (Keys/Keys0, Values/Values0, etc.) uniformly
     if let Some(action) = &cli.action {
-        if let Some(iter_action) = normalize_iter_action(action, yaml_mode) {
+        if let Some(iter_action) = normalize_iter_action(action, yaml_mode, null_output) {
+            // Nothing follows a final get-values, its items are printed
+            // as they are found instead of being copied first
+            if is_last && matches!(iter_action.kind, IterKind::GetValues) {
+                let items = crate::yaml::get_values_iter(iter_action.path, &value, opts)?;
+                output::print_items(items, &iter_action.policy);
+                return Ok(value);
+            }
             let result = match iter_action.kind {
-                IterKind::Keys => crate::yaml::keys(iter_action.path, &value)?,
-                IterKind::Values => crate::yaml::values(iter_action.path, &value)?,
-                IterKind::KeyValues => crate::yaml::key_values(iter_action.path, &value)?,
-                IterKind::GetValues => crate::yaml::get_values(iter_action.path, &value)?,
+                IterKind::Keys => crate::yaml::keys(iter_action.path, &value, opts)?,
+                IterKind::Values => crate::yaml::values(iter_action.path, &value, opts)?,
+                IterKind::KeyValues => crate::yaml::key_values(iter_action.path, &value, opts)?,
+                IterKind::KeyValuesRecursive => {
+                    crate::yaml::key_values_recursive(iter_action.path, &value, opts)?
+                }
+                IterKind::GetValues => crate::yaml::get_values(iter_action.path, &value, opts)?,
+                IterKind::Types => crate::yaml::get_types(iter_action.path, &value, opts)?,
+                IterKind::Flatten => crate::yaml::flatten(iter_action.path, &value, opts)?,
             };
             if is_last {
-                if let crate::yaml::Value::Sequence(seq) = &result {
-                    output::print_items(seq.iter(), &iter_action.policy);
+                match &result {
+                    // key-values: keys and values alternate
+                    crate::yaml::Value::Sequence(seq)
+                        if matches!(
+                            iter_action.kind,
+                            IterKind::KeyValues | IterKind::KeyValuesRecursive
+                        ) =>
+                    {
+                        let pairs = seq.chunks(2).map(|kv| (output::MappingKey(&kv[0]), &kv[1]));
+                        output::print_kv_items(pairs, &iter_action.policy);
+                    }
+                    crate::yaml::Value::Sequence(seq)
+                        if matches!(iter_action.kind, IterKind::Keys) =>
+                    {
+                        output::print_items(
+                            seq.iter().map(output::MappingKey),
+                            &iter_action.policy,
+                        );
+                    }
+                    crate::yaml::Value::Sequence(seq) => {
+                        output::print_items(seq.iter(), &iter_action.policy);
+                    }
+                    // flatten: mapping of full paths to leaves
+                    crate::yaml::Value::Mapping(map) => {
+                        let leaves = map.iter().map(|(k, v)| (crate::yaml::serialize_raw(k), v));
+                        output::print_flat_items(leaves, &iter_action.policy);
+                    }
+                    _ => {}
                 }
             }
+            state.leave_document();
             return Ok(result);
         }
     }
@@ -613,16 +2538,72 @@ fn run_single(
         Some(def::Actions::GetValue {
             path,
             default,
+            default_yaml,
+            into_json_schema_type,
             yaml,
-            line_buffer: _,
+            resolve_aliases: _,
+            keep_aliases,
+            fail_on_null,
+            binary,
+            as_bool,
+            date_format,
+            ..
         }) => {
             let yaml_mode = yaml_mode || *yaml;
             let path = path.as_ref().map(|s| s.as_str());
 
-            match crate::yaml::get_value(path, &value) {
+            match crate::yaml::get_value(path, &value, opts) {
+                Ok(crate::yaml::Value::Null) if *fail_on_null => Err(null_error(path)),
                 Ok(result) => {
-                    if is_last {
-                        let output = output_value(&result, yaml_mode)?;
+                    check_value_type(&result, into_json_schema_type.as_deref(), path)?;
+                    let result = if *as_bool || date_format.is_some() {
+                        let text = match crate::yaml::InnerValue::inner(&result) {
+                            crate::yaml::Value::Mapping(_) | crate::yaml::Value::Sequence(_) => {
+                                None
+                            }
+                            scalar => Some(
+                                state
+                                    .document
+                                    .as_ref()
+                                    .and_then(|doc| {
+                                        crate::yaml::source_text(doc, path, &result, opts)
+                                    })
+                                    .unwrap_or_else(|| crate::yaml::serialize_raw(scalar)),
+                            ),
+                        };
+                        let type_name = crate::yaml::value_to_type_name(&result);
+                        converted_scalar(text, type_name, path, date_format.as_deref(), opts)?
+                    } else {
+                        result
+                    };
+                    // Later actions work on the selected value, not on the
+                    // document, and keep the aliases written in it
+                    let aliases = match crate::yaml::get_at_path(&value, path, opts) {
+                        Ok(selected) => state.aliases.select(&value, selected),
+                        Err(_) => crate::yaml::SourceAliases::default(),
+                    };
+                    state.leave_document();
+                    state.aliases = aliases;
+                    if is_last && *binary {
+                        let text = match crate::yaml::InnerValue::inner(&result) {
+                            crate::yaml::Value::Mapping(_) | crate::yaml::Value::Sequence(_) => {
+                                None
+                            }
+                            scalar => Some(crate::yaml::serialize_raw(scalar)),
+                        };
+                        write_binary(text, crate::yaml::value_to_type_name(&result), path)?;
+                    } else if is_last {
+                        let output = match &result {
+                            crate::yaml::Value::Mapping(_) | crate::yaml::Value::Sequence(_)
+                                if *keep_aliases =>
+                            {
+                                crate::yaml::serialize_aliased(&result, &state.aliases)?
+                            }
+                            crate::yaml::Value::Mapping(_) | crate::yaml::Value::Sequence(_) => {
+                                state.serialize(&result)?
+                            }
+                            _ => output_value(&result, yaml_mode, null_output)?,
+                        };
                         print!("{}", output);
                         // Ensure output ends with newline for proper multi-doc YAML separation
                         if multi_doc_yaml && !output.ends_with('\n') {
@@ -631,48 +2612,223 @@ fn run_single(
                     }
                     Ok(result)
                 }
-                Err(crate::yaml::Error::Path(e)) => {
+                Err(e @ crate::yaml::Error::Path { .. }) => {
                     if let Some(default_val) = default {
+                        let (output, result) =
+                            get_value_default(default_val, *default_yaml, yaml_mode, null_output)?;
+                        check_value_type(&result, into_json_schema_type.as_deref(), path)?;
+                        state.leave_document();
                         if is_last {
-                            print!("{}", default_val);
-                            if multi_doc_yaml && !default_val.ends_with('\n') {
+                            print!("{}", output);
+                            if multi_doc_yaml && !output.ends_with('\n') {
                                 println!();
                             }
                         }
-                        return Ok(crate::yaml::Value::String(default_val.clone()));
+                        return Ok(result);
                     }
-                    Err(e)
+                    Err(e.into())
                 }
-                Err(e) => Err(e.to_string()),
+                Err(e) => Err(e.into()),
             }
         }
 
-        Some(def::Actions::GetType { path }) => {
+        Some(def::Actions::GetType { path, schema, .. }) => {
             let path = path.as_ref().map(|s| s.as_str());
-            let result = crate::yaml::get_type(path, &value)?;
+            let schema = parse_schema(schema.as_deref())?;
+            let result = crate::yaml::get_type(path, &value, schema, opts)?;
             if is_last {
                 println!("{}", crate::yaml::serialize_raw(&result));
             }
             Ok(result)
         }
 
-        Some(def::Actions::GetLength { path }) => {
+        Some(def::Actions::GetLength { path, .. }) => {
             let path = path.as_ref().map(|s| s.as_str());
-            let result = crate::yaml::get_length(path, &value)?;
+            let result = crate::yaml::get_length(path, &value, opts)?;
             if is_last {
                 println!("{}", crate::yaml::serialize_raw(&result));
             }
             Ok(result)
         }
 
+        Some(def::Actions::Export { path, prefix, .. }) => {
+            let path = path.as_ref().map(|s| s.as_str());
+            let exports = crate::yaml::export(path, &value, prefix, opts)?;
+            if is_last {
+                print!("{}", exports);
+            }
+            Ok(crate::yaml::Value::String(exports))
+        }
+
+        Some(def::Actions::ToDotenv {
+            path, separator, ..
+        }) => {
+            let path = path.as_ref().map(|s| s.as_str());
+            let lines = crate::yaml::to_dotenv(path, &value, separator, opts)?;
+            if is_last {
+                print!("{}", lines);
+            }
+            Ok(crate::yaml::Value::String(lines))
+        }
+
+        Some(def::Actions::Validate { schema, report }) => {
+            // A valid document goes on to the next action unchanged
+            validate(&value, schema, report)?;
+            Ok(value)
+        }
+
+        Some(def::Actions::Render { template }) => {
+            let text = crate::yaml::Template::from_file(template)?.render(&value, opts)?;
+            if is_last {
+                print!("{}", text);
+            }
+            Ok(crate::yaml::Value::String(text))
+        }
+
+        Some(def::Actions::Explore { path, .. }) => {
+            if is_last {
+                explore(path.as_deref(), &value, opts)?;
+            }
+            Ok(value)
+        }
+
+        // Later actions work on the only result of the filter
+        Some(def::Actions::Filter {
+            filter,
+            yaml,
+            arg,
+            argjson,
+        }) => {
+            let vars = crate::yaml::Vars::new(arg, argjson)?;
+            let results = crate::yaml::JqFilter::parse_with_vars(filter, &vars)?.run(&value)?;
+            state.leave_document();
+            if is_last {
+                let policy =
+                    output::OutputPolicy::newline(yaml_mode || *yaml).with_null_output(null_output);
+                output::print_items(results.iter(), &policy);
+                return Ok(crate::yaml::Value::Sequence(results));
+            }
+            match <[_; 1]>::try_from(results) {
+                Ok([result]) => Ok(result),
+                Err(results) => Err(format!(
+                    "filter '{}' gave {} results, the next action needs one: collect them with '[...]'",
+                    filter,
+                    results.len()
+                )
+                .into()),
+            }
+        }
+
+        // Later actions work on the selected elements
+        Some(def::Actions::SelectWhere {
+            path, condition, ..
+        }) => {
+            let selected = crate::yaml::select_where(Some(path), condition, &value, opts)?;
+            if is_last {
+                println!("{}", crate::yaml::serialize(&selected)?);
+            }
+            state.leave_document();
+            Ok(selected)
+        }
+
+        // Later actions work on the aggregated number
+        Some(
+            action @ (def::Actions::Sum { .. }
+            | def::Actions::Min { .. }
+            | def::Actions::Max { .. }
+            | def::Actions::Avg { .. }
+            | def::Actions::CountWhere { .. }),
+        ) => {
+            let result = aggregate(action, &value, opts)?;
+            if is_last {
+                println!("{}", crate::yaml::serialize_raw(&result));
+            }
+            state.leave_document();
+            Ok(result)
+        }
+
+        // Mapping of the paths found to their values, as flatten gives
+        Some(action @ def::Actions::Search { with_values, .. }) => {
+            let matches = search(action, &value, opts)?;
+            if is_last {
+                print_search(&matches, *with_values, yaml_mode, null_output);
+            }
+            let result = matches
+                .into_iter()
+                .map(|(path, v)| (crate::yaml::Value::String(path), v.clone()))
+                .collect();
+            state.leave_document();
+            Ok(crate::yaml::Value::Mapping(result))
+        }
+
+        // Anchors were found in the input document, before it was read
+        Some(def::Actions::ListAnchors) => {
+            let anchors = anchors_mapping(&state.anchors);
+            if is_last {
+                println!("{}", crate::yaml::serialize(&anchors)?);
+            }
+            state.leave_document();
+            Ok(anchors)
+        }
+
+        Some(def::Actions::GetAnchor { name, yaml }) => {
+            let result = crate::yaml::get_value(anchor_path(&state.anchors, name)?, &value, opts)?;
+            state.leave_document();
+            if is_last {
+                let output = match &result {
+                    crate::yaml::Value::Mapping(_) | crate::yaml::Value::Sequence(_) => {
+                        state.serialize(&result)?
+                    }
+                    _ => output_value(&result, yaml_mode || *yaml, null_output)?,
+                };
+                print!("{}", output);
+                if multi_doc_yaml && !output.ends_with('\n') {
+                    println!();
+                }
+            }
+            Ok(result)
+        }
+
         Some(def::Actions::Apply {
             overlays,
             merge_policy,
+            url_timeout,
+            strict_tags,
+            preview: _,
+        }) => {
+            // The base document is the input of the chain, from stdin or `--file`
+            let result = apply_overlays(
+                overlays,
+                merge_policy.as_ref(),
+                *url_timeout,
+                *strict_tags,
+                value,
+                state,
+            )?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Merge {
+            files,
+            merge_policy,
+            url_timeout,
+            strict_tags,
+            preview: _,
         }) => {
-            let policies = crate::yaml::parse_merge_policies(merge_policy.as_ref())?;
-            let result = crate::yaml::apply(overlays, &policies, value)?;
+            // The first file was read as the input document of the chain
+            let result = apply_overlays(
+                &files[1..],
+                merge_policy.as_ref(),
+                *url_timeout,
+                *strict_tags,
+                value,
+                state,
+            )?;
             if is_last {
-                println!("{}", crate::yaml::serialize(&result)?);
+                println!("{}", state.serialize(&result)?);
             }
             Ok(result)
         }
@@ -681,23 +2837,248 @@ fn run_single(
             key,
             value: val_str,
             yaml,
+            from,
+            from_file,
+            file,
+            if_missing,
+            if_equals,
+            keep_tag,
+            tag,
+            binary,
+            arg,
+            argjson,
+            preview: _,
+            ..
         }) => {
+            let vars = crate::yaml::Vars::new(arg, argjson)?;
+            let current = crate::yaml::get_value(Some(key), &value, opts)
+                .map(|current| crate::yaml::serialize_raw(&current));
+            if !set_value_allowed(*if_missing, if_equals.as_deref(), current)? {
+                state.skipped = true;
+                if is_last {
+                    println!("{}", state.serialize(&value)?);
+                }
+                return Ok(value);
+            }
+            crate::yaml::check_protected(&value, &state.protected, key)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), key)?;
+            let new_value = match (val_str, from, file, from_file) {
+                (Some(val_str), _, _, _) if *binary => {
+                    crate::yaml::binary_value(value_bytes(val_str, state.stdin_values)?)
+                }
+                (Some(val_str), _, _, _) if !vars.is_empty() => {
+                    vars.resolve(value_text(val_str, state.stdin_values)?, *yaml)?
+                }
+                (Some(val_str), _, _, _) if val_str == "-" && state.stdin_values => {
+                    from_file_value(val_str, false, *yaml, None, true)?
+                }
+                (Some(val_str), _, _, _) => {
+                    crate::yaml::parse_value(value_text(val_str, state.stdin_values)?, *yaml)?
+                }
+                (None, Some(from), Some(file), _) => crate::yaml::get_value_from_file(
+                    Some(from),
+                    &in_base_dir(state.base_dir.as_deref(), file),
+                    opts,
+                )?,
+                (None, Some(from), None, _) => crate::yaml::get_value(Some(from), &value, opts)?,
+                (None, None, _, Some(from_file)) => from_file_value(
+                    from_file,
+                    *binary,
+                    *yaml,
+                    state.base_dir.as_deref(),
+                    state.stdin_values,
+                )?,
+                (None, None, _, None) => unreachable!("clap requires VALUE, --from or --from-file"),
+            };
+            let replaced_tag = match crate::yaml::get_value(Some(key), &value, opts) {
+                Ok(crate::yaml::Value::Tagged(t)) if *keep_tag => Some(t.tag),
+                _ => None,
+            };
+            let new_value = crate::yaml::tag_new_value(new_value, tag.as_deref(), replaced_tag);
+            let result = crate::yaml::set_value(key, new_value, value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::SetValues {
+            assignments,
+            yaml,
+            from,
+        }) => {
+            let mut result = value;
+            let assignments =
+                set_values_assignments(assignments, from.as_deref(), state.base_dir.as_deref())?;
+            for (key, val_str) in assignments {
+                crate::yaml::check_protected(&result, &state.protected, &key)?;
+                crate::yaml::check_allowed(&result, state.allowed.as_ref(), &key)?;
+                let new_value = crate::yaml::parse_value(&val_str, *yaml)?;
+                result = crate::yaml::set_value(&key, new_value, result, opts)?;
+            }
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(update @ def::Actions::Update { key: Some(key), .. }) => {
+            let op = parse_update_op(update)?;
+            crate::yaml::check_protected(&value, &state.protected, key)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), key)?;
+            let result = crate::yaml::update(key, &op, value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Map { key, expr, .. }) => {
+            let expr = crate::yaml::Expr::parse(expr)?;
+            crate::yaml::check_protected(&value, &state.protected, key)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), key)?;
+            let result = crate::yaml::map(key, &expr, value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(
+            action @ (def::Actions::Sort { key: Some(key), .. }
+            | def::Actions::Unique { key: Some(key), .. }
+            | def::Actions::Reverse { key: Some(key), .. }),
+        ) => {
+            crate::yaml::check_protected(&value, &state.protected, key)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), key)?;
+            let result = crate::yaml::reorder(key, &seq_op(action), value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Ensure {
+            key: Some(key),
+            kind,
+            default,
+            ..
+        }) => {
+            let default = crate::yaml::ensure_default(kind.as_deref(), default.as_deref())?;
+            crate::yaml::check_protected(&value, &state.protected, key)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), key)?;
+            let result = crate::yaml::ensure(key, default, value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Del {
+            keys,
+            ignore_missing,
+            preview: _,
+            ..
+        }) => {
+            let mut result = value;
+            for key in &crate::yaml::deletion_paths(&result, keys) {
+                if *ignore_missing && crate::yaml::get_at_path(&result, Some(key), opts).is_err() {
+                    continue;
+                }
+                crate::yaml::check_protected(&result, &state.protected, key)?;
+                crate::yaml::check_allowed(&result, state.allowed.as_ref(), key)?;
+                result = crate::yaml::del(key, result, opts)?;
+            }
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Append {
+            key,
+            value: val_str,
+            yaml,
+            ..
+        }) => {
+            crate::yaml::check_protected(&value, &state.protected, key)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), key)?;
+            let new_value = crate::yaml::parse_value(val_str, *yaml)?;
+            let result = crate::yaml::insert(key, None, new_value, value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Insert {
+            key,
+            index,
+            value: val_str,
+            yaml,
+            ..
+        }) => {
+            crate::yaml::check_protected(&value, &state.protected, key)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), key)?;
             let new_value = crate::yaml::parse_value(val_str, *yaml)?;
-            let result = crate::yaml::set_value(key, new_value, value)?;
+            let result = crate::yaml::insert(key, Some(index), new_value, value, opts)?;
             if is_last {
-                println!("{}", crate::yaml::serialize(&result)?);
+                println!("{}", state.serialize(&result)?);
             }
             Ok(result)
         }
 
-        Some(def::Actions::Del { key }) => {
-            let result = crate::yaml::del(key, value)?;
+        Some(def::Actions::RenameKey { key, new_name, .. }) => {
+            let renamed = crate::yaml::sibling_path(key, new_name);
+            for path in [key, &renamed] {
+                crate::yaml::check_protected(&value, &state.protected, path)?;
+                crate::yaml::check_allowed(&value, state.allowed.as_ref(), path)?;
+            }
+            let result = crate::yaml::rename_key(key, new_name, value, opts)?;
             if is_last {
-                println!("{}", crate::yaml::serialize(&result)?);
+                println!("{}", state.serialize(&result)?);
             }
             Ok(result)
         }
 
+        Some(def::Actions::Move { src, dst, .. }) => {
+            for path in [src, dst] {
+                crate::yaml::check_protected(&value, &state.protected, path)?;
+                crate::yaml::check_allowed(&value, state.allowed.as_ref(), path)?;
+            }
+            let result = crate::yaml::move_value(src, dst, value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Copy { src, dst, .. }) => {
+            crate::yaml::check_protected(&value, &state.protected, dst)?;
+            crate::yaml::check_allowed(&value, state.allowed.as_ref(), dst)?;
+            let result = crate::yaml::copy_value(src, dst, value, opts)?;
+            if is_last {
+                println!("{}", state.serialize(&result)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Annotate {
+            managed_by,
+            sources,
+        }) => {
+            // Comments are not kept in values, the header is only output
+            if is_last {
+                let timestamp = crate::yaml::annotation_timestamp(state.deterministic)?;
+                let text = format!("{}\n", state.serialize(&value)?);
+                print!(
+                    "{}",
+                    crate::yaml::annotate(&text, managed_by, sources, &timestamp)
+                );
+            }
+            Ok(value)
+        }
+
         // Iteration actions are handled before this match
         Some(def::Actions::Keys { .. })
         | Some(def::Actions::Keys0 { .. })
@@ -706,10 +3087,74 @@ fn run_single(
         | Some(def::Actions::KeyValues { .. })
         | Some(def::Actions::KeyValues0 { .. })
         | Some(def::Actions::GetValues { .. })
-        | Some(def::Actions::GetValues0 { .. }) => {
+        | Some(def::Actions::GetValues0 { .. })
+        | Some(def::Actions::GetTypes { .. })
+        | Some(def::Actions::GetTypes0 { .. })
+        | Some(def::Actions::Flatten { .. })
+        | Some(def::Actions::Flatten0 { .. }) => {
             unreachable!("Iteration actions handled above")
         }
 
-        None => Err("Missing action".to_string()),
+        Some(def::Actions::Save { name }) => {
+            state.registers.insert(name.clone(), value.clone());
+            if is_last {
+                println!("{}", state.output(&value, yaml_mode)?);
+            }
+            Ok(value)
+        }
+
+        Some(def::Actions::Load { name, path, .. }) => {
+            let saved = state
+                .registers
+                .get(name)
+                .cloned()
+                .ok_or_else(|| format!("no value saved under name '{}'", name))?;
+            let result = match path {
+                Some(path) => {
+                    crate::yaml::check_protected(&value, &state.protected, path)?;
+                    crate::yaml::check_allowed(&value, state.allowed.as_ref(), path)?;
+                    crate::yaml::set_value(path, saved, value, opts)?
+                }
+                None => {
+                    crate::yaml::check_allowed_changes(&value, &saved, state.allowed.as_ref())?;
+                    state.leave_document();
+                    saved
+                }
+            };
+            if is_last {
+                println!("{}", state.output(&result, yaml_mode)?);
+            }
+            Ok(result)
+        }
+
+        Some(def::Actions::Unflatten) => {
+            // The structure was built from stdin before running the chain
+            if is_last {
+                println!("{}", state.output(&value, yaml_mode)?);
+            }
+            Ok(value)
+        }
+
+        Some(
+            def::Actions::Update { key: None, .. }
+            | def::Actions::Sort { key: None, .. }
+            | def::Actions::Unique { key: None, .. }
+            | def::Actions::Reverse { key: None, .. }
+            | def::Actions::Ensure { key: None, .. },
+        ) => unreachable!("clap requires KEY or --path"),
+
+        Some(def::Actions::ParentPath { .. })
+        | Some(def::Actions::JoinPath { .. })
+        | Some(def::Actions::RelativePath { .. })
+        | Some(def::Actions::CheckSyntax { .. })
+        | Some(def::Actions::TestSuite { .. })
+        | Some(def::Actions::LspLite { .. })
+        | Some(def::Actions::Tags)
+        | Some(def::Actions::Features)
+        | Some(def::Actions::Introspect { .. }) => {
+            unreachable!("Standalone actions handled before reading input")
+        }
+
+        None => Err("Missing action".into()),
     }
 }
@@ -3,8 +3,49 @@
 //! This module provides a trait-based abstraction for outputting YAML values,
 //! whether they come from zero-copy (`ValueRef`) or owned (`Value`) sources.
 
+use super::error::CliError;
 use crate::yaml;
 
+// =============================================================================
+// Raw Nulls
+// =============================================================================
+
+/// Raw output of a value, nulls written as `null_output` if given
+/// (`--null-output`) instead of an empty string.
+pub fn raw(value: &yaml::Value, null_output: Option<&str>) -> String {
+    match (value, null_output) {
+        (yaml::Value::Null, Some(text)) => text.to_string(),
+        _ => yaml::serialize_raw(value),
+    }
+}
+
+/// Raw output of a value (zero-copy), nulls written as `null_output` if
+/// given.
+pub fn raw_ref(value: fyaml::ValueRef<'_>, null_output: Option<&str>) -> String {
+    match null_output {
+        Some(text) if value.is_null() => text.to_string(),
+        _ => yaml::serialize_raw_ref(value),
+    }
+}
+
+/// Single-line YAML flow text of a value (see `yaml::key_text`), nulls
+/// written as `null_output` if given.
+pub fn flow(value: &yaml::Value, null_output: Option<&str>) -> String {
+    match (value, null_output) {
+        (yaml::Value::Null, Some(text)) => text.to_string(),
+        _ => yaml::key_text(value).unwrap_or_else(|_| yaml::serialize_raw(value)),
+    }
+}
+
+/// Single-line YAML flow text of a value (zero-copy), nulls written as
+/// `null_output` if given.
+pub fn flow_ref(value: fyaml::ValueRef<'_>, null_output: Option<&str>) -> String {
+    match yaml::Value::from_node_ref(value.as_node()) {
+        Ok(value) => flow(&value, null_output),
+        Err(_) => raw_ref(value, null_output),
+    }
+}
+
 // =============================================================================
 // YamlOutput Trait
 // =============================================================================
@@ -16,15 +57,20 @@ pub trait YamlOutput {
     /// Serialize to strict YAML format.
     fn to_yaml_string(&self) -> Result<String, yaml::Error>;
 
-    /// Serialize to raw format (unquoted scalars, YAML for complex types).
-    fn to_raw_string(&self) -> String;
+    /// Serialize to raw format (unquoted scalars, YAML for complex types),
+    /// nulls written as `null_output` if given.
+    fn to_raw_string(&self, null_output: Option<&str>) -> String;
+
+    /// Serialize to single-line YAML flow text, which parses back to the
+    /// same scalar, nulls written as `null_output` if given.
+    fn to_flow_string(&self, null_output: Option<&str>) -> String;
 
     /// Output with current policy (yaml_mode determines format).
-    fn format(&self, yaml_mode: bool) -> String {
+    fn format(&self, yaml_mode: bool, null_output: Option<&str>) -> String {
         if yaml_mode {
             self.to_yaml_string().unwrap_or_default()
         } else {
-            self.to_raw_string()
+            self.to_raw_string(null_output)
         }
     }
 }
@@ -34,8 +80,12 @@ impl YamlOutput for fyaml::ValueRef<'_> {
         yaml::serialize_ref(*self)
     }
 
-    fn to_raw_string(&self) -> String {
-        yaml::serialize_raw_ref(*self)
+    fn to_raw_string(&self, null_output: Option<&str>) -> String {
+        raw_ref(*self, null_output)
+    }
+
+    fn to_flow_string(&self, null_output: Option<&str>) -> String {
+        flow_ref(*self, null_output)
     }
 }
 
@@ -44,8 +94,12 @@ impl YamlOutput for yaml::Value {
         yaml::serialize(self)
     }
 
-    fn to_raw_string(&self) -> String {
-        yaml::serialize_raw(self)
+    fn to_raw_string(&self, null_output: Option<&str>) -> String {
+        raw(self, null_output)
+    }
+
+    fn to_flow_string(&self, null_output: Option<&str>) -> String {
+        flow(self, null_output)
     }
 }
 
@@ -54,8 +108,61 @@ impl YamlOutput for &yaml::Value {
         yaml::serialize(self)
     }
 
-    fn to_raw_string(&self) -> String {
-        yaml::serialize_raw(self)
+    fn to_raw_string(&self, null_output: Option<&str>) -> String {
+        raw(self, null_output)
+    }
+
+    fn to_flow_string(&self, null_output: Option<&str>) -> String {
+        flow(self, null_output)
+    }
+}
+
+/// A mapping key: mappings and sequences used as keys are written on a
+/// single line in their canonical form (see `yaml::key_text`), which
+/// parses back to the same key.
+pub struct MappingKey<T>(pub T);
+
+impl YamlOutput for MappingKey<fyaml::ValueRef<'_>> {
+    fn to_yaml_string(&self) -> Result<String, yaml::Error> {
+        if self.0.is_mapping() || self.0.is_sequence() {
+            yaml::key_text(&yaml::Value::from_node_ref(self.0.as_node())?)
+        } else {
+            self.0.to_yaml_string()
+        }
+    }
+
+    fn to_raw_string(&self, null_output: Option<&str>) -> String {
+        if self.0.is_mapping() || self.0.is_sequence() {
+            self.to_yaml_string().unwrap_or_default()
+        } else {
+            self.0.to_raw_string(null_output)
+        }
+    }
+
+    fn to_flow_string(&self, null_output: Option<&str>) -> String {
+        self.0.to_flow_string(null_output)
+    }
+}
+
+impl YamlOutput for MappingKey<&yaml::Value> {
+    fn to_yaml_string(&self) -> Result<String, yaml::Error> {
+        match self.0 {
+            yaml::Value::Mapping(_) | yaml::Value::Sequence(_) => yaml::key_text(self.0),
+            _ => yaml::serialize(self.0),
+        }
+    }
+
+    fn to_raw_string(&self, null_output: Option<&str>) -> String {
+        match self.0 {
+            yaml::Value::Mapping(_) | yaml::Value::Sequence(_) => {
+                self.to_yaml_string().unwrap_or_default()
+            }
+            _ => self.0.to_raw_string(null_output),
+        }
+    }
+
+    fn to_flow_string(&self, null_output: Option<&str>) -> String {
+        self.0.to_flow_string(null_output)
     }
 }
 
@@ -70,6 +177,12 @@ pub struct OutputPolicy {
     pub separator: Separator,
     /// If true, output strict YAML; if false, use raw format for scalars.
     pub yaml_mode: bool,
+    /// Separator between a key and its value, the item separator if `None`.
+    pub kv_separator: Option<String>,
+    /// If true, write items as double-quoted strings, on a single line.
+    pub quote: bool,
+    /// Text of null values in raw output, an empty string if `None`.
+    pub null_output: Option<String>,
 }
 
 /// Type of separator between output items.
@@ -97,6 +210,9 @@ impl OutputPolicy {
         Self {
             separator: Separator::Newline,
             yaml_mode,
+            kv_separator: None,
+            quote: false,
+            null_output: None,
         }
     }
 
@@ -105,8 +221,55 @@ impl OutputPolicy {
         Self {
             separator: Separator::Nul,
             yaml_mode,
+            kv_separator: None,
+            quote: false,
+            null_output: None,
+        }
+    }
+    /// Set how key-value pairs are written.
+    pub fn with_pairs(mut self, kv_separator: Option<&str>, quote: bool) -> Self {
+        self.kv_separator = kv_separator.map(str::to_string);
+        self.quote = quote;
+        self
+    }
+
+    /// Set the text of null values in raw output (`--null-output`).
+    pub fn with_null_output(mut self, null_output: Option<&str>) -> Self {
+        self.null_output = null_output.map(str::to_string);
+        self
+    }
+
+    /// Format an item, quoting it if required.
+    fn format<T: YamlOutput>(&self, item: &T) -> String {
+        let text = item.format(self.yaml_mode, self.null_output.as_deref());
+        if self.quote {
+            double_quote(&text)
+        } else {
+            text
+        }
+    }
+}
+
+/// Write a string as a double-quoted string, valid in both YAML and JSON.
+///
+/// Line breaks and other control characters are escaped, so the result
+/// always holds on a single line.
+fn double_quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
         }
     }
+    quoted.push('"');
+    quoted
 }
 
 // =============================================================================
@@ -117,6 +280,7 @@ impl OutputPolicy {
 pub fn print_items<T: YamlOutput>(iter: impl Iterator<Item = T>, policy: &OutputPolicy) {
     let sep = policy.separator.as_str();
     let yaml_mode = policy.yaml_mode;
+    let null_output = policy.null_output.as_deref();
 
     match policy.separator {
         Separator::Newline => {
@@ -126,7 +290,7 @@ pub fn print_items<T: YamlOutput>(iter: impl Iterator<Item = T>, policy: &Output
                     print!("{}", sep);
                 }
                 first = false;
-                print!("{}", item.format(yaml_mode));
+                print!("{}", item.format(yaml_mode, null_output));
             }
             // Add trailing newline if we printed anything
             if !first {
@@ -135,7 +299,7 @@ pub fn print_items<T: YamlOutput>(iter: impl Iterator<Item = T>, policy: &Output
         }
         Separator::Nul => {
             for item in iter {
-                print!("{}\0", item.format(yaml_mode));
+                print!("{}\0", item.format(yaml_mode, null_output));
             }
         }
     }
@@ -147,7 +311,7 @@ pub fn print_kv_items<K: YamlOutput, V: YamlOutput>(
     policy: &OutputPolicy,
 ) {
     let sep = policy.separator.as_str();
-    let yaml_mode = policy.yaml_mode;
+    let kv_sep = policy.kv_separator.as_deref().unwrap_or(sep);
 
     match policy.separator {
         Separator::Newline => {
@@ -157,9 +321,9 @@ pub fn print_kv_items<K: YamlOutput, V: YamlOutput>(
                     print!("{}", sep);
                 }
                 first = false;
-                print!("{}", k.format(yaml_mode));
-                print!("{}", sep);
-                print!("{}", v.format(yaml_mode));
+                print!("{}", policy.format(&k));
+                print!("{}", kv_sep);
+                print!("{}", policy.format(&v));
             }
             // Add trailing newline if we printed anything
             if !first {
@@ -168,13 +332,34 @@ pub fn print_kv_items<K: YamlOutput, V: YamlOutput>(
         }
         Separator::Nul => {
             for (k, v) in iter {
-                print!("{}\0", k.format(yaml_mode));
-                print!("{}\0", v.format(yaml_mode));
+                print!("{}{}", policy.format(&k), kv_sep);
+                print!("{}\0", policy.format(&v));
             }
         }
     }
 }
 
+/// Print flattened leaves (full path and value) with the given policy.
+///
+/// Uses one `path=value` line per leaf in newline mode, as `unflatten`
+/// reads them, values written as single-line YAML so that multi-line
+/// strings, empty strings and nulls read back the same. Path and value are
+/// NUL-terminated otherwise.
+pub fn print_flat_items<V: YamlOutput>(
+    iter: impl Iterator<Item = (String, V)>,
+    policy: &OutputPolicy,
+) {
+    let yaml_mode = policy.yaml_mode;
+    let null_output = policy.null_output.as_deref();
+
+    for (path, v) in iter {
+        match policy.separator {
+            Separator::Newline => println!("{}={}", path, v.to_flow_string(null_output)),
+            Separator::Nul => print!("{}\0{}\0", path, v.format(yaml_mode, null_output)),
+        }
+    }
+}
+
 /// Print get-values iterator (handles both sequence and mapping cases).
 pub fn print_get_values(iter: yaml::GetValuesIter<'_>, policy: &OutputPolicy) {
     match iter {
@@ -187,6 +372,313 @@ pub fn print_get_values(iter: yaml::GetValuesIter<'_>, policy: &OutputPolicy) {
     }
 }
 
+// =============================================================================
+// Output File
+// =============================================================================
+
+/// Point stdout at `fd`, returning a duplicate of the previous stdout.
+#[cfg(unix)]
+fn redirect_stdout(fd: std::os::unix::io::RawFd) -> std::io::Result<std::os::unix::io::RawFd> {
+    use std::io::Write;
+
+    std::io::stdout().flush().ok();
+    // SAFETY: plain fd duplication, `fd` stays open through fd 1
+    let saved = unsafe { libc::dup(libc::STDOUT_FILENO) };
+    if saved < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    if unsafe { libc::dup2(fd, libc::STDOUT_FILENO) } < 0 {
+        let e = std::io::Error::last_os_error();
+        unsafe { libc::close(saved) };
+        return Err(e);
+    }
+    Ok(saved)
+}
+
+/// Point stdout back at `saved`, a duplicate given by `redirect_stdout`.
+#[cfg(unix)]
+fn restore_stdout(saved: std::os::unix::io::RawFd) {
+    use std::io::Write;
+
+    std::io::stdout().flush().ok();
+    // SAFETY: `saved` is a stdout duplicate owned by the caller
+    unsafe {
+        libc::dup2(saved, libc::STDOUT_FILENO);
+        libc::close(saved);
+    }
+}
+
+/// Redirection of stdout to a file, which is only replaced once the whole
+/// run succeeded.
+///
+/// Output is written to a temporary file next to the target, then renamed
+/// over it, so readers never see a partial file and the input can be the
+/// output file itself. Dropping it without [`OutputFile::commit`] restores
+/// stdout and leaves the target untouched.
+#[cfg(unix)]
+pub struct OutputFile {
+    path: std::path::PathBuf,
+    tmp: std::path::PathBuf,
+    backup: Option<String>,
+    /// Duplicate of the original stdout, `None` once restored
+    saved_stdout: Option<std::os::unix::io::RawFd>,
+}
+
+#[cfg(unix)]
+impl OutputFile {
+    /// Redirect stdout to a temporary file next to `path`.
+    pub fn redirect(path: &str, backup: Option<&str>) -> Result<Self, CliError> {
+        use std::os::unix::io::AsRawFd;
+
+        let path = std::path::PathBuf::from(path);
+        let name = path
+            .file_name()
+            .ok_or_else(|| CliError::io(format!("invalid output file '{}'", path.display())))?;
+        let (tmp, file) = create_tmp_file(&path, &name.to_string_lossy())
+            .map_err(|e| CliError::io(format!("Failed to write '{}': {}", path.display(), e)))?;
+
+        let saved = redirect_stdout(file.as_raw_fd()).map_err(|e| {
+            std::fs::remove_file(&tmp).ok();
+            CliError::io(format!(
+                "Failed to redirect output to '{}': {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        Ok(OutputFile {
+            path,
+            tmp,
+            backup: backup.map(str::to_string),
+            saved_stdout: Some(saved),
+        })
+    }
+
+    /// Restore stdout and replace the target with the output, keeping the
+    /// previous content with the backup suffix if one was given.
+    pub fn commit(mut self) -> Result<(), CliError> {
+        if let Some(saved) = self.saved_stdout.take() {
+            restore_stdout(saved);
+        }
+        let err = |e: std::io::Error| {
+            CliError::io(format!("Failed to write '{}': {}", self.path.display(), e))
+        };
+
+        if let Ok(metadata) = std::fs::metadata(&self.path) {
+            std::fs::set_permissions(&self.tmp, metadata.permissions()).map_err(err)?;
+            if let Some(suffix) = &self.backup {
+                let mut backup = self.path.clone().into_os_string();
+                backup.push(suffix);
+                std::fs::copy(&self.path, &backup).map_err(err)?;
+            }
+        }
+        std::fs::rename(&self.tmp, &self.path).map_err(err)
+    }
+
+    /// Temporary file receiving the output until it is committed.
+    pub fn tmp_path(&self) -> &std::path::Path {
+        &self.tmp
+    }
+}
+
+/// Create a new file next to `path`.
+///
+/// As for [`create_capture_file`], an existing name, possibly a planted
+/// symlink, is never opened: the next one is tried instead. The file is
+/// readable by the user only until commit gives it the permissions of an
+/// existing target; a new target gets the usual ones the umask leaves.
+#[cfg(unix)]
+fn create_tmp_file(
+    path: &std::path::Path,
+    name: &str,
+) -> std::io::Result<(std::path::PathBuf, std::fs::File)> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mode = if path.exists() { 0o600 } else { 0o666 };
+    let mut attempt = 0;
+    loop {
+        let tmp = path.with_file_name(format!(".{}.{}.{}.tmp", name, std::process::id(), attempt));
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(mode)
+            .open(&tmp)
+        {
+            Ok(file) => return Ok((tmp, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        if let Some(saved) = self.saved_stdout.take() {
+            restore_stdout(saved);
+            std::fs::remove_file(&self.tmp).ok();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub enum OutputFile {}
+
+#[cfg(not(unix))]
+impl OutputFile {
+    pub fn redirect(_path: &str, _backup: Option<&str>) -> Result<Self, CliError> {
+        Err("--output is only supported on unix".into())
+    }
+
+    pub fn commit(self) -> Result<(), CliError> {
+        match self {}
+    }
+
+    pub fn tmp_path(&self) -> &std::path::Path {
+        match *self {}
+    }
+}
+
+// =============================================================================
+// Post-processing
+// =============================================================================
+
+/// Capture of stdout, piped through a shell command once the whole run
+/// succeeded (`--post-process`).
+///
+/// Output is kept in an unlinked temporary file rather than streamed to
+/// the command, so shyaml never writes to a pipe the command closed, and a
+/// failed run writes nothing. The command writes to the stdout in place
+/// before the capture, the `--output` file included.
+#[cfg(unix)]
+pub struct PostProcess {
+    command: String,
+    capture: std::fs::File,
+    /// Duplicate of the original stdout, `None` once restored
+    saved_stdout: Option<std::os::unix::io::RawFd>,
+}
+
+#[cfg(unix)]
+impl PostProcess {
+    /// Capture stdout until [`PostProcess::finish`].
+    pub fn capture(command: &str) -> Result<Self, CliError> {
+        use std::os::unix::io::AsRawFd;
+
+        let err = |e: std::io::Error| {
+            CliError::io(format!("Failed to capture output for '{}': {}", command, e))
+        };
+        let (path, capture) = create_capture_file().map_err(err)?;
+        // Only the open file is needed, nothing is left behind
+        std::fs::remove_file(&path).map_err(err)?;
+        let saved = redirect_stdout(capture.as_raw_fd()).map_err(err)?;
+        Ok(PostProcess {
+            command: command.to_string(),
+            capture,
+            saved_stdout: Some(saved),
+        })
+    }
+
+    /// Restore stdout and run the command with the captured output as its
+    /// input.
+    ///
+    /// A command ended by `SIGPIPE` stopped because its reader went away,
+    /// as shyaml itself would have, and doesn't fail the run.
+    pub fn finish(mut self) -> Result<(), CliError> {
+        use std::io::Seek;
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(saved) = self.saved_stdout.take() {
+            restore_stdout(saved);
+        }
+        let err = |e: std::io::Error| {
+            CliError::io(format!(
+                "Failed to run post-process command '{}': {}",
+                self.command, e
+            ))
+        };
+        self.capture.rewind().map_err(err)?;
+        let input = self.capture.try_clone().map_err(err)?;
+        let status = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(input)
+            .status()
+            .map_err(err)?;
+        match (status.code(), status.signal()) {
+            (Some(0), _) => Ok(()),
+            (_, Some(libc::SIGPIPE)) => Ok(()),
+            (Some(code), _) => Err(CliError::new(
+                super::error::ErrorKind::Other,
+                format!(
+                    "post-process command '{}' failed with exit code {}",
+                    self.command, code
+                ),
+            )),
+            (None, _) => Err(CliError::new(
+                super::error::ErrorKind::Other,
+                format!("post-process command '{}' was killed", self.command),
+            )),
+        }
+    }
+}
+
+/// Create a new file in the temporary directory, readable by the user
+/// only.
+///
+/// The file is never opened through an existing path, which another user
+/// could have planted as a symlink: names already taken are skipped.
+#[cfg(unix)]
+fn create_capture_file() -> std::io::Result<(std::path::PathBuf, std::fs::File)> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let mut attempt = 0;
+    loop {
+        let path = std::env::temp_dir().join(format!(
+            ".shyaml-post-process.{}.{}.tmp",
+            std::process::id(),
+            attempt
+        ));
+        match std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)
+        {
+            Ok(file) => return Ok((path, file)),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists && attempt < 100 => {
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for PostProcess {
+    fn drop(&mut self) {
+        if let Some(saved) = self.saved_stdout.take() {
+            restore_stdout(saved);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub enum PostProcess {}
+
+#[cfg(not(unix))]
+impl PostProcess {
+    pub fn capture(_command: &str) -> Result<Self, CliError> {
+        Err("--post-process is only supported on unix".into())
+    }
+
+    pub fn finish(self) -> Result<(), CliError> {
+        match self {}
+    }
+}
+
 // =============================================================================
 // Unit Tests
 // =============================================================================
@@ -212,10 +704,19 @@ mod tests {
         assert!(!policy.yaml_mode);
     }
 
+    #[test]
+    fn test_double_quote_escapes() {
+        assert_eq!(double_quote("a b"), "\"a b\"");
+        assert_eq!(
+            double_quote("say \"hi\"\\\nnext\tline\u{1}"),
+            r#""say \"hi\"\\\nnext\tline\u0001""#
+        );
+    }
+
     #[test]
     fn test_value_yaml_output() {
         let value = yaml::Value::String("hello".to_string());
-        assert_eq!(value.to_raw_string(), "hello");
+        assert_eq!(value.to_raw_string(None), "hello");
         // YAML mode quotes strings
         let yaml_str = value.to_yaml_string().unwrap();
         assert!(yaml_str.contains("hello"));
@@ -224,17 +725,27 @@ mod tests {
     #[test]
     fn test_value_yaml_output_integer() {
         let value = yaml::Value::Number(yaml::Number::Int(42));
-        assert_eq!(value.to_raw_string(), "42");
+        assert_eq!(value.to_raw_string(None), "42");
         assert_eq!(value.to_yaml_string().unwrap().trim(), "42");
     }
 
+    #[test]
+    fn test_raw_null_output() {
+        assert_eq!(raw(&yaml::Value::Null, None), "");
+        assert_eq!(raw(&yaml::Value::Null, Some("null")), "null");
+        let empty = yaml::Value::String(String::new());
+        assert_eq!(raw(&empty, Some("null")), "");
+        // YAML mode is not affected
+        assert_eq!(yaml::Value::Null.format(true, Some("none")).trim(), "null");
+    }
+
     #[test]
     fn test_format_respects_mode() {
         let value = yaml::Value::String("test".to_string());
         // Raw mode returns unquoted
-        assert_eq!(value.format(false), "test");
+        assert_eq!(value.format(false, None), "test");
         // YAML mode may quote/format
-        let yaml_output = value.format(true);
+        let yaml_output = value.format(true, None);
         assert!(yaml_output.contains("test"));
     }
 }
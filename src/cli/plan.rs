@@ -18,42 +18,167 @@ pub enum ExecutionMode {
 
 /// Classification of an action's behavior.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum ActionKind {
-    /// Mutation: modifies the document in place (set-value, del)
+pub(crate) enum ActionKind {
+    /// Mutation: modifies the document in place (set-value, set-values, update, map, ensure, del,
+    /// append, insert, rename-key, move, copy, annotate)
     Mutation,
-    /// ReadOnly: reads but doesn't modify (get-value, get-type, get-length)
+    /// ReadOnly: reads but doesn't modify (get-value, get-type, get-length, export,
+    /// to-dotenv, validate, render, explore, filter, search)
     ReadOnly,
-    /// Derived: produces a different structure than input (keys, values, get-values, key-values)
+    /// Derived: produces a different structure than input (keys, values, get-values, key-values,
+    /// get-types, flatten)
     /// These cannot use DocMode because subsequent commands operate on the derived result.
     Derived,
-    /// Complex: requires full Value processing (apply)
+    /// Complex: requires full Value processing (apply, save, load, unflatten)
     Complex,
+    /// Standalone: does not run on an input document (parent-path, join-path, relative-path,
+    /// check-syntax, test-suite, lsp-lite, tags, features, introspect)
+    Standalone,
 }
 
-impl ActionKind {
-    fn from_action(action: &Actions) -> Self {
-        match action {
-            // Mutations: can use Editor directly
-            Actions::SetValue { .. } | Actions::Del { .. } => ActionKind::Mutation,
-
-            // Read-only: just read from document, can use zero-copy
-            Actions::GetValue { .. } | Actions::GetType { .. } | Actions::GetLength { .. } => {
-                ActionKind::ReadOnly
+/// Define `ActionKind::from_action()` and `ActionKind::of_name()` from the
+/// actions of each kind, given with their name on the command line, so that
+/// both tell the same kind.
+macro_rules! action_kinds {
+    ($($kind:ident: [$($variant:ident = $name:literal),+ $(,)?],)+) => {
+        impl ActionKind {
+            fn from_action(action: &Actions) -> Self {
+                match action {
+                    $($(Actions::$variant { .. })|+ => ActionKind::$kind,)+
+                }
+            }
+
+            /// Kind of the action of this name on the command line.
+            pub(crate) fn of_name(name: &str) -> Option<Self> {
+                match name {
+                    $($($name)|+ => Some(ActionKind::$kind),)+
+                    _ => None,
+                }
             }
+        }
+    };
+}
+
+action_kinds! {
+    // Mutations: can use Editor directly
+    Mutation: [
+        SetValue = "set-value",
+        SetValues = "set-values",
+        Update = "update",
+        Map = "map",
+        Sort = "sort",
+        Unique = "unique",
+        Reverse = "reverse",
+        Ensure = "ensure",
+        Del = "del",
+        Append = "append",
+        Insert = "insert",
+        RenameKey = "rename-key",
+        Move = "move",
+        Copy = "copy",
+        Annotate = "annotate",
+    ],
+    // Read-only: just read from document, can use zero-copy
+    ReadOnly: [
+        GetValue = "get-value",
+        GetType = "get-type",
+        GetLength = "get-length",
+        Export = "export",
+        ToDotenv = "to-dotenv",
+        Validate = "validate",
+        Render = "render",
+        Explore = "explore",
+        Filter = "filter",
+        SelectWhere = "select-where",
+        Sum = "sum",
+        Min = "min",
+        Max = "max",
+        Avg = "avg",
+        CountWhere = "count-where",
+        Search = "search",
+        ListAnchors = "list-anchors",
+        GetAnchor = "get-anchor",
+    ],
+    // Derived: produce a different structure (sequence of keys/values)
+    // The result is a Value, not the original document
+    Derived: [
+        Keys = "keys",
+        Keys0 = "keys-0",
+        GetTypes = "get-types",
+        GetTypes0 = "get-types-0",
+        Values = "values",
+        Values0 = "values-0",
+        KeyValues = "key-values",
+        KeyValues0 = "key-values-0",
+        GetValues = "get-values",
+        GetValues0 = "get-values-0",
+        Flatten = "flatten",
+        Flatten0 = "flatten-0",
+    ],
+    // Complex: requires full Value-based processing
+    Complex: [
+        Apply = "apply",
+        Merge = "merge",
+        Save = "save",
+        Load = "load",
+        Unflatten = "unflatten",
+    ],
+    // Standalone: path helpers working on their arguments only, syntax
+    // checks parsing their own inputs, test suites reporting on all
+    // documents at once, editor requests carrying their own text and
+    // build information and command line descriptions
+    Standalone: [
+        ParentPath = "parent-path",
+        JoinPath = "join-path",
+        RelativePath = "relative-path",
+        CheckSyntax = "check-syntax",
+        TestSuite = "test-suite",
+        LspLite = "lsp-lite",
+        Tags = "tags",
+        Features = "features",
+        Introspect = "introspect",
+    ],
+}
 
-            // Derived: produce a different structure (sequence of keys/values)
-            // The result is a Value, not the original document
-            Actions::Keys { .. }
-            | Actions::Keys0 { .. }
-            | Actions::Values { .. }
-            | Actions::Values0 { .. }
-            | Actions::KeyValues { .. }
-            | Actions::KeyValues0 { .. }
-            | Actions::GetValues { .. }
-            | Actions::GetValues0 { .. } => ActionKind::Derived,
-
-            // Complex: requires full Value-based processing
-            Actions::Apply { .. } => ActionKind::Complex,
+impl ActionKind {
+    /// Every kind, in the order they are described.
+    pub(crate) const ALL: [ActionKind; 5] = [
+        ActionKind::Mutation,
+        ActionKind::ReadOnly,
+        ActionKind::Derived,
+        ActionKind::Complex,
+        ActionKind::Standalone,
+    ];
+
+    /// Name of the kind, as `introspect` tells it.
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ActionKind::Mutation => "mutation",
+            ActionKind::ReadOnly => "read-only",
+            ActionKind::Derived => "derived",
+            ActionKind::Complex => "complex",
+            ActionKind::Standalone => "standalone",
+        }
+    }
+
+    /// How actions of the kind take part in a chain.
+    pub(crate) fn description(self) -> &'static str {
+        match self {
+            ActionKind::Mutation => {
+                "modifies the document, which is printed at the end of the chain, or read by the next action"
+            }
+            ActionKind::ReadOnly => {
+                "reads a value of the document, which is printed at the end of the chain, or read by the next action in place of the document"
+            }
+            ActionKind::Derived => {
+                "builds items from the document (keys, values, types or leaves), which are printed at the end of the chain, or read as a sequence by the next action"
+            }
+            ActionKind::Complex => {
+                "works on the whole document as a value (merges, saved values, unflattening), which is printed at the end of the chain, or read by the next action"
+            }
+            ActionKind::Standalone => {
+                "does not read an input document, and can't be chained"
+            }
         }
     }
 }
@@ -64,8 +189,9 @@ impl ActionKind {
 /// - Single read-only or mutation: DocMode
 /// - Pure mutation chain: DocMode  
 /// - Mutations followed by a single read-only at end: DocMode
-/// - Any derived action: ValueMode (because next action operates on derived result)
-/// - Any complex action (apply): ValueMode
+/// - Mutations followed by get-values at end: DocMode (items are streamed)
+/// - Any other derived action: ValueMode (because next action operates on derived result)
+/// - Any complex action (apply, save, load, unflatten): ValueMode
 /// - Mixed mutations with non-final read-only: check if read-only produces a value for next action
 ///
 /// # Examples:
@@ -76,6 +202,7 @@ impl ActionKind {
 /// - `keys foo ; get-value 0` -> ValueMode (derived action not at end)
 /// - `apply overlay.yaml` -> ValueMode (complex)
 /// - `set-value a 1 ; keys foo` -> ValueMode (derived in chain needs Value output)
+/// - `set-value a 1 ; get-values foo` -> DocMode (final get-values streams its items)
 pub fn analyze_chain(actions: &[Option<Actions>]) -> ExecutionMode {
     if actions.is_empty() {
         return ExecutionMode::DocMode;
@@ -86,9 +213,10 @@ pub fn analyze_chain(actions: &[Option<Actions>]) -> ExecutionMode {
         if let Some(action) = &actions[0] {
             let kind = ActionKind::from_action(action);
             return match kind {
-                ActionKind::Mutation | ActionKind::ReadOnly | ActionKind::Derived => {
-                    ExecutionMode::DocMode
-                }
+                ActionKind::Mutation
+                | ActionKind::ReadOnly
+                | ActionKind::Derived
+                | ActionKind::Standalone => ExecutionMode::DocMode,
                 ActionKind::Complex => ExecutionMode::ValueMode,
             };
         }
@@ -120,6 +248,16 @@ pub fn analyze_chain(actions: &[Option<Actions>]) -> ExecutionMode {
                 }
                 // Final read-only: can use zero-copy from Document after mutations
             }
+            ActionKind::Derived
+                if is_last
+                    && matches!(
+                        action,
+                        Actions::GetValues { .. } | Actions::GetValues0 { .. }
+                    ) =>
+            {
+                // Final get-values: items are streamed from the Document after
+                // mutations, a huge sequence is never copied
+            }
             ActionKind::Derived => {
                 // Derived actions produce a different structure (e.g., sequence of keys).
                 // Even if last, the output needs Value-based serialization.
@@ -130,6 +268,10 @@ pub fn analyze_chain(actions: &[Option<Actions>]) -> ExecutionMode {
                 // Apply requires full Value processing
                 return ExecutionMode::ValueMode;
             }
+            ActionKind::Standalone => {
+                // Never part of a chain: rejected before planning
+                continue;
+            }
         }
     }
 
@@ -141,6 +283,19 @@ pub fn is_readonly(action: &Actions) -> bool {
     matches!(ActionKind::from_action(action), ActionKind::ReadOnly)
 }
 
+/// Check if an action is standalone (does not read any document).
+pub fn is_standalone(action: &Actions) -> bool {
+    matches!(ActionKind::from_action(action), ActionKind::Standalone)
+}
+
+/// Check if an action may change the document (mutations, apply, load).
+pub fn is_modifying(action: &Actions) -> bool {
+    matches!(
+        ActionKind::from_action(action),
+        ActionKind::Mutation | ActionKind::Complex
+    )
+}
+
 /// Check if an action is derived (iteration: keys, values, etc.).
 pub fn is_derived(action: &Actions) -> bool {
     matches!(ActionKind::from_action(action), ActionKind::Derived)
@@ -157,35 +312,62 @@ mod tests {
     fn set_value() -> Option<Actions> {
         Some(Actions::SetValue {
             key: "a".to_string(),
-            value: "1".to_string(),
+            named: Default::default(),
+            value: Some("1".to_string()),
             yaml: false,
+            from: None,
+            from_file: None,
+            file: None,
+            if_missing: false,
+            if_equals: None,
+            keep_tag: false,
+            tag: None,
+            binary: false,
+            arg: vec![],
+            argjson: vec![],
+            preview: Default::default(),
         })
     }
 
     fn del() -> Option<Actions> {
         Some(Actions::Del {
-            key: "a".to_string(),
+            keys: vec!["a".to_string()],
+            named: Default::default(),
+            ignore_missing: false,
+            preview: Default::default(),
         })
     }
 
     fn get_value() -> Option<Actions> {
         Some(Actions::GetValue {
             path: Some("a".to_string()),
+            named: Default::default(),
             default: None,
+            named_default: None,
+            default_yaml: false,
+            into_json_schema_type: None,
             yaml: false,
-            line_buffer: false,
+            resolve_aliases: false,
+            keep_aliases: false,
+            fail_on_null: false,
+            binary: false,
+            as_bool: false,
+            date_format: None,
         })
     }
 
     fn get_type() -> Option<Actions> {
         Some(Actions::GetType {
             path: Some("a".to_string()),
+            named: Default::default(),
+            schema: None,
         })
     }
 
     fn keys() -> Option<Actions> {
         Some(Actions::Keys {
             path: Some("a".to_string()),
+            named: Default::default(),
             yaml: false,
         })
     }
@@ -193,7 +375,10 @@ mod tests {
     fn apply() -> Option<Actions> {
         Some(Actions::Apply {
             merge_policy: None,
+            url_timeout: crate::yaml::merge::URL_TIMEOUT,
+            strict_tags: false,
             overlays: vec!["overlay.yaml".to_string()],
+            preview: Default::default(),
         })
     }
 
@@ -253,6 +438,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_get_values_at_end_is_doc_mode() {
+        let get_values = || {
+            Some(Actions::GetValues {
+                path: Some("a".to_string()),
+                named: Default::default(),
+                yaml: false,
+            })
+        };
+        assert_eq!(
+            analyze_chain(&[set_value(), del(), get_values()]),
+            ExecutionMode::DocMode
+        );
+        // Not at the end, the next action operates on the items
+        assert_eq!(
+            analyze_chain(&[set_value(), get_values(), get_value()]),
+            ExecutionMode::ValueMode
+        );
+        assert_eq!(
+            analyze_chain(&[apply(), get_values()]),
+            ExecutionMode::ValueMode
+        );
+    }
+
     #[test]
     fn test_complex_anywhere_is_value_mode() {
         assert_eq!(
@@ -261,6 +470,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_save_load_is_value_mode() {
+        let save = Some(Actions::Save {
+            name: "orig".to_string(),
+        });
+        let load = Some(Actions::Load {
+            name: "orig".to_string(),
+            path: None,
+            named: Default::default(),
+        });
+        assert_eq!(
+            analyze_chain(&[save, set_value(), load]),
+            ExecutionMode::ValueMode
+        );
+    }
+
+    #[test]
+    fn test_unflatten_is_value_mode() {
+        assert_eq!(
+            analyze_chain(&[Some(Actions::Unflatten)]),
+            ExecutionMode::ValueMode
+        );
+    }
+
     #[test]
     fn test_empty_chain_is_doc_mode() {
         assert_eq!(analyze_chain(&[]), ExecutionMode::DocMode);
@@ -270,16 +503,62 @@ mod tests {
     fn test_is_readonly() {
         assert!(is_readonly(&Actions::GetValue {
             path: None,
+            named: Default::default(),
             default: None,
+            named_default: None,
+            default_yaml: false,
+            into_json_schema_type: None,
             yaml: false,
-            line_buffer: false,
+            resolve_aliases: false,
+            keep_aliases: false,
+            fail_on_null: false,
+            binary: false,
+            as_bool: false,
+            date_format: None,
+        }));
+        assert!(is_readonly(&Actions::GetType {
+            path: None,
+            named: Default::default(),
+            schema: None,
+        }));
+        assert!(is_readonly(&Actions::GetLength {
+            path: None,
+            named: Default::default(),
         }));
-        assert!(is_readonly(&Actions::GetType { path: None }));
-        assert!(is_readonly(&Actions::GetLength { path: None }));
         assert!(!is_readonly(&Actions::SetValue {
             key: "a".to_string(),
-            value: "1".to_string(),
+            named: Default::default(),
+            value: Some("1".to_string()),
             yaml: false,
+            from: None,
+            from_file: None,
+            file: None,
+            if_missing: false,
+            if_equals: None,
+            keep_tag: false,
+            tag: None,
+            binary: false,
+            arg: vec![],
+            argjson: vec![],
+            preview: Default::default(),
+        }));
+    }
+
+    #[test]
+    fn test_is_standalone() {
+        assert!(is_standalone(&Actions::ParentPath {
+            path: Some("a.b".to_string()),
+            named: Default::default(),
+        }));
+        assert!(is_standalone(&Actions::CheckSyntax {
+            files: vec![],
+            config: None,
+            report: "text".to_string(),
+        }));
+        assert!(!is_standalone(&Actions::GetType {
+            path: None,
+            named: Default::default(),
+            schema: None,
         }));
     }
 }
@@ -0,0 +1,250 @@
+//! Reports of problems for CI systems.
+//!
+//! SARIF 2.1.0 is the format read by GitHub code scanning and other CI
+//! dashboards to annotate files with the problems found, and GitHub
+//! Actions turns workflow commands printed by a step into annotations.
+//! JUnit XML is the format of test results displayed by most CI systems.
+
+use super::error::json_string;
+use crate::yaml::{Outcome, Problem};
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Level of the problems of a rule: those preventing the file from being
+/// loaded are errors.
+fn level(rule: &str) -> &'static str {
+    match rule {
+        "syntax" | "read-error" | "undefined-alias" => "error",
+        _ => "warning",
+    }
+}
+
+fn result(file: &str, problem: &Problem) -> String {
+    let region = match problem.location {
+        Some((line, column)) => format!(
+            ", \"region\": {{\"startLine\": {}, \"startColumn\": {}}}",
+            line, column
+        ),
+        None => String::new(),
+    };
+    format!(
+        "{{\"ruleId\": {}, \"level\": \"{}\", \"message\": {{\"text\": {}}}, \
+         \"locations\": [{{\"physicalLocation\": {{\"artifactLocation\": {{\"uri\": {}}}{}}}}}]}}",
+        json_string(problem.rule),
+        level(problem.rule),
+        json_string(&problem.message),
+        json_string(file),
+        region
+    )
+}
+
+/// SARIF log of the problems found in each file, as a single run.
+pub fn sarif(problems: &[(String, Problem)]) -> String {
+    let mut rules: Vec<&str> = problems.iter().map(|(_, p)| p.rule).collect();
+    rules.sort_unstable();
+    rules.dedup();
+    let rules: Vec<String> = rules
+        .iter()
+        .map(|id| format!("{{\"id\": {}}}", json_string(id)))
+        .collect();
+    let results: Vec<String> = problems
+        .iter()
+        .map(|(file, problem)| format!("        {}", result(file, problem)))
+        .collect();
+    let results = if results.is_empty() {
+        "[]".to_string()
+    } else {
+        format!("[\n{}\n      ]", results.join(",\n"))
+    };
+    format!(
+        concat!(
+            "{{\n",
+            "  \"$schema\": \"{}\",\n",
+            "  \"version\": \"2.1.0\",\n",
+            "  \"runs\": [\n",
+            "    {{\n",
+            "      \"tool\": {{\"driver\": {{\"name\": \"shyaml\", \"informationUri\": \"{}\", \"rules\": [{}]}}}},\n",
+            "      \"results\": {}\n",
+            "    }}\n",
+            "  ]\n",
+            "}}"
+        ),
+        SCHEMA,
+        env!("CARGO_PKG_REPOSITORY"),
+        rules.join(", "),
+        results
+    )
+}
+
+/// Escape the message of a GitHub Actions workflow command.
+fn github_data(s: &str) -> String {
+    s.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a property of a GitHub Actions workflow command.
+fn github_property(s: &str) -> String {
+    github_data(s).replace(':', "%3A").replace(',', "%2C")
+}
+
+/// GitHub Actions annotation of a problem found in a file.
+pub fn github(file: &str, problem: &Problem) -> String {
+    let location = match problem.location {
+        Some((line, column)) => format!(",line={},col={}", line, column),
+        None => String::new(),
+    };
+    format!(
+        "::{} file={}{},title={}::{}",
+        level(problem.rule),
+        github_property(file),
+        location,
+        github_property(problem.rule),
+        github_data(&problem.message)
+    )
+}
+
+/// GitHub Actions error annotation of a message not tied to a file, with
+/// an optional title.
+pub fn github_error(title: &str, message: &str) -> String {
+    match title {
+        "" => format!("::error::{}", github_data(message)),
+        _ => format!(
+            "::error title={}::{}",
+            github_property(title),
+            github_data(message)
+        ),
+    }
+}
+
+/// Escape text or an attribute value of an XML document.
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// JUnit XML report of the outcomes of a test suite, with a `testsuite`
+/// element per input it ran on.
+pub fn junit(suite: &str, results: &[(String, Vec<Outcome>)]) -> String {
+    let failures = |outcomes: &[Outcome]| outcomes.iter().filter(|o| o.failure.is_some()).count();
+    let mut xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <testsuites name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+        xml_escape(suite),
+        results.iter().map(|(_, o)| o.len()).sum::<usize>(),
+        results.iter().map(|(_, o)| failures(o)).sum::<usize>()
+    );
+    for (label, outcomes) in results {
+        xml += &format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\">\n",
+            xml_escape(label),
+            outcomes.len(),
+            failures(outcomes)
+        );
+        for outcome in outcomes {
+            let testcase = format!(
+                "<testcase classname=\"{}\" name=\"{}\"",
+                xml_escape(suite),
+                xml_escape(&outcome.name)
+            );
+            xml += &match &outcome.failure {
+                Some(failure) => format!(
+                    "    {}>\n      <failure message=\"{}\"/>\n    </testcase>\n",
+                    testcase,
+                    xml_escape(failure)
+                ),
+                None => format!("    {}/>\n", testcase),
+            };
+        }
+        xml += "  </testsuite>\n";
+    }
+    xml + "</testsuites>"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report() {
+        let problems = vec![
+            (
+                "a.yaml".to_string(),
+                Problem {
+                    location: Some((2, 1)),
+                    rule: "syntax",
+                    message: "duplicate key".to_string(),
+                },
+            ),
+            (
+                "b \"c\".yaml".to_string(),
+                Problem {
+                    location: None,
+                    rule: "read-error",
+                    message: "Failed to read".to_string(),
+                },
+            ),
+        ];
+        let report = sarif(&problems);
+        assert!(report.contains(
+            "{\"ruleId\": \"syntax\", \"level\": \"error\", \"message\": {\"text\": \"duplicate key\"}, \
+             \"locations\": [{\"physicalLocation\": {\"artifactLocation\": {\"uri\": \"a.yaml\"}, \
+             \"region\": {\"startLine\": 2, \"startColumn\": 1}}}]}"
+        ));
+        assert!(report.contains("{\"uri\": \"b \\\"c\\\".yaml\"}}}]}"));
+        assert!(report.contains("\"rules\": [{\"id\": \"read-error\"}, {\"id\": \"syntax\"}]"));
+    }
+
+    #[test]
+    fn test_github() {
+        let problem = Problem {
+            location: Some((3, 5)),
+            rule: "truthy",
+            message: "100% wrong,\nreally".to_string(),
+        };
+        assert_eq!(
+            github("dir/a,b.yaml", &problem),
+            "::warning file=dir/a%2Cb.yaml,line=3,col=5,title=truthy::100%25 wrong,%0Areally"
+        );
+        assert_eq!(
+            github_error("a.b", "unexpected key"),
+            "::error title=a.b::unexpected key"
+        );
+        assert_eq!(github_error("", "failed"), "::error::failed");
+    }
+
+    #[test]
+    fn test_empty_report() {
+        assert!(sarif(&[]).contains("\"results\": []\n"));
+    }
+
+    #[test]
+    fn test_junit() {
+        let outcomes = vec![
+            Outcome {
+                name: "a equals 1".to_string(),
+                failure: None,
+            },
+            Outcome {
+                name: "b exists".to_string(),
+                failure: Some("missing key 'b' in <struct>".to_string()),
+            },
+        ];
+        assert_eq!(
+            junit("web & db", &[("<stdin>".to_string(), outcomes)]),
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<testsuites name=\"web &amp; db\" tests=\"2\" failures=\"1\">\n",
+                "  <testsuite name=\"&lt;stdin&gt;\" tests=\"2\" failures=\"1\">\n",
+                "    <testcase classname=\"web &amp; db\" name=\"a equals 1\"/>\n",
+                "    <testcase classname=\"web &amp; db\" name=\"b exists\">\n",
+                "      <failure message=\"missing key 'b' in &lt;struct&gt;\"/>\n",
+                "    </testcase>\n",
+                "  </testsuite>\n",
+                "</testsuites>"
+            )
+        );
+    }
+}
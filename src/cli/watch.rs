@@ -0,0 +1,239 @@
+//! Waiting for changes of an input file, for `--watch`.
+//!
+//! Uses inotify on Linux and kqueue on macOS and the BSDs. Editors often
+//! save by writing a new file and renaming it over the old one, so the
+//! file is followed by name rather than by inode.
+
+use super::error::CliError;
+
+/// Whether files can be watched on this platform.
+pub const SUPPORTED: bool = cfg!(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+));
+
+/// Time during which further events are taken as part of the same change,
+/// in milliseconds.
+const SETTLE_MS: i32 = 50;
+
+fn watch_error(path: &str) -> CliError {
+    CliError::io(format!(
+        "Failed to watch '{}': {}",
+        path,
+        std::io::Error::last_os_error()
+    ))
+}
+
+#[cfg(target_os = "linux")]
+pub struct FileWatcher {
+    fd: libc::c_int,
+    /// Name of the file in the watched directory
+    name: std::ffi::OsString,
+    path: String,
+}
+
+#[cfg(target_os = "linux")]
+impl FileWatcher {
+    /// Watch the directory of `path` for changes of the file.
+    pub fn new(path: &str) -> Result<Self, CliError> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let file = std::path::Path::new(path);
+        let name = file
+            .file_name()
+            .ok_or_else(|| CliError::io(format!("Failed to watch '{}': not a file", path)))?
+            .to_os_string();
+        let dir = match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => std::path::Path::new("."),
+        };
+        let dir = std::ffi::CString::new(dir.as_os_str().as_bytes())
+            .map_err(|_| CliError::io(format!("Failed to watch '{}': invalid path", path)))?;
+
+        // SAFETY: plain system calls, the descriptor is owned by the watcher
+        let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+        if fd < 0 {
+            return Err(watch_error(path));
+        }
+        let mask = libc::IN_CLOSE_WRITE | libc::IN_MOVED_TO | libc::IN_CREATE;
+        if unsafe { libc::inotify_add_watch(fd, dir.as_ptr(), mask) } < 0 {
+            let e = watch_error(path);
+            unsafe { libc::close(fd) };
+            return Err(e);
+        }
+        Ok(FileWatcher {
+            fd,
+            name,
+            path: path.to_string(),
+        })
+    }
+
+    /// Block until the file is written, created or replaced.
+    pub fn wait(&mut self) -> Result<(), CliError> {
+        while !self.changed(-1)? {}
+        // A save usually comes as several events
+        while self.changed(SETTLE_MS)? {}
+        Ok(())
+    }
+
+    /// Read the events coming within `timeout` milliseconds (-1 to wait
+    /// forever), telling whether one is about the file.
+    fn changed(&self, timeout: i32) -> Result<bool, CliError> {
+        use std::os::unix::ffi::OsStrExt;
+
+        let mut poll = libc::pollfd {
+            fd: self.fd,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `poll` and `buffer` outlive the calls using them
+        match unsafe { libc::poll(&mut poll, 1, timeout) } {
+            0 => return Ok(false),
+            n if n < 0 => return Err(watch_error(&self.path)),
+            _ => {}
+        }
+        let mut buffer = [0u8; 4096];
+        let len = unsafe { libc::read(self.fd, buffer.as_mut_ptr().cast(), buffer.len()) };
+        if len < 0 {
+            return Err(watch_error(&self.path));
+        }
+
+        let header = std::mem::size_of::<libc::inotify_event>();
+        let events = &buffer[..len as usize];
+        let mut offset = 0;
+        let mut changed = false;
+        while offset + header <= events.len() {
+            // SAFETY: the kernel writes whole events, read unaligned from the buffer
+            let event: libc::inotify_event =
+                unsafe { std::ptr::read_unaligned(events[offset..].as_ptr().cast()) };
+            let end = (offset + header + event.len as usize).min(events.len());
+            // The name is padded with NUL bytes
+            let name = events[offset + header..end].split(|b| *b == 0).next();
+            changed |= name == Some(self.name.as_bytes());
+            offset = end;
+        }
+        Ok(changed)
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        // SAFETY: the descriptor is owned by the watcher
+        unsafe { libc::close(self.fd) };
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+pub struct FileWatcher {
+    kq: libc::c_int,
+    path: String,
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+impl FileWatcher {
+    pub fn new(path: &str) -> Result<Self, CliError> {
+        // SAFETY: plain system call, the descriptor is owned by the watcher
+        let kq = unsafe { libc::kqueue() };
+        if kq < 0 {
+            return Err(watch_error(path));
+        }
+        Ok(FileWatcher {
+            kq,
+            path: path.to_string(),
+        })
+    }
+
+    /// Block until the file is written, deleted or replaced.
+    ///
+    /// The file is opened again for each wait, as it may have been replaced.
+    pub fn wait(&mut self) -> Result<(), CliError> {
+        use std::os::unix::io::AsRawFd;
+
+        let file = loop {
+            match std::fs::File::open(&self.path) {
+                Ok(file) => break file,
+                // Between the removal of the old file and the creation of the new one
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                    std::thread::sleep(std::time::Duration::from_millis(SETTLE_MS as u64))
+                }
+                Err(e) => {
+                    return Err(CliError::io(format!(
+                        "Failed to watch '{}': {}",
+                        self.path, e
+                    )))
+                }
+            }
+        };
+        // SAFETY: `kevent` is plain data, zeroed fields are unused
+        let mut change: libc::kevent = unsafe { std::mem::zeroed() };
+        change.ident = file.as_raw_fd() as _;
+        change.filter = libc::EVFILT_VNODE;
+        change.flags = libc::EV_ADD | libc::EV_CLEAR | libc::EV_ONESHOT;
+        change.fflags = libc::NOTE_WRITE
+            | libc::NOTE_EXTEND
+            | libc::NOTE_DELETE
+            | libc::NOTE_RENAME
+            | libc::NOTE_ATTRIB;
+        let mut event: libc::kevent = unsafe { std::mem::zeroed() };
+        let n = unsafe { libc::kevent(self.kq, &change, 1, &mut event, 1, std::ptr::null()) };
+        if n < 0 {
+            return Err(watch_error(&self.path));
+        }
+        // A save usually comes as several events
+        std::thread::sleep(std::time::Duration::from_millis(SETTLE_MS as u64));
+        Ok(())
+    }
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+impl Drop for FileWatcher {
+    fn drop(&mut self) {
+        // SAFETY: the descriptor is owned by the watcher
+        unsafe { libc::close(self.kq) };
+    }
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+)))]
+pub enum FileWatcher {}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "macos",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+)))]
+impl FileWatcher {
+    pub fn new(_path: &str) -> Result<Self, CliError> {
+        Err("--watch is not supported on this platform".into())
+    }
+
+    pub fn wait(&mut self) -> Result<(), CliError> {
+        match *self {}
+    }
+}
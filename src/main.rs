@@ -1,4 +1,3 @@
-use colored::*;
 use std::process;
 
 mod cli;
@@ -12,11 +11,10 @@ fn main() {
     }
 
     match cli::run() {
-        Ok(true) => process::exit(0),
-        Ok(false) => process::exit(1),
+        Ok(outcome) => process::exit(outcome.exit_code()),
         Err(e) => {
-            eprintln!("{}: {}", "Error".bright_red(), e);
-            std::process::exit(127);
+            cli::error::report(&e);
+            process::exit(e.exit_code());
         }
     }
 }
@@ -18,8 +18,16 @@
 //! - `!merge:replace` - Replace parent value entirely
 //! - `!merge:append` - Append to sequence (default for sequences)
 //! - `!merge:prepend` - Prepend to sequence
+//!
+//! # Namespace Registry
+//!
+//! Namespaces are registered in a [`Registry`], each parsing the
+//! operations written after its name into a [`Directive`]. Parts of a tag
+//! in no registered namespace are left as they are. `shyaml tags` lists
+//! the namespaces of [`registry()`] and their operations.
 
 use std::fmt;
+use std::sync::LazyLock;
 
 /// Merge operation extracted from a tag.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -42,6 +50,13 @@ impl fmt::Display for MergeOp {
     }
 }
 
+/// Directive parsed from a tag part by its namespace.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Directive {
+    /// `!merge:OPERATION`, used by `apply`
+    Merge(MergeOp),
+}
+
 /// Result of parsing a tag string.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ParsedTag {
@@ -59,34 +74,98 @@ pub struct ParsedTag {
 /// Errors that can occur during tag parsing.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum TagError {
-    /// Unknown merge operation (e.g., `!merge:foo`)
-    UnknownOperation(String),
-    /// Multiple merge directives in compound tag (e.g., `!merge:replace;merge:append`)
-    MultipleMergeDirectives,
+    /// Unknown operation of a namespace (e.g., `!merge:foo`)
+    UnknownOperation {
+        namespace: String,
+        operation: String,
+        expected: Vec<String>,
+    },
+    /// Several directives of a namespace in compound tag (e.g., `!merge:replace;merge:append`)
+    MultipleDirectives(String),
     /// Unexpected arguments on operation (e.g., `!merge:replace(x)`)
-    UnexpectedArguments(String),
+    UnexpectedArguments {
+        namespace: String,
+        operation: String,
+    },
+    /// Tag part looking like a mistyped directive (e.g., `!Merge:replace`)
+    SuspiciousDirective {
+        part: String,
+        namespace: String,
+        expected: Vec<String>,
+    },
     /// Empty tag
     EmptyTag,
 }
 
+impl TagError {
+    fn unknown_operation(namespace: &dyn Namespace, operation: &str) -> Self {
+        TagError::UnknownOperation {
+            namespace: namespace.name().to_string(),
+            operation: operation.to_string(),
+            expected: namespace
+                .operations()
+                .iter()
+                .map(|op| op.name.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// List alternatives as `a, b, or c`.
+fn one_of(items: &[String]) -> String {
+    match items {
+        [] => String::new(),
+        [item] => item.clone(),
+        [first, second] => format!("{} or {}", first, second),
+        [init @ .., last] => format!("{}, or {}", init.join(", "), last),
+    }
+}
+
 impl fmt::Display for TagError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TagError::UnknownOperation(op) => {
+            TagError::UnknownOperation {
+                namespace,
+                operation,
+                expected,
+            } => {
                 write!(
                     f,
-                    "unknown merge operation '{}': expected replace, append, or prepend",
-                    op
+                    "unknown {} operation '{}': expected {}",
+                    namespace,
+                    operation,
+                    one_of(expected)
                 )
             }
-            TagError::MultipleMergeDirectives => {
+            TagError::MultipleDirectives(namespace) => {
                 write!(
                     f,
-                    "multiple merge directives in tag: only one merge directive allowed per node"
+                    "multiple {0} directives in tag: only one {0} directive allowed per node",
+                    namespace
                 )
             }
-            TagError::UnexpectedArguments(op) => {
-                write!(f, "unexpected arguments for merge operation '{}': current operations do not accept arguments", op)
+            TagError::UnexpectedArguments {
+                namespace,
+                operation,
+            } => {
+                write!(f, "unexpected arguments for {} operation '{}': current operations do not accept arguments", namespace, operation)
+            }
+            TagError::SuspiciousDirective {
+                part,
+                namespace,
+                expected,
+            } => {
+                let expected: Vec<String> = expected
+                    .iter()
+                    .map(|op| format!("!{}:{}", namespace, op))
+                    .collect();
+                write!(
+                    f,
+                    "suspicious tag '!{}': looks like a mistyped {} directive, expected {}",
+                    part,
+                    namespace,
+                    one_of(&expected)
+                )
             }
             TagError::EmptyTag => {
                 write!(f, "empty tag")
@@ -97,6 +176,222 @@ impl fmt::Display for TagError {
 
 impl std::error::Error for TagError {}
 
+// =============================================================================
+// Namespace Registry
+// =============================================================================
+
+/// Operation of a namespace, as listed by `shyaml tags`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Operation {
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Namespace of directives, written `!NAMESPACE:OPERATION` in tags.
+pub trait Namespace: Send + Sync {
+    /// Name before the colon, as `merge`
+    fn name(&self) -> &'static str;
+
+    /// What the directives of the namespace are for
+    fn description(&self) -> &'static str;
+
+    fn operations(&self) -> &'static [Operation];
+
+    /// Parse an operation of the namespace, with the content of its
+    /// parentheses if any.
+    fn parse(&self, operation: &str, arguments: Option<&str>) -> Result<Directive, TagError>;
+}
+
+/// The `merge:` namespace of inline merge directives.
+struct MergeNamespace;
+
+impl Namespace for MergeNamespace {
+    fn name(&self) -> &'static str {
+        "merge"
+    }
+
+    fn description(&self) -> &'static str {
+        "How apply merges an overlay value into the base document"
+    }
+
+    fn operations(&self) -> &'static [Operation] {
+        &[
+            Operation {
+                name: "replace",
+                description: "Replace the base value entirely",
+            },
+            Operation {
+                name: "append",
+                description: "Append to the base sequence (default for sequences)",
+            },
+            Operation {
+                name: "prepend",
+                description: "Prepend to the base sequence",
+            },
+        ]
+    }
+
+    fn parse(&self, operation: &str, arguments: Option<&str>) -> Result<Directive, TagError> {
+        if arguments.is_some() {
+            return Err(TagError::UnexpectedArguments {
+                namespace: self.name().to_string(),
+                operation: operation.to_string(),
+            });
+        }
+        match operation {
+            "replace" => Ok(Directive::Merge(MergeOp::Replace)),
+            "append" => Ok(Directive::Merge(MergeOp::Append)),
+            "prepend" => Ok(Directive::Merge(MergeOp::Prepend)),
+            other => Err(TagError::unknown_operation(self, other)),
+        }
+    }
+}
+
+/// Namespaces whose directives are parsed out of tags.
+pub struct Registry {
+    namespaces: Vec<Box<dyn Namespace>>,
+}
+
+impl Registry {
+    /// Registry of the namespaces built into shyaml.
+    pub fn builtin() -> Self {
+        let mut registry = Registry {
+            namespaces: Vec::new(),
+        };
+        registry.register(Box::new(MergeNamespace));
+        registry
+    }
+
+    /// Add a namespace, replacing any namespace of the same name.
+    pub fn register(&mut self, namespace: Box<dyn Namespace>) {
+        self.namespaces.retain(|n| n.name() != namespace.name());
+        self.namespaces.push(namespace);
+    }
+
+    pub fn namespaces(&self) -> impl Iterator<Item = &dyn Namespace> {
+        self.namespaces.iter().map(|n| n.as_ref())
+    }
+
+    fn get(&self, name: &str) -> Option<&dyn Namespace> {
+        self.namespaces().find(|n| n.name() == name)
+    }
+
+    /// Parse a single tag part, `Ok(None)` if it's in no registered
+    /// namespace.
+    fn parse_part(&self, part: &str) -> Result<Option<(&'static str, Directive)>, TagError> {
+        let Some((name, content)) = part.split_once(':') else {
+            return Ok(None);
+        };
+        let Some(namespace) = self.get(name) else {
+            return Ok(None);
+        };
+        let (operation, arguments) = match content.find('(') {
+            Some(paren_pos) => (
+                &content[..paren_pos],
+                Some(content[paren_pos + 1..].trim_end_matches(')')),
+            ),
+            None => (content, None),
+        };
+        let directive = namespace.parse(operation, arguments)?;
+        Ok(Some((namespace.name(), directive)))
+    }
+
+    /// Parse a tag string and extract its directives, see [`parse_tag`].
+    pub fn parse_tag(&self, tag: &str) -> Result<ParsedTag, TagError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(TagError::EmptyTag);
+        }
+
+        // Remove leading '!' if present for parsing
+        let tag_content = tag.strip_prefix('!').unwrap_or(tag);
+        if tag_content.is_empty() {
+            return Err(TagError::EmptyTag);
+        }
+
+        // Split on ';' outside parentheses to get tag parts
+        let parts = split_tag_parts(tag_content);
+
+        let mut merge_op: Option<MergeOp> = None;
+        let mut found: Vec<&str> = Vec::new();
+        let mut remaining_parts: Vec<&str> = Vec::new();
+
+        for part in &parts {
+            match self.parse_part(part)? {
+                Some((namespace, _)) if found.contains(&namespace) => {
+                    return Err(TagError::MultipleDirectives(namespace.to_string()));
+                }
+                Some((namespace, directive)) => {
+                    found.push(namespace);
+                    match directive {
+                        Directive::Merge(op) => merge_op = Some(op),
+                    }
+                }
+                None => remaining_parts.push(part),
+            }
+        }
+
+        // Reconstruct remaining tag
+        let remaining = if remaining_parts.is_empty() {
+            None
+        } else {
+            Some(format!("!{}", remaining_parts.join(";")))
+        };
+
+        Ok(ParsedTag {
+            remaining,
+            merge_op,
+        })
+    }
+
+    /// Check a tag more strictly than [`Registry::parse_tag`], for
+    /// `apply --strict-tags`.
+    ///
+    /// Parts in no namespace are also rejected when they look like a
+    /// directive: a namespace with other case or a typo (`!Merge:replace`,
+    /// `!mrege:replace`), without its colon (`!merge`, `!merge-replace`),
+    /// or an operation without namespace (`!replace`).
+    pub fn check_tag_strict(&self, tag: &str) -> Result<(), TagError> {
+        let Some(remaining) = self.parse_tag(tag)?.remaining else {
+            return Ok(());
+        };
+        let content = remaining.strip_prefix('!').unwrap_or(&remaining);
+        for part in split_tag_parts(content) {
+            if let Some(namespace) = self.resembling(part) {
+                return Err(TagError::SuspiciousDirective {
+                    part: part.to_string(),
+                    namespace: namespace.name().to_string(),
+                    expected: namespace
+                        .operations()
+                        .iter()
+                        .map(|op| op.name.to_string())
+                        .collect(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Namespace a tag part, which is in none, looks like a directive of.
+    fn resembling(&self, part: &str) -> Option<&dyn Namespace> {
+        let name = part.split('(').next().unwrap_or(part).to_lowercase();
+        let prefix = name.split_once(':').map(|(prefix, _)| prefix);
+        self.namespaces().find(|namespace| {
+            name.starts_with(namespace.name())
+                || namespace.operations().iter().any(|op| name == op.name)
+                || prefix.is_some_and(|prefix| edit_distance(prefix, namespace.name()) <= 2)
+        })
+    }
+}
+
+/// Namespaces built into shyaml.
+static REGISTRY: LazyLock<Registry> = LazyLock::new(Registry::builtin);
+
+/// The registry of the namespaces built into shyaml.
+pub fn registry() -> &'static Registry {
+    &REGISTRY
+}
+
 /// Parse a tag string and extract any merge directive.
 ///
 /// # Arguments
@@ -130,45 +425,28 @@ impl std::error::Error for TagError {}
 /// assert_eq!(result.remaining, Some("!custom".to_string()));
 /// ```
 pub fn parse_tag(tag: &str) -> Result<ParsedTag, TagError> {
-    let tag = tag.trim();
-    if tag.is_empty() {
-        return Err(TagError::EmptyTag);
-    }
-
-    // Remove leading '!' if present for parsing
-    let tag_content = tag.strip_prefix('!').unwrap_or(tag);
-    if tag_content.is_empty() {
-        return Err(TagError::EmptyTag);
-    }
-
-    // Split on ';' outside parentheses to get tag parts
-    let parts = split_tag_parts(tag_content);
+    registry().parse_tag(tag)
+}
 
-    let mut merge_op: Option<MergeOp> = None;
-    let mut remaining_parts: Vec<&str> = Vec::new();
+/// Check a tag more strictly than [`parse_tag`], for `apply --strict-tags`,
+/// see [`Registry::check_tag_strict`].
+pub fn check_tag_strict(tag: &str) -> Result<(), TagError> {
+    registry().check_tag_strict(tag)
+}
 
-    for part in &parts {
-        if let Some(op) = parse_merge_part(part)? {
-            if merge_op.is_some() {
-                return Err(TagError::MultipleMergeDirectives);
-            }
-            merge_op = Some(op);
-        } else {
-            remaining_parts.push(part);
+/// Levenshtein distance between two strings.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
         }
+        previous = current;
     }
-
-    // Reconstruct remaining tag
-    let remaining = if remaining_parts.is_empty() {
-        None
-    } else {
-        Some(format!("!{}", remaining_parts.join(";")))
-    };
-
-    Ok(ParsedTag {
-        remaining,
-        merge_op,
-    })
+    previous[b.len()]
 }
 
 /// Split tag content on ';' but respect parentheses.
@@ -199,31 +477,6 @@ fn split_tag_parts(content: &str) -> Vec<&str> {
     parts
 }
 
-/// Parse a single tag part to check if it's a merge directive.
-/// Returns `Ok(Some(MergeOp))` if it's a merge directive,
-/// `Ok(None)` if it's not, or `Err` if it's an invalid merge directive.
-fn parse_merge_part(part: &str) -> Result<Option<MergeOp>, TagError> {
-    // Check if this part starts with "merge:"
-    let merge_content = match part.strip_prefix("merge:") {
-        Some(content) => content,
-        None => return Ok(None), // Not a merge directive
-    };
-
-    // Check for arguments (parentheses)
-    if let Some(paren_pos) = merge_content.find('(') {
-        let op_name = &merge_content[..paren_pos];
-        return Err(TagError::UnexpectedArguments(op_name.to_string()));
-    }
-
-    // Parse the operation
-    match merge_content {
-        "replace" => Ok(Some(MergeOp::Replace)),
-        "append" => Ok(Some(MergeOp::Append)),
-        "prepend" => Ok(Some(MergeOp::Prepend)),
-        other => Err(TagError::UnknownOperation(other.to_string())),
-    }
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -344,19 +597,23 @@ mod tests {
     #[test]
     fn test_error_unknown_operation() {
         let result = parse_tag("!merge:unknown");
-        assert!(matches!(result, Err(TagError::UnknownOperation(op)) if op == "unknown"));
+        assert!(
+            matches!(result, Err(TagError::UnknownOperation { operation, .. }) if operation == "unknown")
+        );
     }
 
     #[test]
     fn test_error_multiple_merge_directives() {
         let result = parse_tag("!merge:replace;merge:append");
-        assert!(matches!(result, Err(TagError::MultipleMergeDirectives)));
+        assert!(matches!(result, Err(TagError::MultipleDirectives(ns)) if ns == "merge"));
     }
 
     #[test]
     fn test_error_unexpected_arguments() {
         let result = parse_tag("!merge:replace(foo)");
-        assert!(matches!(result, Err(TagError::UnexpectedArguments(op)) if op == "replace"));
+        assert!(
+            matches!(result, Err(TagError::UnexpectedArguments { operation, .. }) if operation == "replace")
+        );
     }
 
     #[test]
@@ -396,15 +653,141 @@ mod tests {
 
     #[test]
     fn test_tag_error_display() {
-        let err = TagError::UnknownOperation("foo".to_string());
-        assert!(err.to_string().contains("foo"));
-        assert!(err.to_string().contains("unknown"));
+        let err = parse_tag("!merge:foo").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "unknown merge operation 'foo': expected replace, append, or prepend"
+        );
 
-        let err = TagError::MultipleMergeDirectives;
-        assert!(err.to_string().contains("multiple"));
+        let err = TagError::MultipleDirectives("merge".to_string());
+        assert!(err.to_string().contains("multiple merge directives"));
 
-        let err = TagError::UnexpectedArguments("replace".to_string());
-        assert!(err.to_string().contains("replace"));
+        let err = parse_tag("!merge:replace(x)").unwrap_err();
+        assert!(err.to_string().contains("merge operation 'replace'"));
         assert!(err.to_string().contains("arguments"));
+
+        let err = check_tag_strict("!Merge:replace").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "suspicious tag '!Merge:replace': looks like a mistyped merge directive, \
+             expected !merge:replace, !merge:append, or !merge:prepend"
+        );
+    }
+
+    // ==========================================================================
+    // Strict checks
+    // ==========================================================================
+
+    #[test]
+    fn test_strict_accepts_directives_and_other_tags() {
+        for tag in [
+            "!merge:replace",
+            "!custom;merge:append",
+            "!foo",
+            "!k8s:secret",
+            "!tag(args)",
+        ] {
+            assert_eq!(check_tag_strict(tag), Ok(()), "{}", tag);
+        }
+    }
+
+    #[test]
+    fn test_strict_rejects_suspicious_directives() {
+        for (tag, part) in [
+            ("!Merge:replace", "Merge:replace"),
+            ("!mrege:replace", "mrege:replace"),
+            ("!merg:append", "merg:append"),
+            ("!merge", "merge"),
+            ("!custom;merge-replace", "merge-replace"),
+            ("!replace", "replace"),
+        ] {
+            assert!(
+                matches!(
+                    check_tag_strict(tag),
+                    Err(TagError::SuspiciousDirective { part: p, .. }) if p == part
+                ),
+                "{}",
+                tag
+            );
+        }
+        assert!(matches!(
+            check_tag_strict("!merge:repalce"),
+            Err(TagError::UnknownOperation { operation, .. }) if operation == "repalce"
+        ));
+    }
+
+    #[test]
+    fn test_edit_distance() {
+        assert_eq!(edit_distance("merge", "merge"), 0);
+        assert_eq!(edit_distance("mrege", "merge"), 2);
+        assert_eq!(edit_distance("merg", "merge"), 1);
+        assert_eq!(edit_distance("k8s", "merge"), 5);
+    }
+
+    // ==========================================================================
+    // Registry
+    // ==========================================================================
+
+    struct EnvNamespace;
+
+    impl Namespace for EnvNamespace {
+        fn name(&self) -> &'static str {
+            "env"
+        }
+
+        fn description(&self) -> &'static str {
+            "Test namespace"
+        }
+
+        fn operations(&self) -> &'static [Operation] {
+            &[Operation {
+                name: "var",
+                description: "Test operation",
+            }]
+        }
+
+        fn parse(&self, operation: &str, _arguments: Option<&str>) -> Result<Directive, TagError> {
+            match operation {
+                "var" => Ok(Directive::Merge(MergeOp::Replace)),
+                other => Err(TagError::unknown_operation(self, other)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_lists_builtin_namespaces() {
+        let names: Vec<&str> = registry().namespaces().map(|n| n.name()).collect();
+        assert_eq!(names, vec!["merge"]);
+        let ops: Vec<&str> = registry()
+            .namespaces()
+            .flat_map(|n| n.operations().iter().map(|op| op.name))
+            .collect();
+        assert_eq!(ops, vec!["replace", "append", "prepend"]);
+    }
+
+    #[test]
+    fn test_registered_namespace_parses_its_parts() {
+        let mut registry = Registry::builtin();
+        // Not a directive until registered
+        assert_eq!(
+            registry.parse_tag("!env:var").unwrap().remaining,
+            Some("!env:var".to_string())
+        );
+
+        registry.register(Box::new(EnvNamespace));
+        let result = registry.parse_tag("!custom;env:var(HOME)").unwrap();
+        assert_eq!(result.remaining, Some("!custom".to_string()));
+        assert!(matches!(
+            registry.parse_tag("!env:vra"),
+            Err(TagError::UnknownOperation { namespace, .. }) if namespace == "env"
+        ));
+        assert!(matches!(
+            registry.parse_tag("!env:var;env:var"),
+            Err(TagError::MultipleDirectives(namespace)) if namespace == "env"
+        ));
+        assert!(matches!(
+            registry.check_tag_strict("!Env:var"),
+            Err(TagError::SuspiciousDirective { namespace, .. }) if namespace == "env"
+        ));
     }
 }
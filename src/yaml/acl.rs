@@ -0,0 +1,233 @@
+//! Paths mutations are allowed to modify.
+//!
+//! The allowed paths are read from a file with one path per line, blank
+//! lines and `#` comments being ignored. A path allows modifying itself and
+//! everything below it, and a `*` element matches any key or index (`\*`
+//! being a literal `*` key):
+//!
+//! ```text
+//! # managed by the release bot
+//! image.tag
+//! spec.containers.*.image
+//! ```
+
+use super::error::Error;
+use super::flatten::FlattenNode;
+use super::path::{join_elements, split_path, split_path_literal};
+use super::regions::resolved_parts;
+use fyaml::{Document, Value};
+
+/// Element of an allowed path matching any key or index.
+const WILDCARD: &str = "*";
+
+/// Set of paths mutations are restricted to.
+#[derive(Debug, Clone)]
+pub struct AllowedPaths {
+    /// File the paths were read from, for error messages
+    file: String,
+    /// Elements of the paths, telling whether they are escaped
+    paths: Vec<Vec<(String, bool)>>,
+}
+
+impl AllowedPaths {
+    /// Read the allowed paths from a file.
+    pub fn from_file(file: &str) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+        Ok(Self::parse(file, &content))
+    }
+
+    fn parse(file: &str, content: &str) -> Self {
+        let paths = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(split_path_literal)
+            .collect();
+        AllowedPaths {
+            file: file.to_string(),
+            paths,
+        }
+    }
+
+    fn allows(&self, parts: &[String]) -> bool {
+        self.paths.iter().any(|allowed| {
+            allowed.len() <= parts.len()
+                && allowed
+                    .iter()
+                    .zip(parts)
+                    .all(|((a, literal), p)| (!literal && a == WILDCARD) || a == p)
+        })
+    }
+
+    fn error(&self, path: &str) -> Error {
+        Error::Base(format!(
+            "cannot modify '{}', it is not in the paths allowed by '{}'.",
+            path, self.file
+        ))
+    }
+
+    fn check<N: FlattenNode + Copy>(&self, root: Option<N>, path: &str) -> Result<(), Error> {
+        let parts = match root {
+            Some(root) => resolved_parts(root, path)?,
+            None => split_path(path),
+        };
+        if self.allows(&parts) {
+            Ok(())
+        } else {
+            Err(self.error(path))
+        }
+    }
+
+    /// Find the first changed path that isn't allowed.
+    fn disallowed_change<'a>(
+        &self,
+        parts: &mut Vec<String>,
+        original: Option<&'a Value>,
+        result: Option<&'a Value>,
+    ) -> Option<String> {
+        if original == result || self.allows(parts) {
+            return None;
+        }
+        let (Some(original), Some(result)) = (original, result) else {
+            return Some(join_elements(parts));
+        };
+        let (Some(before), Some(after)) = (original.children(), result.children()) else {
+            return Some(join_elements(parts));
+        };
+        if original.is_sequence() != result.is_sequence() {
+            return Some(join_elements(parts));
+        }
+        let removed = before
+            .iter()
+            .filter(|(k, _)| !after.iter().any(|(a, _)| a == k));
+        let keys: Vec<&String> = after
+            .iter()
+            .map(|(k, _)| k)
+            .chain(removed.map(|(k, _)| k))
+            .collect();
+        for key in keys {
+            let find = |children: &[(String, &'a Value)]| {
+                children.iter().find(|(k, _)| k == key).map(|(_, v)| *v)
+            };
+            parts.push(key.clone());
+            let change = self.disallowed_change(parts, find(&before), find(&after));
+            parts.pop();
+            if change.is_some() {
+                return change;
+            }
+        }
+        None
+    }
+}
+
+/// Fail if a path isn't allowed to be modified.
+pub fn check_allowed(
+    value: &Value,
+    allowed: Option<&AllowedPaths>,
+    path: &str,
+) -> Result<(), Error> {
+    match allowed {
+        Some(allowed) => allowed.check(Some(value), path),
+        None => Ok(()),
+    }
+}
+
+/// Fail if a path of a document isn't allowed to be modified.
+pub fn check_allowed_doc(
+    doc: &Document,
+    allowed: Option<&AllowedPaths>,
+    path: &str,
+) -> Result<(), Error> {
+    match allowed {
+        Some(allowed) => allowed.check(doc.root_value(), path),
+        None => Ok(()),
+    }
+}
+
+/// Fail if a value differs from the original outside of the allowed paths.
+///
+/// Used for actions that don't modify a single path, like `apply`.
+pub fn check_allowed_changes(
+    original: &Value,
+    result: &Value,
+    allowed: Option<&AllowedPaths>,
+) -> Result<(), Error> {
+    let Some(allowed) = allowed else {
+        return Ok(());
+    };
+    match allowed.disallowed_change(&mut Vec::new(), Some(original), Some(result)) {
+        Some(path) => Err(allowed.error(&path)),
+        None => Ok(()),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(content: &str) -> AllowedPaths {
+        AllowedPaths::parse("acl.txt", content)
+    }
+
+    #[test]
+    fn test_parse_skips_comments_and_blank_lines() {
+        let acl = allowed("# bot\n\n  image.tag  \nx\\.y\n");
+        let paths: Vec<Vec<&str>> = acl
+            .paths
+            .iter()
+            .map(|path| path.iter().map(|(part, _)| part.as_str()).collect())
+            .collect();
+        assert_eq!(paths, vec![vec!["image", "tag"], vec!["x.y"]]);
+    }
+
+    #[test]
+    fn test_check_paths() {
+        let value: Value = "image:\n  tag: 1\n  name: x\nc:\n- a: 1\n- a: 2\n"
+            .parse()
+            .unwrap();
+        let acl = allowed("image.tag\nc.*.a\n");
+        let check = |path| check_allowed(&value, Some(&acl), path);
+        assert!(check("image.tag").is_ok());
+        assert!(check("image.tag.x").is_ok());
+        assert!(check("c.-1.a").is_ok());
+        assert!(check("image").is_err());
+        assert!(check("image.name").is_err());
+        assert!(check("c.0").is_err());
+        assert!(check("").is_err());
+        assert!(check_allowed(&value, None, "image").is_ok());
+
+        let value: Value = "c:\n  '*': 1\n  d: 2\n".parse().unwrap();
+        let acl = allowed("c.\\*\n");
+        assert!(check_allowed(&value, Some(&acl), r"c.\*").is_ok());
+        assert!(check_allowed(&value, Some(&acl), "c.d").is_err());
+    }
+
+    #[test]
+    fn test_check_changes() {
+        let original: Value = "a:\n  b: 1\n  c: 2\nl: [1, 2]\n".parse().unwrap();
+        let acl = allowed("a.b\nl.1\n");
+        let changed = |text: &str| {
+            let result: Value = text.parse().unwrap();
+            check_allowed_changes(&original, &result, Some(&acl)).map_err(|e| e.to_string())
+        };
+        assert!(changed("a:\n  b: 5\n  c: 2\nl: [1, 3]\n").is_ok());
+        assert!(changed("a:\n  c: 2\nl: [1, 2]\n").is_ok());
+        assert!(changed("a:\n  b: 1\n  c: 3\nl: [1, 2]\n")
+            .unwrap_err()
+            .contains("'a.c'"));
+        assert!(changed("a:\n  b: 1\n  c: 2\nl: [1, 2]\nd: 1\n")
+            .unwrap_err()
+            .contains("'d'"));
+        assert!(changed("a:\n  b: 1\nl: [1, 2]\n")
+            .unwrap_err()
+            .contains("'a.c'"));
+        assert!(changed("a:\n  b: 1\n  c: 2\nl: [1, 2, 3]\n")
+            .unwrap_err()
+            .contains("'l.2'"));
+    }
+}
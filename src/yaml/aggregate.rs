@@ -0,0 +1,233 @@
+//! Aggregation of numbers.
+//!
+//! Provides the `sum`, `min`, `max` and `avg` actions, reducing the
+//! elements of a sequence, the values of a mapping or every node matched by
+//! a path with `*` elements, and `count-where`, counting the elements of a
+//! sequence matching a condition.
+
+use super::error::Error;
+use super::expr::{arithmetic, BinOp};
+use super::filter::{expand_wildcards, has_wildcard, select_where};
+use super::path::escape_path_element;
+use super::query::{get_at_path, type_error_seq_or_struct, value_to_type_name};
+use super::serialize::serialize_raw;
+use super::update::{as_f64, as_i64};
+use super::{InnerValue, Options};
+use fyaml::{Number, Value};
+
+/// Reduction applied to the numbers found at a path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,
+    Min,
+    Max,
+    /// Arithmetic mean, always a float
+    Avg,
+}
+
+impl Aggregate {
+    fn name(self) -> &'static str {
+        match self {
+            Aggregate::Sum => "sum",
+            Aggregate::Min => "min",
+            Aggregate::Max => "max",
+            Aggregate::Avg => "avg",
+        }
+    }
+}
+
+/// Numbers found at `path`, with their own path for error messages.
+fn numbers<'a>(
+    op: Aggregate,
+    path: Option<&str>,
+    value: &'a Value,
+    opts: &Options,
+) -> Result<Vec<(String, &'a Number)>, Error> {
+    let nodes: Vec<(String, &Value)> = match path {
+        Some(path) if has_wildcard(path) => expand_wildcards(value, path)
+            .into_iter()
+            .map(|path| Ok((path.clone(), get_at_path(value, Some(&path), opts)?)))
+            .collect::<Result<_, Error>>()?,
+        _ => {
+            let target = get_at_path(value, path, opts)?;
+            let prefix = path.map_or(String::new(), |path| format!("{}.", path));
+            match target.inner() {
+                Value::Sequence(seq) => seq
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (format!("{}{}", prefix, i), v))
+                    .collect(),
+                Value::Mapping(map) => map
+                    .iter()
+                    .map(|(k, v)| {
+                        let key = escape_path_element(&serialize_raw(k));
+                        (format!("{}{}", prefix, key), v)
+                    })
+                    .collect(),
+                _ => return Err(type_error_seq_or_struct(op.name(), target)),
+            }
+        }
+    };
+    nodes
+        .into_iter()
+        .map(|(path, node)| match node.inner() {
+            Value::Number(n) => Ok((path, n)),
+            other => Err(Error::type_error_at(
+                &path,
+                format!(
+                    "{} does not support '{}' type at '{}'.",
+                    op.name(),
+                    value_to_type_name(other),
+                    path
+                ),
+            )),
+        })
+        .collect()
+}
+
+/// Whether `a` comes before `b` in ascending order.
+fn less_or_equal(a: &Number, b: &Number) -> bool {
+    match (as_i64(a), as_i64(b)) {
+        (Some(x), Some(y)) => x <= y,
+        _ => as_f64(a) <= as_f64(b),
+    }
+}
+
+/// Reduce the numbers found at `path`: the elements of a sequence, the
+/// values of a mapping, or the nodes matched by a path with `*` elements.
+///
+/// Sums of integers stay integers. The sum of no numbers is 0.
+///
+/// # Errors
+///
+/// Returns an error if the path doesn't exist, designates a scalar, if a
+/// value isn't a number, if an integer sum overflows, or if there is no
+/// number for `min`, `max` and `avg`.
+pub fn aggregate(
+    op: Aggregate,
+    path: Option<&str>,
+    value: &Value,
+    opts: &Options,
+) -> Result<Value, Error> {
+    let numbers = numbers(op, path, value, opts)?;
+    let Some(((_, first), rest)) = numbers.split_first() else {
+        return match op {
+            Aggregate::Sum => Ok(Value::Number(Number::Int(0))),
+            _ => Err(Error::Base(format!(
+                "{} needs at least one number",
+                op.name()
+            ))),
+        };
+    };
+    let result = match op {
+        Aggregate::Sum => {
+            let mut sum = Value::Number((*first).clone());
+            for (_, n) in rest {
+                let Value::Number(total) = &sum else {
+                    unreachable!("sums of numbers are numbers")
+                };
+                sum = arithmetic(BinOp::Add, total, n)?;
+            }
+            return Ok(sum);
+        }
+        Aggregate::Min | Aggregate::Max => rest.iter().fold(*first, |best, (_, n)| {
+            if less_or_equal(best, n) == (op == Aggregate::Min) {
+                best
+            } else {
+                *n
+            }
+        }),
+        Aggregate::Avg => {
+            let sum: f64 = numbers.iter().map(|(_, n)| as_f64(n)).sum();
+            return Ok(Value::Number(Number::Float(sum / numbers.len() as f64)));
+        }
+    };
+    Ok(Value::Number(result.clone()))
+}
+
+/// Number of elements of the sequence at `path` matching `condition`, as
+/// `select-where` selects them.
+///
+/// # Errors
+///
+/// Returns an error in the cases `select-where` does.
+pub fn count_where(
+    path: Option<&str>,
+    condition: &str,
+    value: &Value,
+    opts: &Options,
+) -> Result<Value, Error> {
+    match select_where(path, condition, value, opts)? {
+        Value::Sequence(seq) => Ok(Value::Number(Number::Int(seq.len() as i64))),
+        _ => unreachable!("select-where gives a sequence"),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn inventory() -> Value {
+        "resources:\n  web: {replicas: 2, cpu: 0.5}\n  api: {replicas: 3, cpu: 1.5}\n\
+         sizes: [4, -1, 7]\nnames: {a: x}\nnone: []\n"
+            .parse()
+            .unwrap()
+    }
+
+    fn run(op: Aggregate, path: &str) -> Result<String, Error> {
+        aggregate(op, Some(path), &inventory(), &Options::default()).map(|v| serialize_raw(&v))
+    }
+
+    #[test]
+    fn test_sum() {
+        assert_eq!(run(Aggregate::Sum, "sizes").unwrap(), "10");
+        assert_eq!(run(Aggregate::Sum, "resources.*.replicas").unwrap(), "5");
+        assert_eq!(run(Aggregate::Sum, "resources.*.cpu").unwrap(), "2");
+        assert_eq!(run(Aggregate::Sum, "none").unwrap(), "0");
+    }
+
+    #[test]
+    fn test_min_max_avg() {
+        assert_eq!(run(Aggregate::Min, "sizes").unwrap(), "-1");
+        assert_eq!(run(Aggregate::Max, "sizes").unwrap(), "7");
+        assert_eq!(run(Aggregate::Max, "resources.*.cpu").unwrap(), "1.5");
+        assert_eq!(run(Aggregate::Avg, "resources.*.replicas").unwrap(), "2.5");
+    }
+
+    #[test]
+    fn test_mapping_values() {
+        let value: Value = "a: 1\nb: 2\n".parse().unwrap();
+        assert_eq!(
+            serialize_raw(&aggregate(Aggregate::Sum, None, &value, &Options::default()).unwrap()),
+            "3"
+        );
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = run(Aggregate::Sum, "names").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "sum does not support 'str' type at 'names.a'."
+        );
+        let err = run(Aggregate::Min, "none").unwrap_err();
+        assert_eq!(err.to_string(), "min needs at least one number");
+        let err = run(Aggregate::Avg, "sizes.0").unwrap_err();
+        assert!(err.to_string().contains("avg does not support 'int' type"));
+    }
+
+    #[test]
+    fn test_count_where() {
+        let value: Value = "- {state: up}\n- {state: down}\n- {state: up}\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            count_where(None, "state=up", &value, &Options::default()).unwrap(),
+            Value::Number(Number::Int(2))
+        );
+    }
+}
@@ -0,0 +1,610 @@
+//! Anchors and aliases of output documents.
+//!
+//! Provides the `--factor-anchors` output: every mapping or sequence that
+//! appears more than once in a document is emitted once with an anchor, and
+//! as an alias everywhere else. `get-value --keep-aliases` emits instead
+//! the anchors and aliases written in the input, found in its text.
+//! `list-anchors` and `get-anchor` find the anchors a document defines.
+
+use super::error::Error;
+use super::lint::{alias_marks, anchor_marks, document_lines};
+use super::path::join_elements;
+use super::regions::line_values;
+use super::serialize::{serialize, serialize_raw};
+use fyaml::Value;
+use std::collections::{HashMap, HashSet};
+
+/// Serialize a value to YAML, emitting repeated subtrees as anchor and aliases.
+///
+/// The output is the same as `serialize()` when nothing is repeated.
+pub fn serialize_factored(value: &Value) -> Result<String, Error> {
+    let mut counts = HashMap::new();
+    count_subtrees(value, &mut counts);
+
+    // Only count the copies that stay visible: the ones nested in an alias
+    // are emitted by their anchor.
+    let mut repeated = HashMap::new();
+    count_visible(value, &counts, &mut HashSet::new(), &mut repeated);
+    repeated.retain(|_, count| *count > 1);
+
+    if repeated.is_empty() || has_complex_keys(value) {
+        return serialize(value);
+    }
+    emit(
+        value,
+        Names::Factored {
+            repeated,
+            anchors: HashMap::new(),
+        },
+    )
+}
+
+/// Anchors and aliases of a document as written in its text, by path.
+///
+/// Aliases are resolved into copies of their anchored node when the
+/// document is parsed, these tell where they were.
+#[derive(Debug, Clone, Default)]
+pub struct SourceAliases {
+    /// Name of the anchor held by the node at each path
+    anchors: HashMap<String, String>,
+    /// Name of the anchor the alias at each path refers to
+    aliases: HashMap<String, String>,
+}
+
+impl SourceAliases {
+    fn is_empty(&self) -> bool {
+        self.anchors.is_empty() && self.aliases.is_empty()
+    }
+
+    /// The anchors and aliases nested in `node`, one of the nodes of
+    /// `root`, by path from it.
+    ///
+    /// None are left when `node` isn't found in `root`, or for the anchor
+    /// or alias of `node` itself.
+    pub fn select(&self, root: &Value, node: &Value) -> SourceAliases {
+        let mut path = Vec::new();
+        if self.is_empty() || !locate(root, node, &mut path) {
+            return SourceAliases::default();
+        }
+        if path.is_empty() {
+            return self.clone();
+        }
+        let prefix = format!("{}.", join_elements(&path));
+        let nested = |names: &HashMap<String, String>| -> HashMap<String, String> {
+            names
+                .iter()
+                .filter_map(|(path, name)| {
+                    Some((path.strip_prefix(&prefix)?.to_string(), name.clone()))
+                })
+                .collect()
+        };
+        SourceAliases {
+            anchors: nested(&self.anchors),
+            aliases: nested(&self.aliases),
+        }
+    }
+}
+
+/// Anchors and aliases of each document of a stream, as written.
+///
+/// As with [`anchor_paths`], only those of the root and of block style
+/// entries are found.
+pub fn source_aliases(text: &str) -> Vec<SourceAliases> {
+    let lines: Vec<&str> = text.lines().collect();
+    document_lines(text)
+        .into_iter()
+        .map(|range| {
+            let text = lines[range].join("\n");
+            let by_path = |marks| -> HashMap<String, String> {
+                mark_paths(&text, marks)
+                    .into_iter()
+                    .map(|(name, path)| (path, name))
+                    .collect()
+            };
+            SourceAliases {
+                anchors: by_path(anchor_marks(&text)),
+                aliases: by_path(alias_marks(&text)),
+            }
+        })
+        .collect()
+}
+
+/// Anchors of each document of a stream, as written, listed as
+/// [`anchor_paths`] does.
+pub fn source_anchors(text: &str) -> Vec<Vec<(String, String)>> {
+    let lines: Vec<&str> = text.lines().collect();
+    document_lines(text)
+        .into_iter()
+        .map(|range| anchor_paths(&lines[range].join("\n")))
+        .collect()
+}
+
+/// Serialize a value keeping the anchors and aliases of its source.
+///
+/// A node the source has an alias for is emitted as an alias when its
+/// anchor was emitted before on an equal node. It is emitted in full if it
+/// changed since, and with the anchor if the anchored node isn't part of
+/// the value. Equal nodes that weren't aliases in the source stay copies.
+pub fn serialize_aliased(value: &Value, source: &SourceAliases) -> Result<String, Error> {
+    if source.is_empty() || has_complex_keys(value) {
+        return serialize(value);
+    }
+    emit(
+        value,
+        Names::Source {
+            source,
+            emitted: HashMap::new(),
+        },
+    )
+}
+
+/// Anchors defined in the text of a document, with the path of the
+/// node holding each of them, in document order.
+///
+/// Nodes don't keep their anchor once parsed, so they are found in the
+/// text: only the anchors of the root and of block style entries are
+/// listed, not the ones nested in flow collections. A name defined twice
+/// is listed twice, aliases referring to the last definition.
+pub fn anchor_paths(text: &str) -> Vec<(String, String)> {
+    mark_paths(text, anchor_marks(text))
+}
+
+/// Names of anchors or aliases found in a text, with the path of the node
+/// each of them is on.
+fn mark_paths(text: &str, marks: Vec<(&str, usize, usize)>) -> Vec<(String, String)> {
+    let lines: Vec<&str> = text.lines().collect();
+    let values = line_values(text);
+    marks
+        .into_iter()
+        .filter_map(|(name, line, column)| {
+            let text = lines[line - 1];
+            let start: usize = text.chars().take(column - 1).map(char::len_utf8).sum();
+            let path = match &values[line - 1] {
+                _ if text.starts_with("--- ") && text[4..start].trim().is_empty() => String::new(),
+                // The anchor may follow a tag: `key: !tag &name value`
+                Some((path, value_column))
+                    if *value_column <= start && is_tag(&text[*value_column..start]) =>
+                {
+                    path.clone()
+                }
+                _ => return None,
+            };
+            Some((name.to_string(), path))
+        })
+        .collect()
+}
+
+/// Find the path of `node`, one of the nodes of `root`.
+fn locate(root: &Value, node: &Value, path: &mut Vec<String>) -> bool {
+    if std::ptr::eq(root, node) {
+        return true;
+    }
+    match root {
+        Value::Mapping(map) => {
+            for (key, child) in map {
+                path.push(serialize_raw(key));
+                if locate(child, node, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+        Value::Sequence(seq) => {
+            for (index, child) in seq.iter().enumerate() {
+                path.push(index.to_string());
+                if locate(child, node, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+        Value::Tagged(tagged) => locate(&tagged.value, node, path),
+        _ => false,
+    }
+}
+
+/// Whether the text before an anchor is empty or a single tag.
+fn is_tag(text: &str) -> bool {
+    let text = text.trim_end();
+    text.is_empty() || (text.starts_with('!') && !text.contains(' '))
+}
+
+/// Non-empty mappings and sequences, the only nodes worth an anchor.
+fn children(value: &Value) -> Option<Vec<&Value>> {
+    match value {
+        Value::Mapping(map) if !map.is_empty() => Some(map.values().collect()),
+        Value::Sequence(seq) if !seq.is_empty() => Some(seq.iter().collect()),
+        _ => None,
+    }
+}
+
+fn count_subtrees<'a>(value: &'a Value, counts: &mut HashMap<&'a Value, usize>) {
+    if let Some(children) = children(value) {
+        *counts.entry(value).or_default() += 1;
+        for child in children {
+            count_subtrees(child, counts);
+        }
+    }
+}
+
+fn count_visible<'a>(
+    value: &'a Value,
+    counts: &HashMap<&'a Value, usize>,
+    seen: &mut HashSet<&'a Value>,
+    visible: &mut HashMap<&'a Value, usize>,
+) {
+    let Some(children) = children(value) else {
+        return;
+    };
+    if counts[value] > 1 {
+        *visible.entry(value).or_default() += 1;
+        if !seen.insert(value) {
+            return;
+        }
+    }
+    for child in children {
+        count_visible(child, counts, seen, visible);
+    }
+}
+
+fn has_complex_keys(value: &Value) -> bool {
+    match value {
+        Value::Mapping(map) => map.iter().any(|(k, v)| {
+            matches!(k, Value::Mapping(_) | Value::Sequence(_) | Value::Tagged(_))
+                || has_complex_keys(v)
+        }),
+        Value::Sequence(seq) => seq.iter().any(has_complex_keys),
+        _ => false,
+    }
+}
+
+/// Turn a mapping key into an anchor name.
+fn anchor_base(key: Option<&Value>) -> String {
+    let name: String = match key {
+        Some(Value::String(s)) => s
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+            .collect(),
+        _ => String::new(),
+    };
+    if name.is_empty() {
+        "anchor".to_string()
+    } else {
+        name
+    }
+}
+/// How a node is emitted.
+enum Reference {
+    /// As an alias of an already emitted node
+    Alias,
+    /// With an anchor, before its aliases
+    Anchor,
+    /// As is
+    Plain,
+}
+
+/// The nodes emitted as anchor and aliases.
+enum Names<'a> {
+    /// Repeated subtrees, anchors named after their key
+    Factored {
+        /// Subtrees to emit as anchor and aliases
+        repeated: HashMap<&'a Value, usize>,
+        /// Anchor names of the subtrees already emitted
+        anchors: HashMap<&'a Value, String>,
+    },
+    /// Anchors and aliases of the source, found by path
+    Source {
+        source: &'a SourceAliases,
+        /// Node last emitted with each anchor
+        emitted: HashMap<&'a str, &'a Value>,
+    },
+}
+
+/// Emit a mapping or sequence in block style with anchors and aliases.
+fn emit<'a>(value: &'a Value, names: Names<'a>) -> Result<String, Error> {
+    let mut emitter = Emitter {
+        names,
+        path: Vec::new(),
+        out: String::new(),
+    };
+    match value {
+        Value::Mapping(_) => emitter.mapping(value, 0)?,
+        Value::Sequence(_) => emitter.sequence(value, 0)?,
+        _ => return serialize(value),
+    }
+    Ok(emitter.out.trim_end_matches('\n').to_string())
+}
+
+/// Block style emitter following the layout of `serialize()`.
+struct Emitter<'a> {
+    names: Names<'a>,
+    /// Path of the node being emitted
+    path: Vec<String>,
+    out: String,
+}
+
+impl<'a> Emitter<'a> {
+    /// Emit the anchor or alias of a node, if any.
+    fn reference(&mut self, value: &'a Value, key: Option<&Value>) -> Reference {
+        let name = match &mut self.names {
+            Names::Factored { repeated, anchors } => {
+                if !repeated.contains_key(value) {
+                    return Reference::Plain;
+                }
+                if let Some(name) = anchors.get(value) {
+                    self.out.push_str(&format!(" *{}", name));
+                    return Reference::Alias;
+                }
+
+                let base = anchor_base(key);
+                let mut name = base.clone();
+                let mut n = 1;
+                while anchors.values().any(|a| *a == name) {
+                    n += 1;
+                    name = format!("{}{}", base, n);
+                }
+                anchors.insert(value, name.clone());
+                name
+            }
+            Names::Source { source, emitted } => {
+                let source: &'a SourceAliases = source;
+                let path = join_elements(&self.path);
+                let name = match (source.aliases.get(&path), source.anchors.get(&path)) {
+                    (Some(name), _) => match emitted.get(name.as_str()) {
+                        Some(anchored) if *anchored == value => {
+                            self.out.push_str(&format!(" *{}", name));
+                            return Reference::Alias;
+                        }
+                        // Changed since it was read
+                        Some(_) => return Reference::Plain,
+                        // The anchor isn't part of the value, the first
+                        // alias takes its place
+                        None => name,
+                    },
+                    (None, Some(name)) => name,
+                    (None, None) => return Reference::Plain,
+                };
+                emitted.insert(name.as_str(), value);
+                name.clone()
+            }
+        };
+        self.out.push_str(&format!(" &{}", name));
+        Reference::Anchor
+    }
+
+    fn mapping(&mut self, value: &'a Value, indent: usize) -> Result<(), Error> {
+        let Value::Mapping(map) = value else {
+            unreachable!("mapping() is only called on mappings");
+        };
+        for (key, child) in map {
+            self.out.push_str(&format!(
+                "{:indent$}{}:",
+                "",
+                serialize(key)?,
+                indent = indent
+            ));
+            self.path.push(serialize_raw(key));
+            self.value(child, indent, Some(key))?;
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    fn sequence(&mut self, value: &'a Value, indent: usize) -> Result<(), Error> {
+        let Value::Sequence(seq) = value else {
+            unreachable!("sequence() is only called on sequences");
+        };
+        for (index, child) in seq.iter().enumerate() {
+            self.out
+                .push_str(&format!("{:indent$}-", "", indent = indent));
+            self.path.push(index.to_string());
+            self.value(child, indent, None)?;
+            self.path.pop();
+        }
+        Ok(())
+    }
+
+    /// Emit a value following `key:` (or `-` without key) at `indent`.
+    fn value(&mut self, value: &'a Value, indent: usize, key: Option<&Value>) -> Result<(), Error> {
+        let anchored = match self.reference(value, key) {
+            Reference::Alias => {
+                self.out.push('\n');
+                return Ok(());
+            }
+            Reference::Anchor => true,
+            Reference::Plain => false,
+        };
+        if children(value).is_none() {
+            let text = serialize(value)?;
+            let text = text
+                .trim_end_matches('\n')
+                .replace('\n', &format!("\n{:indent$}", "", indent = indent + 2));
+            self.out.push_str(&format!(" {}\n", text));
+            return Ok(());
+        }
+
+        // Sequences below a key are not indented, like serialize() does
+        let nested = if key.is_some() && matches!(value, Value::Sequence(_)) {
+            indent
+        } else {
+            indent + 2
+        };
+        if key.is_some() || anchored {
+            self.out.push('\n');
+        }
+
+        let body_start = self.out.len();
+        match value {
+            Value::Mapping(_) => self.mapping(value, nested)?,
+            _ => self.sequence(value, nested)?,
+        }
+        // Plain nodes nested in a sequence start on the line of their `-`
+        if key.is_none() && !anchored {
+            self.out.replace_range(body_start..body_start + nested, " ");
+        }
+        Ok(())
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::document_to_value;
+    use fyaml::FyParser;
+
+    /// Parse with aliases resolved, like documents read from stdin.
+    fn parse(yaml: &str) -> Value {
+        let doc = FyParser::from_string(yaml)
+            .unwrap()
+            .doc_iter()
+            .next()
+            .unwrap()
+            .unwrap();
+        document_to_value(&doc).unwrap()
+    }
+
+    #[test]
+    fn test_no_repetition_is_plain_serialize() {
+        let value = parse("a:\n- - 1\n  - 2\n- x: 1\n  y:\n  - 1\nb: {}\n");
+        assert_eq!(
+            serialize_factored(&value).unwrap(),
+            serialize(&value).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_repeated_mapping_becomes_alias() {
+        let value = parse("dev:\n  image: web\n  port: 80\nprod:\n  image: web\n  port: 80\n");
+        assert_eq!(
+            serialize_factored(&value).unwrap(),
+            "dev: &dev\n  image: web\n  port: 80\nprod: *dev"
+        );
+    }
+
+    #[test]
+    fn test_repeated_subtree_in_sequence() {
+        let value = parse("- a: 1\n  b: 2\n- a: 1\n  b: 2\n- [x]\n");
+        assert_eq!(
+            serialize_factored(&value).unwrap(),
+            "- &anchor\n  a: 1\n  b: 2\n- *anchor\n- - x"
+        );
+    }
+
+    #[test]
+    fn test_nested_repetitions_use_outer_anchor() {
+        let value = parse("a:\n  b:\n    c: 1\nd:\n  b:\n    c: 1\n");
+        assert_eq!(
+            serialize_factored(&value).unwrap(),
+            "a: &a\n  b:\n    c: 1\nd: *a"
+        );
+    }
+
+    #[test]
+    fn test_output_parses_back_to_same_value() {
+        let value = parse(
+            "x:\n  list: &l\n  - 1\n  - {k: v}\n  other: *l\ny:\n- - {k: v}\n  - 2\n- {k: v}\n",
+        );
+        let output = serialize_factored(&value).unwrap();
+        assert!(output.contains('&'), "output: {}", output);
+        assert_eq!(parse(&output), value);
+    }
+
+    #[test]
+    fn test_anchor_names_are_unique() {
+        let value = Value::Sequence(vec![parse("a: [1]\nb: [1]\n"), parse("a: [2]\nb: [2]\n")]);
+        let output = serialize_factored(&value).unwrap();
+        assert!(
+            output.contains("&a\n") && output.contains("&a2\n"),
+            "output: {}",
+            output
+        );
+        assert_eq!(parse(&output), value);
+    }
+
+    #[test]
+    fn test_anchor_paths() {
+        let text = "--- &root\nbase: &base\n  image: web\nlist:\n- &first a\n- b\n\
+                    tagged: !!str &t x\nflow: {k: &nested v}\nlast: &base 1\n";
+        let paths = anchor_paths(text);
+        let paths: Vec<(&str, &str)> = paths
+            .iter()
+            .map(|(n, p)| (n.as_str(), p.as_str()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                ("root", ""),
+                ("base", "base"),
+                ("first", "list.0"),
+                ("t", "tagged"),
+                ("base", "last"),
+            ]
+        );
+    }
+
+    /// Serialize the value of the first document keeping its aliases.
+    fn aliased(yaml: &str) -> String {
+        serialize_aliased(&parse(yaml), &source_aliases(yaml)[0]).unwrap()
+    }
+
+    #[test]
+    fn test_source_aliases_keep_their_names() {
+        assert_eq!(
+            aliased("base: &base_cfg\n  image: web\ndev: *base_cfg\nname: &n x\nother: *n\n"),
+            "base: &base_cfg\n  image: web\ndev: *base_cfg\nname: &n x\nother: *n"
+        );
+    }
+
+    #[test]
+    fn test_equal_nodes_without_alias_stay_copies() {
+        assert_eq!(
+            aliased("a: &a\n  x: 1\nb:\n  x: 1\nc: *a\nd:\n- 1\ne:\n- 1\n"),
+            "a: &a\n  x: 1\nb:\n  x: 1\nc: *a\nd:\n- 1\ne:\n- 1"
+        );
+        let yaml = "a:\n  x: 1\nb:\n  x: 1\n";
+        assert_eq!(aliased(yaml), serialize(&parse(yaml)).unwrap());
+    }
+
+    #[test]
+    fn test_changed_alias_is_emitted_in_full() {
+        let yaml = "a: &a\n  x: 1\nb: *a\nc: *a\n";
+        let mut value = parse(yaml);
+        *value.get_mut("b").unwrap() = parse("x: 2\n");
+        assert_eq!(
+            serialize_aliased(&value, &source_aliases(yaml)[0]).unwrap(),
+            "a: &a\n  x: 1\nb:\n  x: 2\nc: *a"
+        );
+    }
+
+    #[test]
+    fn test_selected_aliases() {
+        let yaml = "base: &base\n  image: web\nenvs:\n  dev: *base\n  prod: *base\n";
+        let value = parse(yaml);
+        let envs = &value["envs"];
+        let source = source_aliases(yaml)[0].select(&value, envs);
+        // The anchor isn't selected, the first alias takes its place
+        assert_eq!(
+            serialize_aliased(envs, &source).unwrap(),
+            "dev: &base\n  image: web\nprod: *base"
+        );
+    }
+
+    #[test]
+    fn test_source_aliases_of_each_document() {
+        let text =
+            "# comment\na: &x [1]\nb: *x\n---\n- &y {k: v}\n- *y\n...\n%YAML 1.2\n--- &r\nc: *x\n";
+        let sources = source_aliases(text);
+        assert_eq!(sources.len(), 3);
+        assert_eq!(sources[0].aliases["b"], "x");
+        assert_eq!(sources[1].anchors["0"], "y");
+        assert_eq!(sources[1].aliases["1"], "y");
+        assert_eq!(sources[2].anchors[""], "r");
+        assert_eq!(sources[2].aliases["c"], "x");
+    }
+}
@@ -0,0 +1,143 @@
+//! Provenance header of generated documents.
+//!
+//! The header is a block of comment lines at the top of the document:
+//!
+//! ```text
+//! # DO NOT EDIT: managed by TOOL
+//! # generated: 2024-01-31T12:00:00Z
+//! # sources: base.yaml, overlay.yaml
+//! ```
+//!
+//! Annotating an already annotated document replaces its header.
+
+use super::error::Error;
+use fyaml::{Document, FyParser};
+use time::macros::format_description;
+use time::OffsetDateTime;
+
+/// First line of the header, followed by the tool name.
+const BANNER: &str = "# DO NOT EDIT: managed by ";
+const GENERATED: &str = "# generated: ";
+const SOURCES: &str = "# sources: ";
+
+/// Current UTC time for the header.
+///
+/// `SOURCE_DATE_EPOCH` overrides the current time, for reproducible output.
+/// Without it, a `deterministic` timestamp is the Unix epoch.
+pub fn annotation_timestamp(deterministic: bool) -> Result<String, Error> {
+    let now = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch
+            .trim()
+            .parse::<i64>()
+            .ok()
+            .and_then(|secs| OffsetDateTime::from_unix_timestamp(secs).ok())
+            .ok_or_else(|| {
+                Error::Base(format!(
+                    "invalid SOURCE_DATE_EPOCH '{}', expected a number of seconds.",
+                    epoch
+                ))
+            })?,
+        Err(_) if deterministic => OffsetDateTime::UNIX_EPOCH,
+        Err(_) => OffsetDateTime::now_utc(),
+    };
+    now.format(format_description!(
+        "[year]-[month]-[day]T[hour]:[minute]:[second]Z"
+    ))
+    .map_err(|e| Error::Base(e.to_string()))
+}
+
+/// Insert the provenance header at the top of a document's text, replacing
+/// a previous one.
+pub fn annotate(text: &str, tool: &str, sources: &[String], timestamp: &str) -> String {
+    let (start, body) = match text.strip_prefix("---\n") {
+        Some(body) => ("---\n", body),
+        None => ("", text),
+    };
+
+    let mut lines = body.lines().peekable();
+    if lines.peek().is_some_and(|line| line.starts_with(BANNER)) {
+        lines.next();
+        while lines
+            .peek()
+            .is_some_and(|line| line.starts_with(GENERATED) || line.starts_with(SOURCES))
+        {
+            lines.next();
+        }
+    }
+
+    let mut out = format!("{}{}{}\n{}{}\n", start, BANNER, tool, GENERATED, timestamp);
+    if !sources.is_empty() {
+        out.push_str(&format!("{}{}\n", SOURCES, sources.join(", ")));
+    }
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+    out
+}
+
+/// Insert the provenance header at the top of a document, replacing a
+/// previous one.
+///
+/// The document is emitted and parsed back, so that the header is kept as a
+/// comment by later mutations.
+pub fn annotate_doc(
+    doc: &mut Document,
+    tool: &str,
+    sources: &[String],
+    timestamp: &str,
+) -> Result<(), Error> {
+    if doc.root().is_none() {
+        return Err(Error::Base(
+            "cannot annotate an empty document.".to_string(),
+        ));
+    }
+    let text = annotate(&doc.emit()?, tool, sources, timestamp);
+    if let Some(annotated) = FyParser::from_string(&text)?.doc_iter().next() {
+        *doc = annotated?;
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TS: &str = "2024-01-31T12:00:00Z";
+
+    #[test]
+    fn test_annotate_inserts_header() {
+        assert_eq!(
+            annotate("# app\na: 1\n", "gen", &[], TS),
+            "# DO NOT EDIT: managed by gen\n# generated: 2024-01-31T12:00:00Z\n# app\na: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_annotate_replaces_header() {
+        let first = annotate("a: 1\n", "gen", &["a.yaml".to_string()], TS);
+        let second = annotate(&first, "other", &[], "2025-01-01T00:00:00Z");
+        assert_eq!(
+            second,
+            "# DO NOT EDIT: managed by other\n# generated: 2025-01-01T00:00:00Z\na: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_annotate_keeps_document_start() {
+        assert_eq!(
+            annotate(
+                "---\na: 1\n",
+                "gen",
+                &["x.yaml".into(), "y.yaml".into()],
+                TS
+            ),
+            "---\n# DO NOT EDIT: managed by gen\n# generated: 2024-01-31T12:00:00Z\n\
+             # sources: x.yaml, y.yaml\na: 1\n"
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! `!!binary` scalars.
+//!
+//! Binary data is stored in YAML as the base64 text of a `!!binary`
+//! scalar: `get-value --binary` writes its decoded bytes, and
+//! `set-value --binary` stores bytes that way.
+
+use super::error::Error;
+use fyaml::{TaggedValue, Value};
+
+/// Tag of binary scalars
+pub const BINARY_TAG: &str = "!!binary";
+
+const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Standard base64 text of `bytes`, padded with `=`.
+fn encode(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, b)| n | (*b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+/// Bytes of a standard base64 text, ignoring whitespace as `!!binary`
+/// scalars are often split on several lines.
+fn decode(text: &str) -> Option<Vec<u8>> {
+    let digits: Vec<u8> = text.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+    if !digits.len().is_multiple_of(4) {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(digits.len() / 4 * 3);
+    for (index, chunk) in digits.chunks(4).enumerate() {
+        let last = index == digits.len() / 4 - 1;
+        let padding = chunk.iter().rev().take_while(|b| **b == b'=').count();
+        if padding > 2 || (padding > 0 && !last) {
+            return None;
+        }
+        let mut n = 0u32;
+        for (i, b) in chunk[..4 - padding].iter().enumerate() {
+            let digit = ALPHABET.iter().position(|a| a == b)? as u32;
+            n |= digit << (18 - 6 * i);
+        }
+        bytes.extend_from_slice(&n.to_be_bytes()[1..4 - padding]);
+    }
+    Some(bytes)
+}
+
+/// A `!!binary` scalar holding `bytes`.
+pub fn binary_value(bytes: &[u8]) -> Value {
+    Value::Tagged(Box::new(TaggedValue {
+        tag: BINARY_TAG.to_string(),
+        value: Value::String(encode(bytes)),
+    }))
+}
+
+/// A `!!binary` scalar holding the content of a file.
+pub fn binary_from_file(file: &str) -> Result<Value, Error> {
+    let content =
+        std::fs::read(file).map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+    Ok(binary_value(&content))
+}
+
+/// Decode the base64 text of a binary scalar found at `path`.
+///
+/// The `!!binary` tag isn't required, base64 text stored in plain strings
+/// decodes as well.
+///
+/// # Errors
+///
+/// Returns an error if the text isn't valid base64.
+pub fn decode_binary(text: &str, path: &str) -> Result<Vec<u8>, Error> {
+    decode(text).ok_or_else(|| {
+        Error::Base(format!(
+            "invalid binary value at '{}', expected base64 text.",
+            path
+        ))
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(b""), "");
+        assert_eq!(encode(b"f"), "Zg==");
+        assert_eq!(encode(b"fo"), "Zm8=");
+        assert_eq!(encode(b"foo"), "Zm9v");
+        assert_eq!(encode(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode(&[0xff, 0xfe, 0x00]), "//4A");
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("Zm9vYmE=").unwrap(), b"fooba");
+        assert_eq!(decode("Zm9v\n  YmFy\n").unwrap(), b"foobar");
+        assert_eq!(decode("//4A").unwrap(), [0xff, 0xfe, 0x00]);
+        assert!(decode("Zm9").is_none());
+        assert!(decode("Zm==Zm9v").is_none());
+        assert!(decode("Zm9*").is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let bytes: Vec<u8> = (0..=255).collect();
+        let Value::Tagged(tagged) = binary_value(&bytes) else {
+            panic!("expected a tagged value");
+        };
+        assert_eq!(tagged.tag, BINARY_TAG);
+        let Value::String(text) = &tagged.value else {
+            panic!("expected a string");
+        };
+        assert_eq!(decode_binary(text, "cert").unwrap(), bytes);
+        assert_eq!(
+            decode_binary("!", "cert").unwrap_err().to_string(),
+            "invalid binary value at 'cert', expected base64 text."
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! Differences between two versions of a document.
+//!
+//! Used by `--diff` to show what a mutation changes, either as a unified
+//! diff of the emitted YAML or as one line per changed leaf:
+//!
+//! ```text
+//! ~ spec.replicas: 2 -> 3
+//! + image.tag: 1.25
+//! - debug: true
+//! ```
+
+use super::flatten::{leaves, FlattenNode};
+use super::path::join_elements;
+use fyaml::Value;
+use indexmap::IndexMap;
+use similar::TextDiff;
+
+/// Lines of context around each change of a unified diff.
+const CONTEXT: usize = 3;
+
+/// Unified diff between two texts, empty if they are the same.
+///
+/// Both sides are named `label` in the header.
+pub fn unified_diff(label: &str, before: &str, after: &str) -> String {
+    TextDiff::from_lines(before, after)
+        .unified_diff()
+        .context_radius(CONTEXT)
+        .header(label, label)
+        .to_string()
+}
+
+/// Raw value of each leaf of a document, by path (`.` for the root).
+fn leaf_values(value: &Value) -> IndexMap<String, String> {
+    if matches!(value, Value::Null) {
+        return IndexMap::new();
+    }
+    leaves(value)
+        .into_iter()
+        .map(|(elements, leaf)| {
+            let path = match join_elements(&elements) {
+                path if path.is_empty() => ".".to_string(),
+                path => path,
+            };
+            (path, leaf.raw())
+        })
+        .collect()
+}
+
+/// Changed leaves between two documents, one per line: `~ PATH: OLD -> NEW`
+/// for a modified value, `+ PATH: NEW` for an added one and `- PATH: OLD`
+/// for a removed one. Empty if nothing changed.
+pub fn path_diff(before: &Value, after: &Value) -> String {
+    let before = leaf_values(before);
+    let after = leaf_values(after);
+    let mut out = String::new();
+    for (path, new) in &after {
+        match before.get(path) {
+            Some(old) if old == new => {}
+            Some(old) => out.push_str(&format!("~ {}: {} -> {}\n", path, old, new)),
+            None => out.push_str(&format!("+ {}: {}\n", path, new)),
+        }
+    }
+    for (path, old) in &before {
+        if !after.contains_key(path) {
+            out.push_str(&format!("- {}: {}\n", path, old));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unified_diff() {
+        let before = "a: 1\nb: 2\nc: 3\nd: 4\ne: 5\nf: 6\ng: 7\nh: 8\ni: 9\nj: 10\n";
+        let after = "a: 1\nb: 3\nc: 3\nd: 4\ne: 5\nf: 6\ng: 7\nh: 8\ni: 9\nj: 10\nk: 11\n";
+        assert_eq!(
+            unified_diff("x.yaml", before, after),
+            "--- x.yaml\n+++ x.yaml\n\
+             @@ -1,5 +1,5 @@\n a: 1\n-b: 2\n+b: 3\n c: 3\n d: 4\n e: 5\n\
+             @@ -8,3 +8,4 @@\n h: 8\n i: 9\n j: 10\n+k: 11\n"
+        );
+        assert_eq!(unified_diff("x.yaml", before, before), "");
+        assert_eq!(
+            unified_diff("x.yaml", "", "a: 1\n"),
+            "--- x.yaml\n+++ x.yaml\n@@ -0,0 +1 @@\n+a: 1\n"
+        );
+    }
+
+    #[test]
+    fn test_path_diff() {
+        let before: Value = "a: {b: 1, c: 2}\nl: [x, y]\nd: true\n".parse().unwrap();
+        let after: Value = "a: {b: 5, c: 2}\nl: [x]\ne: new\n".parse().unwrap();
+        assert_eq!(
+            path_diff(&before, &after),
+            "~ a.b: 1 -> 5\n+ e: new\n- l.1: y\n- d: true\n"
+        );
+        assert_eq!(path_diff(&before, &before), "");
+        assert_eq!(path_diff(&Value::Null, &after).lines().count(), 4);
+        let scalar: Value = "1".parse().unwrap();
+        assert_eq!(path_diff(&scalar, &after).lines().last(), Some("- .: 1"));
+    }
+}
@@ -5,142 +5,138 @@
 //! allocated, preserving comments and formatting.
 
 use super::error::Error;
-use super::path::{resolve_index, split_path};
-use fyaml::Document;
+use super::filter::{deletion_order, resolve_filters};
+use super::mutation::{
+    insert_position, is_rename, move_paths, rename_exists_error, type_error_insert,
+    type_error_rename,
+};
+use super::navigate::{check_new_key_ref, new_key, walk_steps, walk_steps_to, Step};
+use super::path::{join_elements, split_path};
+use super::query::{get_value_ref, value_ref_type_name};
+use super::serialize::serialize;
+use super::style::key_text;
+use super::Options;
+use fyaml::{Document, NodeRef, TaggedValue, Value, ValueRef};
+
+/// Element of an Editor path designating the entry of a mapping key: a
+/// scalar key quoted, not to be read as an alias or escaped, as libfyaml
+/// compares scalar keys by text, a mapping or sequence key as flow YAML.
+fn key_segment(key: &ValueRef<'_>) -> Result<String, Error> {
+    match key.as_str() {
+        Some(text) if key.is_scalar() => Ok(format!("'{}'", text.replace('\'', "''"))),
+        _ => key_text(&Value::from_node_ref(key.as_node())?),
+    }
+}
 
-/// Convert shyaml dot-notation path to fyaml slash-notation path.
-///
-/// shyaml paths: `a.b.c`, `a\.b` (escaped dot), `a\\b` (escaped backslash)
-/// fyaml paths: `/a/b/c`, `/a.b`, `/a\b`
-///
-/// Note: Empty segments are preserved (valid YAML keys).
-fn dot_path_to_slash_path(dot_path: &str) -> String {
-    let parts = split_path(dot_path);
-    if parts.is_empty() || (parts.len() == 1 && parts[0].is_empty()) {
-        // Root path
-        return String::new();
+/// Editor path of the node reached through `steps` from the root.
+fn editor_path(steps: &[Step<'_>]) -> Result<String, Error> {
+    let mut path = String::new();
+    for step in steps {
+        path.push('/');
+        match step {
+            Step::Key(key) => path.push_str(&key_segment(key)?),
+            Step::Index(index) => path.push_str(&index.to_string()),
+        }
     }
-    // Join with / and prepend /
-    format!("/{}", parts.join("/"))
+    Ok(path)
 }
 
-/// Split a path into parent path and final key.
-///
-/// Examples:
-/// - `a.b.c` -> (`a.b`, `c`)
-/// - `a` -> (``, `a`)
-/// - `` -> error
-fn split_parent_and_key(dot_path: &str) -> Result<(String, String), Error> {
-    let parts = split_path(dot_path);
-    if parts.is_empty() || (parts.len() == 1 && parts[0].is_empty()) {
-        return Err(Error::Path("Empty path".to_string()));
+/// Editor path of the node a path designates in the document, its filter
+/// elements being already resolved.
+fn existing_path(doc: &Document, dot_path: &str, opts: &Options) -> Result<String, Error> {
+    let root = get_value_ref(None, doc, opts)?;
+    let (steps, _) = walk_steps_to(root, &split_path(dot_path), dot_path, opts)?;
+    editor_path(&steps)
+}
+
+/// Resolve the filter elements of a path against the document.
+fn resolve_filters_doc(doc: &Document, dot_path: &str) -> Result<String, Error> {
+    match doc.root_value() {
+        Some(root) => resolve_filters(root, dot_path),
+        None => Ok(dot_path.to_string()),
     }
-    if parts.len() == 1 {
-        return Ok((String::new(), parts[0].clone()));
+}
+
+/// Paths to delete in turn so as to remove the nodes `dot_paths` designate
+/// in the document, wildcards expanded.
+#[must_use]
+pub fn deletion_paths_doc(doc: &Document, dot_paths: &[String]) -> Vec<String> {
+    match doc.root_value() {
+        Some(root) => deletion_order(root, dot_paths),
+        None => dot_paths.to_vec(),
     }
-    let key = parts.last().unwrap().clone();
-    let parent_parts = &parts[..parts.len() - 1];
-    let parent = parent_parts.join(".");
-    Ok((parent, key))
 }
 
-/// Ensure all ancestor mappings exist for a given path.
-///
-/// Creates empty mappings for any missing intermediate keys.
-/// Also replaces null values with empty mappings when encountered.
-/// Returns error if a non-null scalar exists at an intermediate path.
-fn ensure_ancestors(doc: &mut Document, dot_path: &str) -> Result<(), Error> {
-    let parts = split_path(dot_path);
-    if parts.len() <= 1 {
-        // No ancestors to create (root level key)
-        return Ok(());
+/// Emit a node as YAML text that can be set elsewhere in the document.
+pub(super) fn node_yaml(node: NodeRef<'_>) -> Result<String, Error> {
+    let text = node.emit()?;
+    // Empty values emit as nothing, which can't be parsed back
+    Ok(if text.is_empty() {
+        "null".to_string()
+    } else {
+        text
+    })
+}
+
+/// YAML text of a key of the document, as written.
+fn key_yaml(key: &ValueRef<'_>) -> Result<String, Error> {
+    if key.is_scalar() {
+        node_yaml(key.as_node())
+    } else {
+        key_text(&Value::from_node_ref(key.as_node())?)
     }
+}
 
-    // Check/create each ancestor level
-    let mut current_path = String::new();
-    for part in &parts[..parts.len() - 1] {
-        if current_path.is_empty() {
-            current_path = part.clone();
-        } else {
-            current_path = format!("{}.{}", current_path, part);
-        }
+/// YAML text of a block mapping entry, from the YAML text of its key and
+/// value.
+fn entry_yaml(key: &str, value: &str) -> String {
+    let value: Vec<String> = value
+        .trim_end()
+        .lines()
+        .map(|l| format!("  {}", l))
+        .collect();
+    format!("{}:\n{}", key, value.join("\n"))
+}
 
-        let slash_path = dot_path_to_slash_path(&current_path);
-
-        // Check if this path exists and what type it is
-        let node_at_path = doc.at_path(&slash_path);
-
-        match node_at_path {
-            Some(node) => {
-                // Path exists - check if it's a null scalar that needs to be replaced
-                if node.is_scalar() {
-                    let is_null = node.scalar_bytes().map(|b| b.is_empty()).unwrap_or(false);
-                    if is_null {
-                        // It's null - replace with empty mapping
-                        let mut ed = doc.edit();
-                        ed.set_yaml_at(&slash_path, "__placeholder__: ~")
-                            .map_err(|e| {
-                                Error::Base(format!(
-                                    "Failed to replace null at '{}': {}",
-                                    current_path, e
-                                ))
-                            })?;
-                        let placeholder_path = format!("{}/__placeholder__", slash_path);
-                        ed.delete_at(&placeholder_path).map_err(|e| {
-                            Error::Base(format!("Failed to clean placeholder: {}", e))
-                        })?;
-                    }
-                    // else: non-null scalar - will error later when we try to set into it
-                }
-                // else: it's a mapping or sequence - that's fine, continue
-            }
-            None => {
-                // Need to create this mapping
-                // First ensure its parent exists (recursive base case: root always exists or is created)
-                let (parent_dot, key) = split_parent_and_key(&current_path)?;
-                let parent_slash = if parent_dot.is_empty() {
-                    String::new()
-                } else {
-                    dot_path_to_slash_path(&parent_dot)
-                };
-
-                // Check if we need to create root first
-                if parent_slash.is_empty() && doc.root().is_none() {
-                    // Create empty root mapping using block style YAML
-                    let mut ed = doc.edit();
-                    let root = ed.build_from_yaml("__placeholder__: ~").map_err(|e| {
-                        Error::Base(format!("Failed to create root mapping: {}", e))
-                    })?;
-                    ed.set_root(root)
-                        .map_err(|e| Error::Base(format!("Failed to set root: {}", e)))?;
-                    ed.delete_at("/__placeholder__")
-                        .map_err(|e| Error::Base(format!("Failed to clean placeholder: {}", e)))?;
-                }
+/// YAML text of the nested mappings that path elements add to lead to
+/// `value`.
+fn nested_yaml(parts: &[String], value: &str) -> Result<String, Error> {
+    parts
+        .iter()
+        .rev()
+        .try_fold(value.to_string(), |text, part| {
+            Ok(entry_yaml(&key_text(&new_key(part))?, &text))
+        })
+}
 
-                // Now create the mapping at this level using block-style YAML
-                let target_path = if parent_slash.is_empty() {
-                    format!("/{}", key)
-                } else {
-                    format!("{}/{}", parent_slash, key)
-                };
-
-                let mut ed = doc.edit();
-                ed.set_yaml_at(&target_path, "__placeholder__: ~")
-                    .map_err(|e| {
-                        Error::Base(format!(
-                            "Failed to create mapping at '{}': {}",
-                            current_path, e
-                        ))
-                    })?;
-                // Remove the placeholder to leave an empty block mapping
-                let placeholder_path = format!("{}/__placeholder__", target_path);
-                ed.delete_at(&placeholder_path)
-                    .map_err(|e| Error::Base(format!("Failed to clean placeholder: {}", e)))?;
-            }
+/// Text of a new key the Editor can add itself, creating keys from the
+/// text of a path element: a scalar written plain, without the `/` ending
+/// the element.
+fn plain_key(key: &Value) -> Option<String> {
+    let text = key_text(key).ok()?;
+    let plain = matches!(
+        key,
+        Value::Null | Value::Bool(_) | Value::Number(_) | Value::String(_)
+    ) && !text.starts_with(['\'', '"'])
+        && !text.contains('/');
+    plain.then_some(text)
+}
+
+/// YAML text of a mapping of the document with entries added, given as
+/// the YAML text of their keys and values. A flow mapping is rewritten in
+/// block style.
+fn with_entries(map: ValueRef<'_>, entries: &[(String, String)]) -> Result<String, Error> {
+    let mut lines = Vec::new();
+    let text = node_yaml(map.as_node())?;
+    if text.starts_with('{') {
+        for (key, value) in map.map_iter() {
+            lines.push(entry_yaml(&key_yaml(&key)?, &node_yaml(value.as_node())?));
         }
+    } else {
+        lines.push(text.trim_end().to_string());
     }
-
-    Ok(())
+    lines.extend(entries.iter().map(|(key, value)| entry_yaml(key, value)));
+    Ok(lines.join("\n"))
 }
 
 /// Set a value at a path in the document using Editor.
@@ -153,25 +149,23 @@ fn ensure_ancestors(doc: &mut Document, dot_path: &str) -> Result<(), Error> {
 /// * `dot_path` - Path in dot notation (e.g., `a.b.c`)
 /// * `value_str` - The value to set
 /// * `parse_as_yaml` - If true, parse value_str as YAML; if false, treat as literal string
+/// * `opts` - Options for the path lookup
 pub fn set_value_doc(
     doc: &mut Document,
     dot_path: &str,
     value_str: &str,
     parse_as_yaml: bool,
+    opts: &Options,
 ) -> Result<(), Error> {
-    let parts = split_path(dot_path);
-    if parts.is_empty() || (parts.len() == 1 && parts[0].is_empty()) {
-        return Err(Error::Path("Empty path".to_string()));
-    }
-
     // Prepare the YAML value
     // Always normalize through Value for consistent block style output
     let yaml_value = if parse_as_yaml {
         // Parse as YAML, convert to Value, then re-emit for consistent style
         let v: fyaml::Value = value_str
             .parse()
-            .map_err(|e| Error::Base(format!("Failed to parse YAML value: {}", e)))?;
-        v.to_yaml_string()
+            .map_err(|e| Error::parse_in("YAML value", value_str, e))?;
+        shorthand_tags(v)
+            .to_yaml_string()
             .map_err(|e| Error::Base(format!("Failed to serialize value: {}", e)))?
             .trim()
             .to_string()
@@ -193,136 +187,302 @@ pub fn set_value_doc(
             .to_string()
     };
 
-    // Handle empty document - create root mapping using block style
-    if doc.root().is_none() {
-        let mut ed = doc.edit();
-        // Use placeholder to create block-style mapping, then remove placeholder
-        let root = ed
-            .build_from_yaml("__placeholder__: ~")
-            .map_err(|e| Error::Base(format!("Failed to create root mapping: {}", e)))?;
-        ed.set_root(root)
-            .map_err(|e| Error::Base(format!("Failed to set root: {}", e)))?;
-        ed.delete_at("/__placeholder__")
-            .map_err(|e| Error::Base(format!("Failed to clean placeholder: {}", e)))?;
-    }
+    set_yaml_value_doc(doc, dot_path, &yaml_value, opts)
+}
 
-    // Check if parent is a scalar (invalid target for set, unless it's null)
-    let key = parts.last().unwrap();
-    if parts.len() > 1 {
-        let parent_parts = &parts[..parts.len() - 1];
-        let parent_slash_path = format!("/{}", parent_parts.join("/"));
-
-        if let Some(parent) = doc.at_path(&parent_slash_path) {
-            if parent.is_scalar() {
-                // Check if it's a null scalar (empty bytes) - if so, we can replace it with a mapping
-                let is_null = parent.scalar_bytes().map(|b| b.is_empty()).unwrap_or(false);
-                if is_null {
-                    // It's null - replace with empty mapping
-                    let parent_dot_path = parent_parts.join(".");
-                    let mut ed = doc.edit();
-                    ed.set_yaml_at(&parent_slash_path, "__placeholder__: ~")
-                        .map_err(|e| {
-                            Error::Base(format!(
-                                "Failed to replace null at '{}': {}",
-                                parent_dot_path, e
-                            ))
-                        })?;
-                    let placeholder_path = format!("{}/__placeholder__", parent_slash_path);
-                    ed.delete_at(&placeholder_path)
-                        .map_err(|e| Error::Base(format!("Failed to clean placeholder: {}", e)))?;
-                } else {
-                    // Non-null scalar - can't set into it
-                    return Err(Error::Path(format!(
-                        "invalid path '{}', cannot set value on scalar at '{}'.",
-                        dot_path, key
-                    )));
-                }
-            }
+/// `value` with the tags of the YAML core schema, that parsing expands to
+/// `tag:yaml.org,2002:binary`, back to their `!!binary` shorthand.
+fn shorthand_tags(value: Value) -> Value {
+    match value {
+        Value::Mapping(map) => Value::Mapping(
+            map.into_iter()
+                .map(|(k, v)| (shorthand_tags(k), shorthand_tags(v)))
+                .collect(),
+        ),
+        Value::Sequence(seq) => Value::Sequence(seq.into_iter().map(shorthand_tags).collect()),
+        Value::Tagged(t) => {
+            let TaggedValue { tag, value } = *t;
+            let tag = match tag.strip_prefix("tag:yaml.org,2002:") {
+                Some(name) => format!("!!{}", name),
+                None => tag,
+            };
+            Value::Tagged(Box::new(TaggedValue {
+                tag,
+                value: shorthand_tags(value),
+            }))
         }
+        other => other,
     }
+}
 
-    // Ensure ancestors exist for mapping paths (fyaml handles sequences directly)
-    ensure_ancestors(doc, dot_path)?;
+/// Set already formatted YAML text at a path in the document using Editor.
+///
+/// Unlike `set_value_doc()`, `yaml_value` is used as is, without being
+/// normalized through `Value`. Missing keys on the way are added, and a
+/// null on the way is replaced, with mappings.
+pub(super) fn set_yaml_value_doc(
+    doc: &mut Document,
+    dot_path: &str,
+    yaml_value: &str,
+    opts: &Options,
+) -> Result<(), Error> {
+    let dot_path = &resolve_filters_doc(doc, dot_path)?;
+    let parts = split_path(dot_path);
+    if parts.is_empty() || (parts.len() == 1 && parts[0].is_empty()) {
+        return Err(Error::path_error("Empty path".to_string()));
+    }
 
-    let slash_path = dot_path_to_slash_path(dot_path);
+    let (path, text) = match doc.root_value() {
+        None => (String::new(), nested_yaml(&parts, yaml_value)?),
+        Some(root) => {
+            let (steps, node) = walk_steps(root, &parts, dot_path, opts)?;
+            let path = editor_path(&steps)?;
+            match parts[steps.len()..].split_first() {
+                None => (path, yaml_value.to_string()),
+                Some(_) if node.is_null() => {
+                    (path, nested_yaml(&parts[steps.len()..], yaml_value)?)
+                }
+                Some((part, rest)) if node.is_mapping() => {
+                    let key = new_key(part);
+                    check_new_key_ref(node, &key, part, dot_path)?;
+                    let value = nested_yaml(rest, yaml_value)?;
+                    match plain_key(&key) {
+                        Some(key) => (format!("{}/{}", path, key), value),
+                        None => (path, with_entries(node, &[(key_text(&key)?, value)])?),
+                    }
+                }
+                Some((part, _)) => {
+                    return Err(Error::path_error_at(
+                        dot_path,
+                        format!(
+                            "invalid path '{}', cannot set value on scalar at '{}'.",
+                            dot_path, part
+                        ),
+                    ))
+                }
+            }
+        }
+    };
 
     let mut ed = doc.edit();
-    ed.set_yaml_at(&slash_path, &yaml_value)
+    ed.set_yaml_at(&path, &text)
         .map_err(|e| Error::Base(format!("Failed to set value at '{}': {}", dot_path, e)))?;
 
     Ok(())
 }
 
-/// Delete a value at a path in the document using Editor.
+/// Set a value at a path in the document only if nothing exists there yet.
 ///
-/// This is the Editor-based equivalent of `del()`.
+/// This is the Editor-based equivalent of `ensure()`.
+pub fn ensure_doc(
+    doc: &mut Document,
+    dot_path: &str,
+    default: &Value,
+    opts: &Options,
+) -> Result<(), Error> {
+    match get_value_ref(Some(dot_path), doc, opts) {
+        Ok(_) => Ok(()),
+        Err(Error::Path { .. }) => {
+            set_yaml_value_doc(doc, dot_path, serialize(default)?.trim_end(), opts)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Insert a value in the sequence at a path in the document (the root if
+/// empty), creating the sequence if missing.
 ///
-/// # Arguments
-/// * `doc` - The document to modify
-/// * `dot_path` - Path in dot notation
-pub fn del_doc(doc: &mut Document, dot_path: &str) -> Result<(), Error> {
-    if dot_path.is_empty() {
-        return Err(Error::Path("Empty path".to_string()));
+/// This is the Editor-based equivalent of `insert()`. The Editor can only
+/// append, so the elements after the insertion point are re-appended after
+/// the new one.
+pub fn insert_doc(
+    doc: &mut Document,
+    dot_path: &str,
+    index: Option<&str>,
+    value: &Value,
+    opts: &Options,
+) -> Result<(), Error> {
+    let dot_path = &resolve_filters_doc(doc, dot_path)?;
+    let item = serialize(value)?;
+
+    let parts = if dot_path.is_empty() {
+        Vec::new()
+    } else {
+        split_path(dot_path)
+    };
+    let target = match doc.root_value() {
+        Some(root) => {
+            let (steps, node) = walk_steps(root, &parts, dot_path, opts)?;
+            (steps.len() == parts.len() && !node.is_null())
+                .then(|| editor_path(&steps).map(|path| (path, node)))
+                .transpose()?
+        }
+        None => None,
+    };
+    let Some((slash_path, target)) = target else {
+        let seq = serialize(&Value::Sequence(vec![value.clone()]))?;
+        if dot_path.is_empty() {
+            let mut ed = doc.edit();
+            let root = ed
+                .build_from_yaml(&seq)
+                .map_err(|e| Error::Base(format!("Failed to create root sequence: {}", e)))?;
+            return ed
+                .set_root(root)
+                .map_err(|e| Error::Base(format!("Failed to set root: {}", e)));
+        }
+        return set_yaml_value_doc(doc, dot_path, seq.trim_end(), opts);
+    };
+    if !target.is_sequence() {
+        return Err(type_error_insert(dot_path, value_ref_type_name(&target)));
     }
 
+    let len = target.seq_len().unwrap_or(0);
+    let position = match index {
+        Some(index) => insert_position(index, len, dot_path)?,
+        None => len,
+    };
+    let tail = (position..len)
+        .map(|i| match doc.at_path(&format!("{}/{}", slash_path, i)) {
+            Some(node) => node_yaml(node),
+            None => Err(Error::Base(format!(
+                "Missing element {} of '{}'",
+                i, dot_path
+            ))),
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    let mut ed = doc.edit();
+    for i in (position..len).rev() {
+        ed.delete_at(&format!("{}/{}", slash_path, i))
+            .map_err(|e| Error::Base(format!("Failed to delete at '{}': {}", dot_path, e)))?;
+    }
+    for text in std::iter::once(item.as_str()).chain(tail.iter().map(String::as_str)) {
+        let node = ed
+            .build_from_yaml(text)
+            .map_err(|e| Error::Base(format!("Failed to build element: {}", e)))?;
+        ed.seq_append_at(&slash_path, node)
+            .map_err(|e| Error::Base(format!("Failed to insert at '{}': {}", dot_path, e)))?;
+    }
+    Ok(())
+}
+
+/// Rename the key at a path in the document, keeping its value and position.
+///
+/// This is the Editor-based equivalent of `rename_key()`. The Editor adds
+/// keys at the end of mappings, so the entries after the renamed one are
+/// re-added after it.
+pub fn rename_key_doc(
+    doc: &mut Document,
+    dot_path: &str,
+    new_name: &str,
+    opts: &Options,
+) -> Result<(), Error> {
+    if dot_path.is_empty() {
+        return Err(Error::path_error("Empty path".to_string()));
+    }
+    let dot_path = &resolve_filters_doc(doc, dot_path)?;
     let parts = split_path(dot_path);
-    if parts.is_empty() || (parts.len() == 1 && parts[0].is_empty()) {
-        return Err(Error::Path("Empty path".to_string()));
+    let parent_parts = &parts[..parts.len() - 1];
+    let root = get_value_ref(None, doc, opts)?;
+    let (steps, _) = walk_steps_to(root, &parts, dot_path, opts)?;
+    let (parent_steps, parent) = walk_steps_to(root, parent_parts, dot_path, opts)?;
+    let Some(Step::Key(renamed)) = steps.last().filter(|_| parent.is_mapping()) else {
+        return Err(type_error_rename(dot_path, value_ref_type_name(&parent)));
+    };
+    if renamed.is_scalar() && renamed.as_str() == Some(new_name) {
+        return Ok(());
+    }
+    if parent
+        .map_iter()
+        .any(|(key, _)| key.is_scalar() && key.as_str() == Some(new_name))
+    {
+        return Err(rename_exists_error(dot_path, new_name));
     }
 
-    // Check if the parent is a sequence - if so, validate index before delete
-    let key = parts.last().unwrap();
-    if !parts.is_empty() {
-        // Get parent path
-        let parent_slash_path = if parts.len() == 1 {
-            String::new() // Root level
+    let renamed = key_segment(renamed)?;
+    let mut tail: Vec<(String, String, String)> = Vec::new();
+    for (key, value) in parent.map_iter() {
+        let segment = key_segment(&key)?;
+        let key = if segment == renamed {
+            key_text(&Value::String(new_name.to_string()))?
+        } else if tail.is_empty() {
+            continue;
         } else {
-            let parent_parts = &parts[..parts.len() - 1];
-            format!("/{}", parent_parts.join("/"))
+            key_yaml(&key)?
         };
+        tail.push((segment, key, node_yaml(value.as_node())?));
+    }
+    let parent_path = editor_path(&parent_steps)?;
 
-        // Get parent node to check if it's a sequence
-        let parent_node = if parent_slash_path.is_empty() {
-            doc.root()
-        } else {
-            doc.at_path(&parent_slash_path)
-        };
+    let mut ed = doc.edit();
+    for (segment, _, _) in &tail {
+        ed.delete_at(&format!("{}/{}", parent_path, segment))
+            .map_err(|e| Error::Base(format!("Failed to delete at '{}': {}", dot_path, e)))?;
+    }
+    // Re-added as text, for the Editor to keep keys as written
+    let root = get_value_ref(None, doc, opts)?;
+    let (_, parent) = walk_steps_to(root, parent_parts, dot_path, opts)?;
+    let entries: Vec<(String, String)> = tail.into_iter().map(|(_, k, v)| (k, v)).collect();
+    let text = with_entries(parent, &entries)?;
+    let mut ed = doc.edit();
+    ed.set_yaml_at(&parent_path, &text)
+        .map_err(|e| Error::Base(format!("Failed to rename '{}': {}", dot_path, e)))?;
+    Ok(())
+}
 
-        if let Some(parent) = parent_node {
-            if parent.is_sequence() {
-                // Validate index using resolve_index for proper error messages
-                let seq_len = parent.seq_len().unwrap_or(0);
-                let resolved_idx = resolve_index(key, seq_len, dot_path)?;
-
-                // Build path with resolved index for deletion
-                let slash_path = if parent_slash_path.is_empty() {
-                    format!("/{}", resolved_idx)
-                } else {
-                    format!("{}/{}", parent_slash_path, resolved_idx)
-                };
-
-                let mut ed = doc.edit();
-                ed.delete_at(&slash_path).map_err(|e| {
-                    Error::Base(format!("Failed to delete at '{}': {}", dot_path, e))
-                })?;
-
-                return Ok(());
-            }
-        }
+/// Move the value at a path of the document to another path, replacing what
+/// was there.
+///
+/// This is the Editor-based equivalent of `move_value()`.
+pub fn move_doc(doc: &mut Document, src: &str, dst: &str, opts: &Options) -> Result<(), Error> {
+    let root = get_value_ref(None, doc, opts)?;
+    let (src_parts, dst_parts) = move_paths(root, src, dst)?;
+    let (src, dst) = (join_elements(&src_parts), join_elements(&dst_parts));
+    if is_rename(root, &src_parts, &dst_parts) {
+        return rename_key_doc(doc, &src, &dst_parts[dst_parts.len() - 1], opts);
+    }
+    let value = node_yaml(get_value_ref(Some(&src), doc, opts)?.as_node())?;
+    set_yaml_value_doc(doc, &dst, &value, opts)?;
+    del_doc(doc, &src, opts)
+}
+
+/// Copy the value at a path of the document to another path, replacing what
+/// was there.
+///
+/// This is the Editor-based equivalent of `copy_value()`. Comments of the
+/// copied value are copied too.
+pub fn copy_doc(doc: &mut Document, src: &str, dst: &str, opts: &Options) -> Result<(), Error> {
+    let value = node_yaml(get_value_ref(Some(src), doc, opts)?.as_node())?;
+    set_yaml_value_doc(doc, dst, &value, opts)
+}
+
+/// Delete a value at a path in the document using Editor.
+///
+/// This is the Editor-based equivalent of `del()`.
+///
+/// # Arguments
+/// * `doc` - The document to modify
+/// * `dot_path` - Path in dot notation
+/// * `opts` - Options for the path lookup
+pub fn del_doc(doc: &mut Document, dot_path: &str, opts: &Options) -> Result<(), Error> {
+    if dot_path.is_empty() {
+        return Err(Error::path_error("Empty path".to_string()));
     }
 
-    // Not a sequence or parent doesn't exist - use original path
-    let slash_path = dot_path_to_slash_path(dot_path);
+    let dot_path = &resolve_filters_doc(doc, dot_path)?;
+    let path = existing_path(doc, dot_path, opts)?;
+    if path.is_empty() {
+        return Err(Error::path_error("Empty path".to_string()));
+    }
 
     let mut ed = doc.edit();
     let deleted = ed
-        .delete_at(&slash_path)
+        .delete_at(&path)
         .map_err(|e| Error::Base(format!("Failed to delete at '{}': {}", dot_path, e)))?;
-
     if !deleted {
-        return Err(Error::Path(format!(
-            "invalid path '{}', missing key '{}' in struct.",
-            dot_path, key
+        return Err(Error::Base(format!(
+            "Failed to delete at '{}': not found",
+            dot_path
         )));
     }
 
@@ -337,56 +497,14 @@ pub fn del_doc(doc: &mut Document, dot_path: &str) -> Result<(), Error> {
 mod tests {
     use super::*;
 
-    #[test]
-    fn test_dot_path_to_slash_path_simple() {
-        assert_eq!(dot_path_to_slash_path("a"), "/a");
-        assert_eq!(dot_path_to_slash_path("a.b"), "/a/b");
-        assert_eq!(dot_path_to_slash_path("a.b.c"), "/a/b/c");
-    }
-
-    #[test]
-    fn test_dot_path_to_slash_path_escaped_dot() {
-        // `a\.b` in shyaml means key "a.b"
-        assert_eq!(dot_path_to_slash_path(r"a\.b"), "/a.b");
-        assert_eq!(dot_path_to_slash_path(r"a\.b.c"), "/a.b/c");
-    }
-
-    #[test]
-    fn test_dot_path_to_slash_path_empty_segments() {
-        // Empty string is a valid YAML key
-        assert_eq!(dot_path_to_slash_path("a..b"), "/a//b");
-        assert_eq!(dot_path_to_slash_path(".a"), "//a");
-    }
-
-    #[test]
-    fn test_dot_path_to_slash_path_root() {
-        assert_eq!(dot_path_to_slash_path(""), "");
-    }
-
-    #[test]
-    fn test_split_parent_and_key() {
-        let (parent, key) = split_parent_and_key("a.b.c").unwrap();
-        assert_eq!(parent, "a.b");
-        assert_eq!(key, "c");
-
-        let (parent, key) = split_parent_and_key("a").unwrap();
-        assert_eq!(parent, "");
-        assert_eq!(key, "a");
-
-        let (parent, key) = split_parent_and_key("a.b").unwrap();
-        assert_eq!(parent, "a");
-        assert_eq!(key, "b");
-    }
-
-    #[test]
-    fn test_split_parent_and_key_empty_error() {
-        assert!(split_parent_and_key("").is_err());
+    fn opts() -> Options {
+        Options::default()
     }
 
     #[test]
     fn test_set_value_doc_simple() {
         let mut doc = Document::parse_str("name: old").unwrap();
-        set_value_doc(&mut doc, "name", "new", false).unwrap();
+        set_value_doc(&mut doc, "name", "new", false, &opts()).unwrap();
 
         let root = doc.root().unwrap();
         let name = root.at_path("/name").unwrap();
@@ -396,7 +514,7 @@ mod tests {
     #[test]
     fn test_set_value_doc_nested() {
         let mut doc = Document::parse_str("config: {}").unwrap();
-        set_value_doc(&mut doc, "config.host", "localhost", false).unwrap();
+        set_value_doc(&mut doc, "config.host", "localhost", false, &opts()).unwrap();
 
         let root = doc.root().unwrap();
         let host = root.at_path("/config/host").unwrap();
@@ -407,7 +525,7 @@ mod tests {
     fn test_set_value_doc_create_intermediate() {
         // Start with a document that has null root (empty YAML)
         let mut doc = Document::new().unwrap();
-        set_value_doc(&mut doc, "a.b.c", "deep", false).unwrap();
+        set_value_doc(&mut doc, "a.b.c", "deep", false, &opts()).unwrap();
 
         let root = doc.root().unwrap();
         let val = root.at_path("/a/b/c").unwrap();
@@ -417,7 +535,7 @@ mod tests {
     #[test]
     fn test_set_value_doc_yaml_mode() {
         let mut doc = Document::parse_str("data: {}").unwrap();
-        set_value_doc(&mut doc, "data.items", "[1, 2, 3]", true).unwrap();
+        set_value_doc(&mut doc, "data.items", "[1, 2, 3]", true, &opts()).unwrap();
 
         let root = doc.root().unwrap();
         let items = root.at_path("/data/items").unwrap();
@@ -428,7 +546,7 @@ mod tests {
     #[test]
     fn test_del_doc_simple() {
         let mut doc = Document::parse_str("a: 1\nb: 2").unwrap();
-        del_doc(&mut doc, "b").unwrap();
+        del_doc(&mut doc, "b", &opts()).unwrap();
 
         let root = doc.root().unwrap();
         assert!(root.at_path("/a").is_some());
@@ -438,7 +556,7 @@ mod tests {
     #[test]
     fn test_del_doc_nested() {
         let mut doc = Document::parse_str("config:\n  host: localhost\n  port: 5432").unwrap();
-        del_doc(&mut doc, "config.port").unwrap();
+        del_doc(&mut doc, "config.port", &opts()).unwrap();
 
         let root = doc.root().unwrap();
         assert!(root.at_path("/config/host").is_some());
@@ -448,7 +566,7 @@ mod tests {
     #[test]
     fn test_del_doc_missing_key_error() {
         let mut doc = Document::parse_str("a: 1").unwrap();
-        let result = del_doc(&mut doc, "nonexistent");
+        let result = del_doc(&mut doc, "nonexistent", &opts());
         assert!(result.is_err());
         let err = result.unwrap_err().to_string();
         assert!(err.contains("missing key 'nonexistent'"));
@@ -457,7 +575,7 @@ mod tests {
     #[test]
     fn test_del_doc_empty_path_error() {
         let mut doc = Document::parse_str("a: 1").unwrap();
-        let result = del_doc(&mut doc, "");
+        let result = del_doc(&mut doc, "", &opts());
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("Empty path"));
     }
@@ -484,7 +602,14 @@ fn test_set_value_doc_null_to_mapping() {
     );
 
     // Set a nested value - this should replace null with mapping
-    set_value_doc(&mut doc, "config.host", "localhost", false).unwrap();
+    set_value_doc(
+        &mut doc,
+        "config.host",
+        "localhost",
+        false,
+        &Options::default(),
+    )
+    .unwrap();
 
     // Verify config is now a mapping
     let config = doc.at_path("/config").unwrap();
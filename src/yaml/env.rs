@@ -0,0 +1,231 @@
+//! Environment variable interpolation in scalar values.
+//!
+//! Expands `${VAR}` and `${VAR:-default}` references found in string
+//! scalars. Bare `$VAR` forms are left untouched so that values that
+//! merely contain a dollar sign are not mangled.
+//!
+//! An [`EnvAllow`] list restricts the variables that can be expanded, so
+//! that secrets present in the environment don't end up in the output.
+
+use super::error::Error;
+use fyaml::{TaggedValue, Value};
+
+/// Variables that interpolation may read, by exact name or by prefix
+/// (given with a trailing `*`, as in `APP_*`).
+#[derive(Clone, Debug, Default)]
+pub struct EnvAllow {
+    names: Vec<String>,
+    prefixes: Vec<String>,
+}
+
+impl EnvAllow {
+    pub fn new(entries: &[String]) -> Result<Self, Error> {
+        let mut allow = EnvAllow::default();
+        for entry in entries {
+            match entry.strip_suffix('*') {
+                Some(prefix) if prefix.is_empty() || is_valid_name(prefix) => {
+                    allow.prefixes.push(prefix.to_string())
+                }
+                None if is_valid_name(entry) => allow.names.push(entry.clone()),
+                _ => {
+                    return Err(Error::Base(format!(
+                        "invalid environment variable name or prefix '{}'",
+                        entry
+                    )))
+                }
+            }
+        }
+        Ok(allow)
+    }
+
+    pub fn allows(&self, name: &str) -> bool {
+        self.names.iter().any(|n| n == name) || self.prefixes.iter().any(|p| name.starts_with(p))
+    }
+}
+
+/// Expand environment variable references in all string scalars of a value.
+///
+/// Mapping keys are left untouched. With `strict`, a reference to an
+/// undefined variable that has no default is an error; otherwise it
+/// expands to an empty string (as `envsubst` does). Variables outside of
+/// `allow` are taken as undefined.
+pub fn env_subst(value: Value, strict: bool, allow: Option<&EnvAllow>) -> Result<Value, Error> {
+    let lookup = |name: &str| match allow {
+        Some(allow) if !allow.allows(name) => None,
+        _ => std::env::var(name).ok(),
+    };
+    subst_value(value, &lookup, strict)
+}
+
+fn subst_value(
+    value: Value,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    strict: bool,
+) -> Result<Value, Error> {
+    match value {
+        Value::String(s) => Ok(Value::String(expand_str(&s, lookup, strict)?)),
+        Value::Sequence(seq) => seq
+            .into_iter()
+            .map(|v| subst_value(v, lookup, strict))
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::Sequence),
+        Value::Mapping(map) => {
+            let mut result = indexmap::IndexMap::with_capacity(map.len());
+            for (k, v) in map {
+                result.insert(k, subst_value(v, lookup, strict)?);
+            }
+            Ok(Value::Mapping(result))
+        }
+        Value::Tagged(t) => {
+            let TaggedValue { tag, value } = *t;
+            Ok(Value::Tagged(Box::new(TaggedValue {
+                tag,
+                value: subst_value(value, lookup, strict)?,
+            })))
+        }
+        other => Ok(other),
+    }
+}
+
+/// Expand `${VAR}` / `${VAR:-default}` references in a single string.
+fn expand_str(
+    input: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    strict: bool,
+) -> Result<String, Error> {
+    let mut out = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(start) = rest.find("${") {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            // Unterminated reference: keep the remainder verbatim
+            out.push_str(&rest[start..]);
+            return Ok(out);
+        };
+        let expr = &after[..end];
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+
+        if !is_valid_name(name) {
+            // Not a variable reference we understand: keep it verbatim
+            out.push_str(&rest[start..start + 2 + end + 1]);
+        } else {
+            match (lookup(name), default) {
+                (Some(v), Some(d)) if v.is_empty() => out.push_str(d),
+                (Some(v), _) => out.push_str(&v),
+                (None, Some(d)) => out.push_str(d),
+                (None, None) if strict => {
+                    return Err(Error::Base(format!(
+                        "undefined environment variable '{}'",
+                        name
+                    )))
+                }
+                (None, None) => {}
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_alphabetic() || c == '_' => {}
+        _ => return false,
+    }
+    chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fyaml::Number;
+    use indexmap::indexmap;
+
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOST" => Some("db.local".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand_simple() {
+        assert_eq!(
+            expand_str("http://${HOST}:80", &lookup, false).unwrap(),
+            "http://db.local:80"
+        );
+    }
+
+    #[test]
+    fn test_expand_default_when_unset_or_empty() {
+        assert_eq!(expand_str("${NOPE:-x}", &lookup, true).unwrap(), "x");
+        assert_eq!(expand_str("${EMPTY:-x}", &lookup, true).unwrap(), "x");
+        assert_eq!(expand_str("${HOST:-x}", &lookup, true).unwrap(), "db.local");
+    }
+
+    #[test]
+    fn test_expand_undefined_lenient_is_empty() {
+        assert_eq!(expand_str("a${NOPE}b", &lookup, false).unwrap(), "ab");
+    }
+
+    #[test]
+    fn test_expand_undefined_strict_errors() {
+        let err = expand_str("${NOPE}", &lookup, true).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("undefined environment variable 'NOPE'"));
+    }
+
+    #[test]
+    fn test_expand_leaves_other_forms_untouched() {
+        assert_eq!(expand_str("$HOST", &lookup, true).unwrap(), "$HOST");
+        assert_eq!(expand_str("${1abc}", &lookup, true).unwrap(), "${1abc}");
+        assert_eq!(expand_str("cost ${", &lookup, true).unwrap(), "cost ${");
+    }
+
+    #[test]
+    fn test_subst_value_only_touches_string_values() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("${HOST}".to_string()) => Value::String("${HOST}".to_string()),
+            Value::String("n".to_string()) => Value::Number(Number::Int(1)),
+            Value::String("l".to_string()) => Value::Sequence(vec![
+                Value::String("${NOPE:-d}".to_string()),
+            ]),
+        });
+        let result = subst_value(value, &lookup, false).unwrap();
+        assert_eq!(
+            result,
+            Value::Mapping(indexmap! {
+                Value::String("${HOST}".to_string()) => Value::String("db.local".to_string()),
+                Value::String("n".to_string()) => Value::Number(Number::Int(1)),
+                Value::String("l".to_string()) => Value::Sequence(vec![
+                    Value::String("d".to_string()),
+                ]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_env_allow() {
+        let allow = EnvAllow::new(&["HOME".to_string(), "APP_*".to_string()]).unwrap();
+        assert!(allow.allows("HOME"));
+        assert!(allow.allows("APP_NAME"));
+        assert!(!allow.allows("HOMEPATH"));
+        assert!(!allow.allows("AWS_SECRET_ACCESS_KEY"));
+        assert!(EnvAllow::new(&["*".to_string()]).unwrap().allows("ANY"));
+        assert!(EnvAllow::new(&["A-B".to_string()]).is_err());
+        assert!(EnvAllow::new(&["A*B".to_string()]).is_err());
+    }
+}
@@ -2,6 +2,7 @@
 
 use crate::tag::TagError;
 use fyaml::error::Error as FyError;
+use fyaml::ParseError;
 use std::io;
 use thiserror::Error;
 
@@ -9,20 +10,30 @@ use thiserror::Error;
 #[derive(Debug, Error)]
 pub enum Error {
     /// Error from fyaml library
-    #[error("{0}")]
+    #[error("{}", describe_fy(.0))]
     Fy(#[from] FyError),
 
     /// I/O error
     #[error("{0}")]
     Io(String),
 
-    /// Path navigation error
-    #[error("{0}")]
-    Path(String),
+    /// Path navigation error, about the node at `path` when known
+    #[error("{message}")]
+    Path {
+        message: String,
+        path: Option<String>,
+    },
+
+    /// Type mismatch error, about the node at `path` when known
+    #[error("{message}")]
+    Type {
+        message: String,
+        path: Option<String>,
+    },
 
-    /// Type mismatch error
+    /// Invalid input in a format other than YAML
     #[error("{0}")]
-    Type(String),
+    Parse(String),
 
     /// Generic error
     #[error("{0}")]
@@ -52,3 +63,101 @@ impl From<TagError> for Error {
         Error::Base(e.to_string())
     }
 }
+
+/// 1-based line and column of a parse error.
+///
+/// libfyaml locations are already 1-based, fyaml adds 1 again.
+pub fn parse_error_location(e: &ParseError) -> Option<(usize, usize)> {
+    e.location().map(|(line, column)| {
+        (
+            line.saturating_sub(1) as usize,
+            column.saturating_sub(1) as usize,
+        )
+    })
+}
+
+fn describe_fy(e: &FyError) -> String {
+    match e {
+        FyError::ParseError(p) => match parse_error_location(p) {
+            Some((line, column)) => format!("Parse error at {}:{}: {}", line, column, p.message()),
+            None => e.to_string(),
+        },
+        _ => e.to_string(),
+    }
+}
+
+impl Error {
+    /// Path error not about a given node.
+    pub fn path_error(message: impl Into<String>) -> Error {
+        Error::Path {
+            message: message.into(),
+            path: None,
+        }
+    }
+
+    /// Path error about the node at `path`.
+    pub fn path_error_at(path: &str, message: impl Into<String>) -> Error {
+        Error::Path {
+            message: message.into(),
+            path: Some(path.to_string()),
+        }
+    }
+
+    /// Type error not about a given node.
+    pub fn type_error(message: impl Into<String>) -> Error {
+        Error::Type {
+            message: message.into(),
+            path: None,
+        }
+    }
+
+    /// Type error about the node at `path`.
+    pub fn type_error_at(path: &str, message: impl Into<String>) -> Error {
+        Error::Type {
+            message: message.into(),
+            path: Some(path.to_string()),
+        }
+    }
+
+    /// Path of the node the error is about, if known.
+    #[must_use]
+    pub fn failing_path(&self) -> Option<&str> {
+        match self {
+            Error::Path { path, .. } | Error::Type { path, .. } => path.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Error parsing `input` described by `what`, showing the line where
+    /// parsing failed when the location is known.
+    pub fn parse_in(what: &str, input: &str, e: FyError) -> Error {
+        let mut message = format!("Failed to parse {}: {}", what, describe_fy(&e));
+        let location = match &e {
+            FyError::ParseError(p) => parse_error_location(p),
+            _ => None,
+        };
+        // Errors found at the end of the input point past its last line
+        let location =
+            location.and_then(
+                |(line, column)| match input.lines().nth(line.saturating_sub(1)) {
+                    Some(text) => Some((line, column, text)),
+                    None => input
+                        .lines()
+                        .enumerate()
+                        .last()
+                        .map(|(n, text)| (n + 1, text.chars().count() + 1, text)),
+                },
+            );
+        if let Some((line, column, text)) = location {
+            let number = line.to_string();
+            message.push_str(&format!(
+                "\n {} | {}\n {} | {}^",
+                number,
+                text,
+                " ".repeat(number.len()),
+                " ".repeat(column.saturating_sub(1))
+            ));
+        }
+        Error::Parse(message)
+    }
+}
@@ -0,0 +1,271 @@
+//! Export formatting of mappings.
+//!
+//! Converts a mapping into `export NAME='value'` lines that can be safely
+//! `eval`'ed by a POSIX shell, or flattens it into `NAME=value` lines of a
+//! dotenv (`.env`) file.
+
+use super::error::Error;
+use super::flatten::{leaves, FlattenNode};
+use super::path::join_elements;
+use super::query::{as_mapping, get_at_path, get_value_ref, type_error_struct};
+use super::serialize::{serialize_raw, serialize_raw_ref};
+use super::Options;
+use fyaml::{Document, Value};
+use std::collections::HashMap;
+
+/// Quote a string for a POSIX shell, using single quotes.
+///
+/// Embedded single quotes are written as `'\''`.
+#[must_use]
+pub fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Build a shell variable name from a prefix and a mapping key.
+///
+/// Characters not allowed in shell variable names are replaced by `_`.
+fn shell_var_name(prefix: &str, key: &str) -> Result<String, Error> {
+    let name: String = format!("{}{}", prefix, key)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    match name.chars().next() {
+        Some(c) if !c.is_ascii_digit() => Ok(name),
+        _ => Err(Error::Base(format!(
+            "cannot export key '{}' as a shell variable name (use --prefix).",
+            key
+        ))),
+    }
+}
+
+fn export_line(prefix: &str, key: &str, value: &str) -> Result<String, Error> {
+    Ok(format!(
+        "export {}={}\n",
+        shell_var_name(prefix, key)?,
+        shell_quote(value)
+    ))
+}
+
+/// Format the mapping at path as shell export lines (zero-copy).
+pub fn export_ref(
+    path: Option<&str>,
+    doc: &Document,
+    prefix: &str,
+    opts: &Options,
+) -> Result<String, Error> {
+    let value = get_value_ref(path, doc, opts)?;
+
+    if !value.is_mapping() {
+        return Err(type_error_struct("export", &value));
+    }
+
+    value
+        .map_iter()
+        .map(|(k, v)| export_line(prefix, &serialize_raw_ref(k), &serialize_raw_ref(v)))
+        .collect()
+}
+
+/// Format the mapping at path as shell export lines.
+pub fn export(
+    path: Option<&str>,
+    value: &Value,
+    prefix: &str,
+    opts: &Options,
+) -> Result<String, Error> {
+    let target = get_at_path(value, path, opts)?;
+    let map = as_mapping(target, "export", opts)?;
+
+    map.iter()
+        .map(|(k, v)| export_line(prefix, &serialize_raw(k), &serialize_raw(v)))
+        .collect()
+}
+
+// =============================================================================
+// Dotenv Output
+// =============================================================================
+
+/// Turn a key into a dotenv name component: uppercased, with characters
+/// not allowed in variable names replaced by `_`.
+fn dotenv_name_part(key: &str) -> String {
+    key.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+fn flatten_dotenv<N: FlattenNode>(node: N, separator: &str) -> Result<String, Error> {
+    // Generated names, with the path they come from, to detect collisions
+    let mut origins: HashMap<String, String> = HashMap::new();
+    let mut output = String::new();
+
+    for (elements, leaf) in leaves(node) {
+        if leaf.children().is_some() {
+            // Empty mappings and sequences have no dotenv representation
+            continue;
+        }
+        let name = elements
+            .iter()
+            .map(|e| dotenv_name_part(e))
+            .collect::<Vec<_>>()
+            .join(separator);
+        let path = join_elements(&elements);
+        let value = leaf.raw();
+
+        if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+            return Err(Error::Base(format!(
+                "cannot use '{}' as a dotenv variable name (from '{}').",
+                name, path
+            )));
+        }
+        if value.contains('\n') {
+            return Err(Error::Base(format!(
+                "value at '{}' contains a newline, which a dotenv file can't represent.",
+                path
+            )));
+        }
+        if let Some(other) = origins.insert(name.clone(), path.clone()) {
+            return Err(Error::Base(format!(
+                "dotenv name collision: '{}' and '{}' both map to '{}'.",
+                other, path, name
+            )));
+        }
+        output.push_str(&format!("{}={}\n", name, value));
+    }
+    Ok(output)
+}
+
+/// Flatten the mapping at path into dotenv `NAME=value` lines (zero-copy).
+///
+/// Nested keys (and sequence indexes) are uppercased and joined with
+/// `separator`.
+pub fn to_dotenv_ref(
+    path: Option<&str>,
+    doc: &Document,
+    separator: &str,
+    opts: &Options,
+) -> Result<String, Error> {
+    let value = get_value_ref(path, doc, opts)?;
+
+    if !value.is_mapping() {
+        return Err(type_error_struct("to-dotenv", &value));
+    }
+
+    flatten_dotenv(value, separator)
+}
+
+/// Flatten the mapping at path into dotenv `NAME=value` lines.
+pub fn to_dotenv(
+    path: Option<&str>,
+    value: &Value,
+    separator: &str,
+    opts: &Options,
+) -> Result<String, Error> {
+    let target = get_at_path(value, path, opts)?;
+    as_mapping(target, "to-dotenv", opts)?;
+
+    flatten_dotenv(target, separator)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fyaml::Number;
+    use indexmap::indexmap;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+        assert_eq!(shell_quote("$(rm -rf /)"), "'$(rm -rf /)'");
+    }
+
+    #[test]
+    fn test_shell_var_name_sanitizes() {
+        assert_eq!(shell_var_name("", "db-host").unwrap(), "db_host");
+        assert_eq!(shell_var_name("APP_", "a.b").unwrap(), "APP_a_b");
+    }
+
+    #[test]
+    fn test_shell_var_name_leading_digit_errors() {
+        assert!(shell_var_name("", "1st").is_err());
+        assert!(shell_var_name("", "").is_err());
+        assert_eq!(shell_var_name("X_", "1st").unwrap(), "X_1st");
+    }
+
+    #[test]
+    fn test_export_mapping() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("name".to_string()) => Value::String("it's".to_string()),
+            Value::String("port".to_string()) => Value::Number(Number::Int(80)),
+        });
+        assert_eq!(
+            export(None, &value, "", &Options::default()).unwrap(),
+            "export name='it'\\''s'\nexport port='80'\n"
+        );
+    }
+
+    #[test]
+    fn test_export_requires_mapping() {
+        let err = export(None, &Value::Sequence(vec![]), "", &Options::default()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("export does not support 'sequence' type"));
+    }
+
+    #[test]
+    fn test_to_dotenv_flattens_nested_keys() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("db".to_string()) => Value::Mapping(indexmap! {
+                Value::String("host".to_string()) => Value::String("localhost".to_string()),
+                Value::String("max-conn".to_string()) => Value::Number(Number::Int(5)),
+            }),
+            Value::String("tags".to_string()) => Value::Sequence(vec![
+                Value::String("a".to_string()),
+            ]),
+        });
+        assert_eq!(
+            to_dotenv(None, &value, "_", &Options::default()).unwrap(),
+            "DB_HOST=localhost\nDB_MAX_CONN=5\nTAGS_0=a\n"
+        );
+        assert_eq!(
+            to_dotenv(Some("db"), &value, "__", &Options::default()).unwrap(),
+            "HOST=localhost\nMAX_CONN=5\n"
+        );
+    }
+
+    #[test]
+    fn test_to_dotenv_detects_collisions() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("a".to_string()) => Value::Mapping(indexmap! {
+                Value::String("b".to_string()) => Value::Number(Number::Int(1)),
+            }),
+            Value::String("a_b".to_string()) => Value::Number(Number::Int(2)),
+        });
+        let err = to_dotenv(None, &value, "_", &Options::default()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("'a.b' and 'a_b' both map to 'A_B'"));
+        // A different separator avoids the collision
+        assert!(to_dotenv(None, &value, "__", &Options::default()).is_ok());
+    }
+
+    #[test]
+    fn test_to_dotenv_rejects_multiline_values() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("a".to_string()) => Value::String("x\ny".to_string()),
+        });
+        assert!(to_dotenv(None, &value, "_", &Options::default())
+            .unwrap_err()
+            .to_string()
+            .contains("contains a newline"));
+    }
+}
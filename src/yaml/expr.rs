@@ -0,0 +1,723 @@
+//! Expressions of the `map` action.
+//!
+//! A small expression language computing a new value from the current
+//! one, written `.`:
+//!
+//! ```text
+//! . * 2
+//! upper(.) + "-prod"
+//! max(. - 1, 0)
+//! ```
+//!
+//! Numbers follow the rules of `update`: integer operands give an integer
+//! result, any float involved makes it a float. `+` concatenates as soon as
+//! one of its operands is a string.
+
+use super::doc_mutation::set_yaml_value_doc;
+use super::error::Error;
+use super::mutation::set_value;
+use super::path::join_path;
+use super::query::{get_at_path, get_value_ref, type_error_seq_or_struct, value_to_type_name};
+use super::serialize::{serialize, serialize_raw};
+use super::update::{as_f64, as_i64, format_number};
+use super::{InnerValue, Options};
+use fyaml::{Document, Number, TaggedValue, Value};
+
+/// Functions callable in expressions, with their number of arguments.
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("abs", 1),
+    ("ceil", 1),
+    ("float", 1),
+    ("floor", 1),
+    ("int", 1),
+    ("length", 1),
+    ("lower", 1),
+    ("max", 2),
+    ("min", 2),
+    ("replace", 3),
+    ("round", 1),
+    ("string", 1),
+    ("trim", 1),
+    ("upper", 1),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub(super) enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// The value being mapped, `.`
+    Current,
+    Literal(Value),
+    Neg(Box<Node>),
+    Binary(BinOp, Box<Node>, Box<Node>),
+    Call(&'static str, Vec<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Dot,
+    Number(Number),
+    Str(String),
+    Ident(String),
+    Op(char),
+    LParen,
+    RParen,
+    Comma,
+}
+
+/// Parsed expression of a `map` action.
+#[derive(Clone, Debug)]
+pub struct Expr {
+    source: String,
+    root: Node,
+}
+
+impl Expr {
+    /// Parse an expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on syntax errors, unknown functions and calls with
+    /// the wrong number of arguments.
+    pub fn parse(source: &str) -> Result<Self, Error> {
+        let invalid =
+            |message: String| Error::Base(format!("invalid expression '{}': {}", source, message));
+        let tokens = tokenize(source).map_err(invalid)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let root = parser.expr().map_err(invalid)?;
+        if let Some((token, column)) = parser.tokens.get(parser.pos) {
+            return Err(invalid(format!(
+                "unexpected {} at column {}",
+                describe(token),
+                column
+            )));
+        }
+        Ok(Expr {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    /// Evaluate the expression with `value` as `.`.
+    pub fn eval(&self, value: &Value) -> Result<Value, Error> {
+        eval(&self.root, value.inner())
+    }
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Dot => "'.'".to_string(),
+        Token::Number(n) => format!("number {}", format_number(n)),
+        Token::Str(s) => format!("string '{}'", s),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Op(c) => format!("'{}'", c),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+        Token::Comma => "','".to_string(),
+    }
+}
+
+/// Split an expression into tokens, with the column they start at.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '.' if !chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()) => {
+                i += 1;
+                Token::Dot
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    i += 1;
+                    if i < chars.len() && matches!(chars[i], '+' | '-') {
+                        i += 1;
+                    }
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                let number =
+                    match text.parse::<i64>() {
+                        Ok(n) => Number::Int(n),
+                        Err(_) => Number::Float(text.parse::<f64>().map_err(|_| {
+                            format!("invalid number '{}' at column {}", text, column)
+                        })?),
+                    };
+                Token::Number(number)
+            }
+            '"' | '\'' => {
+                let mut s = String::new();
+                i += 1;
+                loop {
+                    match chars.get(i) {
+                        None => return Err(format!("unterminated string at column {}", column)),
+                        Some(&q) if q == c => break,
+                        Some('\\') => {
+                            i += 1;
+                            match chars.get(i) {
+                                Some('n') => s.push('\n'),
+                                Some('t') => s.push('\t'),
+                                Some(&e) => s.push(e),
+                                None => {
+                                    return Err(format!("unterminated string at column {}", column))
+                                }
+                            }
+                        }
+                        Some(&other) => s.push(other),
+                    }
+                    i += 1;
+                }
+                i += 1;
+                Token::Str(s)
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+                Token::Ident(chars[start..i].iter().collect())
+            }
+            '+' | '-' | '*' | '/' | '%' => {
+                i += 1;
+                Token::Op(c)
+            }
+            '(' => {
+                i += 1;
+                Token::LParen
+            }
+            ')' => {
+                i += 1;
+                Token::RParen
+            }
+            ',' => {
+                i += 1;
+                Token::Comma
+            }
+            other => return Err(format!("unexpected '{}' at column {}", other, column)),
+        };
+        tokens.push((token, column));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn advance(&mut self) -> Result<(Token, usize), String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of expression".to_string())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), String> {
+        match self.advance()? {
+            (token, _) if token == expected => Ok(()),
+            (token, column) => Err(format!(
+                "expected {} at column {}, found {}",
+                describe(&expected),
+                column,
+                describe(&token)
+            )),
+        }
+    }
+
+    /// `term (('+' | '-') term)*`
+    fn expr(&mut self) -> Result<Node, String> {
+        let mut node = self.term()?;
+        while let Some(Token::Op(c @ ('+' | '-'))) = self.peek() {
+            let op = if *c == '+' { BinOp::Add } else { BinOp::Sub };
+            self.pos += 1;
+            node = Node::Binary(op, Box::new(node), Box::new(self.term()?));
+        }
+        Ok(node)
+    }
+
+    /// `unary (('*' | '/' | '%') unary)*`
+    fn term(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        while let Some(Token::Op(c @ ('*' | '/' | '%'))) = self.peek() {
+            let op = match c {
+                '*' => BinOp::Mul,
+                '/' => BinOp::Div,
+                _ => BinOp::Rem,
+            };
+            self.pos += 1;
+            node = Node::Binary(op, Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.pos += 1;
+            return Ok(Node::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        let (token, column) = self.advance()?;
+        match token {
+            Token::Dot => Ok(Node::Current),
+            Token::Number(n) => Ok(Node::Literal(Value::Number(n))),
+            Token::Str(s) => Ok(Node::Literal(Value::String(s))),
+            Token::Ident(name) if name == "true" || name == "false" => {
+                Ok(Node::Literal(Value::Bool(name == "true")))
+            }
+            Token::Ident(name) if name == "null" => Ok(Node::Literal(Value::Null)),
+            Token::Ident(name) => {
+                let &(function, arity) = FUNCTIONS
+                    .iter()
+                    .find(|(f, _)| *f == name)
+                    .ok_or_else(|| format!("unknown function '{}' at column {}", name, column))?;
+                self.expect(Token::LParen)?;
+                let mut args = Vec::new();
+                if self.peek() != Some(&Token::RParen) {
+                    args.push(self.expr()?);
+                    while self.peek() == Some(&Token::Comma) {
+                        self.pos += 1;
+                        args.push(self.expr()?);
+                    }
+                }
+                self.expect(Token::RParen)?;
+                if args.len() != arity {
+                    return Err(format!(
+                        "{}() takes {} argument{}, {} given",
+                        function,
+                        arity,
+                        if arity == 1 { "" } else { "s" },
+                        args.len()
+                    ));
+                }
+                Ok(Node::Call(function, args))
+            }
+            Token::LParen => {
+                let node = self.expr()?;
+                self.expect(Token::RParen)?;
+                Ok(node)
+            }
+            other => Err(format!(
+                "unexpected {} at column {}",
+                describe(&other),
+                column
+            )),
+        }
+    }
+}
+
+// =============================================================================
+// Evaluation
+// =============================================================================
+
+pub(super) fn type_error(what: &str, value: &Value) -> Error {
+    Error::type_error(format!(
+        "{} does not support '{}' type.",
+        what,
+        value_to_type_name(value)
+    ))
+}
+
+fn number<'a>(what: &str, value: &'a Value) -> Result<&'a Number, Error> {
+    match value.inner() {
+        Value::Number(n) => Ok(n),
+        other => Err(type_error(what, other)),
+    }
+}
+
+fn string(what: &str, value: &Value) -> Result<String, Error> {
+    match value.inner() {
+        v @ (Value::String(_) | Value::Number(_) | Value::Bool(_)) => Ok(serialize_raw(v)),
+        other => Err(type_error(what, other)),
+    }
+}
+
+/// Integer value of a float, if it has no fractional part and fits.
+fn integral(f: f64) -> Value {
+    if f.fract() == 0.0 && f.abs() < i64::MAX as f64 {
+        Value::Number(Number::Int(f as i64))
+    } else {
+        Value::Number(Number::Float(f))
+    }
+}
+
+pub(super) fn arithmetic(op: BinOp, a: &Number, b: &Number) -> Result<Value, Error> {
+    if let (Some(x), Some(y)) = (as_i64(a), as_i64(b)) {
+        if matches!(op, BinOp::Div | BinOp::Rem) && y == 0 {
+            return Err(Error::Base("division by zero".to_string()));
+        }
+        let result = match op {
+            BinOp::Add => x.checked_add(y),
+            BinOp::Sub => x.checked_sub(y),
+            BinOp::Mul => x.checked_mul(y),
+            // Exact divisions stay integers
+            BinOp::Div if x.checked_rem(y) == Some(0) => x.checked_div(y),
+            BinOp::Div => return Ok(Value::Number(Number::Float(x as f64 / y as f64))),
+            BinOp::Rem => x.checked_rem(y),
+        };
+        return result
+            .map(|n| Value::Number(Number::Int(n)))
+            .ok_or_else(|| Error::Base(format!("integer overflow in '{}'", symbol(op))));
+    }
+    let (x, y) = (as_f64(a), as_f64(b));
+    if matches!(op, BinOp::Div | BinOp::Rem) && y == 0.0 {
+        return Err(Error::Base("division by zero".to_string()));
+    }
+    Ok(Value::Number(Number::Float(match op {
+        BinOp::Add => x + y,
+        BinOp::Sub => x - y,
+        BinOp::Mul => x * y,
+        BinOp::Div => x / y,
+        BinOp::Rem => x % y,
+    })))
+}
+
+fn eval(node: &Node, current: &Value) -> Result<Value, Error> {
+    match node {
+        Node::Current => Ok(current.clone()),
+        Node::Literal(value) => Ok(value.clone()),
+        Node::Neg(operand) => {
+            let value = eval(operand, current)?;
+            arithmetic(BinOp::Sub, &Number::Int(0), number("'-'", &value)?)
+        }
+        Node::Binary(op, left, right) => {
+            let (a, b) = (eval(left, current)?, eval(right, current)?);
+            match (op, a.inner(), b.inner()) {
+                (_, Value::Number(x), Value::Number(y)) => arithmetic(*op, x, y),
+                (BinOp::Add, Value::String(_), _) | (BinOp::Add, _, Value::String(_)) => {
+                    Ok(Value::String(string("'+'", &a)? + &string("'+'", &b)?))
+                }
+                // Report the operand that isn't a number
+                (_, Value::Number(_), _) => Err(type_error(&format!("'{}'", symbol(*op)), &b)),
+                _ => Err(type_error(&format!("'{}'", symbol(*op)), &a)),
+            }
+        }
+        Node::Call(function, args) => {
+            let args = args
+                .iter()
+                .map(|arg| eval(arg, current))
+                .collect::<Result<Vec<_>, _>>()?;
+            call(function, &args)
+        }
+    }
+}
+
+pub(super) fn symbol(op: BinOp) -> char {
+    match op {
+        BinOp::Add => '+',
+        BinOp::Sub => '-',
+        BinOp::Mul => '*',
+        BinOp::Div => '/',
+        BinOp::Rem => '%',
+    }
+}
+
+fn call(function: &str, args: &[Value]) -> Result<Value, Error> {
+    let what = format!("{}()", function);
+    let what = what.as_str();
+    Ok(match function {
+        "upper" => Value::String(string(what, &args[0])?.to_uppercase()),
+        "lower" => Value::String(string(what, &args[0])?.to_lowercase()),
+        "trim" => Value::String(string(what, &args[0])?.trim().to_string()),
+        "string" => Value::String(string(what, &args[0])?),
+        "replace" => Value::String(
+            string(what, &args[0])?
+                .replace(string(what, &args[1])?.as_str(), &string(what, &args[2])?),
+        ),
+        "length" => {
+            let len = match args[0].inner() {
+                Value::Sequence(seq) => seq.len(),
+                Value::Mapping(map) => map.len(),
+                Value::String(s) => s.chars().count(),
+                other => return Err(type_error(what, other)),
+            };
+            Value::Number(Number::Int(len as i64))
+        }
+        "int" => match args[0].inner() {
+            Value::Number(n) => match as_i64(n) {
+                Some(i) => Value::Number(Number::Int(i)),
+                None => integral(as_f64(n).trunc()),
+            },
+            Value::String(s) => s
+                .trim()
+                .parse::<i64>()
+                .map(|i| Value::Number(Number::Int(i)))
+                .map_err(|_| Error::Base(format!("int() can't convert '{}'", s)))?,
+            other => return Err(type_error(what, other)),
+        },
+        "float" => match args[0].inner() {
+            Value::Number(n) => Value::Number(Number::Float(as_f64(n))),
+            Value::String(s) => s
+                .trim()
+                .parse::<f64>()
+                .map(|f| Value::Number(Number::Float(f)))
+                .map_err(|_| Error::Base(format!("float() can't convert '{}'", s)))?,
+            other => return Err(type_error(what, other)),
+        },
+        "abs" | "round" | "floor" | "ceil" => {
+            let n = number(what, &args[0])?;
+            if let Some(i) = as_i64(n) {
+                return match function {
+                    "abs" => i
+                        .checked_abs()
+                        .map(|i| Value::Number(Number::Int(i)))
+                        .ok_or_else(|| Error::Base("integer overflow in 'abs()'".to_string())),
+                    _ => Ok(Value::Number(Number::Int(i))),
+                };
+            }
+            let f = as_f64(n);
+            match function {
+                "abs" => Value::Number(Number::Float(f.abs())),
+                "round" => integral(f.round()),
+                "floor" => integral(f.floor()),
+                _ => integral(f.ceil()),
+            }
+        }
+        "min" | "max" => {
+            let (a, b) = (number(what, &args[0])?, number(what, &args[1])?);
+            let first = match (as_i64(a), as_i64(b)) {
+                (Some(x), Some(y)) => (x <= y) == (function == "min"),
+                _ => (as_f64(a) <= as_f64(b)) == (function == "min"),
+            };
+            Value::Number(if first { a.clone() } else { b.clone() })
+        }
+        _ => unreachable!("functions are checked when parsing"),
+    })
+}
+
+// =============================================================================
+// Map Action
+// =============================================================================
+
+/// New values of the elements of the sequence or the values of the mapping
+/// at `path`, with their paths.
+fn mapped<'a>(
+    path: &str,
+    target: &'a Value,
+    expr: &Expr,
+) -> Result<Vec<(String, &'a Value, Value)>, Error> {
+    let children: Vec<(String, &Value)> = match target.inner() {
+        Value::Sequence(seq) => seq
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        Value::Mapping(map) => map.iter().map(|(k, v)| (serialize_raw(k), v)).collect(),
+        _ => return Err(type_error_seq_or_struct("map", target)),
+    };
+    children
+        .into_iter()
+        .map(|(key, child)| {
+            let child_path = join_path(path, &[key.as_str()]);
+            let result = expr.eval(child).map_err(|e| {
+                Error::Base(format!(
+                    "map '{}' failed at '{}': {}",
+                    expr.source, child_path, e
+                ))
+            })?;
+            Ok((key, child, result))
+        })
+        .collect()
+}
+
+/// Replace each element of the sequence, or each value of the mapping, at
+/// `path` by the result of `expr` (owned version for command chains).
+pub fn map(path: &str, expr: &Expr, value: Value, opts: &Options) -> Result<Value, Error> {
+    let target = get_at_path(&value, Some(path), opts)?;
+    let results = mapped(path, target, expr)?.into_iter();
+    let collection = match target.inner() {
+        Value::Sequence(_) => Value::Sequence(results.map(|(_, _, v)| v).collect()),
+        Value::Mapping(map) => Value::Mapping(
+            map.keys()
+                .cloned()
+                .zip(results.map(|(_, _, v)| v))
+                .collect(),
+        ),
+        _ => unreachable!("mapped() only accepts collections"),
+    };
+    let collection = match target {
+        Value::Tagged(t) => Value::Tagged(Box::new(TaggedValue {
+            tag: t.tag.clone(),
+            value: collection,
+        })),
+        _ => collection,
+    };
+    set_value(path, collection, value, opts)
+}
+
+/// Replace each element of the sequence, or each value of the mapping, at
+/// `path` by the result of `expr`, in place using Editor.
+pub fn map_doc(doc: &mut Document, path: &str, expr: &Expr, opts: &Options) -> Result<(), Error> {
+    let target = Value::from_node_ref(get_value_ref(Some(path), doc, opts)?.as_node())?;
+    let changes: Vec<(String, String)> = mapped(path, &target, expr)?
+        .into_iter()
+        .filter(|(_, old, new)| old.inner() != new)
+        .map(|(key, _, new)| {
+            let text = match &new {
+                // Floats stay recognizable as floats
+                Value::Number(n) => format_number(n),
+                other => serialize(other)?.trim_end().to_string(),
+            };
+            Ok((join_path(path, &[key.as_str()]), text))
+        })
+        .collect::<Result<_, Error>>()?;
+    for (child_path, text) in changes {
+        set_yaml_value_doc(doc, &child_path, &text, opts)?;
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval_str(source: &str, current: &str) -> Value {
+        let current: Value = current.parse().unwrap();
+        Expr::parse(source).unwrap().eval(&current).unwrap()
+    }
+
+    fn int(i: i64) -> Value {
+        Value::Number(Number::Int(i))
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        assert_eq!(eval_str(". * 2", "3"), int(6));
+        assert_eq!(eval_str("1 + . * 2", "3"), int(7));
+        assert_eq!(eval_str("(1 + .) * 2", "3"), int(8));
+        assert_eq!(eval_str("-. + 1", "3"), int(-2));
+        assert_eq!(eval_str(". / 2", "4"), int(2));
+        assert_eq!(eval_str(". / 2", "3"), Value::Number(Number::Float(1.5)));
+        assert_eq!(eval_str(". % 4", "10"), int(2));
+        assert_eq!(eval_str(". * 1.5", "2"), Value::Number(Number::Float(3.0)));
+    }
+
+    #[test]
+    fn test_strings() {
+        assert_eq!(eval_str("upper(.)", "web"), Value::String("WEB".into()));
+        assert_eq!(
+            eval_str(". + '-' + 1", "web"),
+            Value::String("web-1".into())
+        );
+        assert_eq!(
+            eval_str("replace(trim(.), \"a\", 'o')", "' banana '"),
+            Value::String("bonono".into())
+        );
+        assert_eq!(eval_str("length(.)", "[1, 2]"), int(2));
+        assert_eq!(eval_str("int(.)", "'42'"), int(42));
+    }
+
+    #[test]
+    fn test_numeric_functions() {
+        assert_eq!(eval_str("round(.)", "2.5"), int(3));
+        assert_eq!(eval_str("floor(.)", "-1.5"), int(-2));
+        assert_eq!(eval_str("max(. - 5, 0)", "3"), int(0));
+        assert_eq!(
+            eval_str("min(., 2.5)", "3"),
+            Value::Number(Number::Float(2.5))
+        );
+        assert_eq!(eval_str("abs(.)", "-4"), int(4));
+    }
+
+    #[test]
+    fn test_eval_errors() {
+        let expr = Expr::parse(". * 2").unwrap();
+        let err = expr.eval(&Value::String("a".into())).unwrap_err();
+        assert!(err.to_string().contains("'*' does not support 'str' type"));
+        let err = Expr::parse(". / 0").unwrap().eval(&int(1)).unwrap_err();
+        assert!(err.to_string().contains("division by zero"));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (source, message) in [
+            (". *", "unexpected end of expression"),
+            (". 2", "unexpected number 2 at column 3"),
+            ("shout(.)", "unknown function 'shout' at column 1"),
+            ("max(.)", "max() takes 2 arguments, 1 given"),
+            ("'abc", "unterminated string at column 1"),
+            (". & 1", "unexpected '&' at column 3"),
+        ] {
+            let err = Expr::parse(source).unwrap_err().to_string();
+            assert!(err.contains(message), "{}: {}", source, err);
+        }
+    }
+
+    #[test]
+    fn test_map() {
+        let value: Value = "replicas: {web: 2, api: 3}\nnames: [a, b]\n"
+            .parse()
+            .unwrap();
+        let value = map(
+            "replicas",
+            &Expr::parse(". * 2").unwrap(),
+            value,
+            &Options::default(),
+        )
+        .unwrap();
+        let value = map(
+            "names",
+            &Expr::parse("upper(.)").unwrap(),
+            value,
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            serialize(&value).unwrap(),
+            "replicas:\n  web: 4\n  api: 6\nnames:\n- A\n- B"
+        );
+    }
+
+    #[test]
+    fn test_map_errors() {
+        let value: Value = "a: [1, x]\nb: 1\n".parse().unwrap();
+        let err = map(
+            "a",
+            &Expr::parse(". * 2").unwrap(),
+            value.clone(),
+            &Options::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("failed at 'a.1'"));
+        let err = map("b", &Expr::parse(".").unwrap(), value, &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("map does not support 'int' type"));
+    }
+}
@@ -0,0 +1,661 @@
+//! Filter segments in paths.
+//!
+//! A path element can select the first element of a sequence matching a
+//! predicate instead of giving its index:
+//!
+//! - `[FIELD=VALUE]` / `[FIELD!=VALUE]`: compare a field of the element
+//! - `[?(@ OP VALUE)]` / `[?(@.FIELD OP VALUE)]`: compare the element itself
+//!   or one of its fields, with `OP` one of `==`, `!=`, `<`, `<=`, `>`, `>=`
+//!
+//! Filters are resolved to indices against the document before the path is
+//! used, so every action accepts them.
+//!
+//! Actions working on several paths also accept a `*` element, matching
+//! every key of a mapping or element of a sequence.
+//!
+//! The `select-where` action keeps every element matching a condition
+//! instead, `FIELD=VALUE`, `FIELD!=VALUE` or `FIELD~REGEX`.
+
+use super::error::Error;
+use super::flatten::FlattenNode;
+use super::path::{
+    escape_path_element, join_elements, resolve_index, split_path, split_path_literal,
+};
+use super::query::{get_at_path, type_error_seq};
+use super::{InnerValue, Options};
+use fyaml::Value;
+use regex::Regex;
+
+/// Comparison operator of a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// Predicate selecting sequence elements.
+#[derive(Debug, Clone, PartialEq)]
+struct Filter {
+    /// Path of the compared value below the element (empty for the element)
+    field: Vec<String>,
+    op: CmpOp,
+    value: String,
+}
+
+impl Filter {
+    /// Parse a path element as a filter, `None` if it isn't one.
+    fn parse(element: &str) -> Option<Filter> {
+        let inner = element.strip_prefix('[')?.strip_suffix(']')?;
+
+        if let Some(expr) = inner.strip_prefix("?(").and_then(|e| e.strip_suffix(')')) {
+            let (lhs, op, rhs) = split_operator(expr)?;
+            let field = match lhs.trim() {
+                "@" => Vec::new(),
+                lhs => split_path(lhs.strip_prefix("@.")?),
+            };
+            return Some(Filter {
+                field,
+                op,
+                value: unquote(rhs.trim()).to_string(),
+            });
+        }
+
+        let (field, op, value) = split_operator(inner)?;
+        if !matches!(op, CmpOp::Eq | CmpOp::Ne) || field.is_empty() {
+            return None;
+        }
+        Some(Filter {
+            field: split_path(field),
+            op,
+            value: value.to_string(),
+        })
+    }
+
+    /// Check whether a sequence element matches.
+    fn matches<N: FlattenNode>(&self, element: N) -> bool {
+        let Some(target) = field_value(&self.field, element) else {
+            return false;
+        };
+        let raw = target.raw();
+
+        match self.op {
+            CmpOp::Eq => raw == self.value,
+            CmpOp::Ne => raw != self.value,
+            op => match (raw.parse::<f64>(), self.value.parse::<f64>()) {
+                (Ok(a), Ok(b)) => match op {
+                    CmpOp::Lt => a < b,
+                    CmpOp::Le => a <= b,
+                    CmpOp::Gt => a > b,
+                    _ => a >= b,
+                },
+                _ => false,
+            },
+        }
+    }
+}
+
+/// Value at `field` below a sequence element, if any.
+fn field_value<N: FlattenNode>(field: &[String], element: N) -> Option<N> {
+    field.iter().try_fold(element, |node, key| {
+        node.children()?
+            .into_iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v)
+    })
+}
+
+/// Condition of the `select-where` action.
+#[derive(Debug)]
+enum Condition {
+    /// `FIELD=VALUE` or `FIELD!=VALUE`
+    Compare(Filter),
+    /// `FIELD~REGEX`, matching anywhere in the raw value unless anchored
+    Regex { field: Vec<String>, regex: Regex },
+}
+
+impl Condition {
+    fn parse(condition: &str) -> Result<Condition, Error> {
+        let invalid =
+            |reason: String| Error::Base(format!("invalid condition '{}': {}", condition, reason));
+        let pos = condition
+            .find(['=', '!', '~'])
+            .filter(|&pos| pos > 0)
+            .ok_or_else(|| invalid("expected FIELD=VALUE, FIELD!=VALUE or FIELD~REGEX".into()))?;
+        let field = split_path(&condition[..pos]);
+        let rest = &condition[pos..];
+        if let Some(pattern) = rest.strip_prefix('~') {
+            let regex = Regex::new(pattern).map_err(|e| invalid(e.to_string()))?;
+            return Ok(Condition::Regex { field, regex });
+        }
+        let (op, value) = match rest.strip_prefix("!=") {
+            Some(value) => (CmpOp::Ne, value),
+            None => match rest.strip_prefix('=') {
+                Some(value) => (CmpOp::Eq, value),
+                None => {
+                    return Err(invalid(
+                        "expected FIELD=VALUE, FIELD!=VALUE or FIELD~REGEX".into(),
+                    ))
+                }
+            },
+        };
+        Ok(Condition::Compare(Filter {
+            field,
+            op,
+            value: value.to_string(),
+        }))
+    }
+
+    fn matches<N: FlattenNode>(&self, element: N) -> bool {
+        match self {
+            Condition::Compare(filter) => filter.matches(element),
+            Condition::Regex { field, regex } => {
+                field_value(field, element).is_some_and(|target| regex.is_match(&target.raw()))
+            }
+        }
+    }
+}
+
+/// Elements of the sequence at `path` matching `condition`, in order.
+///
+/// Elements missing the field of the condition never match, whatever the
+/// operator.
+///
+/// # Errors
+///
+/// Returns an error if the condition is invalid, or if the path doesn't
+/// exist or designates something else than a sequence.
+pub fn select_where(
+    path: Option<&str>,
+    condition: &str,
+    value: &Value,
+    opts: &Options,
+) -> Result<Value, Error> {
+    let condition = Condition::parse(condition)?;
+    let target = get_at_path(value, path, opts)?;
+    let Value::Sequence(seq) = target.inner() else {
+        return Err(type_error_seq("select-where", target));
+    };
+    Ok(Value::Sequence(
+        seq.iter()
+            .filter(|element| condition.matches(*element))
+            .cloned()
+            .collect(),
+    ))
+}
+
+/// Split `LHS OP RHS` on its first comparison operator.
+fn split_operator(expr: &str) -> Option<(&str, CmpOp, &str)> {
+    let pos = expr.find(['=', '!', '<', '>'])?;
+    let rest = &expr[pos..];
+    let (op, len) = if rest.starts_with("==") {
+        (CmpOp::Eq, 2)
+    } else if rest.starts_with("!=") {
+        (CmpOp::Ne, 2)
+    } else if rest.starts_with("<=") {
+        (CmpOp::Le, 2)
+    } else if rest.starts_with(">=") {
+        (CmpOp::Ge, 2)
+    } else if rest.starts_with('=') {
+        (CmpOp::Eq, 1)
+    } else if rest.starts_with('<') {
+        (CmpOp::Lt, 1)
+    } else if rest.starts_with('>') {
+        (CmpOp::Gt, 1)
+    } else {
+        return None;
+    };
+    Some((&expr[..pos], op, &expr[pos + len..]))
+}
+
+/// Remove matching single or double quotes around a value.
+fn unquote(value: &str) -> &str {
+    for quote in ['\'', '"'] {
+        if let Some(inner) = value
+            .strip_prefix(quote)
+            .and_then(|v| v.strip_suffix(quote))
+        {
+            return inner;
+        }
+    }
+    value
+}
+
+/// Replace the filter elements of a path by the index of the first element
+/// they match.
+///
+/// Paths without filters are returned unchanged. Resolution stops at the
+/// first missing key, leaving the rest of the path for the caller to report
+/// or create.
+pub(super) fn resolve_filters<N: FlattenNode + Copy>(root: N, path: &str) -> Result<String, Error> {
+    let mut parts = split_path_literal(path);
+    if !parts
+        .iter()
+        .any(|(p, literal)| !literal && Filter::parse(p).is_some())
+    {
+        return Ok(path.to_string());
+    }
+
+    let mut current = Some(root);
+    let mut resolved = 0;
+    for (part, literal) in parts.iter_mut() {
+        let Some(node) = current.take() else {
+            break;
+        };
+        let Some(children) = node.children() else {
+            break;
+        };
+        resolved += 1;
+        let filter = Filter::parse(part).filter(|_| !*literal);
+        // A mapping key looking like a filter is found as is
+        if filter.is_some() && !node.is_sequence() && !children.iter().any(|(k, _)| k == part) {
+            return Err(Error::path_error_at(
+                path,
+                format!(
+                    "invalid path '{}', filter '{}' can only be used on a sequence.",
+                    path, part
+                ),
+            ));
+        }
+
+        if let Some(filter) = filter.filter(|_| node.is_sequence()) {
+            let (index, element) = children
+                .into_iter()
+                .find(|(_, element)| filter.matches(*element))
+                .ok_or_else(|| {
+                    Error::path_error_at(
+                        path,
+                        format!(
+                            "invalid path '{}', no element matching '{}' in sequence.",
+                            path, part
+                        ),
+                    )
+                })?;
+            *part = index;
+            current = Some(element);
+        } else if node.is_sequence() {
+            let index = resolve_index(part, children.len(), path)?;
+            current = children.into_iter().nth(index).map(|(_, v)| v);
+        } else {
+            current = children
+                .into_iter()
+                .find(|(k, _)| k == part)
+                .map(|(_, v)| v);
+        }
+    }
+
+    // Filters left past a missing key are kept as written
+    Ok(parts
+        .iter()
+        .enumerate()
+        .map(|(i, (part, literal))| match Filter::parse(part) {
+            Some(_) if i >= resolved && !literal => part.clone(),
+            _ => escape_path_element(part),
+        })
+        .collect::<Vec<_>>()
+        .join("."))
+}
+
+/// Path element matching every child of a mapping or sequence.
+const WILDCARD: &str = "*";
+
+/// Whether a path has a `*` element, an escaped `\*` being a literal key.
+pub(super) fn has_wildcard(path: &str) -> bool {
+    split_path_literal(path)
+        .iter()
+        .any(|(part, literal)| !literal && part == WILDCARD)
+}
+
+/// Expand the `*` elements of a path into the paths they match, in document
+/// order.
+///
+/// Paths without wildcards are returned unchanged, and `\*` is a literal
+/// `*` key. Otherwise filters are
+/// resolved along the way, and branches missing the rest of the path are
+/// skipped, so `services.*.debug` only matches the services having `debug`.
+pub(super) fn expand_wildcards<N: FlattenNode + Copy>(root: N, path: &str) -> Vec<String> {
+    if !has_wildcard(path) {
+        return vec![path.to_string()];
+    }
+    let parts = split_path_literal(path);
+    let mut paths = Vec::new();
+    expand_parts(root, &parts, &mut Vec::new(), &mut paths);
+    paths
+}
+
+fn expand_parts<N: FlattenNode + Copy>(
+    node: N,
+    parts: &[(String, bool)],
+    prefix: &mut Vec<String>,
+    paths: &mut Vec<String>,
+) {
+    let Some(((part, literal), rest)) = parts.split_first() else {
+        paths.push(join_elements(prefix));
+        return;
+    };
+    let Some(children) = node.children() else {
+        return;
+    };
+
+    let selected: Vec<(String, N)> = if part == WILDCARD && !literal {
+        children
+    } else if let Some(filter) = Filter::parse(part).filter(|_| !literal && node.is_sequence()) {
+        children
+            .into_iter()
+            .find(|(_, element)| filter.matches(*element))
+            .into_iter()
+            .collect()
+    } else if node.is_sequence() {
+        match resolve_index(part, children.len(), part) {
+            Ok(index) => children.into_iter().nth(index).into_iter().collect(),
+            Err(_) => return,
+        }
+    } else {
+        children.into_iter().filter(|(k, _)| k == part).collect()
+    };
+
+    for (key, child) in selected {
+        prefix.push(key);
+        expand_parts(child, rest, prefix, paths);
+        prefix.pop();
+    }
+}
+
+/// Expand the wildcards of every path and resolve its filters and negative
+/// indexes, all against `root`, then order the paths so that deleting them
+/// in turn removes the nodes they designated in `root`.
+///
+/// Higher indexes of a sequence come first, so that removing an element
+/// doesn't shift the ones left to delete, and nested paths come before
+/// their parent. Paths that can't be resolved are kept as given, for the
+/// deletion to report them.
+pub(super) fn deletion_order<N: FlattenNode + Copy>(root: N, paths: &[String]) -> Vec<String> {
+    let mut resolved: Vec<(Vec<String>, String)> = paths
+        .iter()
+        .flat_map(|path| expand_wildcards(root, path))
+        .map(|path| {
+            let path = resolve_filters(root, &path)
+                .map(|resolved| resolve_negative_indexes(root, &resolved))
+                .unwrap_or(path);
+            (split_path(&path), path)
+        })
+        .collect();
+    resolved.sort_by(|(a, _), (b, _)| {
+        for (a, b) in a.iter().zip(b) {
+            let order = match (a.parse::<usize>(), b.parse::<usize>()) {
+                (Ok(a), Ok(b)) => b.cmp(&a),
+                _ => a.cmp(b),
+            };
+            if order.is_ne() {
+                return order;
+            }
+        }
+        b.len().cmp(&a.len())
+    });
+    resolved.dedup_by(|(a, _), (b, _)| a == b);
+    resolved.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Replace the negative indexes of a path by the indexes they designate in
+/// `root`, up to the first element that isn't found.
+fn resolve_negative_indexes<N: FlattenNode + Copy>(root: N, path: &str) -> String {
+    let mut parts = split_path(path);
+    let mut changed = false;
+    let mut current = Some(root);
+    for part in parts.iter_mut() {
+        let Some(node) = current.take() else {
+            break;
+        };
+        let Some(children) = node.children() else {
+            break;
+        };
+        current = if node.is_sequence() {
+            let Ok(index) = resolve_index(part, children.len(), path) else {
+                break;
+            };
+            if *part != index.to_string() {
+                *part = index.to_string();
+                changed = true;
+            }
+            children.into_iter().nth(index).map(|(_, v)| v)
+        } else {
+            children
+                .into_iter()
+                .find(|(k, _)| k == part)
+                .map(|(_, v)| v)
+        };
+    }
+    if changed {
+        join_elements(&parts)
+    } else {
+        path.to_string()
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::serialize_raw;
+    use fyaml::Number;
+    use indexmap::indexmap;
+
+    fn sample() -> Value {
+        let user = |name: &str, age: i64| {
+            Value::Mapping(indexmap! {
+                Value::String("name".to_string()) => Value::String(name.to_string()),
+                Value::String("age".to_string()) => Value::Number(Number::Int(age)),
+            })
+        };
+        Value::Mapping(indexmap! {
+            Value::String("users".to_string()) => Value::Sequence(vec![user("bob", 20), user("alice", 30)]),
+            Value::String("ports".to_string()) => Value::Sequence(vec![
+                Value::Number(Number::Int(80)),
+                Value::Number(Number::Int(8080)),
+            ]),
+        })
+    }
+
+    #[test]
+    fn test_parse_field_filter() {
+        assert_eq!(
+            Filter::parse("[a.b!=x]"),
+            Some(Filter {
+                field: vec!["a".to_string(), "b".to_string()],
+                op: CmpOp::Ne,
+                value: "x".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_expression_filter() {
+        assert_eq!(
+            Filter::parse("[?(@ >= '10')]"),
+            Some(Filter {
+                field: vec![],
+                op: CmpOp::Ge,
+                value: "10".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_not_a_filter() {
+        assert_eq!(Filter::parse("name"), None);
+        assert_eq!(Filter::parse("[name]"), None);
+        assert_eq!(Filter::parse("[a<1]"), None);
+        assert_eq!(Filter::parse("[?(x > 1)]"), None);
+    }
+
+    #[test]
+    fn test_resolve_without_filter_is_unchanged() {
+        assert_eq!(resolve_filters(&sample(), r"a\.b.-1").unwrap(), r"a\.b.-1");
+    }
+
+    #[test]
+    fn test_resolve_field_filter() {
+        assert_eq!(
+            resolve_filters(&sample(), "users.[name=alice].age").unwrap(),
+            "users.1.age"
+        );
+    }
+
+    #[test]
+    fn test_resolve_numeric_comparison() {
+        assert_eq!(
+            resolve_filters(&sample(), "ports.[?(@ > 1024)]").unwrap(),
+            "ports.1"
+        );
+        assert_eq!(
+            resolve_filters(&sample(), "users.[?(@.age < 25)].name").unwrap(),
+            "users.0.name"
+        );
+    }
+
+    #[test]
+    fn test_resolve_no_match_errors() {
+        let err = resolve_filters(&sample(), "users.[name=carol]").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("no element matching '[name=carol]' in sequence"));
+    }
+
+    #[test]
+    fn test_resolve_filter_on_mapping_errors() {
+        let err = resolve_filters(&sample(), "[name=bob]").unwrap_err();
+        assert!(err.to_string().contains("can only be used on a sequence"));
+    }
+
+    #[test]
+    fn test_resolve_keys_looking_like_filters() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("[a=b]".to_string()) => sample(),
+        });
+        assert_eq!(
+            resolve_filters(&value, "[a=b].users.[name=alice]").unwrap(),
+            r"\[a\=b].users.1"
+        );
+        // Escaped, the element is a key even on a sequence
+        let err = resolve_filters(&value, r"[a=b].users.\[name=alice]").unwrap_err();
+        assert!(err.to_string().contains("non-integer index '[name=alice]'"));
+        assert_eq!(
+            expand_wildcards(&value, "[a=b].*.0"),
+            vec![r"\[a\=b].users.0", r"\[a\=b].ports.0"]
+        );
+    }
+
+    #[test]
+    fn test_expand_without_wildcard_is_unchanged() {
+        assert_eq!(expand_wildcards(&sample(), "missing.x"), vec!["missing.x"]);
+    }
+
+    #[test]
+    fn test_expand_wildcards() {
+        assert_eq!(
+            expand_wildcards(&sample(), "users.*.name"),
+            vec!["users.0.name", "users.1.name"]
+        );
+        assert_eq!(
+            expand_wildcards(&sample(), "*.-1"),
+            vec!["users.1", "ports.1"]
+        );
+        assert_eq!(
+            expand_wildcards(&sample(), "*.[name=alice].age"),
+            vec!["users.1.age"]
+        );
+    }
+
+    #[test]
+    fn test_expand_skips_missing_branches() {
+        assert_eq!(expand_wildcards(&sample(), "*.0.age"), vec!["users.0.age"]);
+        assert!(expand_wildcards(&sample(), "users.*.email").is_empty());
+    }
+
+    #[test]
+    fn test_expand_escaped_wildcard_is_a_key() {
+        let value: Value = "'*': {a: 1}\nb: {a: 2}\n".parse().unwrap();
+        assert_eq!(expand_wildcards(&value, r"\*.a"), vec![r"\*.a"]);
+        assert_eq!(expand_wildcards(&value, "*.a"), vec![r"\*.a", "b.a"]);
+        assert!(has_wildcard("a.*"));
+        assert!(!has_wildcard(r"a.\*"));
+        assert!(!has_wildcard("a.b*"));
+    }
+
+    #[test]
+    fn test_deletion_order() {
+        let paths = |paths: &[&str]| paths.iter().map(|p| p.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            deletion_order(&sample(), &paths(&["ports.0", "ports.1"])),
+            vec!["ports.1", "ports.0"]
+        );
+        assert_eq!(
+            deletion_order(
+                &sample(),
+                &paths(&["users.[name=bob]", "users.-1.age", "users.*.name"])
+            ),
+            vec!["users.1.age", "users.1.name", "users.0.name", "users.0"]
+        );
+        assert_eq!(
+            deletion_order(&sample(), &paths(&["ports.-1", "ports.1", "missing.-1"])),
+            vec!["missing.-1", "ports.1"]
+        );
+    }
+
+    #[test]
+    fn test_select_where() {
+        let names = |value: Value| match value {
+            Value::Sequence(seq) => seq
+                .iter()
+                .map(|user| {
+                    serialize_raw(get_at_path(user, Some("name"), &Options::default()).unwrap())
+                })
+                .collect::<Vec<_>>(),
+            _ => panic!("not a sequence"),
+        };
+        let value = sample();
+        assert_eq!(
+            names(select_where(Some("users"), "age=30", &value, &Options::default()).unwrap()),
+            vec!["alice"]
+        );
+        assert_eq!(
+            names(select_where(Some("users"), "name!=alice", &value, &Options::default()).unwrap()),
+            vec!["bob"]
+        );
+        assert_eq!(
+            names(select_where(Some("users"), "name~^(a|b)", &value, &Options::default()).unwrap()),
+            vec!["bob", "alice"]
+        );
+        assert_eq!(
+            names(select_where(Some("users"), "email!=x", &value, &Options::default()).unwrap()),
+            Vec::<String>::new()
+        );
+    }
+
+    #[test]
+    fn test_select_where_errors() {
+        let value = sample();
+        let err = select_where(Some("users"), "name", &value, &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("invalid condition 'name'"));
+        let err = select_where(Some("users"), "name~(", &value, &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("invalid condition 'name~('"));
+        let err = select_where(None, "name=bob", &value, &Options::default()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("select-where does not support 'struct' type"));
+    }
+
+    #[test]
+    fn test_resolve_stops_at_missing_key() {
+        assert_eq!(
+            resolve_filters(&sample(), "missing.[name=bob]").unwrap(),
+            "missing.[name=bob]"
+        );
+    }
+}
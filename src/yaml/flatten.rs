@@ -0,0 +1,410 @@
+//! Flattening of nested structures into leaves.
+//!
+//! Provides the `flatten` action, listing every leaf of a structure with the
+//! dot-notation path leading to it, the traversal shared with the dotenv
+//! output and `key-values --recursive`, and its inverse `unflatten`.
+
+use super::error::Error;
+use super::mutation::{parse_value, set_value_at_path};
+use super::path::{join_elements, split_assignment, split_path};
+use super::query::{
+    as_mapping, get_at_path, get_value_ref, key_values_ref, type_error_seq_or_struct,
+};
+use super::serialize::{serialize_raw, serialize_raw_ref};
+use super::style::key_text;
+use super::{InnerValue, Options};
+use fyaml::{Document, Value, ValueRef};
+use indexmap::IndexMap;
+
+/// Path element designating a mapping key: the text of a scalar, and the
+/// `:(VALUE)` form for a mapping or sequence (see `key_text`).
+fn key_element(key: &Value) -> String {
+    match key.inner() {
+        Value::Mapping(_) | Value::Sequence(_) => match key_text(key) {
+            Ok(text) => format!(":({})", text),
+            Err(_) => serialize_raw(key),
+        },
+        _ => serialize_raw(key),
+    }
+}
+
+/// Like `key_element()`, for a key of a document.
+fn key_element_ref(key: ValueRef<'_>) -> String {
+    if key.is_mapping() || key.is_sequence() {
+        if let Ok(key) = Value::from_node_ref(key.as_node()) {
+            return key_element(&key);
+        }
+    }
+    serialize_raw_ref(key)
+}
+
+/// Node that can be flattened into leaves.
+///
+/// Implemented for both `ValueRef` (zero-copy) and `&Value` (owned) so
+/// that both execution modes share the flattening logic.
+pub(super) trait FlattenNode: Sized {
+    /// Children of a mapping or sequence (keyed by index), `None` for scalars.
+    fn children(&self) -> Option<Vec<(String, Self)>>;
+    /// Raw string representation of a scalar.
+    fn raw(&self) -> String;
+    /// Whether the node is a sequence, whose children are keyed by index.
+    fn is_sequence(&self) -> bool;
+}
+
+impl<'a> FlattenNode for ValueRef<'a> {
+    fn children(&self) -> Option<Vec<(String, Self)>> {
+        if self.is_mapping() {
+            Some(
+                self.map_iter()
+                    .map(|(k, v)| (key_element_ref(k), v))
+                    .collect(),
+            )
+        } else if self.is_sequence() {
+            Some(
+                self.seq_iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v))
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn raw(&self) -> String {
+        serialize_raw_ref(*self)
+    }
+
+    fn is_sequence(&self) -> bool {
+        ValueRef::is_sequence(self)
+    }
+}
+
+impl FlattenNode for &Value {
+    fn children(&self) -> Option<Vec<(String, Self)>> {
+        match self.inner() {
+            Value::Mapping(map) => Some(map.iter().map(|(k, v)| (key_element(k), v)).collect()),
+            Value::Sequence(seq) => Some(
+                seq.iter()
+                    .enumerate()
+                    .map(|(i, v)| (i.to_string(), v))
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+
+    fn raw(&self) -> String {
+        serialize_raw(self)
+    }
+
+    fn is_sequence(&self) -> bool {
+        matches!(self.inner(), Value::Sequence(_))
+    }
+}
+
+/// Collect the leaves below a node, with the path elements leading to them.
+///
+/// Leaves are scalars and empty mappings or sequences.
+pub(super) fn leaves<N: FlattenNode>(node: N) -> Vec<(Vec<String>, N)> {
+    let mut result = Vec::new();
+    collect_leaves(node, true, &mut Vec::new(), &mut result);
+    result
+}
+
+/// Collect the leaves below a node like `leaves`, sequences being leaves
+/// too.
+fn mapping_leaves<N: FlattenNode>(node: N) -> Vec<(Vec<String>, N)> {
+    let mut result = Vec::new();
+    collect_leaves(node, false, &mut Vec::new(), &mut result);
+    result
+}
+
+fn collect_leaves<N: FlattenNode>(
+    node: N,
+    sequences: bool,
+    elements: &mut Vec<String>,
+    result: &mut Vec<(Vec<String>, N)>,
+) {
+    if !sequences && node.is_sequence() {
+        result.push((elements.clone(), node));
+        return;
+    }
+    match node.children() {
+        Some(children) if !children.is_empty() => {
+            for (key, child) in children {
+                elements.push(key);
+                collect_leaves(child, sequences, elements, result);
+                elements.pop();
+            }
+        }
+        _ => result.push((elements.clone(), node)),
+    }
+}
+
+/// Build the full path of a leaf, below the optional base path.
+fn leaf_path(base: Option<&str>, elements: Vec<String>) -> String {
+    let mut parts = base.map(split_path).unwrap_or_default();
+    parts.extend(elements);
+    join_elements(&parts)
+}
+
+/// List every leaf below path with its full dot-notation path (zero-copy).
+pub fn flatten_ref<'a>(
+    path: Option<&str>,
+    doc: &'a Document,
+    opts: &Options,
+) -> Result<Vec<(String, ValueRef<'a>)>, Error> {
+    let value = get_value_ref(path, doc, opts)?;
+
+    if !value.is_sequence() && !value.is_mapping() {
+        return Err(type_error_seq_or_struct("flatten", &value));
+    }
+
+    Ok(leaves(value)
+        .into_iter()
+        .map(|(elements, leaf)| (leaf_path(path, elements), leaf))
+        .collect())
+}
+
+/// List every leaf below path as a mapping from full dot-notation path to value.
+pub fn flatten(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let target = get_at_path(value, path, opts)?;
+
+    if !matches!(target.inner(), Value::Sequence(_) | Value::Mapping(_)) {
+        return Err(type_error_seq_or_struct("flatten", target));
+    }
+
+    let result: IndexMap<Value, Value> = leaves(target)
+        .into_iter()
+        .map(|(elements, leaf)| (Value::String(leaf_path(path, elements)), leaf.clone()))
+        .collect();
+    Ok(Value::Mapping(result))
+}
+
+/// Path of a leaf below the value of `key`, relative to their mapping.
+fn key_path(key: String, elements: Vec<String>) -> String {
+    let mut parts = vec![key];
+    parts.extend(elements);
+    join_elements(&parts)
+}
+
+/// List every leaf below the mapping at path, walking nested mappings,
+/// with the dot-notation path leading to it from there (zero-copy).
+pub fn key_values_recursive_ref<'a>(
+    path: Option<&str>,
+    doc: &'a Document,
+    opts: &Options,
+) -> Result<Vec<(String, ValueRef<'a>)>, Error> {
+    Ok(key_values_ref(path, doc, opts)?
+        .flat_map(|(key, value)| {
+            let key = serialize_raw_ref(key);
+            mapping_leaves(value)
+                .into_iter()
+                .map(move |(elements, leaf)| (key_path(key.clone(), elements), leaf))
+        })
+        .collect())
+}
+
+/// List every leaf below the mapping at path, walking nested mappings, as
+/// alternating dot-notation paths from there and values.
+pub fn key_values_recursive(
+    path: Option<&str>,
+    value: &Value,
+    opts: &Options,
+) -> Result<Value, Error> {
+    let map = as_mapping(get_at_path(value, path, opts)?, "key-values", opts)?;
+    let result: Vec<Value> = map
+        .iter()
+        .flat_map(|(key, value)| {
+            let key = serialize_raw(key);
+            mapping_leaves(value)
+                .into_iter()
+                .flat_map(move |(elements, leaf)| {
+                    [Value::String(key_path(key.clone(), elements)), leaf.clone()]
+                })
+        })
+        .collect();
+    Ok(Value::Sequence(result))
+}
+
+/// Build a structure from `dotted.path=value` lines.
+///
+/// Lines are split as `set-values` assignments are. Values get the same
+/// scalar type inference as `set-value`, `{}` and `[]` being the empty
+/// leaves `flatten` writes, and mappings whose keys are exactly `0` to
+/// `n-1` become sequences again, so that the lines given by `flatten`
+/// rebuild the original structure.
+pub fn unflatten(input: &str, opts: &Options) -> Result<Value, Error> {
+    let mut root = Value::Mapping(IndexMap::new());
+
+    for (i, line) in input.lines().enumerate() {
+        if line.is_empty() {
+            continue;
+        }
+        let (path, value) = split_assignment(line).ok_or_else(|| {
+            Error::Parse(format!(
+                "invalid line {}: '{}', expected 'path=value'.",
+                i + 1,
+                line
+            ))
+        })?;
+        let value = match value {
+            "{}" => Value::Mapping(IndexMap::new()),
+            "[]" => Value::Sequence(vec![]),
+            _ => parse_value(value, false)?,
+        };
+        set_value_at_path(&mut root, path, value, opts)?;
+    }
+
+    Ok(index_keys_to_sequences(root))
+}
+
+/// Turn mappings keyed by `0` to `n-1` back into sequences, recursively.
+fn index_keys_to_sequences(value: Value) -> Value {
+    let Value::Mapping(map) = value else {
+        return value;
+    };
+    let mut map: IndexMap<Value, Value> = map
+        .into_iter()
+        .map(|(k, v)| (k, index_keys_to_sequences(v)))
+        .collect();
+
+    let len = map.len();
+    let is_index = |k: &Value| matches!(k, Value::String(s) if s.parse::<usize>().is_ok_and(|i| i < len && i.to_string() == *s));
+    if len == 0 || !map.keys().all(is_index) {
+        return Value::Mapping(map);
+    }
+    Value::Sequence(
+        (0..len)
+            .map(|i| map.swap_remove(&Value::String(i.to_string())).unwrap())
+            .collect(),
+    )
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fyaml::Number;
+    use indexmap::indexmap;
+
+    fn sample() -> Value {
+        Value::Mapping(indexmap! {
+            Value::String("a.b".to_string()) => Value::Mapping(indexmap! {
+                Value::String("c".to_string()) => Value::Number(Number::Int(1)),
+            }),
+            Value::String("list".to_string()) => Value::Sequence(vec![
+                Value::String("x".to_string()),
+                Value::Mapping(IndexMap::new()),
+            ]),
+        })
+    }
+
+    #[test]
+    fn test_flatten_escapes_paths() {
+        assert_eq!(
+            flatten(None, &sample(), &Options::default()).unwrap(),
+            Value::Mapping(indexmap! {
+                Value::String(r"a\.b.c".to_string()) => Value::Number(Number::Int(1)),
+                Value::String("list.0".to_string()) => Value::String("x".to_string()),
+                Value::String("list.1".to_string()) => Value::Mapping(IndexMap::new()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_flatten_keeps_base_path() {
+        assert_eq!(
+            flatten(Some(r"a\.b"), &sample(), &Options::default()).unwrap(),
+            Value::Mapping(indexmap! {
+                Value::String(r"a\.b.c".to_string()) => Value::Number(Number::Int(1)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_flatten_scalar_errors() {
+        let err = flatten(Some("list.0"), &sample(), &Options::default()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("flatten does not support 'str' type"));
+    }
+
+    #[test]
+    fn test_key_values_recursive_stops_at_sequences() {
+        assert_eq!(
+            key_values_recursive(None, &sample(), &Options::default()).unwrap(),
+            Value::Sequence(vec![
+                Value::String(r"a\.b.c".to_string()),
+                Value::Number(Number::Int(1)),
+                Value::String("list".to_string()),
+                Value::Sequence(vec![
+                    Value::String("x".to_string()),
+                    Value::Mapping(IndexMap::new()),
+                ]),
+            ])
+        );
+        let err = key_values_recursive(Some("list"), &sample(), &Options::default()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("key-values does not support 'sequence' type"));
+    }
+
+    #[test]
+    fn test_unflatten_rebuilds_structure() {
+        assert_eq!(
+            unflatten("a\\.b.c=1\nlist.0=x\nlist.1=true\n", &Options::default()).unwrap(),
+            Value::Mapping(indexmap! {
+                Value::String("a.b".to_string()) => Value::Mapping(indexmap! {
+                    Value::String("c".to_string()) => Value::Number(Number::Int(1)),
+                }),
+                Value::String("list".to_string()) => Value::Sequence(vec![
+                    Value::String("x".to_string()),
+                    Value::Bool(true),
+                ]),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unflatten_round_trips_flatten() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("a=b".to_string()) => Value::Mapping(indexmap! {
+                Value::String("[c=d]".to_string()) => Value::Number(Number::Int(1)),
+            }),
+            Value::String("e".to_string()) => Value::String("f=g".to_string()),
+        });
+        let Value::Mapping(leaves) = flatten(None, &value, &Options::default()).unwrap() else {
+            panic!("not a mapping");
+        };
+        let lines: String = leaves
+            .iter()
+            .map(|(path, leaf)| format!("{}={}\n", serialize_raw(path), serialize_raw(leaf)))
+            .collect();
+        assert_eq!(lines, "a\\=b.\\[c\\=d]=1\ne=f=g\n");
+        assert_eq!(unflatten(&lines, &Options::default()).unwrap(), value);
+    }
+
+    #[test]
+    fn test_unflatten_keeps_non_index_mappings() {
+        assert_eq!(
+            unflatten("a.1=x\na.2=y\n", &Options::default()).unwrap(),
+            Value::Mapping(indexmap! {
+                Value::String("a".to_string()) => Value::Mapping(indexmap! {
+                    Value::String("1".to_string()) => Value::String("x".to_string()),
+                    Value::String("2".to_string()) => Value::String("y".to_string()),
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn test_unflatten_invalid_line_errors() {
+        let err = unflatten("a=1\nb\n", &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("invalid line 2: 'b'"));
+    }
+}
@@ -0,0 +1,1149 @@
+//! jq-style filters of the `filter` action.
+//!
+//! A subset of the jq language, run on the parsed document:
+//!
+//! ```text
+//! .spec.containers[].image
+//! .[] | select(.enabled) | .name
+//! map(.port) | add
+//! {name, ports: [.ports[] | . + 1]}
+//! ```
+//!
+//! A filter gives any number of results: `.[]` gives one per element, `,`
+//! concatenates the results of two filters, `|` feeds each result of its
+//! left side to its right side, and `select()` drops the values its
+//! condition is false or null for. `$name` is the value of a variable of
+//! `--arg` or `--argjson`.
+
+use super::error::Error;
+use super::expr::{arithmetic, symbol, type_error, BinOp};
+use super::serialize::serialize_raw;
+use super::update::{as_f64, as_i64, format_number, parse_number};
+use super::vars::Vars;
+use super::InnerValue;
+use fyaml::{Number, Value};
+use indexmap::IndexMap;
+use std::cmp::Ordering;
+
+/// Functions callable in filters, with their number of arguments.
+const FUNCTIONS: &[(&str, usize)] = &[
+    ("add", 0),
+    ("ascii_downcase", 0),
+    ("ascii_upcase", 0),
+    ("empty", 0),
+    ("has", 1),
+    ("keys", 0),
+    ("length", 0),
+    ("map", 1),
+    ("not", 0),
+    ("select", 1),
+    ("to_entries", 0),
+    ("tonumber", 0),
+    ("tostring", 0),
+    ("type", 0),
+];
+
+/// Punctuation, longest first.
+const PUNCTUATION: &[&str] = &[
+    "..", "==", "!=", "<=", ">=", "//", "|", ",", ":", ";", "?", "(", ")", "[", "]", "{", "}", "+",
+    "-", "*", "/", "%", "<", ">", ".",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Node {
+    /// `.`
+    Identity,
+    /// `..`, the input and all its descendants
+    Recurse,
+    Literal(Value),
+    /// `.key` and `.[index]`, the index being computed from the input
+    Index(Box<Node>, Box<Node>),
+    Slice(Box<Node>, Option<Box<Node>>, Option<Box<Node>>),
+    /// `.[]`
+    Iterate(Box<Node>),
+    /// `f?`, dropping the errors of `f`
+    Try(Box<Node>),
+    Pipe(Box<Node>, Box<Node>),
+    Comma(Box<Node>, Box<Node>),
+    /// `a // b`
+    Alternative(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Compare(CmpOp, Box<Node>, Box<Node>),
+    Arith(BinOp, Box<Node>, Box<Node>),
+    Neg(Box<Node>),
+    /// `[f]`, collecting the results of `f`
+    Array(Option<Box<Node>>),
+    Object(Vec<(Node, Node)>),
+    Call(&'static str, Vec<Node>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    /// `.key` or `."key"`
+    Field(String),
+    Number(Number),
+    Str(String),
+    Ident(String),
+    /// `$name`
+    Var(String),
+    Punct(&'static str),
+}
+
+/// Parsed filter of a `filter` action.
+#[derive(Clone, Debug)]
+pub struct JqFilter {
+    source: String,
+    root: Node,
+}
+
+impl JqFilter {
+    /// Parse a filter, with the values of its `$name` variables in `vars`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on syntax errors, unknown functions, calls with
+    /// the wrong number of arguments and undefined variables.
+    pub fn parse_with_vars(source: &str, vars: &Vars) -> Result<Self, Error> {
+        let invalid =
+            |message: String| Error::Base(format!("invalid filter '{}': {}", source, message));
+        let tokens = tokenize(source).map_err(invalid)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            vars,
+        };
+        let root = parser.pipe().map_err(invalid)?;
+        if let Some((token, column)) = parser.tokens.get(parser.pos) {
+            return Err(invalid(format!(
+                "unexpected {} at column {}",
+                describe(token),
+                column
+            )));
+        }
+        Ok(JqFilter {
+            source: source.to_string(),
+            root,
+        })
+    }
+
+    /// Results of the filter with `value` as input.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error when an operation doesn't apply to the values it
+    /// is given, outside of a `?`.
+    pub fn run(&self, value: &Value) -> Result<Vec<Value>, Error> {
+        eval(&self.root, value)
+            .map_err(|e| Error::Base(format!("filter '{}' failed: {}", self.source, e)))
+    }
+}
+
+// =============================================================================
+// Parsing
+// =============================================================================
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Field(name) => format!("'.{}'", name),
+        Token::Number(n) => format!("number {}", format_number(n)),
+        Token::Str(s) => format!("string \"{}\"", s),
+        Token::Ident(name) => format!("'{}'", name),
+        Token::Var(name) => format!("'${}'", name),
+        Token::Punct(p) => format!("'{}'", p),
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Read the double-quoted string starting at `chars[*i]`.
+fn string_literal(chars: &[char], i: &mut usize) -> Result<String, String> {
+    let column = *i + 1;
+    let mut s = String::new();
+    *i += 1;
+    loop {
+        match chars.get(*i) {
+            None => return Err(format!("unterminated string at column {}", column)),
+            Some('"') => break,
+            Some('\\') => {
+                *i += 1;
+                match chars.get(*i) {
+                    Some('n') => s.push('\n'),
+                    Some('t') => s.push('\t'),
+                    Some(&e) => s.push(e),
+                    None => return Err(format!("unterminated string at column {}", column)),
+                }
+            }
+            Some(&other) => s.push(other),
+        }
+        *i += 1;
+    }
+    *i += 1;
+    Ok(s)
+}
+
+/// Split a filter into tokens, with the column they start at.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, String> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let column = i + 1;
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '.' if chars.get(i + 1).is_some_and(|c| is_ident_start(*c)) => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                Token::Field(chars[start..i].iter().collect())
+            }
+            '.' if chars.get(i + 1) == Some(&'"') => {
+                i += 1;
+                Token::Field(string_literal(&chars, &mut i)?)
+            }
+            '"' => Token::Str(string_literal(&chars, &mut i)?),
+            c if c.is_ascii_digit() => {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                if i < chars.len() && matches!(chars[i], 'e' | 'E') {
+                    i += 1;
+                    if i < chars.len() && matches!(chars[i], '+' | '-') {
+                        i += 1;
+                    }
+                    while i < chars.len() && chars[i].is_ascii_digit() {
+                        i += 1;
+                    }
+                }
+                let text: String = chars[start..i].iter().collect();
+                Token::Number(
+                    parse_number(&text)
+                        .map_err(|_| format!("invalid number '{}' at column {}", text, column))?,
+                )
+            }
+            '$' if chars.get(i + 1).is_some_and(|c| is_ident_start(*c)) => {
+                let start = i + 1;
+                i = start;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                Token::Var(chars[start..i].iter().collect())
+            }
+            c if is_ident_start(c) => {
+                let start = i;
+                while i < chars.len() && is_ident_char(chars[i]) {
+                    i += 1;
+                }
+                Token::Ident(chars[start..i].iter().collect())
+            }
+            _ => {
+                let rest: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                let punct = PUNCTUATION
+                    .iter()
+                    .copied()
+                    .find(|p| rest.starts_with(p))
+                    .ok_or_else(|| format!("unexpected '{}' at column {}", c, column))?;
+                i += punct.chars().count();
+                Token::Punct(punct)
+            }
+        };
+        tokens.push((token, column));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    vars: &'a Vars,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_punct(&self, punct: &str) -> bool {
+        matches!(self.peek(), Some(Token::Punct(p)) if *p == punct)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(name)) if name == keyword)
+    }
+
+    fn advance(&mut self) -> Result<(Token, usize), String> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| "unexpected end of filter".to_string())?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, punct: &'static str) -> Result<(), String> {
+        match self.advance()? {
+            (Token::Punct(p), _) if p == punct => Ok(()),
+            (token, column) => Err(format!(
+                "expected '{}' at column {}, found {}",
+                punct,
+                column,
+                describe(&token)
+            )),
+        }
+    }
+
+    /// `comma ('|' comma)*`
+    fn pipe(&mut self) -> Result<Node, String> {
+        let mut node = self.comma()?;
+        while self.peek_punct("|") {
+            self.pos += 1;
+            node = Node::Pipe(Box::new(node), Box::new(self.comma()?));
+        }
+        Ok(node)
+    }
+
+    /// `alternative (',' alternative)*`
+    fn comma(&mut self) -> Result<Node, String> {
+        let mut node = self.alternative()?;
+        while self.peek_punct(",") {
+            self.pos += 1;
+            node = Node::Comma(Box::new(node), Box::new(self.alternative()?));
+        }
+        Ok(node)
+    }
+
+    /// `or ('//' alternative)?`
+    fn alternative(&mut self) -> Result<Node, String> {
+        let node = self.or()?;
+        if self.peek_punct("//") {
+            self.pos += 1;
+            return Ok(Node::Alternative(
+                Box::new(node),
+                Box::new(self.alternative()?),
+            ));
+        }
+        Ok(node)
+    }
+
+    /// `and ('or' and)*`
+    fn or(&mut self) -> Result<Node, String> {
+        let mut node = self.and()?;
+        while self.peek_keyword("or") {
+            self.pos += 1;
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    /// `compare ('and' compare)*`
+    fn and(&mut self) -> Result<Node, String> {
+        let mut node = self.compare()?;
+        while self.peek_keyword("and") {
+            self.pos += 1;
+            node = Node::And(Box::new(node), Box::new(self.compare()?));
+        }
+        Ok(node)
+    }
+
+    /// `additive (('==' | '!=' | '<' | '<=' | '>' | '>=') additive)?`
+    fn compare(&mut self) -> Result<Node, String> {
+        let node = self.additive()?;
+        let op = match self.peek() {
+            Some(Token::Punct("==")) => CmpOp::Eq,
+            Some(Token::Punct("!=")) => CmpOp::Ne,
+            Some(Token::Punct("<")) => CmpOp::Lt,
+            Some(Token::Punct("<=")) => CmpOp::Le,
+            Some(Token::Punct(">")) => CmpOp::Gt,
+            Some(Token::Punct(">=")) => CmpOp::Ge,
+            _ => return Ok(node),
+        };
+        self.pos += 1;
+        Ok(Node::Compare(
+            op,
+            Box::new(node),
+            Box::new(self.additive()?),
+        ))
+    }
+
+    /// `term (('+' | '-') term)*`
+    fn additive(&mut self) -> Result<Node, String> {
+        let mut node = self.term()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct("+")) => BinOp::Add,
+                Some(Token::Punct("-")) => BinOp::Sub,
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+            node = Node::Arith(op, Box::new(node), Box::new(self.term()?));
+        }
+    }
+
+    /// `unary (('*' | '/' | '%') unary)*`
+    fn term(&mut self) -> Result<Node, String> {
+        let mut node = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Punct("*")) => BinOp::Mul,
+                Some(Token::Punct("/")) => BinOp::Div,
+                Some(Token::Punct("%")) => BinOp::Rem,
+                _ => return Ok(node),
+            };
+            self.pos += 1;
+            node = Node::Arith(op, Box::new(node), Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Node, String> {
+        if self.peek_punct("-") {
+            self.pos += 1;
+            return Ok(Node::Neg(Box::new(self.unary()?)));
+        }
+        self.postfix()
+    }
+
+    /// `primary ('.key' | '[' ... ']' | '?')*`
+    fn postfix(&mut self) -> Result<Node, String> {
+        let mut node = self.primary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Field(name)) => {
+                    let key = Node::Literal(Value::String(name.clone()));
+                    self.pos += 1;
+                    node = Node::Index(Box::new(node), Box::new(key));
+                }
+                // `.a.[0]` is `.a[0]`
+                Some(Token::Punct("."))
+                    if self.tokens.get(self.pos + 1).map(|(t, _)| t)
+                        == Some(&Token::Punct("[")) =>
+                {
+                    self.pos += 1;
+                }
+                Some(Token::Punct("[")) => {
+                    self.pos += 1;
+                    node = self.bracket(node)?;
+                }
+                Some(Token::Punct("?")) => {
+                    self.pos += 1;
+                    node = Node::Try(Box::new(node));
+                }
+                _ => return Ok(node),
+            }
+        }
+    }
+
+    /// The suffix of `node` after `[`: `]`, `index]`, or `from:to]`.
+    fn bracket(&mut self, node: Node) -> Result<Node, String> {
+        if self.peek_punct("]") {
+            self.pos += 1;
+            return Ok(Node::Iterate(Box::new(node)));
+        }
+        let mut from = None;
+        if !self.peek_punct(":") {
+            let index = self.pipe()?;
+            if !self.peek_punct(":") {
+                self.expect("]")?;
+                return Ok(Node::Index(Box::new(node), Box::new(index)));
+            }
+            from = Some(Box::new(index));
+        }
+        self.pos += 1;
+        let to = if self.peek_punct("]") {
+            None
+        } else {
+            Some(Box::new(self.pipe()?))
+        };
+        self.expect("]")?;
+        Ok(Node::Slice(Box::new(node), from, to))
+    }
+
+    fn primary(&mut self) -> Result<Node, String> {
+        let (token, column) = self.advance()?;
+        match token {
+            Token::Punct(".") => Ok(Node::Identity),
+            Token::Punct("..") => Ok(Node::Recurse),
+            Token::Field(name) => Ok(Node::Index(
+                Box::new(Node::Identity),
+                Box::new(Node::Literal(Value::String(name))),
+            )),
+            Token::Number(n) => Ok(Node::Literal(Value::Number(n))),
+            Token::Str(s) => Ok(Node::Literal(Value::String(s))),
+            Token::Ident(name) if name == "true" || name == "false" => {
+                Ok(Node::Literal(Value::Bool(name == "true")))
+            }
+            Token::Ident(name) if name == "null" => Ok(Node::Literal(Value::Null)),
+            Token::Ident(name) if name != "and" && name != "or" => self.call(&name, column),
+            Token::Var(name) => match self.vars.get(&name) {
+                Ok(value) => Ok(Node::Literal(value.clone())),
+                Err(_) => Err(format!(
+                    "undefined variable '${}' at column {}",
+                    name, column
+                )),
+            },
+            Token::Punct("(") => {
+                let node = self.pipe()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Token::Punct("[") => {
+                if self.peek_punct("]") {
+                    self.pos += 1;
+                    return Ok(Node::Array(None));
+                }
+                let node = self.pipe()?;
+                self.expect("]")?;
+                Ok(Node::Array(Some(Box::new(node))))
+            }
+            Token::Punct("{") => self.object(),
+            other => Err(format!(
+                "unexpected {} at column {}",
+                describe(&other),
+                column
+            )),
+        }
+    }
+
+    /// A function call, its arguments separated by `;`.
+    fn call(&mut self, name: &str, column: usize) -> Result<Node, String> {
+        let &(function, arity) = FUNCTIONS
+            .iter()
+            .find(|(f, _)| *f == name)
+            .ok_or_else(|| format!("unknown function '{}' at column {}", name, column))?;
+        let mut args = Vec::new();
+        if arity > 0 {
+            self.expect("(")?;
+            args.push(self.pipe()?);
+            while self.peek_punct(";") {
+                self.pos += 1;
+                args.push(self.pipe()?);
+            }
+            self.expect(")")?;
+        }
+        if args.len() != arity {
+            return Err(format!(
+                "{}() takes {} argument{}, {} given",
+                function,
+                arity,
+                if arity == 1 { "" } else { "s" },
+                args.len()
+            ));
+        }
+        Ok(Node::Call(function, args))
+    }
+
+    /// The entries of an object after `{`: `key: value`, or `key` for
+    /// `key: .key`.
+    fn object(&mut self) -> Result<Node, String> {
+        let mut entries = Vec::new();
+        while !self.peek_punct("}") {
+            if !entries.is_empty() {
+                self.expect(",")?;
+            }
+            let (token, column) = self.advance()?;
+            let key = match token {
+                Token::Ident(name) | Token::Str(name) => Node::Literal(Value::String(name)),
+                Token::Punct("(") => {
+                    let key = self.pipe()?;
+                    self.expect(")")?;
+                    key
+                }
+                other => {
+                    return Err(format!(
+                        "unexpected {} at column {}",
+                        describe(&other),
+                        column
+                    ))
+                }
+            };
+            let value = if self.peek_punct(":") {
+                self.pos += 1;
+                self.alternative()?
+            } else {
+                Node::Index(Box::new(Node::Identity), Box::new(key.clone()))
+            };
+            entries.push((key, value));
+        }
+        self.pos += 1;
+        Ok(Node::Object(entries))
+    }
+}
+
+// =============================================================================
+// Evaluation
+// =============================================================================
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value.inner(), Value::Null | Value::Bool(false))
+}
+
+/// Rank of the types in the jq ordering.
+fn rank(value: &Value) -> u8 {
+    match value.inner() {
+        Value::Null => 0,
+        Value::Bool(_) => 1,
+        Value::Number(_) => 2,
+        Value::String(_) => 3,
+        Value::Sequence(_) => 4,
+        _ => 5,
+    }
+}
+
+/// Keys of a mapping in sorted order, with their value.
+fn sorted_entries(map: &IndexMap<Value, Value>) -> Vec<(String, &Value)> {
+    let mut entries: Vec<(String, &Value)> =
+        map.iter().map(|(k, v)| (serialize_raw(k), v)).collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
+
+/// Order of two values: by type, then by content, mappings comparing their
+/// sorted keys first.
+fn compare(a: &Value, b: &Value) -> Ordering {
+    match (a.inner(), b.inner()) {
+        (Value::Bool(x), Value::Bool(y)) => x.cmp(y),
+        (Value::Number(x), Value::Number(y)) => match (as_i64(x), as_i64(y)) {
+            (Some(x), Some(y)) => x.cmp(&y),
+            _ => as_f64(x).partial_cmp(&as_f64(y)).unwrap_or(Ordering::Equal),
+        },
+        (Value::String(x), Value::String(y)) => x.cmp(y),
+        (Value::Sequence(x), Value::Sequence(y)) => x
+            .iter()
+            .zip(y.iter())
+            .map(|(x, y)| compare(x, y))
+            .find(|o| o.is_ne())
+            .unwrap_or_else(|| x.len().cmp(&y.len())),
+        (Value::Mapping(x), Value::Mapping(y)) => {
+            let (x, y) = (sorted_entries(x), sorted_entries(y));
+            let keys = |entries: &[(String, &Value)]| {
+                entries.iter().map(|(k, _)| k.clone()).collect::<Vec<_>>()
+            };
+            keys(&x).cmp(&keys(&y)).then_with(|| {
+                x.iter()
+                    .zip(y.iter())
+                    .map(|((_, x), (_, y))| compare(x, y))
+                    .find(|o| o.is_ne())
+                    .unwrap_or(Ordering::Equal)
+            })
+        }
+        _ => rank(a).cmp(&rank(b)),
+    }
+}
+
+fn index(value: &Value, key: &Value) -> Result<Value, Error> {
+    match (value.inner(), key.inner()) {
+        (Value::Null, Value::String(_) | Value::Number(_)) => Ok(Value::Null),
+        (Value::Mapping(map), Value::String(name)) => Ok(map
+            .iter()
+            .find(|(k, _)| serialize_raw(k) == *name)
+            .map(|(_, v)| v.clone())
+            .unwrap_or(Value::Null)),
+        (Value::Sequence(seq), Value::Number(n)) => {
+            let i = as_i64(n).unwrap_or_else(|| as_f64(n).floor() as i64);
+            let i = if i < 0 { i + seq.len() as i64 } else { i };
+            Ok(usize::try_from(i)
+                .ok()
+                .and_then(|i| seq.get(i))
+                .cloned()
+                .unwrap_or(Value::Null))
+        }
+        _ => Err(type_error(
+            &format!("index '{}'", serialize_raw(key)),
+            value,
+        )),
+    }
+}
+
+/// Position of a slice bound in a collection of `len` elements.
+fn bound(value: Option<&Value>, default: usize, len: usize) -> Result<usize, Error> {
+    let n = match value.map(|v| v.inner()) {
+        None | Some(Value::Null) => return Ok(default),
+        Some(Value::Number(n)) => as_i64(n).unwrap_or_else(|| as_f64(n).floor() as i64),
+        Some(other) => return Err(type_error("slice", other)),
+    };
+    let n = if n < 0 { n + len as i64 } else { n };
+    Ok(n.clamp(0, len as i64) as usize)
+}
+
+fn slice(value: &Value, from: Option<&Value>, to: Option<&Value>) -> Result<Value, Error> {
+    match value.inner() {
+        Value::Null => Ok(Value::Null),
+        Value::Sequence(seq) => {
+            let (from, to) = (bound(from, 0, seq.len())?, bound(to, seq.len(), seq.len())?);
+            Ok(Value::Sequence(seq[from..to.max(from)].to_vec()))
+        }
+        Value::String(s) => {
+            let chars: Vec<char> = s.chars().collect();
+            let (from, to) = (
+                bound(from, 0, chars.len())?,
+                bound(to, chars.len(), chars.len())?,
+            );
+            Ok(Value::String(chars[from..to.max(from)].iter().collect()))
+        }
+        other => Err(type_error("slice", other)),
+    }
+}
+
+fn iterate(value: &Value) -> Result<Vec<Value>, Error> {
+    match value.inner() {
+        Value::Sequence(seq) => Ok(seq.clone()),
+        Value::Mapping(map) => Ok(map.values().cloned().collect()),
+        other => Err(type_error("'.[]'", other)),
+    }
+}
+
+fn recurse(value: &Value, results: &mut Vec<Value>) {
+    results.push(value.clone());
+    if let Ok(children) = iterate(value) {
+        for child in &children {
+            recurse(child, results);
+        }
+    }
+}
+
+fn arith(op: BinOp, a: &Value, b: &Value) -> Result<Value, Error> {
+    match (op, a.inner(), b.inner()) {
+        (_, Value::Number(x), Value::Number(y)) => arithmetic(op, x, y),
+        (BinOp::Add, Value::Null, _) => Ok(b.clone()),
+        (BinOp::Add, _, Value::Null) => Ok(a.clone()),
+        (BinOp::Add, Value::String(x), Value::String(y)) => {
+            Ok(Value::String(format!("{}{}", x, y)))
+        }
+        (BinOp::Add, Value::Sequence(x), Value::Sequence(y)) => {
+            Ok(Value::Sequence(x.iter().chain(y).cloned().collect()))
+        }
+        (BinOp::Add, Value::Mapping(x), Value::Mapping(y)) => {
+            let mut merged = x.clone();
+            merged.extend(y.iter().map(|(k, v)| (k.clone(), v.clone())));
+            Ok(Value::Mapping(merged))
+        }
+        (BinOp::Sub, Value::Sequence(x), Value::Sequence(y)) => Ok(Value::Sequence(
+            x.iter()
+                .filter(|v| !y.iter().any(|w| compare(v, w).is_eq()))
+                .cloned()
+                .collect(),
+        )),
+        // Report the operand of the wrong type
+        (_, Value::Number(_), _) => Err(type_error(&format!("'{}'", symbol(op)), b)),
+        _ => Err(type_error(&format!("'{}'", symbol(op)), a)),
+    }
+}
+
+/// Results of `f` on the same input, for each result of `a` and `b`.
+fn product(
+    a: &Node,
+    b: &Node,
+    input: &Value,
+    mut f: impl FnMut(&Value, &Value) -> Result<Value, Error>,
+) -> Result<Vec<Value>, Error> {
+    let rights = eval(b, input)?;
+    let mut results = Vec::new();
+    for left in eval(a, input)? {
+        for right in &rights {
+            results.push(f(&left, right)?);
+        }
+    }
+    Ok(results)
+}
+
+fn eval(node: &Node, input: &Value) -> Result<Vec<Value>, Error> {
+    Ok(match node {
+        Node::Identity => vec![input.clone()],
+        Node::Recurse => {
+            let mut results = Vec::new();
+            recurse(input, &mut results);
+            results
+        }
+        Node::Literal(value) => vec![value.clone()],
+        Node::Index(target, key) => product(target, key, input, index)?,
+        Node::Slice(target, from, to) => {
+            let bounds = |node: &Option<Box<Node>>| -> Result<Vec<Option<Value>>, Error> {
+                match node {
+                    Some(node) => eval(node, input).map(|v| v.into_iter().map(Some).collect()),
+                    None => Ok(vec![None]),
+                }
+            };
+            let (froms, tos) = (bounds(from)?, bounds(to)?);
+            let mut results = Vec::new();
+            for value in eval(target, input)? {
+                for from in &froms {
+                    for to in &tos {
+                        results.push(slice(&value, from.as_ref(), to.as_ref())?);
+                    }
+                }
+            }
+            results
+        }
+        Node::Iterate(target) => {
+            let mut results = Vec::new();
+            for value in eval(target, input)? {
+                results.extend(iterate(&value)?);
+            }
+            results
+        }
+        Node::Try(inner) => eval(inner, input).unwrap_or_default(),
+        Node::Pipe(left, right) => {
+            let mut results = Vec::new();
+            for value in eval(left, input)? {
+                results.extend(eval(right, &value)?);
+            }
+            results
+        }
+        Node::Comma(left, right) => {
+            let mut results = eval(left, input)?;
+            results.extend(eval(right, input)?);
+            results
+        }
+        Node::Alternative(left, right) => {
+            let results: Vec<Value> = eval(left, input)
+                .unwrap_or_default()
+                .into_iter()
+                .filter(truthy)
+                .collect();
+            if results.is_empty() {
+                eval(right, input)?
+            } else {
+                results
+            }
+        }
+        Node::And(left, right) | Node::Or(left, right) => {
+            let is_and = matches!(node, Node::And(..));
+            let mut results = Vec::new();
+            for value in eval(left, input)? {
+                // `false and f` and `true or f` don't run `f`
+                if truthy(&value) != is_and {
+                    results.push(Value::Bool(!is_and));
+                    continue;
+                }
+                for value in eval(right, input)? {
+                    results.push(Value::Bool(truthy(&value)));
+                }
+            }
+            results
+        }
+        Node::Compare(op, left, right) => product(left, right, input, |a, b| {
+            let order = compare(a, b);
+            Ok(Value::Bool(match op {
+                CmpOp::Eq => order.is_eq(),
+                CmpOp::Ne => order.is_ne(),
+                CmpOp::Lt => order.is_lt(),
+                CmpOp::Le => order.is_le(),
+                CmpOp::Gt => order.is_gt(),
+                CmpOp::Ge => order.is_ge(),
+            }))
+        })?,
+        Node::Arith(op, left, right) => product(left, right, input, |a, b| arith(*op, a, b))?,
+        Node::Neg(operand) => eval(operand, input)?
+            .iter()
+            .map(|value| match value.inner() {
+                Value::Number(n) => arithmetic(BinOp::Sub, &Number::Int(0), n),
+                other => Err(type_error("'-'", other)),
+            })
+            .collect::<Result<_, _>>()?,
+        Node::Array(inner) => {
+            let elements = match inner {
+                Some(inner) => eval(inner, input)?,
+                None => Vec::new(),
+            };
+            vec![Value::Sequence(elements)]
+        }
+        Node::Object(entries) => {
+            let mut objects = vec![IndexMap::new()];
+            for (key, value) in entries {
+                let keys = eval(key, input)?;
+                let values = eval(value, input)?;
+                let mut next = Vec::new();
+                for object in &objects {
+                    for key in &keys {
+                        if !matches!(key.inner(), Value::String(_)) {
+                            return Err(type_error("object key", key));
+                        }
+                        for value in &values {
+                            let mut object = object.clone();
+                            object.insert(key.clone(), value.clone());
+                            next.push(object);
+                        }
+                    }
+                }
+                objects = next;
+            }
+            objects.into_iter().map(Value::Mapping).collect()
+        }
+        Node::Call(function, args) => call(function, args, input)?,
+    })
+}
+
+fn call(function: &str, args: &[Node], input: &Value) -> Result<Vec<Value>, Error> {
+    let what = format!("{}()", function);
+    let what = what.as_str();
+    let result = match function {
+        "empty" => return Ok(Vec::new()),
+        "select" => {
+            return Ok(eval(&args[0], input)?
+                .iter()
+                .filter(|v| truthy(v))
+                .map(|_| input.clone())
+                .collect())
+        }
+        "has" => {
+            return eval(&args[0], input)?
+                .iter()
+                .map(|key| match (input.inner(), key.inner()) {
+                    (Value::Mapping(map), Value::String(name)) => {
+                        Ok(Value::Bool(map.keys().any(|k| serialize_raw(k) == *name)))
+                    }
+                    (Value::Sequence(seq), Value::Number(n)) => Ok(Value::Bool(
+                        as_i64(n).is_some_and(|i| i >= 0 && (i as usize) < seq.len()),
+                    )),
+                    (Value::Mapping(_) | Value::Sequence(_), _) => Err(type_error(what, key)),
+                    _ => Err(type_error(what, input)),
+                })
+                .collect();
+        }
+        "map" => {
+            let mut results = Vec::new();
+            for value in iterate(input).map_err(|_| type_error(what, input))? {
+                results.extend(eval(&args[0], &value)?);
+            }
+            Value::Sequence(results)
+        }
+        "not" => Value::Bool(!truthy(input)),
+        "length" => match input.inner() {
+            Value::Null => Value::Number(Number::Int(0)),
+            Value::Number(n) => match as_i64(n) {
+                Some(i) => Value::Number(Number::Int(i.saturating_abs())),
+                None => Value::Number(Number::Float(as_f64(n).abs())),
+            },
+            Value::String(s) => Value::Number(Number::Int(s.chars().count() as i64)),
+            Value::Sequence(seq) => Value::Number(Number::Int(seq.len() as i64)),
+            Value::Mapping(map) => Value::Number(Number::Int(map.len() as i64)),
+            other => return Err(type_error(what, other)),
+        },
+        "keys" => match input.inner() {
+            Value::Mapping(map) => Value::Sequence(
+                sorted_entries(map)
+                    .into_iter()
+                    .map(|(k, _)| Value::String(k))
+                    .collect(),
+            ),
+            Value::Sequence(seq) => Value::Sequence(
+                (0..seq.len() as i64)
+                    .map(|i| Value::Number(Number::Int(i)))
+                    .collect(),
+            ),
+            other => return Err(type_error(what, other)),
+        },
+        "to_entries" => match input.inner() {
+            Value::Mapping(map) => Value::Sequence(
+                map.iter()
+                    .map(|(k, v)| {
+                        Value::Mapping(IndexMap::from([
+                            (Value::String("key".to_string()), k.clone()),
+                            (Value::String("value".to_string()), v.clone()),
+                        ]))
+                    })
+                    .collect(),
+            ),
+            other => return Err(type_error(what, other)),
+        },
+        "add" => {
+            let mut sum = Value::Null;
+            for value in iterate(input).map_err(|_| type_error(what, input))? {
+                sum = arith(BinOp::Add, &sum, &value)?;
+            }
+            sum
+        }
+        "type" => Value::String(
+            match input.inner() {
+                Value::Null => "null",
+                Value::Bool(_) => "boolean",
+                Value::Number(_) => "number",
+                Value::String(_) => "string",
+                Value::Sequence(_) => "array",
+                _ => "object",
+            }
+            .to_string(),
+        ),
+        "tostring" => match input.inner() {
+            Value::Null => Value::String("null".to_string()),
+            v @ (Value::String(_) | Value::Number(_) | Value::Bool(_)) => {
+                Value::String(serialize_raw(v))
+            }
+            other => return Err(type_error(what, other)),
+        },
+        "tonumber" => match input.inner() {
+            Value::Number(n) => Value::Number(n.clone()),
+            Value::String(s) => Value::Number(
+                parse_number(s.trim())
+                    .map_err(|_| Error::Base(format!("tonumber() can't convert '{}'", s)))?,
+            ),
+            other => return Err(type_error(what, other)),
+        },
+        "ascii_downcase" | "ascii_upcase" => match input.inner() {
+            Value::String(s) if function == "ascii_downcase" => {
+                Value::String(s.to_ascii_lowercase())
+            }
+            Value::String(s) => Value::String(s.to_ascii_uppercase()),
+            other => return Err(type_error(what, other)),
+        },
+        _ => unreachable!("functions are checked when parsing"),
+    };
+    Ok(vec![result])
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::serialize;
+
+    const DOC: &str = "services:\n- {name: web, port: 80, enabled: true}\n- {name: db, port: 5432, enabled: false}\n- {name: cache, port: 6379}\n";
+
+    fn run(source: &str) -> Vec<String> {
+        let value: Value = DOC.parse().unwrap();
+        JqFilter::parse_with_vars(source, &Vars::default())
+            .unwrap()
+            .run(&value)
+            .unwrap()
+            .iter()
+            .map(|v| serialize_raw(v).trim_end().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_paths() {
+        assert_eq!(run(".services[0].name"), vec!["web"]);
+        assert_eq!(run(".services[-1].port"), vec!["6379"]);
+        assert_eq!(run(".services[].name"), vec!["web", "db", "cache"]);
+        assert_eq!(run(".services[1:][].name"), vec!["db", "cache"]);
+        assert_eq!(run(".missing.key | tostring"), vec!["null"]);
+        assert_eq!(run(".services | length"), vec!["3"]);
+    }
+
+    #[test]
+    fn test_select_and_pipes() {
+        assert_eq!(run(".services[] | select(.enabled) | .name"), vec!["web"]);
+        assert_eq!(
+            run(".services[] | select(.port > 100 and .enabled != true) | .name"),
+            vec!["db", "cache"]
+        );
+        assert_eq!(
+            run(".services[] | .enabled // \"unset\""),
+            vec!["true", "unset", "unset"]
+        );
+        assert_eq!(run(".services[0] | .name, .port"), vec!["web", "80"]);
+    }
+
+    #[test]
+    fn test_constructions() {
+        assert_eq!(run("[.services[].port] | add"), vec!["11891"]);
+        assert_eq!(run(".services | map(.port * 2) | .[0]"), vec!["160"]);
+        let value: Value = DOC.parse().unwrap();
+        let results = JqFilter::parse_with_vars(
+            ".services[0] | {name, url: (\"http://\" + .name)}",
+            &Vars::default(),
+        )
+        .unwrap()
+        .run(&value)
+        .unwrap();
+        assert_eq!(
+            serialize(&results[0]).unwrap(),
+            "name: web\nurl: http://web"
+        );
+    }
+
+    #[test]
+    fn test_functions() {
+        assert_eq!(run(".services[0] | keys | .[0]"), vec!["enabled"]);
+        assert_eq!(run(".services[2] | has(\"enabled\")"), vec!["false"]);
+        assert_eq!(run(".services[0].port | type"), vec!["number"]);
+        assert_eq!(run(".services[0].name | ascii_upcase"), vec!["WEB"]);
+        assert_eq!(run("\"42\" | tonumber + 1"), vec!["43"]);
+        assert_eq!(run("[.. | select(type == \"string\")] | length"), vec!["3"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let value: Value = DOC.parse().unwrap();
+        let err = JqFilter::parse_with_vars(".services.name", &Vars::default())
+            .unwrap()
+            .run(&value)
+            .unwrap_err();
+        assert!(
+            err.to_string().contains(
+                "filter '.services.name' failed: index 'name' does not support 'sequence' type"
+            ),
+            "{}",
+            err
+        );
+        assert!(
+            JqFilter::parse_with_vars(".services.name?", &Vars::default())
+                .unwrap()
+                .run(&value)
+                .unwrap()
+                .is_empty()
+        );
+        for (source, message) in [
+            (".a |", "unexpected end of filter"),
+            ("shout", "unknown function 'shout' at column 1"),
+            ("select | .", "expected '(' at column 8, found '|'"),
+            (".a ]", "unexpected ']' at column 4"),
+            ("\"abc", "unterminated string at column 1"),
+            (". & 1", "unexpected '&' at column 3"),
+            (".a == $env", "undefined variable '$env' at column 7"),
+        ] {
+            let err = JqFilter::parse_with_vars(source, &Vars::default())
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(message), "{}: {}", source, err);
+        }
+    }
+
+    #[test]
+    fn test_variables() {
+        let value: Value = DOC.parse().unwrap();
+        let vars = Vars::new(
+            &["name".to_string(), "db".to_string()],
+            &["min".to_string(), "100".to_string()],
+        )
+        .unwrap();
+        let filter = JqFilter::parse_with_vars(
+            ".services[] | select(.name == $name or .port < $min) | .port",
+            &vars,
+        )
+        .unwrap();
+        let results: Vec<String> = filter
+            .run(&value)
+            .unwrap()
+            .iter()
+            .map(serialize_raw)
+            .collect();
+        assert_eq!(results, vec!["80", "5432"]);
+    }
+}
@@ -0,0 +1,872 @@
+//! Validation of documents against a JSON Schema.
+//!
+//! Supports the validation keywords of drafts 7 to 2020-12. `$ref` is
+//! limited to pointers within the schema (`#/definitions/...`,
+//! `#/$defs/...`), and `format`, an annotation, isn't checked.
+//!
+//! Violations are reported at the dot-notation path of the value to fix,
+//! including missing and unexpected keys, so that they can be fed to the
+//! other actions.
+
+use super::error::Error;
+use super::path::join_elements;
+use super::serialize::{serialize_inline, serialize_raw};
+use super::InnerValue;
+use fyaml::{Number, Value};
+use regex::Regex;
+use std::cell::RefCell;
+use std::collections::HashMap;
+
+/// Nesting of `$ref` past which a schema is considered to loop.
+const MAX_REF_DEPTH: usize = 256;
+
+/// A value not matching the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// Dot-notation path of the value, empty for the document root
+    pub path: String,
+    pub message: String,
+}
+
+/// A JSON Schema, in JSON or YAML.
+pub struct JsonSchema {
+    root: Value,
+    /// Compiled `pattern` and `patternProperties` regexes
+    regexes: RefCell<HashMap<String, Regex>>,
+}
+
+impl JsonSchema {
+    /// Read a schema from a JSON or YAML file.
+    pub fn from_file(file: &str) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+        let root: Value = content
+            .parse()
+            .map_err(|e| Error::parse_in(&format!("schema '{}'", file), &content, e))?;
+        Self::new(root)
+    }
+
+    pub fn new(root: Value) -> Result<Self, Error> {
+        if !matches!(root.inner(), Value::Mapping(_) | Value::Bool(_)) {
+            return Err(invalid_schema("expected an object or a boolean"));
+        }
+        Ok(JsonSchema {
+            root,
+            regexes: RefCell::new(HashMap::new()),
+        })
+    }
+
+    /// Check `value` against the schema, returning the violations found.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the schema itself is invalid or uses an
+    /// unsupported `$ref`.
+    pub fn validate(&self, value: &Value) -> Result<Vec<Violation>, Error> {
+        let mut violations = Vec::new();
+        self.check(&self.root, value, &mut Vec::new(), 0, &mut violations)?;
+        Ok(violations)
+    }
+
+    fn check(
+        &self,
+        schema: &Value,
+        value: &Value,
+        path: &mut Vec<String>,
+        depth: usize,
+        out: &mut Vec<Violation>,
+    ) -> Result<(), Error> {
+        let value = value.inner();
+        match schema.inner() {
+            Value::Bool(true) => return Ok(()),
+            Value::Bool(false) => {
+                push(out, path, "not allowed by the schema".to_string());
+                return Ok(());
+            }
+            Value::Mapping(_) => {}
+            _ => return Err(invalid_schema("expected an object or a boolean")),
+        }
+        let schema = schema.inner();
+
+        if let Some(reference) = schema.get("$ref") {
+            let reference = reference
+                .as_str()
+                .ok_or_else(|| invalid_schema("'$ref' must be a string"))?;
+            if depth >= MAX_REF_DEPTH {
+                return Err(Error::Base(format!(
+                    "$ref '{}' is nested too deeply, the schema probably loops.",
+                    reference
+                )));
+            }
+            let target = self.resolve(reference)?;
+            self.check(target, value, path, depth + 1, out)?;
+        }
+
+        self.check_type(schema, value, path, out)?;
+        match value {
+            Value::Number(n) => check_number(schema, n, path, out)?,
+            Value::String(s) => self.check_string(schema, s, path, out)?,
+            Value::Sequence(items) => self.check_array(schema, items, path, depth, out)?,
+            Value::Mapping(_) => self.check_object(schema, value, path, depth, out)?,
+            _ => {}
+        }
+        self.check_combinators(schema, value, path, depth, out)
+    }
+
+    /// Find the target of a `#/...` JSON pointer in the schema.
+    fn resolve(&self, reference: &str) -> Result<&Value, Error> {
+        let unsupported = || {
+            Error::Base(format!(
+                "unsupported $ref '{}', only references within the schema ('#/...') are.",
+                reference
+            ))
+        };
+        let pointer = reference.strip_prefix('#').ok_or_else(unsupported)?;
+        if pointer.is_empty() {
+            return Ok(&self.root);
+        }
+        let pointer = pointer.strip_prefix('/').ok_or_else(unsupported)?;
+        let mut target = &self.root;
+        for token in pointer.split('/') {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            target = match target.inner() {
+                Value::Sequence(items) => token.parse::<usize>().ok().and_then(|i| items.get(i)),
+                other => other.get(token.as_str()),
+            }
+            .ok_or_else(|| Error::Base(format!("$ref '{}' not found in the schema.", reference)))?;
+        }
+        Ok(target)
+    }
+
+    fn regex(&self, pattern: &str) -> Result<Regex, Error> {
+        if let Some(regex) = self.regexes.borrow().get(pattern) {
+            return Ok(regex.clone());
+        }
+        let regex = Regex::new(pattern).map_err(|e| {
+            Error::Base(format!(
+                "invalid JSON Schema, bad pattern '{}': {}",
+                pattern, e
+            ))
+        })?;
+        self.regexes
+            .borrow_mut()
+            .insert(pattern.to_string(), regex.clone());
+        Ok(regex)
+    }
+
+    fn check_type(
+        &self,
+        schema: &Value,
+        value: &Value,
+        path: &[String],
+        out: &mut Vec<Violation>,
+    ) -> Result<(), Error> {
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::Sequence(types) => types.iter().map(type_keyword).collect(),
+                other => vec![type_keyword(other)],
+            }
+            .into_iter()
+            .collect::<Option<_>>()
+            .ok_or_else(|| invalid_schema("'type' must be a type name or a list of them"))?;
+            if !types.iter().any(|t| has_type(value, t)) {
+                let expected: Vec<String> = types.iter().map(|t| format!("'{}'", t)).collect();
+                push(
+                    out,
+                    path,
+                    format!(
+                        "expected type {}, got '{}'",
+                        expected.join(" or "),
+                        json_type(value)
+                    ),
+                );
+            }
+        }
+        if let Some(allowed) = schema.get("enum") {
+            let allowed = allowed
+                .as_sequence()
+                .ok_or_else(|| invalid_schema("'enum' must be a list"))?;
+            if !allowed.iter().any(|v| v.inner() == value) {
+                let allowed: Vec<String> = allowed.iter().map(serialize_inline).collect();
+                push(
+                    out,
+                    path,
+                    format!("expected one of [{}]", allowed.join(", ")),
+                );
+            }
+        }
+        if let Some(expected) = schema.get("const") {
+            if expected.inner() != value {
+                push(
+                    out,
+                    path,
+                    format!("expected {}", serialize_inline(expected)),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn check_string(
+        &self,
+        schema: &Value,
+        s: &str,
+        path: &[String],
+        out: &mut Vec<Violation>,
+    ) -> Result<(), Error> {
+        let length = s.chars().count();
+        if let Some(min) = size_keyword(schema, "minLength")? {
+            if length < min {
+                push(
+                    out,
+                    path,
+                    format!("expected at least {} characters, got {}", min, length),
+                );
+            }
+        }
+        if let Some(max) = size_keyword(schema, "maxLength")? {
+            if length > max {
+                push(
+                    out,
+                    path,
+                    format!("expected at most {} characters, got {}", max, length),
+                );
+            }
+        }
+        if let Some(pattern) = schema.get("pattern") {
+            let pattern = pattern
+                .as_str()
+                .ok_or_else(|| invalid_schema("'pattern' must be a string"))?;
+            if !self.regex(pattern)?.is_match(s) {
+                push(out, path, format!("expected to match '{}'", pattern));
+            }
+        }
+        Ok(())
+    }
+
+    fn check_array(
+        &self,
+        schema: &Value,
+        items: &[Value],
+        path: &mut Vec<String>,
+        depth: usize,
+        out: &mut Vec<Violation>,
+    ) -> Result<(), Error> {
+        if let Some(min) = size_keyword(schema, "minItems")? {
+            if items.len() < min {
+                push(
+                    out,
+                    path,
+                    format!("expected at least {} items, got {}", min, items.len()),
+                );
+            }
+        }
+        if let Some(max) = size_keyword(schema, "maxItems")? {
+            if items.len() > max {
+                push(
+                    out,
+                    path,
+                    format!("expected at most {} items, got {}", max, items.len()),
+                );
+            }
+        }
+
+        // Items checked by position: `prefixItems`, or `items` as a list
+        // before 2020-12, the others by `items` or `additionalItems`
+        let (prefix, rest) = match (schema.get("prefixItems"), schema.get("items")) {
+            (Some(prefix), items) => (prefix.as_sequence(), items),
+            (None, Some(Value::Sequence(prefix))) => {
+                (Some(prefix.as_slice()), schema.get("additionalItems"))
+            }
+            (None, items) => (None, items),
+        };
+        let prefix = prefix.unwrap_or_default();
+        for (i, item) in items.iter().enumerate() {
+            let item_schema = match prefix.get(i) {
+                Some(schema) => schema,
+                None => match rest {
+                    Some(schema) => schema,
+                    None => break,
+                },
+            };
+            path.push(i.to_string());
+            self.check(item_schema, item, path, depth, out)?;
+            path.pop();
+        }
+
+        if schema.get("uniqueItems").and_then(Value::as_bool) == Some(true) {
+            for (i, item) in items.iter().enumerate() {
+                if let Some(j) = items[..i]
+                    .iter()
+                    .position(|other| other.inner() == item.inner())
+                {
+                    path.push(i.to_string());
+                    push(out, path, format!("duplicate of item {}", j));
+                    path.pop();
+                }
+            }
+        }
+
+        if let Some(contains) = schema.get("contains") {
+            let mut matches = 0;
+            for item in items {
+                let mut violations = Vec::new();
+                self.check(contains, item, path, depth, &mut violations)?;
+                if violations.is_empty() {
+                    matches += 1;
+                }
+            }
+            let min = size_keyword(schema, "minContains")?.unwrap_or(1);
+            if matches < min {
+                push(
+                    out,
+                    path,
+                    format!(
+                        "expected at least {} items matching the 'contains' schema, got {}",
+                        min, matches
+                    ),
+                );
+            }
+            if let Some(max) = size_keyword(schema, "maxContains")? {
+                if matches > max {
+                    push(
+                        out,
+                        path,
+                        format!(
+                            "expected at most {} items matching the 'contains' schema, got {}",
+                            max, matches
+                        ),
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn check_object(
+        &self,
+        schema: &Value,
+        object: &Value,
+        path: &mut Vec<String>,
+        depth: usize,
+        out: &mut Vec<Violation>,
+    ) -> Result<(), Error> {
+        let Value::Mapping(entries) = object else {
+            return Ok(());
+        };
+        let entries: Vec<(String, &Value)> = entries.iter().map(|(k, v)| (key(k), v)).collect();
+        let has_key = |name: &str| entries.iter().any(|(k, _)| k == name);
+
+        if let Some(min) = size_keyword(schema, "minProperties")? {
+            if entries.len() < min {
+                push(
+                    out,
+                    path,
+                    format!("expected at least {} keys, got {}", min, entries.len()),
+                );
+            }
+        }
+        if let Some(max) = size_keyword(schema, "maxProperties")? {
+            if entries.len() > max {
+                push(
+                    out,
+                    path,
+                    format!("expected at most {} keys, got {}", max, entries.len()),
+                );
+            }
+        }
+        if let Some(required) = schema.get("required") {
+            for name in names(required, "required")? {
+                if !has_key(name) {
+                    path.push(name.to_string());
+                    push(out, path, "required key is missing".to_string());
+                    path.pop();
+                }
+            }
+        }
+        // `dependencies` is the draft 7 form of both keywords
+        for keyword in ["dependentRequired", "dependentSchemas", "dependencies"] {
+            let Some(Value::Mapping(dependencies)) = schema.get(keyword) else {
+                continue;
+            };
+            for (name, dependency) in dependencies {
+                let name = key(name);
+                if !has_key(&name) {
+                    continue;
+                }
+                match dependency {
+                    Value::Sequence(_) => {
+                        for required in names(dependency, keyword)? {
+                            if !has_key(required) {
+                                path.push(required.to_string());
+                                push(out, path, format!("key required by '{}' is missing", name));
+                                path.pop();
+                            }
+                        }
+                    }
+                    _ => self.check(dependency, object, path, depth, out)?,
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_mapping);
+        let patterns: Vec<(Regex, &Value)> = match schema.get("patternProperties") {
+            Some(Value::Mapping(patterns)) => patterns
+                .iter()
+                .map(|(pattern, schema)| Ok((self.regex(&key(pattern))?, schema)))
+                .collect::<Result<_, Error>>()?,
+            _ => Vec::new(),
+        };
+        let additional = schema.get("additionalProperties");
+        let property_names = schema.get("propertyNames");
+
+        for (name, value) in &entries {
+            path.push(name.clone());
+            let mut known = false;
+            if let Some(schema) = properties.and_then(|p| p.get(&Value::String(name.clone()))) {
+                known = true;
+                self.check(schema, value, path, depth, out)?;
+            }
+            for (regex, schema) in &patterns {
+                if regex.is_match(name) {
+                    known = true;
+                    self.check(schema, value, path, depth, out)?;
+                }
+            }
+            match additional {
+                Some(Value::Bool(false)) if !known => {
+                    push(out, path, "unexpected key".to_string());
+                }
+                Some(schema) if !known => self.check(schema, value, path, depth, out)?,
+                _ => {}
+            }
+            if let Some(schema) = property_names {
+                let mut violations = Vec::new();
+                self.check(
+                    schema,
+                    &Value::String(name.clone()),
+                    path,
+                    depth,
+                    &mut violations,
+                )?;
+                for violation in violations {
+                    push(out, path, format!("invalid key, {}", violation.message));
+                }
+            }
+            path.pop();
+        }
+        Ok(())
+    }
+
+    fn check_combinators(
+        &self,
+        schema: &Value,
+        value: &Value,
+        path: &mut Vec<String>,
+        depth: usize,
+        out: &mut Vec<Violation>,
+    ) -> Result<(), Error> {
+        // Whether `value` matches a subschema, without reporting why not
+        let matches = |schema: &Value, path: &mut Vec<String>| -> Result<bool, Error> {
+            let mut violations = Vec::new();
+            self.check(schema, value, path, depth, &mut violations)?;
+            Ok(violations.is_empty())
+        };
+
+        if let Some(all) = schema.get("allOf") {
+            for schema in subschemas(all, "allOf")? {
+                self.check(schema, value, path, depth, out)?;
+            }
+        }
+        if let Some(any) = schema.get("anyOf") {
+            let mut matched = false;
+            for schema in subschemas(any, "anyOf")? {
+                if matches(schema, path)? {
+                    matched = true;
+                    break;
+                }
+            }
+            if !matched {
+                push(
+                    out,
+                    path,
+                    "expected to match at least one of the 'anyOf' schemas".to_string(),
+                );
+            }
+        }
+        if let Some(one) = schema.get("oneOf") {
+            let mut matched = 0;
+            for schema in subschemas(one, "oneOf")? {
+                if matches(schema, path)? {
+                    matched += 1;
+                }
+            }
+            if matched != 1 {
+                push(
+                    out,
+                    path,
+                    format!(
+                        "expected to match exactly one of the 'oneOf' schemas, matched {}",
+                        matched
+                    ),
+                );
+            }
+        }
+        if let Some(not) = schema.get("not") {
+            if matches(not, path)? {
+                push(
+                    out,
+                    path,
+                    "expected not to match the 'not' schema".to_string(),
+                );
+            }
+        }
+        if let Some(condition) = schema.get("if") {
+            let branch = if matches(condition, path)? {
+                schema.get("then")
+            } else {
+                schema.get("else")
+            };
+            if let Some(branch) = branch {
+                self.check(branch, value, path, depth, out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn check_number(
+    schema: &Value,
+    n: &Number,
+    path: &[String],
+    out: &mut Vec<Violation>,
+) -> Result<(), Error> {
+    let x = as_f64(n);
+    let bounds = [
+        ("minimum", ">="),
+        ("exclusiveMinimum", ">"),
+        ("maximum", "<="),
+        ("exclusiveMaximum", "<"),
+    ];
+    for (keyword, operator) in bounds {
+        // Draft 4 boolean forms of the exclusive bounds aren't supported
+        let Some(Value::Number(bound)) = schema.get(keyword) else {
+            continue;
+        };
+        let within = match operator {
+            ">=" => x >= as_f64(bound),
+            ">" => x > as_f64(bound),
+            "<=" => x <= as_f64(bound),
+            _ => x < as_f64(bound),
+        };
+        if !within {
+            push(
+                out,
+                path,
+                format!(
+                    "expected a value {} {}, got {}",
+                    operator,
+                    show_number(bound),
+                    show_number(n)
+                ),
+            );
+        }
+    }
+    if let Some(divisor) = schema.get("multipleOf") {
+        let divisor = match divisor {
+            Value::Number(d) if as_f64(d) > 0.0 => d,
+            _ => return Err(invalid_schema("'multipleOf' must be a positive number")),
+        };
+        let multiple = match (as_i128(n), as_i128(divisor)) {
+            (Some(n), Some(d)) => n % d == 0,
+            _ => {
+                let quotient = x / as_f64(divisor);
+                quotient.is_finite() && (quotient - quotient.round()).abs() < 1e-9
+            }
+        };
+        if !multiple {
+            push(
+                out,
+                path,
+                format!(
+                    "expected a multiple of {}, got {}",
+                    show_number(divisor),
+                    show_number(n)
+                ),
+            );
+        }
+    }
+    Ok(())
+}
+
+fn push(out: &mut Vec<Violation>, path: &[String], message: String) {
+    out.push(Violation {
+        path: join_elements(path),
+        message,
+    });
+}
+
+fn invalid_schema(reason: &str) -> Error {
+    Error::Base(format!("invalid JSON Schema, {}.", reason))
+}
+
+/// Key of a mapping entry as a property name.
+fn key(k: &Value) -> String {
+    match k.inner() {
+        Value::String(s) => s.clone(),
+        other => serialize_raw(other),
+    }
+}
+
+/// A type name of the `type` keyword, `null` being also read unquoted from
+/// YAML schemas.
+fn type_keyword(value: &Value) -> Option<&str> {
+    match value {
+        Value::Null => Some("null"),
+        other => other.as_str(),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value.inner() {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(Number::Float(_)) => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Sequence(_) => "array",
+        Value::Mapping(_) => "object",
+        Value::Tagged(_) => unreachable!("untagged"),
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("number", Value::Number(_)) => true,
+        // Floats with a zero fractional part are integers since draft 6
+        ("integer", Value::Number(Number::Float(f))) => f.is_finite() && f.fract() == 0.0,
+        (name, value) => json_type(value) == name,
+    }
+}
+
+fn as_f64(n: &Number) -> f64 {
+    match n {
+        Number::Int(i) => *i as f64,
+        Number::UInt(u) => *u as f64,
+        Number::Float(f) => *f,
+    }
+}
+
+fn as_i128(n: &Number) -> Option<i128> {
+    match n {
+        Number::Int(i) => Some(*i as i128),
+        Number::UInt(u) => Some(*u as i128),
+        Number::Float(_) => None,
+    }
+}
+
+fn size_keyword(schema: &Value, keyword: &str) -> Result<Option<usize>, Error> {
+    schema
+        .get(keyword)
+        .map(|v| {
+            v.as_u64().map(|n| n as usize).ok_or_else(|| {
+                invalid_schema(&format!("'{}' must be a non-negative integer", keyword))
+            })
+        })
+        .transpose()
+}
+
+fn names<'a>(value: &'a Value, keyword: &str) -> Result<Vec<&'a str>, Error> {
+    value
+        .as_sequence()
+        .and_then(|names| names.iter().map(Value::as_str).collect())
+        .ok_or_else(|| invalid_schema(&format!("'{}' must be a list of key names", keyword)))
+}
+
+fn subschemas<'a>(value: &'a Value, keyword: &str) -> Result<&'a [Value], Error> {
+    value
+        .as_sequence()
+        .ok_or_else(|| invalid_schema(&format!("'{}' must be a list of schemas", keyword)))
+}
+
+fn show_number(n: &Number) -> String {
+    match n {
+        Number::Int(i) => i.to_string(),
+        Number::UInt(u) => u.to_string(),
+        Number::Float(f) => f.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn violations(schema: &str, doc: &str) -> Vec<String> {
+        let schema = JsonSchema::new(schema.parse().unwrap()).unwrap();
+        schema
+            .validate(&doc.parse().unwrap())
+            .unwrap()
+            .iter()
+            .map(|v| format!("{}: {}", v.path, v.message))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_document() {
+        let schema = r#"{"type": "object", "required": ["name"],
+            "properties": {"name": {"type": "string"}, "port": {"type": "integer"}}}"#;
+        assert!(violations(schema, "name: web\nport: 80\n").is_empty());
+    }
+
+    #[test]
+    fn test_types() {
+        let schema =
+            "{properties: {a: {type: integer}, b: {type: [string, 'null']}, c: {type: null}}}";
+        assert_eq!(
+            violations(schema, "a: 1.0\nb: ~\nc: 1\n"),
+            vec!["c: expected type 'null', got 'integer'"]
+        );
+        assert_eq!(
+            violations(schema, "a: 1.5\nb: [1]\n"),
+            vec![
+                "a: expected type 'integer', got 'number'",
+                "b: expected type 'string' or 'null', got 'array'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_object_keywords() {
+        let schema = r#"{
+            "required": ["name", "image"],
+            "properties": {"name": {"minLength": 2}},
+            "patternProperties": {"^x-": true},
+            "additionalProperties": false,
+            "propertyNames": {"pattern": "^[a-z-]+$"}
+        }"#;
+        assert_eq!(
+            violations(schema, "name: w\nx-note: 1\nPort: 80\n"),
+            vec![
+                "image: required key is missing",
+                "name: expected at least 2 characters, got 1",
+                "Port: unexpected key",
+                "Port: invalid key, expected to match '^[a-z-]+$'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_array_keywords() {
+        let schema = "{items: {type: integer, minimum: 0}, uniqueItems: true, maxItems: 3, contains: {const: 1}}";
+        assert_eq!(
+            violations(schema, "[2, -1, 2, 3]"),
+            vec![
+                ": expected at most 3 items, got 4",
+                "1: expected a value >= 0, got -1",
+                "2: duplicate of item 0",
+                ": expected at least 1 items matching the 'contains' schema, got 0",
+            ]
+        );
+        let tuple = "{prefixItems: [{type: string}], items: false}";
+        assert_eq!(
+            violations(tuple, "[a, b]"),
+            vec!["1: not allowed by the schema"]
+        );
+        let draft7 = "{items: [{type: string}], additionalItems: {type: integer}}";
+        assert_eq!(
+            violations(draft7, "[a, b]"),
+            vec!["1: expected type 'integer', got 'string'"]
+        );
+    }
+
+    #[test]
+    fn test_number_keywords() {
+        let schema = "{exclusiveMaximum: 10, multipleOf: 0.5}";
+        assert!(violations(schema, "2.5").is_empty());
+        assert_eq!(
+            violations(schema, "10.25"),
+            vec![
+                ": expected a value < 10, got 10.25",
+                ": expected a multiple of 0.5, got 10.25",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_combinators() {
+        let schema = r#"{
+            "anyOf": [{"type": "string"}, {"type": "integer"}],
+            "oneOf": [{"minimum": 0}, {"maximum": 10}],
+            "not": {"const": 5}
+        }"#;
+        assert_eq!(
+            violations(schema, "5"),
+            vec![
+                ": expected to match exactly one of the 'oneOf' schemas, matched 2",
+                ": expected not to match the 'not' schema",
+            ]
+        );
+        assert!(violations(schema, "-1").is_empty());
+        // Numeric keywords don't apply to other types
+        assert_eq!(
+            violations(schema, "true"),
+            vec![
+                ": expected to match at least one of the 'anyOf' schemas",
+                ": expected to match exactly one of the 'oneOf' schemas, matched 2",
+            ]
+        );
+
+        let conditional = "{if: {properties: {kind: {const: db}}}, then: {required: [port]}, else: {maxProperties: 1}}";
+        assert_eq!(
+            violations(conditional, "kind: db\n"),
+            vec!["port: required key is missing"]
+        );
+        assert_eq!(
+            violations(conditional, "kind: web\nport: 1\n"),
+            vec![": expected at most 1 keys, got 2"]
+        );
+    }
+
+    #[test]
+    fn test_refs() {
+        let schema = r##"{
+            "$defs": {"port": {"type": "integer", "maximum": 65535}},
+            "properties": {
+                "ports": {"items": {"$ref": "#/$defs/port"}},
+                "child": {"$ref": "#"}
+            }
+        }"##;
+        assert_eq!(
+            violations(schema, "ports: [80, 70000]\nchild:\n  ports: [x]\n"),
+            vec![
+                "ports.1: expected a value <= 65535, got 70000",
+                "child.ports.0: expected type 'integer', got 'string'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paths_are_escaped() {
+        assert_eq!(
+            violations("{properties: {a.b: {enum: [1, x]}}}", "a.b: 2\n"),
+            vec![r"a\.b: expected one of [1, 'x']"]
+        );
+    }
+
+    #[test]
+    fn test_invalid_schemas() {
+        for (schema, message) in [
+            ("[1]", "expected an object or a boolean"),
+            ("{$ref: 'other.json#/a'}", "unsupported $ref"),
+            ("{$ref: '#/missing'}", "not found in the schema"),
+            ("{$ref: '#'}", "nested too deeply"),
+            ("{pattern: '('}", "bad pattern"),
+            ("{minLength: -1}", "must be a non-negative integer"),
+        ] {
+            let error = JsonSchema::new(schema.parse().unwrap())
+                .and_then(|s| s.validate(&Value::String("x".to_string())))
+                .unwrap_err();
+            assert!(error.to_string().contains(message), "{}: {}", schema, error);
+        }
+    }
+}
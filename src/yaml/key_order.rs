@@ -0,0 +1,180 @@
+//! Ordering of mapping keys in output documents.
+//!
+//! Keys are kept in input order by default. They can instead be sorted,
+//! or laid out in the order of the `properties` of a JSON Schema, so that
+//! generated documents come out the same whatever order their sources
+//! were written in.
+
+use super::error::Error;
+use super::serialize::serialize_raw;
+use fyaml::{TaggedValue, Value};
+
+/// Order of the keys of every mapping of a document.
+#[derive(Clone, Debug, Default)]
+pub enum KeyOrder {
+    /// Input order, as left by the chain
+    #[default]
+    Preserve,
+    /// Sorted by the raw text of the keys
+    Sorted,
+    /// Keys declared in the `properties` of the schema first, in schema
+    /// order, then the other keys in input order
+    Schema(Value),
+}
+
+impl KeyOrder {
+    /// Key order named `order`, reading the reference schema from `schema`
+    /// for the `schema` order.
+    pub fn new(order: &str, schema: Option<&str>) -> Result<Self, Error> {
+        match (order, schema) {
+            ("schema", Some(file)) => {
+                let content = std::fs::read_to_string(file)
+                    .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+                let root: Value = content
+                    .parse()
+                    .map_err(|e| Error::parse_in(&format!("schema '{}'", file), &content, e))?;
+                Ok(KeyOrder::Schema(root))
+            }
+            ("schema", None) => Err(Error::Base(
+                "key order 'schema' requires a reference schema".to_string(),
+            )),
+            (_, Some(_)) => Err(Error::Base(format!(
+                "a reference schema is only used with key order 'schema', not '{}'",
+                order
+            ))),
+            ("preserve", None) => Ok(KeyOrder::Preserve),
+            ("sorted", None) => Ok(KeyOrder::Sorted),
+            _ => Err(Error::Base(format!("unknown key order '{}'", order))),
+        }
+    }
+
+    pub fn is_preserve(&self) -> bool {
+        matches!(self, KeyOrder::Preserve)
+    }
+
+    /// Reorder the keys of all mappings of `value`.
+    pub fn apply(&self, value: Value) -> Value {
+        match self {
+            KeyOrder::Preserve => value,
+            KeyOrder::Sorted => sort_keys(value),
+            KeyOrder::Schema(schema) => order_by_schema(value, Some(schema)),
+        }
+    }
+}
+
+fn sort_keys(value: Value) -> Value {
+    match value {
+        Value::Mapping(map) => {
+            let mut entries: Vec<_> = map
+                .into_iter()
+                .map(|(k, v)| (serialize_raw(&k), k, sort_keys(v)))
+                .collect();
+            entries.sort_by(|a, b| a.0.cmp(&b.0));
+            Value::Mapping(entries.into_iter().map(|(_, k, v)| (k, v)).collect())
+        }
+        Value::Sequence(seq) => Value::Sequence(seq.into_iter().map(sort_keys).collect()),
+        Value::Tagged(t) => {
+            let TaggedValue { tag, value } = *t;
+            Value::Tagged(Box::new(TaggedValue {
+                tag,
+                value: sort_keys(value),
+            }))
+        }
+        other => other,
+    }
+}
+
+/// Schema keyword `name` of `schema`, if `schema` is an object.
+fn keyword<'a>(schema: Option<&'a Value>, name: &str) -> Option<&'a Value> {
+    match schema {
+        Some(Value::Mapping(map)) => map.get(&Value::String(name.to_string())),
+        _ => None,
+    }
+}
+
+fn order_by_schema(value: Value, schema: Option<&Value>) -> Value {
+    match value {
+        Value::Mapping(mut map) => {
+            let properties = match keyword(schema, "properties") {
+                Some(Value::Mapping(properties)) => Some(properties),
+                _ => None,
+            };
+            let mut result = indexmap::IndexMap::with_capacity(map.len());
+            for (name, property) in properties.into_iter().flatten() {
+                let Value::String(name) = name else {
+                    continue;
+                };
+                let key = Value::String(name.clone());
+                if let Some(v) = map.shift_remove(&key) {
+                    result.insert(key, order_by_schema(v, Some(property)));
+                }
+            }
+            for (k, v) in map {
+                result.insert(k, order_by_schema(v, None));
+            }
+            Value::Mapping(result)
+        }
+        Value::Sequence(seq) => {
+            let items = keyword(schema, "items");
+            Value::Sequence(seq.into_iter().map(|v| order_by_schema(v, items)).collect())
+        }
+        Value::Tagged(t) => {
+            let TaggedValue { tag, value } = *t;
+            Value::Tagged(Box::new(TaggedValue {
+                tag,
+                value: order_by_schema(value, schema),
+            }))
+        }
+        other => other,
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::serialize;
+
+    fn reorder(order: &KeyOrder, yaml: &str) -> String {
+        serialize(&order.apply(yaml.parse().unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_sorted() {
+        assert_eq!(
+            reorder(&KeyOrder::Sorted, "b: 1\na: [{z: 1, y: 2}]\n"),
+            "a:\n- y: 2\n  z: 1\nb: 1"
+        );
+    }
+
+    #[test]
+    fn test_preserve() {
+        assert_eq!(reorder(&KeyOrder::Preserve, "b: 1\na: 2\n"), "b: 1\na: 2");
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema: Value = "properties:\n  name: {}\n  servers:\n    items:\n      properties: {host: {}, port: {}}\n"
+            .parse()
+            .unwrap();
+        let order = KeyOrder::Schema(schema);
+        assert_eq!(
+            reorder(
+                &order,
+                "extra: 1\nservers:\n- port: 80\n  host: a\nname: x\n"
+            ),
+            "name: x\nservers:\n- host: a\n  port: 80\nextra: 1"
+        );
+    }
+
+    #[test]
+    fn test_new_errors() {
+        assert!(KeyOrder::new("schema", None).is_err());
+        assert!(KeyOrder::new("sorted", Some("schema.json")).is_err());
+        assert!(KeyOrder::new("random", None).is_err());
+        assert!(KeyOrder::new("sorted", None).is_ok());
+    }
+}
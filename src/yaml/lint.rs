@@ -0,0 +1,919 @@
+//! Anchor, alias and style linting.
+//!
+//! Reports anchors defined twice in a document, anchors no alias refers
+//! to, and aliases to an anchor not defined before them. A subset of the
+//! yamllint rules (`line-length`, `truthy`, `indentation` and
+//! `trailing-spaces`) can be enabled with a yamllint configuration.
+//!
+//! The parser resolves aliases and plain scalars without keeping where
+//! they were defined, so they are found by scanning the text.
+
+use super::error::Error;
+use super::query::get_at_path;
+use super::Options;
+use fyaml::Value;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// The problems found by `lint()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintKind {
+    /// An anchor defined again, with the line of its previous definition
+    RedefinedAnchor(String, usize),
+    /// An anchor no alias refers to
+    UnusedAnchor(String),
+    /// An alias to an anchor not defined before it in its document
+    UndefinedAlias(String),
+    /// A line longer than allowed, with its length and the maximum
+    LineTooLong(usize, usize),
+    /// A boolean-like plain scalar, with the allowed values
+    Truthy(Vec<String>),
+    /// A line not indented as expected, with the expected and found columns
+    WrongIndentation(usize, usize),
+    /// Spaces at the end of a line
+    TrailingSpaces,
+}
+
+impl LintKind {
+    /// Name of the rule reporting the problem.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            LintKind::RedefinedAnchor(..) => "redefined-anchor",
+            LintKind::UnusedAnchor(_) => "unused-anchor",
+            LintKind::UndefinedAlias(_) => "undefined-alias",
+            LintKind::LineTooLong(..) => "line-length",
+            LintKind::Truthy(_) => "truthy",
+            LintKind::WrongIndentation(..) => "indentation",
+            LintKind::TrailingSpaces => "trailing-spaces",
+        }
+    }
+}
+
+impl fmt::Display for LintKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LintKind::RedefinedAnchor(name, line) => write!(
+                f,
+                "anchor '&{}' redefined, previously defined at line {}",
+                name, line
+            ),
+            LintKind::UnusedAnchor(name) => write!(f, "anchor '&{}' is never used", name),
+            LintKind::UndefinedAlias(name) => {
+                write!(f, "alias '*{}' refers to an undefined anchor", name)
+            }
+            // Style messages are the ones of yamllint, with the rule name
+            LintKind::LineTooLong(length, max) => write!(
+                f,
+                "line too long ({} > {} characters) (line-length)",
+                length, max
+            ),
+            LintKind::Truthy(allowed) => {
+                let mut allowed = allowed.clone();
+                allowed.sort();
+                write!(
+                    f,
+                    "truthy value should be one of [{}] (truthy)",
+                    allowed.join(", ")
+                )
+            }
+            LintKind::WrongIndentation(expected, found) => write!(
+                f,
+                "wrong indentation: expected {} but found {} (indentation)",
+                expected, found
+            ),
+            LintKind::TrailingSpaces => write!(f, "trailing spaces (trailing-spaces)"),
+        }
+    }
+}
+
+/// A problem found at a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lint {
+    pub line: usize,
+    pub column: usize,
+    pub kind: LintKind,
+}
+
+impl Lint {
+    fn new(line: usize, column: usize, kind: LintKind) -> Self {
+        Lint { line, column, kind }
+    }
+}
+
+// =============================================================================
+// Style Rules
+// =============================================================================
+
+/// Values the `truthy` rule looks for, as in yamllint.
+const TRUTHY: &[&str] = &[
+    "YES", "Yes", "yes", "NO", "No", "no", "TRUE", "True", "true", "FALSE", "False", "false", "ON",
+    "On", "on", "OFF", "Off", "off",
+];
+
+#[derive(Debug, Clone)]
+struct LineLength {
+    max: usize,
+    allow_non_breakable_words: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Truthy {
+    allowed_values: Vec<String>,
+    check_keys: bool,
+}
+
+/// The `indent-sequences` option of the `indentation` rule.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IndentSequences {
+    Indented,
+    NotIndented,
+    Whatever,
+}
+
+#[derive(Debug, Clone)]
+struct Indentation {
+    /// Spaces per level, `None` to use the first indentation found
+    spaces: Option<usize>,
+    indent_sequences: IndentSequences,
+}
+
+/// Style rules to check, each `None` when disabled.
+///
+/// Options default to the yamllint ones.
+#[derive(Debug, Clone, Default)]
+pub struct StyleRules {
+    line_length: Option<LineLength>,
+    truthy: Option<Truthy>,
+    indentation: Option<Indentation>,
+    trailing_spaces: bool,
+}
+
+impl StyleRules {
+    /// Read the rules enabled in a yamllint configuration file.
+    pub fn from_file(file: &str) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+        Self::from_config(&content)
+    }
+
+    /// Read the rules enabled in a yamllint configuration.
+    ///
+    /// `extends: default` enables the four supported rules, which `rules`
+    /// can then disable (`disable`) or configure. Other rules are ignored.
+    pub fn from_config(text: &str) -> Result<Self, Error> {
+        let config: Value = if text.trim().is_empty() {
+            Value::Null
+        } else {
+            text.parse()?
+        };
+        let mut rules = StyleRules::default();
+        match get_at_path(&config, Some("extends"), &Options::default()) {
+            Ok(Value::String(preset)) if preset == "default" => {
+                rules.line_length = Some(line_length(&Value::Null)?);
+                rules.truthy = Some(truthy(&Value::Null)?);
+                rules.indentation = Some(indentation(&Value::Null)?);
+                rules.trailing_spaces = true;
+            }
+            Ok(other) => {
+                return Err(Error::Base(format!(
+                    "unsupported configuration 'extends: {}', only 'default' is.",
+                    crate::yaml::serialize_raw(other)
+                )))
+            }
+            Err(_) => {}
+        }
+
+        let Ok(Value::Mapping(configured)) =
+            get_at_path(&config, Some("rules"), &Options::default())
+        else {
+            return Ok(rules);
+        };
+        for (name, options) in configured {
+            let enabled = match options {
+                Value::String(s) if s == "disable" => false,
+                Value::String(s) if s == "enable" => true,
+                Value::Null | Value::Mapping(_) => true,
+                _ => {
+                    return Err(Error::Base(format!(
+                        "invalid configuration of rule '{}', expected enable, disable or options.",
+                        crate::yaml::serialize_raw(name)
+                    )))
+                }
+            };
+            let options = match options {
+                Value::Mapping(_) => options,
+                _ => &Value::Null,
+            };
+            match name.as_str() {
+                Some("line-length") => {
+                    rules.line_length = enabled.then(|| line_length(options)).transpose()?
+                }
+                Some("truthy") => rules.truthy = enabled.then(|| truthy(options)).transpose()?,
+                Some("indentation") => {
+                    rules.indentation = enabled.then(|| indentation(options)).transpose()?
+                }
+                Some("trailing-spaces") => rules.trailing_spaces = enabled,
+                _ => {}
+            }
+        }
+        Ok(rules)
+    }
+}
+
+fn invalid_option(rule: &str, option: &str) -> Error {
+    Error::Base(format!(
+        "invalid value for option '{}' of rule '{}'.",
+        option, rule
+    ))
+}
+
+fn option<'a>(options: &'a Value, name: &str) -> Option<&'a Value> {
+    get_at_path(options, Some(name), &Options::default()).ok()
+}
+
+fn line_length(options: &Value) -> Result<LineLength, Error> {
+    let invalid = |name| invalid_option("line-length", name);
+    Ok(LineLength {
+        max: match option(options, "max") {
+            None => 80,
+            Some(v) => v
+                .as_u64()
+                .map(|max| max as usize)
+                .ok_or_else(|| invalid("max"))?,
+        },
+        allow_non_breakable_words: match option(options, "allow-non-breakable-words") {
+            None => true,
+            Some(v) => v
+                .as_bool()
+                .ok_or_else(|| invalid("allow-non-breakable-words"))?,
+        },
+    })
+}
+
+fn truthy(options: &Value) -> Result<Truthy, Error> {
+    let invalid = |name| invalid_option("truthy", name);
+    Ok(Truthy {
+        allowed_values: match option(options, "allowed-values") {
+            None => vec!["true".to_string(), "false".to_string()],
+            Some(Value::Sequence(values)) => values
+                .iter()
+                .map(|v| v.as_str().map(str::to_string))
+                .collect::<Option<_>>()
+                .ok_or_else(|| invalid("allowed-values"))?,
+            Some(_) => return Err(invalid("allowed-values")),
+        },
+        check_keys: match option(options, "check-keys") {
+            None => true,
+            Some(v) => v.as_bool().ok_or_else(|| invalid("check-keys"))?,
+        },
+    })
+}
+
+fn indentation(options: &Value) -> Result<Indentation, Error> {
+    let invalid = |name| invalid_option("indentation", name);
+    Ok(Indentation {
+        spaces: match option(options, "spaces") {
+            None => None,
+            Some(Value::String(s)) if s == "consistent" => None,
+            Some(v) => Some(
+                v.as_u64()
+                    .filter(|spaces| *spaces > 0)
+                    .ok_or_else(|| invalid("spaces"))? as usize,
+            ),
+        },
+        // `consistent` isn't tracked and accepts both styles
+        indent_sequences: match option(options, "indent-sequences") {
+            None => IndentSequences::Indented,
+            Some(Value::Bool(true)) => IndentSequences::Indented,
+            Some(Value::Bool(false)) => IndentSequences::NotIndented,
+            Some(Value::String(s)) if s == "whatever" || s == "consistent" => {
+                IndentSequences::Whatever
+            }
+            Some(_) => return Err(invalid("indent-sequences")),
+        },
+    })
+}
+
+// =============================================================================
+// Linting
+// =============================================================================
+
+/// Check the anchors and aliases of every document of a YAML stream, and
+/// its style following `rules`.
+///
+/// Lints are sorted by location.
+pub fn lint(text: &str, rules: &StyleRules) -> Vec<Lint> {
+    let scan = scan(text);
+    let mut lints = Vec::new();
+    for marks in &scan.docs {
+        lint_anchors(marks, &mut lints);
+    }
+    if let Some(truthy) = &rules.truthy {
+        lint_truthy(&scan, truthy, &mut lints);
+    }
+    if let Some(indentation) = &rules.indentation {
+        lint_indentation(text, &scan, indentation, &mut lints);
+    }
+    for (n, line) in text.lines().enumerate() {
+        if let Some(line_length) = &rules.line_length {
+            lint_line_length(n + 1, line, line_length, &mut lints);
+        }
+        let trimmed = line.trim_end_matches([' ', '\t']);
+        if rules.trailing_spaces && trimmed.len() < line.len() {
+            let column = trimmed.chars().count() + 1;
+            lints.push(Lint::new(n + 1, column, LintKind::TrailingSpaces));
+        }
+    }
+    lints.sort_by_key(|lint| (lint.line, lint.column));
+    lints
+}
+
+fn lint_anchors(marks: &[Mark], lints: &mut Vec<Lint>) {
+    // Anchor name to its last definition, and whether an alias used it
+    let mut defined: HashMap<&str, (&Mark, bool)> = HashMap::new();
+    let unused = |mark: &Mark, name: &str| {
+        Lint::new(
+            mark.line,
+            mark.column,
+            LintKind::UnusedAnchor(name.to_string()),
+        )
+    };
+    for mark in marks {
+        match mark.token {
+            Token::Anchor(name) => {
+                if let Some((previous, used)) = defined.insert(name, (mark, false)) {
+                    if !used {
+                        lints.push(unused(previous, name));
+                    }
+                    lints.push(Lint::new(
+                        mark.line,
+                        mark.column,
+                        LintKind::RedefinedAnchor(name.to_string(), previous.line),
+                    ));
+                }
+            }
+            Token::Alias(name) => match defined.get_mut(name) {
+                Some((_, used)) => *used = true,
+                None => lints.push(Lint::new(
+                    mark.line,
+                    mark.column,
+                    LintKind::UndefinedAlias(name.to_string()),
+                )),
+            },
+            Token::Plain { .. } => {}
+        }
+    }
+    lints.extend(
+        defined
+            .iter()
+            .filter(|(_, (_, used))| !used)
+            .map(|(name, (mark, _))| unused(mark, name)),
+    );
+}
+
+/// Replace the aliases to undefined anchors by `null`, so that the
+/// documents holding them can be parsed.
+pub fn null_undefined_aliases(text: &str) -> String {
+    let mut lints = Vec::new();
+    for marks in &scan(text).docs {
+        lint_anchors(marks, &mut lints);
+    }
+    let mut lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+    // From the end of each line, keeping the columns of the others valid
+    lints.sort_by_key(|lint| std::cmp::Reverse((lint.line, lint.column)));
+    for lint in lints {
+        let LintKind::UndefinedAlias(name) = &lint.kind else {
+            continue;
+        };
+        let line = &mut lines[lint.line - 1];
+        let start: usize = line.chars().take(lint.column - 1).map(char::len_utf8).sum();
+        line.replace_range(start..start + 1 + name.len(), "null");
+    }
+    lines.join("\n")
+}
+
+/// Names of the anchors defined in the text, with their 1-based line and
+/// column, in document order.
+pub(super) fn anchor_marks(text: &str) -> Vec<(&str, usize, usize)> {
+    scan(text)
+        .docs
+        .iter()
+        .flatten()
+        .filter_map(|mark| match mark.token {
+            Token::Anchor(name) => Some((name, mark.line, mark.column)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Names of the anchors the aliases of the text refer to, with the 1-based
+/// line and column of each alias, in document order.
+pub(super) fn alias_marks(text: &str) -> Vec<(&str, usize, usize)> {
+    scan(text)
+        .docs
+        .iter()
+        .flatten()
+        .filter_map(|mark| match mark.token {
+            Token::Alias(name) => Some((name, mark.line, mark.column)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Line ranges of the documents of a stream, starting with their `---`
+/// marker if they have one.
+///
+/// A document starts at a `---` marker or at its first line that isn't
+/// blank, a comment or a directive, and ends before the next marker.
+pub(super) fn document_lines(text: &str) -> Vec<Range<usize>> {
+    let structural = scan(text).structural;
+    let mut docs = Vec::new();
+    let mut start = None;
+    for (n, line) in text.lines().enumerate() {
+        let content = line.trim_start();
+        if structural[n] && is_document_marker(line) {
+            if let Some(start) = start.take() {
+                docs.push(start..n);
+            }
+            if line.starts_with("---") {
+                start = Some(n);
+            }
+        } else if start.is_none()
+            && structural[n]
+            && !content.is_empty()
+            && !content.starts_with('#')
+        {
+            start = Some(n);
+        }
+    }
+    if let Some(start) = start {
+        docs.push(start..structural.len());
+    }
+    docs
+}
+
+fn lint_truthy(scan: &Scan, rules: &Truthy, lints: &mut Vec<Lint>) {
+    for mark in scan.docs.iter().flatten() {
+        let Token::Plain { text, key } = mark.token else {
+            continue;
+        };
+        if (key && !rules.check_keys)
+            || !TRUTHY.contains(&text)
+            || rules.allowed_values.iter().any(|v| v == text)
+        {
+            continue;
+        }
+        lints.push(Lint::new(
+            mark.line,
+            mark.column,
+            LintKind::Truthy(rules.allowed_values.clone()),
+        ));
+    }
+}
+
+fn lint_line_length(number: usize, line: &str, rules: &LineLength, lints: &mut Vec<Lint>) {
+    let length = line.chars().count();
+    if length <= rules.max {
+        return;
+    }
+    if rules.allow_non_breakable_words {
+        // A single word (an URL...) after the indentation and indicators
+        let word = line
+            .trim_start()
+            .trim_start_matches(['-', '#', ' '])
+            .trim_end();
+        if !word.contains(' ') {
+            return;
+        }
+    }
+    lints.push(Lint::new(
+        number,
+        rules.max + 1,
+        LintKind::LineTooLong(length, rules.max),
+    ));
+}
+
+/// Check that each nesting level of block collections is indented by the
+/// same number of spaces.
+fn lint_indentation(text: &str, scan: &Scan, rules: &Indentation, lints: &mut Vec<Lint>) {
+    let mut spaces = rules.spaces;
+    // Columns of the enclosing entries and sequence item contents
+    let mut levels: Vec<usize> = Vec::new();
+    // Column of the key ending the previous line, whose value is nested
+    let mut open_key: Option<usize> = None;
+
+    for (n, line) in text.lines().enumerate() {
+        let content = line.trim_start_matches(' ');
+        if !scan.structural[n] || content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+        if is_document_marker(line) {
+            levels.clear();
+            open_key = None;
+            continue;
+        }
+        let indent = line.len() - content.len();
+        let item = content == "-" || content.starts_with("- ");
+
+        let mut dedented = false;
+        while levels.last().is_some_and(|level| *level > indent) {
+            levels.pop();
+            dedented = true;
+        }
+        let expected = match levels.last().copied() {
+            None if indent > 0 => Some(0),
+            None => None,
+            Some(parent) if parent == indent => {
+                let unindented_sequence = item && open_key == Some(indent);
+                (unindented_sequence && rules.indent_sequences == IndentSequences::Indented)
+                    .then(|| indent + spaces.unwrap_or(2))
+            }
+            // Back to a column none of the enclosing levels uses
+            Some(parent) if dedented => Some(parent),
+            Some(parent) => {
+                if item
+                    && open_key == Some(parent)
+                    && rules.indent_sequences == IndentSequences::NotIndented
+                {
+                    Some(parent)
+                } else {
+                    let step = *spaces.get_or_insert(indent - parent);
+                    (indent != parent + step).then_some(parent + step)
+                }
+            }
+        };
+        if let Some(expected) = expected {
+            lints.push(Lint::new(
+                n + 1,
+                indent + 1,
+                LintKind::WrongIndentation(expected, indent),
+            ));
+        }
+        if levels.last() != Some(&indent) {
+            levels.push(indent);
+        }
+
+        let mut key_column = indent;
+        if item {
+            let rest = content[1..].trim_start_matches(' ');
+            if !rest.is_empty() && !rest.starts_with('#') {
+                key_column = line.len() - rest.len();
+                levels.push(key_column);
+            }
+        }
+        let code = content.split(" #").next().unwrap_or_default().trim_end();
+        open_key = code.ends_with(':').then_some(key_column);
+    }
+}
+
+// =============================================================================
+// Scanner
+// =============================================================================
+
+/// A token found in the text.
+#[derive(Clone, Copy)]
+enum Token<'a> {
+    /// `&name`
+    Anchor(&'a str),
+    /// `*name`
+    Alias(&'a str),
+    /// A plain scalar, and whether it is a mapping key
+    Plain { text: &'a str, key: bool },
+}
+
+/// A token at a 1-based line and column.
+struct Mark<'a> {
+    token: Token<'a>,
+    line: usize,
+    column: usize,
+}
+
+struct Scan<'a> {
+    /// Anchors, aliases and plain scalars of each document
+    docs: Vec<Vec<Mark<'a>>>,
+    /// Whether each line starts in block context, not inside a block
+    /// scalar, a quoted scalar or a flow collection
+    structural: Vec<bool>,
+}
+
+fn is_blank(c: Option<&char>) -> bool {
+    c.is_none_or(|c| c.is_whitespace())
+}
+
+fn is_flow_indicator(c: char) -> bool {
+    matches!(c, ',' | '[' | ']' | '{' | '}')
+}
+
+fn is_document_marker(line: &str) -> bool {
+    (line.starts_with("---") || line.starts_with("...")) && is_blank(line.chars().nth(3).as_ref())
+}
+
+/// Find the anchors, aliases and plain scalars of each document.
+///
+/// These can only start where a node can: at the start of a line, after
+/// an indicator (`- `, `? `, `: `, `[`, `{` or `,`) or after a property.
+/// Comments, quoted scalars and block scalars are skipped.
+fn scan(text: &str) -> Scan<'_> {
+    let mut docs = vec![Vec::new()];
+    let mut structural = Vec::new();
+    let mut flow_depth = 0usize;
+    // Quote of a quoted scalar spanning several lines
+    let mut quote: Option<char> = None;
+    // Indentation of the line holding a block scalar, whose lines are skipped
+    let mut block_scalar = None;
+
+    for (n, line) in text.lines().enumerate() {
+        let content = line.trim_start_matches(' ');
+        let indent = line.len() - content.len();
+        if let Some(parent) = block_scalar {
+            if content.trim().is_empty() || indent > parent {
+                structural.push(false);
+                continue;
+            }
+            block_scalar = None;
+        }
+        structural.push(quote.is_none() && flow_depth == 0 && !line.starts_with('%'));
+        if !structural[n] && line.starts_with('%') {
+            continue;
+        }
+
+        let mut i = 0;
+        if structural[n] && is_document_marker(line) {
+            if !docs.last().is_some_and(Vec::is_empty) {
+                docs.push(Vec::new());
+            }
+            i = 3;
+        }
+
+        let chars: Vec<char> = line.chars().collect();
+        let offset = |k: usize| chars[..k].iter().map(|c| c.len_utf8()).sum::<usize>();
+        let mut push = |token, column| {
+            docs.last_mut().unwrap().push(Mark {
+                token,
+                line: n + 1,
+                column,
+            })
+        };
+
+        let mut node_start = quote.is_none();
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1);
+            if let Some(q) = quote {
+                match c {
+                    '\'' if q == '\'' && next == Some(&'\'') => i += 1,
+                    '\\' if q == '"' => i += 1,
+                    _ if c == q => quote = None,
+                    _ => {}
+                }
+                i += 1;
+                continue;
+            }
+            match c {
+                ' ' | '\t' => {}
+                '#' if i == 0 || chars[i - 1].is_whitespace() => break,
+                '&' | '*' if node_start => {
+                    let start = i + 1;
+                    let mut end = start;
+                    while end < chars.len()
+                        && !chars[end].is_whitespace()
+                        && !(flow_depth > 0 && is_flow_indicator(chars[end]))
+                    {
+                        end += 1;
+                    }
+                    let name = &line[offset(start)..offset(end)];
+                    let token = if c == '&' {
+                        Token::Anchor(name)
+                    } else {
+                        Token::Alias(name)
+                    };
+                    push(token, i + 1);
+                    node_start = c == '&';
+                    i = end;
+                    continue;
+                }
+                '!' if node_start => {
+                    while i < chars.len() && !chars[i].is_whitespace() {
+                        i += 1;
+                    }
+                    continue;
+                }
+                '\'' | '"' if node_start => {
+                    quote = Some(c);
+                    node_start = false;
+                }
+                '|' | '>' if node_start && flow_depth == 0 => {
+                    block_scalar = Some(indent);
+                    break;
+                }
+                '[' | '{' if node_start => flow_depth += 1,
+                ']' | '}' if flow_depth > 0 => {
+                    flow_depth -= 1;
+                    node_start = false;
+                }
+                ',' if flow_depth > 0 => node_start = true,
+                '-' | '?' if node_start && is_blank(next) => {}
+                ':' if is_blank(next)
+                    || (flow_depth > 0 && next.is_some_and(|c| is_flow_indicator(*c))) =>
+                {
+                    node_start = true
+                }
+                _ if node_start => {
+                    let mut end = i;
+                    while end < chars.len() {
+                        let (c, next) = (chars[end], chars.get(end + 1));
+                        let ends = match c {
+                            '#' => chars[end - 1].is_whitespace(),
+                            ':' => {
+                                is_blank(next)
+                                    || (flow_depth > 0
+                                        && next.is_some_and(|c| is_flow_indicator(*c)))
+                            }
+                            _ => flow_depth > 0 && is_flow_indicator(c),
+                        };
+                        if ends && end > i {
+                            break;
+                        }
+                        end += 1;
+                    }
+                    let text = line[offset(i)..offset(end)].trim_end();
+                    let key = chars.get(end) == Some(&':');
+                    push(Token::Plain { text, key }, i + 1);
+                    node_start = false;
+                    i = end;
+                    continue;
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+    }
+    Scan { docs, structural }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lints_with(text: &str, config: &str) -> Vec<String> {
+        let rules = StyleRules::from_config(config).unwrap();
+        lint(text, &rules)
+            .iter()
+            .map(|l| format!("{}:{}: {}", l.line, l.column, l.kind))
+            .collect()
+    }
+
+    fn lints(text: &str) -> Vec<String> {
+        lints_with(text, "")
+    }
+
+    #[test]
+    fn test_used_anchors() {
+        let text = "base: &base {a: 1}\nlist: [&x 1, *x]\nmerged:\n  <<: *base\n&k key: *k\n";
+        assert!(lints(text).is_empty(), "{:?}", lints(text));
+    }
+
+    #[test]
+    fn test_redefined_and_unused_anchors() {
+        assert_eq!(
+            lints("a: &x 1\nb: &x 2\nc: *x\nd: &y 3\n"),
+            vec![
+                "1:4: anchor '&x' is never used",
+                "2:4: anchor '&x' redefined, previously defined at line 1",
+                "4:4: anchor '&y' is never used",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_undefined_alias() {
+        assert_eq!(
+            lints("a: *x\nb: &x 1\nc: *x\n"),
+            vec!["1:4: alias '*x' refers to an undefined anchor"]
+        );
+    }
+
+    #[test]
+    fn test_null_undefined_aliases() {
+        assert_eq!(
+            null_undefined_aliases("a: &x 1\nb: [*x, *y]\n---\nc: *x\n"),
+            "a: &x 1\nb: [*x, null]\n---\nc: null\n"
+        );
+    }
+
+    #[test]
+    fn test_anchors_are_per_document() {
+        assert_eq!(
+            lints("a: &x 1\nb: *x\n---\nc: *x\n"),
+            vec!["4:4: alias '*x' refers to an undefined anchor"]
+        );
+    }
+
+    #[test]
+    fn test_skips_scalars_and_comments() {
+        let text = concat!(
+            "a: Tom &Jerry  # &b *c\n",
+            "b: '&x *y'\n",
+            "c: \"*z\n  &w\"\n",
+            "d: |\n  &v *u\n",
+            "e: 3 * 4\n",
+        );
+        assert!(lints(text).is_empty(), "{:?}", lints(text));
+    }
+
+    #[test]
+    fn test_style_rules_disabled_by_default() {
+        assert!(lints("on: yes  \nlist:\n   - 1\n").is_empty());
+    }
+
+    #[test]
+    fn test_truthy() {
+        let text = "on: yes\nb: 'no'\nc: [Off, true]\nd: |\n  yes\ne: yes please\n";
+        assert_eq!(
+            lints_with(text, "rules: {truthy: enable}"),
+            vec![
+                "1:1: truthy value should be one of [false, true] (truthy)",
+                "1:5: truthy value should be one of [false, true] (truthy)",
+                "3:5: truthy value should be one of [false, true] (truthy)",
+            ]
+        );
+        let config =
+            "rules:\n  truthy:\n    allowed-values: ['yes', 'no']\n    check-keys: false\n";
+        assert_eq!(
+            lints_with(text, config),
+            vec![
+                "3:5: truthy value should be one of [no, yes] (truthy)",
+                "3:10: truthy value should be one of [no, yes] (truthy)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_line_length_and_trailing_spaces() {
+        let config = "rules: {line-length: {max: 10}, trailing-spaces: enable}";
+        let text = "a: 1234567 \nb: https://example.com/x\n- https://example.com/x\nc: 1 2 3 4 5\n";
+        assert_eq!(
+            lints_with(text, config),
+            vec![
+                "1:11: line too long (11 > 10 characters) (line-length)",
+                "1:11: trailing spaces (trailing-spaces)",
+                "2:11: line too long (24 > 10 characters) (line-length)",
+                "4:11: line too long (12 > 10 characters) (line-length)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_indentation() {
+        let config = "rules: {indentation: {spaces: 2}}";
+        let text = concat!(
+            "a:\n",
+            "    b: 1\n",
+            "c:\n",
+            "  - d: 1\n",
+            "    e:\n",
+            "      f: 1\n",
+            "   g: 1\n",
+            "h:\n",
+            "- 1\n",
+            "- 2\n",
+            "i: |\n",
+            "     text\n",
+            "j: [1,\n",
+            "       2]\n",
+        );
+        assert_eq!(
+            lints_with(text, config),
+            vec![
+                "2:5: wrong indentation: expected 2 but found 4 (indentation)",
+                "7:4: wrong indentation: expected 2 but found 3 (indentation)",
+                "9:1: wrong indentation: expected 2 but found 0 (indentation)",
+            ]
+        );
+
+        let config = "rules: {indentation: {spaces: consistent, indent-sequences: false}}";
+        assert_eq!(
+            lints_with("a:\n    b:\n    - 1\nc:\n    - 2\n", config),
+            vec!["5:5: wrong indentation: expected 0 but found 4 (indentation)"]
+        );
+    }
+
+    #[test]
+    fn test_extends_default() {
+        let text = "a: yes\n";
+        assert_eq!(lints_with(text, "extends: default").len(), 1);
+        assert!(lints_with(text, "extends: default\nrules: {truthy: disable}").is_empty());
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for config in [
+            "extends: relaxed",
+            "rules: {line-length: {max: long}}",
+            "rules: {truthy: 3}",
+        ] {
+            assert!(StyleRules::from_config(config).is_err(), "{}", config);
+        }
+    }
+}
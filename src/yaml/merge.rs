@@ -3,10 +3,16 @@
 //! Provides merge policies, inline merge directives, and overlay application.
 
 use super::error::Error;
-use super::InnerValue;
-use crate::tag::{parse_tag, MergeOp};
+use super::style::key_text;
+use super::{InnerValue, Options};
+use crate::tag::{check_tag_strict, parse_tag, MergeOp};
 use fyaml::{TaggedValue, Value};
 use std::collections::HashMap;
+use std::process::Command;
+use std::sync::{LazyLock, Mutex};
+
+/// Seconds after which fetching an overlay URL is given up by default
+pub const URL_TIMEOUT: u64 = 30;
 
 // =============================================================================
 // Merge Policy
@@ -21,6 +27,9 @@ pub enum MergePolicy {
     Replace,
     /// Prepend overlay sequence to base sequence
     Prepend,
+    /// Append overlay sequence to base sequence, keeping duplicates where
+    /// they are (deep merge for mappings)
+    AppendAll,
 }
 
 impl std::str::FromStr for MergePolicy {
@@ -31,8 +40,9 @@ impl std::str::FromStr for MergePolicy {
             "merge" => Ok(MergePolicy::Merge),
             "replace" => Ok(MergePolicy::Replace),
             "prepend" => Ok(MergePolicy::Prepend),
+            "append-all" => Ok(MergePolicy::AppendAll),
             _ => Err(format!(
-                "Invalid merge policy '{}': expected merge, replace, or prepend",
+                "Invalid merge policy '{}': expected merge, replace, prepend, or append-all",
                 s
             )),
         }
@@ -40,7 +50,8 @@ impl std::str::FromStr for MergePolicy {
 }
 
 /// Parse merge policy specifications from CLI arguments
-/// Format: "path=policy" where policy is merge|replace|prepend
+/// Format: "path=policy" where policy is merge|replace|prepend|append-all, and path
+/// can be a pattern (see `policy_for`)
 pub fn parse_merge_policies(
     args: Option<&Vec<String>>,
 ) -> Result<HashMap<String, MergePolicy>, String> {
@@ -64,6 +75,58 @@ pub fn parse_merge_policies(
     Ok(policies)
 }
 
+/// Whether a key matches a pattern key, where `*` matches any characters.
+fn key_matches(pattern: &str, key: &str) -> bool {
+    match pattern.split_once('*') {
+        None => pattern == key,
+        Some((prefix, rest)) => key.strip_prefix(prefix).is_some_and(|key| {
+            (0..=key.len())
+                .filter(|i| key.is_char_boundary(*i))
+                .any(|i| key_matches(rest, &key[i..]))
+        }),
+    }
+}
+
+/// Whether the keys of a merge path match the keys of a pattern, where a
+/// `**` key matches any number of keys, at least one at the end of the
+/// pattern.
+fn path_matches(pattern: &[&str], keys: &[&str]) -> bool {
+    match (pattern.split_first(), keys.split_first()) {
+        (None, None) => true,
+        (Some((&"**", [])), _) => !keys.is_empty(),
+        (Some((&"**", rest)), _) => (0..=keys.len()).any(|i| path_matches(rest, &keys[i..])),
+        (Some((pattern_key, rest)), Some((key, keys))) => {
+            key_matches(pattern_key, key) && path_matches(rest, keys)
+        }
+        _ => false,
+    }
+}
+
+/// Policy given for a merge path. Paths containing `*` are patterns: an
+/// exact path wins over them, and among matching patterns the one with the
+/// most plain keys, then the fewest `**`.
+fn policy_for(policies: &HashMap<String, MergePolicy>, path: &str) -> Option<MergePolicy> {
+    if let Some(policy) = policies.get(path) {
+        return Some(*policy);
+    }
+    let keys: Vec<&str> = match path {
+        "" => Vec::new(),
+        _ => path.split('.').collect(),
+    };
+    policies
+        .iter()
+        .filter(|(pattern, _)| pattern.contains('*'))
+        .filter(|(pattern, _)| path_matches(&pattern.split('.').collect::<Vec<_>>(), &keys))
+        .max_by_key(|(pattern, _)| {
+            (
+                pattern.split('.').filter(|key| !key.contains('*')).count(),
+                std::cmp::Reverse(pattern.matches("**").count()),
+                std::cmp::Reverse(pattern.as_str()),
+            )
+        })
+        .map(|(_, policy)| *policy)
+}
+
 // =============================================================================
 // Merge Directive Extraction
 // =============================================================================
@@ -85,6 +148,53 @@ fn extract_merge_directive(value: Value) -> Result<(Option<MergeOp>, Value), Err
     }
 }
 
+/// Check every tag of an overlay with [`check_tag_strict`], including the
+/// ones of subtrees that are added or replaced without being merged.
+fn check_tags(value: &Value, path: &str, overlay_path: &str) -> Result<(), Error> {
+    let location = || {
+        if path.is_empty() {
+            "at root".to_string()
+        } else {
+            format!("at '{}'", path)
+        }
+    };
+    let child_path = |key: String| {
+        if path.is_empty() {
+            key
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+    if let Value::Tagged(tagged) = value {
+        check_tag_strict(&tagged.tag).map_err(|e| {
+            Error::Base(format!(
+                "Invalid tag in '{}' {}: {}",
+                overlay_path,
+                location(),
+                e
+            ))
+        })?;
+    }
+    match value.inner() {
+        Value::Mapping(map) => {
+            for (key, child) in map {
+                let key_str = match key {
+                    Value::String(s) => s.clone(),
+                    _ => key_text(key)?,
+                };
+                check_tags(child, &child_path(key_str), overlay_path)?;
+            }
+        }
+        Value::Sequence(seq) => {
+            for (index, child) in seq.iter().enumerate() {
+                check_tags(child, &child_path(index.to_string()), overlay_path)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
 fn validate_merge_op_for_type(op: &MergeOp, value: &Value, path: &str) -> Result<(), Error> {
     if !matches!(op, MergeOp::Append | MergeOp::Prepend) {
         return Ok(());
@@ -100,7 +210,7 @@ fn validate_merge_op_for_type(op: &MergeOp, value: &Value, path: &str) -> Result
         format!("at '{}'", path)
     };
 
-    Err(Error::Type(format!(
+    Err(Error::type_error(format!(
         "Invalid merge directive {}: !merge:{} can only be used on sequences, got {}",
         location,
         op,
@@ -129,13 +239,14 @@ fn merge_values(
     overlay: Value,
     path: &str,
     policies: &HashMap<String, MergePolicy>,
+    opts: &Options,
 ) -> Result<Value, Error> {
     let (inline_op, stripped_overlay) = extract_merge_directive(overlay)?;
 
-    let cli_policy = policies.get(path);
+    let cli_policy = policy_for(policies, path);
 
     if let Some(policy) = cli_policy {
-        return apply_policy(*policy, base, stripped_overlay, path, policies);
+        return apply_policy(policy, base, stripped_overlay, path, policies, opts);
     }
 
     if let Some(op) = inline_op {
@@ -146,10 +257,10 @@ fn merge_values(
             MergeOp::Append => MergePolicy::Merge,
             MergeOp::Prepend => MergePolicy::Prepend,
         };
-        return apply_policy(policy, base, stripped_overlay, path, policies);
+        return apply_policy(policy, base, stripped_overlay, path, policies, opts);
     }
 
-    apply_default_merge(base, stripped_overlay, path, policies)
+    apply_default_merge(base, stripped_overlay, path, policies, opts)
 }
 
 fn apply_policy(
@@ -158,6 +269,7 @@ fn apply_policy(
     overlay: Value,
     path: &str,
     policies: &HashMap<String, MergePolicy>,
+    opts: &Options,
 ) -> Result<Value, Error> {
     match policy {
         MergePolicy::Replace => Ok(overlay),
@@ -172,7 +284,7 @@ fn apply_policy(
             {
                 let mut result = overlay_seq.clone();
                 for elt in base_seq {
-                    if !result.contains(elt) {
+                    if opts.no_dedup || !result.contains(elt) {
                         result.push(elt.clone());
                     }
                 }
@@ -187,7 +299,13 @@ fn apply_policy(
             }
             Ok(overlay)
         }
-        MergePolicy::Merge => apply_default_merge(base, overlay, path, policies),
+        MergePolicy::AppendAll => match (base.inner(), overlay.inner()) {
+            (Value::Sequence(base_seq), Value::Sequence(overlay_seq)) => Ok(Value::Sequence(
+                base_seq.iter().chain(overlay_seq).cloned().collect(),
+            )),
+            _ => apply_default_merge(base, overlay, path, policies, opts),
+        },
+        MergePolicy::Merge => apply_default_merge(base, overlay, path, policies, opts),
     }
 }
 
@@ -196,6 +314,7 @@ fn apply_default_merge(
     overlay: Value,
     path: &str,
     policies: &HashMap<String, MergePolicy>,
+    opts: &Options,
 ) -> Result<Value, Error> {
     let overlay_inner = overlay.inner();
     let base_inner = base.inner();
@@ -232,7 +351,7 @@ fn apply_default_merge(
 
                 let key_str = match &key {
                     Value::String(s) => s.clone(),
-                    _ => format!("{:?}", key),
+                    _ => key_text(&key)?,
                 };
                 let new_path = if path.is_empty() {
                     key_str
@@ -241,7 +360,7 @@ fn apply_default_merge(
                 };
 
                 let merged_value = if let Some(base_value) = result.get(&key) {
-                    merge_values(base_value.clone(), overlay_value, &new_path, policies)?
+                    merge_values(base_value.clone(), overlay_value, &new_path, policies, opts)?
                 } else {
                     let (_, stripped) = extract_merge_directive(overlay_value)?;
                     stripped
@@ -271,7 +390,11 @@ fn apply_default_merge(
 
             let mut result = base_seq;
             for elt in overlay_seq {
-                if let Some(pos) = result.iter().position(|x| x == &elt) {
+                if let Some(pos) = result
+                    .iter()
+                    .position(|x| x == &elt)
+                    .filter(|_| !opts.no_dedup)
+                {
                     result.remove(pos);
                 }
                 result.push(elt);
@@ -297,7 +420,7 @@ fn apply_default_merge(
             } else {
                 format!("at '{}'", path)
             };
-            Err(Error::Type(format!(
+            Err(Error::type_error(format!(
                 "Type mismatch {}: cannot merge {} with {}",
                 location, base_type, overlay_type
             )))
@@ -305,31 +428,103 @@ fn apply_default_merge(
     }
 }
 
+// =============================================================================
+// Overlay Sources
+// =============================================================================
+
+/// Overlays read from stdin or fetched from URLs, read once per process as
+/// they are applied to every document
+static FETCHED: LazyLock<Mutex<HashMap<String, String>>> = LazyLock::new(Default::default);
+
+/// Fetch an `https://` URL with `curl`, giving up after `timeout` seconds.
+fn fetch(url: &str, timeout: u64) -> Result<String, Error> {
+    let failed = |reason: String| Error::Io(format!("Failed to fetch '{}': {}", url, reason));
+    let output = Command::new("curl")
+        // `-q` must come first for curl to skip `~/.curlrc`
+        .args(["-q", "--fail", "--silent", "--show-error", "--location"])
+        .args(["--proto", "=https", "--proto-redir", "=https", "--max-time"])
+        .arg(timeout.to_string())
+        .arg("--url")
+        .arg(url)
+        .output()
+        .map_err(|e| failed(format!("can't run curl: {}", e)))?;
+    match output.status.code() {
+        Some(0) => {
+            String::from_utf8(output.stdout).map_err(|_| failed("content isn't UTF-8".to_string()))
+        }
+        // Operation timeout
+        Some(28) => Err(failed(format!("timed out after {} seconds", timeout))),
+        _ => Err(failed(
+            String::from_utf8_lossy(&output.stderr)
+                .trim()
+                .trim_start_matches("curl: ")
+                .to_string(),
+        )),
+    }
+}
+
+/// Content of an overlay: a file, `-` for stdin, or an `https://` URL
+/// fetched within `url_timeout` seconds. Other paths, even ones looking
+/// like URLs of another scheme, are read from disk; only `http://` is
+/// rejected as a likely mistake.
+fn read_overlay(overlay: &str, url_timeout: u64) -> Result<String, Error> {
+    if overlay != "-" && !overlay.starts_with("https://") && !overlay.starts_with("http://") {
+        return std::fs::read_to_string(overlay)
+            .map_err(|e| Error::Io(format!("Failed to read '{}': {}", overlay, e)));
+    }
+    let mut fetched = FETCHED.lock().unwrap();
+    if let Some(content) = fetched.get(overlay) {
+        return Ok(content.clone());
+    }
+    let content = match overlay {
+        "-" => std::io::read_to_string(std::io::stdin())
+            .map_err(|e| Error::Io(format!("Failed to read overlay from stdin: {}", e)))?,
+        url if url.starts_with("http://") => {
+            return Err(Error::Base(format!(
+                "Unsupported overlay URL '{}': only https:// URLs can be fetched",
+                url
+            )))
+        }
+        url => fetch(url, url_timeout)?,
+    };
+    fetched.insert(overlay.to_string(), content.clone());
+    Ok(content)
+}
+
 // =============================================================================
 // Apply (Merge Overlays)
 // =============================================================================
 
-/// Apply overlay files to a base value.
+/// Apply overlays to a base value: files, `-` for stdin, or `https://` URLs
+/// fetched within `url_timeout` seconds.
+///
+/// With `strict_tags`, overlays holding a tag that looks like a mistyped
+/// merge directive are rejected before being merged.
 pub fn apply(
     overlay_paths: &[String],
     policies: &HashMap<String, MergePolicy>,
     base: Value,
+    url_timeout: u64,
+    strict_tags: bool,
+    opts: &Options,
 ) -> Result<Value, Error> {
     let mut result = base;
 
     for overlay_path in overlay_paths {
-        let overlay_str = std::fs::read_to_string(overlay_path)
-            .map_err(|e| Error::Io(format!("Failed to read '{}': {}", overlay_path, e)))?;
+        let overlay_str = read_overlay(overlay_path, url_timeout)?;
 
         let overlay: Value = if overlay_str.trim().is_empty() {
             Value::Null
         } else {
             overlay_str
                 .parse()
-                .map_err(|e| Error::Base(format!("Failed to parse '{}': {}", overlay_path, e)))?
+                .map_err(|e| Error::parse_in(&format!("'{}'", overlay_path), &overlay_str, e))?
         };
+        if strict_tags {
+            check_tags(&overlay, "", overlay_path)?;
+        }
 
-        result = merge_values(result, overlay, "", policies)?;
+        result = merge_values(result, overlay, "", policies, opts)?;
     }
 
     Ok(result)
@@ -371,6 +566,14 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_merge_policy_from_str_append_all() {
+        assert_eq!(
+            "append-all".parse::<MergePolicy>().unwrap(),
+            MergePolicy::AppendAll
+        );
+    }
+
     #[test]
     fn test_merge_policy_from_str_invalid() {
         let err = "invalid".parse::<MergePolicy>().unwrap_err();
@@ -435,6 +638,24 @@ mod tests {
         assert_eq!(value_type_name(&Value::Mapping(indexmap! {})), "mapping");
     }
 
+    // -------------------------------------------------------------------------
+    // check_tags Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_check_tags_reaches_nested_values() {
+        let overlay: Value = "a: !merge:replace\n  b: [1, !mrege:prepend [2]]\nc: !foo 3\n"
+            .parse()
+            .unwrap();
+        let err = check_tags(&overlay, "", "overlay.yaml").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Invalid tag in 'overlay.yaml' at 'a.b.1': suspicious tag '!mrege:prepend'"));
+
+        let overlay: Value = "a: !merge:replace {b: !custom 1}\n".parse().unwrap();
+        assert!(check_tags(&overlay, "", "overlay.yaml").is_ok());
+    }
+
     // -------------------------------------------------------------------------
     // extract_merge_directive Tests
     // -------------------------------------------------------------------------
@@ -484,7 +705,7 @@ mod tests {
         let overlay = Value::String("new".to_string());
         let policies = HashMap::new();
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         assert_eq!(result, Value::String("new".to_string()));
     }
 
@@ -494,7 +715,7 @@ mod tests {
         let overlay = Value::Null;
         let policies = HashMap::new();
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         assert_eq!(result, Value::String("keep".to_string()));
     }
 
@@ -504,7 +725,7 @@ mod tests {
         let overlay = Value::String("new".to_string());
         let policies = HashMap::new();
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         assert_eq!(result, Value::String("new".to_string()));
     }
 
@@ -524,7 +745,7 @@ mod tests {
         });
         let policies = HashMap::new();
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(map.len(), 3);
             assert_eq!(
@@ -555,7 +776,7 @@ mod tests {
         });
         let policies = HashMap::new();
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(map.len(), 1);
             assert!(map.contains_key(&Value::String("keep".to_string())));
@@ -582,7 +803,7 @@ mod tests {
         ]);
         let policies = HashMap::new();
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             // [a, c, b, d] - b moved to where overlay placed it
             assert_eq!(seq.len(), 4);
@@ -595,6 +816,33 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_merge_sequences_no_dedup() {
+        let base = Value::Sequence(vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+        ]);
+        let overlay = Value::Sequence(vec![
+            Value::String("b".to_string()),
+            Value::String("c".to_string()),
+        ]);
+        let opts = Options {
+            no_dedup: true,
+            ..Options::default()
+        };
+
+        let result = merge_values(base, overlay, "", &HashMap::new(), &opts).unwrap();
+        assert_eq!(
+            result,
+            Value::Sequence(
+                ["a", "b", "b", "c"]
+                    .iter()
+                    .map(|s| Value::String(s.to_string()))
+                    .collect()
+            )
+        );
+    }
+
     // -------------------------------------------------------------------------
     // merge_values Tests - Policy Override
     // -------------------------------------------------------------------------
@@ -611,7 +859,7 @@ mod tests {
         let mut policies = HashMap::new();
         policies.insert("".to_string(), MergePolicy::Replace);
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(map.len(), 1);
             assert!(map.contains_key(&Value::String("c".to_string())));
@@ -633,7 +881,7 @@ mod tests {
         let mut policies = HashMap::new();
         policies.insert("".to_string(), MergePolicy::Prepend);
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             // [x, y, a, b] - overlay comes first
             assert_eq!(seq.len(), 4);
@@ -646,6 +894,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_merge_sequence_with_append_all_policy() {
+        let base = Value::Sequence(vec![
+            Value::String("a".to_string()),
+            Value::String("b".to_string()),
+        ]);
+        let overlay = Value::Sequence(vec![
+            Value::String("a".to_string()),
+            Value::String("c".to_string()),
+        ]);
+        let mut policies = HashMap::new();
+        policies.insert("".to_string(), MergePolicy::AppendAll);
+
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
+        // [a, b, a, c] - duplicates are kept where they are
+        assert_eq!(
+            result,
+            Value::Sequence(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+                Value::String("a".to_string()),
+                Value::String("c".to_string()),
+            ])
+        );
+    }
+
     // -------------------------------------------------------------------------
     // merge_values Tests - Type Mismatch Errors
     // -------------------------------------------------------------------------
@@ -656,8 +930,9 @@ mod tests {
         let overlay = Value::Sequence(vec![]);
         let policies = HashMap::new();
 
-        let err = merge_values(base, overlay, "test.path", &policies).unwrap_err();
-        assert!(matches!(err, Error::Type(_)));
+        let err =
+            merge_values(base, overlay, "test.path", &policies, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Type { .. }));
         assert!(err.to_string().contains("cannot merge"));
         assert!(err.to_string().contains("at 'test.path'"));
     }
@@ -668,7 +943,7 @@ mod tests {
         let overlay = Value::Sequence(vec![]);
         let policies = HashMap::new();
 
-        let err = merge_values(base, overlay, "", &policies).unwrap_err();
+        let err = merge_values(base, overlay, "", &policies, &Options::default()).unwrap_err();
         assert!(err.to_string().contains("at root"));
     }
 
@@ -689,7 +964,7 @@ mod tests {
         }));
         let policies = HashMap::new();
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(map.len(), 1);
             assert!(map.contains_key(&Value::String("b".to_string())));
@@ -707,8 +982,9 @@ mod tests {
         }));
         let policies = HashMap::new();
 
-        let err = merge_values(base, overlay, "config", &policies).unwrap_err();
-        assert!(matches!(err, Error::Type(_)));
+        let err =
+            merge_values(base, overlay, "config", &policies, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Type { .. }));
         assert!(err.to_string().contains("!merge:append"));
         assert!(err.to_string().contains("sequences"));
     }
@@ -725,7 +1001,7 @@ mod tests {
         let mut policies = HashMap::new();
         policies.insert("".to_string(), MergePolicy::Replace);
 
-        let result = merge_values(base, overlay, "", &policies).unwrap();
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
         // Replace wins - only overlay content
         if let Value::Sequence(seq) = result {
             assert_eq!(seq.len(), 1);
@@ -734,4 +1010,73 @@ mod tests {
             panic!("Expected sequence");
         }
     }
+
+    // -------------------------------------------------------------------------
+    // Policy Pattern Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_path_matches_patterns() {
+        let matches = |pattern: &str, path: &str| {
+            let keys: Vec<&str> = match path {
+                "" => Vec::new(),
+                _ => path.split('.').collect(),
+            };
+            path_matches(&pattern.split('.').collect::<Vec<_>>(), &keys)
+        };
+        assert!(matches("services.*.env", "services.web.env"));
+        assert!(!matches("services.*.env", "services.env"));
+        assert!(!matches("services.*.env", "services.web.db.env"));
+        assert!(matches("services.web-*", "services.web-1"));
+        assert!(!matches("services.web-*", "services.db-1"));
+        assert!(matches("**.env", "env"));
+        assert!(matches("**.env", "services.web.env"));
+        assert!(matches("services.**", "services.web.env"));
+        assert!(!matches("services.**", "services"));
+        assert!(matches("a.**.z", "a.z"));
+        assert!(matches("a.**.z", "a.b.c.z"));
+        assert!(!matches("**", ""));
+    }
+
+    #[test]
+    fn test_policy_for_prefers_specific_paths() {
+        let args = vec![
+            "**=prepend".to_string(),
+            "services.*.env=replace".to_string(),
+            "services.db.env=merge".to_string(),
+        ];
+        let policies = parse_merge_policies(Some(&args)).unwrap();
+        assert_eq!(
+            policy_for(&policies, "services.web.env"),
+            Some(MergePolicy::Replace)
+        );
+        assert_eq!(
+            policy_for(&policies, "services.db.env"),
+            Some(MergePolicy::Merge)
+        );
+        assert_eq!(
+            policy_for(&policies, "services.web.ports"),
+            Some(MergePolicy::Prepend)
+        );
+        assert_eq!(policy_for(&policies, ""), None);
+    }
+
+    #[test]
+    fn test_merge_with_pattern_policy() {
+        let base: Value = "services:\n  web: {env: [A=1], ports: [80]}\n  db: {env: [B=1]}\n"
+            .parse()
+            .unwrap();
+        let overlay: Value = "services:\n  web: {env: [A=2], ports: [443]}\n  db: {env: [C=1]}\n"
+            .parse()
+            .unwrap();
+        let args = vec!["services.*.env=replace".to_string()];
+        let policies = parse_merge_policies(Some(&args)).unwrap();
+
+        let result = merge_values(base, overlay, "", &policies, &Options::default()).unwrap();
+        let expected: Value =
+            "services:\n  web: {env: [A=2], ports: [80, 443]}\n  db: {env: [C=1]}\n"
+                .parse()
+                .unwrap();
+        assert_eq!(result, expected);
+    }
 }
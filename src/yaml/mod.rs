@@ -6,20 +6,70 @@
 //! # Module Organization
 //!
 //! - [`error`]: Error types for YAML operations
-//! - [`path`]: Path parsing and index resolution
-//! - [`query`]: Query operations (get, type, length, keys, values)
-//! - [`mutation`]: Mutation operations (set, delete) - Value-based
+//! - [`path`]: Path parsing, escaping and index resolution
+//! - [`filter`]: Filter elements selecting sequence elements in paths, and `select-where`
+//! - [`navigate`]: Path walking shared by queries and mutations
+//! - [`query`]: Query operations (get, type, types, length, keys, values)
+//! - [`mutation`]: Mutation operations (set, ensure, insert, rename, move, copy, delete) - Value-based
+//! - [`update`]: In-place arithmetic and string updates of scalars
+//! - [`expr`]: Expressions mapped over sequences and mappings by `map`
+//! - [`sequence`]: Sorting, deduplication and reversal of sequences
+//! - [`jq`]: jq-style filters of the `filter` action
 //! - [`doc_mutation`]: Editor-based mutations (practical COW)
 //! - [`merge`]: Merge operations for the `apply` command
+//! - [`regions`]: Regions protected by `# shyaml:ignore-start/end` comments
+//! - [`acl`]: Paths mutations are allowed to modify
+//! - [`env`]: Environment variable interpolation in scalar values
+//! - [`export`]: Shell export and dotenv formatting of mappings
+//! - [`flatten`]: Flattening of nested structures into path/value leaves and back
+//! - [`key_order`]: Ordering of mapping keys in output documents
+//! - [`outline`]: Outline of a document for tree views
+//! - [`search`]: Search of the keys and values matching a pattern
+//! - [`aggregate`]: Sums, extrema, means and counts of sequence elements or mapping values
 //! - [`serialize`]: Serialization utilities
+//! - [`binary`]: Base64 encoding of `!!binary` scalars
+//! - [`truth`]: Words read as true or false where a boolean is expected
+//! - [`timestamp`]: Recognition and formatting of `!!timestamp` scalars
+//! - [`anchors`]: Factoring of repeated subtrees into anchors and aliases, anchor lookup
+//! - [`style`]: Indentation, collection style and quoting of output documents
+//! - [`annotate`]: Provenance header of generated documents
+//! - [`toml_bridge`]: Conversion between TOML documents and values
 
+mod acl;
+mod aggregate;
+mod anchors;
+mod annotate;
+mod binary;
+mod diff;
 mod doc_mutation;
+mod env;
 mod error;
+mod export;
+mod expr;
+mod filter;
+mod flatten;
+mod jq;
+mod json_schema;
+mod key_order;
+mod lint;
 pub mod merge;
 mod mutation;
+mod navigate;
+mod outline;
 mod path;
 mod query;
+mod regions;
+mod search;
+mod sequence;
 mod serialize;
+mod style;
+mod template;
+mod test_suite;
+mod timestamp;
+mod toml_bridge;
+mod truth;
+mod update;
+mod vars;
 
 // Re-export fyaml types
 pub use fyaml::{Document, FyParser, Value};
@@ -62,30 +112,152 @@ impl InnerValue for Value {
     }
 }
 
+// =============================================================================
+// Options
+// =============================================================================
+
+/// Options of the operations on documents, given by global command line
+/// flags and passed along with the paths they apply to.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    /// Path elements only designate string keys (`--string-keys-only`):
+    /// `8080` doesn't find the integer key 8080
+    pub string_keys_only: bool,
+    /// Sequences are merged without deduplication (`--no-dedup`), as the
+    /// `append-all` policy does
+    pub no_dedup: bool,
+    /// Tagged collections aren't read through their tag where a plain
+    /// mapping or sequence is expected (`--keep-tags`)
+    pub keep_tags: bool,
+    /// Words read as true and as false where a boolean is expected
+    /// (`--truthy` and `--falsy`)
+    pub truthiness: Truthiness,
+}
+
 // Re-export error type
 pub use error::Error;
 
+// Re-export environment interpolation
+pub use env::{env_subst, EnvAllow};
+
+// Re-export shell export and dotenv formatting
+pub use export::{export, export_ref, to_dotenv, to_dotenv_ref};
+
+// Re-export flattening
+pub use flatten::{
+    flatten, flatten_ref, key_values_recursive, key_values_recursive_ref, unflatten,
+};
+
+// Re-export document differences
+pub use diff::{path_diff, unified_diff};
+
+// Re-export key ordering
+pub use key_order::KeyOrder;
+
+// Re-export document outline
+pub use outline::{outline, OutlineNode};
+
+// Re-export sequence reordering
+pub use sequence::{reorder, reorder_doc, SeqOp};
+
+// Re-export jq-style filters
+pub use jq::JqFilter;
+
+// Re-export sequence selection
+pub use filter::select_where;
+
+// Re-export aggregations
+pub use aggregate::{aggregate, count_where, Aggregate};
+
+// Re-export search
+pub use search::{search, SearchScope};
+
+// Re-export path helpers
+pub use path::{join_path, parent_path, relative_path, sibling_path};
+
 // Re-export merge types
 pub use merge::{apply, parse_merge_policies};
 
+// Re-export protected regions
+pub use regions::{
+    check_protected, check_protected_doc, protected_paths, restore_protected, IGNORE_START,
+};
+
+// Re-export allowed paths
+pub use acl::{check_allowed, check_allowed_changes, check_allowed_doc, AllowedPaths};
+
+// Re-export JSON Schema validation
+pub use json_schema::JsonSchema;
+
+// Re-export style rules of check-syntax, and the repair of broken aliases
+pub use lint::{null_undefined_aliases, StyleRules};
+
+// Re-export assertion suites
+pub use test_suite::{Outcome, TestSuite};
+
+// Re-export text templates
+pub use template::Template;
+
 // Re-export mutation functions (Value-based, for fallback/apply)
-pub use mutation::{del, parse_value, set_value};
+pub use mutation::{
+    copy_value, del, deletion_paths, ensure, ensure_default, insert, move_value, parse_assignment,
+    parse_value, read_assignments, rename_key, set_value, tag_new_value, value_from_file,
+};
 
 // Re-export Editor-based mutation functions (practical COW)
-pub use doc_mutation::{del_doc, set_value_doc};
+pub use doc_mutation::{
+    copy_doc, del_doc, deletion_paths_doc, ensure_doc, insert_doc, move_doc, rename_key_doc,
+    set_value_doc,
+};
+
+// Re-export scalar updates
+pub use update::{parse_number, parse_replace, update, update_doc, UpdateOp};
+
+// Re-export variables of --arg and --argjson
+pub use vars::Vars;
+
+// Re-export mapped expressions
+pub use expr::{map, map_doc, Expr};
 
 // Re-export query functions (zero-copy)
 pub use query::{
-    get_length_ref, get_type_ref, get_value_ref, get_values_ref, key_values_ref, keys_ref,
-    values_ref, GetValuesIter,
+    get_length_ref, get_type_ref, get_types_ref, get_value_ref, get_values_ref, key_values_ref,
+    keys_ref, values_ref, GetValuesIter, KeysIter,
 };
 
 // Re-export query functions (owned)
-pub use query::{get_length, get_type, get_value, get_values, key_values, keys, values};
+pub use query::{
+    check_json_schema_type, get_at_path, get_length, get_type, get_types, get_value,
+    get_value_from_file, get_values, get_values_iter, key_values, keys, value_ref_type_name,
+    value_to_type_name, values, Schema,
+};
 
 // Re-export serialization functions
 pub use serialize::{serialize, serialize_raw, serialize_raw_ref, serialize_ref};
 
+// Re-export binary scalars
+pub use binary::{binary_from_file, binary_value, decode_binary};
+
+// Re-export truthiness tables
+pub use truth::{source_text, Truthiness};
+
+// Re-export timestamps
+pub use timestamp::format_timestamp;
+
+// Re-export anchor factoring and the anchors and aliases of documents
+pub use anchors::{
+    serialize_aliased, serialize_factored, source_aliases, source_anchors, SourceAliases,
+};
+
+// Re-export output styles
+pub use style::{key_text, serialize_styled, Style};
+
+// Re-export TOML conversion
+pub use toml_bridge::{from_toml, serialize_toml};
+
+// Re-export provenance header
+pub use annotate::{annotate, annotate_doc, annotation_timestamp};
+
 // =============================================================================
 // Streaming
 // =============================================================================
@@ -101,6 +273,102 @@ pub fn streaming_documents_from_stdin(
     Ok(parser.doc_iter().map(|r| r.map_err(Error::from)))
 }
 
+/// Stream the documents of a string, like `streaming_documents_from_stdin()`.
+pub fn streaming_documents_from_str(
+    text: &str,
+) -> Result<impl Iterator<Item = Result<Document, Error>>, Error> {
+    let parser = FyParser::from_string(text)?;
+    Ok(parser.doc_iter().map(|r| r.map_err(Error::from)))
+}
+
+/// Stream documents from a file, like `streaming_documents_from_stdin()`.
+pub fn streaming_documents_from_file(
+    file: &str,
+) -> Result<impl Iterator<Item = Result<Document, Error>>, Error> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+    let parser = FyParser::from_string(&content)?;
+    Ok(parser.doc_iter().map(|r| r.map_err(Error::from)))
+}
+
+/// Read all the documents of a YAML file.
+pub fn documents_from_file(file: &str) -> Result<Vec<Document>, Error> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+    let what = format!("'{}'", file);
+    let parser =
+        FyParser::from_string(&content).map_err(|e| Error::parse_in(&what, &content, e))?;
+    parser
+        .doc_iter()
+        .map(|doc| doc.map_err(|e| Error::parse_in(&what, &content, e)))
+        .collect()
+}
+
+/// A problem found by `check_syntax()`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Problem {
+    /// 1-based line and column, when known
+    pub location: Option<(usize, usize)>,
+    /// Name of the lint rule, `syntax` for parse errors
+    pub rule: &'static str,
+    pub message: String,
+}
+
+/// Parse all the documents of a YAML stream without using them, and lint
+/// their anchors and aliases and the style `rules`.
+///
+/// Returns the number of documents, or the problems found sorted by
+/// location: parsing can't resume after a syntax error, so only the first
+/// one is reported.
+pub fn check_syntax(input: &str, rules: &StyleRules) -> Result<usize, Vec<Problem>> {
+    let lints = lint::lint(input, rules);
+    let mut problems: Vec<Problem> = lints
+        .iter()
+        .map(|l| Problem {
+            location: Some((l.line, l.column)),
+            rule: l.kind.rule(),
+            message: l.kind.to_string(),
+        })
+        .collect();
+
+    let mut count = 0;
+    let parsed = FyParser::from_string(input).and_then(|parser| {
+        for doc in parser.doc_iter() {
+            doc?;
+            count += 1;
+        }
+        Ok(())
+    });
+    let syntax_error = |location, message: String| Problem {
+        location,
+        rule: "syntax",
+        message,
+    };
+    match parsed {
+        Err(fyaml::Error::ParseError(e)) => match error::parse_error_location(&e) {
+            Some((line, column)) => {
+                // Already reported with the alias name
+                let undefined_alias = lints
+                    .iter()
+                    .any(|l| l.line == line && matches!(l.kind, lint::LintKind::UndefinedAlias(_)));
+                if !undefined_alias {
+                    problems.push(syntax_error(Some((line, column)), e.message().to_string()));
+                }
+            }
+            None => problems.push(syntax_error(None, e.message().to_string())),
+        },
+        Err(e) => problems.push(syntax_error(None, e.to_string())),
+        Ok(()) => {}
+    }
+
+    if problems.is_empty() {
+        return Ok(count);
+    }
+    // Problems without location last
+    problems.sort_by_key(|p| p.location.unwrap_or((usize::MAX, 0)));
+    Err(problems)
+}
+
 /// Convert a Document to an owned Value.
 ///
 /// Use this when you need to mutate the document or pass it through
@@ -1,20 +1,51 @@
 //! Mutation operations for YAML values.
 //!
-//! Provides set-value, delete, and parse operations.
+//! Provides set-value, ensure, insert, rename, move, copy, delete, and parse
+//! operations.
 
 use super::error::Error;
-use super::path::{resolve_index, split_path};
+use super::filter::{deletion_order, resolve_filters};
+use super::flatten::FlattenNode;
+use super::navigate::{check_new_key, new_key, path_error_missing_key, visit_path, PathVisitor};
+use super::path::{join_elements, resolve_index, split_assignment};
+use super::query::{get_at_path, value_to_type_name};
+use super::regions::resolved_parts;
+use super::Options;
+use fyaml::TaggedValue;
 pub use fyaml::Value;
+use indexmap::IndexMap;
 
 /// Set a value at a key path.
-pub fn set_value(key: &str, new_value: Value, mut base: Value) -> Result<Value, Error> {
+pub fn set_value(
+    key: &str,
+    new_value: Value,
+    mut base: Value,
+    opts: &Options,
+) -> Result<Value, Error> {
     if matches!(base, Value::Null) {
         base = Value::Mapping(Default::default());
     }
-    set_value_at_path(&mut base, key, new_value)?;
+    let key = resolve_filters(&base, key)?;
+    set_value_at_path(&mut base, &key, new_value, opts)?;
     Ok(base)
 }
 
+/// Tag the value set by `set-value` with `tag` (`--tag`), replacing its
+/// own, or else with `replaced_tag`, the tag of the value it replaces
+/// (`--keep-tag`), unless it has its own.
+pub fn tag_new_value(new_value: Value, tag: Option<&str>, replaced_tag: Option<String>) -> Value {
+    let tag = match (tag, &new_value, replaced_tag) {
+        (Some(tag), _, _) => tag.to_string(),
+        (None, Value::Tagged(_), _) | (None, _, None) => return new_value,
+        (None, _, Some(replaced_tag)) => replaced_tag,
+    };
+    let value = match new_value {
+        Value::Tagged(t) => t.value,
+        value => value,
+    };
+    Value::Tagged(Box::new(TaggedValue { tag, value }))
+}
+
 /// Parse a string as either full YAML or with scalar type inference.
 ///
 /// With `parse_as_yaml = true` (`-y` flag): the value is parsed as full YAML,
@@ -27,7 +58,7 @@ pub fn parse_value(value_str: &str, parse_as_yaml: bool) -> Result<Value, Error>
     if parse_as_yaml {
         value_str
             .parse()
-            .map_err(|e| Error::Base(format!("Failed to parse value as YAML: {}", e)))
+            .map_err(|e| Error::parse_in("value as YAML", value_str, e))
     } else {
         // Scalar type inference: parse as YAML, accept only scalar results.
         // Sequences and mappings are kept as literal strings.
@@ -38,132 +69,368 @@ pub fn parse_value(value_str: &str, parse_as_yaml: bool) -> Result<Value, Error>
     }
 }
 
-fn set_value_at_path(root: &mut Value, path: &str, value: Value) -> Result<(), Error> {
-    let path_parts = split_path(path);
-
-    if path_parts.is_empty() {
-        return Err(Error::Path("Empty path".to_string()));
+/// Read a value from a file, parsed as YAML with `parse_as_yaml` or kept as
+/// is in a string otherwise.
+pub fn value_from_file(file: &str, parse_as_yaml: bool) -> Result<Value, Error> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+    if parse_as_yaml {
+        parse_value(&content, true)
+    } else {
+        Ok(Value::String(content))
     }
+}
 
-    let mut current = root;
+/// Split a `PATH=VALUE` assignment of `set-values` on its first `=` that
+/// is neither escaped nor inside a filter.
+pub fn parse_assignment(assignment: &str) -> Result<(String, String), Error> {
+    split_assignment(assignment)
+        .map(|(path, value)| (path.to_string(), value.to_string()))
+        .ok_or_else(|| {
+            Error::Base(format!(
+                "invalid assignment '{}', expected 'PATH=VALUE'.",
+                assignment
+            ))
+        })
+}
 
-    for (i, part) in path_parts.iter().enumerate() {
-        let is_last = i == path_parts.len() - 1;
+/// Read `PATH=VALUE` assignments from the lines of a file, ignoring blank
+/// lines and `#` comments.
+pub fn read_assignments(file: &str) -> Result<Vec<(String, String)>, Error> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .map(|(i, line)| {
+            parse_assignment(line).map_err(|e| Error::Base(format!("{}:{}: {}", file, i + 1, e)))
+        })
+        .collect()
+}
 
-        if is_last {
-            match current {
-                Value::Mapping(map) => {
-                    map.insert(Value::String(part.clone()), value);
-                    return Ok(());
-                }
-                Value::Sequence(seq) => {
-                    let idx = resolve_index(part, seq.len(), path)?;
-                    seq[idx] = value;
-                    return Ok(());
-                }
-                _ => {
-                    return Err(Error::Path(format!(
-                        "invalid path '{}', cannot set value on scalar at '{}'.",
-                        path, part
-                    )));
-                }
-            }
-        }
+/// Set the node designated by a path, adding the keys missing on the way.
+struct Set(Value);
 
-        match current {
-            Value::Mapping(map) => {
-                let key = Value::String(part.clone());
-                if !map.contains_key(&key) {
-                    map.insert(key.clone(), Value::Mapping(Default::default()));
-                }
-                current = map.get_mut(&key).unwrap();
-            }
-            Value::Sequence(seq) => {
-                let idx = resolve_index(part, seq.len(), path)?;
-                current = &mut seq[idx];
+impl PathVisitor for Set {
+    type Output = ();
+
+    const CREATES_PARENTS: bool = true;
+
+    fn visit_mapping(
+        self,
+        map: &mut IndexMap<Value, Value>,
+        key: Option<Value>,
+        part: &str,
+        full_path: &str,
+    ) -> Result<(), Error> {
+        let key = match key {
+            Some(key) => key,
+            None => {
+                let key = new_key(part);
+                check_new_key(map, &key, part, full_path)?;
+                key
             }
-            _ => {
-                return Err(Error::Path(format!(
-                    "invalid path '{}', cannot traverse scalar at '{}'.",
-                    path, part
+        };
+        map.insert(key, self.0);
+        Ok(())
+    }
+
+    fn visit_sequence(self, seq: &mut Vec<Value>, index: usize) -> Result<(), Error> {
+        seq[index] = self.0;
+        Ok(())
+    }
+
+    fn scalar_error(&self, part: &str, full_path: &str) -> Error {
+        Error::path_error_at(
+            full_path,
+            format!(
+                "invalid path '{}', cannot set value on scalar at '{}'.",
+                full_path, part
+            ),
+        )
+    }
+}
+
+pub(super) fn set_value_at_path(
+    root: &mut Value,
+    path: &str,
+    value: Value,
+    opts: &Options,
+) -> Result<(), Error> {
+    visit_path(root, path, Set(value), opts)
+}
+
+/// Build the value `ensure` creates at a missing path.
+///
+/// Without `default`, this is an empty value of `kind` (`map` if not
+/// given). A `default` gets the same type inference as `set-value`, is kept
+/// as is with kind `str`, and must be of the given kind otherwise.
+pub fn ensure_default(kind: Option<&str>, default: Option<&str>) -> Result<Value, Error> {
+    let value = match (kind, default) {
+        (Some("seq"), None) => Value::Sequence(Vec::new()),
+        (Some("str"), None) => Value::String(String::new()),
+        (_, None) => Value::Mapping(Default::default()),
+        (None, Some(default)) => parse_value(default, false)?,
+        (Some("str"), Some(default)) => Value::String(default.to_string()),
+        (Some(kind), Some(default)) => {
+            let value = parse_value(default, true)?;
+            let expected = if kind == "seq" { "sequence" } else { "struct" };
+            if value_to_type_name(&value) != expected {
+                return Err(Error::type_error(format!(
+                    "ensure --type {} expects a '{}' default, got '{}'.",
+                    kind,
+                    expected,
+                    value_to_type_name(&value)
                 )));
             }
+            value
+        }
+    };
+    Ok(value)
+}
+
+/// Set a value at a key path only if nothing exists there yet.
+pub fn ensure(key: &str, default: Value, base: Value, opts: &Options) -> Result<Value, Error> {
+    match get_at_path(&base, Some(key), opts) {
+        Ok(_) => Ok(base),
+        Err(Error::Path { .. }) => set_value(key, default, base, opts),
+        Err(e) => Err(e),
+    }
+}
+
+/// Resolve the position of an element inserted in a sequence of `len`
+/// elements.
+///
+/// Negative indices count from the end of the resulting sequence, so `-1`
+/// appends.
+pub(super) fn insert_position(index: &str, len: usize, path: &str) -> Result<usize, Error> {
+    resolve_index(index, len + 1, path).map_err(|_| {
+        Error::path_error_at(
+            path,
+            format!(
+                "invalid path '{}', cannot insert at index {} ({} elements in sequence).",
+                path, index, len
+            ),
+        )
+    })
+}
+
+/// Error for adding an element to something that isn't a sequence.
+pub(super) fn type_error_insert(path: &str, type_name: &str) -> Error {
+    Error::type_error_at(
+        path,
+        format!(
+            "invalid path '{}', cannot add an element to a '{}'. Please provide or select a sequence.",
+            path, type_name
+        ),
+    )
+}
+
+/// Insert a value in the sequence at a key path (the root if empty), creating
+/// the sequence if missing.
+///
+/// Without `index`, the value is appended.
+pub fn insert(
+    key: &str,
+    index: Option<&str>,
+    value: Value,
+    base: Value,
+    opts: &Options,
+) -> Result<Value, Error> {
+    let path = (!key.is_empty()).then_some(key);
+    let mut seq = match get_at_path(&base, path, opts) {
+        Ok(Value::Sequence(seq)) => seq.clone(),
+        Ok(Value::Null) | Err(Error::Path { .. }) => Vec::new(),
+        Ok(other) => return Err(type_error_insert(key, value_to_type_name(other))),
+        Err(e) => return Err(e),
+    };
+    let position = match index {
+        Some(index) => insert_position(index, seq.len(), key)?,
+        None => seq.len(),
+    };
+    seq.insert(position, value);
+    match path {
+        Some(key) => set_value(key, Value::Sequence(seq), base, opts),
+        None => Ok(Value::Sequence(seq)),
+    }
+}
+
+/// Error for renaming something that isn't the key of a mapping.
+pub(super) fn type_error_rename(path: &str, type_name: &str) -> Error {
+    Error::type_error_at(
+        path,
+        format!(
+            "invalid path '{}', cannot rename an element of a '{}'. Please provide a key of a struct.",
+            path, type_name
+        ),
+    )
+}
+
+/// Error for renaming a key to the name of another key.
+pub(super) fn rename_exists_error(path: &str, new_name: &str) -> Error {
+    Error::path_error(format!(
+        "cannot rename '{}' to '{}', key already exists.",
+        path, new_name
+    ))
+}
+
+/// Rename the key at a path, keeping its value and position.
+pub fn rename_key(key: &str, new_name: &str, base: Value, opts: &Options) -> Result<Value, Error> {
+    let mut parts = resolved_parts(&base, key)?;
+    let name = match parts.pop() {
+        Some(name) if !key.is_empty() => name,
+        _ => return Err(Error::path_error("Empty path".to_string())),
+    };
+    get_at_path(&base, Some(key), opts)?;
+    let parent = join_elements(&parts);
+    let parent = (!parts.is_empty()).then_some(parent.as_str());
+    let mut map = match get_at_path(&base, parent, opts)? {
+        Value::Mapping(map) => map.clone(),
+        other => return Err(type_error_rename(key, value_to_type_name(other))),
+    };
+    if name == new_name {
+        return Ok(base);
+    }
+    let new_key = Value::String(new_name.to_string());
+    if map.contains_key(&new_key) {
+        return Err(rename_exists_error(key, new_name));
+    }
+    let Some((index, _, value)) = map.shift_remove_full(&Value::String(name)) else {
+        unreachable!("key checked above");
+    };
+    map.shift_insert(index, new_key, value);
+    match parent {
+        Some(parent) => set_value(parent, Value::Mapping(map), base, opts),
+        None => Ok(Value::Mapping(map)),
+    }
+}
+
+/// Resolve the source and destination paths of a move, which must not
+/// contain each other.
+pub(super) fn move_paths<N: FlattenNode + Copy>(
+    root: N,
+    src: &str,
+    dst: &str,
+) -> Result<(Vec<String>, Vec<String>), Error> {
+    if src.is_empty() || dst.is_empty() {
+        return Err(Error::path_error("Empty path".to_string()));
+    }
+    let src_parts = resolved_parts(root, src)?;
+    let dst_parts = resolved_parts(root, dst)?;
+    if src_parts.starts_with(&dst_parts) || dst_parts.starts_with(&src_parts) {
+        return Err(Error::path_error(format!(
+            "cannot move '{}' to '{}', paths overlap.",
+            src, dst
+        )));
+    }
+    Ok((src_parts, dst_parts))
+}
+
+/// Whether a move stays within a mapping and only renames a key.
+pub(super) fn is_rename<N: FlattenNode + Copy>(
+    root: N,
+    src_parts: &[String],
+    dst_parts: &[String],
+) -> bool {
+    let (src_parent, dst_parent) = (
+        &src_parts[..src_parts.len() - 1],
+        &dst_parts[..dst_parts.len() - 1],
+    );
+    if src_parent != dst_parent {
+        return false;
+    }
+    let mut node = root;
+    for part in src_parent {
+        let child = node
+            .children()
+            .and_then(|children| children.into_iter().find(|(k, _)| k == part));
+        match child {
+            Some((_, child)) => node = child,
+            None => return false,
         }
     }
+    let last = dst_parts.last();
+    !node.is_sequence()
+        && node
+            .children()
+            .is_some_and(|children| !children.iter().any(|(k, _)| Some(k) == last))
+}
 
-    Ok(())
+/// Move the value at a path to another path, replacing what was there.
+///
+/// A key moved within its mapping keeps its position.
+pub fn move_value(src: &str, dst: &str, base: Value, opts: &Options) -> Result<Value, Error> {
+    let (src_parts, dst_parts) = move_paths(&base, src, dst)?;
+    let value = get_at_path(&base, Some(src), opts)?.clone();
+    let (src, dst) = (join_elements(&src_parts), join_elements(&dst_parts));
+    if is_rename(&base, &src_parts, &dst_parts) {
+        return rename_key(&src, &dst_parts[dst_parts.len() - 1], base, opts);
+    }
+    del(&src, set_value(&dst, value, base, opts)?, opts)
+}
+
+/// Copy the value at a path to another path, replacing what was there.
+pub fn copy_value(src: &str, dst: &str, base: Value, opts: &Options) -> Result<Value, Error> {
+    let value = get_at_path(&base, Some(src), opts)?.clone();
+    set_value(dst, value, base, opts)
 }
 
 /// Delete a value at a key path.
-pub fn del(key: &str, mut base: Value) -> Result<Value, Error> {
+pub fn del(key: &str, mut base: Value, opts: &Options) -> Result<Value, Error> {
     if matches!(base, Value::Null) {
-        return Err(Error::Path("Cannot delete from empty document".to_string()));
+        return Err(Error::path_error(
+            "Cannot delete from empty document".to_string(),
+        ));
     }
-    del_at_path(&mut base, key)?;
+    let key = resolve_filters(&base, key)?;
+    del_at_path(&mut base, &key, opts)?;
     Ok(base)
 }
 
-fn del_at_path(root: &mut Value, path: &str) -> Result<(), Error> {
-    let path_parts = split_path(path);
+/// Paths to delete in turn so as to remove the nodes `paths` designate in
+/// a value, wildcards expanded.
+#[must_use]
+pub fn deletion_paths(value: &Value, paths: &[String]) -> Vec<String> {
+    deletion_order(value, paths)
+}
 
-    if path_parts.is_empty() || (path_parts.len() == 1 && path_parts[0].is_empty()) {
-        return Err(Error::Path("Empty path".to_string()));
-    }
+/// Delete the node designated by a path.
+struct Delete;
 
-    let mut current = root;
+impl PathVisitor for Delete {
+    type Output = ();
 
-    for (i, part) in path_parts.iter().enumerate() {
-        let is_last = i == path_parts.len() - 1;
+    fn visit_mapping(
+        self,
+        map: &mut IndexMap<Value, Value>,
+        key: Option<Value>,
+        part: &str,
+        full_path: &str,
+    ) -> Result<(), Error> {
+        let key = key.ok_or_else(|| path_error_missing_key(full_path, part))?;
+        map.shift_remove(&key);
+        Ok(())
+    }
 
-        if is_last {
-            return match current {
-                Value::Mapping(map) => {
-                    let key = Value::String(part.clone());
-                    if map.shift_remove(&key).is_none() {
-                        Err(Error::Path(format!(
-                            "invalid path '{}', missing key '{}' in struct.",
-                            path, part
-                        )))
-                    } else {
-                        Ok(())
-                    }
-                }
-                Value::Sequence(seq) => {
-                    let idx = resolve_index(part, seq.len(), path)?;
-                    seq.remove(idx);
-                    Ok(())
-                }
-                _ => Err(Error::Path(format!(
-                    "invalid path '{}', cannot delete from scalar.",
-                    path
-                ))),
-            };
-        }
+    fn visit_sequence(self, seq: &mut Vec<Value>, index: usize) -> Result<(), Error> {
+        seq.remove(index);
+        Ok(())
+    }
 
-        current = match current {
-            Value::Mapping(map) => {
-                let key = Value::String(part.clone());
-                map.get_mut(&key).ok_or_else(|| {
-                    Error::Path(format!(
-                        "invalid path '{}', missing key '{}' in struct.",
-                        path, part
-                    ))
-                })?
-            }
-            Value::Sequence(seq) => {
-                let idx = resolve_index(part, seq.len(), path)?;
-                &mut seq[idx]
-            }
-            _ => {
-                return Err(Error::Path(format!(
-                    "invalid path '{}', cannot traverse scalar at '{}'.",
-                    path, part
-                )));
-            }
-        };
+    fn scalar_error(&self, _part: &str, full_path: &str) -> Error {
+        Error::path_error_at(
+            full_path,
+            format!("invalid path '{}', cannot delete from scalar.", full_path),
+        )
     }
+}
 
-    Ok(())
+fn del_at_path(root: &mut Value, path: &str, opts: &Options) -> Result<(), Error> {
+    if path.is_empty() {
+        return Err(Error::path_error("Empty path".to_string()));
+    }
+    visit_path(root, path, Delete, opts)
 }
 
 // =============================================================================
@@ -264,7 +531,13 @@ mod tests {
         let base = Value::Mapping(indexmap! {
             Value::String("a".to_string()) => Value::Number(Number::Int(1)),
         });
-        let result = set_value("b", Value::Number(Number::Int(2)), base).unwrap();
+        let result = set_value(
+            "b",
+            Value::Number(Number::Int(2)),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(map.len(), 2);
             assert_eq!(
@@ -281,7 +554,13 @@ mod tests {
         let base = Value::Mapping(indexmap! {
             Value::String("key".to_string()) => Value::String("old".to_string()),
         });
-        let result = set_value("key", Value::String("new".to_string()), base).unwrap();
+        let result = set_value(
+            "key",
+            Value::String("new".to_string()),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(
                 map.get(&Value::String("key".to_string())),
@@ -299,7 +578,13 @@ mod tests {
                 Value::String("inner".to_string()) => Value::Number(Number::Int(1)),
             }),
         });
-        let result = set_value("outer.inner", Value::Number(Number::Int(99)), base).unwrap();
+        let result = set_value(
+            "outer.inner",
+            Value::Number(Number::Int(99)),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
         if let Value::Mapping(map) = &result {
             if let Some(Value::Mapping(inner)) = map.get(&Value::String("outer".to_string())) {
                 assert_eq!(
@@ -315,7 +600,13 @@ mod tests {
     #[test]
     fn test_set_value_creates_intermediate_mappings() {
         let base = Value::Mapping(indexmap! {});
-        let result = set_value("a.b.c", Value::String("deep".to_string()), base).unwrap();
+        let result = set_value(
+            "a.b.c",
+            Value::String("deep".to_string()),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
 
         // Navigate to verify: a.b.c = "deep"
         if let Value::Mapping(a_map) = &result {
@@ -335,7 +626,13 @@ mod tests {
     #[test]
     fn test_set_value_on_null_creates_mapping() {
         let base = Value::Null;
-        let result = set_value("key", Value::String("value".to_string()), base).unwrap();
+        let result = set_value(
+            "key",
+            Value::String("value".to_string()),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(
                 map.get(&Value::String("key".to_string())),
@@ -355,7 +652,13 @@ mod tests {
                 Value::String("c".to_string()),
             ]),
         });
-        let result = set_value("items.1", Value::String("changed".to_string()), base).unwrap();
+        let result = set_value(
+            "items.1",
+            Value::String("changed".to_string()),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
         if let Value::Mapping(map) = &result {
             if let Some(Value::Sequence(seq)) = map.get(&Value::String("items".to_string())) {
                 assert_eq!(seq[1], Value::String("changed".to_string()));
@@ -371,7 +674,13 @@ mod tests {
             Value::String("first".to_string()),
             Value::String("last".to_string()),
         ]);
-        let result = set_value("-1", Value::String("modified".to_string()), base).unwrap();
+        let result = set_value(
+            "-1",
+            Value::String("modified".to_string()),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
         if let Value::Sequence(seq) = result {
             assert_eq!(seq[1], Value::String("modified".to_string()));
         } else {
@@ -383,7 +692,13 @@ mod tests {
     fn test_set_value_empty_path_creates_empty_key() {
         // Empty path creates a key with empty string (valid in YAML)
         let base = Value::Mapping(indexmap! {});
-        let result = set_value("", Value::String("value".to_string()), base).unwrap();
+        let result = set_value(
+            "",
+            Value::String("value".to_string()),
+            base,
+            &Options::default(),
+        )
+        .unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(
                 map.get(&Value::String("".to_string())),
@@ -397,19 +712,51 @@ mod tests {
     #[test]
     fn test_set_value_error_traverse_scalar() {
         let base = Value::String("scalar".to_string());
-        let err = set_value("child", Value::Null, base).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = set_value("child", Value::Null, base, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("cannot"));
     }
 
     #[test]
     fn test_set_value_error_index_out_of_range() {
         let base = Value::Sequence(vec![Value::String("only".to_string())]);
-        let err = set_value("5", Value::Null, base).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = set_value("5", Value::Null, base, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("out of range"));
     }
 
+    // -------------------------------------------------------------------------
+    // tag_new_value Tests
+    // -------------------------------------------------------------------------
+
+    fn tagged(tag: &str, value: &str) -> Value {
+        Value::Tagged(Box::new(TaggedValue {
+            tag: tag.to_string(),
+            value: Value::String(value.to_string()),
+        }))
+    }
+
+    #[test]
+    fn test_tag_new_value_keeps_replaced_tag() {
+        let new_value = Value::String("s3cr3t".to_string());
+        let result = tag_new_value(new_value.clone(), None, Some("!vault".to_string()));
+        assert_eq!(result, tagged("!vault", "s3cr3t"));
+        // Nothing to keep
+        assert_eq!(
+            tag_new_value(new_value, None, None),
+            Value::String("s3cr3t".to_string())
+        );
+    }
+
+    #[test]
+    fn test_tag_new_value_own_and_explicit_tags() {
+        let own = tagged("!env", "HOME");
+        let result = tag_new_value(own.clone(), None, Some("!vault".to_string()));
+        assert_eq!(result, own);
+        let result = tag_new_value(own, Some("!vault"), None);
+        assert_eq!(result, tagged("!vault", "HOME"));
+    }
+
     // -------------------------------------------------------------------------
     // del Tests
     // -------------------------------------------------------------------------
@@ -420,7 +767,7 @@ mod tests {
             Value::String("a".to_string()) => Value::Number(Number::Int(1)),
             Value::String("b".to_string()) => Value::Number(Number::Int(2)),
         });
-        let result = del("a", base).unwrap();
+        let result = del("a", base, &Options::default()).unwrap();
         if let Value::Mapping(map) = result {
             assert_eq!(map.len(), 1);
             assert!(!map.contains_key(&Value::String("a".to_string())));
@@ -438,7 +785,7 @@ mod tests {
                 Value::String("remove".to_string()) => Value::Number(Number::Int(2)),
             }),
         });
-        let result = del("outer.remove", base).unwrap();
+        let result = del("outer.remove", base, &Options::default()).unwrap();
         if let Value::Mapping(map) = &result {
             if let Some(Value::Mapping(inner)) = map.get(&Value::String("outer".to_string())) {
                 assert_eq!(inner.len(), 1);
@@ -456,7 +803,7 @@ mod tests {
             Value::String("b".to_string()),
             Value::String("c".to_string()),
         ]);
-        let result = del("1", base).unwrap();
+        let result = del("1", base, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             assert_eq!(seq.len(), 2);
             assert_eq!(seq[0], Value::String("a".to_string()));
@@ -473,7 +820,7 @@ mod tests {
             Value::String("middle".to_string()),
             Value::String("last".to_string()),
         ]);
-        let result = del("-1", base).unwrap();
+        let result = del("-1", base, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             assert_eq!(seq.len(), 2);
             assert_eq!(seq[1], Value::String("middle".to_string()));
@@ -485,8 +832,8 @@ mod tests {
     #[test]
     fn test_del_error_empty_document() {
         let base = Value::Null;
-        let err = del("key", base).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = del("key", base, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("empty document"));
     }
 
@@ -495,16 +842,16 @@ mod tests {
         let base = Value::Mapping(indexmap! {
             Value::String("exists".to_string()) => Value::Number(Number::Int(1)),
         });
-        let err = del("nonexistent", base).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = del("nonexistent", base, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("missing key"));
     }
 
     #[test]
     fn test_del_error_index_out_of_range() {
         let base = Value::Sequence(vec![Value::String("only".to_string())]);
-        let err = del("5", base).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = del("5", base, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("out of range"));
     }
 
@@ -513,8 +860,8 @@ mod tests {
         let base = Value::Mapping(indexmap! {
             Value::String("a".to_string()) => Value::Number(Number::Int(1)),
         });
-        let err = del("", base).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = del("", base, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("Empty path"));
     }
 
@@ -523,9 +870,273 @@ mod tests {
         let base = Value::Mapping(indexmap! {
             Value::String("scalar".to_string()) => Value::String("value".to_string()),
         });
-        let err = del("scalar.child", base).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = del("scalar.child", base, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         // Error is "cannot delete from scalar" when trying to delete a child of a scalar
         assert!(err.to_string().contains("cannot delete from scalar"));
     }
+
+    // =========================================================================
+    // ensure() tests
+    // =========================================================================
+
+    #[test]
+    fn test_ensure_default_kinds() {
+        assert_eq!(
+            ensure_default(None, None).unwrap(),
+            Value::Mapping(Default::default())
+        );
+        assert_eq!(
+            ensure_default(Some("seq"), None).unwrap(),
+            Value::Sequence(vec![])
+        );
+        assert_eq!(
+            ensure_default(Some("str"), Some("1")).unwrap(),
+            Value::String("1".to_string())
+        );
+        assert_eq!(
+            ensure_default(None, Some("1")).unwrap(),
+            Value::Number(Number::Int(1))
+        );
+    }
+
+    #[test]
+    fn test_ensure_default_type_mismatch() {
+        let err = ensure_default(Some("map"), Some("[1]")).unwrap_err();
+        assert!(matches!(err, Error::Type { .. }));
+    }
+
+    #[test]
+    fn test_ensure_keeps_existing_value() {
+        let base = Value::Mapping(indexmap! {
+            Value::String("a".to_string()) => Value::Null,
+        });
+        let result = ensure(
+            "a",
+            Value::Number(Number::Int(1)),
+            base.clone(),
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(result, base);
+    }
+
+    #[test]
+    fn test_ensure_creates_missing_path() {
+        let result = ensure(
+            "a.b",
+            Value::Sequence(vec![]),
+            Value::Null,
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            Value::Mapping(indexmap! {
+                Value::String("a".to_string()) => Value::Mapping(indexmap! {
+                    Value::String("b".to_string()) => Value::Sequence(vec![]),
+                }),
+            })
+        );
+    }
+
+    // -------------------------------------------------------------------------
+    // insert Tests
+    // -------------------------------------------------------------------------
+
+    fn seq(items: &[i64]) -> Value {
+        Value::Sequence(
+            items
+                .iter()
+                .map(|i| Value::Number(Number::Int(*i)))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_insert_positions() {
+        let base = Value::Mapping(indexmap! { Value::String("s".into()) => seq(&[1, 2]) });
+        let int = |i| Value::Number(Number::Int(i));
+        let at = |index| insert("s", index, int(9), base.clone(), &Options::default()).unwrap();
+        assert_eq!(
+            get_at_path(&at(None), Some("s"), &Options::default()).unwrap(),
+            &seq(&[1, 2, 9])
+        );
+        assert_eq!(
+            get_at_path(&at(Some("0")), Some("s"), &Options::default()).unwrap(),
+            &seq(&[9, 1, 2])
+        );
+        assert_eq!(
+            get_at_path(&at(Some("2")), Some("s"), &Options::default()).unwrap(),
+            &seq(&[1, 2, 9])
+        );
+        assert_eq!(
+            get_at_path(&at(Some("-1")), Some("s"), &Options::default()).unwrap(),
+            &seq(&[1, 2, 9])
+        );
+        assert_eq!(
+            get_at_path(&at(Some("-3")), Some("s"), &Options::default()).unwrap(),
+            &seq(&[9, 1, 2])
+        );
+        assert!(insert("s", Some("3"), int(9), base.clone(), &Options::default()).is_err());
+        assert!(insert("s", Some("-4"), int(9), base, &Options::default()).is_err());
+    }
+
+    #[test]
+    fn test_insert_creates_sequence() {
+        let result = insert(
+            "a.b",
+            None,
+            Value::Number(Number::Int(1)),
+            Value::Null,
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            get_at_path(&result, Some("a.b"), &Options::default()).unwrap(),
+            &seq(&[1])
+        );
+    }
+
+    #[test]
+    fn test_insert_at_root() {
+        let result = insert(
+            "",
+            Some("0"),
+            Value::Number(Number::Int(0)),
+            seq(&[1]),
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(result, seq(&[0, 1]));
+    }
+
+    #[test]
+    fn test_insert_into_scalar_fails() {
+        let base =
+            Value::Mapping(indexmap! { Value::String("s".into()) => Value::String("x".into()) });
+        let err = insert("s", None, Value::Null, base, &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("cannot add an element to a 'str'"));
+    }
+
+    // -------------------------------------------------------------------------
+    // rename_key / move_value Tests
+    // -------------------------------------------------------------------------
+
+    fn keys_of(value: &Value) -> Vec<String> {
+        match value {
+            Value::Mapping(map) => map
+                .keys()
+                .map(|k| k.as_str().unwrap().to_string())
+                .collect(),
+            _ => panic!("not a mapping"),
+        }
+    }
+
+    #[test]
+    fn test_rename_key_keeps_position() {
+        let base: Value = "a: 1\nb:\n  x: 2\n  y: 3\nc: 4\n".parse().unwrap();
+        let result = rename_key("b.x", "z", base.clone(), &Options::default()).unwrap();
+        assert_eq!(
+            keys_of(get_at_path(&result, Some("b"), &Options::default()).unwrap()),
+            ["z", "y"]
+        );
+        let result = rename_key("b", "d", base, &Options::default()).unwrap();
+        assert_eq!(keys_of(&result), ["a", "d", "c"]);
+    }
+
+    #[test]
+    fn test_rename_key_errors() {
+        let base: Value = "a: 1\nb: 2\nl: [1]\n".parse().unwrap();
+        assert!(rename_key("a", "b", base.clone(), &Options::default())
+            .unwrap_err()
+            .to_string()
+            .contains("key already exists"));
+        assert!(rename_key("l.0", "x", base.clone(), &Options::default())
+            .unwrap_err()
+            .to_string()
+            .contains("cannot rename an element of a 'sequence'"));
+        assert!(matches!(
+            rename_key("x", "y", base, &Options::default()),
+            Err(Error::Path { .. })
+        ));
+    }
+
+    #[test]
+    fn test_move_value() {
+        let base: Value = "a:\n  x: 1\nb: 2\nl: [1, 2]\n".parse().unwrap();
+        let result = move_value("a.x", "c.y", base.clone(), &Options::default()).unwrap();
+        assert_eq!(keys_of(&result), ["a", "b", "l", "c"]);
+        assert_eq!(
+            keys_of(get_at_path(&result, Some("a"), &Options::default()).unwrap()),
+            Vec::<String>::new()
+        );
+
+        let result = move_value("b", "z", base.clone(), &Options::default()).unwrap();
+        assert_eq!(keys_of(&result), ["a", "z", "l"]);
+
+        let result = move_value("l.-1", "b", base.clone(), &Options::default()).unwrap();
+        assert_eq!(keys_of(&result), ["a", "b", "l"]);
+        assert_eq!(
+            get_at_path(&result, Some("l"), &Options::default()).unwrap(),
+            &seq(&[1])
+        );
+    }
+
+    #[test]
+    fn test_move_value_overlapping_paths() {
+        let base: Value = "a:\n  x: 1\n".parse().unwrap();
+        for (src, dst) in [("a", "a.y"), ("a.x", "a"), ("a", "a")] {
+            assert!(move_value(src, dst, base.clone(), &Options::default())
+                .unwrap_err()
+                .to_string()
+                .contains("paths overlap"));
+        }
+    }
+
+    #[test]
+    fn test_copy_value() {
+        let base: Value = "a:\n  x: 1\nb: 2\n".parse().unwrap();
+        let result = copy_value("a", "c", base.clone(), &Options::default()).unwrap();
+        assert_eq!(keys_of(&result), ["a", "b", "c"]);
+        assert_eq!(
+            get_at_path(&result, Some("c"), &Options::default()).unwrap(),
+            get_at_path(&result, Some("a"), &Options::default()).unwrap()
+        );
+
+        let result = copy_value("a", "a.y", base.clone(), &Options::default()).unwrap();
+        assert_eq!(
+            keys_of(get_at_path(&result, Some("a"), &Options::default()).unwrap()),
+            ["x", "y"]
+        );
+        assert_eq!(
+            keys_of(get_at_path(&result, Some("a.y"), &Options::default()).unwrap()),
+            ["x"]
+        );
+
+        assert!(matches!(
+            copy_value("z", "c", base, &Options::default()),
+            Err(Error::Path { .. })
+        ));
+    }
+
+    #[test]
+    fn test_parse_assignment() {
+        assert_eq!(
+            parse_assignment("a.b=x=y").unwrap(),
+            ("a.b".to_string(), "x=y".to_string())
+        );
+        assert_eq!(
+            parse_assignment("a=").unwrap(),
+            ("a".to_string(), String::new())
+        );
+        assert_eq!(
+            parse_assignment(r"users.[name=bob].a\=b=x").unwrap(),
+            (r"users.[name=bob].a\=b".to_string(), "x".to_string())
+        );
+        assert!(parse_assignment("a")
+            .unwrap_err()
+            .to_string()
+            .contains("expected 'PATH=VALUE'"));
+    }
 }
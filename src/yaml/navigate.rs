@@ -0,0 +1,584 @@
+//! Path navigation shared by queries and mutations.
+//!
+//! Paths are walked the same way whatever the nodes: document nodes
+//! (`ValueRef`, zero-copy), owned values and mutable values all implement
+//! [`PathNode`]. Mutations act on the node designated by the last element
+//! of a path from its parent, through a [`PathVisitor`] setting or deleting
+//! it, so that a new kind of path element only needs implementing here.
+//!
+//! Path elements designate mapping keys as strings, and also as integer or
+//! boolean keys unless [`Options::string_keys_only`] is set, or as keys
+//! typed with `:(VALUE)`.
+
+use super::error::Error;
+use super::path::{resolve_index, split_path};
+use super::query::{plain_scalar_type_name, value_ref_schema_type_name, Schema};
+use super::serialize::serialize_raw;
+use super::style::key_text;
+use super::Options;
+use fyaml::{Document, Number, Value, ValueRef};
+use indexmap::IndexMap;
+
+// =============================================================================
+// Key Coercion
+// =============================================================================
+
+/// Non-string keys a path element also designates: the integer key 8080
+/// for `8080`, the boolean key true for `true`.
+fn coerced_keys(part: &str, opts: &Options) -> Vec<Value> {
+    if opts.string_keys_only {
+        return Vec::new();
+    }
+    let mut keys = Vec::new();
+    if let Ok(n) = part.parse::<i64>() {
+        keys.push(Value::Number(Number::Int(n)));
+    }
+    if let Ok(b) = part.parse::<bool>() {
+        keys.push(Value::Bool(b));
+    }
+    keys
+}
+
+/// Key explicitly typed in a path element written `:(VALUE)`, VALUE being
+/// a null, a boolean or an integer as the YAML core schema reads them, as
+/// in `:(~)`, `:(True)` or `:(8080)`, or a flow mapping or sequence, as in
+/// `:([a, b])` (see `key_text`).
+///
+/// Other elements, including `:(VALUE)` with any other VALUE, designate
+/// keys as usual.
+fn typed_key(part: &str) -> Option<Value> {
+    let text = part.strip_prefix(":(")?.strip_suffix(')')?;
+    if text.starts_with(['[', '{']) {
+        return text
+            .parse::<Value>()
+            .ok()
+            .filter(|key| matches!(key, Value::Mapping(_) | Value::Sequence(_)));
+    }
+    match plain_scalar_type_name(text, Schema::Core) {
+        "NoneType" => Some(Value::Null),
+        "bool" => Some(Value::Bool(text.eq_ignore_ascii_case("true"))),
+        "int" => text.parse().ok().map(|n| Value::Number(Number::Int(n))),
+        _ => None,
+    }
+}
+
+/// Whether a non-string mapping key is the given null, boolean, integer,
+/// mapping or sequence.
+fn key_ref_is(k: &ValueRef<'_>, key: &Value) -> bool {
+    match (value_ref_schema_type_name(k, Schema::Core), key) {
+        ("struct" | "sequence", _) => Value::from_node_ref(k.as_node()).is_ok_and(|k| k == *key),
+        ("NoneType", Value::Null) => true,
+        ("bool", Value::Bool(b)) => k.as_bool() == Some(*b),
+        ("int", Value::Number(Number::Int(n))) => k.as_i64() == Some(*n),
+        _ => false,
+    }
+}
+
+/// Entry of a mapping whose key is `part` read as YAML, as libfyaml finds
+/// keys in its own paths: a mapping or sequence key by value, a scalar key
+/// by text, so that `'8080'` finds the integer key 8080.
+fn yaml_key_entry<'a>(map: ValueRef<'a>, part: &str) -> Option<(ValueRef<'a>, ValueRef<'a>)> {
+    let doc = Document::parse_str(part).ok()?;
+    let node = doc.root()?;
+    if node.is_scalar() {
+        let text = node.scalar_str().ok()?;
+        return map
+            .map_iter()
+            .find(|(k, _)| k.is_scalar() && k.as_str() == Some(text));
+    }
+    let key = Value::from_node_ref(node).ok()?;
+    map.map_iter().find(|(k, _)| key_ref_is(k, &key))
+}
+
+/// Find the entry of a mapping designated by a path element (zero-copy).
+fn entry_in_map_ref<'a>(
+    map: ValueRef<'a>,
+    part: &str,
+    opts: &Options,
+) -> Option<(ValueRef<'a>, ValueRef<'a>)> {
+    if let Some(key) = typed_key(part) {
+        return map.map_iter().find(|(k, _)| key_ref_is(k, &key));
+    }
+    let string_key = map.map_iter().find(|(k, _)| {
+        value_ref_schema_type_name(k, Schema::Core) == "str" && k.as_str() == Some(part)
+    });
+    if opts.string_keys_only {
+        return string_key;
+    }
+    // Looked up as text first: read as YAML, `[a=b]` or `*x` aren't strings
+    string_key
+        .or_else(|| yaml_key_entry(map, part))
+        .or_else(|| {
+            let coerced = coerced_keys(part, opts);
+            map.map_iter().find(|(k, _)| {
+                (part.is_empty() && k.is_null()) || coerced.iter().any(|key| key_ref_is(k, key))
+            })
+        })
+}
+
+/// Key of a mapping designated by a path element, if the mapping has one.
+pub fn find_key<'a>(
+    map: &'a IndexMap<Value, Value>,
+    part: &str,
+    opts: &Options,
+) -> Option<&'a Value> {
+    // Compared rather than hashed: the integer 8080 is `Int` or `UInt`
+    // depending on where it comes from, and they hash differently
+    let find = |key: Value| {
+        map.keys()
+            .find(|k| !matches!(k, Value::Number(Number::Float(_))) && **k == key)
+    };
+    if let Some(key) = typed_key(part) {
+        return find(key);
+    }
+    let key = Value::String(part.to_string());
+    map.get_key_value(&key)
+        .map(|(k, _)| k)
+        .or_else(|| part.is_empty().then(|| find(Value::Null)).flatten())
+        .or_else(|| coerced_keys(part, opts).into_iter().find_map(find))
+}
+
+/// Key of the entry a path element adds to a mapping: the key typed with
+/// `:(VALUE)`, or else a string.
+pub fn new_key(part: &str) -> Value {
+    typed_key(part).unwrap_or_else(|| Value::String(part.to_string()))
+}
+
+/// Text YAML parsers compare a scalar key by, `None` for collections.
+fn scalar_key_text(key: &Value) -> Option<String> {
+    match key {
+        Value::Tagged(t) => scalar_key_text(&t.value),
+        Value::Mapping(_) | Value::Sequence(_) => None,
+        other => Some(serialize_raw(other)),
+    }
+}
+
+/// Create a path error for a new key of the same text as an existing one,
+/// as the string `'8080'` next to the integer 8080, that YAML parsers
+/// reject as a duplicate.
+fn path_error_key_clash(full_path: &str, part: &str, existing: &str) -> Error {
+    Error::path_error_at(
+        full_path,
+        format!(
+            "invalid path '{}', key '{}' would duplicate the existing key {}.",
+            full_path, part, existing
+        ),
+    )
+}
+
+/// Check that `key`, designated by `part`, can be added to a mapping.
+///
+/// # Errors
+///
+/// Returns a path error if the mapping holds a key of the same text.
+pub fn check_new_key(
+    map: &IndexMap<Value, Value>,
+    key: &Value,
+    part: &str,
+    full_path: &str,
+) -> Result<(), Error> {
+    let Some(text) = scalar_key_text(key) else {
+        return Ok(());
+    };
+    match map
+        .keys()
+        .find(|k| scalar_key_text(k).as_ref() == Some(&text))
+    {
+        Some(existing) => Err(path_error_key_clash(full_path, part, &key_text(existing)?)),
+        None => Ok(()),
+    }
+}
+
+/// Like [`check_new_key`], for a mapping of a document.
+///
+/// # Errors
+///
+/// Returns a path error if the mapping holds a key of the same text.
+pub fn check_new_key_ref(
+    map: ValueRef<'_>,
+    key: &Value,
+    part: &str,
+    full_path: &str,
+) -> Result<(), Error> {
+    let Some(text) = scalar_key_text(key) else {
+        return Ok(());
+    };
+    match map
+        .map_iter()
+        .find(|(k, _)| k.is_scalar() && k.as_str() == Some(text.as_str()))
+    {
+        Some((existing, _)) => {
+            let existing = key_text(&Value::from_node_ref(existing.as_node())?)?;
+            Err(path_error_key_clash(full_path, part, &existing))
+        }
+        None => Ok(()),
+    }
+}
+
+// =============================================================================
+// Walking
+// =============================================================================
+
+/// Create a path error for a key missing from a mapping.
+pub(super) fn path_error_missing_key(full_path: &str, part: &str) -> Error {
+    Error::path_error_at(
+        full_path,
+        format!(
+            "invalid path '{}', missing key '{}' in struct.",
+            full_path, part
+        ),
+    )
+}
+
+/// Create a path error for attempting to traverse a scalar value.
+fn path_error_cannot_traverse(full_path: &str, part: &str) -> Error {
+    Error::path_error_at(
+        full_path,
+        format!(
+            "invalid path '{}', cannot traverse scalar at '{}'.",
+            full_path, part
+        ),
+    )
+}
+
+/// Value of a tagged value, or the value itself.
+fn untagged_mut(value: &mut Value) -> &mut Value {
+    match value {
+        Value::Tagged(t) => &mut t.value,
+        other => other,
+    }
+}
+
+/// Node a path can be walked through.
+///
+/// Implemented for `ValueRef` (zero-copy), `&Value` and `&mut Value`, so
+/// that queries and mutations designate the same nodes with the same paths.
+/// Tagged collections are walked through their tag.
+pub trait PathNode: Sized {
+    /// Child designated by a path element: the value of a mapping key, or a
+    /// sequence element. `None` if the mapping has no such key.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on scalars and on invalid sequence indexes.
+    fn child(self, part: &str, full_path: &str, opts: &Options) -> Result<Option<Self>, Error>;
+}
+
+impl<'a> PathNode for ValueRef<'a> {
+    fn child(self, part: &str, full_path: &str, opts: &Options) -> Result<Option<Self>, Error> {
+        if self.is_mapping() {
+            Ok(entry_in_map_ref(self, part, opts).map(|(_, v)| v))
+        } else if self.is_sequence() {
+            let len = self.seq_len().unwrap_or(0);
+            let idx = resolve_index(part, len, full_path)?;
+            Ok(self.index(idx as i32))
+        } else {
+            Err(path_error_cannot_traverse(full_path, part))
+        }
+    }
+}
+
+impl PathNode for &Value {
+    fn child(self, part: &str, full_path: &str, opts: &Options) -> Result<Option<Self>, Error> {
+        let value = match self {
+            Value::Tagged(t) => &t.value,
+            other => other,
+        };
+        match value {
+            Value::Mapping(map) => Ok(find_key(map, part, opts).map(|key| &map[key])),
+            Value::Sequence(seq) => Ok(Some(&seq[resolve_index(part, seq.len(), full_path)?])),
+            _ => Err(path_error_cannot_traverse(full_path, part)),
+        }
+    }
+}
+
+impl PathNode for &mut Value {
+    fn child(self, part: &str, full_path: &str, opts: &Options) -> Result<Option<Self>, Error> {
+        match untagged_mut(self) {
+            Value::Mapping(map) => {
+                let index = find_key(map, part, opts).and_then(|key| map.get_index_of(key));
+                Ok(index.and_then(|i| map.get_index_mut(i)).map(|(_, v)| v))
+            }
+            Value::Sequence(seq) => {
+                let idx = resolve_index(part, seq.len(), full_path)?;
+                Ok(Some(&mut seq[idx]))
+            }
+            _ => Err(path_error_cannot_traverse(full_path, part)),
+        }
+    }
+}
+
+/// Walk a path from `root`, its filter elements being already resolved.
+///
+/// # Errors
+///
+/// Returns an error if an element designates nothing or a child of a
+/// scalar.
+pub fn walk<N: PathNode>(root: N, path: &str, opts: &Options) -> Result<N, Error> {
+    let mut current = root;
+    for part in &split_path(path) {
+        current = current
+            .child(part, path, opts)?
+            .ok_or_else(|| path_error_missing_key(path, part))?;
+    }
+    Ok(current)
+}
+
+/// Node of a document a path element designates from its parent.
+pub enum Step<'a> {
+    /// Entry of a mapping, by its key
+    Key(ValueRef<'a>),
+    /// Element of a sequence, by its index
+    Index(usize),
+}
+
+/// Walk the elements of a path from the `root` of a document as far as
+/// they designate nodes, its filter elements being already resolved.
+///
+/// Returns the steps taken and the node reached: the designated node, or a
+/// mapping missing the key of the next element, or a scalar.
+///
+/// # Errors
+///
+/// Returns an error on invalid sequence indexes.
+pub fn walk_steps<'a>(
+    root: ValueRef<'a>,
+    parts: &[String],
+    full_path: &str,
+    opts: &Options,
+) -> Result<(Vec<Step<'a>>, ValueRef<'a>), Error> {
+    let mut steps = Vec::with_capacity(parts.len());
+    let mut current = root;
+    for part in parts {
+        if current.is_mapping() {
+            let Some((key, value)) = entry_in_map_ref(current, part, opts) else {
+                break;
+            };
+            steps.push(Step::Key(key));
+            current = value;
+        } else if current.is_sequence() {
+            let len = current.seq_len().unwrap_or(0);
+            let idx = resolve_index(part, len, full_path)?;
+            steps.push(Step::Index(idx));
+            current = current
+                .index(idx as i32)
+                .ok_or_else(|| path_error_missing_key(full_path, part))?;
+        } else {
+            break;
+        }
+    }
+    Ok((steps, current))
+}
+
+/// Like [`walk_steps`], for a path that must designate a node.
+///
+/// # Errors
+///
+/// Returns an error if an element designates nothing or a child of a
+/// scalar.
+pub fn walk_steps_to<'a>(
+    root: ValueRef<'a>,
+    parts: &[String],
+    full_path: &str,
+    opts: &Options,
+) -> Result<(Vec<Step<'a>>, ValueRef<'a>), Error> {
+    let (steps, node) = walk_steps(root, parts, full_path, opts)?;
+    match parts.get(steps.len()) {
+        None => Ok((steps, node)),
+        Some(part) if node.is_mapping() => Err(path_error_missing_key(full_path, part)),
+        Some(part) => Err(path_error_cannot_traverse(full_path, part)),
+    }
+}
+
+// =============================================================================
+// Visiting
+// =============================================================================
+
+/// What a mutation does with the node designated by the last element of a
+/// path, from its parent.
+pub trait PathVisitor {
+    type Output;
+
+    /// Whether keys missing on the way are added, as empty mappings.
+    const CREATES_PARENTS: bool = false;
+
+    /// Act on the entry of `map` designated by `part`, whose key is `key`
+    /// if the mapping has one.
+    fn visit_mapping(
+        self,
+        map: &mut IndexMap<Value, Value>,
+        key: Option<Value>,
+        part: &str,
+        full_path: &str,
+    ) -> Result<Self::Output, Error>;
+
+    /// Act on the element of `seq` at `index`.
+    fn visit_sequence(self, seq: &mut Vec<Value>, index: usize) -> Result<Self::Output, Error>;
+
+    /// Error for a last element designating a child of a scalar.
+    fn scalar_error(&self, part: &str, full_path: &str) -> Error;
+}
+
+/// Walk a path from `root` to the parent of its last element, and let
+/// `visitor` act there. Filter elements must be already resolved.
+///
+/// # Errors
+///
+/// Returns an error if the path is empty, if an element before the last
+/// one designates nothing or a child of a scalar, or if the visitor fails.
+pub fn visit_path<V: PathVisitor>(
+    root: &mut Value,
+    path: &str,
+    visitor: V,
+    opts: &Options,
+) -> Result<V::Output, Error> {
+    let parts = split_path(path);
+    let Some((last, parents)) = parts.split_last() else {
+        return Err(Error::path_error("Empty path".to_string()));
+    };
+
+    let mut current = root;
+    for part in parents {
+        if V::CREATES_PARENTS {
+            if let Value::Mapping(map) = untagged_mut(current) {
+                if find_key(map, part, opts).is_none() {
+                    let key = new_key(part);
+                    check_new_key(map, &key, part, path)?;
+                    map.insert(key, Value::Mapping(Default::default()));
+                }
+            }
+        }
+        current = current
+            .child(part, path, opts)?
+            .ok_or_else(|| path_error_missing_key(path, part))?;
+    }
+
+    match untagged_mut(current) {
+        Value::Mapping(map) => {
+            let key = find_key(map, last, opts).cloned();
+            visitor.visit_mapping(map, key, last, path)
+        }
+        Value::Sequence(seq) => {
+            let index = resolve_index(last, seq.len(), path)?;
+            visitor.visit_sequence(seq, index)
+        }
+        _ => Err(visitor.scalar_error(last, path)),
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::serialize_raw;
+
+    fn doc() -> Value {
+        "ports:\n  8080: web\n  '8081': api\nlist: [a, b]\n~: none\n"
+            .parse()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_walk_owned_and_mutable() {
+        let opts = Options::default();
+        let mut value = doc();
+        assert_eq!(
+            serialize_raw(walk(&value, "ports.8080", &opts).unwrap()),
+            "web"
+        );
+        assert_eq!(
+            serialize_raw(walk(&value, "ports.8081", &opts).unwrap()),
+            "api"
+        );
+        assert_eq!(serialize_raw(walk(&value, "list.-1", &opts).unwrap()), "b");
+        assert_eq!(serialize_raw(walk(&value, "", &opts).unwrap()), "none");
+
+        *walk(&mut value, "ports.8080", &opts).unwrap() = Value::String("www".to_string());
+        assert_eq!(
+            serialize_raw(walk(&value, "ports.:(8080)", &opts).unwrap()),
+            "www"
+        );
+
+        let err = walk(&value, "ports.8082", &opts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid path 'ports.8082', missing key '8082' in struct."
+        );
+        let err = walk(&value, "list.0.x", &opts).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid path 'list.0.x', cannot traverse scalar at 'x'."
+        );
+    }
+
+    #[test]
+    fn test_walk_steps() {
+        let opts = Options::default();
+        let doc = Document::parse_str("ports:\n  8080: web\nlist: [a, b]\n").unwrap();
+        let root = doc.root_value().unwrap();
+
+        let path = "ports.:(8080)";
+        let (steps, node) = walk_steps(root, &split_path(path), path, &opts).unwrap();
+        assert_eq!(steps.len(), 2);
+        assert_eq!(node.as_str(), Some("web"));
+
+        let path = "list.-1";
+        let (steps, node) = walk_steps(root, &split_path(path), path, &opts).unwrap();
+        assert!(matches!(steps[..], [Step::Key(_), Step::Index(1)]));
+        assert_eq!(node.as_str(), Some("b"));
+
+        let path = "ports.8081.x";
+        let (steps, node) = walk_steps(root, &split_path(path), path, &opts).unwrap();
+        assert_eq!(steps.len(), 1);
+        assert!(node.is_mapping());
+        let err = walk_steps_to(root, &split_path(path), path, &opts).err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "invalid path 'ports.8081.x', missing key '8081' in struct."
+        );
+
+        let path = "list.2";
+        let err = walk_steps(root, &split_path(path), path, &opts).err();
+        assert_eq!(
+            err.unwrap().to_string(),
+            "invalid path 'list.2', index 2 is out of range (2 elements in sequence)."
+        );
+    }
+
+    #[test]
+    fn test_find_and_new_key() {
+        let opts = Options::default();
+        let value = doc();
+        let Value::Mapping(map) = walk(&value, "ports", &opts).unwrap() else {
+            panic!("expected a mapping");
+        };
+        assert_eq!(
+            find_key(map, "8080", &opts),
+            Some(&Value::Number(Number::Int(8080)))
+        );
+        assert_eq!(find_key(map, "8082", &opts), None);
+        assert_eq!(new_key(":(8082)"), Value::Number(Number::Int(8082)));
+        assert_eq!(new_key("8082"), Value::String("8082".to_string()));
+    }
+
+    #[test]
+    fn test_string_keys_only() {
+        let opts = Options {
+            string_keys_only: true,
+            ..Options::default()
+        };
+        let value = doc();
+        assert!(walk(&value, "ports.8080", &opts).is_err());
+        assert_eq!(
+            serialize_raw(walk(&value, "ports.8081", &opts).unwrap()),
+            "api"
+        );
+        assert_eq!(
+            serialize_raw(walk(&value, "ports.:(8080)", &opts).unwrap()),
+            "web"
+        );
+    }
+}
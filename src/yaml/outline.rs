@@ -0,0 +1,157 @@
+//! Outline of a document, as browsed by the `explore` action.
+//!
+//! Every node below the root becomes an entry, in document order, along
+//! with its depth and the extent of its subtree, so that a tree view can
+//! skip collapsed subtrees without walking the document again.
+
+use super::error::Error;
+use super::path::escape_path_element;
+use super::query::{get_at_path, type_error_seq_or_struct};
+use super::serialize::serialize_raw;
+use super::{InnerValue, Options};
+use fyaml::Value;
+
+/// Entry of an outline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutlineNode {
+    /// Full path of the node
+    pub path: String,
+    /// Key in the parent mapping, or index in the parent sequence
+    pub label: String,
+    /// First line of a scalar, or the size of a mapping (`{N}`) or sequence (`[N]`)
+    pub summary: String,
+    /// Whether the node is a mapping or a sequence
+    pub container: bool,
+    pub depth: usize,
+    pub parent: Option<usize>,
+    /// Index following the last entry of the subtree
+    pub end: usize,
+}
+
+/// Outline of the mapping or sequence found at `path`.
+///
+/// # Errors
+///
+/// Returns an error if the path doesn't exist or designates a scalar.
+pub fn outline(
+    path: Option<&str>,
+    value: &Value,
+    opts: &Options,
+) -> Result<Vec<OutlineNode>, Error> {
+    let root = get_at_path(value, path, opts)?;
+    if !matches!(root.inner(), Value::Mapping(_) | Value::Sequence(_)) {
+        return Err(type_error_seq_or_struct("explore", root));
+    }
+    let mut nodes = Vec::new();
+    push_children(&mut nodes, root, path.unwrap_or(""), 0, None);
+    Ok(nodes)
+}
+
+fn children(value: &Value) -> Vec<(String, &Value)> {
+    match value.inner() {
+        Value::Mapping(map) => map.iter().map(|(k, v)| (serialize_raw(k), v)).collect(),
+        Value::Sequence(seq) => seq
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (i.to_string(), v))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn push_children(
+    nodes: &mut Vec<OutlineNode>,
+    value: &Value,
+    path: &str,
+    depth: usize,
+    parent: Option<usize>,
+) {
+    for (label, child) in children(value) {
+        let child_path = match escape_path_element(&label) {
+            element if path.is_empty() => element,
+            element => format!("{}.{}", path, element),
+        };
+        let (summary, container) = match child.inner() {
+            Value::Mapping(map) => (format!("{{{}}}", map.len()), true),
+            Value::Sequence(seq) => (format!("[{}]", seq.len()), true),
+            scalar => {
+                let raw = serialize_raw(scalar);
+                match raw.split_once('\n') {
+                    Some((first, _)) => (format!("{}…", first), false),
+                    None => (raw, false),
+                }
+            }
+        };
+        let index = nodes.len();
+        nodes.push(OutlineNode {
+            path: child_path,
+            label,
+            summary,
+            container,
+            depth,
+            parent,
+            end: index + 1,
+        });
+        if container {
+            let path = nodes[index].path.clone();
+            push_children(nodes, child, &path, depth + 1, Some(index));
+            nodes[index].end = nodes.len();
+        }
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(nodes: &[OutlineNode]) -> Vec<(&str, &str, usize, usize)> {
+        nodes
+            .iter()
+            .map(|n| (n.path.as_str(), n.summary.as_str(), n.depth, n.end))
+            .collect()
+    }
+
+    #[test]
+    fn test_outline() {
+        let value: Value = "a: {b: 1, c.d: [x, y]}\ne: \"two\\nlines\"\n"
+            .parse()
+            .unwrap();
+        let nodes = outline(None, &value, &Options::default()).unwrap();
+        assert_eq!(
+            paths(&nodes),
+            vec![
+                ("a", "{2}", 0, 5),
+                ("a.b", "1", 1, 2),
+                ("a.c\\.d", "[2]", 1, 5),
+                ("a.c\\.d.0", "x", 2, 4),
+                ("a.c\\.d.1", "y", 2, 5),
+                ("e", "two…", 0, 6),
+            ]
+        );
+        assert_eq!(nodes[2].label, "c.d");
+        assert_eq!(nodes[3].parent, Some(2));
+        assert!(nodes[2].container && !nodes[3].container);
+    }
+
+    #[test]
+    fn test_outline_subtree() {
+        let value: Value = "a: {b: [1]}\n".parse().unwrap();
+        let nodes = outline(Some("a"), &value, &Options::default()).unwrap();
+        assert_eq!(
+            paths(&nodes),
+            vec![("a.b", "[1]", 0, 2), ("a.b.0", "1", 1, 2)]
+        );
+    }
+
+    #[test]
+    fn test_outline_scalar_errors() {
+        let value: Value = "a: 1\n".parse().unwrap();
+        let err = outline(Some("a"), &value, &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("explore does not support"));
+        assert!(outline(Some("b"), &value, &Options::default()).is_err());
+    }
+}
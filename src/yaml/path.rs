@@ -1,38 +1,118 @@
 //! Path handling for YAML navigation.
 //!
-//! Provides utilities for parsing dot-notation paths and resolving indices.
+//! Provides utilities for parsing and building dot-notation paths and
+//! resolving indices.
 
 use super::error::Error;
 
 /// Split a dot-notation path into its components.
 ///
 /// Handles escape sequences: `\.` for literal dots, `\\` for literal backslashes.
-/// For example, `a.b\.c.d` becomes `["a", "b.c", "d"]`.
+/// For example, `a.b\.c.d` becomes `["a", "b.c", "d"]`. Dots inside an
+/// element starting with `[` (a filter, like `[?(@.a > 1)]`) don't split it,
+/// nor do dots inside the parentheses of a typed key (like `:({x: 1.5})`).
+/// A `[` that is never closed is part of a key like any other character.
 #[must_use]
 pub fn split_path(path: &str) -> Vec<String> {
+    split_path_literal(path)
+        .into_iter()
+        .map(|(element, _)| element)
+        .collect()
+}
+
+/// Split a dot-notation path as [`split_path`] does, telling along with
+/// each element whether its first character is escaped, as in `\[a=b]`:
+/// such an element is always a key, never a filter.
+pub(super) fn split_path_literal(path: &str) -> Vec<(String, bool)> {
+    split_from(path, String::new())
+}
+
+/// Split `path`, the first element of which starts with `element`.
+fn split_from(path: &str, mut element: String) -> Vec<(String, bool)> {
     let mut elements = Vec::new();
     let mut escaped = false;
-    let mut element = String::new();
+    let mut literal = false;
+    // Position of the `[` opening the current filter
+    let mut filter_start = None;
+    // Parentheses left open in a typed key
+    let mut typed_depth = 0usize;
 
-    for c in path.chars() {
+    for (i, c) in path.char_indices() {
         if escaped {
             escaped = false;
+            literal |= element.is_empty();
             element.push(c);
             continue;
         }
         match c {
             '\\' => escaped = true,
-            '.' => {
-                elements.push(element.clone());
-                element.clear();
+            '[' if element.is_empty() => {
+                filter_start = Some(i);
+                element.push(c);
+            }
+            ']' if filter_start.is_some() => {
+                filter_start = None;
+                element.push(c);
+            }
+            '(' if typed_depth > 0 || element == ":" => {
+                typed_depth += 1;
+                element.push(c);
+            }
+            ')' if typed_depth > 0 => {
+                typed_depth -= 1;
+                element.push(c);
+            }
+            '.' if filter_start.is_none() && typed_depth == 0 => {
+                elements.push((std::mem::take(&mut element), literal));
+                literal = false;
             }
             _ => element.push(c),
         }
     }
-    elements.push(element);
+    if let Some(start) = filter_start {
+        // Not a filter, so the dots after the `[` split the path
+        elements.extend(split_from(&path[start + 1..], "[".to_string()));
+        return elements;
+    }
+    elements.push((element, literal));
     elements
 }
 
+/// Split a `PATH=VALUE` assignment on its first `=` that is neither escaped
+/// (`\=`) nor inside a filter, as in `users.[name=bob].age=30`.
+///
+/// The path keeps its escapes. As with [`split_path`], a `[` that is never
+/// closed doesn't start a filter.
+pub(super) fn split_assignment(assignment: &str) -> Option<(&str, &str)> {
+    let mut escaped = false;
+    let mut at_element_start = true;
+    let mut in_filter = false;
+    // First `=` that isn't escaped, for an unclosed filter
+    let mut first_equal = None;
+
+    for (i, c) in assignment.char_indices() {
+        if escaped {
+            escaped = false;
+            at_element_start = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '[' if at_element_start => in_filter = true,
+            ']' => in_filter = false,
+            '=' if !in_filter => return Some((&assignment[..i], &assignment[i + 1..])),
+            '=' => {
+                first_equal.get_or_insert(i);
+            }
+            _ => {}
+        }
+        at_element_start = c == '.';
+    }
+    first_equal
+        .filter(|_| in_filter)
+        .map(|i| (&assignment[..i], &assignment[i + 1..]))
+}
+
 /// Resolve a string index to an actual index in a sequence.
 ///
 /// Handles:
@@ -46,19 +126,25 @@ pub fn split_path(path: &str) -> Vec<String> {
 /// - The index is out of range for the sequence length
 pub fn resolve_index(part: &str, len: usize, full_path: &str) -> Result<usize, Error> {
     let idx: i64 = part.parse().map_err(|_| {
-        Error::Path(format!(
-            "invalid path '{}', non-integer index '{}' provided on a sequence.",
-            full_path, part
-        ))
+        Error::path_error_at(
+            full_path,
+            format!(
+                "invalid path '{}', non-integer index '{}' provided on a sequence.",
+                full_path, part
+            ),
+        )
     })?;
 
     let resolved = if idx < 0 {
         let abs_idx = (-idx) as usize;
         if abs_idx > len {
-            return Err(Error::Path(format!(
-                "invalid path '{}', index {} is out of range ({} elements in sequence).",
-                full_path, idx, len
-            )));
+            return Err(Error::path_error_at(
+                full_path,
+                format!(
+                    "invalid path '{}', index {} is out of range ({} elements in sequence).",
+                    full_path, idx, len
+                ),
+            ));
         }
         len - abs_idx
     } else {
@@ -66,15 +152,102 @@ pub fn resolve_index(part: &str, len: usize, full_path: &str) -> Result<usize, E
     };
 
     if resolved >= len {
-        return Err(Error::Path(format!(
-            "invalid path '{}', index {} is out of range ({} elements in sequence).",
-            full_path, idx, len
-        )));
+        return Err(Error::path_error_at(
+            full_path,
+            format!(
+                "invalid path '{}', index {} is out of range ({} elements in sequence).",
+                full_path, idx, len
+            ),
+        ));
     }
 
     Ok(resolved)
 }
 
+/// Escape a single key so it can be used as one element of a dot-notation path.
+///
+/// This is the inverse of [`split_path`] for one element: `.` becomes `\.`
+/// and `\` becomes `\\`. A leading `[` becomes `\[`, so that the key isn't
+/// read as a filter, and `=` becomes `\=`, so that the path can be the
+/// left side of a `PATH=VALUE` assignment. A key that is just `*` becomes
+/// `\*`, not to be read as a wildcard.
+#[must_use]
+pub fn escape_path_element(element: &str) -> String {
+    let mut escaped = String::with_capacity(element.len());
+    for (i, c) in element.chars().enumerate() {
+        if c == '\\' || c == '.' || c == '=' || (i == 0 && c == '[') || element == "*" {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Join path elements into a dot-notation path, escaping each of them.
+#[must_use]
+pub fn join_elements<S: AsRef<str>>(elements: &[S]) -> String {
+    elements
+        .iter()
+        .map(|e| escape_path_element(e.as_ref()))
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Append raw (unescaped) elements to an existing dot-notation path.
+#[must_use]
+pub fn join_path<S: AsRef<str>>(path: &str, elements: &[S]) -> String {
+    let mut parts = split_path(path);
+    parts.extend(elements.iter().map(|e| e.as_ref().to_string()));
+    join_elements(&parts)
+}
+
+/// Get the path of the parent of the node designated by `path`.
+///
+/// # Errors
+///
+/// Returns an error if the path has a single element, as the parent is then
+/// the document root which has no path representation.
+pub fn parent_path(path: &str) -> Result<String, Error> {
+    let mut parts = split_path(path);
+    if parts.len() < 2 {
+        return Err(Error::path_error_at(
+            path,
+            format!(
+                "invalid path '{}', top-level path has no parent path.",
+                path
+            ),
+        ));
+    }
+    parts.pop();
+    Ok(join_elements(&parts))
+}
+
+/// Get the path of the sibling named `name` of the node designated by `path`.
+#[must_use]
+pub fn sibling_path(path: &str, name: &str) -> String {
+    let mut parts = split_path(path);
+    parts.pop();
+    parts.push(name.to_string());
+    join_elements(&parts)
+}
+
+/// Express `full` relative to `base`, both being dot-notation paths.
+///
+/// # Errors
+///
+/// Returns an error if `full` is not strictly below `base`.
+pub fn relative_path(base: &str, full: &str) -> Result<String, Error> {
+    let base_parts = split_path(base);
+    let full_parts = split_path(full);
+    if full_parts.len() <= base_parts.len() || !full_parts.starts_with(&base_parts) {
+        return Err(Error::path_error_at(
+            full,
+            format!("invalid path '{}', not a sub-path of '{}'.", full, base),
+        ));
+    }
+    Ok(join_elements(&full_parts[base_parts.len()..]))
+}
+
 // =============================================================================
 // Unit Tests
 // =============================================================================
@@ -121,6 +294,24 @@ mod tests {
         assert_eq!(split_path(r"a\\\.b.c"), vec!["a\\.b", "c"]);
     }
 
+    #[test]
+    fn test_split_path_keeps_dots_in_filters() {
+        assert_eq!(
+            split_path("users.[?(@.a.b > 1)].name"),
+            vec!["users", "[?(@.a.b > 1)]", "name"]
+        );
+        assert_eq!(split_path("a[b.c]"), vec!["a[b", "c]"]);
+    }
+
+    #[test]
+    fn test_split_path_keeps_dots_in_typed_keys() {
+        assert_eq!(
+            split_path("a.:({x: (1.5)}).b"),
+            vec!["a", ":({x: (1.5)})", "b"]
+        );
+        assert_eq!(split_path("a(b.c)"), vec!["a(b", "c)"]);
+    }
+
     #[test]
     fn test_split_path_empty_string() {
         // Empty string is a single empty element (valid key in YAML)
@@ -158,6 +349,43 @@ mod tests {
         assert_eq!(split_path(r"a\"), vec!["a"]);
     }
 
+    #[test]
+    fn test_split_path_unclosed_filter() {
+        assert_eq!(split_path("a.[b.c"), vec!["a", "[b", "c"]);
+        assert_eq!(split_path("[x=1].[y.z"), vec!["[x=1]", "[y", "z"]);
+    }
+
+    #[test]
+    fn test_split_path_literal() {
+        assert_eq!(
+            split_path_literal(r"a.\[b=c].[b=c]"),
+            vec![
+                ("a".to_string(), false),
+                ("[b=c]".to_string(), true),
+                ("[b=c]".to_string(), false),
+            ]
+        );
+        // Dots split an escaped `[` element
+        assert_eq!(split_path(r"\[a.b]"), vec!["[a", "b]"]);
+    }
+
+    // =========================================================================
+    // split_assignment() tests
+    // =========================================================================
+
+    #[test]
+    fn test_split_assignment() {
+        assert_eq!(split_assignment("a.b=x=y"), Some(("a.b", "x=y")));
+        assert_eq!(
+            split_assignment("users.[name=bob].age=30"),
+            Some(("users.[name=bob].age", "30"))
+        );
+        assert_eq!(split_assignment(r"a\=b.c=1"), Some((r"a\=b.c", "1")));
+        assert_eq!(split_assignment("a[b=1"), Some(("a[b", "1")));
+        assert_eq!(split_assignment("[a=1"), Some(("[a", "1")));
+        assert_eq!(split_assignment(r"a\=b"), None);
+    }
+
     // =========================================================================
     // resolve_index() tests
     // =========================================================================
@@ -183,7 +411,7 @@ mod tests {
     fn test_resolve_index_positive_out_of_range() {
         let err = resolve_index("3", 3, "items.3").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("index 3 is out of range"));
                 assert!(msg.contains("3 elements"));
             }
@@ -195,7 +423,7 @@ mod tests {
     fn test_resolve_index_negative_out_of_range() {
         let err = resolve_index("-4", 3, "items.-4").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("index -4 is out of range"));
                 assert!(msg.contains("3 elements"));
             }
@@ -207,7 +435,7 @@ mod tests {
     fn test_resolve_index_non_integer() {
         let err = resolve_index("foo", 3, "items.foo").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("non-integer index 'foo'"));
             }
             _ => panic!("Expected Error::Path"),
@@ -218,7 +446,7 @@ mod tests {
     fn test_resolve_index_empty_string() {
         let err = resolve_index("", 3, "items.").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("non-integer index ''"));
             }
             _ => panic!("Expected Error::Path"),
@@ -230,7 +458,7 @@ mod tests {
         // Even index 0 is out of range for empty sequence
         let err = resolve_index("0", 0, "empty.0").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("index 0 is out of range"));
                 assert!(msg.contains("0 elements"));
             }
@@ -242,7 +470,7 @@ mod tests {
     fn test_resolve_index_negative_on_empty_sequence() {
         let err = resolve_index("-1", 0, "empty.-1").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("index -1 is out of range"));
             }
             _ => panic!("Expected Error::Path"),
@@ -256,7 +484,7 @@ mod tests {
         // -(len+1) is out of range
         let err = resolve_index("-6", 5, "test.-6").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("index -6 is out of range"));
             }
             _ => panic!("Expected Error::Path"),
@@ -268,10 +496,69 @@ mod tests {
         // Verify the full path appears in error messages
         let err = resolve_index("99", 3, "deeply.nested.path.99").unwrap_err();
         match err {
-            Error::Path(msg) => {
+            Error::Path { message: msg, .. } => {
                 assert!(msg.contains("deeply.nested.path.99"));
             }
             _ => panic!("Expected Error::Path"),
         }
     }
+
+    // =========================================================================
+    // escape_path_element() / join_path() tests
+    // =========================================================================
+
+    #[test]
+    fn test_escape_path_element_roundtrip() {
+        let elements = vec!["a.b", "c\\d", "", "e\\.f"];
+        assert_eq!(split_path(&join_elements(&elements)), elements);
+    }
+
+    #[test]
+    fn test_escape_path_element() {
+        assert_eq!(escape_path_element("a.b"), r"a\.b");
+        assert_eq!(escape_path_element(r"a\b"), r"a\\b");
+        assert_eq!(escape_path_element("plain"), "plain");
+        assert_eq!(escape_path_element("[a=b]"), r"\[a\=b]");
+        assert_eq!(escape_path_element("a=b"), r"a\=b");
+        assert_eq!(escape_path_element("a[0]"), "a[0]");
+        assert_eq!(escape_path_element("*"), r"\*");
+        assert_eq!(escape_path_element("a*"), "a*");
+        assert_eq!(split_path(&escape_path_element("[a.b")), vec!["[a.b"]);
+    }
+
+    #[test]
+    fn test_join_path_escapes_new_elements_only() {
+        assert_eq!(join_path(r"a\.b", &["c.d", "0"]), r"a\.b.c\.d.0");
+    }
+
+    // =========================================================================
+    // parent_path() / relative_path() tests
+    // =========================================================================
+
+    #[test]
+    fn test_parent_path() {
+        assert_eq!(parent_path("a.b.c").unwrap(), "a.b");
+        assert_eq!(parent_path(r"a\.b.c").unwrap(), r"a\.b");
+        assert_eq!(parent_path(r"a.b\.c").unwrap(), "a");
+    }
+
+    #[test]
+    fn test_parent_path_top_level_errors() {
+        let err = parent_path(r"a\.b").unwrap_err();
+        assert!(matches!(err, Error::Path { message: msg, .. } if msg.contains("has no parent")));
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(relative_path("a", "a.b.c").unwrap(), "b.c");
+        assert_eq!(relative_path(r"a\.b", r"a\.b.c\.d").unwrap(), r"c\.d");
+    }
+
+    #[test]
+    fn test_relative_path_not_below_base() {
+        // "a.b" is not below "a\.b" even if the strings share a prefix
+        assert!(relative_path(r"a\.b", "a.b.c").is_err());
+        assert!(relative_path("a.b", "a.b").is_err());
+        assert!(relative_path("ab", "abc.d").is_err());
+    }
 }
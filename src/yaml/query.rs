@@ -3,11 +3,15 @@
 //! Provides both zero-copy (ValueRef) and owned (Value) query operations.
 
 use super::error::Error;
-use super::path::{resolve_index, split_path};
-use super::InnerValue;
+use super::filter::resolve_filters;
+use super::navigate::walk;
+use super::timestamp::{is_timestamp, is_timestamp_tag};
+use super::{InnerValue, Options};
 use fyaml::{Document, ValueRef};
 pub use fyaml::{Number, Value};
 use indexmap::IndexMap;
+use regex::Regex;
+use std::sync::LazyLock;
 
 // =============================================================================
 // Type Name Helpers
@@ -64,17 +68,148 @@ pub fn value_to_type_name(value: &Value) -> &'static str {
     }
 }
 
+/// Fail unless a value of the given type name matches a JSON Schema type.
+///
+/// As in JSON Schema, an `int` is also a `number`.
+pub fn check_json_schema_type(type_name: &str, expected: &str, path: &str) -> Result<(), Error> {
+    let matches = match expected {
+        "string" => type_name == "str",
+        "integer" => type_name == "int",
+        "number" => type_name == "int" || type_name == "float",
+        "boolean" => type_name == "bool",
+        "array" => type_name == "sequence",
+        "object" => type_name == "struct",
+        "null" => type_name == "NoneType",
+        _ => false,
+    };
+    if matches {
+        return Ok(());
+    }
+    Err(Error::type_error_at(
+        path,
+        format!(
+            "invalid value at '{}', expected JSON Schema type '{}', got '{}'.",
+            path, expected, type_name
+        ),
+    ))
+}
+
 fn is_float_number(n: &Number) -> bool {
     matches!(n, Number::Float(_))
 }
 
+// =============================================================================
+// Schema Resolution
+// =============================================================================
+
+/// YAML schema used to resolve the type of untagged plain scalars.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Schema {
+    /// Every scalar is a string
+    Failsafe,
+    /// Only JSON literals (`null`, `true`, `false` and JSON numbers)
+    Json,
+    /// YAML 1.2 core schema
+    Core,
+}
+
+impl std::str::FromStr for Schema {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "failsafe" => Ok(Schema::Failsafe),
+            "json" => Ok(Schema::Json),
+            "core" => Ok(Schema::Core),
+            _ => Err(format!(
+                "Invalid schema '{}': expected core, json, or failsafe",
+                s
+            )),
+        }
+    }
+}
+
+/// Resolution rules from the YAML 1.2 specification (sections 10.2 and 10.3),
+/// tried in order, the first match giving the type name.
+const JSON_RULES: &[(&str, &str)] = &[
+    ("NoneType", r"^null$"),
+    ("bool", r"^(true|false)$"),
+    ("int", r"^-?(0|[1-9][0-9]*)$"),
+    ("float", r"^-?(0|[1-9][0-9]*)(\.[0-9]*)?([eE][-+]?[0-9]+)?$"),
+];
+
+const CORE_RULES: &[(&str, &str)] = &[
+    ("NoneType", r"^(null|Null|NULL|~|)$"),
+    ("bool", r"^(true|True|TRUE|false|False|FALSE)$"),
+    ("int", r"^([-+]?[0-9]+|0o[0-7]+|0x[0-9a-fA-F]+)$"),
+    (
+        "float",
+        r"^([-+]?(\.[0-9]+|[0-9]+(\.[0-9]*)?)([eE][-+]?[0-9]+)?|[-+]?\.(inf|Inf|INF)|\.(nan|NaN|NAN))$",
+    ),
+];
+
+fn compile_rules(rules: &[(&'static str, &str)]) -> Vec<(&'static str, Regex)> {
+    rules
+        .iter()
+        .map(|(name, re)| (*name, Regex::new(re).expect("valid schema regex")))
+        .collect()
+}
+
+static JSON_REGEXES: LazyLock<Vec<(&'static str, Regex)>> =
+    LazyLock::new(|| compile_rules(JSON_RULES));
+static CORE_REGEXES: LazyLock<Vec<(&'static str, Regex)>> =
+    LazyLock::new(|| compile_rules(CORE_RULES));
+
+/// Resolve the type name of a plain scalar following the given schema.
+#[must_use]
+pub fn plain_scalar_type_name(s: &str, schema: Schema) -> &'static str {
+    let rules = match schema {
+        Schema::Failsafe => return "str",
+        Schema::Json => &*JSON_REGEXES,
+        Schema::Core => &*CORE_REGEXES,
+    };
+    rules
+        .iter()
+        .find(|(_, re)| re.is_match(s))
+        .map_or("str", |(name, _)| name)
+}
+
+/// Get the type name for a ValueRef (zero-copy) following the given schema.
+///
+/// Quoted, literal and folded scalars are always strings.
+#[must_use]
+pub fn value_ref_schema_type_name(v: &ValueRef<'_>, schema: Schema) -> &'static str {
+    if v.is_sequence() {
+        return "sequence";
+    }
+    if v.is_mapping() {
+        return "struct";
+    }
+    if v.as_node().is_non_plain() {
+        return "str";
+    }
+    plain_scalar_type_name(v.as_str().unwrap_or(""), schema)
+}
+
+/// Get the type name for an owned Value following the given schema.
+///
+/// Owned scalars are already resolved, so only the failsafe schema
+/// changes their type.
+#[must_use]
+pub fn value_schema_type_name(value: &Value, schema: Schema) -> &'static str {
+    match (value, schema) {
+        (Value::Null | Value::Bool(_) | Value::Number(_), Schema::Failsafe) => "str",
+        _ => value_to_type_name(value),
+    }
+}
+
 // =============================================================================
 // Error Helpers
 // =============================================================================
 
 /// Trait for getting a type name string from YAML value types.
 /// Allows generic error creation for both `Value` and `ValueRef`.
-trait TypeNamed {
+pub(super) trait TypeNamed {
     fn type_name(&self) -> &'static str;
 }
 
@@ -91,8 +226,8 @@ impl TypeNamed for ValueRef<'_> {
 }
 
 /// Create a type error for operations that require a struct (mapping).
-fn type_error_struct<T: TypeNamed>(op: &str, value: &T) -> Error {
-    Error::Type(format!(
+pub(super) fn type_error_struct<T: TypeNamed>(op: &str, value: &T) -> Error {
+    Error::type_error(format!(
         "{} does not support '{}' type. Please provide or select a struct.",
         op,
         value.type_name()
@@ -100,19 +235,20 @@ fn type_error_struct<T: TypeNamed>(op: &str, value: &T) -> Error {
 }
 
 /// Create a type error for operations that require a sequence or struct.
-fn type_error_seq_or_struct<T: TypeNamed>(op: &str, value: &T) -> Error {
-    Error::Type(format!(
+pub(super) fn type_error_seq_or_struct<T: TypeNamed>(op: &str, value: &T) -> Error {
+    Error::type_error(format!(
         "{} does not support '{}' type. Please provide or select a sequence or struct.",
         op,
         value.type_name()
     ))
 }
 
-/// Create a path error for attempting to traverse a scalar value.
-fn path_error_cannot_traverse(full_path: &str, part: &str) -> Error {
-    Error::Path(format!(
-        "invalid path '{}', cannot traverse scalar at '{}'.",
-        full_path, part
+/// Create a type error for operations that require a sequence.
+pub(super) fn type_error_seq<T: TypeNamed>(op: &str, value: &T) -> Error {
+    Error::type_error(format!(
+        "{} does not support '{}' type. Please provide or select a sequence.",
+        op,
+        value.type_name()
     ))
 }
 
@@ -124,10 +260,15 @@ fn path_error_cannot_traverse(full_path: &str, part: &str) -> Error {
 ///
 /// Returns the inner IndexMap if the value is a mapping (or tagged mapping),
 /// or a type error if not.
-fn as_mapping<'a>(value: &'a Value, op: &str) -> Result<&'a IndexMap<Value, Value>, Error> {
+pub(super) fn as_mapping<'a>(
+    value: &'a Value,
+    op: &str,
+    opts: &Options,
+) -> Result<&'a IndexMap<Value, Value>, Error> {
     match value {
         Value::Mapping(m) => Ok(m),
         Value::Tagged(t) => match &t.value {
+            Value::Mapping(_) if opts.keep_tags => Err(type_error_tagged(op, &t.tag, "struct")),
             Value::Mapping(m) => Ok(m),
             _ => Err(type_error_struct(op, value)),
         },
@@ -135,6 +276,33 @@ fn as_mapping<'a>(value: &'a Value, op: &str) -> Result<&'a IndexMap<Value, Valu
     }
 }
 
+// =============================================================================
+// Tagged Collections
+// =============================================================================
+
+/// Create a type error for a tagged collection refused with `--keep-tags`,
+/// naming its tag as `get-type` does.
+fn type_error_tagged(op: &str, tag: &str, expected: &str) -> Error {
+    Error::type_error(format!(
+        "{} does not support '{}' type. Please provide or select a {}.",
+        op, tag, expected
+    ))
+}
+
+/// Fail on a tagged collection of a document with `--keep-tags`, as the
+/// owned path does.
+fn check_untagged_ref(
+    op: &str,
+    value: &ValueRef<'_>,
+    expected: &str,
+    opts: &Options,
+) -> Result<(), Error> {
+    match value.tag() {
+        Some(tag) if opts.keep_tags => Err(type_error_tagged(op, tag, expected)),
+        _ => Ok(()),
+    }
+}
+
 // Note: inner_value functionality is now provided by InnerValue trait
 // Use value.inner() instead of inner_value(value)
 
@@ -145,71 +313,78 @@ fn as_mapping<'a>(value: &'a Value, op: &str) -> Result<&'a IndexMap<Value, Valu
 /// Zero-copy path navigation returning ValueRef.
 ///
 /// This avoids allocating intermediate `Value` structures for read-only operations.
-pub fn get_value_ref<'a>(path: Option<&str>, doc: &'a Document) -> Result<ValueRef<'a>, Error> {
+pub fn get_value_ref<'a>(
+    path: Option<&str>,
+    doc: &'a Document,
+    opts: &Options,
+) -> Result<ValueRef<'a>, Error> {
     let root = doc
         .root_value()
-        .ok_or_else(|| Error::Path("empty document".into()))?;
+        .ok_or_else(|| Error::path_error("empty document"))?;
 
     match path {
         None => Ok(root),
-        Some(p) => navigate_value_ref(root, p),
+        Some(p) => walk(root, &resolve_filters(root, p)?, opts),
     }
 }
 
-/// Navigate ValueRef using dot-notation path.
-fn navigate_value_ref<'a>(root: ValueRef<'a>, path: &str) -> Result<ValueRef<'a>, Error> {
-    let parts = split_path(path);
-    let mut current = root;
+// =============================================================================
+// Zero-Copy Query Functions
+// =============================================================================
 
-    for part in &parts {
-        current = match navigate_one_step_ref(current, part, path)? {
-            Some(v) => v,
-            None => {
-                return Err(Error::Path(format!(
-                    "invalid path '{}', missing key '{}' in struct.",
-                    path, part
-                )))
-            }
-        };
+/// Get the reported type of a ValueRef: its tag if any, else its type name,
+/// `timestamp` for explicit and plain timestamps.
+fn value_ref_type(value: &ValueRef<'_>) -> String {
+    match value.tag() {
+        Some(tag) if is_timestamp_tag(tag) => "timestamp".to_string(),
+        Some(tag) => tag.to_string(),
+        None if !value.as_node().is_non_plain() && value.as_str().is_some_and(is_timestamp) => {
+            "timestamp".to_string()
+        }
+        None => value_ref_type_name(value).to_string(),
     }
-    Ok(current)
 }
 
-fn navigate_one_step_ref<'a>(
-    current: ValueRef<'a>,
-    part: &str,
-    full_path: &str,
-) -> Result<Option<ValueRef<'a>>, Error> {
-    if current.is_mapping() {
-        Ok(current.get(part))
-    } else if current.is_sequence() {
-        let len = current.seq_len().unwrap_or(0);
-        let idx = resolve_index(part, len, full_path)?;
-        Ok(current.index(idx as i32))
-    } else {
-        Err(path_error_cannot_traverse(full_path, part))
-    }
+/// Get type name using zero-copy, resolving plain scalars with `schema` if given.
+pub fn get_type_ref(
+    path: Option<&str>,
+    doc: &Document,
+    schema: Option<Schema>,
+    opts: &Options,
+) -> Result<String, Error> {
+    let value = get_value_ref(path, doc, opts)?;
+    Ok(match (value.tag(), schema) {
+        (None, Some(schema)) => value_ref_schema_type_name(&value, schema).to_string(),
+        _ => value_ref_type(&value),
+    })
 }
 
-// =============================================================================
-// Zero-Copy Query Functions
-// =============================================================================
-
-/// Get type name using zero-copy.
-pub fn get_type_ref(path: Option<&str>, doc: &Document) -> Result<String, Error> {
-    let value = get_value_ref(path, doc)?;
+/// Iterator over the type of each element (sequence) or value (mapping) using zero-copy.
+pub fn get_types_ref<'a>(
+    path: Option<&str>,
+    doc: &'a Document,
+    opts: &Options,
+) -> Result<Box<dyn Iterator<Item = Value> + 'a>, Error> {
+    let value = get_value_ref(path, doc, opts)?;
 
-    // Check for tag first
-    if let Some(tag) = value.tag() {
-        return Ok(tag.to_string());
+    if value.is_sequence() {
+        Ok(Box::new(
+            value.seq_iter().map(|v| Value::String(value_ref_type(&v))),
+        ))
+    } else if value.is_mapping() {
+        Ok(Box::new(
+            value
+                .map_iter()
+                .map(|(_, v)| Value::String(value_ref_type(&v))),
+        ))
+    } else {
+        Err(type_error_seq_or_struct("get-types", &value))
     }
-
-    Ok(value_ref_type_name(&value).to_string())
 }
 
 /// Get length using zero-copy.
-pub fn get_length_ref(path: Option<&str>, doc: &Document) -> Result<usize, Error> {
-    let value = get_value_ref(path, doc)?;
+pub fn get_length_ref(path: Option<&str>, doc: &Document, opts: &Options) -> Result<usize, Error> {
+    let value = get_value_ref(path, doc, opts)?;
 
     if let Some(len) = value.seq_len() {
         return Ok(len);
@@ -221,44 +396,64 @@ pub fn get_length_ref(path: Option<&str>, doc: &Document) -> Result<usize, Error
     Err(type_error_seq_or_struct("get-length", &value))
 }
 
-/// Iterator for keys using zero-copy.
+/// Enum for keys iterator: keys of a mapping, or indices of a sequence.
+pub enum KeysIter<'a> {
+    Map(Box<dyn Iterator<Item = ValueRef<'a>> + 'a>),
+    Indices(std::ops::Range<usize>),
+}
+
+/// Iterator for keys using zero-copy, the indices of a sequence.
 pub fn keys_ref<'a>(
     path: Option<&str>,
     doc: &'a Document,
-) -> Result<impl Iterator<Item = ValueRef<'a>>, Error> {
-    let value = get_value_ref(path, doc)?;
+    opts: &Options,
+) -> Result<KeysIter<'a>, Error> {
+    let value = get_value_ref(path, doc, opts)?;
 
-    if !value.is_mapping() {
-        return Err(type_error_struct("keys", &value));
+    if !value.is_mapping() && !value.is_sequence() {
+        return Err(type_error_seq_or_struct("keys", &value));
     }
+    check_untagged_ref("keys", &value, "sequence or struct", opts)?;
 
-    Ok(value.map_iter().map(|(k, _)| k))
+    if value.is_sequence() {
+        Ok(KeysIter::Indices(0..value.seq_len().unwrap_or(0)))
+    } else {
+        Ok(KeysIter::Map(Box::new(value.map_iter().map(|(k, _)| k))))
+    }
 }
 
-/// Iterator for values using zero-copy.
+/// Iterator for values using zero-copy, the elements of a sequence.
 pub fn values_ref<'a>(
     path: Option<&str>,
     doc: &'a Document,
-) -> Result<impl Iterator<Item = ValueRef<'a>>, Error> {
-    let value = get_value_ref(path, doc)?;
+    opts: &Options,
+) -> Result<Box<dyn Iterator<Item = ValueRef<'a>> + 'a>, Error> {
+    let value = get_value_ref(path, doc, opts)?;
 
-    if !value.is_mapping() {
-        return Err(type_error_struct("values", &value));
+    if !value.is_mapping() && !value.is_sequence() {
+        return Err(type_error_seq_or_struct("values", &value));
     }
+    check_untagged_ref("values", &value, "sequence or struct", opts)?;
 
-    Ok(value.map_iter().map(|(_, v)| v))
+    if value.is_sequence() {
+        Ok(Box::new(value.seq_iter()))
+    } else {
+        Ok(Box::new(value.map_iter().map(|(_, v)| v)))
+    }
 }
 
 /// Iterator for key-values using zero-copy.
 pub fn key_values_ref<'a>(
     path: Option<&str>,
     doc: &'a Document,
+    opts: &Options,
 ) -> Result<impl Iterator<Item = (ValueRef<'a>, ValueRef<'a>)>, Error> {
-    let value = get_value_ref(path, doc)?;
+    let value = get_value_ref(path, doc, opts)?;
 
     if !value.is_mapping() {
         return Err(type_error_struct("key-values", &value));
     }
+    check_untagged_ref("key-values", &value, "struct", opts)?;
 
     Ok(value.map_iter())
 }
@@ -273,8 +468,12 @@ pub enum GetValuesIter<'a> {
 pub fn get_values_ref<'a>(
     path: Option<&str>,
     doc: &'a Document,
+    opts: &Options,
 ) -> Result<GetValuesIter<'a>, Error> {
-    let value = get_value_ref(path, doc)?;
+    let value = get_value_ref(path, doc, opts)?;
+    if value.is_sequence() || value.is_mapping() {
+        check_untagged_ref("get-values", &value, "sequence or struct", opts)?;
+    }
 
     if value.is_sequence() {
         Ok(GetValuesIter::Seq(Box::new(value.seq_iter())))
@@ -289,80 +488,80 @@ pub fn get_values_ref<'a>(
 // Value-Based Path Navigation (for mutations/chains)
 // =============================================================================
 
-fn lookup_in_map<'a>(
-    map: &'a IndexMap<Value, Value>,
-    part: &str,
-    path: &str,
-) -> Result<&'a Value, Error> {
-    let string_key = Value::String(part.to_string());
-    if let Some(v) = map.get(&string_key) {
-        return Ok(v);
-    }
-    if part.is_empty() {
-        if let Some(v) = map.get(&Value::Null) {
-            return Ok(v);
-        }
-    }
-    Err(Error::Path(format!(
-        "invalid path '{}', missing key '{}' in struct.",
-        path, part
-    )))
-}
-
 /// Navigate to a value at a path (internal helper).
-pub fn get_at_path<'a>(value: &'a Value, path: Option<&str>) -> Result<&'a Value, Error> {
+pub fn get_at_path<'a>(
+    value: &'a Value,
+    path: Option<&str>,
+    opts: &Options,
+) -> Result<&'a Value, Error> {
     let path = match path {
         None => return Ok(value),
-        Some(p) => p,
+        Some(p) => &resolve_filters(value, p)?,
     };
 
-    let parts = split_path(path);
-    let mut current = value;
-
-    for part in &parts {
-        current = match current {
-            Value::Mapping(map) => lookup_in_map(map, part, path)?,
-            Value::Sequence(seq) => {
-                let idx = resolve_index(part, seq.len(), path)?;
-                &seq[idx]
-            }
-            Value::Tagged(tagged) => match &tagged.value {
-                Value::Mapping(map) => lookup_in_map(map, part, path)?,
-                Value::Sequence(seq) => {
-                    let idx = resolve_index(part, seq.len(), path)?;
-                    &seq[idx]
-                }
-                _ => return Err(path_error_cannot_traverse(path, part)),
-            },
-            _ => return Err(path_error_cannot_traverse(path, part)),
-        };
-    }
-
-    Ok(current)
+    walk(value, path, opts)
 }
 
 /// Get value at path (owned version for command chains).
-pub fn get_value(path: Option<&str>, value: &Value) -> Result<Value, Error> {
-    let result = get_at_path(value, path)?;
+pub fn get_value(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let result = get_at_path(value, path, opts)?;
     Ok(result.clone())
 }
 
+/// Get value at path from the YAML document stored in a file.
+pub fn get_value_from_file(path: Option<&str>, file: &str, opts: &Options) -> Result<Value, Error> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+    let value: Value = if content.trim().is_empty() {
+        Value::Null
+    } else {
+        content
+            .parse()
+            .map_err(|e| Error::parse_in(&format!("'{}'", file), &content, e))?
+    };
+    get_value(path, &value, opts)
+}
+
 // =============================================================================
 // Type and Length Operations (Value-based)
 // =============================================================================
 
-pub fn get_type(path: Option<&str>, value: &Value) -> Result<Value, Error> {
-    let target = get_at_path(value, path)?;
-
-    if let Value::Tagged(t) = target {
-        return Ok(Value::String(t.tag.clone()));
+/// Get the reported type of a Value: its tag if any, else its type name.
+fn value_type(value: &Value) -> Value {
+    match value {
+        Value::Tagged(t) if is_timestamp_tag(&t.tag) => Value::String("timestamp".to_string()),
+        Value::Tagged(t) => Value::String(t.tag.clone()),
+        other => Value::String(value_to_type_name(other).to_string()),
     }
+}
 
-    Ok(Value::String(value_to_type_name(target).to_string()))
+pub fn get_type(
+    path: Option<&str>,
+    value: &Value,
+    schema: Option<Schema>,
+    opts: &Options,
+) -> Result<Value, Error> {
+    let target = get_at_path(value, path, opts)?;
+    Ok(match (target, schema) {
+        (Value::Tagged(_), _) | (_, None) => value_type(target),
+        (_, Some(schema)) => Value::String(value_schema_type_name(target, schema).to_string()),
+    })
 }
 
-pub fn get_length(path: Option<&str>, value: &Value) -> Result<Value, Error> {
-    let target = get_at_path(value, path)?;
+pub fn get_types(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let target = get_at_path(value, path, opts)?;
+
+    let types: Vec<Value> = match target.inner() {
+        Value::Sequence(seq) => seq.iter().map(value_type).collect(),
+        Value::Mapping(map) => map.values().map(value_type).collect(),
+        _ => return Err(type_error_seq_or_struct("get-types", target)),
+    };
+
+    Ok(Value::Sequence(types))
+}
+
+pub fn get_length(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let target = get_at_path(value, path, opts)?;
 
     let len = match target.inner() {
         Value::Sequence(seq) => seq.len(),
@@ -377,39 +576,69 @@ pub fn get_length(path: Option<&str>, value: &Value) -> Result<Value, Error> {
 // Keys, Values, Key-Values (Value-based)
 // =============================================================================
 
-pub fn keys(path: Option<&str>, value: &Value) -> Result<Value, Error> {
-    let target = get_at_path(value, path)?;
-    let map = as_mapping(target, "keys")?;
-    let keys: Vec<Value> = map.keys().cloned().collect();
+/// The sequence or mapping at `path`, read through its tag unless
+/// `--keep-tags` is given.
+fn collection_at<'a>(
+    path: Option<&str>,
+    value: &'a Value,
+    op: &str,
+    opts: &Options,
+) -> Result<&'a Value, Error> {
+    let target = get_at_path(value, path, opts)?;
+
+    match (target, target.inner()) {
+        (Value::Tagged(t), Value::Sequence(_) | Value::Mapping(_)) if opts.keep_tags => {
+            Err(type_error_tagged(op, &t.tag, "sequence or struct"))
+        }
+        (_, inner @ (Value::Sequence(_) | Value::Mapping(_))) => Ok(inner),
+        _ => Err(type_error_seq_or_struct(op, target)),
+    }
+}
+
+/// Keys of a mapping, or indices of a sequence.
+pub fn keys(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let keys: Vec<Value> = match collection_at(path, value, "keys", opts)? {
+        Value::Sequence(seq) => (0..seq.len())
+            .map(|i| Value::Number(Number::Int(i as i64)))
+            .collect(),
+        Value::Mapping(map) => map.keys().cloned().collect(),
+        _ => unreachable!("collection_at gives a sequence or a mapping"),
+    };
     Ok(Value::Sequence(keys))
 }
 
-pub fn values(path: Option<&str>, value: &Value) -> Result<Value, Error> {
-    let target = get_at_path(value, path)?;
-    let map = as_mapping(target, "values")?;
-    let vals: Vec<Value> = map.values().cloned().collect();
+/// Values of a mapping, or elements of a sequence.
+pub fn values(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let vals: Vec<Value> = match collection_at(path, value, "values", opts)? {
+        Value::Sequence(seq) => seq.clone(),
+        Value::Mapping(map) => map.values().cloned().collect(),
+        _ => unreachable!("collection_at gives a sequence or a mapping"),
+    };
     Ok(Value::Sequence(vals))
 }
 
-pub fn get_values(path: Option<&str>, value: &Value) -> Result<Value, Error> {
-    let target = get_at_path(value, path)?;
+pub fn get_values(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let items = get_values_iter(path, value, opts)?;
+    Ok(Value::Sequence(items.cloned().collect()))
+}
 
-    match target.inner() {
-        Value::Sequence(seq) => Ok(Value::Sequence(seq.clone())),
-        Value::Mapping(map) => {
-            let result: Vec<Value> = map
-                .iter()
-                .flat_map(|(k, v)| [k.clone(), v.clone()])
-                .collect();
-            Ok(Value::Sequence(result))
-        }
-        _ => Err(type_error_seq_or_struct("get-values", target)),
+/// Iterate over the items `get-values` outputs without copying them: the
+/// elements of a sequence, or the keys and values of a struct alternately.
+pub fn get_values_iter<'a>(
+    path: Option<&str>,
+    value: &'a Value,
+    opts: &Options,
+) -> Result<Box<dyn Iterator<Item = &'a Value> + 'a>, Error> {
+    match collection_at(path, value, "get-values", opts)? {
+        Value::Sequence(seq) => Ok(Box::new(seq.iter())),
+        Value::Mapping(map) => Ok(Box::new(map.iter().flat_map(|(k, v)| [k, v]))),
+        _ => unreachable!("collection_at gives a sequence or a mapping"),
     }
 }
 
-pub fn key_values(path: Option<&str>, value: &Value) -> Result<Value, Error> {
-    let target = get_at_path(value, path)?;
-    let map = as_mapping(target, "key-values")?;
+pub fn key_values(path: Option<&str>, value: &Value, opts: &Options) -> Result<Value, Error> {
+    let target = get_at_path(value, path, opts)?;
+    let map = as_mapping(target, "key-values", opts)?;
     let result: Vec<Value> = map
         .iter()
         .flat_map(|(k, v)| [k.clone(), v.clone()])
@@ -512,7 +741,7 @@ mod tests {
     #[test]
     fn test_get_at_path_none() {
         let value = Value::String("hello".to_string());
-        let result = get_at_path(&value, None).unwrap();
+        let result = get_at_path(&value, None, &Options::default()).unwrap();
         assert_eq!(result, &value);
     }
 
@@ -521,7 +750,7 @@ mod tests {
         let value = Value::Mapping(indexmap! {
             Value::String("name".to_string()) => Value::String("alice".to_string()),
         });
-        let result = get_at_path(&value, Some("name")).unwrap();
+        let result = get_at_path(&value, Some("name"), &Options::default()).unwrap();
         assert_eq!(result, &Value::String("alice".to_string()));
     }
 
@@ -532,7 +761,7 @@ mod tests {
                 Value::String("b".to_string()) => Value::Number(Number::Int(42)),
             }),
         });
-        let result = get_at_path(&value, Some("a.b")).unwrap();
+        let result = get_at_path(&value, Some("a.b"), &Options::default()).unwrap();
         assert_eq!(result, &Value::Number(Number::Int(42)));
     }
 
@@ -545,7 +774,7 @@ mod tests {
                 Value::String("c".to_string()),
             ]),
         });
-        let result = get_at_path(&value, Some("items.1")).unwrap();
+        let result = get_at_path(&value, Some("items.1"), &Options::default()).unwrap();
         assert_eq!(result, &Value::String("b".to_string()));
     }
 
@@ -555,7 +784,7 @@ mod tests {
             Value::String("first".to_string()),
             Value::String("last".to_string()),
         ]);
-        let result = get_at_path(&value, Some("-1")).unwrap();
+        let result = get_at_path(&value, Some("-1"), &Options::default()).unwrap();
         assert_eq!(result, &Value::String("last".to_string()));
     }
 
@@ -564,24 +793,58 @@ mod tests {
         let value = Value::Mapping(indexmap! {
             Value::String("a".to_string()) => Value::Number(Number::Int(1)),
         });
-        let err = get_at_path(&value, Some("b")).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = get_at_path(&value, Some("b"), &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("missing key 'b'"));
     }
 
+    #[test]
+    fn test_get_at_path_numeric_and_bool_keys() {
+        let value = Value::Mapping(indexmap! {
+            Value::Number(Number::Int(8080)) => Value::String("web".to_string()),
+            Value::Bool(true) => Value::String("yes".to_string()),
+            Value::String("80".to_string()) => Value::String("quoted".to_string()),
+        });
+        let result = get_at_path(&value, Some("8080"), &Options::default()).unwrap();
+        assert_eq!(result, &Value::String("web".to_string()));
+        let result = get_at_path(&value, Some("true"), &Options::default()).unwrap();
+        assert_eq!(result, &Value::String("yes".to_string()));
+        let result = get_at_path(&value, Some("80"), &Options::default()).unwrap();
+        assert_eq!(result, &Value::String("quoted".to_string()));
+        assert!(get_at_path(&value, Some("443"), &Options::default()).is_err());
+    }
+
+    #[test]
+    fn test_get_at_path_typed_keys() {
+        let value = Value::Mapping(indexmap! {
+            Value::Null => Value::String("none".to_string()),
+            Value::Bool(false) => Value::String("no".to_string()),
+            Value::Number(Number::Int(1)) => Value::String("one".to_string()),
+            Value::String("true".to_string()) => Value::String("text".to_string()),
+        });
+        let result = get_at_path(&value, Some(":(~)"), &Options::default()).unwrap();
+        assert_eq!(result, &Value::String("none".to_string()));
+        let result = get_at_path(&value, Some(":(False)"), &Options::default()).unwrap();
+        assert_eq!(result, &Value::String("no".to_string()));
+        let result = get_at_path(&value, Some(":(1)"), &Options::default()).unwrap();
+        assert_eq!(result, &Value::String("one".to_string()));
+        let err = get_at_path(&value, Some(":(true)"), &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("missing key ':(true)'"));
+    }
+
     #[test]
     fn test_get_at_path_cannot_traverse_scalar() {
         let value = Value::String("hello".to_string());
-        let err = get_at_path(&value, Some("child")).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = get_at_path(&value, Some("child"), &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("cannot traverse scalar"));
     }
 
     #[test]
     fn test_get_at_path_index_out_of_range() {
         let value = Value::Sequence(vec![Value::String("a".to_string())]);
-        let err = get_at_path(&value, Some("5")).unwrap_err();
-        assert!(matches!(err, Error::Path(_)));
+        let err = get_at_path(&value, Some("5"), &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Path { .. }));
         assert!(err.to_string().contains("out of range"));
     }
 
@@ -593,7 +856,7 @@ mod tests {
                 Value::String("key".to_string()) => Value::String("value".to_string()),
             }),
         }));
-        let result = get_at_path(&value, Some("key")).unwrap();
+        let result = get_at_path(&value, Some("key"), &Options::default()).unwrap();
         assert_eq!(result, &Value::String("value".to_string()));
     }
 
@@ -606,7 +869,7 @@ mod tests {
                 Value::Number(Number::Int(20)),
             ]),
         }));
-        let result = get_at_path(&value, Some("0")).unwrap();
+        let result = get_at_path(&value, Some("0"), &Options::default()).unwrap();
         assert_eq!(result, &Value::Number(Number::Int(10)));
     }
 
@@ -617,7 +880,7 @@ mod tests {
     #[test]
     fn test_get_type_basic() {
         let value = Value::String("hello".to_string());
-        let result = get_type(None, &value).unwrap();
+        let result = get_type(None, &value, None, &Options::default()).unwrap();
         assert_eq!(result, Value::String("str".to_string()));
     }
 
@@ -626,7 +889,7 @@ mod tests {
         let value = Value::Mapping(indexmap! {
             Value::String("count".to_string()) => Value::Number(Number::Int(42)),
         });
-        let result = get_type(Some("count"), &value).unwrap();
+        let result = get_type(Some("count"), &value, None, &Options::default()).unwrap();
         assert_eq!(result, Value::String("int".to_string()));
     }
 
@@ -636,10 +899,89 @@ mod tests {
             tag: "!custom-type".to_string(),
             value: Value::String("data".to_string()),
         }));
-        let result = get_type(None, &value).unwrap();
+        let result = get_type(None, &value, None, &Options::default()).unwrap();
         assert_eq!(result, Value::String("!custom-type".to_string()));
     }
 
+    #[test]
+    fn test_plain_scalar_type_name_schemas() {
+        for (s, core, json) in [
+            ("yes", "str", "str"),
+            ("~", "NoneType", "str"),
+            ("null", "NoneType", "NoneType"),
+            ("False", "bool", "str"),
+            ("0o17", "int", "str"),
+            ("+12", "int", "str"),
+            ("012", "int", "str"),
+            ("-0", "int", "int"),
+            (".5", "float", "str"),
+            ("1e3", "float", "float"),
+            (".NaN", "float", "str"),
+        ] {
+            assert_eq!(plain_scalar_type_name(s, Schema::Core), core, "{}", s);
+            assert_eq!(plain_scalar_type_name(s, Schema::Json), json, "{}", s);
+            assert_eq!(plain_scalar_type_name(s, Schema::Failsafe), "str");
+        }
+    }
+
+    #[test]
+    fn test_get_type_failsafe_schema() {
+        let value = Value::Number(Number::Int(42));
+        let result = get_type(None, &value, Some(Schema::Failsafe), &Options::default()).unwrap();
+        assert_eq!(result, Value::String("str".to_string()));
+    }
+
+    // -------------------------------------------------------------------------
+    // get_types Tests
+    // -------------------------------------------------------------------------
+
+    #[test]
+    fn test_get_types_heterogeneous_sequence() {
+        let value = Value::Sequence(vec![
+            Value::Number(Number::Int(1)),
+            Value::String("two".to_string()),
+            Value::Null,
+            Value::Tagged(Box::new(TaggedValue {
+                tag: "!custom".to_string(),
+                value: Value::String("x".to_string()),
+            })),
+        ]);
+        let result = get_types(None, &value, &Options::default()).unwrap();
+        assert_eq!(
+            result,
+            Value::Sequence(vec![
+                Value::String("int".to_string()),
+                Value::String("str".to_string()),
+                Value::String("NoneType".to_string()),
+                Value::String("!custom".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_get_types_mapping_values() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("a".to_string()) => Value::Bool(true),
+            Value::String("b".to_string()) => Value::Sequence(vec![]),
+        });
+        let result = get_types(None, &value, &Options::default()).unwrap();
+        assert_eq!(
+            result,
+            Value::Sequence(vec![
+                Value::String("bool".to_string()),
+                Value::String("sequence".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_get_types_scalar_error() {
+        let value = Value::String("scalar".to_string());
+        let err = get_types(None, &value, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Type { .. }));
+        assert!(err.to_string().contains("get-types"));
+    }
+
     // -------------------------------------------------------------------------
     // get_length Tests
     // -------------------------------------------------------------------------
@@ -651,7 +993,7 @@ mod tests {
             Value::Number(Number::Int(2)),
             Value::Number(Number::Int(3)),
         ]);
-        let result = get_length(None, &value).unwrap();
+        let result = get_length(None, &value, &Options::default()).unwrap();
         assert_eq!(result, Value::Number(Number::UInt(3)));
     }
 
@@ -661,7 +1003,7 @@ mod tests {
             Value::String("a".to_string()) => Value::Number(Number::Int(1)),
             Value::String("b".to_string()) => Value::Number(Number::Int(2)),
         });
-        let result = get_length(None, &value).unwrap();
+        let result = get_length(None, &value, &Options::default()).unwrap();
         assert_eq!(result, Value::Number(Number::UInt(2)));
     }
 
@@ -673,15 +1015,15 @@ mod tests {
                 Value::String("y".to_string()),
             ]),
         });
-        let result = get_length(Some("items"), &value).unwrap();
+        let result = get_length(Some("items"), &value, &Options::default()).unwrap();
         assert_eq!(result, Value::Number(Number::UInt(2)));
     }
 
     #[test]
     fn test_get_length_scalar_error() {
         let value = Value::String("hello".to_string());
-        let err = get_length(None, &value).unwrap_err();
-        assert!(matches!(err, Error::Type(_)));
+        let err = get_length(None, &value, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Type { .. }));
         assert!(err.to_string().contains("get-length"));
     }
 
@@ -691,7 +1033,7 @@ mod tests {
             tag: "!list".to_string(),
             value: Value::Sequence(vec![Value::Null, Value::Null]),
         }));
-        let result = get_length(None, &value).unwrap();
+        let result = get_length(None, &value, &Options::default()).unwrap();
         assert_eq!(result, Value::Number(Number::UInt(2)));
     }
 
@@ -705,7 +1047,7 @@ mod tests {
             Value::String("a".to_string()) => Value::Number(Number::Int(1)),
             Value::String("b".to_string()) => Value::Number(Number::Int(2)),
         });
-        let result = keys(None, &value).unwrap();
+        let result = keys(None, &value, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             assert_eq!(seq.len(), 2);
             assert_eq!(seq[0], Value::String("a".to_string()));
@@ -715,14 +1057,73 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_keys_and_values_of_sequence() {
+        let value = Value::Sequence(vec![
+            Value::String("x".to_string()),
+            Value::String("y".to_string()),
+        ]);
+        assert_eq!(
+            keys(None, &value, &Options::default()).unwrap(),
+            Value::Sequence(vec![
+                Value::Number(Number::Int(0)),
+                Value::Number(Number::Int(1)),
+            ])
+        );
+        assert_eq!(values(None, &value, &Options::default()).unwrap(), value);
+
+        let doc = Document::parse_str("[x, y]\n").unwrap();
+        assert!(
+            matches!(keys_ref(None, &doc, &Options::default()).unwrap(), KeysIter::Indices(r) if r == (0..2))
+        );
+        assert_eq!(
+            values_ref(None, &doc, &Options::default()).unwrap().count(),
+            2
+        );
+    }
+
     #[test]
     fn test_keys_non_mapping_error() {
-        let value = Value::Sequence(vec![]);
-        let err = keys(None, &value).unwrap_err();
-        assert!(matches!(err, Error::Type(_)));
+        let value = Value::String("x".to_string());
+        let err = keys(None, &value, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Type { .. }));
         assert!(err.to_string().contains("keys"));
     }
 
+    #[test]
+    fn test_keys_and_values_of_tagged_root() {
+        let doc = Document::parse_str("--- !config\na: 1\nb: x\n").unwrap();
+        let value = crate::yaml::document_to_value(&doc).unwrap();
+        assert!(matches!(value, Value::Tagged(_)));
+
+        let owned = keys(None, &value, &Options::default()).unwrap();
+        let KeysIter::Map(zero_copy) = keys_ref(None, &doc, &Options::default()).unwrap() else {
+            panic!("Expected mapping keys");
+        };
+        let zero_copy: Vec<String> = zero_copy
+            .map(|k| k.as_str().unwrap_or_default().to_string())
+            .collect();
+        assert_eq!(
+            owned,
+            Value::Sequence(vec![
+                Value::String("a".to_string()),
+                Value::String("b".to_string()),
+            ])
+        );
+        assert_eq!(zero_copy, ["a", "b"]);
+
+        assert_eq!(
+            values_ref(None, &doc, &Options::default()).unwrap().count(),
+            2
+        );
+        assert_eq!(
+            key_values_ref(None, &doc, &Options::default())
+                .unwrap()
+                .count(),
+            2
+        );
+    }
+
     // -------------------------------------------------------------------------
     // values Tests
     // -------------------------------------------------------------------------
@@ -733,7 +1134,7 @@ mod tests {
             Value::String("x".to_string()) => Value::Number(Number::Int(10)),
             Value::String("y".to_string()) => Value::Number(Number::Int(20)),
         });
-        let result = values(None, &value).unwrap();
+        let result = values(None, &value, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             assert_eq!(seq.len(), 2);
             assert_eq!(seq[0], Value::Number(Number::Int(10)));
@@ -753,7 +1154,7 @@ mod tests {
             Value::String("a".to_string()),
             Value::String("b".to_string()),
         ]);
-        let result = get_values(None, &value).unwrap();
+        let result = get_values(None, &value, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             assert_eq!(seq.len(), 2);
             assert_eq!(seq[0], Value::String("a".to_string()));
@@ -767,7 +1168,7 @@ mod tests {
         let value = Value::Mapping(indexmap! {
             Value::String("k".to_string()) => Value::String("v".to_string()),
         });
-        let result = get_values(None, &value).unwrap();
+        let result = get_values(None, &value, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             // Should be flattened key-value pairs
             assert_eq!(seq.len(), 2);
@@ -781,8 +1182,8 @@ mod tests {
     #[test]
     fn test_get_values_scalar_error() {
         let value = Value::String("scalar".to_string());
-        let err = get_values(None, &value).unwrap_err();
-        assert!(matches!(err, Error::Type(_)));
+        let err = get_values(None, &value, &Options::default()).unwrap_err();
+        assert!(matches!(err, Error::Type { .. }));
     }
 
     // -------------------------------------------------------------------------
@@ -795,7 +1196,7 @@ mod tests {
             Value::String("a".to_string()) => Value::Number(Number::Int(1)),
             Value::String("b".to_string()) => Value::Number(Number::Int(2)),
         });
-        let result = key_values(None, &value).unwrap();
+        let result = key_values(None, &value, &Options::default()).unwrap();
         if let Value::Sequence(seq) = result {
             assert_eq!(seq.len(), 4); // 2 key-value pairs flattened
             assert_eq!(seq[0], Value::String("a".to_string()));
@@ -817,7 +1218,7 @@ mod tests {
         let value = Value::Mapping(indexmap! {
             Value::String("a.b".to_string()) => Value::Number(Number::Int(42)),
         });
-        let result = get_at_path(&value, Some(r"a\.b")).unwrap();
+        let result = get_at_path(&value, Some(r"a\.b"), &Options::default()).unwrap();
         assert_eq!(result, &Value::Number(Number::Int(42)));
     }
 
@@ -827,7 +1228,7 @@ mod tests {
         let value = Value::Mapping(indexmap! {
             Value::String("".to_string()) => Value::String("empty-key-value".to_string()),
         });
-        let result = get_at_path(&value, Some("")).unwrap();
+        let result = get_at_path(&value, Some(""), &Options::default()).unwrap();
         assert_eq!(result, &Value::String("empty-key-value".to_string()));
     }
 }
@@ -0,0 +1,434 @@
+//! Regions protected by comment directives.
+//!
+//! Entries between a `# shyaml:ignore-start` and a `# shyaml:ignore-end`
+//! comment line are managed by hand: mutations refuse to touch them and
+//! `apply` leaves them as they were in the base document.
+//!
+//! Comments are not attached to nodes by the parser, so the regions are
+//! found by scanning the emitted text of block style documents.
+
+use super::error::Error;
+use super::filter::resolve_filters;
+use super::flatten::FlattenNode;
+use super::mutation::set_value;
+use super::path::{join_elements, resolve_index, split_path};
+use super::query::get_value;
+use super::Options;
+use fyaml::{Document, Value};
+
+/// Comment line opening a protected region.
+pub const IGNORE_START: &str = "# shyaml:ignore-start";
+
+/// Comment line closing a protected region.
+pub const IGNORE_END: &str = "# shyaml:ignore-end";
+
+/// A mapping key or sequence item on the path of the current line.
+struct Entry {
+    column: usize,
+    segment: String,
+    item: bool,
+}
+
+/// Find the paths of the entries starting in protected regions.
+///
+/// Entries nested in an already protected entry are not listed.
+pub fn protected_paths(text: &str) -> Result<Vec<String>, Error> {
+    let mut stack: Vec<Entry> = Vec::new();
+    let mut protected: Vec<Vec<String>> = Vec::new();
+    let mut region_start = None;
+    // Column of the entry holding a block scalar, whose lines are skipped
+    let mut block_scalar = None;
+
+    for (n, line) in text.lines().enumerate() {
+        let content = line.trim_start_matches(' ');
+        let column = line.len() - content.len();
+        if let Some(block_column) = block_scalar {
+            if content.is_empty() || column > block_column {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if content.is_empty() || content.starts_with("---") || content.starts_with("...") {
+            continue;
+        }
+
+        if content.starts_with('#') {
+            match content.trim_end() {
+                IGNORE_START if region_start.is_some() => {
+                    return Err(Error::Base(format!(
+                        "nested '{}' at line {}.",
+                        IGNORE_START,
+                        n + 1
+                    )));
+                }
+                IGNORE_START => region_start = Some(n + 1),
+                IGNORE_END if region_start.is_none() => {
+                    return Err(Error::Base(format!(
+                        "'{}' without '{}' at line {}.",
+                        IGNORE_END,
+                        IGNORE_START,
+                        n + 1
+                    )));
+                }
+                IGNORE_END => region_start = None,
+                _ => {}
+            }
+            continue;
+        }
+
+        let (Some(first), _) = push_entries(&mut stack, column, content, &mut block_scalar) else {
+            continue;
+        };
+        if region_start.is_some() {
+            let path: Vec<String> = stack[..=first].iter().map(|e| e.segment.clone()).collect();
+            if !protected.iter().any(|p| path.starts_with(p)) {
+                protected.push(path);
+            }
+        }
+    }
+
+    if let Some(line) = region_start {
+        return Err(Error::Base(format!(
+            "unterminated '{}' at line {}.",
+            IGNORE_START, line
+        )));
+    }
+    Ok(protected.iter().map(|p| join_elements(p)).collect())
+}
+
+/// Find the path of the value each line of a block style document holds
+/// after its keys and sequence dashes, with the column the value starts at.
+///
+/// Lines holding no key or dash have none, except the ones of top-level
+/// values, whose path is empty.
+pub(super) fn line_values(text: &str) -> Vec<Option<(String, usize)>> {
+    let mut stack: Vec<Entry> = Vec::new();
+    let mut values = Vec::new();
+    let mut block_scalar = None;
+
+    for line in text.lines() {
+        let content = line.trim_start_matches(' ');
+        let column = line.len() - content.len();
+        if let Some(block_column) = block_scalar {
+            if content.is_empty() || column > block_column {
+                values.push(None);
+                continue;
+            }
+            block_scalar = None;
+        }
+        if content.is_empty() || is_marker(content) {
+            values.push(None);
+            continue;
+        }
+        let value = match push_entries(&mut stack, column, content, &mut block_scalar) {
+            (Some(_), rest) => {
+                let path: Vec<&str> = stack.iter().map(|e| e.segment.as_str()).collect();
+                Some((join_elements(&path), line.len() - rest.len()))
+            }
+            (None, _) if column == 0 => Some((String::new(), 0)),
+            (None, _) => None,
+        };
+        values.push(value);
+    }
+    values
+}
+
+/// Whether a line is a comment or a document marker.
+fn is_marker(content: &str) -> bool {
+    content.starts_with('#') || content.starts_with("---") || content.starts_with("...")
+}
+
+/// Push the entries starting on a line, returning the stack index of the
+/// first one and the rest of the line, after their keys and dashes.
+fn push_entries<'a>(
+    stack: &mut Vec<Entry>,
+    mut column: usize,
+    mut content: &'a str,
+    block_scalar: &mut Option<usize>,
+) -> (Option<usize>, &'a str) {
+    let mut first = None;
+
+    while content == "-" || content.starts_with("- ") {
+        let mut index = 0;
+        while let Some(top) = stack.last() {
+            if top.column < column || (top.column == column && !top.item) {
+                break;
+            }
+            if top.column == column {
+                index = top.segment.parse::<usize>().map_or(0, |i| i + 1);
+            }
+            stack.pop();
+        }
+        stack.push(Entry {
+            column,
+            segment: index.to_string(),
+            item: true,
+        });
+        first.get_or_insert(stack.len() - 1);
+
+        let rest = content[1..].trim_start_matches(' ');
+        column += content.len() - rest.len();
+        content = rest;
+    }
+
+    if let Some((key, value)) = split_key(content) {
+        while stack.last().is_some_and(|top| top.column >= column) {
+            stack.pop();
+        }
+        stack.push(Entry {
+            column,
+            segment: key,
+            item: false,
+        });
+        first.get_or_insert(stack.len() - 1);
+        content = value.trim_start();
+    }
+
+    if content.starts_with(['|', '>']) {
+        *block_scalar = stack.last().map(|top| top.column);
+    }
+    (first, content)
+}
+
+/// Split `KEY: VALUE`, returning the unquoted key and the value text.
+fn split_key(content: &str) -> Option<(String, &str)> {
+    let (key, rest) = match content.chars().next()? {
+        '"' | '\'' => split_quoted(content)?,
+        '{' | '[' | '&' | '*' | '!' | '|' | '>' => return None,
+        _ => {
+            let end = content
+                .find(": ")
+                .or_else(|| content.trim_end().strip_suffix(':').map(str::len))?;
+            (content[..end].trim_end().to_string(), &content[end..])
+        }
+    };
+    let value = rest.strip_prefix(':')?;
+    if !value.is_empty() && !value.starts_with(' ') {
+        return None;
+    }
+    Some((key, value))
+}
+
+/// Split the quoted scalar starting `content`, returning its unescaped
+/// text and the rest of the line, or `None` if it doesn't end on the line.
+///
+/// A single quote is escaped as `''`, and double-quoted scalars have
+/// backslash escapes.
+fn split_quoted(content: &str) -> Option<(String, &str)> {
+    let mut chars = content.char_indices();
+    let (_, quote) = chars.next()?;
+    let mut text = String::new();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' if quote == '\'' => {
+                if !content[i + 1..].starts_with('\'') {
+                    return Some((text, &content[i + 1..]));
+                }
+                chars.next();
+                text.push('\'');
+            }
+            '"' if quote == '"' => return Some((text, &content[i + 1..])),
+            '\\' if quote == '"' => {
+                let (_, escape) = chars.next()?;
+                let digits = match escape {
+                    'x' => 2,
+                    'u' => 4,
+                    'U' => 8,
+                    _ => 0,
+                };
+                if digits > 0 {
+                    let code = content.get(i + 2..i + 2 + digits)?;
+                    text.push(char::from_u32(u32::from_str_radix(code, 16).ok()?)?);
+                    chars.nth(digits - 1);
+                    continue;
+                }
+                text.push(match escape {
+                    '0' => '\0',
+                    'a' => '\x07',
+                    'b' => '\x08',
+                    't' => '\t',
+                    'n' => '\n',
+                    'v' => '\x0b',
+                    'f' => '\x0c',
+                    'r' => '\r',
+                    'e' => '\x1b',
+                    'N' => '\u{85}',
+                    '_' => '\u{a0}',
+                    'L' => '\u{2028}',
+                    'P' => '\u{2029}',
+                    other => other,
+                });
+            }
+            c => text.push(c),
+        }
+    }
+    None
+}
+
+/// Split a path, with filters and negative indices resolved against the
+/// document.
+pub(super) fn resolved_parts<N: FlattenNode + Copy>(
+    root: N,
+    path: &str,
+) -> Result<Vec<String>, Error> {
+    let mut parts = split_path(&resolve_filters(root, path)?);
+    let mut current = Some(root);
+    for part in parts.iter_mut() {
+        let Some(node) = current.take() else {
+            break;
+        };
+        let Some(children) = node.children() else {
+            break;
+        };
+        if node.is_sequence() {
+            if let Ok(index) = resolve_index(part, children.len(), path) {
+                *part = index.to_string();
+            }
+        }
+        current = children
+            .into_iter()
+            .find(|(k, _)| k == part)
+            .map(|(_, v)| v);
+    }
+    Ok(parts)
+}
+
+fn check<N: FlattenNode + Copy>(root: N, protected: &[String], path: &str) -> Result<(), Error> {
+    if protected.is_empty() {
+        return Ok(());
+    }
+    let parts = resolved_parts(root, path)?;
+    for region in protected {
+        let region_parts = split_path(region);
+        let len = parts.len().min(region_parts.len());
+        if parts[..len] == region_parts[..len] {
+            return Err(Error::Base(format!(
+                "cannot modify '{}', '{}' is in a region protected by '{}'.",
+                path, region, IGNORE_START
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// Fail if modifying a path would change a protected region.
+///
+/// This covers the protected entries, their children and their parents.
+pub fn check_protected(value: &Value, protected: &[String], path: &str) -> Result<(), Error> {
+    check(value, protected, path)
+}
+
+/// Fail if modifying a path of a document would change a protected region.
+pub fn check_protected_doc(doc: &Document, protected: &[String], path: &str) -> Result<(), Error> {
+    match doc.root_value() {
+        Some(root) => check(root, protected, path),
+        None => Ok(()),
+    }
+}
+
+/// Put back the protected entries of the original document into a result.
+pub fn restore_protected(
+    original: &Value,
+    mut result: Value,
+    protected: &[String],
+    opts: &Options,
+) -> Result<Value, Error> {
+    for path in protected {
+        let value = get_value(Some(path), original, opts)?;
+        result = set_value(path, value, result, opts)?;
+    }
+    Ok(result)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_region() {
+        assert!(protected_paths("a: 1\nb:\n  c: 2\n").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_top_level_entries() {
+        let text = "a: 1\n# shyaml:ignore-start\nb:\n  c: 2\nd: 3\n# shyaml:ignore-end\ne: 4\n";
+        assert_eq!(protected_paths(text).unwrap(), vec!["b", "d"]);
+    }
+
+    #[test]
+    fn test_nested_entries_and_items() {
+        let text = "\
+spec:
+  ports:
+  - 80
+  # shyaml:ignore-start
+  - name: admin
+    port: 9000
+  # shyaml:ignore-end
+  - 443
+  x.y: |
+    # shyaml:ignore-start
+";
+        assert_eq!(protected_paths(text).unwrap(), vec!["spec.ports.1"]);
+    }
+
+    #[test]
+    fn test_quoted_and_dotted_keys() {
+        let text = "# shyaml:ignore-start\n\"a: b\": 1\nc.d: 2\n# shyaml:ignore-end\n";
+        assert_eq!(protected_paths(text).unwrap(), vec!["a: b", r"c\.d"]);
+    }
+
+    #[test]
+    fn test_keys_with_escaped_quotes() {
+        let text = r#"a: 1
+# shyaml:ignore-start
+"a\"b": x
+'it''s': y
+"c: d\\": 'e: f'
+# shyaml:ignore-end
+g: 2
+"#;
+        assert_eq!(
+            protected_paths(text).unwrap(),
+            vec![r#"a"b"#, "it's", r"c: d\\"]
+        );
+        assert_eq!(
+            line_values(text)[3],
+            Some(("it's".to_string(), "'it''s': ".len()))
+        );
+    }
+
+    #[test]
+    fn test_split_quoted() {
+        assert_eq!(
+            split_quoted(r#""a\"b\x41é": x"#),
+            Some((r#"a"bAé"#.to_string(), ": x"))
+        );
+        assert_eq!(
+            split_quoted("'it''s': y"),
+            Some(("it's".to_string(), ": y"))
+        );
+        assert_eq!(split_quoted("'a'''"), Some(("a'".to_string(), "")));
+        assert_eq!(split_quoted(r#""a\" b"#), None);
+    }
+
+    #[test]
+    fn test_unterminated_region() {
+        let err = protected_paths("a: 1\n# shyaml:ignore-start\nb: 2\n").unwrap_err();
+        assert!(err.to_string().contains("unterminated"));
+    }
+
+    #[test]
+    fn test_check_overlaps() {
+        let value: Value = "a:\n  b: [1, 2]\nc: 3\n".parse().unwrap();
+        let protected = vec!["a.b".to_string()];
+        assert!(check_protected(&value, &protected, "c").is_ok());
+        assert!(check_protected(&value, &protected, "a.x").is_ok());
+        assert!(check_protected(&value, &protected, "a").is_err());
+        assert!(check_protected(&value, &protected, "a.b.-1").is_err());
+    }
+}
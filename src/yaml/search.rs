@@ -0,0 +1,151 @@
+//! Search of the keys and values matching a pattern.
+//!
+//! Provides the `search` action, listing the full path of every node whose
+//! mapping key or scalar value matches a regular expression, in document
+//! order.
+
+use super::error::Error;
+use super::flatten::FlattenNode;
+use super::path::{join_elements, split_path};
+use super::query::{get_at_path, type_error_seq_or_struct};
+use super::serialize::serialize_raw;
+use super::{InnerValue, Options};
+use fyaml::Value;
+use regex::Regex;
+
+/// What a search pattern is matched against.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchScope {
+    /// Mapping keys and scalar values
+    All,
+    Keys,
+    Values,
+}
+
+/// Nodes below `path` whose key or scalar value matches `pattern`, with
+/// their full dot-notation path.
+///
+/// # Errors
+///
+/// Returns an error if the pattern is not a valid regular expression, or
+/// if the path doesn't exist or designates a scalar.
+pub fn search<'a>(
+    path: Option<&str>,
+    value: &'a Value,
+    pattern: &str,
+    scope: SearchScope,
+    opts: &Options,
+) -> Result<Vec<(String, &'a Value)>, Error> {
+    let regex = Regex::new(pattern)
+        .map_err(|e| Error::Base(format!("invalid search pattern '{}': {}", pattern, e)))?;
+    let target = get_at_path(value, path, opts)?;
+    if !matches!(target.inner(), Value::Mapping(_) | Value::Sequence(_)) {
+        return Err(type_error_seq_or_struct("search", target));
+    }
+
+    let mut elements = path.map(split_path).unwrap_or_default();
+    let mut matches = Vec::new();
+    collect_matches(target, &regex, scope, &mut elements, &mut matches);
+    Ok(matches)
+}
+
+fn collect_matches<'a>(
+    node: &'a Value,
+    regex: &Regex,
+    scope: SearchScope,
+    elements: &mut Vec<String>,
+    matches: &mut Vec<(String, &'a Value)>,
+) {
+    let Some(children) = FlattenNode::children(&node) else {
+        return;
+    };
+    // Sequence indexes are not keys
+    let keyed = !matches!(node.inner(), Value::Sequence(_));
+    for (key, child) in children {
+        let is_scalar = !matches!(child.inner(), Value::Mapping(_) | Value::Sequence(_));
+        let found = match scope {
+            SearchScope::Keys => keyed && regex.is_match(&key),
+            SearchScope::Values => is_scalar && regex.is_match(&serialize_raw(child.inner())),
+            SearchScope::All => {
+                (keyed && regex.is_match(&key))
+                    || (is_scalar && regex.is_match(&serialize_raw(child.inner())))
+            }
+        };
+        elements.push(key);
+        if found {
+            matches.push((join_elements(elements), child));
+        }
+        collect_matches(child, regex, scope, elements, matches);
+        elements.pop();
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str =
+        "db:\n  host: db.internal\n  replicas: [db.internal, backup]\nhosts:\n  web.internal: 80\n";
+
+    fn paths(path: Option<&str>, pattern: &str, scope: SearchScope) -> Vec<String> {
+        let value: Value = DOC.parse().unwrap();
+        search(path, &value, pattern, scope, &Options::default())
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect()
+    }
+
+    #[test]
+    fn test_search_keys_and_values() {
+        assert_eq!(
+            paths(None, r"\.internal$", SearchScope::All),
+            vec!["db.host", "db.replicas.0", r"hosts.web\.internal"]
+        );
+        assert_eq!(
+            paths(None, "^host", SearchScope::All),
+            vec!["db.host", "hosts"]
+        );
+    }
+
+    #[test]
+    fn test_search_scope() {
+        assert_eq!(
+            paths(None, "internal", SearchScope::Keys),
+            vec![r"hosts.web\.internal"]
+        );
+        assert_eq!(
+            paths(None, "internal", SearchScope::Values),
+            vec!["db.host", "db.replicas.0"]
+        );
+        assert!(paths(None, "^0$", SearchScope::Keys).is_empty());
+    }
+
+    #[test]
+    fn test_search_below_path() {
+        assert_eq!(
+            paths(Some("db.replicas"), "b", SearchScope::All),
+            vec!["db.replicas.0", "db.replicas.1"]
+        );
+    }
+
+    #[test]
+    fn test_search_errors() {
+        let value: Value = DOC.parse().unwrap();
+        let err = search(None, &value, "(", SearchScope::All, &Options::default()).unwrap_err();
+        assert!(err.to_string().contains("invalid search pattern '('"));
+        let err = search(
+            Some("db.host"),
+            &value,
+            "x",
+            SearchScope::All,
+            &Options::default(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("search does not support"));
+    }
+}
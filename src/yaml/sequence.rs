@@ -0,0 +1,271 @@
+//! Reordering of sequences.
+//!
+//! Provides the `sort`, `unique` and `reverse` actions, rearranging the
+//! elements of the sequence at a path. Elements are compared by their raw
+//! text, or by the value of a field below them with `--by`.
+
+use super::doc_mutation::{del_doc, node_yaml, set_yaml_value_doc};
+use super::error::Error;
+use super::mutation::set_value;
+use super::path::join_path;
+use super::query::{get_at_path, get_value_ref, type_error_seq, value_to_type_name};
+use super::serialize::{serialize, serialize_raw};
+use super::update::as_f64;
+use super::{InnerValue, Options};
+use fyaml::{Document, TaggedValue, Value};
+use std::collections::HashSet;
+
+/// Rearrangement of the elements of a sequence.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SeqOp {
+    /// Stable sort, by raw text unless `numeric`
+    Sort {
+        by: Option<String>,
+        numeric: bool,
+        reverse: bool,
+    },
+    /// Keep the first of the elements that are equal, or have an equal field
+    Unique {
+        by: Option<String>,
+    },
+    Reverse,
+}
+
+impl SeqOp {
+    fn name(&self) -> &'static str {
+        match self {
+            SeqOp::Sort { .. } => "sort",
+            SeqOp::Unique { .. } => "unique",
+            SeqOp::Reverse => "reverse",
+        }
+    }
+}
+
+/// What an element is compared by: itself, or its field `by` if it has it.
+fn key<'a>(element: &'a Value, by: Option<&str>, opts: &Options) -> Option<&'a Value> {
+    match by {
+        None => Some(element),
+        Some(by) => get_at_path(element, Some(by), opts).ok(),
+    }
+}
+
+/// Indices of the elements of `seq` kept by `op`, in their new order.
+///
+/// Elements without the field `by` are sorted first, and are all kept by
+/// `unique`.
+fn order(op: &SeqOp, path: &str, seq: &[Value], opts: &Options) -> Result<Vec<usize>, Error> {
+    let mut indices: Vec<usize> = (0..seq.len()).collect();
+    match op {
+        SeqOp::Reverse => indices.reverse(),
+        SeqOp::Unique { by } => {
+            // Typed text, so that `1` and `"1"` differ
+            let keys = seq
+                .iter()
+                .map(|e| key(e, by.as_deref(), opts).map(serialize).transpose())
+                .collect::<Result<Vec<_>, Error>>()?;
+            let mut seen = HashSet::new();
+            indices.retain(|&i| keys[i].as_ref().is_none_or(|k| seen.insert(k)));
+        }
+        SeqOp::Sort {
+            by,
+            numeric: true,
+            reverse,
+        } => {
+            let keys = seq
+                .iter()
+                .enumerate()
+                .map(
+                    |(i, e)| match key(e, by.as_deref(), opts).map(InnerValue::inner) {
+                        None | Some(Value::Null) => Ok(f64::NEG_INFINITY),
+                        Some(Value::Number(n)) => Ok(as_f64(n)),
+                        Some(other @ Value::String(s)) => s
+                            .trim()
+                            .parse::<f64>()
+                            .map_err(|_| numeric_error(path, i, by.as_deref(), other)),
+                        Some(other) => Err(numeric_error(path, i, by.as_deref(), other)),
+                    },
+                )
+                .collect::<Result<Vec<f64>, Error>>()?;
+            indices.sort_by(|&a, &b| match reverse {
+                false => keys[a].total_cmp(&keys[b]),
+                true => keys[b].total_cmp(&keys[a]),
+            });
+        }
+        SeqOp::Sort {
+            by,
+            numeric: false,
+            reverse,
+        } => {
+            let keys: Vec<Option<String>> = seq
+                .iter()
+                .map(|e| key(e, by.as_deref(), opts).map(serialize_raw))
+                .collect();
+            indices.sort_by(|&a, &b| match reverse {
+                false => keys[a].cmp(&keys[b]),
+                true => keys[b].cmp(&keys[a]),
+            });
+        }
+    }
+    Ok(indices)
+}
+
+fn numeric_error(path: &str, index: usize, by: Option<&str>, value: &Value) -> Error {
+    let mut element = join_path(path, &[index.to_string()]);
+    if let Some(by) = by {
+        element = format!("{}.{}", element, by);
+    }
+    Error::type_error_at(
+        &element,
+        format!(
+            "sort --numeric does not support '{}' value at '{}'.",
+            match value {
+                Value::String(s) => s.clone(),
+                other => value_to_type_name(other).to_string(),
+            },
+            element
+        ),
+    )
+}
+
+/// Rearrange the elements of the sequence at `path` (owned version for
+/// command chains).
+pub fn reorder(path: &str, op: &SeqOp, value: Value, opts: &Options) -> Result<Value, Error> {
+    let target = get_at_path(&value, Some(path), opts)?;
+    let Value::Sequence(seq) = target.inner() else {
+        return Err(type_error_seq(op.name(), target));
+    };
+    let reordered = Value::Sequence(
+        order(op, path, seq, opts)?
+            .into_iter()
+            .map(|i| seq[i].clone())
+            .collect(),
+    );
+    let reordered = match target {
+        Value::Tagged(t) => Value::Tagged(Box::new(TaggedValue {
+            tag: t.tag.clone(),
+            value: reordered,
+        })),
+        _ => reordered,
+    };
+    set_value(path, reordered, value, opts)
+}
+
+/// Rearrange the elements of the sequence at `path`, in place using
+/// Editor. Elements keep their comments.
+pub fn reorder_doc(
+    doc: &mut Document,
+    path: &str,
+    op: &SeqOp,
+    opts: &Options,
+) -> Result<(), Error> {
+    let target = Value::from_node_ref(get_value_ref(Some(path), doc, opts)?.as_node())?;
+    let Value::Sequence(seq) = target.inner() else {
+        return Err(type_error_seq(op.name(), &target));
+    };
+    let indices = order(op, path, seq, opts)?;
+    let element = |i: usize| join_path(path, &[i.to_string()]);
+    let texts = (0..seq.len())
+        .map(|i| node_yaml(get_value_ref(Some(&element(i)), doc, opts)?.as_node()))
+        .collect::<Result<Vec<String>, Error>>()?;
+    for (i, &j) in indices.iter().enumerate() {
+        if i != j {
+            set_yaml_value_doc(doc, &element(i), &texts[j], opts)?;
+        }
+    }
+    // Last first, so deletions don't shift the indexes of the others
+    for i in (indices.len()..seq.len()).rev() {
+        del_doc(doc, &element(i), opts)?;
+    }
+    Ok(())
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(op: SeqOp, input: &str) -> String {
+        let value: Value = input.parse().unwrap();
+        serialize(&reorder("s", &op, value, &Options::default()).unwrap()).unwrap()
+    }
+
+    fn sort(by: Option<&str>, numeric: bool, reverse: bool) -> SeqOp {
+        SeqOp::Sort {
+            by: by.map(str::to_string),
+            numeric,
+            reverse,
+        }
+    }
+
+    #[test]
+    fn test_sort() {
+        assert_eq!(
+            run(sort(None, false, false), "s: [b, 10, a, 9]"),
+            "s:\n- 10\n- 9\n- a\n- b"
+        );
+        assert_eq!(
+            run(sort(None, true, false), "s: [10, '9', 1.5]"),
+            "s:\n- 1.5\n- '9'\n- 10"
+        );
+        assert_eq!(
+            run(sort(None, true, true), "s: [1, 3, 2]"),
+            "s:\n- 3\n- 2\n- 1"
+        );
+    }
+
+    #[test]
+    fn test_sort_by_field_is_stable() {
+        assert_eq!(
+            run(
+                sort(Some("n"), true, true),
+                "s: [{id: a, n: 1}, {id: b, n: 2}, {id: c, n: 1}, {id: d}]"
+            ),
+            "s:\n- id: b\n  n: 2\n- id: a\n  n: 1\n- id: c\n  n: 1\n- id: d"
+        );
+    }
+
+    #[test]
+    fn test_unique() {
+        assert_eq!(
+            run(SeqOp::Unique { by: None }, "s: [1, '1', 1, a, a]"),
+            "s:\n- 1\n- '1'\n- a"
+        );
+        assert_eq!(
+            run(
+                SeqOp::Unique {
+                    by: Some("k".to_string())
+                },
+                "s: [{k: 1, v: a}, {v: b}, {k: 1, v: c}, {v: d}]"
+            ),
+            "s:\n- k: 1\n  v: a\n- v: b\n- v: d"
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        assert_eq!(run(SeqOp::Reverse, "s: [1, 2, 3]"), "s:\n- 3\n- 2\n- 1");
+    }
+
+    #[test]
+    fn test_errors() {
+        let value: Value = "s: [1, x]\nm: {a: 1}\n".parse().unwrap();
+        let err = reorder(
+            "s",
+            &sort(None, true, false),
+            value.clone(),
+            &Options::default(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "sort --numeric does not support 'x' value at 's.1'."
+        );
+        let err = reorder("m", &SeqOp::Reverse, value, &Options::default()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("reverse does not support 'struct' type"));
+    }
+}
@@ -87,3 +87,31 @@ pub fn serialize_raw(value: &Value) -> String {
         _ => serialize(value).unwrap_or_default(),
     }
 }
+
+/// Short single-line rendering of a value for messages: strings are quoted
+/// and collections are in flow style.
+#[must_use]
+pub fn serialize_inline(value: &Value) -> String {
+    match value {
+        Value::Null => "null".to_string(),
+        Value::String(s) => format!("'{}'", s),
+        Value::Sequence(items) => format!(
+            "[{}]",
+            items
+                .iter()
+                .map(serialize_inline)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Mapping(entries) => format!(
+            "{{{}}}",
+            entries
+                .iter()
+                .map(|(k, v)| format!("{}: {}", serialize_inline(k), serialize_inline(v)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        Value::Tagged(tagged) => format!("{} {}", tagged.tag, serialize_inline(&tagged.value)),
+        scalar => serialize_raw(scalar),
+    }
+}
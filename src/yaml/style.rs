@@ -0,0 +1,473 @@
+//! Layout options of output documents.
+//!
+//! Provides the `--indent`, `--flow`, `--width` and `--quote-strings`
+//! output: documents are emitted from the Value with the indentation,
+//! collection style and string quoting asked for, instead of the fixed
+//! layout of `serialize()`.
+
+use super::error::Error;
+use super::serialize::serialize;
+use fyaml::Value;
+
+/// Style of mappings and sequences.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum FlowStyle {
+    /// Flow style for collections of scalars that fit on their line
+    Auto,
+    /// Block style everywhere
+    #[default]
+    Block,
+    /// Flow style everywhere, broken over lines that exceed the width
+    Flow,
+}
+
+/// Layout of output documents.
+#[derive(Clone, Debug)]
+pub struct Style {
+    /// Spaces per nesting level of mappings
+    pub indent: usize,
+    pub flow: FlowStyle,
+    /// Line width flow collections are kept within
+    pub width: usize,
+    /// Double-quote every string value, not only those that need it
+    pub quote_strings: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            indent: 2,
+            flow: FlowStyle::Block,
+            width: 80,
+            quote_strings: false,
+        }
+    }
+}
+
+impl Style {
+    /// Style given by the command line options, `None` if there are none.
+    pub fn new(
+        indent: Option<usize>,
+        flow: Option<&str>,
+        width: Option<usize>,
+        quote_strings: Option<&str>,
+    ) -> Result<Option<Self>, Error> {
+        if indent.is_none() && flow.is_none() && width.is_none() && quote_strings.is_none() {
+            return Ok(None);
+        }
+        let default = Style::default();
+        let flow = match flow {
+            None => default.flow,
+            Some("auto") => FlowStyle::Auto,
+            Some("block") => FlowStyle::Block,
+            Some("flow") => FlowStyle::Flow,
+            Some(other) => return Err(Error::Base(format!("unknown flow style '{}'", other))),
+        };
+        let quote_strings = match quote_strings {
+            None | Some("needed") => false,
+            Some("always") => true,
+            Some(other) => return Err(Error::Base(format!("unknown string quoting '{}'", other))),
+        };
+        Ok(Some(Style {
+            indent: indent.unwrap_or(default.indent),
+            flow,
+            width: width.unwrap_or(default.width),
+            quote_strings,
+        }))
+    }
+}
+
+/// Serialize a value to YAML with the layout of `style`.
+pub fn serialize_styled(value: &Value, style: &Style) -> Result<String, Error> {
+    let mut emitter = Emitter {
+        style,
+        out: String::new(),
+    };
+    if style.flow == FlowStyle::Flow {
+        return emitter.flow_lines(value, 0, 0);
+    }
+    match inner(value) {
+        (tag, inner @ (Value::Mapping(_) | Value::Sequence(_))) if !is_leaf(inner) => {
+            if let Some(tag) = tag {
+                emitter.out.push_str(&format!("{}\n", tag));
+            }
+            emitter.block(inner, 0)?;
+            Ok(emitter.out.trim_end_matches('\n').to_string())
+        }
+        _ => emitter.scalar(value, false, false, 0),
+    }
+}
+
+/// Single-line flow text of a mapping key, as `[a, b]` or `{x: 1}`.
+///
+/// This is the canonical form mappings and sequences used as keys are
+/// listed in by `keys`, written in merge paths, and found with in paths
+/// as `:([a, b])`. It parses back to the same key.
+pub fn key_text(key: &Value) -> Result<String, Error> {
+    let style = Style::default();
+    let emitter = Emitter {
+        style: &style,
+        out: String::new(),
+    };
+    emitter.flow_key(key)
+}
+
+/// Tag and untagged value.
+fn inner(value: &Value) -> (Option<&str>, &Value) {
+    match value {
+        Value::Tagged(t) => (Some(&t.tag), &t.value),
+        other => (None, other),
+    }
+}
+
+/// Scalars and empty collections, always emitted on one line.
+fn is_leaf(value: &Value) -> bool {
+    match value {
+        Value::Mapping(map) => map.is_empty(),
+        Value::Sequence(seq) => seq.is_empty(),
+        _ => true,
+    }
+}
+
+/// Whether a non-empty collection only holds leaves.
+fn is_flat(value: &Value) -> bool {
+    match inner(value).1 {
+        Value::Mapping(map) => {
+            !map.is_empty()
+                && map
+                    .iter()
+                    .all(|(k, v)| is_leaf(inner(k).1) && is_leaf(inner(v).1))
+        }
+        Value::Sequence(seq) => !seq.is_empty() && seq.iter().all(|v| is_leaf(inner(v).1)),
+        _ => false,
+    }
+}
+
+fn double_quoted(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\x{:02x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+struct Emitter<'a> {
+    style: &'a Style,
+    out: String,
+}
+
+impl Emitter<'_> {
+    /// Text of a scalar or an empty collection. Multi-line block scalars
+    /// have their content indented at `indent`.
+    fn scalar(&self, value: &Value, key: bool, flow: bool, indent: usize) -> Result<String, Error> {
+        match value {
+            Value::Tagged(t) => Ok(format!(
+                "{} {}",
+                t.tag,
+                self.scalar(&t.value, key, flow, indent)?
+            )),
+            Value::String(s) if self.style.quote_strings && !key => Ok(double_quoted(s)),
+            Value::String(s) => {
+                let text = serialize(value)?.trim_end_matches('\n').to_string();
+                // Plain scalars can't hold flow indicators in flow collections
+                let plain = !text.starts_with(['\'', '"']);
+                if flow
+                    && (text.contains('\n') || (plain && text.contains([',', '[', ']', '{', '}'])))
+                {
+                    return Ok(double_quoted(s));
+                }
+                let mut lines = text.lines();
+                let mut out = lines.next().unwrap_or_default().to_string();
+                for line in lines {
+                    out.push('\n');
+                    if !line.is_empty() {
+                        let line = line.strip_prefix("  ").unwrap_or(line);
+                        out.push_str(&format!("{:indent$}{}", "", line, indent = indent));
+                    }
+                }
+                Ok(out)
+            }
+            Value::Mapping(_) => Ok("{}".to_string()),
+            Value::Sequence(_) => Ok("[]".to_string()),
+            _ => Ok(serialize(value)?.trim_end_matches('\n').to_string()),
+        }
+    }
+
+    /// Single-line flow text of a value.
+    fn flow(&self, value: &Value) -> Result<String, Error> {
+        match inner(value) {
+            (tag, Value::Mapping(map)) if !map.is_empty() => {
+                let entries = map
+                    .iter()
+                    .map(|(k, v)| Ok(format!("{}: {}", self.flow_key(k)?, self.flow(v)?)))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(tagged(tag, format!("{{{}}}", entries.join(", "))))
+            }
+            (tag, Value::Sequence(seq)) if !seq.is_empty() => {
+                let items = seq
+                    .iter()
+                    .map(|v| self.flow(v))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                Ok(tagged(tag, format!("[{}]", items.join(", "))))
+            }
+            _ => self.scalar(value, false, true, 0),
+        }
+    }
+
+    fn flow_key(&self, key: &Value) -> Result<String, Error> {
+        match inner(key).1 {
+            Value::Mapping(_) | Value::Sequence(_) => self.flow(key),
+            _ => self.scalar(key, true, true, 0),
+        }
+    }
+
+    /// Flow text of a value starting at column `col`, broken over lines
+    /// indented from `indent` when it doesn't fit in the width.
+    fn flow_lines(&self, value: &Value, indent: usize, col: usize) -> Result<String, Error> {
+        let line = self.flow(value)?;
+        if col + line.chars().count() <= self.style.width || is_leaf(inner(value).1) {
+            return Ok(line);
+        }
+        let (tag, value) = inner(value);
+        let nested = indent + self.style.indent;
+        let (open, items, close) = match value {
+            Value::Mapping(map) => {
+                let mut items = Vec::with_capacity(map.len());
+                for (k, v) in map {
+                    let key = format!("{}: ", self.flow_key(k)?);
+                    let start = nested + key.chars().count();
+                    items.push(format!("{}{}", key, self.flow_lines(v, nested, start)?));
+                }
+                ('{', items, '}')
+            }
+            Value::Sequence(seq) => {
+                let items = seq
+                    .iter()
+                    .map(|v| self.flow_lines(v, nested, nested))
+                    .collect::<Result<Vec<_>, Error>>()?;
+                ('[', items, ']')
+            }
+            _ => unreachable!("scalars fit on their line"),
+        };
+        let body = items
+            .iter()
+            .map(|item| format!("{:nested$}{}", "", item, nested = nested))
+            .collect::<Vec<_>>()
+            .join(",\n");
+        Ok(tagged(
+            tag,
+            format!(
+                "{}\n{}\n{:indent$}{}",
+                open,
+                body,
+                "",
+                close,
+                indent = indent
+            ),
+        ))
+    }
+
+    /// Text following `key:` or `-` at column `col`, if the value stays on
+    /// that line.
+    fn inline(&self, value: &Value, indent: usize, col: usize) -> Result<Option<String>, Error> {
+        if is_leaf(inner(value).1) {
+            return Ok(Some(self.scalar(
+                value,
+                false,
+                false,
+                indent + self.style.indent,
+            )?));
+        }
+        if self.style.flow == FlowStyle::Auto && is_flat(value) {
+            let text = self.flow(value)?;
+            if col + 1 + text.chars().count() <= self.style.width {
+                return Ok(Some(text));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Emit a non-empty collection in block style, its lines indented at `indent`.
+    fn block(&mut self, value: &Value, indent: usize) -> Result<(), Error> {
+        match value {
+            Value::Mapping(map) => {
+                for (k, v) in map {
+                    let key = self.flow_key(k)?;
+                    let line = format!("{:indent$}{}:", "", key, indent = indent);
+                    let col = line.chars().count();
+                    self.out.push_str(&line);
+                    self.child(v, indent, col, false)?;
+                }
+            }
+            Value::Sequence(seq) => {
+                for v in seq {
+                    self.out
+                        .push_str(&format!("{:indent$}-", "", indent = indent));
+                    self.child(v, indent, indent + 1, true)?;
+                }
+            }
+            _ => unreachable!("block() is only called on collections"),
+        }
+        Ok(())
+    }
+
+    /// Emit a value following `key:` (or `-` in a sequence) at column `col`.
+    fn child(&mut self, value: &Value, indent: usize, col: usize, item: bool) -> Result<(), Error> {
+        if let Some(text) = self.inline(value, indent, col)? {
+            self.out.push_str(&format!(" {}\n", text));
+            return Ok(());
+        }
+        let (tag, value) = inner(value);
+        // Sequences below a key are not indented, like serialize() does,
+        // and the content of an item is aligned after its `- `
+        let nested = match value {
+            _ if item => indent + 2,
+            Value::Sequence(_) => indent,
+            _ => indent + self.style.indent,
+        };
+        match tag {
+            Some(tag) => self.out.push_str(&format!(" {}\n", tag)),
+            None if item => {
+                // The first entry starts on the line of its `-`
+                let start = self.out.len();
+                self.block(value, nested)?;
+                self.out.replace_range(start..start + nested - 1, "");
+                return Ok(());
+            }
+            None => self.out.push('\n'),
+        }
+        self.block(value, nested)
+    }
+}
+
+fn tagged(tag: Option<&str>, text: String) -> String {
+    match tag {
+        Some(tag) => format!("{} {}", tag, text),
+        None => text,
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOC: &str = "name: app\nports: [80, 443]\ndb:\n  host: localhost\n  users: [{name: admin, role: owner}]\n";
+
+    fn styled(yaml: &str, style: Style) -> String {
+        serialize_styled(&yaml.parse().unwrap(), &style).unwrap()
+    }
+
+    #[test]
+    fn test_default_style_matches_serialize() {
+        let value: Value = DOC.parse().unwrap();
+        assert_eq!(
+            serialize_styled(&value, &Style::default()).unwrap(),
+            serialize(&value).unwrap().trim_end_matches('\n')
+        );
+    }
+
+    #[test]
+    fn test_indent() {
+        let style = Style {
+            indent: 4,
+            ..Style::default()
+        };
+        assert_eq!(
+            styled(DOC, style),
+            "name: app\nports:\n- 80\n- 443\ndb:\n    host: localhost\n    users:\n    - name: admin\n      role: owner"
+        );
+    }
+
+    #[test]
+    fn test_flow_auto() {
+        let style = Style {
+            flow: FlowStyle::Auto,
+            ..Style::default()
+        };
+        assert_eq!(
+            styled(DOC, style),
+            "name: app\nports: [80, 443]\ndb:\n  host: localhost\n  users:\n  - {name: admin, role: owner}"
+        );
+    }
+
+    #[test]
+    fn test_flow_auto_width() {
+        let style = Style {
+            flow: FlowStyle::Auto,
+            width: 9,
+            ..Style::default()
+        };
+        assert_eq!(
+            styled("a: [1, 2]\nbb: [1, 2]\n", style),
+            "a: [1, 2]\nbb:\n- 1\n- 2"
+        );
+    }
+
+    #[test]
+    fn test_flow() {
+        let style = Style {
+            flow: FlowStyle::Flow,
+            ..Style::default()
+        };
+        assert_eq!(styled("a: [x, 'y,z']\n", style), "{a: [x, \"y,z\"]}");
+    }
+
+    #[test]
+    fn test_key_text() {
+        let key: Value = "[eu, {zone: b}, 'x,y']".parse().unwrap();
+        let text = key_text(&key).unwrap();
+        assert_eq!(text, "[eu, {zone: b}, \"x,y\"]");
+        assert_eq!(text.parse::<Value>().unwrap(), key);
+    }
+
+    #[test]
+    fn test_flow_width() {
+        let style = Style {
+            flow: FlowStyle::Flow,
+            width: 20,
+            ..Style::default()
+        };
+        assert_eq!(
+            styled("name: app\nports: [80, 443]\n", style),
+            "{\n  name: app,\n  ports: [80, 443]\n}"
+        );
+    }
+
+    #[test]
+    fn test_quote_strings() {
+        let style = Style {
+            quote_strings: true,
+            ..Style::default()
+        };
+        assert_eq!(
+            styled("a: x\nb: 1\nc: [\"say \\\"hi\\\"\"]\n", style),
+            "a: \"x\"\nb: 1\nc:\n- \"say \\\"hi\\\"\""
+        );
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(Style::new(None, None, None, None).unwrap().is_none());
+        let style = Style::new(None, Some("auto"), None, Some("always"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(style.flow, FlowStyle::Auto);
+        assert!(style.quote_strings);
+        assert_eq!(style.indent, 2);
+        assert!(Style::new(None, Some("compact"), None, None).is_err());
+    }
+}
@@ -0,0 +1,290 @@
+//! Text templates rendered from document data.
+//!
+//! `{{PATH}}` is replaced by the raw value at PATH, and
+//! `{{#each PATH}}...{{/each}}` repeats its body for each element of the
+//! sequence or struct at PATH:
+//!
+//! ```text
+//! {{#each services}}
+//! - {{@key}}: {{image}} ({{replicas}} replicas)
+//! {{/each}}
+//! ```
+//!
+//! Inside a loop, paths are relative to the current element, `.` is the
+//! element itself, `@key` its key (or index) and `@index` its position;
+//! `@root.PATH` starts from the document root. A line holding only a block
+//! tag is left out of the output.
+
+use super::error::Error;
+use super::path::{escape_path_element, join_path};
+use super::query::{get_at_path, value_to_type_name};
+use super::serialize::serialize_raw;
+use super::{InnerValue, Options};
+use fyaml::Value;
+
+#[derive(Debug, PartialEq)]
+enum Node {
+    Text(String),
+    Value(String),
+    Each(String, Vec<Node>),
+}
+
+/// A parsed template.
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+/// Element of the loops being rendered.
+struct Scope {
+    path: String,
+    key: String,
+    index: usize,
+}
+
+/// Length of the first line of `text`, if it holds only spaces.
+fn blank_to_eol(text: &str) -> Option<usize> {
+    let line = text.find('\n').map_or(text.len(), |i| i + 1);
+    text[..line].trim().is_empty().then_some(line)
+}
+
+impl Template {
+    /// Read a template file.
+    pub fn from_file(file: &str) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+        Self::parse(&content)
+            .map_err(|e| Error::Base(format!("invalid template '{}', {}.", file, e)))
+    }
+
+    fn parse(text: &str) -> Result<Self, String> {
+        // Stack of the loops being parsed, with the line they start on
+        let mut stack: Vec<(String, usize, Vec<Node>)> = Vec::new();
+        let mut nodes = Vec::new();
+        let mut rest = text;
+        // Whether `rest` starts a line
+        let mut line_start = true;
+        let line_of = |rest: &str| text[..text.len() - rest.len()].matches('\n').count() + 1;
+
+        while let Some(start) = rest.find("{{") {
+            let line = line_of(&rest[start..]);
+            let end = rest[start..]
+                .find("}}")
+                .ok_or_else(|| format!("line {}, unclosed '{{{{'", line))?;
+            let tag = rest[start + 2..start + end].trim();
+            let mut before = &rest[..start];
+            rest = &rest[start + end + 2..];
+
+            let block = tag.starts_with('#') || tag.starts_with('/');
+            let indent = before.rfind('\n').map(|i| i + 1);
+            let standalone = block
+                && (indent.is_some() || line_start)
+                && before[indent.unwrap_or(0)..].trim().is_empty();
+            line_start = false;
+            if standalone {
+                // A block tag alone on its line doesn't leave an empty line
+                if let Some(eol) = blank_to_eol(rest) {
+                    before = &before[..indent.unwrap_or(0)];
+                    rest = &rest[eol..];
+                    line_start = true;
+                }
+            }
+            if !before.is_empty() {
+                nodes.push(Node::Text(before.to_string()));
+            }
+
+            if let Some(path) = tag.strip_prefix("#each") {
+                let path = path.trim();
+                if path.is_empty() {
+                    return Err(format!("line {}, missing path after '#each'", line));
+                }
+                stack.push((path.to_string(), line, std::mem::take(&mut nodes)));
+            } else if tag == "/each" {
+                let (path, _, outer) = stack
+                    .pop()
+                    .ok_or_else(|| format!("line {}, '{{{{/each}}}}' without '#each'", line))?;
+                let body = std::mem::replace(&mut nodes, outer);
+                nodes.push(Node::Each(path, body));
+            } else if block {
+                return Err(format!("line {}, unknown block tag '{}'", line, tag));
+            } else if tag.is_empty() {
+                return Err(format!("line {}, empty tag", line));
+            } else {
+                nodes.push(Node::Value(tag.to_string()));
+            }
+        }
+        if let Some((path, line, _)) = stack.pop() {
+            return Err(format!("line {}, '#each {}' is never closed", line, path));
+        }
+        if !rest.is_empty() {
+            nodes.push(Node::Text(rest.to_string()));
+        }
+        Ok(Template { nodes })
+    }
+
+    /// Render the template from a document.
+    pub fn render(&self, doc: &Value, opts: &Options) -> Result<String, Error> {
+        let mut out = String::new();
+        render_nodes(&self.nodes, doc, &mut Vec::new(), &mut out, opts)?;
+        Ok(out)
+    }
+}
+
+/// Path from the document root of a template path.
+fn resolve(path: &str, scopes: &[Scope]) -> Option<String> {
+    if path == "@root" {
+        return None;
+    }
+    if let Some(path) = path.strip_prefix("@root.") {
+        return Some(path.to_string());
+    }
+    let current = scopes.last().map(|s| s.path.as_str());
+    match (current, path) {
+        (current, ".") => current.map(str::to_string),
+        (None, path) => Some(path.to_string()),
+        (Some(current), path) => Some(format!("{}.{}", current, path)),
+    }
+}
+
+fn render_nodes(
+    nodes: &[Node],
+    doc: &Value,
+    scopes: &mut Vec<Scope>,
+    out: &mut String,
+    opts: &Options,
+) -> Result<(), Error> {
+    for node in nodes {
+        match node {
+            Node::Text(text) => out.push_str(text),
+            Node::Value(tag) => match (tag.as_str(), scopes.last()) {
+                ("@key", Some(scope)) => out.push_str(&scope.key),
+                ("@index", Some(scope)) => out.push_str(&scope.index.to_string()),
+                ("@key" | "@index", None) => {
+                    return Err(Error::Base(format!(
+                        "'{{{{{}}}}}' can only be used inside '#each'",
+                        tag
+                    )))
+                }
+                (path, _) => {
+                    let path = resolve(path, scopes);
+                    let value = get_at_path(doc, path.as_deref(), opts)?;
+                    match value.inner() {
+                        Value::Null => {}
+                        Value::Sequence(_) | Value::Mapping(_) => {
+                            return Err(Error::type_error_at(
+                                path.as_deref().unwrap_or_default(),
+                                format!(
+                                    "can't render {} at '{}', iterate over it with '#each'.",
+                                    value_to_type_name(value),
+                                    path.as_deref().unwrap_or_default()
+                                ),
+                            ))
+                        }
+                        _ => out.push_str(&serialize_raw(value)),
+                    }
+                }
+            },
+            Node::Each(path, body) => {
+                let path = resolve(path, scopes);
+                let value = get_at_path(doc, path.as_deref(), opts)?;
+                let keys: Vec<String> = match value.inner() {
+                    Value::Null => Vec::new(),
+                    Value::Sequence(items) => (0..items.len()).map(|i| i.to_string()).collect(),
+                    Value::Mapping(entries) => entries.keys().map(serialize_raw).collect(),
+                    _ => {
+                        return Err(Error::type_error_at(
+                            path.as_deref().unwrap_or_default(),
+                            format!(
+                                "can't iterate over {} at '{}', expected a sequence or struct.",
+                                value_to_type_name(value),
+                                path.as_deref().unwrap_or_default()
+                            ),
+                        ))
+                    }
+                };
+                for (index, key) in keys.into_iter().enumerate() {
+                    let element = match &path {
+                        Some(path) => join_path(path, &[&key]),
+                        None => escape_path_element(&key),
+                    };
+                    scopes.push(Scope {
+                        path: element,
+                        key,
+                        index,
+                    });
+                    render_nodes(body, doc, scopes, out, opts)?;
+                    scopes.pop();
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str, doc: &str) -> Result<String, String> {
+        let template = Template::parse(template)?;
+        template
+            .render(&doc.parse().unwrap(), &Options::default())
+            .map_err(|e| e.to_string())
+    }
+
+    #[test]
+    fn test_render_loops() {
+        let doc = "name: web\nservices:\n  api: {port: 80, hosts: [a, b]}\n  db.main: {port: 5432, hosts: []}\n";
+        assert_eq!(
+            render(
+                "# {{ name }}\n{{#each services}}\n- {{@key}} ({{@index}}): {{port}} on {{@root.name}}\n  {{#each hosts}}{{.}} {{/each}}\n{{/each}}\nend\n",
+                doc
+            )
+            .unwrap(),
+            "# web\n- api (0): 80 on web\n  a b \n- db.main (1): 5432 on web\n  \nend\n"
+        );
+        assert_eq!(render("{{a}}|{{b}}", "a: ~\nb: true\n").unwrap(), "|true");
+        assert_eq!(render("{{#each a}}x{{/each}}", "a:\n").unwrap(), "");
+        assert_eq!(
+            render("{{#each .}}{{@key}}={{.}};{{/each}}", "a.b: 1\nc: 2\n").unwrap(),
+            "a.b=1;c=2;"
+        );
+    }
+
+    #[test]
+    fn test_render_errors() {
+        for (template, doc, message) in [
+            ("{{a}}", "a: [1]", "can't render sequence at 'a'"),
+            (
+                "{{#each a}}{{/each}}",
+                "a: 1",
+                "can't iterate over int at 'a'",
+            ),
+            (
+                "{{#each a}}{{b}}{{/each}}",
+                "a: [{c: 1}]",
+                "invalid path 'a.0.b'",
+            ),
+            ("{{@key}}", "a: 1", "can only be used inside '#each'"),
+        ] {
+            let error = render(template, doc).unwrap_err();
+            assert!(error.contains(message), "{}: {}", template, error);
+        }
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for (template, message) in [
+            ("a\n{{b", "line 2, unclosed '{{'"),
+            (
+                "{{#each a}}\n{{#each b}}{{/each}}",
+                "line 1, '#each a' is never closed",
+            ),
+            ("{{/each}}", "'{{/each}}' without '#each'"),
+            ("{{#if a}}", "unknown block tag '#if a'"),
+            ("{{ }}", "empty tag"),
+        ] {
+            let error = Template::parse(template).err().unwrap();
+            assert!(error.contains(message), "{}: {}", template, error);
+        }
+    }
+}
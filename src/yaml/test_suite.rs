@@ -0,0 +1,560 @@
+//! Test suites of assertions on documents.
+//!
+//! A suite is a YAML file listing assertions on the values found at paths
+//! of a document:
+//!
+//! ```yaml
+//! name: web config
+//! setup: [base.yaml]
+//! tests:
+//!   - name: three replicas
+//!     path: spec.replicas
+//!     op: equals
+//!     expected: 3
+//!   - path: image.tag
+//!     op: matches
+//!     expected: '^1\.2[0-9]$'
+//!   - path: debug
+//!     op: missing
+//!   - name: production overlay
+//!     setup: [prod.yaml]
+//!     path: spec.replicas
+//!     expected: 5
+//! ```
+//!
+//! `op` is one of `equals` (the default when `expected` is given),
+//! `not-equals`, `exists` (the default otherwise), `missing`, `type` (a
+//! `get-type` name), `matches` (a regex on the raw value), `length`,
+//! `truthy` and `falsy`. `path` defaults to the document root, `name` to a
+//! description of the assertion.
+//!
+//! `truthy` and `falsy` read scalars as written with the truthiness table
+//! of `--truthy` and `--falsy`, unless the suite has its own `truthy` and
+//! `falsy` word lists.
+//!
+//! `setup` overlays, relative to the suite file, are applied to the
+//! document as with `apply` before checking it: those of the suite before
+//! all the assertions, and those of an assertion before it only.
+
+use super::error::Error;
+use super::merge::{apply, URL_TIMEOUT};
+use super::query::{get_at_path, value_to_type_name};
+use super::serialize::{serialize_inline, serialize_raw, serialize_raw_ref};
+use super::truth::{source_text, Truthiness};
+use super::{document_to_value, InnerValue, Options};
+use fyaml::{Document, Value, ValueRef};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Equals,
+    NotEquals,
+    Exists,
+    Missing,
+    Type,
+    Matches,
+    Length,
+    Truthy,
+    Falsy,
+}
+
+const OPS: &[(&str, Op)] = &[
+    ("equals", Op::Equals),
+    ("not-equals", Op::NotEquals),
+    ("exists", Op::Exists),
+    ("missing", Op::Missing),
+    ("type", Op::Type),
+    ("matches", Op::Matches),
+    ("length", Op::Length),
+    ("truthy", Op::Truthy),
+    ("falsy", Op::Falsy),
+];
+
+const TYPE_NAMES: &[&str] = &[
+    "str", "int", "float", "bool", "sequence", "struct", "NoneType",
+];
+
+impl Op {
+    fn name(self) -> &'static str {
+        OPS.iter().find(|(_, op)| *op == self).unwrap().0
+    }
+}
+
+struct Assertion {
+    name: String,
+    path: Option<String>,
+    op: Op,
+    expected: Value,
+    /// Compiled `expected` of `matches`
+    regex: Option<Regex>,
+    setup: Vec<String>,
+}
+
+/// Result of an assertion of a suite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub name: String,
+    /// Why the assertion failed, `None` if it passed
+    pub failure: Option<String>,
+}
+
+/// Assertions read from a test suite file.
+pub struct TestSuite {
+    pub name: String,
+    setup: Vec<String>,
+    /// Table of the `truthy` and `falsy` entries, the one of the options
+    /// if the suite has neither
+    truthiness: Option<Truthiness>,
+    tests: Vec<Assertion>,
+}
+
+/// Overlay files of a `setup` entry, relative to the directory of the
+/// suite file.
+fn setup_files(test: &Value, dir: &Path) -> Result<Vec<String>, String> {
+    let files = match test.get("setup").map(InnerValue::inner) {
+        None => return Ok(Vec::new()),
+        Some(Value::String(file)) => vec![file.as_str()],
+        Some(Value::Sequence(files)) => files
+            .iter()
+            .map(|file| file.inner().as_str())
+            .collect::<Option<_>>()
+            .ok_or_else(|| "expected 'setup' files to be strings".to_string())?,
+        Some(_) => return Err("expected 'setup' to be a file or a sequence of files".to_string()),
+    };
+    Ok(files
+        .into_iter()
+        .map(|file| dir.join(file).to_string_lossy().into_owned())
+        .collect())
+}
+
+/// Words of a `truthy` or `falsy` entry as written, none if it is
+/// missing.
+fn truth_words(suite: Option<ValueRef<'_>>, entry: &str) -> Result<Vec<String>, String> {
+    match suite.and_then(|suite| suite.get(entry)) {
+        None => Ok(Vec::new()),
+        Some(words) if words.is_sequence() => Ok(words.seq_iter().map(serialize_raw_ref).collect()),
+        Some(word) if !word.is_mapping() => Ok(vec![serialize_raw_ref(word)]),
+        Some(_) => Err(format!("expected '{}' to be a sequence of words", entry)),
+    }
+}
+
+/// Apply `setup` overlays to a document.
+fn set_up(doc: &Value, setup: &[String], opts: &Options) -> Result<Value, Error> {
+    apply(
+        setup,
+        &HashMap::new(),
+        doc.clone(),
+        URL_TIMEOUT,
+        false,
+        opts,
+    )
+}
+
+impl TestSuite {
+    /// Read a test suite file, named after the file unless it sets `name`.
+    pub fn from_file(file: &str) -> Result<Self, Error> {
+        let content = std::fs::read_to_string(file)
+            .map_err(|e| Error::Io(format!("Failed to read '{}': {}", file, e)))?;
+        let suite = Document::parse_str(&content)
+            .map_err(|e| Error::parse_in(&format!("test suite '{}'", file), &content, e))?;
+        Self::new(&suite, file)
+    }
+
+    fn new(source: &Document, file: &str) -> Result<Self, Error> {
+        let invalid =
+            |reason: String| Error::Base(format!("invalid test suite '{}', {}.", file, reason));
+        let dir = Path::new(file).parent().unwrap_or(Path::new(""));
+        let suite = document_to_value(source)?;
+        let setup = setup_files(suite.inner(), dir).map_err(invalid)?;
+        let truthy = truth_words(source.root_value(), "truthy").map_err(invalid)?;
+        let falsy = truth_words(source.root_value(), "falsy").map_err(invalid)?;
+        let truthiness = match truthy.is_empty() && falsy.is_empty() {
+            true => None,
+            false => Some(Truthiness::new(truthy, falsy)?),
+        };
+        let tests = suite
+            .inner()
+            .get("tests")
+            .and_then(|tests| tests.inner().as_sequence())
+            .ok_or_else(|| invalid("expected a 'tests' sequence".to_string()))?;
+        let tests = tests
+            .iter()
+            .enumerate()
+            .map(|(i, test)| {
+                Assertion::new(test.inner(), dir)
+                    .map_err(|e| invalid(format!("test {}, {}", i + 1, e)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(TestSuite {
+            name: suite
+                .inner()
+                .get("name")
+                .map(serialize_raw)
+                .unwrap_or_else(|| file.to_string()),
+            setup,
+            truthiness,
+            tests,
+        })
+    }
+
+    /// Check every assertion of the suite on a document, read from
+    /// `source` if it comes from one, failing if the `setup` of the suite
+    /// can't be applied to it.
+    pub fn run(
+        &self,
+        doc: &Value,
+        source: Option<&Document>,
+        opts: &Options,
+    ) -> Result<Vec<Outcome>, Error> {
+        let doc = set_up(doc, &self.setup, opts)?;
+        let truthiness = self.truthiness.as_ref().unwrap_or(&opts.truthiness);
+        Ok(self
+            .tests
+            .iter()
+            .map(|test| {
+                let result = match test.setup.is_empty() {
+                    true => test.check(&doc, source, truthiness, opts),
+                    false => set_up(&doc, &test.setup, opts)
+                        .map_err(|e| format!("setup failed, {}", e))
+                        .and_then(|doc| test.check(&doc, source, truthiness, opts)),
+                };
+                Outcome {
+                    name: test.name.clone(),
+                    failure: result.err(),
+                }
+            })
+            .collect())
+    }
+}
+
+impl Assertion {
+    fn new(test: &Value, dir: &Path) -> Result<Self, String> {
+        if !test.is_mapping() {
+            return Err("expected a mapping".to_string());
+        }
+        let setup = setup_files(test, dir)?;
+        let path = test.get("path").map(serialize_raw);
+        let expected = test.get("expected");
+        let op = match test.get("op").map(serialize_raw) {
+            Some(name) => OPS
+                .iter()
+                .find(|(n, _)| *n == name)
+                .map(|(_, op)| *op)
+                .ok_or_else(|| format!("unknown op '{}'", name))?,
+            None if expected.is_some() => Op::Equals,
+            None => Op::Exists,
+        };
+
+        let takes_expected = !matches!(op, Op::Exists | Op::Missing | Op::Truthy | Op::Falsy);
+        let expected = match (takes_expected, expected) {
+            (true, Some(expected)) => expected.clone(),
+            (false, None) => Value::Null,
+            (true, None) => return Err(format!("op '{}' needs an 'expected' value", op.name())),
+            (false, Some(_)) => {
+                return Err(format!(
+                    "op '{}' doesn't take an 'expected' value",
+                    op.name()
+                ))
+            }
+        };
+        let regex = match op {
+            Op::Type if !TYPE_NAMES.contains(&serialize_raw(&expected).as_str()) => {
+                return Err(format!(
+                    "expected a type name among {}",
+                    TYPE_NAMES.join(", ")
+                ))
+            }
+            Op::Length if expected.as_u64().is_none() => {
+                return Err("expected a length".to_string())
+            }
+            Op::Matches => Some(
+                Regex::new(&serialize_raw(&expected))
+                    .map_err(|e| format!("invalid regex: {}", e))?,
+            ),
+            _ => None,
+        };
+
+        let name = match test.get("name") {
+            Some(name) => serialize_raw(name),
+            None => {
+                let path = path.as_deref().unwrap_or("document");
+                match op {
+                    Op::Exists | Op::Missing | Op::Truthy | Op::Falsy => {
+                        format!("{} {}", path, op.name())
+                    }
+                    _ => format!("{} {} {}", path, op.name(), serialize_inline(&expected)),
+                }
+            }
+        };
+        Ok(Assertion {
+            name,
+            path,
+            op,
+            expected,
+            regex,
+            setup,
+        })
+    }
+
+    fn check(
+        &self,
+        doc: &Value,
+        source: Option<&Document>,
+        truthiness: &Truthiness,
+        opts: &Options,
+    ) -> Result<(), String> {
+        let value = match (self.op, get_at_path(doc, self.path.as_deref(), opts)) {
+            (Op::Missing, Ok(value)) => {
+                return Err(format!(
+                    "expected no value, got {}",
+                    serialize_inline(value)
+                ))
+            }
+            (Op::Missing, Err(_)) => return Ok(()),
+            (_, Err(e)) => return Err(e.to_string()),
+            (_, Ok(value)) => value,
+        };
+        let expected = &self.expected;
+        let got = || serialize_inline(value);
+        match self.op {
+            Op::Exists | Op::Missing => Ok(()),
+            Op::Equals if value.inner() == expected.inner() => Ok(()),
+            Op::Equals => Err(format!(
+                "expected {}, got {}",
+                serialize_inline(expected),
+                got()
+            )),
+            Op::NotEquals if value.inner() != expected.inner() => Ok(()),
+            Op::NotEquals => Err(format!("expected a value other than {}", got())),
+            Op::Type => {
+                let (expected, found) = (serialize_raw(expected), value_to_type_name(value));
+                match expected == found {
+                    true => Ok(()),
+                    false => Err(format!("expected type '{}', got '{}'", expected, found)),
+                }
+            }
+            Op::Matches => match self.regex.as_ref() {
+                Some(regex) if regex.is_match(&serialize_raw(value)) => Ok(()),
+                _ => Err(format!(
+                    "expected to match '{}', got {}",
+                    serialize_raw(expected),
+                    got()
+                )),
+            },
+            Op::Length => {
+                let length = match value.inner() {
+                    Value::Sequence(items) => items.len(),
+                    Value::Mapping(entries) => entries.len(),
+                    _ => {
+                        return Err(format!(
+                            "expected a sequence or struct, got '{}'",
+                            value_to_type_name(value)
+                        ))
+                    }
+                };
+                match expected.as_u64() == Some(length as u64) {
+                    true => Ok(()),
+                    false => Err(format!(
+                        "expected length {}, got {}",
+                        serialize_raw(expected),
+                        length
+                    )),
+                }
+            }
+            Op::Truthy | Op::Falsy => {
+                let text = match value.inner() {
+                    Value::Sequence(_) | Value::Mapping(_) => None,
+                    scalar => Some(
+                        source
+                            .and_then(|source| {
+                                source_text(source, self.path.as_deref(), value, opts)
+                            })
+                            .unwrap_or_else(|| serialize_raw(scalar)),
+                    ),
+                };
+                let found = text.as_deref().and_then(|text| truthiness.of(text));
+                match found == Some(self.op == Op::Truthy) {
+                    true => Ok(()),
+                    false => Err(format!(
+                        "expected a {} value, got {}",
+                        self.op.name(),
+                        text.map_or_else(got, |text| format!("'{}'", text))
+                    )),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(suite: &str, doc: &str) -> Vec<String> {
+        run_with(suite, doc, &Options::default())
+    }
+
+    fn run_with(suite: &str, doc: &str, opts: &Options) -> Vec<String> {
+        let suite = TestSuite::new(&Document::parse_str(suite).unwrap(), "suite.yaml").unwrap();
+        let source = Document::parse_str(doc).unwrap();
+        suite
+            .run(&document_to_value(&source).unwrap(), Some(&source), opts)
+            .unwrap()
+            .iter()
+            .map(|o| match &o.failure {
+                Some(failure) => format!("FAIL {}: {}", o.name, failure),
+                None => format!("PASS {}", o.name),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_ops() {
+        let suite = r"
+tests:
+  - {path: a, expected: 1}
+  - {path: a, op: not-equals, expected: 1}
+  - {path: b, op: exists}
+  - {path: c}
+  - {path: b, op: missing}
+  - {path: c, op: missing}
+  - {path: b, op: type, expected: sequence}
+  - {path: s, op: matches, expected: '^v\d+$'}
+  - {path: s, op: matches, expected: '^\d+$'}
+  - {path: b, op: length, expected: 2}
+  - {path: a, op: length, expected: 2}
+";
+        assert_eq!(
+            run(suite, "a: 1\nb: [x, y]\ns: v12\n"),
+            vec![
+                "PASS a equals 1",
+                "FAIL a not-equals 1: expected a value other than 1",
+                "PASS b exists",
+                "FAIL c exists: invalid path 'c', missing key 'c' in struct.",
+                "FAIL b missing: expected no value, got ['x', 'y']",
+                "PASS c missing",
+                "PASS b type 'sequence'",
+                r"PASS s matches '^v\d+$'",
+                r"FAIL s matches '^\d+$': expected to match '^\d+$', got 'v12'",
+                "PASS b length 2",
+                "FAIL a length 2: expected a sequence or struct, got 'int'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_equals_is_typed() {
+        let suite = "tests:\n  - {name: port, path: port, expected: '80'}\n  - {path: ratio, expected: 0.5}\n";
+        assert_eq!(
+            run(suite, "port: 80\nratio: 0.50\n"),
+            vec!["FAIL port: expected '80', got 80", "PASS ratio equals 0.5"]
+        );
+    }
+
+    #[test]
+    fn test_truthiness() {
+        let tests = "tests:\n  - {path: a, op: truthy}\n  - {path: b, op: falsy}\n  - {path: c, op: truthy}\n";
+        assert_eq!(
+            run(tests, "a: true\nb: off\nc: [1]\n"),
+            vec![
+                "PASS a truthy",
+                "FAIL b falsy: expected a falsy value, got 'off'",
+                "FAIL c truthy: expected a truthy value, got [1]",
+            ]
+        );
+        let suite = format!("truthy: [yes, on]\nfalsy: [no, off]\n{}", tests);
+        assert_eq!(
+            run(&suite, "a: on\nb: off\nc: true\n"),
+            vec![
+                "PASS a truthy",
+                "PASS b falsy",
+                "FAIL c truthy: expected a truthy value, got 'true'",
+            ]
+        );
+        // Without a table of its own, the suite uses the one of the options
+        let opts = Options {
+            truthiness: Truthiness::parse(Some("on"), Some("off")).unwrap(),
+            ..Options::default()
+        };
+        assert_eq!(
+            run_with(tests, "a: on\nb: off\nc: true\n", &opts),
+            vec![
+                "PASS a truthy",
+                "PASS b falsy",
+                "FAIL c truthy: expected a truthy value, got 'true'",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_suites() {
+        for (suite, message) in [
+            ("a: 1", "expected a 'tests' sequence"),
+            ("tests: [1]", "test 1, expected a mapping"),
+            ("tests: [{op: nope}]", "test 1, unknown op 'nope'"),
+            ("tests: [{op: type}]", "op 'type' needs an 'expected' value"),
+            (
+                "tests: [{op: missing, expected: 1}]",
+                "doesn't take an 'expected' value",
+            ),
+            (
+                "tests: [{op: type, expected: list}]",
+                "expected a type name",
+            ),
+            ("tests: [{op: matches, expected: '('}]", "invalid regex"),
+            (
+                "{setup: {a: 1}, tests: []}",
+                "expected 'setup' to be a file",
+            ),
+            (
+                "tests: [{setup: [1]}]",
+                "test 1, expected 'setup' files to be strings",
+            ),
+        ] {
+            let error = TestSuite::new(&Document::parse_str(suite).unwrap(), "suite.yaml")
+                .err()
+                .unwrap();
+            assert!(error.to_string().contains(message), "{}: {}", suite, error);
+        }
+    }
+
+    #[test]
+    fn test_setup_overlays() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("base.yaml"), "replicas: 2\n").unwrap();
+        std::fs::write(tmp.path().join("prod.yaml"), "replicas: 5\n").unwrap();
+        std::fs::write(tmp.path().join("bad.yaml"), "replicas: [5]\n").unwrap();
+        let file = tmp.path().join("suite.yaml");
+        std::fs::write(
+            &file,
+            "setup: base.yaml\ntests:\n  - {path: replicas, expected: 2}\n  \
+             - {name: prod, setup: [prod.yaml], path: replicas, expected: 5}\n  \
+             - {name: bad, setup: [bad.yaml], path: replicas, expected: 5}\n",
+        )
+        .unwrap();
+        let suite = TestSuite::from_file(file.to_str().unwrap()).unwrap();
+        let outcomes = suite
+            .run(&"name: web\n".parse().unwrap(), None, &Options::default())
+            .unwrap();
+        assert_eq!(outcomes[0].failure, None);
+        assert_eq!(outcomes[1].failure, None);
+        let failure = outcomes[2].failure.as_deref().unwrap();
+        assert!(
+            failure.starts_with("setup failed, ") && failure.contains("cannot merge"),
+            "{}",
+            failure
+        );
+
+        // The suite's setup must apply to every document
+        let error = suite
+            .run(
+                &"replicas: [1]\n".parse().unwrap(),
+                None,
+                &Options::default(),
+            )
+            .unwrap_err();
+        assert!(error.to_string().contains("cannot merge"), "{}", error);
+    }
+}
@@ -0,0 +1,172 @@
+//! Timestamps of the YAML 1.1 `!!timestamp` type.
+//!
+//! Plain scalars like `2001-12-14` or `2001-12-14T21:59:43.10-05:00` are
+//! timestamps for `get-type`, and `get-value --date-format` writes them as
+//! Unix epoch seconds, as RFC 3339 or with a `time` format description.
+//! Timestamps without a time are at midnight, and those without a time
+//! zone are in UTC, as the YAML 1.1 type specifies.
+
+use super::error::Error;
+use fyaml::{Number, Value};
+use regex::Regex;
+use std::sync::LazyLock;
+use time::format_description::well_known::Rfc3339;
+use time::{Date, Month, OffsetDateTime, PrimitiveDateTime, Time, UtcOffset};
+
+/// Tags of explicit timestamps, short and expanded.
+const TIMESTAMP_TAGS: &[&str] = &["!!timestamp", "tag:yaml.org,2002:timestamp"];
+
+static TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^([0-9]{4})-([0-9]{1,2})-([0-9]{1,2})(?:(?:[Tt]|[ \t]+)([0-9]{1,2}):([0-9]{2}):([0-9]{2})(?:\.([0-9]*))?(?:[ \t]*(Z|([-+])([0-9]{1,2})(?::([0-9]{2}))?))?)?$",
+    )
+    .expect("valid timestamp regex")
+});
+
+/// Whether `tag` is the tag of timestamps.
+#[must_use]
+pub fn is_timestamp_tag(tag: &str) -> bool {
+    TIMESTAMP_TAGS.contains(&tag)
+}
+
+/// Whether the text of a plain scalar is a timestamp.
+#[must_use]
+pub fn is_timestamp(text: &str) -> bool {
+    parse_timestamp(text).is_some()
+}
+
+/// Date and time of a timestamp, `None` if the text isn't one.
+fn parse_timestamp(text: &str) -> Option<OffsetDateTime> {
+    let caps = TIMESTAMP.captures(text)?;
+    let number = |i: usize| caps.get(i).map(|m| m.as_str().parse::<u32>().ok());
+    // A date alone has two digit months and days
+    if caps.get(4).is_none() && (caps[2].len() != 2 || caps[3].len() != 2) {
+        return None;
+    }
+    let month = Month::try_from(number(2)?? as u8).ok()?;
+    let date = Date::from_calendar_date(number(1)?? as i32, month, number(3)?? as u8).ok()?;
+    let nanos = caps.get(7).map_or(0, |m| {
+        let digits: String = m
+            .as_str()
+            .chars()
+            .chain("000000000".chars())
+            .take(9)
+            .collect();
+        digits.parse().unwrap_or(0)
+    });
+    let time = match caps.get(4) {
+        None => Time::MIDNIGHT,
+        Some(_) => Time::from_hms_nano(
+            number(4)?? as u8,
+            number(5)?? as u8,
+            number(6)?? as u8,
+            nanos,
+        )
+        .ok()?,
+    };
+    let offset = match caps.get(9) {
+        None => UtcOffset::UTC,
+        Some(sign) => {
+            let sign = if sign.as_str() == "-" { -1 } else { 1 };
+            let hours = number(10)?? as i8;
+            let minutes = number(11).unwrap_or(Some(0))? as i8;
+            UtcOffset::from_hms(sign * hours, sign * minutes, 0).ok()?
+        }
+    };
+    Some(PrimitiveDateTime::new(date, time).assume_offset(offset))
+}
+
+/// Write the timestamp at `path` with `format`: `epoch` for Unix epoch
+/// seconds (an integer), `rfc3339`, or a `time` format description like
+/// `[year]/[month]/[day]`.
+///
+/// # Errors
+///
+/// Returns an error if the text isn't a timestamp or the format is invalid.
+pub fn format_timestamp(text: &str, format: &str, path: &str) -> Result<Value, Error> {
+    let timestamp = parse_timestamp(text).ok_or_else(|| {
+        Error::type_error_at(
+            path,
+            format!(
+                "invalid value at '{}', expected a timestamp, got '{}'.",
+                path, text
+            ),
+        )
+    })?;
+    let invalid_format =
+        |e: &dyn std::fmt::Display| Error::Base(format!("invalid date format '{}': {}", format, e));
+    Ok(match format {
+        "epoch" => Value::Number(Number::Int(timestamp.unix_timestamp())),
+        "rfc3339" => Value::String(timestamp.format(&Rfc3339).map_err(|e| invalid_format(&e))?),
+        _ => {
+            let description =
+                time::format_description::parse(format).map_err(|e| invalid_format(&e))?;
+            Value::String(
+                timestamp
+                    .format(&description)
+                    .map_err(|e| invalid_format(&e))?,
+            )
+        }
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::serialize_raw;
+
+    fn format(text: &str, format: &str) -> String {
+        serialize_raw(&format_timestamp(text, format, "t").unwrap())
+    }
+
+    #[test]
+    fn test_is_timestamp() {
+        for text in [
+            "2001-12-14",
+            "2001-12-14t21:59:43.10-05:00",
+            "2001-12-14 21:59:43.10 -5",
+            "2001-12-15T02:59:43.1Z",
+            "2001-12-15 2:59:43.10",
+        ] {
+            assert!(is_timestamp(text), "{}", text);
+        }
+        for text in [
+            "2001-1-14",
+            "2001-13-01",
+            "2001-12-14T25:00:00",
+            "today",
+            "1.5",
+        ] {
+            assert!(!is_timestamp(text), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format("2001-12-14", "epoch"), "1008288000");
+        assert_eq!(
+            format("2001-12-14 21:59:43.10 -5", "rfc3339"),
+            "2001-12-14T21:59:43.1-05:00"
+        );
+        assert_eq!(
+            format("2001-12-14t21:59:43.10-05:00", "epoch"),
+            "1008385183"
+        );
+        assert_eq!(format("2001-12-14", "[day]/[month]/[year]"), "14/12/2001");
+    }
+
+    #[test]
+    fn test_format_timestamp_errors() {
+        let err = format_timestamp("soon", "epoch", "when").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid value at 'when', expected a timestamp, got 'soon'."
+        );
+        let err = format_timestamp("2001-12-14", "[nope]", "when").unwrap_err();
+        assert!(err.to_string().starts_with("invalid date format '[nope]'"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Conversion between TOML documents and values.
+//!
+//! TOML maps onto the value model, except for its datetimes, which are read
+//! as strings. The other way round, TOML has no null and its documents are
+//! tables, so such values can't be written.
+
+use super::error::Error;
+use super::path::{join_elements, join_path};
+use super::query::value_to_type_name;
+use super::serialize::serialize_raw;
+use fyaml::{Number, Value};
+
+/// Parse a TOML document into a value.
+pub fn from_toml(input: &str) -> Result<Value, Error> {
+    let table: ::toml::Table = input
+        .parse()
+        .map_err(|e| Error::Parse(format!("Failed to parse TOML: {}", e)))?;
+    Ok(table_to_value(table))
+}
+
+fn table_to_value(table: ::toml::Table) -> Value {
+    Value::Mapping(
+        table
+            .into_iter()
+            .map(|(k, v)| (Value::String(k), toml_to_value(v)))
+            .collect(),
+    )
+}
+
+fn toml_to_value(value: ::toml::Value) -> Value {
+    match value {
+        ::toml::Value::String(s) => Value::String(s),
+        ::toml::Value::Integer(i) => Value::Number(Number::Int(i)),
+        ::toml::Value::Float(f) => Value::Number(Number::Float(f)),
+        ::toml::Value::Boolean(b) => Value::Bool(b),
+        ::toml::Value::Datetime(d) => Value::String(d.to_string()),
+        ::toml::Value::Array(a) => Value::Sequence(a.into_iter().map(toml_to_value).collect()),
+        ::toml::Value::Table(t) => table_to_value(t),
+    }
+}
+
+/// Serialize a value as a TOML document.
+///
+/// Tags are dropped and mapping keys are written as their raw string. Like
+/// `serialize()`, the output has no trailing newline.
+pub fn serialize_toml(value: &Value) -> Result<String, Error> {
+    match value_to_toml(value, "")? {
+        ::toml::Value::Table(table) => ::toml::to_string(&table)
+            .map(|s| s.trim_end_matches('\n').to_string())
+            .map_err(|e| Error::Base(format!("Failed to serialize TOML: {}", e))),
+        _ => Err(Error::type_error(format!(
+            "cannot write a '{}' as TOML, documents must be a struct.",
+            value_to_type_name(value)
+        ))),
+    }
+}
+
+/// Path of a child, for error messages.
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        join_elements(&[key])
+    } else {
+        join_path(path, &[key])
+    }
+}
+
+fn value_to_toml(value: &Value, path: &str) -> Result<::toml::Value, Error> {
+    Ok(match value {
+        Value::Null => {
+            return Err(Error::type_error_at(
+                path,
+                format!(
+                    "cannot write null value at '{}' as TOML, which has no null.",
+                    path
+                ),
+            ))
+        }
+        Value::Bool(b) => ::toml::Value::Boolean(*b),
+        Value::Number(Number::Int(i)) => ::toml::Value::Integer(*i),
+        Value::Number(Number::UInt(u)) => {
+            ::toml::Value::Integer(i64::try_from(*u).map_err(|_| {
+                Error::type_error_at(
+                    path,
+                    format!(
+                        "cannot write {} at '{}' as TOML, integers are limited to 64 bits signed.",
+                        u, path
+                    ),
+                )
+            })?)
+        }
+        Value::Number(Number::Float(f)) => ::toml::Value::Float(*f),
+        Value::String(s) => ::toml::Value::String(s.clone()),
+        Value::Sequence(seq) => ::toml::Value::Array(
+            seq.iter()
+                .enumerate()
+                .map(|(i, v)| value_to_toml(v, &child_path(path, &i.to_string())))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Mapping(map) => ::toml::Value::Table(
+            map.iter()
+                .map(|(k, v)| {
+                    let key = serialize_raw(k);
+                    let child = value_to_toml(v, &child_path(path, &key))?;
+                    Ok((key, child))
+                })
+                .collect::<Result<_, Error>>()?,
+        ),
+        Value::Tagged(tagged) => value_to_toml(&tagged.value, path)?,
+    })
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::yaml::serialize;
+
+    fn yaml(input: &str) -> Value {
+        input.parse().unwrap()
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let value = from_toml(
+            "name = \"web\"\nport = 80\n\n[db]\nhost = \"localhost\"\nsince = 2024-01-31\n",
+        )
+        .unwrap();
+        assert_eq!(
+            serialize(&value).unwrap(),
+            "name: web\nport: 80\ndb:\n  host: localhost\n  since: 2024-01-31"
+        );
+    }
+
+    #[test]
+    fn test_from_toml_invalid() {
+        let err = from_toml("a = ").unwrap_err();
+        assert!(err.to_string().starts_with("Failed to parse TOML"));
+    }
+
+    #[test]
+    fn test_serialize_toml_keeps_order() {
+        let value = yaml("z: 1\na:\n  b: [1, 2]\nm: true\n");
+        assert_eq!(
+            serialize_toml(&value).unwrap(),
+            "z = 1\nm = true\n\n[a]\nb = [1, 2]"
+        );
+    }
+
+    #[test]
+    fn test_serialize_toml_rejects_null() {
+        let err = serialize_toml(&yaml("a:\n  b:\n")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "cannot write null value at 'a.b' as TOML, which has no null."
+        );
+    }
+
+    #[test]
+    fn test_serialize_toml_rejects_non_table() {
+        let err = serialize_toml(&yaml("[1, 2]")).unwrap_err();
+        assert!(err.to_string().contains("documents must be a struct"));
+    }
+}
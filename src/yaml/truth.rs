@@ -0,0 +1,200 @@
+//! Truthiness of scalars.
+//!
+//! YAML 1.1 reads `yes`, `on` or `y` as booleans where YAML 1.2 only knows
+//! `true` and `false`: the words read as true or false when a boolean is
+//! expected (`get-value --as-bool`, the `truthy` and `falsy` ops of test
+//! suites) come from a table, YAML 1.2 by default, that `--truthy` and
+//! `--falsy` or the `truthy` and `falsy` entries of a test suite replace.
+//!
+//! Words are compared with scalars as written: values read `off` as the
+//! boolean false, so the text is taken from the source document (see
+//! [`source_text`]).
+
+use super::error::Error;
+use super::query::get_value_ref;
+use super::serialize::serialize_raw_ref;
+use super::Options;
+use fyaml::{Document, Value};
+
+/// Words read as true and as false, compared with the raw text of
+/// scalars.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Truthiness {
+    truthy: Vec<String>,
+    falsy: Vec<String>,
+}
+
+impl Default for Truthiness {
+    /// The booleans of the YAML 1.2 core schema.
+    fn default() -> Self {
+        let words = |words: &[&str]| words.iter().map(|w| w.to_string()).collect();
+        Truthiness {
+            truthy: words(&["true", "True", "TRUE"]),
+            falsy: words(&["false", "False", "FALSE"]),
+        }
+    }
+}
+
+impl Truthiness {
+    /// A table of the given words, the default ones being kept for a side
+    /// given no word.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a word is empty or is both truthy and falsy.
+    pub fn new(truthy: Vec<String>, falsy: Vec<String>) -> Result<Self, Error> {
+        let default = Truthiness::default();
+        let table = Truthiness {
+            truthy: if truthy.is_empty() {
+                default.truthy
+            } else {
+                truthy
+            },
+            falsy: if falsy.is_empty() {
+                default.falsy
+            } else {
+                falsy
+            },
+        };
+        if table
+            .truthy
+            .iter()
+            .chain(&table.falsy)
+            .any(|w| w.is_empty())
+        {
+            return Err(Error::Base(
+                "invalid truthiness table, words can't be empty.".to_string(),
+            ));
+        }
+        if let Some(word) = table.truthy.iter().find(|w| table.falsy.contains(w)) {
+            return Err(Error::Base(format!(
+                "invalid truthiness table, '{}' is both truthy and falsy.",
+                word
+            )));
+        }
+        Ok(table)
+    }
+
+    /// A table of comma separated words, as given to `--truthy` and
+    /// `--falsy`.
+    pub fn parse(truthy: Option<&str>, falsy: Option<&str>) -> Result<Self, Error> {
+        let words = |list: Option<&str>| {
+            list.map_or(Vec::new(), |list| {
+                list.split(',').map(|w| w.trim().to_string()).collect()
+            })
+        };
+        Truthiness::new(words(truthy), words(falsy))
+    }
+
+    /// Boolean of the raw text of a scalar, `None` if it is in neither
+    /// list.
+    #[must_use]
+    pub fn of(&self, text: &str) -> Option<bool> {
+        if self.truthy.iter().any(|w| w == text) {
+            Some(true)
+        } else if self.falsy.iter().any(|w| w == text) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    /// Boolean of the raw text of the scalar at `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a type error if the text is in neither list.
+    pub fn to_bool(&self, text: &str, path: &str) -> Result<bool, Error> {
+        self.of(text).ok_or_else(|| {
+            Error::type_error_at(
+                path,
+                format!(
+                    "'{}' at '{}' is neither truthy ({}) nor falsy ({}).",
+                    text,
+                    path,
+                    self.truthy.join(", "),
+                    self.falsy.join(", ")
+                ),
+            )
+        })
+    }
+}
+
+/// Raw text of the scalar at `path` of `doc`, if `value` is still the
+/// value read there.
+#[must_use]
+pub fn source_text(
+    doc: &Document,
+    path: Option<&str>,
+    value: &Value,
+    opts: &Options,
+) -> Option<String> {
+    let node = get_value_ref(path, doc, opts).ok()?;
+    if node.is_mapping() || node.is_sequence() {
+        return None;
+    }
+    let read = Value::from_node_ref(node.as_node()).ok()?;
+    (read == *value).then(|| serialize_raw_ref(node))
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_table() {
+        let table = Truthiness::default();
+        assert_eq!(table.of("true"), Some(true));
+        assert_eq!(table.of("FALSE"), Some(false));
+        assert_eq!(table.of("yes"), None);
+        assert_eq!(
+            table.to_bool("on", "a").unwrap_err().to_string(),
+            "'on' at 'a' is neither truthy (true, True, TRUE) nor falsy (false, False, FALSE)."
+        );
+    }
+
+    #[test]
+    fn test_pinned_table() {
+        let table = Truthiness::parse(Some("yes, on,1"), Some("no,off,0")).unwrap();
+        assert_eq!(table.of("on"), Some(true));
+        assert_eq!(table.of("0"), Some(false));
+        assert_eq!(table.of("true"), None);
+
+        // A side given no word keeps the default words
+        let table = Truthiness::parse(Some("yes"), None).unwrap();
+        assert_eq!(table.of("yes"), Some(true));
+        assert_eq!(table.of("false"), Some(false));
+    }
+
+    #[test]
+    fn test_invalid_tables() {
+        let err = Truthiness::parse(Some("yes,1"), Some("1")).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid truthiness table, '1' is both truthy and falsy."
+        );
+        assert!(Truthiness::parse(Some("yes,,on"), None).is_err());
+    }
+
+    #[test]
+    fn test_source_text() {
+        let doc = Document::parse_str("a: off\nb: [1]\n").unwrap();
+        assert_eq!(
+            source_text(&doc, Some("a"), &Value::Bool(false), &Options::default()).as_deref(),
+            Some("off")
+        );
+        // Changed since it was read
+        assert_eq!(
+            source_text(&doc, Some("a"), &Value::Bool(true), &Options::default()),
+            None
+        );
+        assert_eq!(
+            source_text(&doc, Some("b"), &Value::Null, &Options::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,429 @@
+//! Update operations on scalar values.
+//!
+//! Provides the `update` action: read the scalar at a path, apply an
+//! arithmetic operation or a string edit to it and write the result back in
+//! a single pass.
+
+use super::doc_mutation::set_yaml_value_doc;
+use super::error::Error;
+use super::mutation::set_value;
+use super::query::{get_at_path, get_value_ref, value_ref_type_name, value_to_type_name};
+use super::serialize::{serialize, serialize_raw, serialize_raw_ref};
+use super::{InnerValue, Options};
+use fyaml::{Document, Number, Value};
+use regex::Regex;
+
+/// Operation applied by `update`.
+#[derive(Debug, Clone)]
+pub enum UpdateOp {
+    /// Add the operand
+    Add(Number),
+    /// Multiply by the operand
+    Mul(Number),
+    /// Keep the smaller of the value and the operand
+    Min(Number),
+    /// Keep the larger of the value and the operand
+    Max(Number),
+    /// Append a string
+    AppendStr(String),
+    /// Prepend a string
+    Prefix(String),
+    /// Replace all occurrences of a string
+    Replace(String, String),
+    /// Replace all matches of a regex (`$1`, `${name}` expand to captures)
+    ReplaceRegex(Regex, String),
+}
+
+impl UpdateOp {
+    fn is_arithmetic(&self) -> bool {
+        matches!(
+            self,
+            UpdateOp::Add(_) | UpdateOp::Mul(_) | UpdateOp::Min(_) | UpdateOp::Max(_)
+        )
+    }
+}
+
+/// Parse a number given on the command line, as an integer if possible.
+pub fn parse_number(s: &str) -> Result<Number, Error> {
+    if let Ok(i) = s.parse::<i64>() {
+        return Ok(Number::Int(i));
+    }
+    match s.parse::<f64>() {
+        Ok(f) if f.is_finite() => Ok(Number::Float(f)),
+        _ => Err(Error::Base(format!("invalid number '{}'.", s))),
+    }
+}
+
+/// Parse an `OLD/NEW` replacement spec.
+///
+/// The first unescaped `/` separates both parts, `\/` stands for a literal
+/// `/` in either of them. With `regex`, OLD is a regular expression. OLD
+/// must not be empty, nor a regex matching the empty string.
+pub fn parse_replace(spec: &str, regex: bool) -> Result<UpdateOp, Error> {
+    let mut parts = vec![String::new()];
+    let mut chars = spec.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.as_str().starts_with('/') => {
+                parts.last_mut().unwrap().push('/');
+                chars.next();
+            }
+            '/' if parts.len() == 1 => parts.push(String::new()),
+            _ => parts.last_mut().unwrap().push(c),
+        }
+    }
+    let [old, new]: [String; 2] = parts.try_into().map_err(|_| {
+        Error::Base(format!(
+            "invalid replacement '{}', expected 'OLD/NEW'.",
+            spec
+        ))
+    })?;
+
+    if !regex {
+        if old.is_empty() {
+            return Err(Error::Base(format!("empty OLD in replacement '{}'.", spec)));
+        }
+        return Ok(UpdateOp::Replace(old, new));
+    }
+    let re =
+        Regex::new(&old).map_err(|e| Error::Base(format!("invalid regex '{}': {}", old, e)))?;
+    // An empty match would insert NEW between every character
+    if re.is_match("") {
+        return Err(Error::Base(format!(
+            "regex '{}' of replacement matches the empty string.",
+            old
+        )));
+    }
+    Ok(UpdateOp::ReplaceRegex(re, new))
+}
+
+pub(super) fn as_f64(n: &Number) -> f64 {
+    match n {
+        Number::Int(i) => *i as f64,
+        Number::UInt(u) => *u as f64,
+        Number::Float(f) => *f,
+    }
+}
+
+pub(super) fn as_i64(n: &Number) -> Option<i64> {
+    match n {
+        Number::Int(i) => Some(*i),
+        Number::UInt(u) => i64::try_from(*u).ok(),
+        Number::Float(_) => None,
+    }
+}
+
+fn as_i128(n: &Number) -> Option<i128> {
+    match n {
+        Number::Int(i) => Some(i128::from(*i)),
+        Number::UInt(u) => Some(i128::from(*u)),
+        Number::Float(_) => None,
+    }
+}
+
+/// Combine `current` with the operand of an arithmetic operation.
+///
+/// Integer operands on integer values give an integer result, which must
+/// fit an `i64`, or a `u64` if positive; any float involved makes the
+/// result a float, which must be finite.
+fn compute(op: &UpdateOp, current: &Number, path: &str) -> Result<Number, Error> {
+    let operand = match op {
+        UpdateOp::Add(n) | UpdateOp::Mul(n) | UpdateOp::Min(n) | UpdateOp::Max(n) => n,
+        _ => unreachable!("string edits are applied by edit_str()"),
+    };
+
+    if let (Some(a), Some(b)) = (as_i128(current), as_i128(operand)) {
+        let result = match op {
+            UpdateOp::Add(_) => a.checked_add(b),
+            UpdateOp::Mul(_) => a.checked_mul(b),
+            UpdateOp::Min(_) => Some(a.min(b)),
+            _ => Some(a.max(b)),
+        };
+        return result
+            .and_then(|r| {
+                i64::try_from(r)
+                    .map(Number::Int)
+                    .or_else(|_| u64::try_from(r).map(Number::UInt))
+                    .ok()
+            })
+            .ok_or_else(|| Error::Base(format!("integer overflow updating '{}'.", path)));
+    }
+
+    let (a, b) = (as_f64(current), as_f64(operand));
+    let result = match op {
+        UpdateOp::Add(_) => a + b,
+        UpdateOp::Mul(_) => a * b,
+        UpdateOp::Min(_) => a.min(b),
+        _ => a.max(b),
+    };
+    if !result.is_finite() {
+        return Err(Error::Base(format!("float overflow updating '{}'.", path)));
+    }
+    Ok(Number::Float(result))
+}
+
+/// Apply a string edit to `current`.
+fn edit_str(op: &UpdateOp, current: &str) -> String {
+    match op {
+        UpdateOp::AppendStr(s) => format!("{}{}", current, s),
+        UpdateOp::Prefix(s) => format!("{}{}", s, current),
+        UpdateOp::Replace(old, new) => current.replace(old.as_str(), new),
+        UpdateOp::ReplaceRegex(re, new) => re.replace_all(current, new.as_str()).into_owned(),
+        _ => unreachable!("arithmetic operations are applied by compute()"),
+    }
+}
+
+/// Format a number as a YAML scalar, keeping floats recognizable as floats.
+pub(super) fn format_number(n: &Number) -> String {
+    match n {
+        Number::Int(i) => i.to_string(),
+        Number::UInt(u) => u.to_string(),
+        // Debug formatting always keeps a decimal point or exponent
+        Number::Float(f) => format!("{:?}", f),
+    }
+}
+
+fn type_error_update(op: &UpdateOp, type_name: &str) -> Error {
+    let expected = if op.is_arithmetic() {
+        "an int or float"
+    } else {
+        "a scalar"
+    };
+    Error::type_error(format!(
+        "update does not support '{}' type. Please provide or select {}.",
+        type_name, expected
+    ))
+}
+
+/// Update the scalar at path in place using Editor.
+pub fn update_doc(
+    doc: &mut Document,
+    path: &str,
+    op: &UpdateOp,
+    opts: &Options,
+) -> Result<(), Error> {
+    let value = get_value_ref(Some(path), doc, opts)?;
+    let type_name = value_ref_type_name(&value);
+
+    let yaml_value = if op.is_arithmetic() {
+        let current = match type_name {
+            "int" => value
+                .as_i64()
+                .map(Number::Int)
+                .or_else(|| value.as_u64().map(Number::UInt)),
+            "float" => value.as_f64().map(Number::Float),
+            _ => None,
+        }
+        .ok_or_else(|| type_error_update(op, type_name))?;
+        format_number(&compute(op, &current, path)?)
+    } else {
+        if !matches!(type_name, "str" | "int" | "float" | "bool") {
+            return Err(type_error_update(op, type_name));
+        }
+        // String edits always give a string, quoted if needed
+        let edited = edit_str(op, &serialize_raw_ref(value));
+        serialize(&Value::String(edited))?.trim_end().to_string()
+    };
+
+    set_yaml_value_doc(doc, path, &yaml_value, opts)
+}
+
+/// Update the scalar at path (owned version for command chains).
+pub fn update(path: &str, op: &UpdateOp, value: Value, opts: &Options) -> Result<Value, Error> {
+    let new_value = match get_at_path(&value, Some(path), opts)?.inner() {
+        Value::Number(n) if op.is_arithmetic() => Value::Number(compute(op, n, path)?),
+        current @ (Value::String(_) | Value::Number(_) | Value::Bool(_)) if !op.is_arithmetic() => {
+            Value::String(edit_str(op, &serialize_raw(current)))
+        }
+        other => return Err(type_error_update(op, value_to_type_name(other))),
+    };
+
+    set_value(path, new_value, value, opts)
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indexmap::indexmap;
+
+    #[test]
+    fn test_parse_number() {
+        assert_eq!(parse_number("3").unwrap(), Number::Int(3));
+        assert_eq!(parse_number("-2").unwrap(), Number::Int(-2));
+        assert_eq!(parse_number("1.5").unwrap(), Number::Float(1.5));
+        assert!(parse_number("abc").is_err());
+        assert!(parse_number("inf").is_err());
+    }
+
+    #[test]
+    fn test_compute_integers() {
+        let add = UpdateOp::Add(Number::Int(-1));
+        assert_eq!(compute(&add, &Number::Int(3), "a").unwrap(), Number::Int(2));
+        let mul = UpdateOp::Mul(Number::Int(2));
+        assert_eq!(compute(&mul, &Number::Int(3), "a").unwrap(), Number::Int(6));
+        let min = UpdateOp::Min(Number::Int(2));
+        assert_eq!(compute(&min, &Number::Int(3), "a").unwrap(), Number::Int(2));
+        let max = UpdateOp::Max(Number::Int(5));
+        assert_eq!(compute(&max, &Number::Int(3), "a").unwrap(), Number::Int(5));
+    }
+
+    #[test]
+    fn test_compute_float_promotion() {
+        let mul = UpdateOp::Mul(Number::Float(0.5));
+        assert_eq!(
+            compute(&mul, &Number::Int(3), "a").unwrap(),
+            Number::Float(1.5)
+        );
+    }
+
+    #[test]
+    fn test_compute_overflow_errors() {
+        let add = UpdateOp::Add(Number::Int(-1));
+        let err = compute(&add, &Number::Int(i64::MIN), "a").unwrap_err();
+        assert!(err.to_string().contains("integer overflow"));
+        let mul = UpdateOp::Mul(Number::Int(3));
+        let err = compute(&mul, &Number::Int(i64::MAX), "a").unwrap_err();
+        assert!(err.to_string().contains("integer overflow"));
+    }
+
+    #[test]
+    fn test_compute_float_overflow_errors() {
+        let mul = UpdateOp::Mul(Number::Int(10));
+        let err = compute(&mul, &Number::Float(1e308), "a").unwrap_err();
+        assert!(err.to_string().contains("float overflow"));
+    }
+
+    #[test]
+    fn test_compute_large_unsigned() {
+        let add = UpdateOp::Add(Number::Int(1));
+        assert_eq!(
+            compute(&add, &Number::UInt(u64::MAX - 1), "a").unwrap(),
+            Number::UInt(u64::MAX)
+        );
+        let err = compute(&add, &Number::UInt(u64::MAX), "a").unwrap_err();
+        assert!(err.to_string().contains("integer overflow"));
+        let add = UpdateOp::Add(Number::Int(-2));
+        assert_eq!(
+            compute(&add, &Number::UInt(1u64 << 63), "a").unwrap(),
+            Number::Int(i64::MAX - 1)
+        );
+    }
+
+    #[test]
+    fn test_format_number_keeps_float() {
+        assert_eq!(format_number(&Number::Float(3.0)), "3.0");
+        assert_eq!(format_number(&Number::Int(3)), "3");
+    }
+
+    #[test]
+    fn test_parse_replace() {
+        let op = parse_replace(r"a\/b/c/d", false).unwrap();
+        assert!(matches!(op, UpdateOp::Replace(old, new) if old == "a/b" && new == "c/d"));
+        assert!(parse_replace("no-separator", false).is_err());
+        assert!(parse_replace("(/x", true).is_err());
+    }
+
+    #[test]
+    fn test_parse_replace_rejects_empty_old() {
+        let err = parse_replace("/X", false).unwrap_err();
+        assert!(err.to_string().contains("empty OLD in replacement"));
+    }
+
+    #[test]
+    fn test_parse_replace_rejects_regex_matching_empty() {
+        let err = parse_replace("a*/X", true).unwrap_err();
+        assert!(err.to_string().contains("matches the empty string"));
+        assert!(parse_replace("a+/X", true).is_ok());
+    }
+
+    #[test]
+    fn test_edit_str() {
+        assert_eq!(
+            edit_str(&UpdateOp::AppendStr(":8080".into()), "host"),
+            "host:8080"
+        );
+        assert_eq!(edit_str(&UpdateOp::Prefix("v".into()), "1.2"), "v1.2");
+        let op = parse_replace("1.25/1.26", false).unwrap();
+        assert_eq!(edit_str(&op, "nginx:1.25"), "nginx:1.26");
+        let op = parse_replace(r":(\d+)\.(\d+)/:$1.x", true).unwrap();
+        assert_eq!(edit_str(&op, "nginx:1.25"), "nginx:1.x");
+    }
+
+    #[test]
+    fn test_update_value() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("replicas".to_string()) => Value::Number(Number::Int(2)),
+        });
+        let result = update(
+            "replicas",
+            &UpdateOp::Add(Number::Int(1)),
+            value,
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            Value::Mapping(indexmap! {
+                Value::String("replicas".to_string()) => Value::Number(Number::Int(3)),
+            })
+        );
+    }
+
+    #[test]
+    fn test_update_string_edit_gives_string() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("v".to_string()) => Value::Number(Number::Int(1)),
+        });
+        let result = update(
+            "v",
+            &UpdateOp::AppendStr("0".into()),
+            value,
+            &Options::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            Value::Mapping(indexmap! {
+                Value::String("v".to_string()) => Value::String("10".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn test_update_requires_number() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("a".to_string()) => Value::String("x".to_string()),
+        });
+        let err = update(
+            "a",
+            &UpdateOp::Add(Number::Int(1)),
+            value,
+            &Options::default(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("update does not support 'str' type"));
+    }
+
+    #[test]
+    fn test_update_string_edit_requires_scalar() {
+        let value = Value::Mapping(indexmap! {
+            Value::String("a".to_string()) => Value::Sequence(vec![]),
+        });
+        let err = update(
+            "a",
+            &UpdateOp::Prefix("v".into()),
+            value,
+            &Options::default(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Please provide or select a scalar"));
+    }
+}
@@ -0,0 +1,190 @@
+//! Named variables of `--arg` and `--argjson`.
+//!
+//! As with jq, `--arg NAME VALUE` sets `$NAME` to a string and
+//! `--argjson NAME JSON` to a parsed value, so that values reach
+//! `set-value` and `filter` without being spliced in by the shell. A
+//! `set-value` VALUE that is a single `$NAME` (or `${NAME}`) is the value
+//! of the variable as is; in a longer VALUE, each `$NAME` is replaced by
+//! the raw text of its value, `$$` giving a literal `$`.
+
+use super::error::Error;
+use super::mutation::parse_value;
+use super::serialize::serialize_raw;
+use fyaml::Value;
+use indexmap::IndexMap;
+
+/// Values of the variables, by name.
+#[derive(Debug, Clone, Default)]
+pub struct Vars {
+    values: IndexMap<String, Value>,
+}
+
+fn is_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+impl Vars {
+    /// Variables of `--arg` and `--argjson`, given as flat lists of names
+    /// and values.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on invalid names and on `--argjson` values that
+    /// don't parse.
+    pub fn new(args: &[String], argjson: &[String]) -> Result<Self, Error> {
+        let mut values = IndexMap::new();
+        let strings = args
+            .chunks(2)
+            .map(|pair| Ok::<_, Error>((&pair[0], Value::String(pair[1].clone()))));
+        let parsed = argjson.chunks(2).map(|pair| {
+            let value = pair[1]
+                .parse::<Value>()
+                .map_err(|e| Error::parse_in(&format!("JSON of '${}'", pair[0]), &pair[1], e))?;
+            Ok((&pair[0], value))
+        });
+        for entry in strings.chain(parsed) {
+            let (name, value) = entry?;
+            if !is_name(name) {
+                return Err(Error::Base(format!("invalid variable name '{}'.", name)));
+            }
+            values.insert(name.clone(), value);
+        }
+        Ok(Vars { values })
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Value of `$name`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the variable isn't defined.
+    pub fn get(&self, name: &str) -> Result<&Value, Error> {
+        self.values
+            .get(name)
+            .ok_or_else(|| Error::Base(format!("undefined variable '${}'.", name)))
+    }
+
+    /// Replace `$NAME` and `${NAME}` in `text` by the raw text of their
+    /// value, and `$$` by `$`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on undefined variables.
+    pub fn interpolate(&self, text: &str) -> Result<String, Error> {
+        let mut result = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find('$') {
+            result.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let (name, len) = if let Some(braced) = after.strip_prefix('{') {
+                match braced.find('}') {
+                    Some(end) => (&braced[..end], end + 2),
+                    None => ("", 0),
+                }
+            } else if let Some(after) = after.strip_prefix('$') {
+                result.push('$');
+                rest = after;
+                continue;
+            } else {
+                let end = after
+                    .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                    .unwrap_or(after.len());
+                (&after[..end], end)
+            };
+            if !is_name(name) {
+                // Not a reference, kept as is
+                result.push('$');
+                rest = after;
+                continue;
+            }
+            result.push_str(&serialize_raw(self.get(name)?));
+            rest = &after[len..];
+        }
+        result.push_str(rest);
+        Ok(result)
+    }
+
+    /// Value given by a `set-value` VALUE, parsed as YAML with
+    /// `parse_as_yaml`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error on undefined variables and invalid YAML.
+    pub fn resolve(&self, text: &str, parse_as_yaml: bool) -> Result<Value, Error> {
+        let reference = text
+            .strip_prefix("${")
+            .and_then(|t| t.strip_suffix('}'))
+            .or_else(|| text.strip_prefix('$'))
+            .filter(|name| is_name(name));
+        match reference {
+            Some(name) => self.get(name).cloned(),
+            None => parse_value(&self.interpolate(text)?, parse_as_yaml),
+        }
+    }
+}
+
+// =============================================================================
+// Tests
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use fyaml::Number;
+
+    fn vars() -> Vars {
+        let strings = ["env", "prod", "port", "8080"].map(String::from);
+        let json = ["ports", "[80, 443]"].map(String::from);
+        Vars::new(&strings, &json).unwrap()
+    }
+
+    #[test]
+    fn test_resolve_whole_value() {
+        let vars = vars();
+        // Strings of --arg are not read as numbers
+        assert_eq!(
+            vars.resolve("$port", false).unwrap(),
+            Value::String("8080".to_string())
+        );
+        assert_eq!(
+            vars.resolve("${ports}", false).unwrap(),
+            Value::Sequence(vec![
+                Value::Number(Number::Int(80)),
+                Value::Number(Number::Int(443)),
+            ])
+        );
+        assert_eq!(
+            vars.resolve("8080", false).unwrap(),
+            Value::Number(Number::Int(8080))
+        );
+    }
+
+    #[test]
+    fn test_interpolate() {
+        let vars = vars();
+        assert_eq!(
+            vars.interpolate("db-$env.${env}_x:$port").unwrap(),
+            "db-prod.prod_x:8080"
+        );
+        assert_eq!(vars.interpolate("pa$$word $ 5$").unwrap(), "pa$word $ 5$");
+        assert_eq!(
+            vars.interpolate("$nope").unwrap_err().to_string(),
+            "undefined variable '$nope'."
+        );
+    }
+
+    #[test]
+    fn test_invalid_vars() {
+        let err = Vars::new(&["a-b".to_string(), "x".to_string()], &[]).unwrap_err();
+        assert_eq!(err.to_string(), "invalid variable name 'a-b'.");
+        assert!(Vars::new(&[], &["a".to_string(), "[1,".to_string()]).is_err());
+    }
+}
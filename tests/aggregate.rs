@@ -0,0 +1,91 @@
+//! Integration tests for the `sum`, `min`, `max`, `avg` and `count-where`
+//! actions
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    resources:
+      web: {replicas: 2, cpu: 0.5}
+      api: {replicas: 3, cpu: 1.5}
+      cron: {replicas: 1}
+    sizes: [10, 4, 7]
+    hosts:
+    - {name: db1, zone: eu}
+    - {name: db2, zone: us}
+    - {name: db3, zone: eu}
+"};
+
+#[test]
+fn test_sum_wildcard_path() {
+    let (stdout, stderr, success) = run_shyaml(&["sum", "resources.*.replicas"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "6\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["sum", "resources.*.cpu"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "2\n");
+}
+
+#[test]
+fn test_min_max_avg_sequence() {
+    for (action, expected) in [("min", "4\n"), ("max", "10\n"), ("avg", "7\n")] {
+        let (stdout, stderr, success) = run_shyaml(&[action, "sizes"], INPUT);
+        assert!(success, "{} failed: {}", action, stderr);
+        assert_output_eq(&stdout, expected);
+    }
+}
+
+#[test]
+fn test_sum_mapping_values_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["update", "sizes.0", "--add", "5", ";", "sum", "sizes"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "26\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["sum"], "a: 1\nb: 2.5\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "3.5\n");
+}
+
+#[test]
+fn test_count_where() {
+    let (stdout, stderr, success) = run_shyaml(&["count-where", "hosts", "zone=eu"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "2\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["count-where", "hosts", "name~^x"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0\n");
+}
+
+#[test]
+fn test_aggregate_errors() {
+    let (_, stderr, success) = run_shyaml(&["sum", "hosts"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("sum does not support 'struct' type at 'hosts.0'"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["max", "sizes.0"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("max does not support 'int' type"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["avg", "resources.*.memory"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("avg needs at least one number"),
+        "stderr: {}",
+        stderr
+    );
+}
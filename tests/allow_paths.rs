@@ -0,0 +1,136 @@
+//! Integration tests for `--allow-paths` restricting modified paths
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const ALLOWED: &str = indoc! {"
+    # release bot
+    image.tag
+
+    containers.*.image
+"};
+
+const INPUT: &str = indoc! {"
+    image:
+      name: web
+      tag: 1.25
+    containers:
+    - image: proxy:2.0
+      name: proxy
+"};
+
+fn with_allowed(tmp: &TempDir, args: &[&str]) -> (String, String, bool) {
+    let allowed = temp_yaml_file(tmp, "allowed.txt", ALLOWED);
+    let mut full = vec!["--allow-paths", allowed.to_str().unwrap()];
+    full.extend_from_slice(args);
+    run_shyaml(&full, INPUT)
+}
+
+#[test]
+fn test_allowed_mutations() {
+    let tmp = TempDir::new().unwrap();
+    let (stdout, stderr, success) = with_allowed(
+        &tmp,
+        &[
+            "set-value",
+            "image.tag",
+            "1.26",
+            ";",
+            "set-value",
+            "containers.-1.image",
+            "proxy:2.1",
+        ],
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            image:
+              name: web
+              tag: 1.26
+            containers:
+            - image: proxy:2.1
+              name: proxy
+        "},
+    );
+}
+
+#[test]
+fn test_disallowed_mutations_fail() {
+    let tmp = TempDir::new().unwrap();
+    for args in [
+        &["set-value", "image.name", "api"][..],
+        &["del", "image"],
+        &["ensure", "replicas"],
+        &["append", "containers", "x"],
+        &["update", "containers.0.name", "--prefix", "x-"],
+        &["save", "doc", ";", "set-value", "image.name", "api"],
+    ] {
+        let (stdout, stderr, success) = with_allowed(&tmp, args);
+        assert!(!success, "{:?} should fail", args);
+        assert!(stdout.is_empty());
+        assert!(
+            stderr.contains("is not in the paths allowed by"),
+            "stderr: {}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn test_apply_outside_allowed_paths_fails() {
+    let tmp = TempDir::new().unwrap();
+    let overlay = temp_yaml_file(&tmp, "overlay.yaml", "image:\n  tag: 1.26\n");
+    let (stdout, stderr, success) = with_allowed(&tmp, &["apply", overlay.to_str().unwrap()]);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("tag: 1.26"), "stdout: {}", stdout);
+
+    let overlay = temp_yaml_file(&tmp, "overlay.yaml", "image:\n  registry: quay.io\n");
+    let (_, stderr, success) = with_allowed(&tmp, &["apply", overlay.to_str().unwrap()]);
+    assert!(!success);
+    assert!(
+        stderr.contains("cannot modify 'image.registry'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_load_outside_allowed_paths_fails() {
+    let tmp = TempDir::new().unwrap();
+    let (_, stderr, success) = with_allowed(&tmp, &["save", "doc", ";", "load", "doc"]);
+    assert!(success, "stderr: {}", stderr);
+
+    let (_, stderr, success) = with_allowed(
+        &tmp,
+        &["save", "doc", ";", "get-value", "image", ";", "load", "doc"],
+    );
+    assert!(!success);
+    assert!(stderr.contains("cannot modify"), "stderr: {}", stderr);
+
+    let (_, stderr, success) =
+        with_allowed(&tmp, &["save", "img", ";", "load", "img", "image.name"]);
+    assert!(!success);
+    assert!(stderr.contains("'image.name'"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_read_only_actions_unrestricted() {
+    let tmp = TempDir::new().unwrap();
+    let (stdout, stderr, success) = with_allowed(&tmp, &["get-value", "image.name"]);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "web");
+}
+
+#[test]
+fn test_missing_allow_paths_file() {
+    let (_, stderr, success) = run_shyaml(
+        &["--allow-paths", "/nonexistent/allowed.txt", "get-value"],
+        INPUT,
+    );
+    assert!(!success);
+    assert!(stderr.contains("Failed to read"), "stderr: {}", stderr);
+}
@@ -0,0 +1,166 @@
+//! Integration tests for the `annotate` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_with_env};
+use indoc::indoc;
+
+const EPOCH: &[(&str, &str)] = &[("SOURCE_DATE_EPOCH", "1706702400")];
+
+#[test]
+fn test_annotate_inserts_header() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &[
+            "annotate",
+            "--managed-by",
+            "helmgen",
+            "--source",
+            "base.yaml",
+            "--source",
+            "prod.yaml",
+        ],
+        "# app settings\nport: 80  # http\n",
+        EPOCH,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # DO NOT EDIT: managed by helmgen
+            # generated: 2024-01-31T12:00:00Z
+            # sources: base.yaml, prod.yaml
+            # app settings
+            port: 80 # http
+        "},
+    );
+}
+
+#[test]
+fn test_annotate_updates_existing_header() {
+    let input = indoc! {"
+        # DO NOT EDIT: managed by helmgen
+        # generated: 2020-01-01T00:00:00Z
+        # sources: old.yaml
+        # app settings
+        port: 80
+    "};
+    let (stdout, stderr, success) =
+        run_shyaml_with_env(&["annotate", "--managed-by", "helmgen"], input, EPOCH);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # DO NOT EDIT: managed by helmgen
+            # generated: 2024-01-31T12:00:00Z
+            # app settings
+            port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_annotate_kept_by_later_mutations() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &[
+            "annotate",
+            "--managed-by",
+            "gen",
+            ";",
+            "set-value",
+            "port",
+            "443",
+        ],
+        "port: 80\n",
+        EPOCH,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # DO NOT EDIT: managed by gen
+            # generated: 2024-01-31T12:00:00Z
+            port: 443
+        "},
+    );
+}
+
+#[test]
+fn test_annotate_at_end_of_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["get-value", "spec", ";", "annotate", "--managed-by", "gen"],
+        "spec:\n  port: 80\n",
+        EPOCH,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # DO NOT EDIT: managed by gen
+            # generated: 2024-01-31T12:00:00Z
+            port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_annotate_invalid_source_date_epoch() {
+    let (_, stderr, success) = run_shyaml_with_env(
+        &["annotate", "--managed-by", "gen"],
+        "a: 1\n",
+        &[("SOURCE_DATE_EPOCH", "yesterday")],
+    );
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid SOURCE_DATE_EPOCH 'yesterday'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_annotate_empty_input() {
+    let (_, stderr, success) = run_shyaml(&["annotate", "--managed-by", "gen"], "");
+    assert!(!success);
+    assert!(stderr.contains("cannot annotate an empty document"));
+}
+
+#[test]
+fn test_annotate_deterministic_uses_epoch() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--deterministic", "annotate", "--managed-by", "helmgen"],
+        "port: 80\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # DO NOT EDIT: managed by helmgen
+            # generated: 1970-01-01T00:00:00Z
+            port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_annotate_deterministic_honors_source_date_epoch() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &[
+            "--deterministic",
+            "--factor-anchors",
+            "annotate",
+            "--managed-by",
+            "helmgen",
+        ],
+        "port: 80\n",
+        EPOCH,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # DO NOT EDIT: managed by helmgen
+            # generated: 2024-01-31T12:00:00Z
+            port: 80
+        "},
+    );
+}
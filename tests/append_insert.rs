@@ -0,0 +1,139 @@
+//! Integration tests for the `append` and `insert` actions
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    # servers
+    hosts:
+    - a  # primary
+    - b
+    port: 80
+"};
+
+#[test]
+fn test_append() {
+    let (stdout, stderr, success) = run_shyaml(&["append", "hosts", "c"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # servers
+            hosts:
+            - a # primary
+            - b
+            - c
+            port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_insert_positions() {
+    for (index, expected) in [
+        ("0", "z\na\nb\n"),
+        ("1", "a\nz\nb\n"),
+        ("2", "a\nb\nz\n"),
+        ("-1", "a\nb\nz\n"),
+        ("-3", "z\na\nb\n"),
+    ] {
+        let (stdout, stderr, success) = run_shyaml(
+            &["insert", "hosts", index, "z", ";", "get-values", "hosts"],
+            INPUT,
+        );
+        assert!(success, "index {}: {}", index, stderr);
+        assert_output_eq(&stdout, expected);
+    }
+}
+
+#[test]
+fn test_insert_keeps_comments() {
+    let (stdout, stderr, success) = run_shyaml(&["insert", "hosts", "0", "z"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # servers
+            hosts:
+            - z
+            - a # primary
+            - b
+            port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_insert_out_of_range() {
+    let (stdout, stderr, success) = run_shyaml(&["insert", "hosts", "3", "z"], INPUT);
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("cannot insert at index 3 (2 elements in sequence)"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_append_creates_sequence() {
+    for input in ["port: 80\n", "hosts:\nport: 80\n", ""] {
+        let (stdout, stderr, success) =
+            run_shyaml(&["append", "hosts", "a", ";", "get-values", "hosts"], input);
+        assert!(success, "input {:?}: {}", input, stderr);
+        assert_output_eq(&stdout, "a\n");
+    }
+}
+
+#[test]
+fn test_append_yaml_value() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["append", "l", "{name: x, port: 1}", "-y"], "l:\n- 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "l:\n- 1\n- name: x\n  port: 1\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["append", "l", "[1]"], "l: []\n");
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("\"[1]\""), "stdout: {}", stdout);
+}
+
+#[test]
+fn test_insert_at_root() {
+    let (stdout, stderr, success) = run_shyaml(&["insert", "", "0", "--", "-1"], "- 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "- -1\n- 1\n");
+}
+
+#[test]
+fn test_append_to_non_sequence_fails() {
+    let (stdout, stderr, success) = run_shyaml(&["append", "port", "1"], INPUT);
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("cannot add an element to a 'int'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_append_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "doc",
+            ";",
+            "append",
+            "hosts",
+            "c",
+            ";",
+            "get-length",
+            "hosts",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "3\n");
+}
@@ -7,7 +7,7 @@
 
 mod common;
 
-use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
 use indoc::indoc;
 use tempfile::TempDir;
 
@@ -446,6 +446,69 @@ fn test_apply_policy_prepend() {
     );
 }
 
+#[test]
+fn test_apply_policy_append_all() {
+    let tmp = TempDir::new().unwrap();
+
+    let base = indoc! {"
+        items:
+          - a
+          - b
+        other:
+          - a
+    "};
+
+    let overlay = temp_yaml_file(
+        &tmp,
+        "overlay.yaml",
+        indoc! {"
+            items:
+              - a
+              - c
+            other:
+              - a
+        "},
+    );
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["apply", "-m", "items=append-all", overlay.to_str().unwrap()],
+        base,
+    );
+
+    assert!(success, "Command failed: {}", stderr);
+    // Only `items` keeps its duplicates
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            items:
+            - a
+            - b
+            - a
+            - c
+            other:
+            - a
+        "},
+    );
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["--no-dedup", "apply", overlay.to_str().unwrap()], base);
+
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            items:
+            - a
+            - b
+            - a
+            - c
+            other:
+            - a
+            - a
+        "},
+    );
+}
+
 #[test]
 fn test_apply_policy_multiple_comma_separated() {
     let tmp = TempDir::new().unwrap();
@@ -538,6 +601,66 @@ fn test_apply_policy_multiple_flags() {
     );
 }
 
+#[test]
+fn test_apply_policy_patterns() {
+    let tmp = TempDir::new().unwrap();
+
+    let base = indoc! {"
+        services:
+          web:
+            env: [DEBUG=1]
+            ports: [80]
+          db:
+            env: [PGDATA=/var/lib/pg]
+            ports: [5432]
+    "};
+
+    let overlay = temp_yaml_file(
+        &tmp,
+        "overlay.yaml",
+        indoc! {"
+            services:
+              web:
+                env: [LOG=info]
+                ports: [443]
+              db:
+                env: [PGPORT=5433]
+                ports: [5433]
+        "},
+    );
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "apply",
+            "-m",
+            "services.*.env=replace,**.ports=prepend",
+            "-m",
+            "services.db.ports=replace",
+            overlay.to_str().unwrap(),
+        ],
+        base,
+    );
+
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            services:
+              web:
+                env:
+                - LOG=info
+                ports:
+                - 443
+                - 80
+              db:
+                env:
+                - PGPORT=5433
+                ports:
+                - 5433
+        "},
+    );
+}
+
 #[test]
 fn test_apply_policy_nested_path() {
     let tmp = TempDir::new().unwrap();
@@ -582,3 +705,162 @@ fn test_apply_policy_nested_path() {
         "},
     );
 }
+
+#[test]
+fn test_apply_overlay_parse_error_shows_line() {
+    let tmp = TempDir::new().unwrap();
+    let overlay = temp_yaml_file(
+        &tmp,
+        "overlay.yaml",
+        indoc! {"
+            spec:
+              ports:
+                - 80
+               - 443
+        "},
+    );
+    let (stdout, stderr, success) = run_shyaml(&["apply", overlay.to_str().unwrap()], "a: 1\n");
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("overlay.yaml': Parse error at 4:4: "),
+        "stderr: {}",
+        stderr
+    );
+    assert!(
+        stderr.ends_with(" 4 |    - 443\n   |    ^\n"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_apply_overlay_from_stdin() {
+    let tmp = TempDir::new().unwrap();
+    let base = temp_yaml_file(&tmp, "base.yaml", "a: 1\nitems: [x]\n---\na: 2\n");
+    let overlay = temp_yaml_file(&tmp, "overlay.yaml", "b: 2\n");
+
+    // Stdin is read once and applied to every document of the base file
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "-y",
+            "-f",
+            base.to_str().unwrap(),
+            "apply",
+            "-",
+            overlay.to_str().unwrap(),
+        ],
+        "items: [y]\n",
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            a: 1
+            items:
+            - x
+            - y
+            b: 2
+            ---
+            a: 2
+            items:
+            - y
+            b: 2
+        "},
+    );
+}
+
+#[test]
+fn test_apply_overlay_from_stdin_needs_file() {
+    let tmp = TempDir::new().unwrap();
+    let base = temp_yaml_file(&tmp, "base.yaml", "a: 1\n");
+
+    let (_, stderr, success) = run_shyaml(&["apply", "-"], "a: 1\n");
+    assert!(!success);
+    assert!(
+        stderr.contains("the base document must be given with --file"),
+        "stderr: {}",
+        stderr
+    );
+
+    // The overlay can come after other actions of the chain
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "-f",
+            base.to_str().unwrap(),
+            "set-value",
+            "b",
+            "2",
+            ";",
+            "apply",
+            "-",
+        ],
+        "c: 3\n",
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb: 2\nc: 3\n");
+
+    let (_, stderr, code) = run_shyaml_code(&["-f", "/nonexistent.yaml", "apply", "-"], "");
+    assert_eq!(code, Some(5));
+    assert!(
+        stderr.contains("Failed to read '/nonexistent.yaml'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_apply_overlay_urls() {
+    let (_, stderr, success) = run_shyaml(&["apply", "http://example.com/overlay.yaml"], "a: 1\n");
+    assert!(!success);
+    assert!(
+        stderr.contains("only https:// URLs can be fetched"),
+        "stderr: {}",
+        stderr
+    );
+
+    // Only https:// is fetched: any other scheme-looking path is a file
+    let tmp = TempDir::new().unwrap();
+    std::fs::create_dir(tmp.path().join("a:")).unwrap();
+    std::fs::write(tmp.path().join("a:").join("b.yaml"), "b: 2\n").unwrap();
+    let overlay = format!("{}/a://b.yaml", tmp.path().display());
+    let (stdout, stderr, success) = run_shyaml(&["apply", &overlay], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb: 2\n");
+
+    // A server accepting connections without ever answering
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("https://{}/overlay.yaml", listener.local_addr().unwrap());
+    std::thread::spawn(move || {
+        let _connections: Vec<_> = listener.incoming().collect();
+    });
+    let (_, stderr, code) = run_shyaml_code(&["apply", "--url-timeout", "1", &url], "a: 1\n");
+    assert_eq!(code, Some(5));
+    assert!(
+        stderr.contains(&format!(
+            "Failed to fetch '{}': timed out after 1 seconds",
+            url
+        )),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_apply_deterministic_rejects_url_overlays() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--deterministic",
+            "apply",
+            "https://example.invalid/overlay.yaml",
+        ],
+        "a: 1\n",
+    );
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("--deterministic doesn't allow https:// overlays"),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -0,0 +1,76 @@
+//! Integration tests for the `--base-dir` option
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use tempfile::TempDir;
+
+#[test]
+fn test_base_dir_apply_overlays() {
+    let tmp = TempDir::new().unwrap();
+    temp_yaml_file(&tmp, "overlay.yaml", "b: 2\n");
+    let dir = tmp.path().to_str().unwrap();
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["--base-dir", dir, "apply", "overlay.yaml"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb: 2\n");
+
+    // Relative to the current directory without it
+    let (_, stderr, success) = run_shyaml(&["apply", "overlay.yaml"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("Failed to read 'overlay.yaml'"));
+}
+
+#[test]
+fn test_base_dir_keeps_absolute_paths() {
+    let tmp = TempDir::new().unwrap();
+    let other = TempDir::new().unwrap();
+    let overlay = temp_yaml_file(&other, "overlay.yaml", "b: 2\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--base-dir",
+            tmp.path().to_str().unwrap(),
+            "apply",
+            overlay.to_str().unwrap(),
+        ],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb: 2\n");
+}
+
+#[test]
+fn test_base_dir_value_files() {
+    let tmp = TempDir::new().unwrap();
+    temp_yaml_file(&tmp, "value.txt", "hello");
+    temp_yaml_file(&tmp, "other.yaml", "x:\n  y: 42\n");
+    temp_yaml_file(&tmp, "assignments.txt", "c=3\n");
+    let dir = tmp.path().to_str().unwrap();
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--base-dir",
+            dir,
+            "set-value",
+            "a",
+            "--from-file",
+            "value.txt",
+            ";",
+            "set-value",
+            "b",
+            "--from",
+            "x.y",
+            "--file",
+            "other.yaml",
+            ";",
+            "set-values",
+            "--from",
+            "assignments.txt",
+        ],
+        "name: web\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name: web\na: hello\nb: 42\nc: 3\n");
+}
@@ -0,0 +1,85 @@
+//! Integration tests for `--binary` of `get-value` and `set-value`
+//!
+//! `!!binary` scalars hold base64 text: `set-value --binary` encodes the
+//! bytes it stores, and `get-value --binary` writes the decoded bytes.
+
+mod common;
+
+use common::{assert_output_eq, binary_path, run_shyaml, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+#[test]
+fn test_set_value_binary() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["set-value", "--binary", "data", "hello\n"], "name: web\n");
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: web
+            data: !!binary aGVsbG8K
+        "},
+    );
+}
+
+#[test]
+fn test_set_value_binary_from_file() {
+    let tmp = TempDir::new().unwrap();
+    let bytes: Vec<u8> = (0..=255).collect();
+    let cert = tmp.path().join("cert.der");
+    std::fs::write(&cert, &bytes).unwrap();
+    let input = temp_yaml_file(&tmp, "input.yaml", "name: web\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--file",
+            input.to_str().unwrap(),
+            "set-value",
+            "--binary",
+            "cert",
+            "--from-file",
+            cert.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert!(stdout.contains("cert: !!binary "), "stdout: {}", stdout);
+
+    // Decoding gives back the bytes of the file
+    let output = std::process::Command::new(binary_path())
+        .arg("--file")
+        .arg(&input)
+        .args(["set-value", "--binary", "cert", "--from-file"])
+        .arg(&cert)
+        .args([";", "get-value", "--binary", "cert"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, bytes);
+}
+
+#[test]
+fn test_get_value_binary() {
+    let input = "data: !!binary |\n  aGVs\n  bG8K\nname: web\n";
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--binary", "data"], input);
+    assert!(success, "Command failed: {}", stderr);
+    assert_eq!(stdout, "hello\n");
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "--binary", "name"], input);
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid binary value at 'name', expected base64 text."),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "--binary"], input);
+    assert!(!success);
+    assert!(
+        stderr.contains("get-value --binary does not support 'struct' type."),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -0,0 +1,63 @@
+//! Integration tests for `--broken-alias`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const INPUT: &str = indoc! {"
+    defaults: &defaults
+      replicas: 2
+    web:
+      settings: *defaults
+      extra: *generated
+    jobs: [*defaults, *later]
+"};
+
+#[test]
+fn test_broken_alias_errors_by_default() {
+    let (_, stderr, success) = run_shyaml(&["get-value", "web.settings.replicas"], INPUT);
+    assert!(!success);
+    assert!(stderr.contains("Parse error at 5:"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_broken_alias_null() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--broken-alias",
+            "null",
+            "get-value",
+            "web.settings.replicas",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "2");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["--broken-alias", "null", "get-types", "jobs"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "struct\nNoneType\n");
+}
+
+#[test]
+fn test_broken_alias_null_per_document_from_file() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "docs.yaml", "a: &x 1\nb: *x\n---\nb: *x\n");
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--broken-alias",
+            "null",
+            "-f",
+            file.to_str().unwrap(),
+            "get-type",
+            "b",
+        ],
+        "",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.starts_with("int"), "stdout: {}", stdout);
+    assert!(stdout.contains("NoneType"), "stdout: {}", stdout);
+}
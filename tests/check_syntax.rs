@@ -0,0 +1,221 @@
+//! Integration tests for the `check-syntax` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+#[test]
+fn test_check_syntax_valid_stdin() {
+    let (stdout, stderr, code) = run_shyaml_code(&["check-syntax"], "a: 1\n---\nb: [1, 2]\n");
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert!(stdout.is_empty());
+}
+
+#[test]
+fn test_check_syntax_reports_location() {
+    let (stdout, _, code) = run_shyaml_code(&["check-syntax"], "a: 1\na: 2\n");
+    assert_eq!(code, Some(1));
+    assert_output_eq(&stdout, "<stdin>:2:1: duplicate key\n");
+}
+
+#[test]
+fn test_check_syntax_files() {
+    let tmp = TempDir::new().unwrap();
+    let good = temp_yaml_file(&tmp, "good.yaml", "a: 1\n");
+    let bad = temp_yaml_file(&tmp, "bad.yaml", "a: 1\n---\nb: \"x\n");
+    let missing = tmp.path().join("missing.yaml");
+
+    let (stdout, stderr, code) = run_shyaml_code(
+        &[
+            "check-syntax",
+            good.to_str().unwrap(),
+            bad.to_str().unwrap(),
+            missing.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 2, "stdout: {}", stdout);
+    assert!(lines[0].starts_with(&format!("{}:3:", bad.display())));
+    assert!(lines[0].ends_with("double-quoted scalar without closing quote"));
+    assert!(lines[1].starts_with(&format!("{}: Failed to read", missing.display())));
+}
+
+#[test]
+fn test_check_syntax_only_valid_files() {
+    let tmp = TempDir::new().unwrap();
+    let good = temp_yaml_file(&tmp, "good.yaml", "a: 1\n");
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "check-syntax",
+            good.to_str().unwrap(),
+            good.to_str().unwrap(),
+        ],
+        "invalid: [",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.is_empty());
+}
+
+#[test]
+fn test_check_syntax_anchors() {
+    let input = indoc! {"
+        base: &base
+          a: 1
+        dev:
+          <<: *base
+        ports: [&http 80, &http 8080, *http]
+        old: &old 1
+        --- # anchors don't cross documents
+        prod: *base
+    "};
+    let (stdout, _, code) = run_shyaml_code(&["check-syntax"], input);
+    assert_eq!(code, Some(1));
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            <stdin>:5:9: anchor '&http' is never used
+            <stdin>:5:19: anchor '&http' redefined, previously defined at line 5
+            <stdin>:6:6: anchor '&old' is never used
+            <stdin>:8:7: alias '*base' refers to an undefined anchor
+        "},
+    );
+}
+
+#[test]
+fn test_check_syntax_anchors_and_syntax_error() {
+    let (stdout, _, code) = run_shyaml_code(&["check-syntax"], "a: &x 1\nb: [1\n");
+    assert_eq!(code, Some(1));
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            <stdin>:1:4: anchor '&x' is never used
+            <stdin>:3:1: flow sequence without a closing bracket
+        "},
+    );
+}
+
+#[test]
+fn test_check_syntax_style_rules() {
+    let tmp = TempDir::new().unwrap();
+    let config = temp_yaml_file(
+        &tmp,
+        ".yamllint",
+        indoc! {"
+            extends: default
+            rules:
+              line-length:
+                max: 30
+              truthy:
+                check-keys: false
+        "},
+    );
+    // Trailing spaces wouldn't survive in an indoc! literal
+    let input = concat!(
+        "on:\n",
+        "  push: yes\n",
+        "jobs:\n",
+        "  build:\n",
+        "    name: a name much longer than thirty characters\n",
+        "    steps:  \n",
+        "    - run: make\n",
+    );
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["check-syntax", "--config", config.to_str().unwrap()],
+        input,
+    );
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            <stdin>:2:9: truthy value should be one of [false, true] (truthy)
+            <stdin>:5:31: line too long (51 > 30 characters) (line-length)
+            <stdin>:6:11: trailing spaces (trailing-spaces)
+            <stdin>:7:5: wrong indentation: expected 6 but found 4 (indentation)
+        "},
+    );
+}
+
+#[test]
+fn test_check_syntax_invalid_config() {
+    let tmp = TempDir::new().unwrap();
+    let config = temp_yaml_file(&tmp, ".yamllint", "extends: relaxed\n");
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["check-syntax", "--config", config.to_str().unwrap()],
+        "a: 1\n",
+    );
+    assert_eq!(code, Some(127));
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("only 'default' is"), "stderr: {}", stderr);
+
+    let (_, stderr, code) = run_shyaml_code(
+        &["check-syntax", "--config", "/nonexistent/.yamllint"],
+        "a: 1\n",
+    );
+    assert_eq!(code, Some(5));
+    assert!(stderr.contains("Failed to read"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_check_syntax_sarif_report() {
+    let tmp = TempDir::new().unwrap();
+    let bad = temp_yaml_file(&tmp, "bad.yaml", "a: &x 1\na: 2\n");
+    let bad = bad.to_str().unwrap();
+
+    let (stdout, stderr, code) = run_shyaml_code(&["check-syntax", "--report", "sarif", bad], "");
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert!(
+        stdout.contains("\"version\": \"2.1.0\""),
+        "stdout: {}",
+        stdout
+    );
+    for result in [
+        (
+            "unused-anchor",
+            "warning",
+            "anchor '&x' is never used",
+            1,
+            4,
+        ),
+        ("syntax", "error", "duplicate key", 2, 1),
+    ] {
+        let (rule, level, message, line, column) = result;
+        let expected = format!(
+            concat!(
+                "{{\"ruleId\": \"{}\", \"level\": \"{}\", \"message\": {{\"text\": \"{}\"}}, ",
+                "\"locations\": [{{\"physicalLocation\": {{\"artifactLocation\": {{\"uri\": \"{}\"}}, ",
+                "\"region\": {{\"startLine\": {}, \"startColumn\": {}}}}}}}]}}"
+            ),
+            rule, level, message, bad, line, column
+        );
+        assert!(stdout.contains(&expected), "stdout: {}", stdout);
+    }
+
+    let (stdout, stderr, code) = run_shyaml_code(&["check-syntax", "--report", "sarif"], "a: 1\n");
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert!(stdout.contains("\"results\": []"), "stdout: {}", stdout);
+}
+
+#[test]
+fn test_check_syntax_github_report() {
+    let (stdout, _, code) =
+        run_shyaml_code(&["check-syntax", "--report", "github"], "a: &x 1\na: 2\n");
+    assert_eq!(code, Some(1));
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            ::warning file=<stdin>,line=1,col=4,title=unused-anchor::anchor '&x' is never used
+            ::error file=<stdin>,line=2,col=1,title=syntax::duplicate key
+        "},
+    );
+}
+
+#[test]
+fn test_check_syntax_cannot_be_chained() {
+    let (_, stderr, success) = run_shyaml(&["check-syntax", ";", "keys"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("cannot be chained"), "stderr: {}", stderr);
+}
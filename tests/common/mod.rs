@@ -14,11 +14,39 @@ pub fn binary_path() -> PathBuf {
 }
 
 /// Run shyaml with given args and stdin, return (stdout, stderr, success).
+#[allow(dead_code)] // Not used by config.rs and env_opts.rs
 pub fn run_shyaml(args: &[&str], stdin_data: &str) -> (String, String, bool) {
+    run_shyaml_with_env(args, stdin_data, &[])
+}
+
+/// Run shyaml with extra environment variables set, return (stdout, stderr, success).
+pub fn run_shyaml_with_env(
+    args: &[&str],
+    stdin_data: &str,
+    envs: &[(&str, &str)],
+) -> (String, String, bool) {
+    let (stdout, stderr, code) = run_shyaml_full(args, stdin_data, envs);
+    (stdout, stderr, code == Some(0))
+}
+
+/// Run shyaml with given args and stdin, return (stdout, stderr, exit code).
+#[allow(dead_code)]
+pub fn run_shyaml_code(args: &[&str], stdin_data: &str) -> (String, String, Option<i32>) {
+    run_shyaml_full(args, stdin_data, &[])
+}
+
+fn run_shyaml_full(
+    args: &[&str],
+    stdin_data: &str,
+    envs: &[(&str, &str)],
+) -> (String, String, Option<i32>) {
     let binary = binary_path();
 
     let mut child = Command::new(&binary)
         .args(args)
+        // Leave out the configuration file of the user running the tests
+        .env("SHYAML_CONFIG", "")
+        .envs(envs.iter().copied())
         .stdin(Stdio::piped())
         .stdout(Stdio::piped())
         .stderr(Stdio::piped())
@@ -26,9 +54,13 @@ pub fn run_shyaml(args: &[&str], stdin_data: &str) -> (String, String, bool) {
         .expect("Failed to spawn shyaml");
 
     if let Some(mut stdin) = child.stdin.take() {
-        stdin
-            .write_all(stdin_data.as_bytes())
-            .expect("Failed to write to stdin");
+        // shyaml may exit before reading its input (e.g. on argument errors)
+        match stdin.write_all(stdin_data.as_bytes()) {
+            Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => {
+                panic!("Failed to write to stdin: {}", e)
+            }
+            _ => {}
+        }
     }
 
     let output = child.wait_with_output().expect("Failed to wait on child");
@@ -36,11 +68,12 @@ pub fn run_shyaml(args: &[&str], stdin_data: &str) -> (String, String, bool) {
     (
         String::from_utf8_lossy(&output.stdout).to_string(),
         String::from_utf8_lossy(&output.stderr).to_string(),
-        output.status.success(),
+        output.status.code(),
     )
 }
 
 /// Assert that actual output equals expected, showing a colored diff on failure.
+#[allow(dead_code)] // Not used by explore.rs, features.rs and truthiness.rs
 pub fn assert_output_eq(actual: &str, expected: &str) {
     if actual != expected {
         let diff = TextDiff::from_lines(expected, actual);
@@ -66,7 +99,7 @@ pub fn assert_output_eq(actual: &str, expected: &str) {
 }
 
 /// Create a temporary file with given content, return its path.
-#[allow(dead_code)] // Used by apply.rs and merge_tags.rs, not all test files
+#[allow(dead_code)] // Used by apply.rs, merge_tags.rs and set_value.rs, not all test files
 pub fn temp_yaml_file(dir: &TempDir, name: &str, content: &str) -> PathBuf {
     let path = dir.path().join(name);
     fs::write(&path, content).expect("Failed to write temp file");
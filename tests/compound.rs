@@ -264,3 +264,70 @@ fn test_backslash_semicolon_is_not_separator() {
         "Expected failure when using literal '\\;' instead of ';'"
     );
 }
+
+#[test]
+fn test_compound_mutation_then_get_values() {
+    let input = indoc! {r#"
+        hosts:
+        - a
+        - b
+        ports: {http: 80}
+    "#};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "append",
+            "hosts",
+            "c",
+            ";",
+            "del",
+            "hosts.0",
+            ";",
+            "get-values",
+            "hosts",
+        ],
+        input,
+    );
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "b\nc\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "ports.https",
+            "443",
+            ";",
+            "get-values-0",
+            "ports",
+        ],
+        input,
+    );
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "http\x0080\0https\x00443\0");
+}
+
+#[test]
+fn test_compound_get_values_after_save() {
+    // save/load chains run on values, the final get-values still prints
+    // every item
+    let input = indoc! {r#"
+        hosts: [a, b]
+    "#};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "orig",
+            ";",
+            "append",
+            "hosts",
+            "c",
+            ";",
+            "get-values",
+            "hosts",
+        ],
+        input,
+    );
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "a\nb\nc\n");
+}
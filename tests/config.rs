@@ -0,0 +1,122 @@
+//! Integration tests for the defaults of global options given by the
+//! configuration file and `SHYAML_<OPTION>` variables
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml_with_env, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const INPUT: &str = indoc! {"
+    name: app
+    db:
+      host: localhost
+      port: 5432
+    extra: 1
+"};
+
+fn with_config(content: &str, args: &[&str], envs: &[(&str, &str)]) -> (String, String, bool) {
+    let dir = TempDir::new().unwrap();
+    let config = temp_yaml_file(&dir, "config.yaml", content);
+    let config = config.to_str().unwrap();
+    let mut envs = envs.to_vec();
+    envs.push(("SHYAML_CONFIG", config));
+    run_shyaml_with_env(args, INPUT, &envs)
+}
+
+#[test]
+fn test_config_file_defaults() {
+    let (stdout, stderr, success) =
+        with_config("indent: 4\nkey-order: sorted\n", &["del", "extra"], &[]);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            db:
+                host: localhost
+                port: 5432
+            name: app
+        "},
+    );
+}
+
+#[test]
+fn test_env_overrides_config_file() {
+    let (stdout, stderr, success) = with_config(
+        "indent: 4\nkey-order: sorted\n",
+        &["del", "extra"],
+        &[("SHYAML_INDENT", "2"), ("SHYAML_KEY_ORDER", "preserve")],
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: app
+            db:
+              host: localhost
+              port: 5432
+        "},
+    );
+}
+
+#[test]
+fn test_command_line_overrides_defaults() {
+    let (stdout, stderr, success) = with_config(
+        "key-order: sorted\ncolor: true\n",
+        &["--no-color", "--key-order", "preserve", "keys"],
+        &[],
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name\ndb\nextra\n");
+}
+
+#[test]
+fn test_config_action_defaults() {
+    let dir = TempDir::new().unwrap();
+    let config = temp_yaml_file(&dir, "config.yaml", "actions:\n  get-value: [-y]\n");
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["get-value", "a"],
+        "a: 'true'\n",
+        &[("SHYAML_CONFIG", config.to_str().unwrap())],
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "'true'\n");
+}
+
+#[test]
+fn test_post_process_has_no_default() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["get-value", "name"],
+        INPUT,
+        &[("SHYAML_POST_PROCESS", "echo injected")],
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "app");
+}
+
+#[test]
+fn test_invalid_config() {
+    let (_, stderr, success) = with_config("indnet: 4\n", &["keys"], &[]);
+    assert!(!success);
+    assert!(
+        stderr.contains("unknown option 'indnet'"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml_with_env(&["keys"], INPUT, &[("SHYAML_YAML", "maybe")]);
+    assert!(!success);
+    assert!(
+        stderr.contains("SHYAML_YAML: expected a boolean"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml_with_env(
+        &["keys"],
+        INPUT,
+        &[("SHYAML_CONFIG", "/nonexistent/config.yaml")],
+    );
+    assert!(!success);
+    assert!(stderr.contains("config file"), "stderr: {}", stderr);
+}
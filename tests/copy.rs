@@ -0,0 +1,114 @@
+//! Integration tests for the `copy` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    defaults:
+      db:
+        host: localhost  # local
+        port: 5432
+"};
+
+#[test]
+fn test_copy_keeps_comments() {
+    let (stdout, stderr, success) = run_shyaml(&["copy", "defaults.db", "prod.db"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            defaults:
+              db:
+                host: localhost # local
+                port: 5432
+            prod:
+              db:
+                host: localhost # local
+                port: 5432
+        "},
+    );
+}
+
+#[test]
+fn test_copy_then_modify_copy() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "copy",
+            "defaults.db",
+            "prod.db",
+            ";",
+            "set-value",
+            "prod.db.host",
+            "prod1",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            defaults:
+              db:
+                host: localhost # local
+                port: 5432
+            prod:
+              db:
+                host: prod1
+                port: 5432
+        "},
+    );
+}
+
+#[test]
+fn test_copy_replaces_destination() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["copy", "defaults.db.port", "port", ";", "get-value", "port"],
+        "defaults:\n  db:\n    port: 5432\nport: 80\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "5432");
+}
+
+#[test]
+fn test_copy_into_itself() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["copy", "defaults", "defaults.base", ";", "keys", "defaults"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db\nbase\n");
+}
+
+#[test]
+fn test_copy_missing_source() {
+    let (stdout, stderr, success) = run_shyaml(&["copy", "missing", "x"], INPUT);
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("missing key 'missing'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_copy_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "doc",
+            ";",
+            "copy",
+            "defaults.db",
+            "prod.db",
+            ";",
+            "get-value",
+            "prod.db.port",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "5432");
+}
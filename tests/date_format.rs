@@ -0,0 +1,78 @@
+//! Integration tests for timestamps: their `get-type` and `get-value --date-format`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+const INPUT: &str = "built: 2001-12-14t21:59:43.10-05:00\nday: 2001-12-14\nquoted: '2001-12-14'\nexplicit: !!timestamp 2001-12-14\nname: web\n";
+
+#[test]
+fn test_get_type_timestamps() {
+    for (path, expected) in [
+        ("built", "timestamp\n"),
+        ("day", "timestamp\n"),
+        ("quoted", "str\n"),
+        ("explicit", "timestamp\n"),
+    ] {
+        let (stdout, stderr, success) = run_shyaml(&["get-type", path], INPUT);
+        assert!(success, "Command failed: {}", stderr);
+        assert_eq!(stdout, expected, "path: {}", path);
+    }
+}
+
+#[test]
+fn test_date_format() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "--date-format", "epoch", "built"], INPUT);
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "1008385183\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "--date-format", "[year]/[month]/[day]", "day"],
+        INPUT,
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "2001/12/14\n");
+
+    // Epoch seconds go on through the chain as integers
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "get-value",
+            "--date-format",
+            "epoch",
+            "day",
+            ";",
+            "get-type",
+        ],
+        INPUT,
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "int\n");
+}
+
+#[test]
+fn test_date_format_errors() {
+    let (_, stderr, success) = run_shyaml(&["get-value", "--date-format", "epoch", "name"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid value at 'name', expected a timestamp, got 'web'."),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "--date-format", "epoch"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("get-value --date-format does not support 'struct' type."),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "--date-format", "[nope]", "day"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid date format '[nope]'"),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -182,3 +182,168 @@ fn test_del_from_root_sequence() {
     assert!(success, "del failed: {}", stderr);
     assert_output_eq(&stdout, expected);
 }
+
+#[test]
+fn test_del_multiple_paths() {
+    let base = indoc! {"
+        a: 1
+        b:
+          c: 2
+          d: 3
+        e: 4
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["del", "a", "b.c", "e"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "b:\n  d: 3\n");
+}
+
+#[test]
+fn test_del_paths_resolved_before_deleting() {
+    let base = indoc! {"
+        seq:
+        - a
+        - b
+        - c
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["del", "seq.0", "seq.1"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "seq:\n- c\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["del", "seq.-1", "seq.0"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "seq:\n- b\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["save", "doc", ";", "del", "seq.0", "seq.1"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "seq:\n- c\n");
+}
+
+#[test]
+fn test_del_wildcard() {
+    let base = indoc! {"
+        services:
+          web:
+            image: nginx  # front
+            debug: true
+          db:
+            image: postgres
+          api:
+            debug: false
+    "};
+
+    let expected = indoc! {"
+        services:
+          web:
+            image: nginx # front
+          db:
+            image: postgres
+          api: {}
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["del", "services.*.debug"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_del_wildcard_sequence_elements() {
+    let base = indoc! {"
+        users:
+          - name: bob
+            tmp: 1
+          - name: alice
+            tmp: 2
+        items:
+          - a
+          - b
+          - c
+    "};
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["del", "users.*.tmp", "items.*", ";", "get-value"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "users:\n- name: bob\n- name: alice\nitems: []");
+}
+
+#[test]
+fn test_del_wildcard_in_value_mode_chain() {
+    let base = indoc! {"
+        a:
+          x: 1
+          y: 2
+        b:
+          x: 3
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["save", "doc", ";", "del", "*.x"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "a:\n  y: 2\nb: {}\n");
+}
+
+#[test]
+fn test_del_escaped_wildcard_key() {
+    let base = indoc! {"
+        hosts:
+          '*': deny
+          example.com: allow
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["del", r"hosts.\*"], base);
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "hosts:\n  example.com: allow\n");
+}
+
+#[test]
+fn test_del_wildcard_matching_nothing() {
+    let (stdout, stderr, success) = run_shyaml(&["del", "*.missing"], "a:\n  x: 1\n");
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "a:\n  x: 1\n");
+}
+
+#[test]
+fn test_del_ignore_missing() {
+    let base = indoc! {"
+        a: 1
+        b:
+          - x
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["del", "--ignore-missing", "missing", "a.c", "b.5", "a"],
+        base,
+    );
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "b:\n- x\n");
+
+    let (stdout, _, success) = run_shyaml(&["del", "missing", "a"], base);
+    assert!(!success);
+    assert!(stdout.is_empty());
+}
+
+#[test]
+fn test_del_ignore_missing_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "doc",
+            ";",
+            "del",
+            "missing",
+            "a",
+            "--ignore-missing",
+        ],
+        "a: 1\nb: 2\n",
+    );
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "b: 2\n");
+}
+
+#[test]
+fn test_del_ignore_missing_on_empty_input() {
+    let (stdout, stderr, success) = run_shyaml(&["del", "--ignore-missing", "a"], "");
+    assert!(success, "del failed: {}", stderr);
+    assert_output_eq(&stdout, "");
+}
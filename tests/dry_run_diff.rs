@@ -0,0 +1,144 @@
+//! Integration tests for the `--dry-run` and `--diff` options of mutations
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const INPUT: &str = indoc! {"
+    # web service
+    name: web
+    replicas: 2
+    debug: true
+"};
+
+#[test]
+fn test_dry_run_exit_code() {
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["set-value", "replicas", "3", "--dry-run"], INPUT);
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "");
+
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["set-value", "replicas", "2", "--dry-run"], INPUT);
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "");
+
+    let (_, stderr, code) =
+        run_shyaml_code(&["del", "--ignore-missing", "nope", "--dry-run"], INPUT);
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_dry_run_skipped_guard_is_unchanged() {
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["set-value", "--if-missing", "replicas", "3", "--dry-run"],
+        INPUT,
+    );
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "");
+}
+
+#[test]
+fn test_dry_run_documents() {
+    // The change of the second document fails the run, though the guard
+    // skipped the write in the first
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["set-value", "--if-missing", "replicas", "3", "--dry-run"],
+        "replicas: 2\n---\nname: web\n",
+    );
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "");
+}
+
+#[test]
+fn test_diff_unified() {
+    let (stdout, stderr, code) = run_shyaml_code(&["set-value", "replicas", "3", "--diff"], INPUT);
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            --- <stdin>
+            +++ <stdin>
+            @@ -1,4 +1,4 @@
+             # web service
+             name: web
+            -replicas: 2
+            +replicas: 3
+             debug: true
+        "},
+    );
+
+    let (stdout, _, code) = run_shyaml_code(&["set-value", "replicas", "2", "--diff"], INPUT);
+    assert_eq!(code, Some(0));
+    assert_output_eq(&stdout, "");
+}
+
+#[test]
+fn test_diff_before_positionals() {
+    let (stdout, stderr, code) = run_shyaml_code(&["set-value", "--diff", "replicas", "3"], INPUT);
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert!(stdout.contains("+replicas: 3\n"), "stdout: {}", stdout);
+
+    let (stdout, stderr, code) = run_shyaml_code(&["del", "--diff=paths", "debug"], INPUT);
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "- debug: true\n");
+}
+
+#[test]
+fn test_diff_paths() {
+    let (stdout, stderr, code) = run_shyaml_code(
+        &[
+            "set-value",
+            "name",
+            "api",
+            ";",
+            "del",
+            "debug",
+            "--diff=paths",
+        ],
+        INPUT,
+    );
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "~ name: web -> api\n- debug: true\n");
+}
+
+#[test]
+fn test_diff_apply() {
+    let tmp = TempDir::new().unwrap();
+    let overlay = temp_yaml_file(&tmp, "overlay.yaml", "replicas: 5\nimage: nginx\n");
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["apply", overlay.to_str().unwrap(), "--diff=paths"], INPUT);
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "~ replicas: 2 -> 5\n+ image: nginx\n");
+}
+
+#[test]
+fn test_diff_apply_file_label() {
+    let tmp = TempDir::new().unwrap();
+    let base = temp_yaml_file(&tmp, "base.yaml", "a: 1\n");
+    let overlay = temp_yaml_file(&tmp, "overlay.yaml", "a: 2\n");
+    let base = base.to_str().unwrap();
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["--file", base, "apply", overlay.to_str().unwrap(), "--diff"],
+        "",
+    );
+    assert_eq!(code, Some(1), "stderr: {}", stderr);
+    assert!(stdout.starts_with(&format!("--- {}\n+++ {}\n", base, base)));
+    assert!(stdout.ends_with("-a: 1\n+a: 2\n"));
+}
+
+#[test]
+fn test_preview_only_on_last_action() {
+    let (_, stderr, success) = run_shyaml(
+        &["set-value", "a", "1", "--dry-run", ";", "del", "debug"],
+        INPUT,
+    );
+    assert!(!success);
+    assert!(stderr.contains("can only be used on the last action of a chain"));
+
+    let (_, stderr, success) = run_shyaml(&["set-value", "a", "1", "--dry-run", "--diff"], INPUT);
+    assert!(!success);
+    assert!(stderr.contains("cannot be used with"));
+}
@@ -0,0 +1,104 @@
+//! Integration tests for the `ensure` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    # config
+    name: web
+    spec:
+      replicas: 2
+"};
+
+#[test]
+fn test_ensure_leaves_existing_value() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["ensure", "spec.replicas", "--default", "1"], INPUT);
+    assert!(success, "ensure failed: {}", stderr);
+    assert_output_eq(&stdout, INPUT);
+}
+
+#[test]
+fn test_ensure_creates_default() {
+    let (stdout, stderr, success) = run_shyaml(&["ensure", "spec.port", "--default", "80"], INPUT);
+    assert!(success, "ensure failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # config
+            name: web
+            spec:
+              replicas: 2
+              port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_ensure_empty_values_by_type() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "ensure", "labels", ";", "ensure", "args", "--type", "seq", ";", "ensure", "note",
+            "--type", "str",
+        ],
+        "name: web\n",
+    );
+    assert!(success, "ensure failed: {}", stderr);
+    assert_output_eq(&stdout, "name: web\nlabels: {}\nargs: []\nnote: ''\n");
+}
+
+#[test]
+fn test_ensure_is_idempotent_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "get-value",
+            "spec",
+            ";",
+            "ensure",
+            "port",
+            "--default",
+            "80",
+            ";",
+            "ensure",
+            "port",
+            "--default",
+            "8080",
+        ],
+        INPUT,
+    );
+    assert!(success, "ensure failed: {}", stderr);
+    assert_output_eq(&stdout, "replicas: 2\nport: 80\n");
+}
+
+#[test]
+fn test_ensure_default_of_wrong_type_error() {
+    let (_, stderr, success) = run_shyaml(
+        &["ensure", "args", "--type", "seq", "--default", "x"],
+        INPUT,
+    );
+    assert!(
+        !success,
+        "ensure should fail on a default of the wrong type"
+    );
+    assert!(stderr.contains("ensure --type seq expects a 'sequence' default, got 'str'."));
+}
+
+#[test]
+fn test_ensure_under_scalar_error() {
+    let (_, stderr, success) = run_shyaml(&["ensure", "name.first"], INPUT);
+    assert!(!success, "ensure should fail below a scalar");
+    assert!(stderr.contains("cannot set value on scalar"));
+}
+
+#[test]
+fn test_ensure_index_out_of_range_error() {
+    let (_, stderr, code) = run_shyaml_code(&["ensure", "b.9"], "b: [1]\n");
+    assert_eq!(code, Some(2), "stderr: {}", stderr);
+    assert!(
+        stderr.contains("invalid path 'b.9', index 9 is out of range"),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -0,0 +1,93 @@
+//! Integration tests for the `SHYAML_<ACTION>_OPTS` default options
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml_with_env};
+use indoc::indoc;
+use tempfile::TempDir;
+
+#[test]
+fn test_default_flags() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["get-value", "a"],
+        "a: 'true'\n",
+        &[("SHYAML_GET_VALUE_OPTS", "-y")],
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "'true'\n");
+
+    // Each action of a chain gets its own defaults
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["set-value", "b", "'x'", ";", "get-value", "b"],
+        "a: 1\n",
+        &[
+            ("SHYAML_GET_VALUE_OPTS", "-y"),
+            ("SHYAML_SET_VALUE_OPTS", "--yaml"),
+        ],
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "x");
+}
+
+#[test]
+fn test_command_line_overrides_defaults() {
+    let envs = [("SHYAML_GET_TYPE_OPTS", "--schema json")];
+    let (stdout, stderr, success) = run_shyaml_with_env(&["get-type", "a"], "a: ~\n", &envs);
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "str\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml_with_env(&["get-type", "--schema", "core", "a"], "a: ~\n", &envs);
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "NoneType\n");
+
+    // A flag given in both places is accepted
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["get-value", "-y", "a"],
+        "a: 'true'\n",
+        &[("SHYAML_GET_VALUE_OPTS", "-y")],
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "'true'\n");
+}
+
+#[test]
+fn test_quoted_values() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["key-values"],
+        "a: 1\nb: x\n",
+        &[("SHYAML_KEY_VALUES_OPTS", "--kv-separator ': '")],
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb: x\n");
+
+    let (_, stderr, success) = run_shyaml_with_env(
+        &["key-values"],
+        "a: 1\n",
+        &[("SHYAML_KEY_VALUES_OPTS", "--kv-separator ': ")],
+    );
+    assert!(!success);
+    assert!(stderr.contains("unbalanced quotes"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_apply_merge_policy_defaults() {
+    let tmp = TempDir::new().unwrap();
+    let overlay = tmp.path().join("overlay.yaml");
+    std::fs::write(&overlay, "tags: [y]\n").unwrap();
+
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["apply", overlay.to_str().unwrap()],
+        "tags: [x]\nname: a\n",
+        &[("SHYAML_APPLY_OPTS", "-m tags=replace")],
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            tags:
+            - y
+            name: a
+        "},
+    );
+}
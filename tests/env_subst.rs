@@ -0,0 +1,103 @@
+//! Integration tests for the `--env-subst` interpolation mode
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_with_env};
+use indoc::indoc;
+
+#[test]
+fn test_env_subst_expands_values() {
+    let input = indoc! {"
+        url: http://${DB_HOST}:5432
+        name: ${APP_NAME:-default-app}
+    "};
+
+    let expected = "url: http://db.internal:5432\nname: default-app";
+
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["--env-subst", "get-value"],
+        input,
+        &[("DB_HOST", "db.internal")],
+    );
+    assert!(success, "env-subst failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_env_subst_before_action() {
+    let input = indoc! {"
+        image: app:${TAG}
+    "};
+
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["--env-subst", "get-value", "image"],
+        input,
+        &[("TAG", "1.2.3")],
+    );
+    assert!(success, "env-subst failed: {}", stderr);
+    assert_output_eq(&stdout, "app:1.2.3");
+}
+
+#[test]
+fn test_env_subst_undefined_is_empty() {
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["--env-subst", "get-value", "a"],
+        "a: x${SHYAML_TEST_UNDEFINED_VAR}y\n",
+        &[],
+    );
+    assert!(success, "env-subst failed: {}", stderr);
+    assert_output_eq(&stdout, "xy");
+}
+
+#[test]
+fn test_env_subst_strict_undefined_errors() {
+    let (_, stderr, success) = run_shyaml_with_env(
+        &["--env-subst-strict", "get-value", "a"],
+        "a: ${SHYAML_TEST_UNDEFINED_VAR}\n",
+        &[],
+    );
+    assert!(!success, "strict mode should fail on undefined variable");
+    assert!(stderr.contains("undefined environment variable 'SHYAML_TEST_UNDEFINED_VAR'"));
+}
+
+#[test]
+fn test_without_env_subst_values_are_literal() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "a"], "a: ${TAG}\n");
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "${TAG}");
+}
+
+#[test]
+fn test_env_allow_hides_other_variables() {
+    let input = indoc! {"
+        name: ${APP_NAME}
+        key: ${SECRET_KEY:-none}
+        tag: ${TAG}
+    "};
+
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["--env-subst", "--env-allow", "APP_*,TAG", "get-value"],
+        input,
+        &[("APP_NAME", "web"), ("SECRET_KEY", "s3cr3t"), ("TAG", "v1")],
+    );
+    assert!(success, "env-subst failed: {}", stderr);
+    assert_output_eq(&stdout, "name: web\nkey: none\ntag: v1");
+}
+
+#[test]
+fn test_env_allow_strict_errors_on_hidden_variable() {
+    let (_, stderr, success) = run_shyaml_with_env(
+        &["--env-subst-strict", "--env-allow", "TAG", "get-value", "a"],
+        "a: ${SECRET_KEY}\n",
+        &[("SECRET_KEY", "s3cr3t")],
+    );
+    assert!(!success);
+    assert!(stderr.contains("undefined environment variable 'SECRET_KEY'"));
+}
+
+#[test]
+fn test_env_allow_requires_env_subst() {
+    let (_, stderr, success) = run_shyaml(&["--env-allow", "TAG", "get-value", "a"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("--env-allow requires --env-subst"));
+}
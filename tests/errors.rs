@@ -0,0 +1,127 @@
+//! Integration tests for error exit codes and `--errors json`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code};
+
+#[test]
+fn test_error_exit_codes() {
+    for (args, input, code) in [
+        (&["get-value", "b"][..], "a: 1\n", 2),
+        (&["set-value", "a.b", "2"], "a: 1\n", 2),
+        (&["get-length", "a"], "a: 1\n", 3),
+        (&["keys"], "", 3),
+        (&["get-value", "a"], "a: [1\n", 4),
+        (&["--input-format", "toml", "keys"], "a = [\n", 4),
+        (&["--allow-paths", "/nonexistent", "keys"], "a: 1\n", 5),
+        (&["nope"], "a: 1\n", 127),
+    ] {
+        let (stdout, stderr, status) = run_shyaml_code(args, input);
+        assert_eq!(status, Some(code), "{:?}: {}", args, stderr);
+        assert!(stdout.is_empty());
+        assert!(stderr.starts_with("Error: "), "stderr: {}", stderr);
+    }
+}
+
+#[test]
+fn test_errors_json() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["--errors", "json", "get-value", "a.c"], "a: {c: 1}\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "1");
+
+    let (_, stderr, status) =
+        run_shyaml_code(&["--errors", "json", "get-value", "a.b"], "a: {c: 1}\n");
+    assert_eq!(status, Some(2));
+    assert_output_eq(
+        &stderr,
+        "{\"code\": \"path-not-found\", \"path\": \"a.b\", \"message\": \"invalid path 'a.b', missing key 'b' in struct.\"}\n",
+    );
+}
+
+#[test]
+fn test_errors_json_path_with_quotes() {
+    let (_, stderr, status) =
+        run_shyaml_code(&["--errors", "json", "get-value", "it's', x.y"], "a: 1\n");
+    assert_eq!(status, Some(2));
+    assert!(
+        stderr.starts_with("{\"code\": \"path-not-found\", \"path\": \"it's', x.y\", "),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_errors_json_without_path() {
+    let (_, stderr, status) = run_shyaml_code(&["--errors", "json", "get-value"], "a: \"x\n");
+    assert_eq!(status, Some(4));
+    assert!(
+        stderr.starts_with("{\"code\": \"parse-error\", \"path\": null, \"message\": \""),
+        "stderr: {}",
+        stderr
+    );
+    assert!(stderr.ends_with("\"}\n"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_errors_json_escapes_message() {
+    let (_, stderr, status) = run_shyaml_code(
+        &["--errors", "json", "--input-format", "toml", "keys"],
+        "a = [\n",
+    );
+    assert_eq!(status, Some(4));
+    assert_eq!(stderr.lines().count(), 1, "stderr: {}", stderr);
+    assert!(stderr.contains("\\n"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_quiet_errors_exit_with_1() {
+    let (stdout, stderr, status) =
+        run_shyaml_code(&["-q", "--errors", "json", "get-value", "b"], "a: 1\n");
+    assert_eq!(status, Some(1));
+    assert!(stdout.is_empty());
+    assert!(stderr.is_empty());
+}
+
+#[test]
+fn test_quiet_levels() {
+    // -q only silences path errors
+    let (_, stderr, status) = run_shyaml_code(&["-q", "get-length", "a"], "a: 3\n");
+    assert_eq!(status, Some(3));
+    assert!(stderr.contains("get-length does not support 'int' type"));
+
+    // -qq silences all errors, which keep their exit code
+    let (stdout, stderr, status) = run_shyaml_code(&["-qq", "get-length", "a"], "a: 3\n");
+    assert_eq!(status, Some(3));
+    assert!(stdout.is_empty());
+    assert!(stderr.is_empty());
+
+    let (_, stderr, status) = run_shyaml_code(&["-q", "-q", "get-value", "a"], "a: [1\n");
+    assert_eq!(status, Some(4));
+    assert!(stderr.is_empty());
+
+    let (_, stderr, status) = run_shyaml_code(&["-qq", "get-value", "b"], "a: 1\n");
+    assert_eq!(status, Some(1));
+    assert!(stderr.is_empty());
+}
+
+#[test]
+fn test_parse_error_location() {
+    let (_, stderr, status) = run_shyaml_code(&["get-value", "a"], "a: 1\nb: [1, 2\n");
+    assert_eq!(status, Some(4));
+    assert_output_eq(
+        &stderr,
+        "Error: Parse error at 3:1: flow sequence without a closing bracket\n",
+    );
+}
+
+#[test]
+fn test_value_parse_error_shows_value() {
+    let (_, stderr, status) = run_shyaml_code(&["set-value", "a", "{b: [1", "-y"], "a: 1\n");
+    assert_eq!(status, Some(4));
+    assert!(
+        stderr.ends_with("Parse error at 2:1: flow sequence without a closing bracket\n 1 | {b: [1\n   |       ^\n"),
+        "stderr: {}",
+        stderr
+    );
+}
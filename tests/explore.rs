@@ -0,0 +1,23 @@
+//! Integration tests for the `explore` action
+//!
+//! The tree view itself needs a terminal, only its checks of the document
+//! are tested here.
+
+mod common;
+
+use common::run_shyaml;
+
+#[test]
+fn test_explore_scalar_errors() {
+    let (stdout, stderr, success) = run_shyaml(&["explore", "a"], "a: 1\n");
+    assert!(!success);
+    assert_eq!(stdout, "");
+    assert!(stderr.contains("explore does not support"), "{}", stderr);
+}
+
+#[test]
+fn test_explore_missing_path_errors() {
+    let (_, stderr, success) = run_shyaml(&["explore", "b"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("missing key 'b'"), "{}", stderr);
+}
@@ -0,0 +1,84 @@
+//! Integration tests for the `export` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+#[test]
+fn test_export_mapping() {
+    let input = indoc! {"
+        db:
+          host: db.local
+          port: 5432
+          password: it's $(secret)
+    "};
+
+    let expected = indoc! {r#"
+        export host='db.local'
+        export port='5432'
+        export password='it'\''s $(secret)'
+    "#};
+
+    let (stdout, stderr, success) = run_shyaml(&["export", "db"], input);
+    assert!(success, "export failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_export_prefix_and_sanitized_names() {
+    let input = indoc! {"
+        db-host: a
+        1st: b
+    "};
+
+    let expected = indoc! {"
+        export APP_db_host='a'
+        export APP_1st='b'
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["export", "--prefix", "APP_"], input);
+    assert!(success, "export failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_export_invalid_name_error() {
+    let (_, stderr, success) = run_shyaml(&["export"], "1st: b\n");
+    assert!(
+        !success,
+        "export should fail on a key starting with a digit"
+    );
+    assert!(stderr.contains("cannot export key '1st'"));
+}
+
+#[test]
+fn test_export_roundtrips_through_shell() {
+    let input = "msg: \"it's a \\\"test\\\"\\nwith $HOME\"\n";
+    let (stdout, stderr, success) = run_shyaml(&["export"], input);
+    assert!(success, "export failed: {}", stderr);
+
+    let output = std::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!("{}printf '%s' \"$msg\"", stdout))
+        .output()
+        .expect("Failed to run sh");
+    assert_output_eq(
+        &String::from_utf8_lossy(&output.stdout),
+        "it's a \"test\"\nwith $HOME",
+    );
+}
+
+#[test]
+fn test_export_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(&["set-value", "b", "2", ";", "export"], "a: 1\n");
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "export a='1'\nexport b='2'\n");
+}
+
+#[test]
+fn test_export_scalar_error() {
+    let (_, stderr, success) = run_shyaml(&["export", "a"], "a: 1\n");
+    assert!(!success, "export should fail on a scalar");
+    assert!(stderr.contains("export does not support 'int' type"));
+}
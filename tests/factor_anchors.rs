@@ -0,0 +1,93 @@
+//! Integration tests for the `--factor-anchors` output option
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    base: &base
+      image: web
+      ports:
+      - 80
+      - 443
+    dev: *base
+    prod:
+      image: web
+      ports: [80, 443]
+"};
+
+#[test]
+fn test_factor_anchors_restores_aliases() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["--factor-anchors", "set-value", "name", "app"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            base: &base
+              image: web
+              ports:
+              - 80
+              - 443
+            dev: *base
+            prod: *base
+            name: app
+        "},
+    );
+}
+
+#[test]
+fn test_without_factor_anchors_aliases_are_expanded() {
+    let (stdout, stderr, success) = run_shyaml(&["del", "prod"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert!(!stdout.contains('*'), "stdout: {}", stdout);
+}
+
+#[test]
+fn test_factor_anchors_after_mutation_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--factor-anchors",
+            "set-value",
+            "prod.image",
+            "api",
+            ";",
+            "del",
+            "base",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            dev:
+              image: web
+              ports: &ports
+              - 80
+              - 443
+            prod:
+              image: api
+              ports: *ports
+        "},
+    );
+}
+
+#[test]
+fn test_factor_anchors_get_value() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--factor-anchors", "get-value", "-y"],
+        "a: [x, y]\nb:\n- [x, y]\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: &a\n- x\n- y\nb:\n- *a\n");
+}
+
+#[test]
+fn test_factor_anchors_without_repetition() {
+    let input = "a:\n  b: 1\nc:\n- 2\n";
+    let (stdout, stderr, success) = run_shyaml(&["--factor-anchors", "set-value", "d", "3"], input);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a:\n  b: 1\nc:\n- 2\nd: 3\n");
+}
@@ -0,0 +1,27 @@
+//! Integration tests for the `features` action
+
+mod common;
+
+use common::run_shyaml;
+
+#[test]
+fn test_features_is_yaml() {
+    let (stdout, stderr, success) = run_shyaml(&["features"], "");
+    assert!(success, "stderr: {}", stderr);
+
+    let (keys, stderr, success) = run_shyaml(&["keys", "features"], &stdout);
+    assert!(success, "stderr: {}", stderr);
+    assert_eq!(keys, "net\ncrypto\ntui\nwatch\ncompat\n");
+
+    let (yaml, _, _) = run_shyaml(&["get-value", "backends.yaml"], &stdout);
+    assert!(yaml.starts_with("libfyaml "), "{}", yaml);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_features_on_linux() {
+    let (stdout, _, _) = run_shyaml(&["features"], "");
+    let (tui, _, _) = run_shyaml(&["get-value", "features.tui"], &stdout);
+    let (watch, _, _) = run_shyaml(&["get-value", "features.watch"], &stdout);
+    assert_eq!((tui.as_str(), watch.as_str()), ("true", "true"));
+}
@@ -0,0 +1,105 @@
+//! Integration tests for the `filter` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    services:
+    - name: web
+      port: 80
+      enabled: true
+    - name: db
+      port: 5432
+      enabled: false
+    - name: cache
+      port: 6379
+"};
+
+#[test]
+fn test_filter_select() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["filter", ".services[] | select(.port > 100) | .name"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db\ncache\n");
+}
+
+#[test]
+fn test_filter_structures() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["filter", ".services | map(select(.enabled)) | .[0]"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name: web\nport: 80\nenabled: true\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["filter", ".services[] | .enabled // \"unset\""], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "true\nunset\nunset\n");
+}
+
+#[test]
+fn test_filter_after_mutation() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "services.2.enabled",
+            "true",
+            ";",
+            "filter",
+            "[.services[] | select(.enabled) | .name]",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "- web\n- cache\n");
+}
+
+#[test]
+fn test_filter_in_chain() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["filter", ".services[0]", ";", "get-value", "port"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "80");
+
+    let (_, stderr, success) = run_shyaml(
+        &["filter", ".services[].name", ";", "get-value", "0"],
+        INPUT,
+    );
+    assert!(!success);
+    assert!(
+        stderr.contains("gave 3 results, the next action needs one"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_filter_empty_document() {
+    let (stdout, stderr, success) = run_shyaml(&["filter", ".a // \"none\""], "");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "none\n");
+}
+
+#[test]
+fn test_filter_errors() {
+    let (_, stderr, success) = run_shyaml(&["filter", ".services.name"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("index 'name' does not support 'sequence' type"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["filter", ".services | shout"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid filter '.services | shout': unknown function 'shout'"),
+        "stderr: {}",
+        stderr
+    );
+}
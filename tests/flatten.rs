@@ -0,0 +1,77 @@
+//! Integration tests for the `flatten` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    db:
+      host: db.local
+      ports:
+      - 5432
+      - 5433
+    www.example.com:
+      enabled: true
+"};
+
+#[test]
+fn test_flatten_all_leaves() {
+    let expected = indoc! {r"
+        db.host=db.local
+        db.ports.0=5432
+        db.ports.1=5433
+        www\.example\.com.enabled=true
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["flatten"], INPUT);
+    assert!(success, "flatten failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_flatten_path_keeps_full_paths() {
+    let (stdout, stderr, success) = run_shyaml(&["flatten", "db.ports"], INPUT);
+    assert!(success, "flatten failed: {}", stderr);
+    assert_output_eq(&stdout, "db.ports.0=5432\ndb.ports.1=5433\n");
+}
+
+#[test]
+fn test_flatten_paths_work_with_get_value() {
+    let (stdout, stderr, success) = run_shyaml(&["flatten-0", "www\\.example\\.com"], INPUT);
+    assert!(success, "flatten-0 failed: {}", stderr);
+    let path = stdout.split('\0').next().unwrap();
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", path], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "true");
+}
+
+#[test]
+fn test_flatten_0_nul_separated() {
+    let (stdout, stderr, success) = run_shyaml(&["flatten-0"], "a:\n  b: |\n    x y\n    z\n");
+    assert!(success, "flatten-0 failed: {}", stderr);
+    assert_output_eq(&stdout, "a.b\0x y\nz\n\0");
+}
+
+#[test]
+fn test_flatten_values_on_one_line() {
+    let input = "a: |\n  x\n  y\nb: null\nc: ''\nd: '8'\ne: {}\n";
+    let (stdout, stderr, success) = run_shyaml(&["flatten"], input);
+    assert!(success, "flatten failed: {}", stderr);
+    assert_output_eq(&stdout, "a=\"x\\ny\\n\"\nb=null\nc=''\nd='8'\ne={}\n");
+}
+
+#[test]
+fn test_flatten_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(&["del", "db", ";", "flatten"], INPUT);
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "www\\.example\\.com.enabled=true\n");
+}
+
+#[test]
+fn test_flatten_scalar_error() {
+    let (_, stderr, success) = run_shyaml(&["flatten", "db.host"], INPUT);
+    assert!(!success, "flatten should fail on a scalar");
+    assert!(stderr.contains("flatten does not support 'str' type"));
+}
@@ -0,0 +1,122 @@
+//! Integration tests for the `get-type` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    yes: yes
+    on: On
+    tilde: ~
+    null: Null
+    empty:
+    true: TRUE
+    hex: 0x1F
+    octal: 0o17
+    int: -12
+    float: 1.5e3
+    inf: -.inf
+    date: 2001-12-14
+    quoted: '12'
+    tagged: !secret 12
+    seq: [1]
+"};
+
+fn types(schema: Option<&str>) -> String {
+    let mut out = String::new();
+    for key in [
+        "yes", "on", "tilde", "null", "empty", "true", "hex", "octal", "int", "float", "inf",
+        "date", "quoted", "tagged", "seq",
+    ] {
+        let mut args = vec!["get-type", key];
+        if let Some(schema) = schema {
+            args.extend(["--schema", schema]);
+        }
+        let (stdout, stderr, success) = run_shyaml(&args, INPUT);
+        assert!(success, "get-type {} failed: {}", key, stderr);
+        out.push_str(&format!("{}: {}", key, stdout));
+    }
+    out
+}
+
+#[test]
+fn test_get_type_core_schema() {
+    assert_output_eq(
+        &types(Some("core")),
+        indoc! {"
+            yes: str
+            on: str
+            tilde: NoneType
+            null: NoneType
+            empty: NoneType
+            true: bool
+            hex: int
+            octal: int
+            int: int
+            float: float
+            inf: float
+            date: str
+            quoted: str
+            tagged: !secret
+            seq: sequence
+        "},
+    );
+}
+
+#[test]
+fn test_get_type_json_schema() {
+    assert_output_eq(
+        &types(Some("json")),
+        indoc! {"
+            yes: str
+            on: str
+            tilde: str
+            null: str
+            empty: str
+            true: str
+            hex: str
+            octal: str
+            int: int
+            float: float
+            inf: str
+            date: str
+            quoted: str
+            tagged: !secret
+            seq: sequence
+        "},
+    );
+}
+
+#[test]
+fn test_get_type_failsafe_schema() {
+    let out = types(Some("failsafe"));
+    for line in out.lines() {
+        match line.split_once(": ") {
+            Some(("tagged", t)) => assert_eq!(t, "!secret"),
+            Some(("seq", t)) => assert_eq!(t, "sequence"),
+            _ => assert!(line.ends_with(": str"), "{}", out),
+        }
+    }
+}
+
+#[test]
+fn test_get_type_default_keeps_yaml_1_1_types() {
+    let out = types(None);
+    assert!(out.contains("yes: bool\n"), "{}", out);
+    assert!(out.contains("on: bool\n"), "{}", out);
+    assert!(out.contains("tilde: NoneType\n"), "{}", out);
+    assert!(out.contains("date: timestamp\n"), "{}", out);
+}
+
+#[test]
+fn test_get_type_schema_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save", "doc", ";", "get-type", "--schema", "failsafe", "int",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "str\n");
+}
@@ -0,0 +1,92 @@
+//! Integration tests for the `get-types` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+#[test]
+fn test_get_types_sequence() {
+    let input = indoc! {"
+        items:
+        - 1
+        - two
+        - 3.5
+        - true
+        - ~
+        - {a: 1}
+        - [x]
+    "};
+
+    let expected = indoc! {"
+        int
+        str
+        float
+        bool
+        NoneType
+        struct
+        sequence
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["get-types", "items"], input);
+    assert!(success, "get-types failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_get_types_reports_tags() {
+    let input = indoc! {"
+        - !secret abc
+        - plain
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["get-types"], input);
+    assert!(success, "get-types failed: {}", stderr);
+    assert_output_eq(&stdout, "!secret\nstr\n");
+}
+
+#[test]
+fn test_get_types_mapping_values() {
+    let input = indoc! {"
+        a: 1
+        b: text
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["get-types"], input);
+    assert!(success, "get-types failed: {}", stderr);
+    assert_output_eq(&stdout, "int\nstr\n");
+}
+
+#[test]
+fn test_get_types_0_nul_separated() {
+    let input = indoc! {"
+        - 1
+        - a
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["get-types-0"], input);
+    assert!(success, "get-types-0 failed: {}", stderr);
+    assert_output_eq(&stdout, "int\0str\0");
+}
+
+#[test]
+fn test_get_types_in_chain() {
+    let input = indoc! {"
+        items:
+        - 1
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "items.0", "x", ";", "get-types", "items"],
+        input,
+    );
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "str\n");
+}
+
+#[test]
+fn test_get_types_scalar_error() {
+    let (_, stderr, success) = run_shyaml(&["get-types", "a"], "a: 1\n");
+    assert!(!success, "get-types should fail on a scalar");
+    assert!(stderr.contains("get-types does not support 'int' type"));
+}
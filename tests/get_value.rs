@@ -0,0 +1,207 @@
+//! Integration tests for `get-value` defaults, type checks and aliases
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+#[test]
+fn test_default_is_raw_string() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "b", "{x: 1}", ";", "get-type"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "str\n");
+}
+
+#[test]
+fn test_default_yaml_structure() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "b", "{x: [1, 2]}", "--default-yaml"],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "x:\n- 1\n- 2");
+}
+
+#[test]
+fn test_default_yaml_scalar_output() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "b", "'quoted'", "--default-yaml"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "quoted");
+}
+
+#[test]
+fn test_default_yaml_is_typed_in_chain() {
+    for (default, expected) in [("42", "int\n"), ("true", "bool\n"), ("[1]", "sequence\n")] {
+        let (stdout, stderr, success) = run_shyaml(
+            &["get-value", "b", default, "--default-yaml", ";", "get-type"],
+            "a: 1\n",
+        );
+        assert!(success, "stderr: {}", stderr);
+        assert_output_eq(&stdout, expected);
+    }
+}
+
+#[test]
+fn test_default_yaml_on_empty_input() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "b", "[1]", "--default-yaml", "-y"], "");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "- 1");
+}
+
+#[test]
+fn test_default_yaml_not_used_when_found() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "a", "[1]", "--default-yaml"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "1");
+}
+
+#[test]
+fn test_default_yaml_invalid() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "b", "\"x", "--default-yaml"], "a: 1\n");
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("Failed to parse value as YAML"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_into_json_schema_type_matches() {
+    for (path, expected) in [
+        ("a", "integer"),
+        ("a", "number"),
+        ("b", "number"),
+        ("c", "string"),
+        ("d", "array"),
+        ("e", "object"),
+        ("f", "boolean"),
+        ("g", "null"),
+    ] {
+        let (_, stderr, success) = run_shyaml(
+            &["get-value", path, "--into-json-schema-type", expected],
+            "a: 1\nb: 1.5\nc: x\nd: [1]\ne: {x: 1}\nf: true\ng:\n",
+        );
+        assert!(success, "{} as {}: {}", path, expected, stderr);
+    }
+}
+
+#[test]
+fn test_into_json_schema_type_mismatch() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "a.port", "--into-json-schema-type", "integer"],
+        "a:\n  port: \"80\"\n",
+    );
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr
+            .contains("invalid value at 'a.port', expected JSON Schema type 'integer', got 'str'."),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_into_json_schema_type_checks_default() {
+    let (_, stderr, success) = run_shyaml(
+        &["get-value", "b", "80", "--into-json-schema-type", "integer"],
+        "a: 1\n",
+    );
+    assert!(!success);
+    assert!(stderr.contains("got 'str'"), "stderr: {}", stderr);
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "get-value",
+            "b",
+            "80",
+            "--default-yaml",
+            "--into-json-schema-type",
+            "integer",
+        ],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "80");
+}
+
+#[test]
+fn test_into_json_schema_type_in_value_mode_chain() {
+    let (_, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "doc",
+            ";",
+            "get-value",
+            "a",
+            "--into-json-schema-type",
+            "array",
+        ],
+        "a: 1\n",
+    );
+    assert!(!success);
+    assert!(stderr.contains("got 'int'"), "stderr: {}", stderr);
+}
+
+const ANCHORS: &str = "envs:\n  base: &base\n    image: web\n  dev: *base\n";
+
+#[test]
+fn test_keep_aliases() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--keep-aliases", "envs"], ANCHORS);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(stdout.trim_end(), "base: &base\n  image: web\ndev: *base");
+
+    // The last flag wins
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "--keep-aliases", "--resolve-aliases", "envs"],
+        ANCHORS,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(stdout.trim_end(), "base:\n  image: web\ndev:\n  image: web");
+}
+
+#[test]
+fn test_keep_aliases_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "envs", ";", "get-value", "--keep-aliases"],
+        ANCHORS,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(stdout.trim_end(), "base: &base\n  image: web\ndev: *base");
+}
+
+#[test]
+fn test_keep_aliases_keeps_anchor_names() {
+    let input = "base: &base_cfg\n  image: web\ndev: *base_cfg\n";
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--keep-aliases"], input);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(stdout.trim_end(), input.trim_end());
+}
+
+#[test]
+fn test_keep_aliases_leaves_equal_copies() {
+    // Equal mappings that weren't aliases stay copies
+    let input = "dev:\n  image: web\nprod:\n  image: web\n";
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--keep-aliases"], input);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(stdout.trim_end(), input.trim_end());
+}
+
+#[test]
+fn test_keep_aliases_of_each_document() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "--keep-aliases"],
+        "a: &x\n  k: 1\nb: *x\n---\nc: &y\n  k: 2\nd: *y\ne:\n  k: 2\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        stdout.trim_end(),
+        "a: &x\n  k: 1\nb: *x\0c: &y\n  k: 2\nd: *y\ne:\n  k: 2",
+    );
+}
@@ -0,0 +1,69 @@
+//! Integration tests for the `introspect` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+fn query(catalogue: &str, path: &str) -> String {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", path], catalogue);
+    assert!(success, "{}: {}", path, stderr);
+    stdout
+}
+
+#[test]
+fn test_introspect_actions() {
+    let (catalogue, stderr, success) = run_shyaml(&["introspect"], "");
+    assert!(success, "stderr: {}", stderr);
+
+    assert_output_eq(&query(&catalogue, "actions.0.name"), "get-value");
+    assert_output_eq(&query(&catalogue, "actions.0.kind"), "read-only");
+    assert_output_eq(
+        &query(&catalogue, "actions.0.about"),
+        "Get node value from given path",
+    );
+    assert_output_eq(&query(&catalogue, "actions.0.arguments.0.name"), "PATH");
+    assert_output_eq(
+        &query(&catalogue, "actions.0.arguments.0.help"),
+        "The path to get value of",
+    );
+    assert_output_eq(
+        &query(&catalogue, "actions.0.arguments.0.required"),
+        "false",
+    );
+}
+
+#[test]
+fn test_introspect_options() {
+    let (catalogue, stderr, success) = run_shyaml(&["introspect"], "");
+    assert!(success, "stderr: {}", stderr);
+
+    // get-type --path and --schema
+    assert_output_eq(&query(&catalogue, "actions.1.name"), "get-type");
+    assert_output_eq(&query(&catalogue, "actions.1.options.0.long"), "--path");
+    let schema = "actions.1.options.1";
+    assert_output_eq(&query(&catalogue, &format!("{}.long", schema)), "--schema");
+    assert_output_eq(&query(&catalogue, &format!("{}.arity", schema)), "value");
+    assert_output_eq(&query(&catalogue, &format!("{}.value", schema)), "SCHEMA");
+    assert_output_eq(&query(&catalogue, &format!("{}.choices.0", schema)), "core");
+
+    // Global options come first
+    assert_output_eq(&query(&catalogue, "options.0.long"), "--color");
+    assert_output_eq(&query(&catalogue, "options.0.arity"), "flag");
+}
+
+#[test]
+fn test_introspect_json() {
+    let (catalogue, stderr, success) = run_shyaml(&["introspect", "--format", "json"], "");
+    assert!(success, "stderr: {}", stderr);
+    assert!(
+        catalogue.starts_with("{\"version\": "),
+        "stdout: {}",
+        catalogue
+    );
+
+    // JSON is YAML too
+    assert_output_eq(
+        &query(&catalogue, "kinds.standalone"),
+        "does not read an input document, and can't be chained",
+    );
+}
@@ -0,0 +1,64 @@
+//! Integration tests for tagged collections and `--keep-tags`
+//!
+//! `keys`, `values`, `key-values` and `get-values` read through the tag of
+//! a collection, whether the document is read zero-copy (single action) or
+//! as a value (chains).
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+const INPUT: &str = "--- !config\nname: web\nports: !list [80, 443]\n";
+
+#[test]
+fn test_tagged_root_zero_copy() {
+    let (stdout, stderr, success) = run_shyaml(&["keys"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name\nports\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-values", "ports"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "80\n443\n");
+}
+
+#[test]
+fn test_tagged_root_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(&["keys", ";", "get-value", "1"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "ports");
+
+    let (stdout, stderr, success) = run_shyaml(&["set-value", "name", "api", ";", "values"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.starts_with("api\n"), "stdout: {}", stdout);
+}
+
+#[test]
+fn test_keep_tags_refuses_tagged_collections() {
+    for args in [
+        &["--keep-tags", "keys"][..],
+        &["--keep-tags", "keys", ";", "get-value", "0"][..],
+    ] {
+        let (_, stderr, success) = run_shyaml(args, INPUT);
+        assert!(!success, "args: {:?}", args);
+        assert!(
+            stderr.contains(
+                "keys does not support '!config' type. Please provide or select a sequence or struct."
+            ),
+            "stderr: {}",
+            stderr
+        );
+    }
+
+    let (_, stderr, success) = run_shyaml(&["--keep-tags", "get-values", "ports"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("get-values does not support '!list' type"),
+        "stderr: {}",
+        stderr
+    );
+
+    // Untagged collections are not concerned
+    let (stdout, stderr, success) = run_shyaml(&["--keep-tags", "keys"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a\n");
+}
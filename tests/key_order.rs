@@ -0,0 +1,127 @@
+//! Integration tests for the `--key-order` output option
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const INPUT: &str = indoc! {"
+    name: app
+    servers:
+    - port: 80
+      host: web
+    db: {user: admin, host: localhost}
+"};
+
+#[test]
+fn test_key_order_preserve_is_default() {
+    let (stdout, stderr, success) = run_shyaml(&["--key-order", "preserve", "keys"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name\nservers\ndb\n");
+}
+
+#[test]
+fn test_key_order_sorted() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["--key-order", "sorted", "set-value", "api", "v2"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            api: v2
+            db:
+              host: localhost
+              user: admin
+            name: app
+            servers:
+            - host: web
+              port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_key_order_sorted_applies_to_queries() {
+    let (stdout, stderr, success) = run_shyaml(&["--key-order", "sorted", "keys"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db\nname\nservers\n");
+}
+
+#[test]
+fn test_key_order_schema() {
+    let tmp = TempDir::new().unwrap();
+    let schema = temp_yaml_file(
+        &tmp,
+        "schema.yaml",
+        indoc! {"
+            properties:
+              servers:
+                items:
+                  properties:
+                    host: {}
+                    port: {}
+              name: {}
+        "},
+    );
+    let schema_path = schema.to_str().unwrap();
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--key-order",
+            "schema",
+            "--key-order-schema",
+            schema_path,
+            "del",
+            "db",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            servers:
+            - host: web
+              port: 80
+            name: app
+        "},
+    );
+}
+
+#[test]
+fn test_key_order_schema_requires_file() {
+    let (_, stderr, success) = run_shyaml(&["--key-order", "schema", "keys"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("requires a reference schema"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_key_order_schema_file_needs_schema_order() {
+    let (_, stderr, success) = run_shyaml(
+        &[
+            "--key-order",
+            "sorted",
+            "--key-order-schema",
+            "schema.yaml",
+            "keys",
+        ],
+        INPUT,
+    );
+    assert!(!success);
+    assert!(
+        stderr.contains("only used with key order 'schema'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_key_order_invalid() {
+    let (_, stderr, success) = run_shyaml(&["--key-order", "random", "keys"], INPUT);
+    assert!(!success);
+    assert!(stderr.contains("invalid value"), "stderr: {}", stderr);
+}
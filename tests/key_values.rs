@@ -0,0 +1,59 @@
+//! Integration tests for `key-values` separators and quoting
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+const INPUT: &str = "name: web\nnote: |\n  line 1\n  line 2\nquote: say \"hi\"\n";
+
+#[test]
+fn test_key_values_default_separator() {
+    let (stdout, stderr, success) = run_shyaml(&["key-values"], "a: 1\nb: 2\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a\n1\nb\n2\n");
+}
+
+#[test]
+fn test_key_values_kv_separator() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["key-values", "--kv-separator", "="], "a: 1\nb: 2\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a=1\nb=2\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["key-values-0", "--kv-separator", "="], "a: 1\nb: 2\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a=1\0b=2\0");
+}
+
+#[test]
+fn test_key_values_quote() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["key-values", "--quote", "--kv-separator", " "], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        "\"name\" \"web\"\n\"note\" \"line 1\\nline 2\\n\"\n\"quote\" \"say \\\"hi\\\"\"\n",
+    );
+}
+
+#[test]
+fn test_key_values_quote_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "doc",
+            ";",
+            "key-values",
+            "--quote",
+            "--kv-separator",
+            ":",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        "\"name\":\"web\"\n\"note\":\"line 1\\nline 2\\n\"\n\"quote\":\"say \\\"hi\\\"\"\n",
+    );
+}
@@ -0,0 +1,84 @@
+//! Integration tests for `key-values --recursive`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    app:
+      db:
+        host: localhost
+        port: 5432
+      name: web
+      empty: {}
+      nodes:
+      - a
+"};
+
+#[test]
+fn test_key_values_recursive() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["key-values", "--recursive", "--kv-separator", "=", "app.db"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "host=localhost\nport=5432\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "key-values",
+            "--recursive",
+            "--quote",
+            "--kv-separator",
+            " ",
+            "app",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert!(
+        stdout.starts_with("\"db.host\" \"localhost\"\n\"db.port\" \"5432\"\n\"name\" \"web\"\n"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_key_values_0_recursive() {
+    let (stdout, stderr, success) = run_shyaml(&["key-values-0", "--recursive", "app.db"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "host\0localhost\0port\x005432\0");
+}
+
+#[test]
+fn test_key_values_recursive_in_chain() {
+    // Keys with dots are escaped, in value mode too
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "app.db.a\\.b",
+            "1",
+            ";",
+            "key-values",
+            "--recursive",
+            "--kv-separator",
+            "=",
+            "app.db",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "host=localhost\nport=5432\na\\.b=1\n");
+}
+
+#[test]
+fn test_key_values_recursive_on_sequence() {
+    let (_, stderr, success) = run_shyaml(&["key-values", "--recursive", "app.nodes"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("key-values does not support 'sequence' type"),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -0,0 +1,47 @@
+//! Integration tests for `keys` and `values` on sequences
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    ports:
+    - 8080
+    - 443
+    name: web
+"};
+
+#[test]
+fn test_keys_of_sequence_are_indices() {
+    let (stdout, stderr, success) = run_shyaml(&["keys", "ports"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0\n1\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["keys-0", "ports"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0\x001\0");
+}
+
+#[test]
+fn test_values_of_sequence() {
+    let (stdout, stderr, success) = run_shyaml(&["values", "ports"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "8080\n443\n");
+
+    // In a chain, on the value handed over by the previous action
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "ports", ";", "keys"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0\n1\n");
+}
+
+#[test]
+fn test_keys_of_scalar_error() {
+    let (_, stderr, success) = run_shyaml(&["keys", "name"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("Please provide or select a sequence or struct."),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -0,0 +1,68 @@
+//! Integration tests for the global `-L` option
+
+mod common;
+
+use common::{assert_output_eq, binary_path, run_shyaml};
+use std::io::{Read, Write};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// Write a first document to shyaml, keeping stdin open, and return what
+/// it outputs before the input ends.
+fn first_output(args: &[&str]) -> String {
+    let mut child = Command::new(binary_path())
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("Failed to spawn shyaml");
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(b"a: 1\nb: 2\n---\n").unwrap();
+    stdin.flush().unwrap();
+
+    let mut stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let mut buf = [0; 64];
+        while let Ok(n @ 1..) = stdout.read(&mut buf) {
+            let chunk = String::from_utf8_lossy(&buf[..n]).to_string();
+            if tx.send(chunk).is_err() {
+                break;
+            }
+        }
+    });
+    // Lines of the first document may come in several writes
+    let mut output = rx.recv_timeout(Duration::from_secs(10)).unwrap_or_default();
+    while let Ok(more) = rx.recv_timeout(Duration::from_millis(500)) {
+        output.push_str(&more);
+    }
+    drop(stdin);
+    child.kill().ok();
+    child.wait().ok();
+    output
+}
+
+#[test]
+fn test_line_buffer_any_action() {
+    assert_output_eq(&first_output(&["keys", "-L"]), "a\nb\n");
+    assert_output_eq(&first_output(&["-L", "get-type", "a"]), "int\n");
+}
+
+#[test]
+fn test_line_buffer_in_chain() {
+    assert_output_eq(
+        &first_output(&["set-value", "c", "3", ";", "get-value", "c", "-L"]),
+        "3",
+    );
+}
+
+#[test]
+fn test_line_buffer_same_output() {
+    let input = "a: 1\n---\na: 2\n";
+    let (buffered, _, _) = run_shyaml(&["get-value", "a"], input);
+    let (stdout, stderr, success) = run_shyaml(&["--line-buffer", "get-value", "a"], input);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, &buffered);
+}
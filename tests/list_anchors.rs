@@ -0,0 +1,79 @@
+//! Integration tests for the `list-anchors` and `get-anchor` actions
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    defaults: &defaults
+      replicas: 2
+      image: web
+    services:
+    - name: api
+      settings: *defaults
+    - &worker
+      name: worker
+      port: &port 8080
+    proxy: {port: *port}
+"};
+
+#[test]
+fn test_list_anchors() {
+    let (stdout, stderr, success) = run_shyaml(&["list-anchors"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            defaults: defaults
+            worker: services.1
+            port: services.1.port
+        "},
+    );
+}
+
+#[test]
+fn test_list_anchors_none() {
+    let (stdout, stderr, success) = run_shyaml(&["list-anchors"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "{}\n");
+}
+
+#[test]
+fn test_get_anchor() {
+    let (stdout, stderr, success) = run_shyaml(&["get-anchor", "defaults"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "replicas: 2\nimage: web");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-anchor", "port"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "8080");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-anchor", "worker", ";", "get-value", "name"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "worker");
+}
+
+#[test]
+fn test_get_anchor_root_and_redefined() {
+    let input = "--- &root\na: &x 1\nb: &x 2\n";
+    let (stdout, stderr, success) = run_shyaml(&["get-anchor", "x"], input);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "2");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-anchor", "root", ";", "keys"], input);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a\nb\n");
+}
+
+#[test]
+fn test_get_anchor_not_found() {
+    let (_, stderr, success) = run_shyaml(&["get-anchor", "missing"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("anchor 'missing' not found."),
+        "stderr: {}",
+        stderr
+    );
+}
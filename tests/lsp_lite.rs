@@ -0,0 +1,93 @@
+//! Integration tests for the `lsp-lite` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+#[test]
+fn test_lsp_lite_line_requests() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["lsp-lite", "--stdio"],
+        indoc! {r#"
+            {"jsonrpc": "2.0", "id": 1, "method": "type", "params": {"text": "db:\n  port: 5432\n", "path": "db.port"}}
+            {"jsonrpc": "2.0", "method": "initialized"}
+            {"jsonrpc": "2.0", "id": 2, "method": "listPaths", "params": {"text": "db:\n  port: 5432\n"}}
+            {"jsonrpc": "2.0", "id": 3, "method": "getValue", "params": {"text": "db:\n  port: 5432\n", "path": "db"}}
+        "#},
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {r#"
+            {"jsonrpc": "2.0", "id": 1, "result": "int"}
+            {"jsonrpc": "2.0", "id": 2, "result": ["db", "db.port"]}
+            {"jsonrpc": "2.0", "id": 3, "result": {"port": 5432}}
+        "#},
+    );
+}
+
+#[test]
+fn test_lsp_lite_apply_edit_keeps_comments() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["lsp-lite", "--stdio"],
+        r##"{"id": 1, "method": "applyEdit", "params": {"text": "# config\nname: web # app\n", "path": "image.tag", "value": "1.26"}}"##,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        "{\"jsonrpc\": \"2.0\", \"id\": 1, \"result\": \"# config\\nname: web # app\\nimage:\\n  tag: 1.26\\n\"}\n",
+    );
+}
+
+#[test]
+fn test_lsp_lite_framed_requests() {
+    let body = r#"{"jsonrpc": "2.0", "id": 7, "method": "type", "params": {"text": "- a"}}"#;
+    let input = format!(
+        "Content-Length: {}\r\n\r\n{}Content-Length: 36\r\n\r\n{{\"jsonrpc\": \"2.0\", \"method\": \"exit\"}}",
+        body.len(),
+        body
+    );
+    let (stdout, stderr, success) = run_shyaml(&["lsp-lite", "--stdio"], &input);
+    assert!(success, "stderr: {}", stderr);
+    let reply = r#"{"jsonrpc": "2.0", "id": 7, "result": "sequence"}"#;
+    assert_output_eq(
+        &stdout,
+        &format!("Content-Length: {}\r\n\r\n{}", reply.len(), reply),
+    );
+}
+
+#[test]
+fn test_lsp_lite_errors() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["lsp-lite", "--stdio"],
+        indoc! {r#"
+            {"id": 1, "method": "getValue", "params": {"text": "a: 1", "path": "a.b"}}
+            {"id": 2, "method": "hover", "params": {}}
+            {"id": 3, "method": "applyEdit", "params": {"text": "a: 1"}}
+            not json: [
+        "#},
+    );
+    assert!(success, "stderr: {}", stderr);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4, "stdout: {}", stdout);
+    assert!(lines[0].contains("\"code\": -32000"), "{}", lines[0]);
+    assert!(
+        lines[0].contains("\"category\": \"path-not-found\", \"path\": \"a.b\""),
+        "{}",
+        lines[0]
+    );
+    assert!(lines[1].contains("\"code\": -32601"), "{}", lines[1]);
+    assert!(lines[2].contains("missing 'path'"), "{}", lines[2]);
+    assert!(lines[3].contains("\"code\": -32700"), "{}", lines[3]);
+}
+
+#[test]
+fn test_lsp_lite_requires_stdio() {
+    let (_, _, success) = run_shyaml(&["lsp-lite"], "");
+    assert!(!success);
+
+    let (_, stderr, success) = run_shyaml(&["lsp-lite", "--stdio", ";", "keys"], "");
+    assert!(!success);
+    assert!(stderr.contains("cannot be chained"), "stderr: {}", stderr);
+}
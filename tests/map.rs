@@ -0,0 +1,112 @@
+//! Integration tests for the `map` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    replicas:  # by service
+      web: 2
+      api: 3
+    names:
+    - web  # frontend
+    - api
+    ratios:
+    - 0.5
+    - 1.5
+"};
+
+#[test]
+fn test_map_mapping_values_keeps_comments() {
+    let (stdout, stderr, success) = run_shyaml(&["map", "replicas", ". * 2"], INPUT);
+    assert!(success, "map failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            replicas: # by service
+              web: 4
+              api: 6
+            names:
+            - web # frontend
+            - api
+            ratios:
+            - 0.5
+            - 1.5
+        "},
+    );
+}
+
+#[test]
+fn test_map_sequence_strings() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "map",
+            "names",
+            "upper(.) + '-' + length(.)",
+            ";",
+            "get-value",
+            "names",
+        ],
+        INPUT,
+    );
+    assert!(success, "map failed: {}", stderr);
+    assert_output_eq(&stdout, "- WEB-3\n- API-3");
+}
+
+#[test]
+fn test_map_floats_stay_floats() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["map", "ratios", ". * 2", ";", "get-type", "ratios.0"],
+        INPUT,
+    );
+    assert!(success, "map failed: {}", stderr);
+    assert_output_eq(&stdout, "float\n");
+}
+
+#[test]
+fn test_map_in_value_mode() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["map", "replicas", "max(. - 2, 1)", ";", "keys", "replicas"],
+        INPUT,
+    );
+    assert!(success, "map failed: {}", stderr);
+    assert_output_eq(&stdout, "web\napi\n");
+}
+
+#[test]
+fn test_map_negative_expression() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["map", "replicas", "-.", ";", "get-value", "replicas.api"],
+        INPUT,
+    );
+    assert!(success, "map failed: {}", stderr);
+    assert_output_eq(&stdout, "-3");
+}
+
+#[test]
+fn test_map_errors() {
+    let (_, stderr, success) = run_shyaml(&["map", "names", ". * 2"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("map '. * 2' failed at 'names.0': '*' does not support 'str' type"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["map", "replicas.web", "."], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("map does not support 'int' type"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["map", "names", "shout(.)"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid expression 'shout(.)': unknown function 'shout'"),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -0,0 +1,98 @@
+//! Integration tests for the `merge` action
+//!
+//! The `merge` action merges files left-to-right with the `apply` engine,
+//! the first file being the base document: stdin isn't read.
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+#[test]
+fn test_merge_files_in_order() {
+    let tmp = TempDir::new().unwrap();
+    let a = temp_yaml_file(&tmp, "a.yaml", "db:\n  host: localhost\n  port: 5432\n");
+    let b = temp_yaml_file(&tmp, "b.yaml", "db:\n  port: 3306\ntags: [x]\n");
+    let c = temp_yaml_file(&tmp, "c.yaml", "db:\n  port: 6543\ntags: [y]\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "merge",
+            a.to_str().unwrap(),
+            b.to_str().unwrap(),
+            c.to_str().unwrap(),
+        ],
+        "",
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            db:
+              host: localhost
+              port: 6543
+            tags:
+            - x
+            - y
+        "},
+    );
+}
+
+#[test]
+fn test_merge_policy_and_chain() {
+    let tmp = TempDir::new().unwrap();
+    let a = temp_yaml_file(&tmp, "a.yaml", "tags: [x]\nname: a\n");
+    let b = temp_yaml_file(&tmp, "b.yaml", "tags: [y]\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "merge",
+            "-m",
+            "tags=replace",
+            a.to_str().unwrap(),
+            b.to_str().unwrap(),
+            ";",
+            "get-value",
+            "tags",
+        ],
+        "",
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "- y");
+}
+
+#[test]
+fn test_merge_errors() {
+    let tmp = TempDir::new().unwrap();
+    let a = temp_yaml_file(&tmp, "a.yaml", "a: 1\n");
+    let a = a.to_str().unwrap();
+
+    // A single file has nothing to merge
+    let (_, _, success) = run_shyaml(&["merge", a], "");
+    assert!(!success);
+
+    let (_, stderr, success) = run_shyaml(&["--file", a, "merge", a, a], "");
+    assert!(!success);
+    assert!(
+        stderr.contains("merge reads its own files, it can't be used with --file"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "a", ";", "merge", a, a], "a: 1\n");
+    assert!(!success);
+    assert!(
+        stderr.contains("merge can only be the first action of a chain"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, code) = run_shyaml_code(&["merge", "/nonexistent.yaml", a], "");
+    assert_eq!(code, Some(5));
+    assert!(
+        stderr.contains("Failed to read '/nonexistent.yaml'"),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -587,3 +587,109 @@ fn test_cli_policy_bypasses_type_validation() {
         "},
     );
 }
+
+// =============================================================================
+// --strict-tags
+// =============================================================================
+
+#[test]
+fn test_strict_tags_rejects_mistyped_directive() {
+    let tmp = TempDir::new().unwrap();
+
+    let overlay = temp_yaml_file(
+        &tmp,
+        "overlay.yaml",
+        indoc! {"
+            items: !Merge:replace
+              - x
+        "},
+    );
+    let overlay = overlay.to_str().unwrap();
+
+    // Taken as a plain tag without the option
+    let (stdout, stderr, success) = run_shyaml(&["apply", overlay], "items: [a]\n");
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "items:\n- a\n- x\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["apply", "--strict-tags", overlay], "items: [a]\n");
+    assert!(!success);
+    assert_eq!(stdout, "");
+    assert!(
+        stderr.contains("at 'items': suspicious tag '!Merge:replace'"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_strict_tags_checks_added_subtrees() {
+    let tmp = TempDir::new().unwrap();
+
+    // `new` isn't in the base document, so its content is never merged
+    let overlay = temp_yaml_file(
+        &tmp,
+        "overlay.yaml",
+        indoc! {"
+            new:
+              paths: !merge:repalce [/opt]
+        "},
+    );
+    let overlay = overlay.to_str().unwrap();
+
+    let (_, stderr, success) = run_shyaml(&["apply", "--strict-tags", overlay], "a: 1\n");
+    assert!(!success);
+    assert!(
+        stderr.contains("at 'new.paths': unknown merge operation 'repalce'"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn test_strict_tags_accepts_valid_tags() {
+    let tmp = TempDir::new().unwrap();
+
+    let overlay = temp_yaml_file(
+        &tmp,
+        "overlay.yaml",
+        indoc! {"
+            items: !merge:prepend
+              - x
+            secret: !vault;merge:replace key
+        "},
+    );
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["apply", "--strict-tags", overlay.to_str().unwrap()],
+        "items: [a]\nsecret: old\n",
+    );
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(&stdout, "items:\n- x\n- a\nsecret: !vault key\n");
+}
+
+// =============================================================================
+// Tag namespace listing
+// =============================================================================
+
+#[test]
+fn test_tags_lists_merge_directives() {
+    let (stdout, stderr, success) = run_shyaml(&["tags"], "");
+    assert!(success, "Command failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            merge: How apply merges an overlay value into the base document
+              !merge:replace  Replace the base value entirely
+              !merge:append   Append to the base sequence (default for sequences)
+              !merge:prepend  Prepend to the base sequence
+        "},
+    );
+}
+
+#[test]
+fn test_tags_cannot_be_chained() {
+    let (_, stderr, success) = run_shyaml(&["tags", ";", "get-value"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("cannot be chained"), "{}", stderr);
+}
@@ -0,0 +1,98 @@
+//! Integration tests for `--null-output` and `get-value --fail-on-null`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code};
+
+const INPUT: &str = "a: ~\nb: ''\nc: 1\n";
+
+#[test]
+fn test_null_output() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "a"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "");
+
+    let (stdout, stderr, success) = run_shyaml(&["--null-output", "null", "get-value", "a"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "null");
+
+    // Empty strings are left as they are
+    let (stdout, stderr, success) = run_shyaml(&["--null-output", "null", "get-value", "b"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "");
+
+    // YAML output is not affected
+    let (stdout, stderr, success) =
+        run_shyaml(&["--null-output", "none", "get-value", "-y", "a"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "~\n");
+}
+
+#[test]
+fn test_null_output_items_and_chains() {
+    let (stdout, stderr, success) = run_shyaml(&["--null-output", "~", "values"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "~\n\n1\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--null-output",
+            "~",
+            "set-value",
+            "d",
+            "x",
+            ";",
+            "get-value",
+            "a",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "~");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["--null-output", "~", "save", "doc", ";", "get-value", "a"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "~");
+}
+
+#[test]
+fn test_fail_on_null() {
+    let (stdout, stderr, code) = run_shyaml_code(&["get-value", "--fail-on-null", "a"], INPUT);
+    assert_eq!(code, Some(6));
+    assert!(stdout.is_empty(), "stdout: {}", stdout);
+    assert!(
+        stderr.contains("invalid value at 'a', value is null."),
+        "stderr: {}",
+        stderr
+    );
+
+    // Empty strings and missing paths keep their own outcome
+    let (_, _, code) = run_shyaml_code(&["get-value", "--fail-on-null", "b"], INPUT);
+    assert_eq!(code, Some(0));
+    let (_, _, code) = run_shyaml_code(&["get-value", "--fail-on-null", "z"], INPUT);
+    assert_eq!(code, Some(2));
+
+    // In a chain too
+    let (_, _, code) = run_shyaml_code(
+        &["save", "doc", ";", "get-value", "--fail-on-null", "a"],
+        INPUT,
+    );
+    assert_eq!(code, Some(6));
+}
+
+#[test]
+fn test_fail_on_null_json_error() {
+    let (_, stderr, code) = run_shyaml_code(
+        &["--errors", "json", "get-value", "--fail-on-null", "a"],
+        INPUT,
+    );
+    assert_eq!(code, Some(6));
+    assert!(
+        stderr.contains("\"code\": \"null-value\", \"path\": \"a\""),
+        "stderr: {}",
+        stderr
+    );
+}
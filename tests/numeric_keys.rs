@@ -0,0 +1,310 @@
+//! Integration tests for paths designating keys that aren't strings
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    ports:
+      8080: web
+      \"9090\": metrics
+      true: enabled
+"};
+
+#[test]
+fn test_get_value_integer_and_bool_keys() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "ports.8080"], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "web");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "ports.true"], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "enabled");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "ports.9090"], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "metrics");
+}
+
+#[test]
+fn test_integer_keys_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "other", "1", ";", "get-value", "ports.8080"],
+        INPUT,
+    );
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "web");
+}
+
+#[test]
+fn test_integer_keys_in_mutations() {
+    // Mutations on values walk paths as queries do
+    let (stdout, stderr, success) = run_shyaml(&["del", "ports.8080", ";", "keys", "ports"], INPUT);
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "9090\ntrue\n");
+
+    // The boolean key is replaced, no string key "true" is added
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "ports.true", "disabled", ";", "keys", "ports"],
+        INPUT,
+    );
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "8080\n9090\ntrue\n");
+}
+
+#[test]
+fn test_string_keys_only() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--string-keys-only", "get-value", "ports.8080", "none"],
+        INPUT,
+    );
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "none");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["--string-keys-only", "get-value", "ports.9090"], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "metrics");
+
+    let (_, stderr, success) =
+        run_shyaml(&["--string-keys-only", "get-value", "ports.true"], INPUT);
+    assert!(!success);
+    assert!(stderr.contains("missing key 'true'"), "stderr: {}", stderr);
+
+    // The flag applies to every command of a chain
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--string-keys-only",
+            "set-value",
+            "a",
+            "1",
+            ";",
+            "get-value",
+            "ports.8080",
+            "none",
+        ],
+        INPUT,
+    );
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "none");
+}
+
+#[test]
+fn test_string_keys_only_in_mutations() {
+    // On the document, then from a chain working on an owned value
+    for chain in [&[][..], &[";", "get-value", "ports"][..]] {
+        let args = [
+            &["--string-keys-only", "set-value", "ports.8080", "x"][..],
+            chain,
+        ]
+        .concat();
+        let (_, stderr, code) = run_shyaml_code(&args, INPUT);
+        assert_eq!(code, Some(2), "stderr: {}", stderr);
+        assert!(
+            stderr.contains("key '8080' would duplicate the existing key 8080"),
+            "stderr: {}",
+            stderr
+        );
+
+        let args = [&["--string-keys-only", "del", "ports.8080"][..], chain].concat();
+        let (_, stderr, code) = run_shyaml_code(&args, INPUT);
+        assert_eq!(code, Some(2), "stderr: {}", stderr);
+        assert!(stderr.contains("missing key '8080'"), "stderr: {}", stderr);
+    }
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["--string-keys-only", "set-value", "ports.8081", "api"],
+        INPUT,
+    );
+    assert!(success, "set-value failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        "ports:\n  8080: web\n  \"9090\": metrics\n  true: enabled\n  '8081': api\n",
+    );
+}
+
+#[test]
+fn test_typed_keys() {
+    let input = indoc! {"
+        flags:
+          ~: unset
+          True: on
+          \"true\": text
+          7: seven
+    "};
+    for (path, expected) in [
+        ("flags.:(~)", "unset"),
+        ("flags.:(null)", "unset"),
+        ("flags.:(true)", "on"),
+        ("flags.:(7)", "seven"),
+        ("flags.true", "text"),
+    ] {
+        let (stdout, stderr, success) = run_shyaml(&["get-value", path], input);
+        assert!(success, "get-value {} failed: {}", path, stderr);
+        assert_output_eq(&stdout, expected);
+    }
+
+    // Explicit types don't depend on --string-keys-only
+    let (stdout, stderr, success) =
+        run_shyaml(&["--string-keys-only", "get-value", "flags.:(7)"], input);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "seven");
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "flags.:(false)"], input);
+    assert!(!success);
+    assert!(
+        stderr.contains("missing key ':(false)'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_typed_keys_in_mutations() {
+    let input = "flags:\n  ~: unset\n  true: enabled\n  8080: web\n";
+    for (args, expected) in [
+        (
+            &["set-value", "flags.:(true)", "disabled"][..],
+            "flags:\n  ~: unset\n  true: disabled\n  8080: web\n",
+        ),
+        (
+            &["del", "flags.:(~)"][..],
+            "flags:\n  true: enabled\n  8080: web\n",
+        ),
+        (
+            &["update", "flags.:(8080)", "--append-str", "Z"][..],
+            "flags:\n  ~: unset\n  true: enabled\n  8080: webZ\n",
+        ),
+        (
+            &["set-value", "flags.:(9)", "nine"][..],
+            "flags:\n  ~: unset\n  true: enabled\n  8080: web\n  9: nine\n",
+        ),
+    ] {
+        let (stdout, stderr, success) = run_shyaml(args, input);
+        assert!(success, "{:?} failed: {}", args, stderr);
+        assert_output_eq(&stdout, expected);
+    }
+
+    // Same keys from a chain, working on an owned value
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "del",
+            "flags.:(~)",
+            ";",
+            "set-value",
+            "flags.:(9)",
+            "nine",
+            ";",
+            "keys",
+            "flags",
+        ],
+        input,
+    );
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "true\n8080\n9\n");
+}
+
+const COMPLEX_KEYS: &str = "? [eu, west]\n: 3\n? {zone: b}\n: 1\nplain: 0\n";
+
+#[test]
+fn test_complex_keys_listed_in_canonical_form() {
+    let (stdout, stderr, success) = run_shyaml(&["keys"], COMPLEX_KEYS);
+    assert!(success, "keys failed: {}", stderr);
+    assert_output_eq(&stdout, "[eu, west]\n{zone: b}\nplain\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["keys", "--yaml"], COMPLEX_KEYS);
+    assert!(success, "keys failed: {}", stderr);
+    assert!(
+        stdout.starts_with("[eu, west]\n{zone: b}\n"),
+        "stdout: {}",
+        stdout
+    );
+
+    // Same output from a chain, working on an owned value
+    let (stdout, stderr, success) = run_shyaml(&["get-value", ";", "keys"], COMPLEX_KEYS);
+    assert!(success, "keys failed: {}", stderr);
+    assert_output_eq(&stdout, "[eu, west]\n{zone: b}\nplain\n");
+}
+
+#[test]
+fn test_complex_keys_in_paths() {
+    for path in [":([eu, west])", ":([eu,west])"] {
+        let (stdout, stderr, success) = run_shyaml(&["get-value", path], COMPLEX_KEYS);
+        assert!(success, "get-value {} failed: {}", path, stderr);
+        assert_output_eq(&stdout, "3");
+    }
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", ";", "get-value", ":({zone: b})"],
+        COMPLEX_KEYS,
+    );
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "1");
+}
+
+#[test]
+fn test_complex_keys_in_mutations() {
+    let listed = "[eu, west]\n{zone: b}\nplain\n";
+    for (args, keys, path, expected) in [
+        (
+            &["set-value", ":([eu, west])", "x"][..],
+            listed,
+            ":([eu, west])",
+            "x",
+        ),
+        (
+            &["update", ":([eu, west])", "--add", "2"][..],
+            listed,
+            ":([eu, west])",
+            "5",
+        ),
+        (
+            &["set-value", ":([us, east])", "4"][..],
+            "[eu, west]\n{zone: b}\nplain\n[us, east]\n",
+            ":([us,east])",
+            "4",
+        ),
+        (
+            &["set-value", ":({zone: c}).n", "2"][..],
+            "[eu, west]\n{zone: b}\nplain\n{zone: c}\n",
+            ":({zone: c}).n",
+            "2",
+        ),
+    ] {
+        let (stdout, stderr, success) = run_shyaml(args, COMPLEX_KEYS);
+        assert!(success, "{:?} failed: {}", args, stderr);
+        let (output, stderr, success) = run_shyaml(&["keys"], &stdout);
+        assert!(success, "keys failed: {}", stderr);
+        assert_output_eq(&output, keys);
+        let (output, stderr, success) = run_shyaml(&["get-value", path], &stdout);
+        assert!(success, "get-value failed: {}", stderr);
+        assert_output_eq(&output, expected);
+    }
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["del", ":([eu, west])", ";", "keys"], COMPLEX_KEYS);
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "{zone: b}\nplain\n");
+    let (stdout, stderr, success) = run_shyaml(&["del", ":([eu, west])"], COMPLEX_KEYS);
+    assert!(success, "del failed: {}", stderr);
+    let (stdout, stderr, success) = run_shyaml(&["keys"], &stdout);
+    assert!(success, "keys failed: {}", stderr);
+    assert_output_eq(&stdout, "{zone: b}\nplain\n");
+}
+
+#[test]
+fn test_complex_keys_in_flatten_and_search() {
+    let (stdout, stderr, success) = run_shyaml(&["flatten"], COMPLEX_KEYS);
+    assert!(success, "flatten failed: {}", stderr);
+    assert_output_eq(&stdout, ":([eu, west])=3\n:({zone: b})=1\nplain=0\n");
+
+    // Paths found by search designate the values again
+    for value in ["3", "1"] {
+        let (path, stderr, success) = run_shyaml(&["search", value], COMPLEX_KEYS);
+        assert!(success, "search failed: {}", stderr);
+        let (stdout, stderr, success) = run_shyaml(&["get-value", path.trim_end()], COMPLEX_KEYS);
+        assert!(success, "get-value {} failed: {}", path, stderr);
+        assert_output_eq(&stdout, value);
+    }
+}
@@ -0,0 +1,97 @@
+//! Integration tests for the global `--output` and `--backup` options
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_output_replaces_file() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+    let path = file.to_str().unwrap();
+
+    let input = fs::read_to_string(&file).unwrap();
+    let (stdout, stderr, success) = run_shyaml(&["-o", path, "set-value", "b", "2"], &input);
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.is_empty());
+    assert_output_eq(&fs::read_to_string(&file).unwrap(), "a: 1\nb: 2\n");
+}
+
+#[test]
+fn test_output_creates_file() {
+    let tmp = TempDir::new().unwrap();
+    let file = tmp.path().join("out.txt");
+
+    let (_, stderr, success) = run_shyaml(
+        &["--output", file.to_str().unwrap(), "get-value", "a"],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&fs::read_to_string(&file).unwrap(), "1");
+}
+
+#[test]
+fn test_output_with_backup() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+
+    let (_, stderr, success) = run_shyaml(
+        &[
+            "-o",
+            file.to_str().unwrap(),
+            "--backup",
+            ".orig",
+            "set-value",
+            "a",
+            "2",
+        ],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&fs::read_to_string(&file).unwrap(), "a: 2\n");
+    assert_output_eq(
+        &fs::read_to_string(tmp.path().join("app.yaml.orig")).unwrap(),
+        "a: 1\n",
+    );
+}
+
+#[test]
+fn test_output_untouched_on_error() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "-o",
+            file.to_str().unwrap(),
+            "--backup",
+            ".orig",
+            "set-value",
+            "b",
+            "2",
+            ";",
+            "get-value",
+            "missing",
+        ],
+        "a: 1\n",
+    );
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("missing key 'missing'"),
+        "stderr: {}",
+        stderr
+    );
+    assert_output_eq(&fs::read_to_string(&file).unwrap(), "a: 1\n");
+    // Only the original file is left, no backup nor temporary file
+    assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_backup_requires_output() {
+    let (_, stderr, success) = run_shyaml(&["--backup", ".orig", "get-value", "a"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("--output <FILE>"), "stderr: {}", stderr);
+}
@@ -0,0 +1,107 @@
+//! Integration tests for filter elements in paths
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    users:
+    - name: bob
+      email: bob@example.org
+    - name: alice  # admin
+      email: alice@example.org
+    ports:
+    - 80
+    - 8080
+"};
+
+#[test]
+fn test_get_value_with_field_filter() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "users.[name=alice].email"], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "alice@example.org");
+}
+
+#[test]
+fn test_get_value_with_expression_filter() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "ports.[?(@ > 1024)]"], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "8080");
+}
+
+#[test]
+fn test_set_value_with_filter_keeps_comments() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "users.[?(@.name == 'alice')].email",
+            "a@example.org",
+        ],
+        INPUT,
+    );
+    assert!(success, "set-value failed: {}", stderr);
+    assert!(
+        stdout.contains("- name: alice # admin\n  email: a@example.org\n"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_del_with_filter_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "get-value",
+            "users",
+            ";",
+            "del",
+            "[name=bob]",
+            ";",
+            "get-value",
+            "0.name",
+        ],
+        INPUT,
+    );
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "alice");
+}
+
+#[test]
+fn test_filter_without_match_uses_default() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "users.[name=carol].email", "none"], INPUT);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "none");
+}
+
+#[test]
+fn test_filter_without_match_error() {
+    let (_, stderr, success) = run_shyaml(&["get-value", "users.[name=carol].email"], INPUT);
+    assert!(!success, "get-value should fail without a matching element");
+    assert!(stderr.contains("no element matching '[name=carol]' in sequence."));
+}
+
+#[test]
+fn test_keys_looking_like_filters() {
+    let input = "'[a=b]': 1\n'[x': {y: 2}\n";
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "[a=b]"], input);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "1");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", r"\[a=b]"], input);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "1");
+
+    // An unclosed `[` doesn't keep the next `.` from splitting the path
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "[x.y"], input);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "2");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", r"\[c=d]", "3", ";", "get-value", "[c=d]"],
+        input,
+    );
+    assert!(success, "set-value failed: {}", stderr);
+    assert_output_eq(&stdout, "3");
+}
@@ -0,0 +1,71 @@
+//! Integration tests for the `parent-path`, `join-path` and `relative-path` actions
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+#[test]
+fn test_parent_path() {
+    let (stdout, stderr, success) = run_shyaml(&["parent-path", r"a.b\.c.d"], "");
+    assert!(success, "parent-path failed: {}", stderr);
+    assert_output_eq(&stdout, "a.b\\.c\n");
+}
+
+#[test]
+fn test_parent_path_keeps_escaped_last_element_whole() {
+    let (stdout, stderr, success) = run_shyaml(&["parent-path", r"a.b\.c"], "");
+    assert!(success, "parent-path failed: {}", stderr);
+    assert_output_eq(&stdout, "a\n");
+}
+
+#[test]
+fn test_parent_path_top_level_error() {
+    let (_, stderr, success) = run_shyaml(&["parent-path", "a"], "");
+    assert!(!success, "parent-path should fail on a top-level path");
+    assert!(stderr.contains("top-level path has no parent path"));
+}
+
+#[test]
+fn test_join_path_escapes_segments() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["join-path", "hosts", "www.example.com", r"C:\tmp", "0"],
+        "",
+    );
+    assert!(success, "join-path failed: {}", stderr);
+    assert_output_eq(&stdout, "hosts.www\\.example\\.com.C:\\\\tmp.0\n");
+}
+
+#[test]
+fn test_join_path_result_is_usable_as_path() {
+    let (path, stderr, success) = run_shyaml(&["join-path", "hosts", "www.example.com"], "");
+    assert!(success, "join-path failed: {}", stderr);
+
+    let input = "hosts:\n  www.example.com: 10.0.0.1\n";
+    let (stdout, stderr, success) = run_shyaml(&["get-value", path.trim_end()], input);
+    assert!(success, "get-value failed: {}", stderr);
+    assert_output_eq(&stdout, "10.0.0.1");
+}
+
+#[test]
+fn test_relative_path() {
+    let (stdout, stderr, success) = run_shyaml(&["relative-path", r"a\.b", r"a\.b.c\.d.e"], "");
+    assert!(success, "relative-path failed: {}", stderr);
+    assert_output_eq(&stdout, "c\\.d.e\n");
+}
+
+#[test]
+fn test_relative_path_not_below_base() {
+    let (_, stderr, success) = run_shyaml(&["relative-path", r"a\.b", "a.b.c"], "");
+    assert!(
+        !success,
+        "relative-path should fail when FULL is not below BASE"
+    );
+    assert!(stderr.contains("not a sub-path of"));
+}
+
+#[test]
+fn test_path_helper_cannot_be_chained() {
+    let (_, stderr, success) = run_shyaml(&["get-value", "a", ";", "parent-path", "a.b"], "a: 1\n");
+    assert!(!success, "path helpers should not be chainable");
+    assert!(stderr.contains("cannot be chained"));
+}
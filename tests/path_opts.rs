@@ -0,0 +1,140 @@
+//! Integration tests for the `--path` and `--default` named arguments
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+#[test]
+fn test_path_looking_like_an_option() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--path", "-x"], "-x: 3\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "3");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--path=-1"], "[a, b]\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "b");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["update", "--path", "-x", "--add", "1"], "-x: 3\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "-x: 4\n");
+}
+
+#[test]
+fn test_named_and_positional_arguments() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--default", "0", "b"], "a: 3\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "get-value",
+            "--default",
+            "0",
+            ";",
+            "get-value",
+            "--path",
+            "a",
+        ],
+        "a: 3\n",
+    );
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("<PATH|--path <PATH>>"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "ensure",
+            "--path",
+            "b",
+            "--default",
+            "4",
+            ";",
+            "get-value",
+            "--path",
+            "b",
+        ],
+        "a: 3\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "4");
+}
+
+#[test]
+fn test_positional_arguments_after_named_path() {
+    // The positional arguments fill the places after the path
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--path", "b", "0"], "a: 3\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["set-value", "--path", "-x.y", "5"], "-x:\n  y: 2\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "-x:\n  y: 5\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["append", "--path", "-l", "c"], "-l:\n- b\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "-l:\n- b\n- c\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["insert", "--path", "-l", "0", "a"], "-l:\n- b\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "-l:\n- a\n- b\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["rename-key", "--path", "-x", "x"], "-x: 1\ny: 2\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "x: 1\ny: 2\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["move", "--path", "-x", "x"], "-x: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "x: 1\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["copy", "--path", "-x", "x"], "-x: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "-x: 1\nx: 1\n");
+
+    // A path can't be given both ways
+    let (stdout, stderr, success) = run_shyaml(&["keys", "--path", "a", "b"], "a: {}\n");
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("unexpected argument 'b' found"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_repeated_path() {
+    let input = "a: 1\nb: 2\nc: 3\nd: 4\n";
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "del",
+            "--path",
+            "a",
+            "--path",
+            "c",
+            "--ignore-missing",
+            "--path",
+            "x",
+        ],
+        input,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "b: 2\nd: 4\n");
+
+    // The last value wins for actions taking a single path
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--path", "a", "--path", "b"], input);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "2");
+}
+
+#[test]
+fn test_path_in_help() {
+    let (_, stderr, _) = run_shyaml(&["keys", "--help"], "");
+    assert!(stderr.contains("--path <PATH>"), "stderr: {}", stderr);
+}
@@ -0,0 +1,100 @@
+//! Integration tests for the global `--post-process` option
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, run_shyaml_with_env, temp_yaml_file};
+use std::fs;
+use tempfile::TempDir;
+
+#[test]
+fn test_post_process_stdout() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--post-process", "tr a-z A-Z", "set-value", "b", "two"],
+        "a: one\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "A: ONE\nB: TWO\n");
+}
+
+#[test]
+fn test_post_process_leaves_no_temporary_file() {
+    let tmp = TempDir::new().unwrap();
+    let (stdout, stderr, success) = run_shyaml_with_env(
+        &["--post-process", "cat", "get-value", "a"],
+        "a: one\n",
+        &[("TMPDIR", tmp.path().to_str().unwrap())],
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "one");
+    assert_eq!(fs::read_dir(tmp.path()).unwrap().count(), 0);
+}
+
+#[test]
+fn test_post_process_output_file() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+    let path = file.to_str().unwrap();
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "-o",
+            path,
+            "--post-process",
+            "sed '1i # header'",
+            "set-value",
+            "b",
+            "2",
+        ],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.is_empty(), "stdout: {}", stdout);
+    assert_output_eq(
+        &fs::read_to_string(&file).unwrap(),
+        "# header\na: 1\nb: 2\n",
+    );
+}
+
+#[test]
+fn test_post_process_failure() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+    let path = file.to_str().unwrap();
+
+    let (stdout, stderr, code) = run_shyaml_code(
+        &[
+            "-o",
+            path,
+            "--post-process",
+            "exit 3",
+            "set-value",
+            "b",
+            "2",
+        ],
+        "a: 1\n",
+    );
+    assert_eq!(code, Some(127));
+    assert!(stdout.is_empty(), "stdout: {}", stdout);
+    assert!(
+        stderr.contains("post-process command 'exit 3' failed with exit code 3"),
+        "stderr: {}",
+        stderr
+    );
+    // The output file is left untouched
+    assert_output_eq(&fs::read_to_string(&file).unwrap(), "a: 1\n");
+}
+
+#[test]
+fn test_post_process_not_run_on_error() {
+    let tmp = TempDir::new().unwrap();
+    let marker = tmp.path().join("ran");
+    let command = format!("touch {}", marker.display());
+
+    let (stdout, _, code) = run_shyaml_code(
+        &["--post-process", &command, "get-value", "missing"],
+        "a: 1\n",
+    );
+    assert_eq!(code, Some(2));
+    assert!(stdout.is_empty(), "stdout: {}", stdout);
+    assert!(!marker.exists());
+}
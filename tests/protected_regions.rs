@@ -0,0 +1,164 @@
+//! Integration tests for regions protected by `# shyaml:ignore-start/end`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const INPUT: &str = indoc! {"
+    name: web
+    # shyaml:ignore-start
+    tuning:
+      workers: 4  # measured by hand
+    hosts:
+    - a
+    # shyaml:ignore-end
+    port: 80
+"};
+
+#[test]
+fn test_mutation_outside_region() {
+    let (stdout, stderr, success) = run_shyaml(&["set-value", "port", "8080"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: web
+            # shyaml:ignore-start
+            tuning:
+              workers: 4 # measured by hand
+            hosts:
+            - a
+            # shyaml:ignore-end
+            port: 8080
+        "},
+    );
+}
+
+#[test]
+fn test_mutations_inside_region_fail() {
+    for args in [
+        &["set-value", "tuning.workers", "8"][..],
+        &["update", "tuning.workers", "--add", "1"],
+        &["ensure", "tuning.threads"],
+        &["del", "hosts.-1"],
+    ] {
+        let (stdout, stderr, success) = run_shyaml(args, INPUT);
+        assert!(!success, "{:?} should fail", args);
+        assert!(stdout.is_empty());
+        assert!(
+            stderr.contains("is in a region protected by '# shyaml:ignore-start'"),
+            "stderr: {}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn test_mutation_of_parent_fails() {
+    let input = "spec:\n  # shyaml:ignore-start\n  a: 1\n  # shyaml:ignore-end\n  b: 2\n";
+    let (_, stderr, success) = run_shyaml(&["del", "spec"], input);
+    assert!(!success);
+    assert!(
+        stderr.contains("'spec.a' is in a region"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["del", "spec.b"], input);
+    assert!(success, "stderr: {}", stderr);
+}
+
+#[test]
+fn test_region_with_escaped_quotes_in_keys() {
+    let input = indoc! {r#"
+        # shyaml:ignore-start
+        "a\"b": x
+        'it''s': y
+        # shyaml:ignore-end
+        "c\"d": z
+    "#};
+    for path in [r#"a"b"#, "it's"] {
+        let (_, stderr, success) = run_shyaml(&["set-value", path, "1"], input);
+        assert!(!success, "{} should be protected", path);
+        assert!(
+            stderr.contains("is in a region protected by"),
+            "stderr: {}",
+            stderr
+        );
+    }
+
+    let (_, stderr, success) = run_shyaml(&["set-value", r#"c"d"#, "1"], input);
+    assert!(success, "stderr: {}", stderr);
+}
+
+#[test]
+fn test_mutation_in_value_mode_chain_fails() {
+    let (_, stderr, success) = run_shyaml(
+        &["set-value", "port", "1", ";", "keys", ";", "get-value", "0"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+
+    let (_, stderr, success) = run_shyaml(
+        &["save", "doc", ";", "set-value", "tuning.workers", "8"],
+        INPUT,
+    );
+    assert!(!success);
+    assert!(stderr.contains("protected"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_apply_keeps_region() {
+    let tmp = TempDir::new().unwrap();
+    let overlay = temp_yaml_file(
+        &tmp,
+        "overlay.yaml",
+        indoc! {"
+            tuning:
+              workers: 16
+            hosts: [b]
+            port: 443
+        "},
+    );
+    let (stdout, stderr, success) = run_shyaml(&["apply", overlay.to_str().unwrap()], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: web
+            tuning:
+              workers: 4
+            hosts:
+            - a
+            port: 443
+        "},
+    );
+}
+
+#[test]
+fn test_unterminated_region_fails() {
+    let (_, stderr, success) =
+        run_shyaml(&["set-value", "a", "1"], "# shyaml:ignore-start\nb: 2\n");
+    assert!(!success);
+    assert!(stderr.contains("unterminated"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_read_only_ignores_markers() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["get-value", "b"], "# shyaml:ignore-start\nb: 2\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "2");
+}
+
+#[test]
+fn test_mutation_without_regions_ignores_stray_markers() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "a", "1"],
+        "a: 0\n# shyaml:ignore-end\nb: 2\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.starts_with("a: 1\n"), "stdout: {}", stdout);
+}
@@ -0,0 +1,144 @@
+//! Integration tests for the `rename-key` and `move` actions
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    # app settings
+    name: web  # service name
+    spec:
+      ports:
+      - 80
+      replicas: 2
+    tail: 1
+"};
+
+#[test]
+fn test_rename_key_keeps_position() {
+    let (stdout, stderr, success) = run_shyaml(&["rename-key", "spec.ports", "listen"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # app settings
+            name: web # service name
+            spec:
+              listen:
+              - 80
+              replicas: 2
+            tail: 1
+        "},
+    );
+}
+
+#[test]
+fn test_rename_top_level_key() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["rename-key", "name", "title", ";", "keys"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "title\nspec\ntail\n");
+}
+
+#[test]
+fn test_rename_key_errors() {
+    for (args, expected) in [
+        (
+            &["rename-key", "name", "tail"][..],
+            "cannot rename 'name' to 'tail', key already exists.",
+        ),
+        (
+            &["rename-key", "spec.ports.0", "x"],
+            "cannot rename an element of a 'sequence'",
+        ),
+        (&["rename-key", "missing", "x"], "missing key 'missing'"),
+    ] {
+        let (stdout, stderr, success) = run_shyaml(args, INPUT);
+        assert!(!success, "{:?} should fail", args);
+        assert!(stdout.is_empty());
+        assert!(stderr.contains(expected), "stderr: {}", stderr);
+    }
+}
+
+#[test]
+fn test_move_to_new_path() {
+    let (stdout, stderr, success) = run_shyaml(&["move", "spec.replicas", "scale.count"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # app settings
+            name: web # service name
+            spec:
+              ports:
+              - 80
+            tail: 1
+            scale:
+              count: 2
+        "},
+    );
+}
+
+#[test]
+fn test_move_within_mapping_keeps_position() {
+    let (stdout, stderr, success) = run_shyaml(&["move", "name", "title", ";", "keys"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "title\nspec\ntail\n");
+}
+
+#[test]
+fn test_move_replaces_destination() {
+    let (stdout, stderr, success) = run_shyaml(&["move", "spec.ports.-1", "tail"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            # app settings
+            name: web # service name
+            spec:
+              ports: []
+              replicas: 2
+            tail: 80
+        "},
+    );
+}
+
+#[test]
+fn test_move_overlapping_paths_fails() {
+    let (stdout, stderr, success) = run_shyaml(&["move", "spec", "spec.old"], INPUT);
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("paths overlap"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_rename_and_move_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "doc",
+            ";",
+            "rename-key",
+            "spec",
+            "config",
+            ";",
+            "move",
+            "tail",
+            "config.tail",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: web
+            config:
+              ports:
+              - 80
+              replicas: 2
+              tail: 1
+        "},
+    );
+}
@@ -0,0 +1,108 @@
+//! Integration tests for the `render` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+#[test]
+fn test_render_each_loops() {
+    let tmp = TempDir::new().unwrap();
+    let input = indoc! {"
+        name: edge
+        services:
+          api:
+            port: 8080
+            hosts: [api.example.com, api.internal]
+          web:
+            port: 3000
+            hosts: [example.com]
+    "};
+    let template = temp_yaml_file(
+        &tmp,
+        "nginx.conf.tpl",
+        indoc! {"
+            # {{name}}
+            {{#each services}}
+            server {
+                server_name {{#each hosts}}{{.}} {{/each}};
+                location / { proxy_pass http://{{@key}}:{{port}}; }
+            }
+            {{/each}}
+        "},
+    );
+
+    let expected = indoc! {"
+        # edge
+        server {
+            server_name api.example.com api.internal ;
+            location / { proxy_pass http://api:8080; }
+        }
+        server {
+            server_name example.com ;
+            location / { proxy_pass http://web:3000; }
+        }
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["render", template.to_str().unwrap()], input);
+    assert!(success, "render failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_render_index_and_root() {
+    let tmp = TempDir::new().unwrap();
+    let input = indoc! {"
+        env: prod
+        items: [a, b]
+    "};
+    let template = temp_yaml_file(
+        &tmp,
+        "items.tpl",
+        "{{#each items}}{{@index}}:{{.}}@{{@root.env}}\n{{/each}}",
+    );
+
+    let (stdout, stderr, success) = run_shyaml(&["render", template.to_str().unwrap()], input);
+    assert!(success, "render failed: {}", stderr);
+    assert_output_eq(&stdout, "0:a@prod\n1:b@prod\n");
+}
+
+#[test]
+fn test_render_errors() {
+    let tmp = TempDir::new().unwrap();
+
+    let template = temp_yaml_file(&tmp, "value.tpl", "{{services}}\n");
+    let (_, stderr, success) = run_shyaml(
+        &["render", template.to_str().unwrap()],
+        "services: {a: 1}\n",
+    );
+    assert!(!success, "render should fail on a struct value");
+    assert!(stderr.contains("can't render struct at 'services'"));
+
+    let template = temp_yaml_file(&tmp, "unclosed.tpl", "{{#each services}}\n");
+    let (_, stderr, success) =
+        run_shyaml(&["render", template.to_str().unwrap()], "services: []\n");
+    assert!(!success, "render should fail on an unclosed loop");
+    assert!(stderr.contains("'#each services' is never closed"));
+}
+
+#[test]
+fn test_render_in_chain() {
+    let tmp = TempDir::new().unwrap();
+    let template = temp_yaml_file(&tmp, "addr.tpl", "{{host}}:{{port}}\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "port",
+            "8080",
+            ";",
+            "render",
+            template.to_str().unwrap(),
+        ],
+        "host: db\nport: 5432\n",
+    );
+    assert!(success, "render failed: {}", stderr);
+    assert_output_eq(&stdout, "db:8080\n");
+}
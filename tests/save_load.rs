@@ -0,0 +1,148 @@
+//! Integration tests for `save` / `load` named captures in chains
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+#[test]
+fn test_save_load_copies_value() {
+    let input = indoc! {r#"
+        source:
+          host: db
+        target: {}
+    "#};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "doc",
+            ";",
+            "get-value",
+            "source.host",
+            ";",
+            "save",
+            "host",
+            ";",
+            "load",
+            "doc",
+            ";",
+            "load",
+            "host",
+            "target.host",
+        ],
+        input,
+    );
+
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    let expected = indoc! {r#"
+        source:
+          host: db
+        target:
+          host: db
+    "#};
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_load_restores_value() {
+    let input = indoc! {r#"
+        a: 1
+    "#};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "save",
+            "orig",
+            ";",
+            "set-value",
+            "a",
+            "2",
+            ";",
+            "load",
+            "orig",
+            ";",
+            "get-value",
+            "a",
+        ],
+        input,
+    );
+
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "1");
+}
+
+#[test]
+fn test_load_saved_structure() {
+    let input = indoc! {r#"
+        a:
+          b: 1
+        c: 2
+    "#};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "get-value",
+            "a",
+            ";",
+            "save",
+            "x",
+            ";",
+            "set-value",
+            "b",
+            "3",
+            ";",
+            "load",
+            "x",
+        ],
+        input,
+    );
+
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "b: 1\n");
+}
+
+#[test]
+fn test_registers_are_per_document() {
+    let input = indoc! {r#"
+        a: 1
+        ---
+        a: 2
+    "#};
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "a", ";", "save", "x", ";", "load", "x"],
+        input,
+    );
+
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "1\n\u{0}2\n");
+}
+
+#[test]
+fn test_save_load_scalar_output() {
+    let input = "a: 'true'\nb: 1\n";
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "a", ";", "save", "a"], input);
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "true\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "a", ";", "-y", "save", "a"], input);
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "'true'\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "a", ";", "save", "a", ";", "load", "a"],
+        input,
+    );
+    assert!(success, "Expected success, got stderr: {}", stderr);
+    assert_output_eq(&stdout, "true\n");
+}
+
+#[test]
+fn test_load_unknown_name_error() {
+    let (_, stderr, success) = run_shyaml(&["load", "nope"], "a: 1\n");
+
+    assert!(!success, "load of an unknown name should fail");
+    assert!(stderr.contains("no value saved under name 'nope'"));
+}
@@ -0,0 +1,105 @@
+//! Integration tests for the `search` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    db:
+      host: db.internal
+      port: 5432
+      replicas:
+      - db.internal
+      - backup.internal
+    web:
+      upstream: db.internal:5432
+      hosts:
+        db.internal: primary
+"};
+
+#[test]
+fn test_search_keys_and_values() {
+    let (stdout, stderr, success) = run_shyaml(&["search", r"db\.internal"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {r"
+            db.host
+            db.replicas.0
+            web.upstream
+            web.hosts.db\.internal
+        "},
+    );
+}
+
+#[test]
+fn test_search_keys_only() {
+    let (stdout, stderr, success) = run_shyaml(&["search", "--keys-only", "host"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db.host\nweb.hosts\n");
+}
+
+#[test]
+fn test_search_values_only_with_values() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["search", "--values-only", "--with-values", "5432"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db.port=5432\nweb.upstream=db.internal:5432\n");
+}
+
+#[test]
+fn test_search_below_path() {
+    let (stdout, stderr, success) = run_shyaml(&["search", "internal", "db.replicas"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db.replicas.0\ndb.replicas.1\n");
+}
+
+#[test]
+fn test_search_no_match_outputs_nothing() {
+    let (stdout, stderr, success) = run_shyaml(&["search", "nowhere"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "");
+}
+
+#[test]
+fn test_search_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "cache.host",
+            "db.internal",
+            ";",
+            "search",
+            "host$",
+            ";",
+            "keys",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db.host\ncache.host\n");
+}
+
+#[test]
+fn test_search_errors() {
+    let (_, stderr, success) = run_shyaml(&["search", "("], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid search pattern '('"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["search", "x", "db.host"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("search does not support 'str' type"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["search", "--keys-only", "--values-only", "x"], INPUT);
+    assert!(!success);
+    assert!(stderr.contains("cannot be used with"), "stderr: {}", stderr);
+}
@@ -0,0 +1,94 @@
+//! Integration tests for the `select-where` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    services:
+    - name: web
+      image: nginx:1.25
+      deploy: {replicas: 2}
+    - name: api
+      image: registry.local/api:3.1
+    - name: worker
+      image: registry.local/worker:3.1
+      deploy: {replicas: 4}
+"};
+
+#[test]
+fn test_select_where_equal() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["select-where", "services", "deploy.replicas=4"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            - name: worker
+              image: registry.local/worker:3.1
+              deploy:
+                replicas: 4
+        "},
+    );
+}
+
+#[test]
+fn test_select_where_not_equal_skips_missing_fields() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "select-where",
+            "services",
+            "deploy.replicas!=4",
+            ";",
+            "get-value",
+            "0.name",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "web");
+}
+
+#[test]
+fn test_select_where_regex() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "select-where",
+            "services",
+            r"image~^registry\.local/",
+            ";",
+            "get-values",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("api"), "stdout: {}", stdout);
+    assert!(!stdout.contains("nginx"), "stdout: {}", stdout);
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["select-where", "services", "name~^x", ";", "get-length"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0\n");
+}
+
+#[test]
+fn test_select_where_errors() {
+    let (_, stderr, success) = run_shyaml(&["select-where", "services", "name"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid condition 'name': expected FIELD=VALUE"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["select-where", "services.0", "name=web"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("select-where does not support 'struct' type"),
+        "stderr: {}",
+        stderr
+    );
+}
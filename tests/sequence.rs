@@ -0,0 +1,128 @@
+//! Integration tests for the `sort`, `unique` and `reverse` actions
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    ports:
+    - 8080
+    - 443
+    - 80
+    tags:
+    - web
+    - api
+    - web
+    users:
+    - name: carol
+      uid: 1002
+    - name: alice
+      uid: 1000
+    - name: bob
+"};
+
+#[test]
+fn test_sort_raw_text() {
+    let (stdout, stderr, success) = run_shyaml(&["sort", "ports"], "ports:\n- 8080\n- 443\n- 80\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "ports:\n- 443\n- 80\n- 8080\n");
+}
+
+#[test]
+fn test_sort_numeric_keeps_other_keys() {
+    let (stdout, stderr, success) = run_shyaml(&["sort", "ports", "--numeric"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert!(
+        stdout.starts_with("ports:\n- 80\n- 443\n- 8080\ntags:\n"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_sort_by_field_in_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "sort",
+            "users",
+            "--by",
+            "name",
+            "-r",
+            ";",
+            "get-value",
+            "users.0.name",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "carol");
+
+    // Elements without the field come first
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "sort",
+            "users",
+            "--by",
+            "uid",
+            "-n",
+            ";",
+            "get-value",
+            "users.1.name",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "alice");
+}
+
+#[test]
+fn test_unique_and_reverse() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "unique",
+            "tags",
+            ";",
+            "reverse",
+            "tags",
+            ";",
+            "get-values",
+            "tags",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "api\nweb\n");
+}
+
+#[test]
+fn test_reverse_keeps_comments() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["reverse", "steps"], "steps:\n- build # first\n- test\n");
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("build # first"), "stdout: {}", stdout);
+    assert!(
+        stdout.find("test").unwrap() < stdout.find("build").unwrap(),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_sequence_errors() {
+    let (_, stderr, success) = run_shyaml(&["sort", "tags", "-n"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("sort --numeric does not support 'web' value at 'tags.0'"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["unique", "users.0"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("unique does not support 'struct' type"),
+        "stderr: {}",
+        stderr
+    );
+}
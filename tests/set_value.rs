@@ -2,8 +2,9 @@
 
 mod common;
 
-use common::{assert_output_eq, run_shyaml};
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
 use indoc::indoc;
+use tempfile::TempDir;
 
 #[test]
 fn test_set_value_simple() {
@@ -160,29 +161,41 @@ fn test_set_value_negative_index_first() {
 
 #[test]
 fn test_set_value_index_out_of_range() {
-    let (stdout, stderr, success) =
-        run_shyaml(&["set-value", "items.5", "x"], "items: [a, b, c]\n");
-    assert!(!success);
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["set-value", "items.5", "x"], "items: [a, b, c]\n");
+    assert_eq!(code, Some(2));
     assert!(stdout.is_empty());
-    assert!(stderr.contains("out of bounds"));
+    assert!(
+        stderr.contains("index 5 is out of range"),
+        "stderr: {}",
+        stderr
+    );
 }
 
 #[test]
 fn test_set_value_negative_index_out_of_range() {
-    let (stdout, stderr, success) =
-        run_shyaml(&["set-value", "items.-10", "x"], "items: [a, b, c]\n");
-    assert!(!success);
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["set-value", "items.-10", "x"], "items: [a, b, c]\n");
+    assert_eq!(code, Some(2));
     assert!(stdout.is_empty());
-    assert!(stderr.contains("out of bounds"));
+    assert!(
+        stderr.contains("index -10 is out of range"),
+        "stderr: {}",
+        stderr
+    );
 }
 
 #[test]
 fn test_set_value_non_integer_index_on_sequence() {
-    let (stdout, stderr, success) =
-        run_shyaml(&["set-value", "items.foo", "x"], "items: [a, b, c]\n");
-    assert!(!success);
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["set-value", "items.foo", "x"], "items: [a, b, c]\n");
+    assert_eq!(code, Some(2));
     assert!(stdout.is_empty());
-    assert!(stderr.contains("invalid sequence index"));
+    assert!(
+        stderr.contains("non-integer index 'foo'"),
+        "stderr: {}",
+        stderr
+    );
 }
 
 #[test]
@@ -247,3 +260,285 @@ fn test_set_value_traverse_sequence_then_set() {
         "},
     );
 }
+
+#[test]
+fn test_set_value_from_same_document() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "chart.appVersion", "--from", "app.version"],
+        "app:\n  version: 1.2.3\nchart:\n  appVersion: 0.1.0\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            app:
+              version: 1.2.3
+            chart:
+              appVersion: 1.2.3
+        "},
+    );
+}
+
+#[test]
+fn test_set_value_from_copies_structure() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "b", "--from", "a", ";", "get-value", "b.x.0"],
+        "a:\n  x:\n  - 1\n  - 2\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "1");
+}
+
+#[test]
+fn test_set_value_from_file() {
+    let tmp = TempDir::new().unwrap();
+    let other = temp_yaml_file(
+        &tmp,
+        "other.yaml",
+        indoc! {"
+            app:
+              version: 2.0.1
+        "},
+    );
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "version",
+            "--from",
+            "app.version",
+            "--file",
+            other.to_str().unwrap(),
+        ],
+        "version: 1.0.0\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "version: 2.0.1\n");
+}
+
+#[test]
+fn test_set_value_from_missing_path() {
+    let (_, stderr, success) = run_shyaml(&["set-value", "b", "--from", "nope"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("missing key 'nope'"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_set_value_from_conflicts_with_value() {
+    let (_, stderr, success) = run_shyaml(&["set-value", "b", "2", "--from", "a"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("cannot be used with"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_set_value_from_file_content_is_raw_string() {
+    let tmp = TempDir::new().unwrap();
+    let cert = temp_yaml_file(
+        &tmp,
+        "cert.pem",
+        "-----BEGIN CERT-----\nMIIB\n-----END CERT-----\n",
+    );
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "tls.cert",
+            "--from-file",
+            cert.to_str().unwrap(),
+            ";",
+            "get-value",
+            "tls.cert",
+        ],
+        "name: web\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "-----BEGIN CERT-----\nMIIB\n-----END CERT-----\n");
+}
+
+#[test]
+fn test_set_value_from_file_as_yaml() {
+    let tmp = TempDir::new().unwrap();
+    let fragment = temp_yaml_file(&tmp, "db.yaml", "host: db1  # primary\nport: 5432\n");
+
+    for prefix in [&[][..], &["save", "doc", ";"]] {
+        let mut args = prefix.to_vec();
+        args.extend([
+            "set-value",
+            "db",
+            "--from-file",
+            fragment.to_str().unwrap(),
+            "-y",
+        ]);
+        let (stdout, stderr, success) = run_shyaml(&args, "name: web\n");
+        assert!(success, "stderr: {}", stderr);
+        assert_output_eq(&stdout, "name: web\ndb:\n  host: db1\n  port: 5432\n");
+    }
+}
+
+#[test]
+fn test_set_value_from_missing_file() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "b", "--from-file", "/nonexistent/value.txt"],
+        "a: 1\n",
+    );
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("Failed to read"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_set_value_from_stdin_marker_fails() {
+    let (stdout, stderr, success) = run_shyaml(&["set-value", "b", "--from-file", "-"], "a: 1\n");
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("it already holds the YAML document"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_set_value_dash_is_literal_without_file() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["set-value", "b", "-", ";", "get-value", "b"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "-");
+}
+
+#[test]
+fn test_set_value_from_stdin_with_file_input() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "name: web\n");
+    let file = file.to_str().unwrap();
+
+    for prefix in [&[][..], &["save", "doc", ";"]] {
+        let mut args = vec!["-f", file];
+        args.extend(prefix);
+        args.extend(["set-value", "password", "-"]);
+        let (stdout, stderr, success) = run_shyaml(&args, "s3cret");
+        assert!(success, "stderr: {}", stderr);
+        assert_output_eq(&stdout, "name: web\npassword: s3cret\n");
+    }
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "-f",
+            file,
+            "set-value",
+            "cert",
+            "-",
+            ";",
+            "get-value",
+            "cert",
+        ],
+        "BEGIN\nEND\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "BEGIN\nEND\n");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["-f", file, "set-value", "db", "--from-file", "-", "-y"],
+        "host: db1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name: web\ndb:\n  host: db1\n");
+}
+
+#[test]
+fn test_set_value_if_missing_writes() {
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["set-value", "b", "2", "--if-missing"], "a: 1\n");
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb: 2\n");
+}
+
+#[test]
+fn test_set_value_if_missing_skips_existing() {
+    let (stdout, stderr, code) =
+        run_shyaml_code(&["set-value", "a", "2", "--if-missing"], "a: 1\n");
+    assert_eq!(code, Some(7), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\n");
+}
+
+#[test]
+fn test_set_value_if_missing_on_empty_input() {
+    let (stdout, stderr, code) = run_shyaml_code(&["set-value", "a", "2", "--if-missing"], "");
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 2\n");
+}
+
+#[test]
+fn test_set_value_if_equals_writes() {
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["set-value", "image.tag", "1.26", "--if-equals", "1.25"],
+        "image:\n  tag: 1.25\n",
+    );
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "image:\n  tag: 1.26\n");
+}
+
+#[test]
+fn test_set_value_if_equals_skips_other_value() {
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["set-value", "image.tag", "1.26", "--if-equals", "1.24"],
+        "image:\n  tag: 1.25\n",
+    );
+    assert_eq!(code, Some(7), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "image:\n  tag: 1.25\n");
+}
+
+#[test]
+fn test_set_value_if_equals_skips_missing() {
+    let (_, stderr, code) = run_shyaml_code(&["set-value", "b", "2", "--if-equals", "1"], "a: 1\n");
+    assert_eq!(code, Some(7), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_set_value_guarded_in_value_mode_chain() {
+    let (stdout, stderr, code) = run_shyaml_code(
+        &[
+            "get-value",
+            "spec",
+            ";",
+            "set-value",
+            "replicas",
+            "5",
+            "--if-missing",
+            ";",
+            "set-value",
+            "name",
+            "web",
+            "--if-missing",
+        ],
+        "spec:\n  replicas: 2\n",
+    );
+    assert_eq!(code, Some(7), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "replicas: 2\nname: web\n");
+}
+
+#[test]
+fn test_set_value_if_missing_documents() {
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["set-value", "a", "2", "--if-missing"],
+        "a: 1\n---\nb: 1\n",
+    );
+    assert_eq!(code, Some(7), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\n\0b: 1\na: 2\n");
+}
+
+#[test]
+fn test_set_value_guard_errors_are_not_skips() {
+    let (_, stderr, code) = run_shyaml_code(&["set-value", "a.b", "2", "--if-missing"], "a: 1\n");
+    assert_eq!(code, Some(2));
+    assert!(stderr.contains("cannot set value on scalar"));
+}
+
+#[test]
+fn test_set_value_skip_is_not_a_quiet_path_error() {
+    let (_, stderr, code) =
+        run_shyaml_code(&["-q", "set-value", "a", "2", "--if-missing"], "a: 1\n");
+    assert_eq!(code, Some(7), "stderr: {}", stderr);
+    let (_, _, code) = run_shyaml_code(&["-q", "get-value", "b"], "a: 1\n");
+    assert_eq!(code, Some(1));
+}
@@ -0,0 +1,94 @@
+//! Integration tests for `set-value --keep-tag` and `--tag`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+
+const INPUT: &str = "password: !vault old-secret\nuser: admin\n";
+
+#[test]
+fn test_set_value_drops_tag_by_default() {
+    let (stdout, stderr, success) = run_shyaml(&["set-value", "password", "new"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "password: new\nuser: admin\n");
+}
+
+#[test]
+fn test_keep_tag() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["set-value", "--keep-tag", "password", "new"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "password: !vault new\nuser: admin\n");
+
+    // Same in a chain, working on values
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "--keep-tag",
+            "password",
+            "new",
+            ";",
+            "get-type",
+            "password",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "!vault\n");
+
+    // Untagged values stay untagged
+    let (stdout, stderr, success) = run_shyaml(&["set-value", "--keep-tag", "user", "root"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "password: !vault old-secret\nuser: root\n");
+}
+
+#[test]
+fn test_keep_tag_yields_to_own_tag() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-value", "--keep-tag", "-y", "password", "!env SECRET"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "password: !env SECRET\nuser: admin\n");
+}
+
+#[test]
+fn test_explicit_tag() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "--tag",
+            "!vault",
+            "-y",
+            "db",
+            "{user: a, password: b}",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert!(stdout.contains("db: !vault"), "stdout: {}", stdout);
+
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "--tag",
+            "!vault",
+            "-y",
+            "db",
+            "{user: a, password: b}",
+            ";",
+            "get-value",
+            "db.password",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "b");
+
+    let (_, stderr, success) = run_shyaml(
+        &["set-value", "--tag", "!a", "--keep-tag", "user", "x"],
+        INPUT,
+    );
+    assert!(!success);
+    assert!(stderr.contains("cannot be used with"), "stderr: {}", stderr);
+}
@@ -0,0 +1,108 @@
+//! Integration tests for the `set-values` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+#[test]
+fn test_set_values_arguments() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-values", "image.tag=1.26", "replicas=3", "note=a=b"],
+        "image:\n  tag: 1.25\nreplicas: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            image:
+              tag: 1.26
+            replicas: 3
+            note: a=b
+        "},
+    );
+}
+
+#[test]
+fn test_set_values_type_inference() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["set-values", "a=1", "b=true", "c=[1]", ";", "get-types"],
+        "",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "int\nbool\nstr\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["set-values", "c=[1]", "-y", ";", "get-type", "c"], "");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "sequence\n");
+}
+
+#[test]
+fn test_set_values_from_file() {
+    let tmp = TempDir::new().unwrap();
+    let values = temp_yaml_file(
+        &tmp,
+        "values.txt",
+        indoc! {"
+            # deploy settings
+            image.tag=1.26
+
+            replicas=3
+        "},
+    );
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-values",
+            "--from",
+            values.to_str().unwrap(),
+            "replicas=5",
+        ],
+        "name: web\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: web
+            image:
+              tag: 1.26
+            replicas: 5
+        "},
+    );
+}
+
+#[test]
+fn test_set_values_invalid_assignment() {
+    let (stdout, stderr, success) = run_shyaml(&["set-values", "a=1", "b"], "x: 1\n");
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("invalid assignment 'b', expected 'PATH=VALUE'"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_set_values_invalid_file_line() {
+    let tmp = TempDir::new().unwrap();
+    let values = temp_yaml_file(&tmp, "values.txt", "a=1\nb\n");
+    let (_, stderr, success) = run_shyaml(
+        &["set-values", "--from", values.to_str().unwrap()],
+        "x: 1\n",
+    );
+    assert!(!success);
+    assert!(stderr.contains("values.txt:2:"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_set_values_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "spec", ";", "set-values", "a=1", "b.c=2"],
+        "spec:\n  a: 0\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb:\n  c: 2\n");
+}
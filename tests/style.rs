@@ -0,0 +1,133 @@
+//! Integration tests for the `--indent`, `--flow`, `--width` and
+//! `--quote-strings` output options
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    name: app
+    ports: [80, 443]
+    db:
+      host: localhost
+      users:
+      - {name: admin, role: owner}
+"};
+
+#[test]
+fn test_style_indent() {
+    let (stdout, stderr, success) = run_shyaml(&["--indent", "4", "del", "ports"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: app
+            db:
+                host: localhost
+                users:
+                - name: admin
+                  role: owner
+        "},
+    );
+}
+
+#[test]
+fn test_style_flow_block_rewrites_flow_input() {
+    let (stdout, stderr, success) = run_shyaml(&["--flow", "block", "del", "db"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: app
+            ports:
+            - 80
+            - 443
+        "},
+    );
+}
+
+#[test]
+fn test_style_flow_auto() {
+    let (stdout, stderr, success) = run_shyaml(&["--flow", "auto", "del", "name"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            ports: [80, 443]
+            db:
+              host: localhost
+              users:
+              - {name: admin, role: owner}
+        "},
+    );
+}
+
+#[test]
+fn test_style_flow_auto_width() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--flow", "auto", "--width", "21", "del", "db.users"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: app
+            ports: [80, 443]
+            db: {host: localhost}
+        "},
+    );
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["--flow", "auto", "--width", "15", "del", "db.users"],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            name: app
+            ports:
+            - 80
+            - 443
+            db:
+              host: localhost
+        "},
+    );
+}
+
+#[test]
+fn test_style_flow() {
+    let (stdout, stderr, success) = run_shyaml(&["--flow", "flow", "del", "db"], INPUT);
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "{name: app, ports: [80, 443]}\n");
+}
+
+#[test]
+fn test_style_quote_strings() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--quote-strings", "always", "set-value", "debug", "true"],
+        "name: app\nport: 80\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name: \"app\"\nport: 80\ndebug: true\n");
+}
+
+#[test]
+fn test_style_conflicts_with_factor_anchors() {
+    let (_, stderr, success) = run_shyaml(&["--indent", "4", "--factor-anchors", "keys"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("can't be used with --factor-anchors"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_style_invalid_indent() {
+    let (_, stderr, success) = run_shyaml(&["--indent", "0", "keys"], INPUT);
+    assert!(!success);
+    assert!(stderr.contains("invalid value"), "stderr: {}", stderr);
+}
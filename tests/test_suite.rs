@@ -0,0 +1,209 @@
+//! Integration tests for the `test-suite` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const SUITE: &str = indoc! {r"
+    name: web config
+    tests:
+      - name: three replicas
+        path: spec.replicas
+        expected: 3
+      - path: image.tag
+        op: matches
+        expected: '^1\.2[0-9]$'
+      - path: debug
+        op: missing
+"};
+
+#[test]
+fn test_suite_passes() {
+    let tmp = TempDir::new().unwrap();
+    let suite = temp_yaml_file(&tmp, "suite.yaml", SUITE);
+    let (stdout, stderr, success) = run_shyaml(
+        &["test-suite", suite.to_str().unwrap()],
+        "spec: {replicas: 3}\nimage: {tag: '1.25'}\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {r"
+            PASS: three replicas
+            PASS: image.tag matches '^1\.2[0-9]$'
+            PASS: debug missing
+            3 passed, 0 failed
+        "},
+    );
+}
+
+#[test]
+fn test_suite_failures_exit_1() {
+    let tmp = TempDir::new().unwrap();
+    let suite = temp_yaml_file(&tmp, "suite.yaml", SUITE);
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["test-suite", suite.to_str().unwrap()],
+        "spec: {replicas: 2}\ndebug: true\n",
+    );
+    assert_eq!(code, Some(1));
+    assert!(stderr.is_empty(), "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {r"
+            FAIL: three replicas: expected 3, got 2
+            FAIL: image.tag matches '^1\.2[0-9]$': invalid path 'image.tag', missing key 'image' in struct.
+            FAIL: debug missing: expected no value, got true
+            0 passed, 3 failed
+        "},
+    );
+}
+
+#[test]
+fn test_suite_multiple_documents() {
+    let tmp = TempDir::new().unwrap();
+    let suite = temp_yaml_file(&tmp, "suite.yaml", "tests:\n  - path: a\n");
+    let (stdout, _, code) = run_shyaml_code(
+        &["test-suite", suite.to_str().unwrap()],
+        "a: 1\n---\nb: 2\n",
+    );
+    assert_eq!(code, Some(1));
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            <stdin> (document 1): PASS: a exists
+            <stdin> (document 2): FAIL: a exists: invalid path 'a', missing key 'a' in struct.
+            1 passed, 1 failed
+        "},
+    );
+}
+
+#[test]
+fn test_suite_files() {
+    let tmp = TempDir::new().unwrap();
+    let suite = temp_yaml_file(
+        &tmp,
+        "suite.yaml",
+        "tests:\n  - {path: replicas, expected: 1}\n",
+    );
+    let a = temp_yaml_file(&tmp, "a.yaml", "replicas: 1\n");
+    let b = temp_yaml_file(&tmp, "b.yaml", "replicas: 2\n---\nreplicas: 1\n");
+    let (a, b) = (a.to_str().unwrap(), b.to_str().unwrap());
+    let (stdout, _, code) = run_shyaml_code(
+        &["test-suite", suite.to_str().unwrap(), "-f", a, "--file", b],
+        "",
+    );
+    assert_eq!(code, Some(1));
+    assert_output_eq(
+        &stdout,
+        &format!(
+            "{a}: PASS: replicas equals 1\n\
+             {b} (document 1): FAIL: replicas equals 1: expected 1, got 2\n\
+             {b} (document 2): PASS: replicas equals 1\n\
+             2 passed, 1 failed\n"
+        ),
+    );
+
+    let (_, stderr, code) = run_shyaml_code(
+        &[
+            "test-suite",
+            suite.to_str().unwrap(),
+            "-f",
+            "/nonexistent.yaml",
+        ],
+        "",
+    );
+    assert_eq!(code, Some(5));
+    assert!(stderr.contains("Failed to read"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_suite_setup_overlays() {
+    let tmp = TempDir::new().unwrap();
+    temp_yaml_file(&tmp, "base.yaml", "spec: {replicas: 1}\n");
+    temp_yaml_file(&tmp, "prod.yaml", "spec: {replicas: 5}\n");
+    let suite = temp_yaml_file(
+        &tmp,
+        "suite.yaml",
+        indoc! {"
+            setup: [base.yaml]
+            tests:
+              - {path: spec.replicas, expected: 1}
+              - {path: name, expected: web}
+              - name: production replicas
+                setup: [prod.yaml]
+                path: spec.replicas
+                expected: 5
+        "},
+    );
+    // Overlays are found next to the suite, whatever the current directory
+    let (stdout, stderr, success) =
+        run_shyaml(&["test-suite", suite.to_str().unwrap()], "name: web\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            PASS: spec.replicas equals 1
+            PASS: name equals 'web'
+            PASS: production replicas
+            3 passed, 0 failed
+        "},
+    );
+}
+
+#[test]
+fn test_suite_junit_report() {
+    let tmp = TempDir::new().unwrap();
+    let suite = temp_yaml_file(&tmp, "suite.yaml", SUITE);
+    let (stdout, _, code) = run_shyaml_code(
+        &["test-suite", suite.to_str().unwrap(), "--report", "junit"],
+        "spec: {replicas: 2}\nimage: {tag: '1.25'}\n",
+    );
+    assert_eq!(code, Some(1));
+    assert_output_eq(
+        &stdout,
+        indoc! {r#"
+            <?xml version="1.0" encoding="UTF-8"?>
+            <testsuites name="web config" tests="3" failures="1">
+              <testsuite name="&lt;stdin&gt;" tests="3" failures="1">
+                <testcase classname="web config" name="three replicas">
+                  <failure message="expected 3, got 2"/>
+                </testcase>
+                <testcase classname="web config" name="image.tag matches '^1\.2[0-9]$'"/>
+                <testcase classname="web config" name="debug missing"/>
+              </testsuite>
+            </testsuites>
+        "#},
+    );
+}
+
+#[test]
+fn test_suite_invalid_suite() {
+    let tmp = TempDir::new().unwrap();
+    let suite = temp_yaml_file(&tmp, "suite.yaml", "tests:\n  - {op: length}\n");
+    let (_, stderr, code) = run_shyaml_code(&["test-suite", suite.to_str().unwrap()], "a: 1\n");
+    assert_eq!(code, Some(127));
+    assert!(
+        stderr.contains("invalid test suite")
+            && stderr.contains("test 1, op 'length' needs an 'expected' value"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, code) = run_shyaml_code(&["test-suite", "/nonexistent/suite.yaml"], "a: 1\n");
+    assert_eq!(code, Some(5));
+    assert!(stderr.contains("Failed to read"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_suite_cannot_be_chained() {
+    let tmp = TempDir::new().unwrap();
+    let suite = temp_yaml_file(&tmp, "suite.yaml", "tests: []\n");
+    let (_, stderr, success) = run_shyaml(
+        &["test-suite", suite.to_str().unwrap(), ";", "get-value", "a"],
+        "a: 1\n",
+    );
+    assert!(!success);
+    assert!(stderr.contains("cannot be chained"), "stderr: {}", stderr);
+}
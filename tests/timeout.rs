@@ -0,0 +1,63 @@
+//! Integration tests for the global `--timeout` option
+
+mod common;
+
+use common::{assert_output_eq, binary_path, run_shyaml};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+#[test]
+fn test_timeout_on_stalled_input() {
+    let start = Instant::now();
+    let mut child = Command::new(binary_path())
+        .args(["--timeout", "1", "get-value", "a"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("Failed to spawn shyaml");
+    // Keep stdin open without writing, as a stalled producer would
+    let _stdin = child.stdin.take();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert_eq!(output.status.code(), Some(124));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("timed out after 1 seconds"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_timeout_not_reached() {
+    let (stdout, stderr, success) = run_shyaml(&["--timeout", "30", "get-value", "a"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "1");
+}
+
+#[test]
+fn test_timeout_must_be_positive() {
+    let (_, stderr, success) = run_shyaml(&["--timeout", "0", "get-value", "a"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("--timeout"), "stderr: {}", stderr);
+}
+
+#[test]
+fn test_timeout_lets_post_process_finish() {
+    // The deadline passes while the command runs, after the actions
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--timeout",
+            "1",
+            "--post-process",
+            "sleep 2; cat",
+            "get-value",
+            "a",
+        ],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "1");
+}
@@ -0,0 +1,72 @@
+//! Integration tests for the `to-dotenv` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+#[test]
+fn test_to_dotenv_flattens_mapping() {
+    let input = indoc! {"
+        app:
+          name: demo
+          db:
+            host: db.local
+            max-conn: 10
+          features:
+          - a
+          - b
+    "};
+
+    let expected = indoc! {"
+        NAME=demo
+        DB_HOST=db.local
+        DB_MAX_CONN=10
+        FEATURES_0=a
+        FEATURES_1=b
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["to-dotenv", "app"], input);
+    assert!(success, "to-dotenv failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_to_dotenv_custom_separator() {
+    let input = indoc! {"
+        db:
+          host: db.local
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["to-dotenv", "--separator", "__"], input);
+    assert!(success, "to-dotenv failed: {}", stderr);
+    assert_output_eq(&stdout, "DB__HOST=db.local\n");
+}
+
+#[test]
+fn test_to_dotenv_collision_error() {
+    let input = indoc! {"
+        db:
+          host: a
+        db_host: b
+    "};
+
+    let (_, stderr, success) = run_shyaml(&["to-dotenv"], input);
+    assert!(!success, "to-dotenv should fail on name collision");
+    assert!(stderr.contains("'db.host' and 'db_host' both map to 'DB_HOST'"));
+}
+
+#[test]
+fn test_to_dotenv_in_chain() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["set-value", "b.c", "2", ";", "to-dotenv"], "a: 1\n");
+    assert!(success, "chain failed: {}", stderr);
+    assert_output_eq(&stdout, "A=1\nB_C=2\n");
+}
+
+#[test]
+fn test_to_dotenv_scalar_error() {
+    let (_, stderr, success) = run_shyaml(&["to-dotenv", "a"], "a: 1\n");
+    assert!(!success, "to-dotenv should fail on a scalar");
+    assert!(stderr.contains("to-dotenv does not support 'int' type"));
+}
@@ -0,0 +1,136 @@
+//! Integration tests for the `--input-format` and `--output-format` options
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const CARGO_TOML: &str = indoc! {r#"
+    [package]
+    name = "demo"
+    version = "0.1.0"
+
+    [dependencies]
+    clap = "4"
+    serde = "1"
+"#};
+
+#[test]
+fn test_query_toml_input() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--input-format", "toml", "get-value", "package.version"],
+        CARGO_TOML,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "0.1.0");
+
+    let (stdout, stderr, success) = run_shyaml(
+        &["--input-format", "toml", "keys", "dependencies"],
+        CARGO_TOML,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "clap\nserde\n");
+}
+
+#[test]
+fn test_toml_to_yaml() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--input-format",
+            "toml",
+            "set-value",
+            "package.version",
+            "0.2.0",
+        ],
+        CARGO_TOML,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            package:
+              name: demo
+              version: 0.2.0
+            dependencies:
+              clap: '4'
+              serde: '1'
+        "},
+    );
+}
+
+#[test]
+fn test_toml_round_trip() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "--input-format",
+            "toml",
+            "--output-format",
+            "toml",
+            "set-value",
+            "dependencies.log",
+            "0.4",
+        ],
+        CARGO_TOML,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {r#"
+            [package]
+            name = "demo"
+            version = "0.1.0"
+
+            [dependencies]
+            clap = "4"
+            serde = "1"
+            log = 0.4
+        "#},
+    );
+}
+
+#[test]
+fn test_yaml_to_toml() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["--output-format", "toml", "get-value", "app"],
+        "app:\n  name: web\n  ports: [80, 443]\n  db:\n    host: localhost\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {r#"
+            name = "web"
+            ports = [80, 443]
+
+            [db]
+            host = "localhost""#},
+    );
+}
+
+#[test]
+fn test_toml_output_errors() {
+    for (input, expected) in [
+        (
+            "a:\n  b: null\n",
+            "cannot write null value at 'a.b' as TOML",
+        ),
+        ("- 1\n", "cannot write a 'sequence' as TOML"),
+    ] {
+        let (stdout, stderr, success) =
+            run_shyaml(&["--output-format", "toml", "get-value"], input);
+        assert!(!success);
+        assert!(stdout.is_empty());
+        assert!(stderr.contains(expected), "stderr: {}", stderr);
+    }
+}
+
+#[test]
+fn test_invalid_toml_input() {
+    let (stdout, stderr, success) = run_shyaml(&["--input-format", "toml", "keys"], "a = \n");
+    assert!(!success);
+    assert!(stdout.is_empty());
+    assert!(
+        stderr.contains("Failed to parse TOML"),
+        "stderr: {}",
+        stderr
+    );
+}
@@ -0,0 +1,106 @@
+//! Integration tests for the truthiness table
+//!
+//! `--truthy` and `--falsy` set the words read as booleans by
+//! `get-value --as-bool` and the `truthy` and `falsy` ops of `test-suite`.
+
+mod common;
+
+use common::{run_shyaml, temp_yaml_file};
+use tempfile::TempDir;
+
+const FLAGS: &str = "debug: off\ncache: yes\nretries: 1\nverbose: true\nlist: [1]\n";
+
+#[test]
+fn test_as_bool_default_table() {
+    let (stdout, stderr, success) = run_shyaml(&["get-value", "--as-bool", "verbose"], FLAGS);
+    assert!(success, "Command failed: {}", stderr);
+    assert_eq!(stdout, "true\n");
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "--as-bool", "debug"], FLAGS);
+    assert!(!success);
+    assert!(
+        stderr.contains("'off' at 'debug' is neither truthy"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["get-value", "--as-bool", "list"], FLAGS);
+    assert!(!success);
+    assert!(
+        stderr.contains("get-value --as-bool does not support 'sequence' type."),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_as_bool_pinned_table() {
+    let pinned = ["--truthy", "yes,on,1", "--falsy", "no,off,0"];
+    for (path, expected) in [
+        ("debug", "false\n"),
+        ("cache", "true\n"),
+        ("retries", "true\n"),
+    ] {
+        let mut args = pinned.to_vec();
+        args.extend(["get-value", "--as-bool", path]);
+        let (stdout, stderr, success) = run_shyaml(&args, FLAGS);
+        assert!(success, "Command failed: {}", stderr);
+        assert_eq!(stdout, expected, "path: {}", path);
+    }
+
+    // Pinned words replace the YAML 1.2 ones
+    let mut args = pinned.to_vec();
+    args.extend(["get-value", "--as-bool", "verbose"]);
+    let (_, _, success) = run_shyaml(&args, FLAGS);
+    assert!(!success);
+
+    // The boolean goes on through the chain
+    let mut args = pinned.to_vec();
+    args.extend(["get-value", "--as-bool", "cache", ";", "get-type"]);
+    let (stdout, stderr, success) = run_shyaml(&args, FLAGS);
+    assert!(success, "Command failed: {}", stderr);
+    assert_eq!(stdout, "bool\n");
+}
+
+#[test]
+fn test_invalid_table() {
+    let (_, stderr, success) = run_shyaml(
+        &["--truthy", "yes,1", "--falsy", "1", "get-value", "a"],
+        "a: 1\n",
+    );
+    assert!(!success);
+    assert!(
+        stderr.contains("invalid truthiness table, '1' is both truthy and falsy."),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_suite_truthiness() {
+    let tmp = TempDir::new().unwrap();
+    let doc = temp_yaml_file(&tmp, "flags.yaml", FLAGS);
+    let tests = "tests:\n  - {path: debug, op: falsy}\n  - {path: cache, op: truthy}\n";
+    let suite = temp_yaml_file(&tmp, "suite.yaml", tests);
+    let args = |extra: &[&'static str]| {
+        let mut args: Vec<&str> = extra.to_vec();
+        args.extend(["test-suite", suite.to_str().unwrap(), "--file"]);
+        args.push(doc.to_str().unwrap());
+        args
+    };
+
+    let (stdout, _, success) = run_shyaml(&args(&[]), "");
+    assert!(!success);
+    assert!(stdout.contains("0 passed, 2 failed"), "stdout: {}", stdout);
+
+    let (stdout, stderr, success) = run_shyaml(&args(&["--truthy", "yes", "--falsy", "off"]), "");
+    assert!(success, "Command failed: {}", stderr);
+    assert!(stdout.contains("2 passed, 0 failed"), "stdout: {}", stdout);
+
+    // Words of the suite take precedence over the command line ones
+    let suite_with_words = format!("truthy: [yes]\nfalsy: [off]\n{}", tests);
+    std::fs::write(&suite, suite_with_words).unwrap();
+    let (stdout, stderr, success) = run_shyaml(&args(&["--truthy", "on"]), "");
+    assert!(success, "Command failed: {}", stderr);
+    assert!(stdout.contains("2 passed, 0 failed"), "stdout: {}", stdout);
+}
@@ -0,0 +1,119 @@
+//! Integration tests for the `unflatten` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+#[test]
+fn test_unflatten_builds_nested_structure() {
+    let input = indoc! {r"
+        db.host=db.local
+        db.ports.0=5432
+        db.ports.1=5433
+        www\.example\.com.enabled=true
+    "};
+    let expected = indoc! {"
+        db:
+          host: db.local
+          ports:
+          - 5432
+          - 5433
+        www.example.com:
+          enabled: true
+    "};
+
+    let (stdout, stderr, success) = run_shyaml(&["unflatten"], input);
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, expected);
+}
+
+#[test]
+fn test_unflatten_value_keeps_equal_signs() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["unflatten", ";", "get-value", "url"],
+        "url=http://x/?a=b\n",
+    );
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, "http://x/?a=b");
+}
+
+#[test]
+fn test_unflatten_reverses_flatten() {
+    let input = indoc! {"
+        a:
+          b:
+          - x
+          - y: 1
+        c: true
+    "};
+
+    let (flat, stderr, success) = run_shyaml(&["flatten"], input);
+    assert!(success, "flatten failed: {}", stderr);
+
+    let (stdout, stderr, success) = run_shyaml(&["unflatten"], &flat);
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, input);
+}
+
+#[test]
+fn test_unflatten_reverses_flatten_escapes() {
+    let input = indoc! {"
+        a=b:
+          c.d: x=y
+    "};
+
+    let (flat, stderr, success) = run_shyaml(&["flatten"], input);
+    assert!(success, "flatten failed: {}", stderr);
+    assert_output_eq(&flat, "a\\=b.c\\.d=x=y\n");
+
+    let (stdout, stderr, success) = run_shyaml(&["unflatten"], &flat);
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, input);
+}
+
+#[test]
+fn test_unflatten_reverses_flatten_values() {
+    let input = indoc! {"
+        a: |
+          multi
+          line
+        b: null
+        c: ''
+        d: '8'
+        e: {}
+        f: []
+        g: \"x: y\"
+    "};
+
+    let (flat, stderr, success) = run_shyaml(&["flatten"], input);
+    assert!(success, "flatten failed: {}", stderr);
+
+    let (stdout, stderr, success) = run_shyaml(&["unflatten", ";", "flatten"], &flat);
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, &flat);
+
+    let (stdout, stderr, success) = run_shyaml(&["unflatten", ";", "get-value", "a"], &flat);
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, "multi\nline\n");
+    let (stdout, stderr, success) = run_shyaml(&["unflatten", ";", "get-type", "b"], &flat);
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, "NoneType\n");
+    let (stdout, stderr, success) = run_shyaml(&["unflatten", ";", "get-type", "c"], &flat);
+    assert!(success, "unflatten failed: {}", stderr);
+    assert_output_eq(&stdout, "str\n");
+}
+
+#[test]
+fn test_unflatten_invalid_line_error() {
+    let (_, stderr, success) = run_shyaml(&["unflatten"], "a=1\nb\n");
+    assert!(!success, "unflatten should fail on a line without '='");
+    assert!(stderr.contains("invalid line 2: 'b', expected 'path=value'."));
+}
+
+#[test]
+fn test_unflatten_must_come_first() {
+    let (_, stderr, success) = run_shyaml(&["get-value", ";", "unflatten"], "a=1\n");
+    assert!(!success, "unflatten should fail when not first");
+    assert!(stderr.contains("unflatten can only be the first action of a chain"));
+}
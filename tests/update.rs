@@ -0,0 +1,225 @@
+//! Integration tests for the `update` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    spec:
+      replicas: 2
+      ratio: 1.5
+    name: web
+"};
+
+#[test]
+fn test_update_add() {
+    let (stdout, stderr, success) = run_shyaml(&["update", "spec.replicas", "--add", "1"], INPUT);
+    assert!(success, "update failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            spec:
+              replicas: 3
+              ratio: 1.5
+            name: web
+        "},
+    );
+}
+
+#[test]
+fn test_update_add_negative() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "update",
+            "spec.replicas",
+            "--add",
+            "-1",
+            ";",
+            "get-value",
+            "spec.replicas",
+        ],
+        INPUT,
+    );
+    assert!(success, "update failed: {}", stderr);
+    assert_output_eq(&stdout, "1");
+}
+
+#[test]
+fn test_update_mul_keeps_float() {
+    let (stdout, stderr, success) = run_shyaml(&["update", "spec.ratio", "--mul", "2"], INPUT);
+    assert!(success, "update failed: {}", stderr);
+    assert!(stdout.contains("ratio: 3.0\n"), "stdout: {}", stdout);
+}
+
+#[test]
+fn test_update_min_max() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "update",
+            "spec.replicas",
+            "--max",
+            "5",
+            ";",
+            "update",
+            "spec.replicas",
+            "--min",
+            "4",
+            ";",
+            "get-value",
+            "spec.replicas",
+        ],
+        INPUT,
+    );
+    assert!(success, "update failed: {}", stderr);
+    assert_output_eq(&stdout, "4");
+}
+
+#[test]
+fn test_update_in_value_mode_chain() {
+    let (stdout, stderr, success) = run_shyaml(
+        &["get-value", "spec", ";", "update", "replicas", "--mul", "3"],
+        INPUT,
+    );
+    assert!(success, "update failed: {}", stderr);
+    assert_output_eq(&stdout, "replicas: 6\nratio: 1.5\n");
+}
+
+#[test]
+fn test_update_non_number_error() {
+    let (_, stderr, success) = run_shyaml(&["update", "name", "--add", "1"], INPUT);
+    assert!(!success, "update should fail on a string");
+    assert!(stderr.contains("update does not support 'str' type"));
+}
+
+#[test]
+fn test_update_invalid_operand_error() {
+    let (_, stderr, success) = run_shyaml(&["update", "spec.replicas", "--add", "x"], INPUT);
+    assert!(!success, "update should fail on invalid operand");
+    assert!(stderr.contains("invalid number 'x'"));
+}
+
+const IMAGE_INPUT: &str = indoc! {"
+    image: nginx:1.25
+    host: db.local
+    version: '1.2'
+    port: 80
+"};
+
+#[test]
+fn test_update_replace() {
+    let (stdout, stderr, success) =
+        run_shyaml(&["update", "image", "--replace", "1.25/1.26"], IMAGE_INPUT);
+    assert!(success, "update failed: {}", stderr);
+    assert!(
+        stdout.starts_with("image: nginx:1.26\n"),
+        "stdout: {}",
+        stdout
+    );
+}
+
+#[test]
+fn test_update_replace_regex() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "update",
+            "image",
+            "--regex",
+            "--replace",
+            r":(\d+)\.\d+$/:$1.27",
+            ";",
+            "get-value",
+            "image",
+        ],
+        IMAGE_INPUT,
+    );
+    assert!(success, "update failed: {}", stderr);
+    assert_output_eq(&stdout, "nginx:1.27");
+}
+
+#[test]
+fn test_update_append_str_and_prefix() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "update",
+            "host",
+            "--append-str",
+            ":5432",
+            ";",
+            "update",
+            "version",
+            "--prefix",
+            "v",
+        ],
+        IMAGE_INPUT,
+    );
+    assert!(success, "update failed: {}", stderr);
+    assert_output_eq(
+        &stdout,
+        indoc! {"
+            image: nginx:1.25
+            host: db.local:5432
+            version: v1.2
+            port: 80
+        "},
+    );
+}
+
+#[test]
+fn test_update_string_edit_result_is_a_string() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "update",
+            "port",
+            "--append-str",
+            "80",
+            ";",
+            "get-type",
+            "port",
+        ],
+        IMAGE_INPUT,
+    );
+    assert!(success, "update failed: {}", stderr);
+    assert_output_eq(&stdout, "str\n");
+}
+
+#[test]
+fn test_update_string_edit_null_error() {
+    let (_, stderr, success) = run_shyaml(&["update", "a", "--prefix", "v"], "a: null\n");
+    assert!(!success, "update should fail on null");
+    assert!(stderr.contains("update does not support 'NoneType' type"));
+}
+
+#[test]
+fn test_update_invalid_replacement_error() {
+    let (_, stderr, success) = run_shyaml(&["update", "image", "--replace", "1.25"], IMAGE_INPUT);
+    assert!(!success, "update should fail on a replacement without '/'");
+    assert!(stderr.contains("invalid replacement '1.25'"));
+}
+
+#[test]
+fn test_update_empty_old_replacement_error() {
+    let (_, stderr, success) = run_shyaml(&["update", "image", "--replace", "/X"], IMAGE_INPUT);
+    assert!(!success, "update should fail on an empty OLD");
+    assert!(stderr.contains("empty OLD in replacement"));
+}
+
+#[test]
+fn test_update_regex_matching_empty_error() {
+    let (_, stderr, success) = run_shyaml(
+        &["update", "image", "--regex", "--replace", "x*/X"],
+        IMAGE_INPUT,
+    );
+    assert!(
+        !success,
+        "update should fail on a regex matching the empty string"
+    );
+    assert!(stderr.contains("matches the empty string"));
+}
+
+#[test]
+fn test_update_float_overflow_error() {
+    let (_, stderr, success) = run_shyaml(&["update", "a", "--mul", "10"], "a: 1.0e308\n");
+    assert!(!success, "update should fail on a float overflow");
+    assert!(stderr.contains("float overflow updating 'a'"));
+}
@@ -0,0 +1,163 @@
+//! Integration tests for the `validate` action
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
+use indoc::indoc;
+use tempfile::TempDir;
+
+const SCHEMA: &str = r#"{
+  "type": "object",
+  "required": ["name", "replicas"],
+  "properties": {
+    "name": {"type": "string"},
+    "replicas": {"type": "integer", "minimum": 1},
+    "ports": {"type": "array", "items": {"type": "integer"}}
+  },
+  "additionalProperties": false
+}"#;
+
+#[test]
+fn test_validate_valid_document() {
+    let tmp = TempDir::new().unwrap();
+    let schema = temp_yaml_file(&tmp, "schema.json", SCHEMA);
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["validate", "--schema", schema.to_str().unwrap()],
+        "name: web\nreplicas: 2\nports: [80]\n",
+    );
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert!(stdout.is_empty());
+}
+
+#[test]
+fn test_validate_reports_violations_with_paths() {
+    let tmp = TempDir::new().unwrap();
+    let schema = temp_yaml_file(&tmp, "schema.json", SCHEMA);
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["validate", "--schema", schema.to_str().unwrap()],
+        "replicas: 0\nports: [80, http]\nextra.key: 1\n",
+    );
+    assert_eq!(code, Some(3));
+    assert_output_eq(
+        &stdout,
+        indoc! {r"
+            name: required key is missing
+            replicas: expected a value >= 1, got 0
+            ports.1: expected type 'integer', got 'string'
+            extra\.key: unexpected key
+        "},
+    );
+    assert!(
+        stderr.contains("4 violation(s) found"),
+        "stderr: {}",
+        stderr
+    );
+
+    // Paths can be fed back to other actions
+    let (stdout, _, success) = run_shyaml(&["get-value", r"extra\.key"], "extra.key: 1\n");
+    assert!(success);
+    assert_output_eq(&stdout, "1");
+}
+
+#[test]
+fn test_validate_yaml_schema_gates_chain() {
+    let tmp = TempDir::new().unwrap();
+    let schema = temp_yaml_file(
+        &tmp,
+        "schema.yaml",
+        indoc! {"
+            required: [name]
+            properties:
+              name: {type: string}
+        "},
+    );
+    let schema = schema.to_str().unwrap();
+    let (stdout, stderr, code) = run_shyaml_code(
+        &[
+            "validate",
+            "--schema",
+            schema,
+            ";",
+            "set-value",
+            "tag",
+            "v2",
+        ],
+        "name: web\n",
+    );
+    assert_eq!(code, Some(0), "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name: web\ntag: v2\n");
+
+    let (stdout, _, code) = run_shyaml_code(
+        &[
+            "validate",
+            "--schema",
+            schema,
+            ";",
+            "set-value",
+            "tag",
+            "v2",
+        ],
+        "name: [web]\n",
+    );
+    assert_eq!(code, Some(3));
+    assert_output_eq(&stdout, "name: expected type 'string', got 'array'\n");
+}
+
+#[test]
+fn test_validate_github_report() {
+    let tmp = TempDir::new().unwrap();
+    let schema = temp_yaml_file(&tmp, "schema.json", SCHEMA);
+    let (stdout, stderr, code) = run_shyaml_code(
+        &[
+            "validate",
+            "--schema",
+            schema.to_str().unwrap(),
+            "--report",
+            "github",
+        ],
+        "- 1\n",
+    );
+    assert_eq!(code, Some(3));
+    assert_output_eq(&stdout, "::error::expected type 'object', got 'array'\n");
+    assert!(
+        stderr.contains("1 violation(s) found"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (stdout, _, code) = run_shyaml_code(
+        &[
+            "validate",
+            "--schema",
+            schema.to_str().unwrap(),
+            "--report",
+            "github",
+        ],
+        "name: web\nreplicas: 1\nports: [x]\n",
+    );
+    assert_eq!(code, Some(3));
+    assert_output_eq(
+        &stdout,
+        "::error title=ports.0::expected type 'integer', got 'string'\n",
+    );
+}
+
+#[test]
+fn test_validate_invalid_schema() {
+    let tmp = TempDir::new().unwrap();
+    let schema = temp_yaml_file(&tmp, "schema.json", r#"{"$ref": "other.json#/a"}"#);
+    let (stdout, stderr, code) = run_shyaml_code(
+        &["validate", "--schema", schema.to_str().unwrap()],
+        "a: 1\n",
+    );
+    assert_eq!(code, Some(127));
+    assert!(stdout.is_empty());
+    assert!(stderr.contains("unsupported $ref"), "stderr: {}", stderr);
+
+    let (_, stderr, code) = run_shyaml_code(
+        &["validate", "--schema", "/nonexistent/schema.json"],
+        "a: 1\n",
+    );
+    assert_eq!(code, Some(5));
+    assert!(stderr.contains("Failed to read"), "stderr: {}", stderr);
+}
@@ -0,0 +1,123 @@
+//! Integration tests for the `--arg` and `--argjson` variables of
+//! `set-value` and `filter`
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml};
+use indoc::indoc;
+
+const INPUT: &str = indoc! {"
+    services:
+    - name: web
+      port: 80
+    - name: db
+      port: 5432
+"};
+
+#[test]
+fn test_set_value_interpolated() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "--arg",
+            "host",
+            "db-1.local:5432",
+            "--arg",
+            "name",
+            "app",
+            "db.url",
+            "postgres://$host/${name}_$$1",
+        ],
+        "db:\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "db:\n  url: postgres://db-1.local:5432/app_$1\n");
+}
+
+#[test]
+fn test_set_value_whole_variable() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "--argjson",
+            "ports",
+            "[80, 443]",
+            "ports",
+            "$ports",
+        ],
+        "name: web\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "name: web\nports:\n- 80\n- 443\n");
+
+    // Strings of --arg are not given a type
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "set-value",
+            "--arg",
+            "v",
+            "true",
+            "a",
+            "$v",
+            ";",
+            "get-type",
+            "a",
+        ],
+        "a: 1\n",
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "str\n");
+}
+
+#[test]
+fn test_set_value_without_variables() {
+    let (stdout, stderr, success) = run_shyaml(&["set-value", "a", "$HOME"], "a: 1\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: $HOME\n");
+}
+
+#[test]
+fn test_filter_variables() {
+    let (stdout, stderr, success) = run_shyaml(
+        &[
+            "filter",
+            "--arg",
+            "name",
+            "db",
+            "--argjson",
+            "extra",
+            "1",
+            ".services[] | select(.name == $name) | .port + $extra",
+        ],
+        INPUT,
+    );
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "5433\n");
+}
+
+#[test]
+fn test_undefined_variable() {
+    let (_, stderr, success) =
+        run_shyaml(&["set-value", "--arg", "a", "1", "x", "$a-$b"], "x: 0\n");
+    assert!(!success);
+    assert!(
+        stderr.contains("undefined variable '$b'"),
+        "stderr: {}",
+        stderr
+    );
+
+    let (_, stderr, success) = run_shyaml(&["filter", ".services[] | $name"], INPUT);
+    assert!(!success);
+    assert!(
+        stderr.contains("undefined variable '$name' at column 15"),
+        "stderr: {}",
+        stderr
+    );
+}
+
+#[test]
+fn test_invalid_argjson() {
+    let (_, stderr, success) = run_shyaml(&["filter", "--argjson", "a", "[1,", "$a"], INPUT);
+    assert!(!success);
+    assert!(stderr.contains("JSON of '$a'"), "stderr: {}", stderr);
+}
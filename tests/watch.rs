@@ -0,0 +1,89 @@
+//! Integration tests for the `--file` and `--watch` options
+
+mod common;
+
+use common::{assert_output_eq, run_shyaml, run_shyaml_code, temp_yaml_file};
+use tempfile::TempDir;
+
+#[test]
+fn test_file_input() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n---\na: 2\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["-f", file.to_str().unwrap(), "get-value", "a"], "a: 3\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_eq!(stdout, "1\u{0}2");
+}
+
+#[test]
+fn test_file_input_leaves_stdin_to_overlays() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+
+    let (stdout, stderr, success) =
+        run_shyaml(&["--file", file.to_str().unwrap(), "apply", "-"], "b: 2\n");
+    assert!(success, "stderr: {}", stderr);
+    assert_output_eq(&stdout, "a: 1\nb: 2\n");
+}
+
+#[test]
+fn test_watch_requires_file() {
+    let (_, stderr, success) = run_shyaml(&["--watch", "get-value", "a"], "a: 1\n");
+    assert!(!success);
+    assert!(stderr.contains("--file"), "{}", stderr);
+}
+
+#[test]
+fn test_watch_conflicts_with_output() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+    let out = tmp.path().join("out.yaml");
+
+    let (_, stderr, success) = run_shyaml(
+        &[
+            "-f",
+            file.to_str().unwrap(),
+            "--watch",
+            "-o",
+            out.to_str().unwrap(),
+            "get-value",
+            "a",
+        ],
+        "",
+    );
+    assert!(!success);
+    assert!(stderr.contains("--output"), "{}", stderr);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_watch_reruns_on_change() {
+    let tmp = TempDir::new().unwrap();
+    let file = temp_yaml_file(&tmp, "app.yaml", "a: 1\n");
+
+    let writer = {
+        let file = file.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(700));
+            std::fs::write(&file, "a: 2\n").unwrap();
+        })
+    };
+    // The watch only ends with the timeout
+    let (stdout, _, code) = run_shyaml_code(
+        &[
+            "-y",
+            "-f",
+            file.to_str().unwrap(),
+            "--watch",
+            "--timeout",
+            "2",
+            "get-value",
+            "a",
+        ],
+        "",
+    );
+    writer.join().unwrap();
+    assert_eq!(code, Some(124));
+    assert_output_eq(&stdout, "1\n---\n2\n");
+}